@@ -23,6 +23,9 @@
 pub mod currency;
 pub mod fee;
 
+pub mod relay_call;
+pub mod runtime_api;
+pub mod weights;
 pub mod xcm_config;
 pub use constants::*;
 pub use opaque::*;
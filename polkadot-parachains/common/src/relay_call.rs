@@ -0,0 +1,156 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Encoding of relay chain calls, for sending them in a `Transact`.
+//!
+//! We don't depend on the relay chain runtimes, so the calls are encoded by hand. The pallet and
+//! call indices differ between Kusama and Polkadot, hence they are passed in as
+//! [`RelayCallIndices`], which runtimes store such that governance can fix them should the
+//! relay chain ever reorder its pallets or calls.
+
+use codec::{Compact, Decode, Encode, MaxEncodedLen};
+use polkadot_core_primitives::{AccountId, Balance};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+use sp_std::vec::Vec;
+
+/// The indices of the relay chain pallets and calls we know how to encode.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, MaxEncodedLen, RuntimeDebug, TypeInfo)]
+pub struct RelayCallIndices {
+	/// The index of the `Balances` pallet.
+	pub balances: u8,
+	/// The index of `Balances::transfer_keep_alive`.
+	pub transfer_keep_alive: u8,
+	/// The index of the `Staking` pallet.
+	pub staking: u8,
+	/// The index of `Staking::bond`.
+	pub bond: u8,
+	/// The index of `Staking::nominate`.
+	pub nominate: u8,
+}
+
+impl RelayCallIndices {
+	/// The indices on Kusama.
+	pub const KUSAMA: Self =
+		Self { balances: 4, transfer_keep_alive: 3, staking: 6, bond: 0, nominate: 5 };
+	/// The indices on Polkadot.
+	pub const POLKADOT: Self =
+		Self { balances: 5, transfer_keep_alive: 3, staking: 7, bond: 0, nominate: 5 };
+}
+
+/// Where the relay chain pays staking rewards to, mirroring `pallet_staking::RewardDestination`.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum RewardDestination {
+	/// Pay into the stash account, increasing the amount at stake accordingly.
+	Staked,
+	/// Pay into the stash account, not increasing the amount at stake.
+	Stash,
+	/// Pay into the controller account.
+	Controller,
+	/// Pay into a specified account.
+	Account(AccountId),
+	/// Receive no reward.
+	None,
+}
+
+/// A relay chain call we know how to encode.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum RelayCall {
+	/// `Balances::transfer_keep_alive`.
+	TransferKeepAlive { dest: AccountId, value: Balance },
+	/// `Staking::bond`.
+	Bond { controller: AccountId, value: Balance, payee: RewardDestination },
+	/// `Staking::nominate`.
+	Nominate { targets: Vec<AccountId> },
+}
+
+/// A `MultiAddress::Id`, which is how the relay chain looks up accounts passed to its calls.
+fn address(account: &AccountId) -> (u8, &AccountId) {
+	(0, account)
+}
+
+impl RelayCall {
+	/// The call as the relay chain with the given `indices` decodes it.
+	pub fn encode_with(&self, indices: &RelayCallIndices) -> Vec<u8> {
+		match self {
+			Self::TransferKeepAlive { dest, value } =>
+				(indices.balances, indices.transfer_keep_alive, address(dest), Compact(*value))
+					.encode(),
+			Self::Bond { controller, value, payee } =>
+				(indices.staking, indices.bond, address(controller), Compact(*value), payee)
+					.encode(),
+			Self::Nominate { targets } =>
+				(indices.staking, indices.nominate, targets.iter().map(address).collect::<Vec<_>>())
+					.encode(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const ALICE: [u8; 32] = [1; 32];
+	const BOB: [u8; 32] = [2; 32];
+	/// `Compact(1_000_000_000_000)`, i.e. one KSM.
+	const ONE_KSM: [u8; 6] = [0x07, 0x00, 0x10, 0xa5, 0xd4, 0xe8];
+
+	#[test]
+	fn transfer_keep_alive_encodes_like_the_relay_chain() {
+		let call = RelayCall::TransferKeepAlive { dest: ALICE.into(), value: 1_000_000_000_000 };
+		assert_eq!(
+			call.encode_with(&RelayCallIndices::KUSAMA),
+			[&[4, 3, 0][..], &ALICE, &ONE_KSM].concat()
+		);
+		assert_eq!(
+			call.encode_with(&RelayCallIndices::POLKADOT),
+			[&[5, 3, 0][..], &ALICE, &ONE_KSM].concat()
+		);
+	}
+
+	#[test]
+	fn bond_encodes_like_the_relay_chain() {
+		let staked = RelayCall::Bond {
+			controller: ALICE.into(),
+			value: 1_000_000_000_000,
+			payee: RewardDestination::Staked,
+		};
+		assert_eq!(
+			staked.encode_with(&RelayCallIndices::KUSAMA),
+			[&[6, 0, 0][..], &ALICE, &ONE_KSM, &[0]].concat()
+		);
+
+		let to_bob = RelayCall::Bond {
+			controller: ALICE.into(),
+			value: 1_000_000_000_000,
+			payee: RewardDestination::Account(BOB.into()),
+		};
+		assert_eq!(
+			to_bob.encode_with(&RelayCallIndices::POLKADOT),
+			[&[7, 0, 0][..], &ALICE, &ONE_KSM, &[3], &BOB].concat()
+		);
+	}
+
+	#[test]
+	fn nominate_encodes_like_the_relay_chain() {
+		let call = RelayCall::Nominate { targets: vec![ALICE.into(), BOB.into()] };
+		assert_eq!(
+			call.encode_with(&RelayCallIndices::KUSAMA),
+			[&[6, 5, 8, 0][..], &ALICE, &[0], &BOB].concat()
+		);
+	}
+}
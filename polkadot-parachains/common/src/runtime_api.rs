@@ -0,0 +1,156 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime APIs implemented by all our parachain runtimes.
+
+use crate::xcm_config::{
+	BarrierRejection, BatchTooLarge, CurrencyId, CurrencyMetadata, InboundXcmWeight,
+	IntegrityFailure, TransactRejection, XcmDryRunEffects, XcmDryRunError, XcmpQueueStatus,
+};
+use sp_runtime::DispatchResult;
+use sp_std::vec::Vec;
+use xcm::{
+	latest::{OriginKind, Weight},
+	VersionedMultiAssets, VersionedMultiLocation,
+};
+
+sp_api::decl_runtime_apis! {
+	/// Fees for sending XCM messages from this chain.
+	pub trait XcmDeliveryFeeApi {
+		/// The fee the router charges for delivering a message of `message_size` bytes to `dest`.
+		///
+		/// This does not include the execution fees at the destination. A zero amount means the
+		/// delivery is free, `None` that we don't route to `dest` or can't price it.
+		fn xcm_delivery_fee(
+			dest: VersionedMultiLocation,
+			message_size: u32,
+		) -> Option<(CurrencyId, u128)>;
+	}
+
+	/// Fee estimates for transfers with `orml_xtokens`, for wallets.
+	pub trait XtokensFeeApi {
+		/// The execution and delivery fee of transferring `currency` to `dest`, in `currency`, and
+		/// the weight of executing the transfer at `dest`. `None` if it can't be estimated, e.g.
+		/// by runtimes without `orml_xtokens`.
+		fn estimate_xtokens_fee(
+			currency: CurrencyId,
+			dest: VersionedMultiLocation,
+		) -> Option<(u128, Weight)>;
+	}
+
+	/// Resolution of asset locations to our currencies, for tooling.
+	pub trait CurrencyRegistryApi {
+		/// The currency at each of `locations`, in order, `None` for unknown locations.
+		///
+		/// Fails for batches of more than [`crate::xcm_config::MAX_CURRENCY_BATCH`] locations.
+		fn batch_resolve_currencies(
+			locations: Vec<VersionedMultiLocation>,
+		) -> Result<Vec<Option<CurrencyId>>, BatchTooLarge>;
+	}
+
+	/// The locations of our currencies, for wallets building transfers.
+	pub trait XcmLocationApi {
+		/// The location of our native token, relative to this chain.
+		fn self_reserve_location() -> VersionedMultiLocation;
+
+		/// The location of `currency` relative to this chain, as used in `orml_xtokens` transfers.
+		/// `None` if the currency has no location.
+		fn currency_location(currency: CurrencyId) -> Option<VersionedMultiLocation>;
+	}
+
+	/// The metadata of the assets we know, for wallets displaying them.
+	pub trait AssetMetadataApi {
+		/// The metadata of the asset at `location`, `None` if it is no currency we know.
+		fn asset_metadata(location: VersionedMultiLocation) -> Option<CurrencyMetadata>;
+	}
+
+	/// Dry-runs of the calls of XCM `Transact` instructions, for integrators.
+	pub trait TransactDryRunApi {
+		/// Dispatch the SCALE encoded `RuntimeCall` `call` as a `Transact` from `origin` with
+		/// `origin_kind` would, without committing any changes.
+		///
+		/// See [`crate::xcm_config::dry_run_transact`].
+		fn dry_run_transact(
+			call: Vec<u8>,
+			origin_kind: OriginKind,
+			origin: VersionedMultiLocation,
+		) -> Result<DispatchResult, TransactRejection>;
+	}
+
+	/// Dry-runs of the XCM `Barrier`, for the operators of chains sending us messages.
+	pub trait BarrierDryRunApi {
+		/// Whether the `Barrier` would admit the SCALE encoded `VersionedXcm` `message` from
+		/// `origin`, and if not, which of its rules rejected it. The message is not executed.
+		///
+		/// See [`crate::xcm_config::dry_run_barrier`].
+		fn dry_run_barrier(
+			message: Vec<u8>,
+			origin: VersionedMultiLocation,
+		) -> Result<(), BarrierRejection>;
+	}
+
+	/// Dry-runs of XCM messages, for debugging failed transfers.
+	pub trait XcmDryRunApi {
+		/// What executing the SCALE encoded `VersionedXcm` `message` from `origin` like an inbound
+		/// message would do, without committing any changes or sending any messages.
+		///
+		/// See [`crate::xcm_config::dry_run_xcm`].
+		fn dry_run_xcm(
+			message: Vec<u8>,
+			origin: VersionedMultiLocation,
+		) -> Result<XcmDryRunEffects, XcmDryRunError>;
+	}
+
+	/// Consistency checks of the runtime's XCM configuration, which the node can run at startup.
+	pub trait IntegrityCheckApi {
+		/// The checks the runtime fails, none if its configuration is consistent.
+		fn check_integrity() -> Vec<IntegrityFailure>;
+	}
+
+	/// XCM related events, for indexers.
+	pub trait XcmEventsApi {
+		/// The SCALE encoded `RuntimeEvent`s of the XCM pallets which have been emitted in this
+		/// block.
+		fn xcm_events() -> Vec<Vec<u8>>;
+	}
+
+	/// The weight of the inbound XCM messages, for the node's metrics.
+	pub trait XcmWeightApi {
+		/// The weight charged and consumed by each inbound message executed in this block, in
+		/// order. Runtimes which don't meter their inbound messages return none.
+		fn inbound_xcm_weights() -> Vec<InboundXcmWeight>;
+
+		/// The weight our XCM executor charges for each instruction, see
+		/// [`crate::xcm_config::XcmInstructionWeight`].
+		fn xcm_instruction_weight() -> Weight;
+	}
+
+	/// The assets trapped by the XCM executor, for reclaiming them.
+	pub trait TrappedAssetsApi {
+		/// The assets trapped by `origin` which haven't been claimed yet, with the number of times
+		/// they have been trapped. Runtimes which don't index their trapped assets return none.
+		fn trapped_assets(origin: VersionedMultiLocation) -> Vec<(VersionedMultiAssets, u32)>;
+	}
+
+	/// The congestion of the XCMP queue, for operators.
+	pub trait XcmpQueueApi {
+		/// The thresholds of the XCMP queue and the backlogs of its channels.
+		///
+		/// See [`crate::xcm_config::xcmp_queue_status`].
+		fn xcmp_queue_status() -> XcmpQueueStatus;
+	}
+}
@@ -0,0 +1,54 @@
+//! Weights for `pallet_xcm_transactor`
+//!
+//! The transactor has no benchmarks upstream. All its calls do is build a `Transact` for the
+//! relay chain and send it through our XCM router, like `pallet_xcm::send` does, so they are
+//! weighed as our benchmarked `pallet_xcm::send`, plus every further instruction and every byte
+//! of the relay call carried by the `Transact`.
+
+use crate::xcm_config::XcmInstructionWeight;
+use core::marker::PhantomData;
+use frame_support::{traits::Get, weights::Weight};
+use frame_system::WeightInfo as _;
+use pallet_xcm::WeightInfo as _;
+
+/// The instructions of the message built for `send_swap_ump`: `WithdrawAsset`, `BuyExecution`,
+/// `Transact`, `RefundSurplus` and `DepositAsset`.
+pub const SWAP_UMP_INSTRUCTIONS: u32 = 5;
+/// The length of the encoded `Registrar::swap` relay call: its pallet and call index and two
+/// para ids.
+pub const SWAP_CALL_LEN: u32 = 10;
+
+/// Weight functions for `pallet_xcm_transactor`.
+pub struct WeightInfo<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo<T> {
+	/// The weight of sending a message of `instructions` instructions to the relay chain, with a
+	/// `Transact` of a `call_len` bytes long call.
+	pub fn send_transact(instructions: u32, call_len: u32) -> Weight {
+		super::pallet_xcm::WeightInfo::<T>::send()
+			.saturating_add(
+				Self::weight_per_instruction().saturating_mul(instructions.saturating_sub(1).into()),
+			)
+			.saturating_add(Self::weight_per_call_byte().saturating_mul(call_len.into()))
+	}
+
+	/// The weight of an instruction beyond the one sent by the `pallet_xcm::send` benchmark: as
+	/// much as our executor charges for executing one, far more than building and sending it
+	/// takes.
+	fn weight_per_instruction() -> Weight {
+		XcmInstructionWeight::get()
+	}
+
+	/// The weight of a byte of the relay call: what `frame_system::remark` takes per byte of its
+	/// argument in its upstream benchmark, and a byte of proof size for the message being stored
+	/// in the queue to the relay chain.
+	fn weight_per_call_byte() -> Weight {
+		let remark = frame_system::weights::SubstrateWeight::<T>::remark;
+		Weight::from_parts(remark(1).saturating_sub(remark(0)).ref_time(), 1)
+	}
+}
+
+impl<T: frame_system::Config> pallet_xcm_transactor::WeightInfo for WeightInfo<T> {
+	fn send_swap_ump() -> Weight {
+		Self::send_transact(SWAP_UMP_INSTRUCTIONS, SWAP_CALL_LEN)
+	}
+}
@@ -1,11 +1,180 @@
+use codec::{Decode, DecodeLimit, Encode, MaxEncodedLen};
 use core::marker::PhantomData;
-use frame_support::{log, traits::ProcessMessageError, weights::Weight};
-use xcm::latest::prelude::*;
-use xcm_executor::traits::ShouldExecute;
+use cumulus_primitives_utility::PriceForParentDelivery;
+use frame_support::{
+	dispatch::PostDispatchInfo,
+	log,
+	storage::{unhashed, with_transaction, TransactionOutcome},
+	traits::{Contains, Get, PalletInfoAccess, ProcessMessageError},
+	weights::Weight,
+	RuntimeDebug,
+};
+use polkadot_primitives::Id as ParaId;
+use polkadot_runtime_common::xcm_sender::PriceForParachainDelivery;
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{Convert, Dispatchable},
+	DispatchResult,
+};
+use sp_std::{
+	collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+	vec,
+	vec::Vec,
+};
+use xcm::{
+	latest::{prelude::*, XcmHash},
+	DoubleEncoded, VersionedMultiLocation, VersionedXcm, MAX_XCM_DECODE_DEPTH,
+};
+use xcm_executor::{
+	traits::{CallDispatcher, ConvertOrigin, ExecuteXcm, ShouldExecute, WeightBounds},
+	XcmExecutor,
+};
+
+frame_support::parameter_types! {
+	/// The proof size we assume for an XCM instruction. Copied from moonbeam:
+	/// https://github.com/PureStake/moonbeam/blob/095031d171b0c163e5649ee35acbc36eef681a82/primitives/xcm/src/ethereum_xcm.rs#L34
+	pub const DefaultProofSize: u64 = 128 * 1024;
+	/// The weight of one XCM instruction, from which both the `UnitWeightCost` of the executor's
+	/// weigher and the `BaseXcmWeight` of `orml_xtokens` are derived. Copied from moonbeam.
+	pub const XcmInstructionWeight: Weight =
+		Weight::from_parts(200_000_000, DefaultProofSize::get());
+}
 
 /// Type alias to conveniently refer to `frame_system`'s `Config::AccountId`.
 pub type AccountIdOf<R> = <R as frame_system::Config>::AccountId;
 
+// Supported Currencies.
+#[derive(
+	Encode,
+	Decode,
+	Eq,
+	PartialEq,
+	Copy,
+	Clone,
+	RuntimeDebug,
+	PartialOrd,
+	Ord,
+	TypeInfo,
+	MaxEncodedLen,
+)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub enum CurrencyId {
+	TEER,
+	/// The native token of the relay chain, held in `orml_tokens` here. Accepted to pay XCM fees.
+	KSM,
+	/// Tether USD, issued by the Asset Hub and held in `orml_tokens` here.
+	USDT,
+}
+
+impl CurrencyId {
+	/// The token symbol of the currency, as announced in the chain properties.
+	pub const fn symbol(&self) -> &'static str {
+		match self {
+			CurrencyId::TEER => "TEER",
+			CurrencyId::KSM => "KSM",
+			CurrencyId::USDT => "USDT",
+		}
+	}
+
+	/// The number of decimals of amounts of the currency, as announced in the chain properties.
+	pub const fn decimals(&self) -> u8 {
+		match self {
+			CurrencyId::TEER => 12,
+			CurrencyId::KSM => 12,
+			CurrencyId::USDT => 6,
+		}
+	}
+
+	/// Whether the currency is native to this chain, i.e. held in `pallet_balances` rather than
+	/// in `orml_tokens`.
+	pub const fn is_native(&self) -> bool {
+		matches!(self, CurrencyId::TEER)
+	}
+
+	/// The metadata wallets display the currency with.
+	pub fn metadata(&self) -> CurrencyMetadata {
+		CurrencyMetadata { symbol: self.symbol().as_bytes().to_vec(), decimals: self.decimals() }
+	}
+
+	/// All currencies, in the order of their variants.
+	///
+	/// Derived from the SCALE encoding of the variants, which is just their index, such that new
+	/// variants are picked up without being listed anywhere.
+	pub fn all() -> impl Iterator<Item = CurrencyId> {
+		(0..=u8::MAX).filter_map(|index| Self::decode(&mut &[index][..]).ok())
+	}
+}
+
+/// The location of USDT as seen from a sibling of the Asset Hub: asset 1984 of its `Assets` pallet.
+pub const USDT_LOCATION: MultiLocation = MultiLocation {
+	parents: 1,
+	interior: X3(Parachain(1000), PalletInstance(50), GeneralIndex(1984)),
+};
+
+/// The currencies held in `pallet_balances`, see [`CurrencyId::is_native`].
+pub struct NativeCurrencies;
+impl Contains<CurrencyId> for NativeCurrencies {
+	fn contains(currency: &CurrencyId) -> bool {
+		currency.is_native()
+	}
+}
+
+/// The currencies held in `orml_tokens`, see [`CurrencyId::is_native`].
+pub struct ForeignCurrencies;
+impl Contains<CurrencyId> for ForeignCurrencies {
+	fn contains(currency: &CurrencyId) -> bool {
+		!currency.is_native()
+	}
+}
+
+/// Converts locations and assets to currencies like `Inner`, but only to the currencies `Filter`
+/// contains.
+///
+/// Native and foreign currencies are held in different pallets, each with an asset transactor of
+/// its own. Matching with a filtered converter keeps a transactor from accepting the currencies
+/// of the other one.
+pub struct FilteredCurrencies<Inner, Filter>(PhantomData<(Inner, Filter)>);
+impl<Inner, Filter> Convert<MultiLocation, Option<CurrencyId>> for FilteredCurrencies<Inner, Filter>
+where
+	Inner: Convert<MultiLocation, Option<CurrencyId>>,
+	Filter: Contains<CurrencyId>,
+{
+	fn convert(location: MultiLocation) -> Option<CurrencyId> {
+		Inner::convert(location).filter(Filter::contains)
+	}
+}
+
+impl<Inner, Filter> Convert<MultiAsset, Option<CurrencyId>> for FilteredCurrencies<Inner, Filter>
+where
+	Inner: Convert<MultiAsset, Option<CurrencyId>>,
+	Filter: Contains<CurrencyId>,
+{
+	fn convert(asset: MultiAsset) -> Option<CurrencyId> {
+		Inner::convert(asset).filter(Filter::contains)
+	}
+}
+
+/// Whether the meaningful bytes of the `GeneralKey` `key`, i.e. the first `length` ones, are
+/// exactly `symbol`.
+///
+/// Used to assert at compile time that the general keys identifying our currencies in XCM match
+/// their symbols.
+pub const fn general_key_matches_symbol(key: &Junction, symbol: &str) -> bool {
+	let GeneralKey { length, data } = key else { return false };
+	let symbol = symbol.as_bytes();
+	if *length as usize != symbol.len() || symbol.len() > data.len() {
+		return false
+	}
+	let mut i = 0;
+	while i < symbol.len() {
+		if data[i] != symbol[i] {
+			return false
+		}
+		i += 1;
+	}
+	true
+}
+
 //TODO: move DenyThenTry to polkadot's xcm module.
 /// Deny executing the XCM if it matches any of the Deny filter regardless of anything else.
 /// If it passes the Deny, and matches one of the Allow cases then it is let through.
@@ -30,7 +199,24 @@ where
 	}
 }
 
+/// Whether any instruction of `message`, including those of its error handlers and appendices,
+/// matches `predicate`.
+fn any_instruction<Call>(
+	message: &[Instruction<Call>],
+	predicate: &impl Fn(&Instruction<Call>) -> bool,
+) -> bool {
+	message.iter().any(|inst| match inst {
+		SetErrorHandler(xcm) | SetAppendix(xcm) => any_instruction(&xcm.0, predicate),
+		inst => predicate(inst),
+	})
+}
+
 // See issue <https://github.com/paritytech/polkadot/issues/5233>
+/// Denies reserve transfers to the relay chain, which doesn't accept them. Error handlers and
+/// appendices are checked as well, as they are executed here just like the message.
+///
+/// Withdrawing the relay chain's token from its reserve is allowed, such that reserve-backed
+/// tokens can return to the relay chain.
 pub struct DenyReserveTransferToRelayChain;
 impl ShouldExecute for DenyReserveTransferToRelayChain {
 	fn should_execute<RuntimeCall>(
@@ -39,18 +225,16 @@ impl ShouldExecute for DenyReserveTransferToRelayChain {
 		_max_weight: Weight,
 		_weight_credit: &mut Weight,
 	) -> Result<(), ProcessMessageError> {
-		if message.iter().any(|inst| {
-			matches!(
-				inst,
-				InitiateReserveWithdraw {
-					reserve: MultiLocation { parents: 1, interior: Here },
-					..
-				} | DepositReserveAsset { dest: MultiLocation { parents: 1, interior: Here }, .. } |
-					TransferReserveAsset {
-						dest: MultiLocation { parents: 1, interior: Here },
-						..
-					}
-			)
+		let message: &[Instruction<RuntimeCall>] = message;
+		if any_instruction(message, &|inst| match inst {
+			InitiateReserveWithdraw {
+				assets,
+				reserve: MultiLocation { parents: 1, interior: Here },
+				..
+			} => !withdraws_relay_token_only(assets, message),
+			DepositReserveAsset { dest: MultiLocation { parents: 1, interior: Here }, .. } |
+			TransferReserveAsset { dest: MultiLocation { parents: 1, interior: Here }, .. } => true,
+			_ => false,
 		}) {
 			return Err(ProcessMessageError::Unsupported) // Deny
 		}
@@ -69,3 +253,1839 @@ impl ShouldExecute for DenyReserveTransferToRelayChain {
 		Ok(())
 	}
 }
+
+/// Whether `assets` may contain the relay chain's token. Wildcards of all assets are assumed not
+/// to, as the barrier can't know what will be in holding.
+fn may_contain_relay_token(assets: &MultiAssetFilter) -> bool {
+	let relay_token = Concrete(MultiLocation::parent());
+	match assets {
+		Definite(assets) => assets.inner().iter().any(|asset| asset.id == relay_token),
+		Wild(AllOf { id, .. } | AllOfCounted { id, .. }) => *id == relay_token,
+		Wild(All | AllCounted(_)) => false,
+	}
+}
+
+/// Whether withdrawing `assets` from the holding register of `message` can only withdraw the relay
+/// chain's token. Wildcards of all assets do, if `message` only puts the relay chain's token into
+/// holding.
+fn withdraws_relay_token_only<Call>(
+	assets: &MultiAssetFilter,
+	message: &[Instruction<Call>],
+) -> bool {
+	let relay_token = Concrete(MultiLocation::parent());
+	match assets {
+		Definite(assets) => assets.inner().iter().all(|asset| asset.id == relay_token),
+		Wild(AllOf { id, .. } | AllOfCounted { id, .. }) => *id == relay_token,
+		Wild(All | AllCounted(_)) => !any_instruction(message, &|inst| match inst {
+			WithdrawAsset(assets) |
+			ReserveAssetDeposited(assets) |
+			ReceiveTeleportedAsset(assets) |
+			ClaimAsset { assets, .. } => assets.inner().iter().any(|asset| asset.id != relay_token),
+			ExchangeAsset { .. } => true,
+			_ => false,
+		}),
+	}
+}
+
+/// Denies reserve transfers of the relay chain's token with ourselves as the reserve.
+///
+/// `DepositReserveAsset` and `TransferReserveAsset` tell the destination that we hold the assets
+/// in reserve for it. We don't for the relay chain's token, so the destination rejects them and the
+/// tokens are stranded: burnt here, but never credited there. Error handlers and appendices are
+/// checked as well.
+pub struct DenyRelayTokenReserveToSelf;
+impl ShouldExecute for DenyRelayTokenReserveToSelf {
+	fn should_execute<RuntimeCall>(
+		_origin: &MultiLocation,
+		message: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ProcessMessageError> {
+		let reserves_relay_token = |inst: &Instruction<RuntimeCall>| match inst {
+			DepositReserveAsset { assets, .. } => may_contain_relay_token(assets),
+			TransferReserveAsset { assets, .. } =>
+				may_contain_relay_token(&Definite(assets.clone())),
+			_ => false,
+		};
+		if any_instruction(message, &reserves_relay_token) {
+			return Err(ProcessMessageError::Unsupported)
+		}
+		Ok(())
+	}
+}
+
+/// Only honour `OriginKind::Superuser` for the relay chain.
+///
+/// Wraps the origin converters of a runtime, such that a superuser origin from any other location
+/// is rejected before any of the `Inner` converters gets to see it. Their order and a future
+/// converter honouring `OriginKind::Superuser` for other locations can't open up Root then.
+pub struct SuperuserOnlyFromRelay<Inner>(PhantomData<Inner>);
+impl<RuntimeOrigin, Inner: ConvertOrigin<RuntimeOrigin>> ConvertOrigin<RuntimeOrigin>
+	for SuperuserOnlyFromRelay<Inner>
+{
+	fn convert_origin(
+		origin: impl Into<MultiLocation>,
+		kind: OriginKind,
+	) -> Result<RuntimeOrigin, MultiLocation> {
+		let origin = origin.into();
+		if kind == OriginKind::Superuser && origin != MultiLocation::parent() {
+			log::warn!(
+				target: "xcm::origin_conversion",
+				"Rejecting superuser origin from non-relay location {:?}",
+				origin,
+			);
+			return Err(origin)
+		}
+		Inner::convert_origin(origin, kind)
+	}
+}
+
+/// Weighs messages like `Inner`, plus `PerAsset` for every asset beyond the first one carried by
+/// an instruction.
+///
+/// Processing assets costs more the more there are, regardless of the weight of the instructions
+/// carrying them. As the barrier only admits messages which buy execution for the weight determined
+/// here, messages with many assets have to pay for them before they are admitted. Messages with at
+/// most one asset per instruction are weighed exactly like by `Inner`.
+pub struct AssetCountWeigher<Inner, PerAsset>(PhantomData<(Inner, PerAsset)>);
+
+impl<Inner, PerAsset, Call> WeightBounds<Call> for AssetCountWeigher<Inner, PerAsset>
+where
+	Inner: WeightBounds<Call>,
+	PerAsset: Get<Weight>,
+{
+	fn weight(message: &mut Xcm<Call>) -> Result<Weight, ()> {
+		let additional = message.0.iter().map(additional_assets).sum();
+		Inner::weight(message)
+			.map(|weight| weight.saturating_add(asset_weight::<PerAsset>(additional)))
+	}
+
+	fn instr_weight(instruction: &Instruction<Call>) -> Result<Weight, ()> {
+		let additional = additional_assets(instruction);
+		Inner::instr_weight(instruction)
+			.map(|weight| weight.saturating_add(asset_weight::<PerAsset>(additional)))
+	}
+}
+
+fn asset_weight<PerAsset: Get<Weight>>(assets: u64) -> Weight {
+	PerAsset::get().saturating_mul(assets)
+}
+
+/// The number of assets beyond the first one carried by `instruction`, including the instructions
+/// nested in it.
+fn additional_assets<Call>(instruction: &Instruction<Call>) -> u64 {
+	match instruction {
+		SetErrorHandler(xcm) | SetAppendix(xcm) => xcm.0.iter().map(additional_assets).sum(),
+		_ =>
+			carried_assets(instruction).map_or(0, |assets| (assets.len() as u64).saturating_sub(1)),
+	}
+}
+
+/// The assets carried by `instruction` itself.
+fn carried_assets<Call>(instruction: &Instruction<Call>) -> Option<&MultiAssets> {
+	match instruction {
+		WithdrawAsset(assets) |
+		ReserveAssetDeposited(assets) |
+		ReceiveTeleportedAsset(assets) |
+		BurnAsset(assets) |
+		ExpectAsset(assets) |
+		ClaimAsset { assets, .. } |
+		TransferAsset { assets, .. } |
+		TransferReserveAsset { assets, .. } => Some(assets),
+		_ => None,
+	}
+}
+
+/// Weighs messages like `Inner`, plus `PerConversion` for every distinct asset id carried by the
+/// message.
+///
+/// The asset transactors convert the id of every asset they see to a currency, and the reserve
+/// check normalizes the reserve of every reserve-backed asset. Both are cached while a message is
+/// executed by [`CachedConversions`], so an id costs one conversion no matter how often the message
+/// carries it, and is only charged once.
+pub struct ConversionWeigher<Inner, PerConversion>(PhantomData<(Inner, PerConversion)>);
+
+impl<Inner, PerConversion, Call> WeightBounds<Call> for ConversionWeigher<Inner, PerConversion>
+where
+	Inner: WeightBounds<Call>,
+	PerConversion: Get<Weight>,
+{
+	fn weight(message: &mut Xcm<Call>) -> Result<Weight, ()> {
+		let conversions = distinct_asset_ids(&message.0);
+		Inner::weight(message)
+			.map(|weight| weight.saturating_add(PerConversion::get().saturating_mul(conversions)))
+	}
+
+	fn instr_weight(instruction: &Instruction<Call>) -> Result<Weight, ()> {
+		// Whether the message converted the ids before is unknown here, all of them are charged.
+		let conversions = distinct_asset_ids(sp_std::slice::from_ref(instruction));
+		Inner::instr_weight(instruction)
+			.map(|weight| weight.saturating_add(PerConversion::get().saturating_mul(conversions)))
+	}
+}
+
+/// The number of distinct asset ids carried by `instructions`, including the instructions nested
+/// in them.
+fn distinct_asset_ids<Call>(instructions: &[Instruction<Call>]) -> u64 {
+	fn collect<'a, Call>(instructions: &'a [Instruction<Call>], ids: &mut BTreeSet<&'a AssetId>) {
+		for instruction in instructions {
+			match instruction {
+				SetErrorHandler(xcm) | SetAppendix(xcm) => collect(&xcm.0, ids),
+				_ => ids.extend(
+					carried_assets(instruction)
+						.into_iter()
+						.flat_map(|assets| assets.inner().iter().map(|asset| &asset.id)),
+				),
+			}
+		}
+	}
+
+	let mut ids = BTreeSet::new();
+	collect(instructions, &mut ids);
+	ids.len() as u64
+}
+
+/// The conversions of asset ids made while a message is executed, see [`CachedConversions`].
+#[derive(Default)]
+pub struct ConversionCache {
+	currencies: BTreeMap<MultiLocation, Option<CurrencyId>>,
+	reserves: BTreeMap<AssetId, Option<MultiLocation>>,
+}
+
+environmental::environmental!(conversion_cache: ConversionCache);
+
+/// Run `f` with a fresh conversion cache, unless one is set up already.
+pub fn with_conversion_cache<R>(f: impl FnOnce() -> R) -> R {
+	conversion_cache::using_once(&mut ConversionCache::default(), f)
+}
+
+/// The currency at `location`, as converted by `convert` the first time it is asked for within
+/// [`with_conversion_cache`]. Without a cache, it is converted every time.
+pub fn cached_currency(
+	location: &MultiLocation,
+	convert: impl FnOnce() -> Option<CurrencyId>,
+) -> Option<CurrencyId> {
+	if let Some(Some(currency)) =
+		conversion_cache::with(|cache| cache.currencies.get(location).copied())
+	{
+		return currency
+	}
+	let currency = convert();
+	conversion_cache::with(|cache| cache.currencies.insert(*location, currency));
+	currency
+}
+
+/// The reserve of the asset `id`, as determined by `reserve` the first time it is asked for within
+/// [`with_conversion_cache`]. Without a cache, it is determined every time.
+pub fn cached_reserve(
+	id: &AssetId,
+	reserve: impl FnOnce() -> Option<MultiLocation>,
+) -> Option<MultiLocation> {
+	if let Some(Some(location)) = conversion_cache::with(|cache| cache.reserves.get(id).copied()) {
+		return location
+	}
+	let location = reserve();
+	conversion_cache::with(|cache| cache.reserves.insert(*id, location));
+	location
+}
+
+/// Wraps the XCM executor, caching the conversions of asset ids while a message is executed, see
+/// [`cached_currency`] and [`cached_reserve`].
+///
+/// Every executor entry point of a runtime must be wrapped, as [`ConversionWeigher`] charges a
+/// message for its distinct asset ids only.
+pub struct CachedConversions<Inner>(PhantomData<Inner>);
+
+impl<Call, Inner: ExecuteXcm<Call>> ExecuteXcm<Call> for CachedConversions<Inner> {
+	type Prepared = Inner::Prepared;
+
+	fn prepare(message: Xcm<Call>) -> Result<Self::Prepared, Xcm<Call>> {
+		Inner::prepare(message)
+	}
+
+	fn execute(
+		origin: impl Into<MultiLocation>,
+		pre: Self::Prepared,
+		hash: XcmHash,
+		weight_credit: Weight,
+	) -> Outcome {
+		with_conversion_cache(|| Inner::execute(origin, pre, hash, weight_credit))
+	}
+
+	fn charge_fees(location: impl Into<MultiLocation>, fees: MultiAssets) -> XcmResult {
+		Inner::charge_fees(location, fees)
+	}
+}
+
+/// Price of delivering a message to a sibling parachain: `BaseFee` plus `ByteFee` for every byte
+/// of the encoded message, in the asset at `AssetLocation`.
+pub struct SizeBasedDelivery<AssetLocation, BaseFee, ByteFee>(
+	PhantomData<(AssetLocation, BaseFee, ByteFee)>,
+);
+impl<AssetLocation, BaseFee, ByteFee> PriceForParachainDelivery
+	for SizeBasedDelivery<AssetLocation, BaseFee, ByteFee>
+where
+	AssetLocation: Get<MultiLocation>,
+	BaseFee: Get<u128>,
+	ByteFee: Get<u128>,
+{
+	fn price_for_parachain_delivery(_para: ParaId, message: &Xcm<()>) -> MultiAssets {
+		let fee = ByteFee::get()
+			.saturating_mul(message.encoded_size() as u128)
+			.saturating_add(BaseFee::get());
+		vec![(AssetLocation::get(), fee).into()].into()
+	}
+}
+
+/// The fee for delivering a message of `message_size` bytes to `dest`, as priced by the router.
+///
+/// Messages to the relay chain are priced by `ParentPrice`, messages to sibling parachains by
+/// `SiblingPrice`. Free delivery is reported as a zero amount of TEER. Returns `None` for
+/// destinations we don't route to and for prices which can't be expressed in a single currency.
+pub fn delivery_fee<ParentPrice, SiblingPrice, AssetToCurrency>(
+	dest: &MultiLocation,
+	message_size: u32,
+) -> Option<(CurrencyId, u128)>
+where
+	ParentPrice: PriceForParentDelivery,
+	SiblingPrice: PriceForParachainDelivery,
+	AssetToCurrency: Convert<MultiAsset, Option<CurrencyId>>,
+{
+	let message = message_of_size(message_size);
+	let price = match dest {
+		MultiLocation { parents: 1, interior: Here } =>
+			ParentPrice::price_for_parent_delivery(&message),
+		MultiLocation { parents: 1, interior: X1(Parachain(id)) } =>
+			SiblingPrice::price_for_parachain_delivery(ParaId::from(*id), &message),
+		_ => return None,
+	};
+
+	match price.inner().as_slice() {
+		[] => Some((CurrencyId::TEER, 0)),
+		[asset @ MultiAsset { fun: Fungible(amount), .. }] =>
+			AssetToCurrency::convert(asset.clone()).map(|currency| (currency, *amount)),
+		_ => None,
+	}
+}
+
+/// The currencies which `Converter` doesn't convert to a location and back to the same currency,
+/// from the location itself as well as from an asset at it. Currencies in `excluded` are skipped.
+///
+/// An asymmetric conversion means a currency can be received but not sent, or vice versa.
+pub fn asymmetric_currencies<Converter>(excluded: &[CurrencyId]) -> Vec<CurrencyId>
+where
+	Converter: Convert<CurrencyId, Option<MultiLocation>>
+		+ Convert<MultiLocation, Option<CurrencyId>>
+		+ Convert<MultiAsset, Option<CurrencyId>>,
+{
+	CurrencyId::all()
+		.filter(|currency| !excluded.contains(currency))
+		.filter(|&currency| {
+			let Some(location) = <Converter as Convert<CurrencyId, _>>::convert(currency) else {
+				return true
+			};
+			let from_location =
+				<Converter as Convert<MultiLocation, Option<CurrencyId>>>::convert(location);
+			let from_asset = <Converter as Convert<MultiAsset, Option<CurrencyId>>>::convert(
+				(location, 1u128).into(),
+			);
+			from_location != Some(currency) || from_asset != Some(currency)
+		})
+		.collect()
+}
+
+/// Messages covering what a weigher has to handle: transfers of one and of many assets, `Transact`,
+/// nested instructions, and messages of exactly `max_instructions` instructions (flat and nested)
+/// as well as of one more.
+pub fn representative_messages<Call>(max_instructions: u32) -> Vec<Xcm<Call>> {
+	let clear_origins = |count: u32| Xcm((0..count).map(|_| ClearOrigin).collect());
+	let transfer = |count: u128| {
+		let assets: MultiAssets = (0..count)
+			.map(|i| (MultiLocation::new(1, X1(GeneralIndex(i))), 100).into())
+			.collect::<Vec<MultiAsset>>()
+			.into();
+		Xcm(vec![
+			ReserveAssetDeposited(assets),
+			ClearOrigin,
+			BuyExecution { fees: (Parent, 100).into(), weight_limit: Unlimited },
+			DepositAsset { assets: Wild(AllCounted(count as u32)), beneficiary: Parent.into() },
+		])
+	};
+
+	vec![
+		Xcm(vec![]),
+		transfer(1),
+		transfer(8),
+		Xcm(vec![Transact {
+			origin_kind: OriginKind::SovereignAccount,
+			require_weight_at_most: Weight::from_parts(1_000_000, 1_000),
+			call: Vec::new().into(),
+		}]),
+		Xcm(vec![SetErrorHandler(transfer(2)), SetAppendix(transfer(1))]),
+		clear_origins(max_instructions),
+		clear_origins(max_instructions + 1),
+		Xcm(vec![SetAppendix(clear_origins(max_instructions.saturating_sub(1)))]),
+		Xcm(vec![SetAppendix(clear_origins(max_instructions))]),
+	]
+}
+
+/// The indices of the `messages` which `A` and `B` weigh differently, including messages only
+/// one of them rejects.
+pub fn weigher_mismatches<A, B, Call>(messages: Vec<Xcm<Call>>) -> Vec<usize>
+where
+	A: WeightBounds<Call>,
+	B: WeightBounds<Call>,
+{
+	messages
+		.into_iter()
+		.enumerate()
+		.filter_map(|(i, mut message)| {
+			(A::weight(&mut message) != B::weight(&mut message)).then_some(i)
+		})
+		.collect()
+}
+
+/// A failed consistency check of a runtime's XCM configuration, see the `IntegrityCheckApi`.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum IntegrityFailure {
+	/// These currencies don't convert to their location and back, see [`asymmetric_currencies`].
+	AsymmetricCurrencies(Vec<CurrencyId>),
+	/// The weigher of the pallet named `pallet` weighs the [`representative_messages`] at the
+	/// indices `messages` unlike the executor, see [`weigher_mismatches`].
+	WeigherMismatch { pallet: Vec<u8>, messages: Vec<u32> },
+}
+
+/// Check that `Converter` converts every currency but the `excluded` ones to its location and
+/// back.
+pub fn check_currency_round_trip<Converter>(excluded: &[CurrencyId]) -> Option<IntegrityFailure>
+where
+	Converter: Convert<CurrencyId, Option<MultiLocation>>
+		+ Convert<MultiLocation, Option<CurrencyId>>
+		+ Convert<MultiAsset, Option<CurrencyId>>,
+{
+	let currencies = asymmetric_currencies::<Converter>(excluded);
+	(!currencies.is_empty()).then_some(IntegrityFailure::AsymmetricCurrencies(currencies))
+}
+
+/// Check that `PalletWeigher`, the weigher of the pallet named `pallet`, weighs messages of up to
+/// `max_instructions` instructions like the executor's `Weigher`.
+pub fn check_weigher<Weigher, PalletWeigher, Call>(
+	pallet: &str,
+	max_instructions: u32,
+) -> Option<IntegrityFailure>
+where
+	Weigher: WeightBounds<Call>,
+	PalletWeigher: WeightBounds<Call>,
+{
+	let messages = weigher_mismatches::<Weigher, PalletWeigher, Call>(representative_messages(
+		max_instructions,
+	));
+	(!messages.is_empty()).then(|| IntegrityFailure::WeigherMismatch {
+		pallet: pallet.as_bytes().to_vec(),
+		messages: messages.into_iter().map(|i| i as u32).collect(),
+	})
+}
+
+/// Maximum number of locations [`resolve_currencies`] accepts at once.
+pub const MAX_CURRENCY_BATCH: u32 = 256;
+
+/// A batch of locations to resolve exceeded [`MAX_CURRENCY_BATCH`].
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct BatchTooLarge {
+	/// The maximum batch size.
+	pub max: u32,
+}
+
+/// Resolve each of `locations` to the currency at it, in order. Locations which can't be
+/// converted to the latest XCM version or are no known currency resolve to `None`.
+///
+/// Batches of more than [`MAX_CURRENCY_BATCH`] locations are rejected as a whole.
+pub fn resolve_currencies<LocationToCurrency>(
+	locations: Vec<VersionedMultiLocation>,
+) -> Result<Vec<Option<CurrencyId>>, BatchTooLarge>
+where
+	LocationToCurrency: Convert<MultiLocation, Option<CurrencyId>>,
+{
+	if locations.len() > MAX_CURRENCY_BATCH as usize {
+		return Err(BatchTooLarge { max: MAX_CURRENCY_BATCH })
+	}
+	Ok(locations
+		.into_iter()
+		.map(|location| {
+			MultiLocation::try_from(location).ok().and_then(LocationToCurrency::convert)
+		})
+		.collect())
+}
+
+/// The metadata of a currency, for wallets displaying its amounts.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct CurrencyMetadata {
+	/// The token symbol, e.g. `TEER`.
+	pub symbol: Vec<u8>,
+	/// The number of decimals of amounts.
+	pub decimals: u8,
+}
+
+/// The metadata of the currency at `location`, `None` if the location can't be converted to the
+/// latest XCM version or is no known currency.
+pub fn currency_metadata<LocationToCurrency>(
+	location: VersionedMultiLocation,
+) -> Option<CurrencyMetadata>
+where
+	LocationToCurrency: Convert<MultiLocation, Option<CurrencyId>>,
+{
+	MultiLocation::try_from(location)
+		.ok()
+		.and_then(LocationToCurrency::convert)
+		.map(|currency| currency.metadata())
+}
+
+/// Why a `Transact` would fail before its call is dispatched.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum TransactRejection {
+	/// The call can't be decoded.
+	UndecodableCall,
+	/// The call is not allowed by the `SafeCallFilter`.
+	CallFiltered,
+	/// The origin can't be converted to a local origin of the given kind.
+	BadOrigin,
+}
+
+/// Dry-run the dispatch of `call` by a `Transact` from `origin` with `origin_kind`, the way the
+/// XCM executor would dispatch it. All changes of the dispatch are rolled back.
+///
+/// Returns the result of the dispatch, or why the executor would not even dispatch the call. An
+/// origin which can't be converted to the latest XCM version is a [`TransactRejection::BadOrigin`].
+pub fn dry_run_transact<Call, SafeCallFilter, OriginConverter, Dispatcher>(
+	call: Vec<u8>,
+	origin_kind: OriginKind,
+	origin: VersionedMultiLocation,
+) -> Result<DispatchResult, TransactRejection>
+where
+	Call: Decode + Dispatchable<PostInfo = PostDispatchInfo>,
+	SafeCallFilter: Contains<Call>,
+	OriginConverter: ConvertOrigin<Call::RuntimeOrigin>,
+	Dispatcher: CallDispatcher<Call>,
+{
+	let call = DoubleEncoded::<Call>::from(call)
+		.take_decoded()
+		.map_err(|_| TransactRejection::UndecodableCall)?;
+	if !SafeCallFilter::contains(&call) {
+		return Err(TransactRejection::CallFiltered)
+	}
+	let origin = MultiLocation::try_from(origin).map_err(|_| TransactRejection::BadOrigin)?;
+	let origin = OriginConverter::convert_origin(origin, origin_kind)
+		.map_err(|_| TransactRejection::BadOrigin)?;
+
+	Ok(with_transaction(|| {
+		TransactionOutcome::Rollback(
+			Dispatcher::dispatch(call, origin).map(|_| ()).map_err(|e| e.error),
+		)
+	}))
+}
+
+/// The rules of a barrier, checked one by one such that [`dry_run_barrier`] can tell which of them
+/// rejects a message. Implemented for tuples of barriers, each of them being a rule.
+pub trait BarrierRules {
+	/// Check `message` against each rule in order, with the name of the rule.
+	fn check_each<Call>(
+		origin: &MultiLocation,
+		message: &mut [Instruction<Call>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+	) -> Vec<(&'static str, Result<(), ProcessMessageError>)>;
+
+	/// Check `message` against the rules in order, stopping at the first which rejects it.
+	fn check_all<Call>(
+		origin: &MultiLocation,
+		message: &mut [Instruction<Call>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+	) -> Result<(), ProcessMessageError>;
+}
+
+/// The name of the barrier `Rule`, its type name without path and generic parameters.
+fn rule_name<Rule>() -> &'static str {
+	let name = core::any::type_name::<Rule>();
+	let name = name.split('<').next().unwrap_or(name);
+	name.rsplit("::").next().unwrap_or(name)
+}
+
+macro_rules! impl_barrier_rules {
+	($($rule:ident),+) => {
+		impl<$($rule: ShouldExecute),+> BarrierRules for ($($rule,)+) {
+			fn check_each<Call>(
+				origin: &MultiLocation,
+				message: &mut [Instruction<Call>],
+				max_weight: Weight,
+				weight_credit: &mut Weight,
+			) -> Vec<(&'static str, Result<(), ProcessMessageError>)> {
+				vec![$((
+					rule_name::<$rule>(),
+					$rule::should_execute(origin, message, max_weight, weight_credit),
+				)),+]
+			}
+
+			fn check_all<Call>(
+				origin: &MultiLocation,
+				message: &mut [Instruction<Call>],
+				max_weight: Weight,
+				weight_credit: &mut Weight,
+			) -> Result<(), ProcessMessageError> {
+				$($rule::should_execute(origin, message, max_weight, weight_credit)?;)+
+				Ok(())
+			}
+		}
+	};
+}
+
+impl_barrier_rules!(A);
+impl_barrier_rules!(A, B);
+impl_barrier_rules!(A, B, C);
+impl_barrier_rules!(A, B, C, D);
+impl_barrier_rules!(A, B, C, D, E);
+impl_barrier_rules!(A, B, C, D, E, F);
+
+/// Deny executing the XCM if any of the `Deny` rules rejects it. If it passes all of them and
+/// matches one of the `Allow` rules, it is let through.
+///
+/// Like nested [`DenyThenTry`]s, but its rules can be checked one by one by [`dry_run_barrier`].
+pub struct DenyAllThenTry<Deny, Allow>(PhantomData<(Deny, Allow)>);
+
+impl<Deny: BarrierRules, Allow: ShouldExecute> ShouldExecute for DenyAllThenTry<Deny, Allow> {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		message: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		weight_credit: &mut Weight,
+	) -> Result<(), ProcessMessageError> {
+		Deny::check_all(origin, message, max_weight, weight_credit)?;
+		Allow::should_execute(origin, message, max_weight, weight_credit)
+	}
+}
+
+/// Why a barrier would not admit a message.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum BarrierRejection {
+	/// The message is no SCALE encoded `VersionedXcm`, or it can't be converted to the latest
+	/// XCM version.
+	UndecodableMessage,
+	/// The origin can't be converted to the latest XCM version.
+	BadOrigin,
+	/// The message can't be weighed, e.g. because it has too many instructions.
+	Unweighable,
+	/// A deny rule rejected the message.
+	Denied { rule: Vec<u8>, error: ProcessMessageError },
+	/// None of the allow rules admitted the message, with the reason each of them gave.
+	NotAllowed { rejections: Vec<(Vec<u8>, ProcessMessageError)> },
+}
+
+/// Check whether a [`DenyAllThenTry<Deny, Allow>`] barrier would admit the SCALE encoded
+/// `VersionedXcm` `message` from `origin`, weighed by `Weigher`, as the first thing the XCM
+/// executor does with an inbound message. Inbound messages have no weight credit.
+///
+/// Nothing is executed, and anything the rules write to storage is rolled back.
+pub fn dry_run_barrier<Call, Weigher, Deny, Allow>(
+	message: Vec<u8>,
+	origin: VersionedMultiLocation,
+) -> Result<(), BarrierRejection>
+where
+	Call: Decode,
+	Weigher: WeightBounds<Call>,
+	Deny: BarrierRules,
+	Allow: BarrierRules,
+{
+	let mut message =
+		VersionedXcm::<Call>::decode_with_depth_limit(MAX_XCM_DECODE_DEPTH, &mut &message[..])
+			.ok()
+			.and_then(|message| Xcm::<Call>::try_from(message).ok())
+			.ok_or(BarrierRejection::UndecodableMessage)?;
+	let origin = MultiLocation::try_from(origin).map_err(|_| BarrierRejection::BadOrigin)?;
+	let max_weight = Weigher::weight(&mut message).map_err(|_| BarrierRejection::Unweighable)?;
+	let mut weight_credit = Weight::zero();
+
+	sp_io::storage::start_transaction();
+	let denied = Deny::check_each(&origin, &mut message.0, max_weight, &mut weight_credit)
+		.into_iter()
+		.find_map(|(rule, result)| result.err().map(|error| (rule, error)));
+	let verdict = match denied {
+		Some((rule, error)) => Err(BarrierRejection::Denied { rule: rule.into(), error }),
+		None => {
+			let results =
+				Allow::check_each(&origin, &mut message.0, max_weight, &mut weight_credit);
+			if results.iter().any(|(_, result)| result.is_ok()) {
+				Ok(())
+			} else {
+				let rejections = results
+					.into_iter()
+					.filter_map(|(rule, result)| result.err().map(|error| (rule.into(), error)))
+					.collect();
+				Err(BarrierRejection::NotAllowed { rejections })
+			}
+		},
+	};
+	sp_io::storage::rollback_transaction();
+	verdict
+}
+
+/// Why a message could not be dry-run.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum XcmDryRunError {
+	/// The message is no SCALE encoded `VersionedXcm`, or it can't be converted to the latest
+	/// XCM version.
+	UndecodableMessage,
+	/// The origin can't be converted to the latest XCM version.
+	BadOrigin,
+}
+
+/// What executing a message would do.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct XcmDryRunEffects {
+	/// The outcome of the execution, with the weight it consumed.
+	pub outcome: Outcome,
+	/// The messages the execution sent, with their destination, in order.
+	pub forwarded: Vec<(VersionedMultiLocation, VersionedXcm<()>)>,
+}
+
+/// Where a [`RecordingRouter`] records the messages it delivers.
+const DRY_RUN_FORWARDED_KEY: &[u8] = b":xcm_dry_run:forwarded:";
+
+/// Wraps the router `Inner`, recording the messages it delivers for [`dry_run_xcm`].
+pub struct RecordingRouter<Inner>(PhantomData<Inner>);
+
+impl<Inner: SendXcm> SendXcm for RecordingRouter<Inner> {
+	type Ticket = (Inner::Ticket, Option<(MultiLocation, Xcm<()>)>);
+
+	fn validate(
+		dest: &mut Option<MultiLocation>,
+		message: &mut Option<Xcm<()>>,
+	) -> SendResult<Self::Ticket> {
+		let sent = dest.clone().zip(message.clone());
+		let (ticket, price) = Inner::validate(dest, message)?;
+		Ok(((ticket, sent), price))
+	}
+
+	fn deliver((ticket, sent): Self::Ticket) -> Result<XcmHash, SendError> {
+		let hash = Inner::deliver(ticket)?;
+		if let Some((dest, message)) = sent {
+			let mut forwarded: Vec<(VersionedMultiLocation, VersionedXcm<()>)> =
+				unhashed::get(DRY_RUN_FORWARDED_KEY).unwrap_or_default();
+			forwarded.push((dest.into(), message.into()));
+			unhashed::put(DRY_RUN_FORWARDED_KEY, &forwarded);
+		}
+		Ok(hash)
+	}
+}
+
+/// The XCM executor configuration `Inner`, with its router wrapped into a [`RecordingRouter`].
+pub struct RecordingConfig<Inner>(PhantomData<Inner>);
+
+impl<Inner: xcm_executor::Config> xcm_executor::Config for RecordingConfig<Inner> {
+	type RuntimeCall = Inner::RuntimeCall;
+	type XcmSender = RecordingRouter<Inner::XcmSender>;
+	type AssetTransactor = Inner::AssetTransactor;
+	type OriginConverter = Inner::OriginConverter;
+	type IsReserve = Inner::IsReserve;
+	type IsTeleporter = Inner::IsTeleporter;
+	type UniversalLocation = Inner::UniversalLocation;
+	type Barrier = Inner::Barrier;
+	type Weigher = Inner::Weigher;
+	type Trader = Inner::Trader;
+	type ResponseHandler = Inner::ResponseHandler;
+	type AssetTrap = Inner::AssetTrap;
+	type AssetLocker = Inner::AssetLocker;
+	type AssetExchanger = Inner::AssetExchanger;
+	type AssetClaims = Inner::AssetClaims;
+	type SubscriptionService = Inner::SubscriptionService;
+	type PalletInstancesInfo = Inner::PalletInstancesInfo;
+	type MaxAssetsIntoHolding = Inner::MaxAssetsIntoHolding;
+	type FeeManager = Inner::FeeManager;
+	type MessageExporter = Inner::MessageExporter;
+	type UniversalAliases = Inner::UniversalAliases;
+	type CallDispatcher = Inner::CallDispatcher;
+	type SafeCallFilter = Inner::SafeCallFilter;
+}
+
+/// Execute the SCALE encoded `VersionedXcm` `message` from `origin` with the executor
+/// configuration `Config`, the way an inbound message without weight credit is executed.
+///
+/// Returns the outcome and the messages the execution sent. Nothing is committed, neither the
+/// changes of the execution nor the sent messages.
+pub fn dry_run_xcm<Config: xcm_executor::Config>(
+	message: Vec<u8>,
+	origin: VersionedMultiLocation,
+) -> Result<XcmDryRunEffects, XcmDryRunError> {
+	let hash = sp_io::hashing::blake2_256(&message);
+	let message = VersionedXcm::<Config::RuntimeCall>::decode_with_depth_limit(
+		MAX_XCM_DECODE_DEPTH,
+		&mut &message[..],
+	)
+	.ok()
+	.and_then(|message| Xcm::<Config::RuntimeCall>::try_from(message).ok())
+	.ok_or(XcmDryRunError::UndecodableMessage)?;
+	let origin = MultiLocation::try_from(origin).map_err(|_| XcmDryRunError::BadOrigin)?;
+
+	sp_io::storage::start_transaction();
+	let outcome =
+		XcmExecutor::<RecordingConfig<Config>>::execute_xcm(origin, message, hash, Weight::MAX);
+	let forwarded = unhashed::get(DRY_RUN_FORWARDED_KEY).unwrap_or_default();
+	sp_io::storage::rollback_transaction();
+	Ok(XcmDryRunEffects { outcome, forwarded })
+}
+
+/// The queue an inbound message has been received by.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum InboundQueue {
+	/// Downward messages of the relay chain.
+	Dmp,
+	/// Messages of sibling parachains.
+	Xcmp,
+}
+
+impl InboundQueue {
+	/// The queue messages from `origin` are received by.
+	pub fn of(origin: &MultiLocation) -> Self {
+		if *origin == MultiLocation::parent() {
+			InboundQueue::Dmp
+		} else {
+			InboundQueue::Xcmp
+		}
+	}
+
+	/// The name of the queue, e.g. as a metric label.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			InboundQueue::Dmp => "dmp",
+			InboundQueue::Xcmp => "xcmp",
+		}
+	}
+}
+
+/// The weight an executed inbound message has been charged, as weighed by the `Weigher` of the
+/// executor, and the weight its execution actually consumed.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct InboundXcmWeight {
+	pub queue: InboundQueue,
+	pub charged: Weight,
+	pub consumed: Weight,
+}
+
+/// The backlog of an inbound XCMP channel.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct InboundChannelBacklog {
+	/// The sibling sending over the channel.
+	pub sender: ParaId,
+	/// Whether we asked the sender to suspend the channel, as its backlog reached the suspend
+	/// threshold.
+	pub suspended: bool,
+	/// The number of messages received but not executed yet.
+	pub messages: u32,
+}
+
+/// The backlog of an outbound XCMP channel.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct OutboundChannelBacklog {
+	/// The sibling receiving over the channel.
+	pub recipient: ParaId,
+	/// Whether the recipient asked us to suspend the channel.
+	pub suspended: bool,
+	/// Whether signals, e.g. to suspend or resume the channel, are waiting to be sent.
+	pub signals: bool,
+	/// The number of pages of messages waiting to be sent.
+	pub pages: u16,
+}
+
+/// The congestion of the XCMP queue, see [`xcmp_queue_status`].
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct XcmpQueueStatus {
+	/// The number of pending messages at which an inbound channel is suspended.
+	pub suspend_threshold: u32,
+	/// The number of pending messages at which further messages of an inbound channel are
+	/// dropped.
+	pub drop_threshold: u32,
+	/// The number of pending messages at which a suspended inbound channel is resumed.
+	pub resume_threshold: u32,
+	/// The inbound channels with a backlog, by sender.
+	pub inbound: Vec<InboundChannelBacklog>,
+	/// The outbound channels with a backlog, by recipient.
+	pub outbound: Vec<OutboundChannelBacklog>,
+	/// The type name of the origin which may execute overweight messages, as configured in
+	/// `cumulus_pallet_xcmp_queue::Config::ExecuteOverweightOrigin`.
+	pub execute_overweight_origin: Vec<u8>,
+}
+
+/// `cumulus_pallet_xcmp_queue::InboundChannelDetails`, whose fields are private.
+#[derive(Decode)]
+struct InboundChannelDetails {
+	sender: ParaId,
+	state: cumulus_pallet_xcmp_queue::InboundState,
+	// The relay block each message has been sent at and its `XcmpMessageFormat`, which encodes
+	// as a single byte.
+	message_metadata: Vec<(polkadot_primitives::BlockNumber, u8)>,
+}
+
+/// `cumulus_pallet_xcmp_queue::OutboundChannelDetails`, whose fields are private.
+#[derive(Decode)]
+struct OutboundChannelDetails {
+	recipient: ParaId,
+	state: cumulus_pallet_xcmp_queue::OutboundState,
+	signals_exist: bool,
+	first_index: u16,
+	last_index: u16,
+}
+
+/// The congestion of the XCMP queue `XcmpQueue`, which executes overweight messages by the origin
+/// `ExecuteOverweightOrigin`.
+///
+/// The storage of `cumulus_pallet_xcmp_queue` is private to it, so we read it by its keys.
+pub fn xcmp_queue_status<XcmpQueue: PalletInfoAccess, ExecuteOverweightOrigin>() -> XcmpQueueStatus
+{
+	let key =
+		|item: &[u8]| frame_support::storage::storage_prefix(XcmpQueue::name().as_bytes(), item);
+	let cumulus_pallet_xcmp_queue::QueueConfigData {
+		suspend_threshold,
+		drop_threshold,
+		resume_threshold,
+		..
+	} = unhashed::get(&key(b"QueueConfig")).unwrap_or_default();
+	let inbound = unhashed::get::<Vec<InboundChannelDetails>>(&key(b"InboundXcmpStatus"))
+		.unwrap_or_default()
+		.into_iter()
+		.map(|channel| InboundChannelBacklog {
+			sender: channel.sender,
+			suspended: channel.state == cumulus_pallet_xcmp_queue::InboundState::Suspended,
+			messages: channel.message_metadata.len() as u32,
+		})
+		.collect();
+	let outbound = unhashed::get::<Vec<OutboundChannelDetails>>(&key(b"OutboundXcmpStatus"))
+		.unwrap_or_default()
+		.into_iter()
+		.map(|channel| OutboundChannelBacklog {
+			recipient: channel.recipient,
+			suspended: channel.state == cumulus_pallet_xcmp_queue::OutboundState::Suspended,
+			signals: channel.signals_exist,
+			pages: channel.last_index.saturating_sub(channel.first_index),
+		})
+		.collect();
+	XcmpQueueStatus {
+		suspend_threshold,
+		drop_threshold,
+		resume_threshold,
+		inbound,
+		outbound,
+		execute_overweight_origin: core::any::type_name::<ExecuteOverweightOrigin>().into(),
+	}
+}
+
+/// A message of roughly `size` encoded bytes, the price functions only look at the size.
+fn message_of_size(size: u32) -> Xcm<()> {
+	Xcm(vec![Transact {
+		origin_kind: OriginKind::SovereignAccount,
+		require_weight_at_most: Weight::zero(),
+		call: vec![0u8; size as usize].into(),
+	}])
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_runtime::DispatchError;
+
+	/// Converts any origin to a superuser flag, like a misconfigured `ParentAsSuperuser` would.
+	struct AnyAsSuperuser;
+	impl ConvertOrigin<bool> for AnyAsSuperuser {
+		fn convert_origin(
+			_origin: impl Into<MultiLocation>,
+			kind: OriginKind,
+		) -> Result<bool, MultiLocation> {
+			Ok(kind == OriginKind::Superuser)
+		}
+	}
+
+	type Converter = SuperuserOnlyFromRelay<AnyAsSuperuser>;
+
+	#[test]
+	fn superuser_from_relay_is_honoured() {
+		assert_eq!(
+			Converter::convert_origin(MultiLocation::parent(), OriginKind::Superuser),
+			Ok(true)
+		);
+	}
+
+	#[test]
+	fn superuser_from_sibling_is_rejected() {
+		let sibling = MultiLocation::new(1, X1(Parachain(1000)));
+		assert_eq!(Converter::convert_origin(sibling, OriginKind::Superuser), Err(sibling));
+	}
+
+	#[test]
+	fn superuser_from_local_account_is_rejected() {
+		let account = MultiLocation::new(0, X1(AccountId32 { network: None, id: [0; 32] }));
+		assert_eq!(Converter::convert_origin(account, OriginKind::Superuser), Err(account));
+	}
+
+	#[test]
+	fn other_origin_kinds_are_passed_through() {
+		let sibling = MultiLocation::new(1, X1(Parachain(1000)));
+		assert_eq!(Converter::convert_origin(sibling, OriginKind::SovereignAccount), Ok(false));
+	}
+
+	/// Weighs every instruction 10.
+	struct PerInstruction;
+	impl WeightBounds<()> for PerInstruction {
+		fn weight(message: &mut Xcm<()>) -> Result<Weight, ()> {
+			Ok(Weight::from_parts(10 * message.0.len() as u64, 0))
+		}
+
+		fn instr_weight(_instruction: &Instruction<()>) -> Result<Weight, ()> {
+			Ok(Weight::from_parts(10, 0))
+		}
+	}
+
+	frame_support::parameter_types! {
+		pub PerAsset: Weight = Weight::from_parts(1, 0);
+	}
+
+	type Weigher = AssetCountWeigher<PerInstruction, PerAsset>;
+
+	/// A reserve transfer of `count` different assets.
+	fn reserve_transfer(count: u128) -> Xcm<()> {
+		let assets: MultiAssets = (0..count)
+			.map(|i| (MultiLocation::new(1, X1(GeneralIndex(i))), 100).into())
+			.collect::<Vec<MultiAsset>>()
+			.into();
+		Xcm(vec![
+			ReserveAssetDeposited(assets),
+			ClearOrigin,
+			BuyExecution { fees: (Parent, 100).into(), weight_limit: Unlimited },
+			DepositAsset { assets: Wild(AllCounted(count as u32)), beneficiary: Parent.into() },
+		])
+	}
+
+	#[test]
+	fn single_asset_message_is_weighed_like_by_inner() {
+		let mut message = reserve_transfer(1);
+		assert_eq!(Weigher::weight(&mut message), PerInstruction::weight(&mut message));
+		assert_eq!(Weigher::instr_weight(&message.0[0]), Ok(Weight::from_parts(10, 0)));
+	}
+
+	#[test]
+	fn many_assets_are_weighed_higher() {
+		// `MaxAssetsIntoHolding` of the runtimes.
+		let mut many = reserve_transfer(64);
+		let mut single = reserve_transfer(1);
+
+		let many_weight = Weigher::weight(&mut many).unwrap();
+		let single_weight = Weigher::weight(&mut single).unwrap();
+		assert!(many_weight.all_gt(single_weight));
+		assert_eq!(many_weight, single_weight + Weight::from_parts(63, 0));
+		assert_eq!(Weigher::instr_weight(&many.0[0]), Ok(Weight::from_parts(10 + 63, 0)));
+	}
+
+	#[test]
+	fn nested_assets_are_weighed() {
+		let mut message = Xcm(vec![SetAppendix(reserve_transfer(3))]);
+		assert_eq!(Weigher::weight(&mut message), Ok(Weight::from_parts(10 + 2, 0)));
+	}
+
+	frame_support::parameter_types! {
+		pub PerConversion: Weight = Weight::from_parts(5, 0);
+	}
+
+	type ConversionsWeigher = ConversionWeigher<PerInstruction, PerConversion>;
+
+	#[test]
+	fn many_distinct_assets_are_weighed_higher() {
+		let mut many = reserve_transfer(64);
+		let mut few = reserve_transfer(2);
+
+		let many_weight = ConversionsWeigher::weight(&mut many).unwrap();
+		let few_weight = ConversionsWeigher::weight(&mut few).unwrap();
+		assert_eq!(many_weight, Weight::from_parts(4 * 10 + 64 * 5, 0));
+		assert_eq!(few_weight, Weight::from_parts(4 * 10 + 2 * 5, 0));
+		assert_eq!(
+			ConversionsWeigher::instr_weight(&many.0[0]),
+			Ok(Weight::from_parts(10 + 64 * 5, 0))
+		);
+	}
+
+	#[test]
+	fn identical_assets_are_converted_once() {
+		let asset: MultiAsset = (Parent, 100).into();
+		let mut repeated = Xcm(vec![
+			WithdrawAsset(asset.clone().into()),
+			ClearOrigin,
+			WithdrawAsset(asset.clone().into()),
+			SetAppendix(Xcm(vec![WithdrawAsset(asset.into())])),
+		]);
+		assert_eq!(
+			ConversionsWeigher::weight(&mut repeated),
+			Ok(Weight::from_parts(4 * 10 + 5, 0))
+		);
+	}
+
+	#[test]
+	fn conversions_are_cached_within_a_message() {
+		let location = MultiLocation::parent();
+		let id = Concrete(location);
+		let conversions = core::cell::Cell::new(0);
+		let convert = || {
+			conversions.set(conversions.get() + 1);
+			Some(CurrencyId::KSM)
+		};
+		let reserve = || {
+			conversions.set(conversions.get() + 1);
+			Some(location)
+		};
+
+		with_conversion_cache(|| {
+			assert_eq!(cached_currency(&location, convert), Some(CurrencyId::KSM));
+			assert_eq!(cached_currency(&location, convert), Some(CurrencyId::KSM));
+			assert_eq!(cached_reserve(&id, reserve), Some(location));
+			assert_eq!(cached_reserve(&id, reserve), Some(location));
+		});
+		assert_eq!(conversions.get(), 2);
+
+		// Without a cache, every call converts.
+		assert_eq!(cached_currency(&location, convert), Some(CurrencyId::KSM));
+		assert_eq!(cached_currency(&location, convert), Some(CurrencyId::KSM));
+		assert_eq!(conversions.get(), 4);
+
+		// Every message starts with an empty cache.
+		with_conversion_cache(|| cached_currency(&location, convert));
+		assert_eq!(conversions.get(), 5);
+	}
+
+	/// Weighs like [`PerInstruction`], but rejects messages of more than `MAX` instructions,
+	/// including nested ones.
+	struct LimitedPerInstruction<const MAX: usize>;
+	impl<const MAX: usize> WeightBounds<()> for LimitedPerInstruction<MAX> {
+		fn weight(message: &mut Xcm<()>) -> Result<Weight, ()> {
+			fn count(xcm: &Xcm<()>) -> usize {
+				xcm.0
+					.iter()
+					.map(|instruction| match instruction {
+						SetErrorHandler(xcm) | SetAppendix(xcm) => 1 + count(xcm),
+						_ => 1,
+					})
+					.sum()
+			}
+			let count = count(message);
+			(count <= MAX).then(|| Weight::from_parts(10 * count as u64, 0)).ok_or(())
+		}
+
+		fn instr_weight(_instruction: &Instruction<()>) -> Result<Weight, ()> {
+			Ok(Weight::from_parts(10, 0))
+		}
+	}
+
+	#[test]
+	fn identical_weighers_match() {
+		let messages = representative_messages(10);
+		assert_eq!(weigher_mismatches::<Weigher, Weigher, ()>(messages), Vec::<usize>::new());
+	}
+
+	#[test]
+	fn diverging_weights_are_reported() {
+		// Only the transfers of several assets are weighed higher by `Weigher`.
+		assert_eq!(
+			weigher_mismatches::<Weigher, PerInstruction, ()>(representative_messages(10)),
+			vec![2, 4]
+		);
+	}
+
+	#[test]
+	fn diverging_instruction_limits_are_reported() {
+		// The messages of just too many instructions are accepted by one and rejected by the other.
+		assert_eq!(
+			weigher_mismatches::<LimitedPerInstruction<10>, LimitedPerInstruction<11>, ()>(
+				representative_messages(10)
+			),
+			vec![6, 8]
+		);
+	}
+
+	fn teer() -> MultiLocation {
+		MultiLocation::new(0, X1(GeneralIndex(0)))
+	}
+
+	/// Charges one unit of TEER per byte.
+	struct PerByte;
+	impl PriceForParachainDelivery for PerByte {
+		fn price_for_parachain_delivery(_para: ParaId, message: &Xcm<()>) -> MultiAssets {
+			vec![(teer(), message.encoded_size() as u128).into()].into()
+		}
+	}
+
+	/// Converts TEER, KSM and USDT from and to their locations.
+	struct Currencies;
+	impl Convert<MultiAsset, Option<CurrencyId>> for Currencies {
+		fn convert(asset: MultiAsset) -> Option<CurrencyId> {
+			match asset.id {
+				Concrete(location) => Self::convert(location),
+				Abstract(_) => None,
+			}
+		}
+	}
+
+	impl Convert<MultiLocation, Option<CurrencyId>> for Currencies {
+		fn convert(location: MultiLocation) -> Option<CurrencyId> {
+			CurrencyId::all().find(|&currency| Self::convert(currency) == Some(location))
+		}
+	}
+
+	impl Convert<CurrencyId, Option<MultiLocation>> for Currencies {
+		fn convert(currency: CurrencyId) -> Option<MultiLocation> {
+			match currency {
+				CurrencyId::TEER => Some(teer()),
+				CurrencyId::KSM => Some(MultiLocation::parent()),
+				CurrencyId::USDT => Some(USDT_LOCATION),
+			}
+		}
+	}
+
+	/// Converts currencies to locations, but has not been updated to convert TEER back.
+	struct OneWay;
+	impl Convert<CurrencyId, Option<MultiLocation>> for OneWay {
+		fn convert(currency: CurrencyId) -> Option<MultiLocation> {
+			Currencies::convert(currency)
+		}
+	}
+
+	impl Convert<MultiLocation, Option<CurrencyId>> for OneWay {
+		fn convert(location: MultiLocation) -> Option<CurrencyId> {
+			Currencies::convert(location).filter(|currency| *currency != CurrencyId::TEER)
+		}
+	}
+
+	impl Convert<MultiAsset, Option<CurrencyId>> for OneWay {
+		fn convert(asset: MultiAsset) -> Option<CurrencyId> {
+			Currencies::convert(asset)
+		}
+	}
+
+	#[test]
+	fn all_currencies_are_listed() {
+		assert_eq!(
+			CurrencyId::all().collect::<Vec<_>>(),
+			vec![CurrencyId::TEER, CurrencyId::KSM, CurrencyId::USDT]
+		);
+	}
+
+	#[test]
+	fn symmetric_conversions_pass() {
+		assert_eq!(asymmetric_currencies::<Currencies>(&[]), vec![]);
+	}
+
+	#[test]
+	fn missing_reverse_conversion_is_reported() {
+		assert_eq!(asymmetric_currencies::<OneWay>(&[]), vec![CurrencyId::TEER]);
+	}
+
+	#[test]
+	fn consistent_configuration_passes_the_integrity_checks() {
+		assert_eq!(check_currency_round_trip::<Currencies>(&[]), None);
+		assert_eq!(check_weigher::<Weigher, Weigher, ()>("pallet_xcm", 10), None);
+	}
+
+	#[test]
+	fn inconsistent_configuration_fails_the_integrity_checks() {
+		assert_eq!(
+			check_currency_round_trip::<OneWay>(&[]),
+			Some(IntegrityFailure::AsymmetricCurrencies(vec![CurrencyId::TEER]))
+		);
+		assert_eq!(
+			check_weigher::<Weigher, PerInstruction, ()>("pallet_xcm", 10),
+			Some(IntegrityFailure::WeigherMismatch {
+				pallet: b"pallet_xcm".to_vec(),
+				messages: vec![2, 4]
+			})
+		);
+	}
+
+	#[test]
+	fn excluded_currencies_are_not_reported() {
+		assert_eq!(asymmetric_currencies::<OneWay>(&[CurrencyId::TEER]), vec![]);
+	}
+
+	#[test]
+	fn filtered_currencies_convert_only_to_the_filtered_ones() {
+		type Native = FilteredCurrencies<Currencies, NativeCurrencies>;
+		type Foreign = FilteredCurrencies<Currencies, ForeignCurrencies>;
+
+		assert_eq!(Native::convert(teer()), Some(CurrencyId::TEER));
+		assert_eq!(Native::convert(MultiLocation::parent()), None);
+		assert_eq!(
+			Foreign::convert(MultiAsset::from((MultiLocation::parent(), 1))),
+			Some(CurrencyId::KSM)
+		);
+		assert_eq!(Native::convert(USDT_LOCATION), None);
+		assert_eq!(Foreign::convert(MultiAsset::from((USDT_LOCATION, 1))), Some(CurrencyId::USDT));
+		assert_eq!(Foreign::convert(MultiAsset::from((teer(), 1))), None);
+	}
+
+	type Fee = (CurrencyId, u128);
+
+	fn general_key(length: u8, key: &[u8; 32]) -> Junction {
+		GeneralKey { length, data: *key }
+	}
+
+	#[test]
+	fn general_key_matching_the_symbol_is_accepted() {
+		let key = general_key(4, b"TEER0000000000000000000000000000");
+		assert!(general_key_matches_symbol(&key, CurrencyId::TEER.symbol()));
+	}
+
+	#[test]
+	fn general_key_is_compared_up_to_its_length_only() {
+		let key = general_key(4, b"TEERSOMETHINGELSE000000000000000");
+		assert!(general_key_matches_symbol(&key, "TEER"));
+		assert!(!general_key_matches_symbol(&key, "TEERS"));
+
+		let truncated = general_key(3, b"TEER0000000000000000000000000000");
+		assert!(!general_key_matches_symbol(&truncated, "TEER"));
+	}
+
+	#[test]
+	fn general_key_not_matching_the_symbol_is_rejected() {
+		let key = general_key(4, b"TEER0000000000000000000000000000");
+		assert!(!general_key_matches_symbol(&key, "TEEX"));
+		assert!(!general_key_matches_symbol(&Parachain(1), "TEER"));
+	}
+
+	#[test]
+	fn free_delivery_to_relay_is_zero() {
+		let fee: Option<Fee> =
+			delivery_fee::<(), PerByte, Currencies>(&MultiLocation::parent(), 100);
+		assert_eq!(fee, Some((CurrencyId::TEER, 0)));
+	}
+
+	#[test]
+	fn sibling_delivery_is_priced_by_message_size() {
+		let sibling = MultiLocation::new(1, X1(Parachain(1000)));
+		let (currency, small) = delivery_fee::<(), PerByte, Currencies>(&sibling, 100).unwrap();
+		let (_, large) = delivery_fee::<(), PerByte, Currencies>(&sibling, 1_000).unwrap();
+
+		assert_eq!(currency, CurrencyId::TEER);
+		assert!(small >= 100);
+		assert!(large >= 1_000 && large - small >= 900);
+	}
+
+	#[test]
+	fn size_based_delivery_charges_larger_messages_more() {
+		frame_support::parameter_types! {
+			pub Teer: MultiLocation = teer();
+			pub const BaseFee: u128 = 1_000;
+			pub const ByteFee: u128 = 10;
+		}
+		type Price = SizeBasedDelivery<Teer, BaseFee, ByteFee>;
+
+		let sibling = MultiLocation::new(1, X1(Parachain(1000)));
+		let (currency, small) = delivery_fee::<(), Price, Currencies>(&sibling, 100).unwrap();
+		let (_, large) = delivery_fee::<(), Price, Currencies>(&sibling, 1_000).unwrap();
+
+		assert_eq!(currency, CurrencyId::TEER);
+		assert!(small >= 1_000 + 10 * 100);
+		assert!(large > small);
+	}
+
+	#[test]
+	fn unrouted_destination_has_no_fee() {
+		let remote = MultiLocation::new(2, X1(GlobalConsensus(NetworkId::Polkadot)));
+		assert_eq!(delivery_fee::<(), PerByte, Currencies>(&remote, 100), None);
+	}
+
+	#[test]
+	fn batch_is_resolved_in_order() {
+		let unknown = MultiLocation::new(1, X1(Parachain(1000)));
+		let locations = vec![teer().into(), unknown.into(), teer().into()];
+
+		assert_eq!(
+			resolve_currencies::<Currencies>(locations),
+			Ok(vec![Some(CurrencyId::TEER), None, Some(CurrencyId::TEER)])
+		);
+	}
+
+	#[test]
+	fn locations_without_latest_version_resolve_to_none() {
+		// Named networks don't exist in v3 anymore.
+		let network = xcm::v2::NetworkId::Named(b"remote".to_vec().try_into().unwrap());
+		let location = xcm::v2::MultiLocation::new(
+			0,
+			xcm::v2::Junctions::X1(xcm::v2::Junction::AccountId32 { network, id: [0; 32] }),
+		);
+		assert_eq!(
+			resolve_currencies::<Currencies>(vec![VersionedMultiLocation::V2(location)]),
+			Ok(vec![None])
+		);
+	}
+
+	#[test]
+	fn known_currencies_have_metadata() {
+		assert_eq!(
+			currency_metadata::<Currencies>(teer().into()),
+			Some(CurrencyMetadata { symbol: b"TEER".to_vec(), decimals: 12 })
+		);
+		assert_eq!(
+			currency_metadata::<Currencies>(MultiLocation::parent().into()),
+			Some(CurrencyMetadata { symbol: b"KSM".to_vec(), decimals: 12 })
+		);
+		assert_eq!(
+			currency_metadata::<Currencies>(USDT_LOCATION.into()),
+			Some(CurrencyMetadata { symbol: b"USDT".to_vec(), decimals: 6 })
+		);
+		let unknown = MultiLocation::new(1, X1(Parachain(1000)));
+		assert_eq!(currency_metadata::<Currencies>(unknown.into()), None);
+	}
+
+	#[test]
+	fn batch_size_is_bounded() {
+		let full = vec![VersionedMultiLocation::from(teer()); MAX_CURRENCY_BATCH as usize];
+		assert_eq!(resolve_currencies::<Currencies>(full.clone()).map(|c| c.len()), Ok(full.len()));
+
+		let oversized = vec![VersionedMultiLocation::from(teer()); MAX_CURRENCY_BATCH as usize + 1];
+		assert_eq!(
+			resolve_currencies::<Currencies>(oversized),
+			Err(BatchTooLarge { max: MAX_CURRENCY_BATCH })
+		);
+	}
+
+	/// Calls dispatched by the para id of their origin.
+	#[derive(Encode, Decode)]
+	enum MockCall {
+		/// Writes to storage and succeeds.
+		Store,
+		/// Fails unless dispatched by para 1000.
+		OnlyPara1000,
+		/// Refused by [`NoForbidden`].
+		Forbidden,
+	}
+
+	impl Dispatchable for MockCall {
+		type RuntimeOrigin = u32;
+		type Config = ();
+		type Info = ();
+		type PostInfo = PostDispatchInfo;
+
+		fn dispatch(self, origin: u32) -> sp_runtime::DispatchResultWithInfo<PostDispatchInfo> {
+			match self {
+				MockCall::Store => sp_io::storage::set(b"stored", &[1]),
+				MockCall::OnlyPara1000 if origin != 1000 =>
+					return Err(DispatchError::BadOrigin.into()),
+				_ => (),
+			}
+			Ok(().into())
+		}
+	}
+
+	struct NoForbidden;
+	impl Contains<MockCall> for NoForbidden {
+		fn contains(call: &MockCall) -> bool {
+			!matches!(call, MockCall::Forbidden)
+		}
+	}
+
+	/// Converts sibling locations with `OriginKind::SovereignAccount` to their para id.
+	struct SiblingAsParaId;
+	impl ConvertOrigin<u32> for SiblingAsParaId {
+		fn convert_origin(
+			origin: impl Into<MultiLocation>,
+			kind: OriginKind,
+		) -> Result<u32, MultiLocation> {
+			match (kind, origin.into()) {
+				(
+					OriginKind::SovereignAccount,
+					MultiLocation { parents: 1, interior: X1(Parachain(id)) },
+				) => Ok(id),
+				(_, origin) => Err(origin),
+			}
+		}
+	}
+
+	fn dry_run(
+		call: Vec<u8>,
+		origin_kind: OriginKind,
+		para: u32,
+	) -> Result<DispatchResult, TransactRejection> {
+		sp_io::TestExternalities::default().execute_with(|| {
+			dry_run_transact::<MockCall, NoForbidden, SiblingAsParaId, MockCall>(
+				call,
+				origin_kind,
+				MultiLocation::new(1, X1(Parachain(para))).into(),
+			)
+		})
+	}
+
+	#[test]
+	fn allowed_call_dry_runs_successfully() {
+		assert_eq!(
+			dry_run(MockCall::OnlyPara1000.encode(), OriginKind::SovereignAccount, 1000),
+			Ok(Ok(()))
+		);
+	}
+
+	#[test]
+	fn failing_dispatch_is_reported() {
+		assert_eq!(
+			dry_run(MockCall::OnlyPara1000.encode(), OriginKind::SovereignAccount, 2000),
+			Ok(Err(DispatchError::BadOrigin))
+		);
+	}
+
+	#[test]
+	fn filtered_call_is_reported() {
+		assert_eq!(
+			dry_run(MockCall::Forbidden.encode(), OriginKind::SovereignAccount, 1000),
+			Err(TransactRejection::CallFiltered)
+		);
+	}
+
+	#[test]
+	fn unconvertible_origin_is_reported() {
+		assert_eq!(
+			dry_run(MockCall::Store.encode(), OriginKind::Native, 1000),
+			Err(TransactRejection::BadOrigin)
+		);
+	}
+
+	#[test]
+	fn undecodable_call_is_reported() {
+		assert_eq!(
+			dry_run(vec![42], OriginKind::SovereignAccount, 1000),
+			Err(TransactRejection::UndecodableCall)
+		);
+	}
+
+	#[test]
+	fn dry_run_changes_nothing() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			assert_eq!(
+				dry_run_transact::<MockCall, NoForbidden, SiblingAsParaId, MockCall>(
+					MockCall::Store.encode(),
+					OriginKind::SovereignAccount,
+					MultiLocation::new(1, X1(Parachain(1000))).into(),
+				),
+				Ok(Ok(()))
+			);
+			assert_eq!(sp_io::storage::get(b"stored"), None);
+		});
+	}
+
+	/// Admits messages buying execution, like `AllowTopLevelPaidExecutionFrom`.
+	struct RequirePayment;
+	impl ShouldExecute for RequirePayment {
+		fn should_execute<RuntimeCall>(
+			_origin: &MultiLocation,
+			message: &mut [Instruction<RuntimeCall>],
+			max_weight: Weight,
+			_weight_credit: &mut Weight,
+		) -> Result<(), ProcessMessageError> {
+			match message.iter().any(|instruction| matches!(instruction, BuyExecution { .. })) {
+				true => Ok(()),
+				false => Err(ProcessMessageError::Overweight(max_weight)),
+			}
+		}
+	}
+
+	/// Admits version subscriptions, like `AllowSubscriptionsFrom`.
+	struct AllowSubscriptions;
+	impl ShouldExecute for AllowSubscriptions {
+		fn should_execute<RuntimeCall>(
+			_origin: &MultiLocation,
+			message: &mut [Instruction<RuntimeCall>],
+			_max_weight: Weight,
+			_weight_credit: &mut Weight,
+		) -> Result<(), ProcessMessageError> {
+			match message {
+				[SubscribeVersion { .. }] => Ok(()),
+				_ => Err(ProcessMessageError::BadFormat),
+			}
+		}
+	}
+
+	/// Writes to storage and lets everything pass.
+	struct Scribble;
+	impl ShouldExecute for Scribble {
+		fn should_execute<RuntimeCall>(
+			_origin: &MultiLocation,
+			_message: &mut [Instruction<RuntimeCall>],
+			_max_weight: Weight,
+			_weight_credit: &mut Weight,
+		) -> Result<(), ProcessMessageError> {
+			sp_io::storage::set(b"scribbled", b"");
+			Ok(())
+		}
+	}
+
+	type DenyRules = (Scribble, DenyReserveTransferToRelayChain);
+	type AllowRules = (RequirePayment, AllowSubscriptions);
+
+	fn dry_run_barrier_with<Weigher: WeightBounds<()>>(
+		message: Xcm<()>,
+	) -> Result<(), BarrierRejection> {
+		sp_io::TestExternalities::default().execute_with(|| {
+			dry_run_barrier::<(), Weigher, DenyRules, AllowRules>(
+				VersionedXcm::from(message).encode(),
+				MultiLocation::new(1, X1(Parachain(1000))).into(),
+			)
+		})
+	}
+
+	#[test]
+	fn rule_names_are_bare_type_names() {
+		assert_eq!(rule_name::<RequirePayment>(), "RequirePayment");
+		assert_eq!(rule_name::<SuperuserOnlyFromRelay<AnyAsSuperuser>>(), "SuperuserOnlyFromRelay");
+	}
+
+	#[test]
+	fn paid_message_is_admitted() {
+		assert_eq!(dry_run_barrier_with::<PerInstruction>(reserve_transfer(1)), Ok(()));
+	}
+
+	#[test]
+	fn unpaid_message_is_rejected_by_each_allow_rule() {
+		let mut message = reserve_transfer(1);
+		message.0.remove(2);
+		assert_eq!(
+			dry_run_barrier_with::<PerInstruction>(message),
+			Err(BarrierRejection::NotAllowed {
+				rejections: vec![
+					(
+						b"RequirePayment".to_vec(),
+						ProcessMessageError::Overweight(Weight::from_parts(30, 0))
+					),
+					(b"AllowSubscriptions".to_vec(), ProcessMessageError::BadFormat),
+				]
+			})
+		);
+	}
+
+	#[test]
+	fn denied_message_is_rejected_by_the_deny_rule() {
+		let message = Xcm(vec![
+			WithdrawAsset((Parent, 100).into()),
+			BuyExecution { fees: (Parent, 100).into(), weight_limit: Unlimited },
+			InitiateReserveWithdraw {
+				assets: Wild(AllCounted(1)),
+				reserve: Parent.into(),
+				xcm: Xcm(vec![]),
+			},
+		]);
+		assert_eq!(
+			dry_run_barrier_with::<PerInstruction>(message),
+			Err(BarrierRejection::Denied {
+				rule: b"DenyReserveTransferToRelayChain".to_vec(),
+				error: ProcessMessageError::Unsupported
+			})
+		);
+	}
+
+	fn deny(
+		rule: impl Fn(&mut [Instruction<()>]) -> Result<(), ProcessMessageError>,
+		mut message: Xcm<()>,
+	) -> Result<(), ProcessMessageError> {
+		rule(&mut message.0)
+	}
+
+	fn relay_chain_rule(message: &mut [Instruction<()>]) -> Result<(), ProcessMessageError> {
+		DenyReserveTransferToRelayChain::should_execute(
+			&MultiLocation::parent(),
+			message,
+			Weight::zero(),
+			&mut Weight::zero(),
+		)
+	}
+
+	fn relay_token_rule(message: &mut [Instruction<()>]) -> Result<(), ProcessMessageError> {
+		DenyRelayTokenReserveToSelf::should_execute(
+			&MultiLocation::parent(),
+			message,
+			Weight::zero(),
+			&mut Weight::zero(),
+		)
+	}
+
+	#[test]
+	fn reserve_transfers_to_the_relay_chain_in_appendices_are_denied() {
+		let to_relay = Xcm(vec![DepositReserveAsset {
+			assets: Wild(AllCounted(1)),
+			dest: Parent.into(),
+			xcm: Xcm(vec![]),
+		}]);
+		assert_eq!(
+			deny(relay_chain_rule, Xcm(vec![SetAppendix(to_relay.clone())])),
+			Err(ProcessMessageError::Unsupported)
+		);
+		assert_eq!(
+			deny(relay_chain_rule, Xcm(vec![SetErrorHandler(to_relay)])),
+			Err(ProcessMessageError::Unsupported)
+		);
+	}
+
+	#[test]
+	fn reserve_transfers_of_the_relay_token_from_here_are_denied() {
+		let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+		let ksm = MultiAsset::from((Parent, 100));
+		let deposit_ksm = Xcm(vec![
+			WithdrawAsset(ksm.clone().into()),
+			DepositReserveAsset {
+				assets: Wild(AllOf { id: Concrete(Parent.into()), fun: WildFungible }),
+				dest: sibling,
+				xcm: Xcm(vec![]),
+			},
+		]);
+		assert_eq!(deny(relay_token_rule, deposit_ksm), Err(ProcessMessageError::Unsupported));
+		let transfer_ksm = Xcm(vec![SetAppendix(Xcm(vec![TransferReserveAsset {
+			assets: ksm.into(),
+			dest: sibling,
+			xcm: Xcm(vec![]),
+		}]))]);
+		assert_eq!(deny(relay_token_rule, transfer_ksm), Err(ProcessMessageError::Unsupported));
+	}
+
+	#[test]
+	fn relay_token_is_withdrawn_to_the_relay_chain() {
+		let ksm = MultiAsset::from((Parent, 100));
+		let withdraw = |assets: MultiAssetFilter| {
+			Xcm(vec![
+				WithdrawAsset(ksm.clone().into()),
+				InitiateReserveWithdraw { assets, reserve: Parent.into(), xcm: Xcm(vec![]) },
+			])
+		};
+		assert_eq!(deny(relay_chain_rule, withdraw(Wild(All))), Ok(()));
+		assert_eq!(deny(relay_chain_rule, withdraw(ksm.clone().into())), Ok(()));
+		let all_ksm = Wild(AllOf { id: Concrete(Parent.into()), fun: WildFungible });
+		assert_eq!(deny(relay_chain_rule, withdraw(all_ksm)), Ok(()));
+	}
+
+	#[test]
+	fn other_assets_are_not_withdrawn_to_the_relay_chain() {
+		let teer = MultiAsset::from((Here, 100));
+		let withdraw_teer = Xcm(vec![
+			WithdrawAsset(teer.clone().into()),
+			InitiateReserveWithdraw {
+				assets: teer.into(),
+				reserve: Parent.into(),
+				xcm: Xcm(vec![]),
+			},
+		]);
+		assert_eq!(deny(relay_chain_rule, withdraw_teer), Err(ProcessMessageError::Unsupported));
+		// All of holding may contain other assets than the relay chain's token.
+		let withdraw_all = Xcm(vec![
+			WithdrawAsset(vec![(Parent, 100).into(), (Here, 100).into()].into()),
+			SetAppendix(Xcm(vec![InitiateReserveWithdraw {
+				assets: Wild(AllCounted(2)),
+				reserve: Parent.into(),
+				xcm: Xcm(vec![]),
+			}])),
+		]);
+		assert_eq!(deny(relay_chain_rule, withdraw_all), Err(ProcessMessageError::Unsupported));
+	}
+
+	#[test]
+	fn reserve_transfers_of_our_tokens_to_siblings_are_allowed() {
+		let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+		let teer = MultiAsset::from((Here, 100));
+		let message = Xcm(vec![
+			WithdrawAsset(teer.clone().into()),
+			DepositReserveAsset { assets: Definite(teer.into()), dest: sibling, xcm: Xcm(vec![]) },
+		]);
+		assert_eq!(deny(relay_token_rule, message.clone()), Ok(()));
+		assert_eq!(deny(relay_chain_rule, message), Ok(()));
+	}
+
+	#[test]
+	fn unweighable_message_is_rejected() {
+		assert_eq!(
+			dry_run_barrier_with::<LimitedPerInstruction<3>>(reserve_transfer(1)),
+			Err(BarrierRejection::Unweighable)
+		);
+	}
+
+	#[test]
+	fn undecodable_message_is_rejected() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			assert_eq!(
+				dry_run_barrier::<(), PerInstruction, DenyRules, AllowRules>(
+					vec![42],
+					MultiLocation::parent().into(),
+				),
+				Err(BarrierRejection::UndecodableMessage)
+			);
+		});
+	}
+
+	#[test]
+	fn barrier_dry_run_changes_nothing() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			assert_eq!(
+				dry_run_barrier::<(), PerInstruction, DenyRules, AllowRules>(
+					VersionedXcm::from(reserve_transfer(1)).encode(),
+					MultiLocation::parent().into(),
+				),
+				Ok(())
+			);
+			assert_eq!(sp_io::storage::get(b"scribbled"), None);
+		});
+	}
+
+	#[test]
+	fn deny_all_then_try_requires_all_deny_rules_and_any_allow_rule() {
+		type Barrier = DenyAllThenTry<DenyRules, AllowRules>;
+		let check = |mut message: Xcm<()>| {
+			sp_io::TestExternalities::default().execute_with(|| {
+				Barrier::should_execute(
+					&MultiLocation::parent(),
+					&mut message.0,
+					Weight::zero(),
+					&mut Weight::zero(),
+				)
+			})
+		};
+
+		assert_eq!(check(reserve_transfer(1)), Ok(()));
+		let subscription =
+			Xcm(vec![SubscribeVersion { query_id: 0, max_response_weight: Weight::zero() }]);
+		assert_eq!(check(subscription), Ok(()));
+		assert!(check(Xcm(vec![ClearOrigin])).is_err());
+		let reserve_withdraw = Xcm(vec![
+			BuyExecution { fees: (Parent, 100).into(), weight_limit: Unlimited },
+			InitiateReserveWithdraw {
+				assets: Wild(AllCounted(1)),
+				reserve: Parent.into(),
+				xcm: Xcm(vec![]),
+			},
+		]);
+		assert_eq!(check(reserve_withdraw), Err(ProcessMessageError::Unsupported));
+	}
+
+	#[test]
+	fn deny_all_then_try_stops_at_the_first_rejecting_deny_rule() {
+		type Barrier = DenyAllThenTry<(DenyReserveTransferToRelayChain, Scribble), AllowRules>;
+		let mut message = Xcm::<()>(vec![InitiateReserveWithdraw {
+			assets: Wild(AllCounted(1)),
+			reserve: Parent.into(),
+			xcm: Xcm(vec![]),
+		}]);
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			assert_eq!(
+				Barrier::should_execute(
+					&MultiLocation::parent(),
+					&mut message.0,
+					Weight::zero(),
+					&mut Weight::zero(),
+				),
+				Err(ProcessMessageError::Unsupported)
+			);
+			assert_eq!(sp_io::storage::get(b"scribbled"), None);
+		});
+	}
+
+	#[test]
+	fn inbound_queue_is_told_by_the_origin() {
+		assert_eq!(InboundQueue::of(&MultiLocation::parent()), InboundQueue::Dmp);
+		assert_eq!(InboundQueue::of(&(Parent, Parachain(1000)).into()), InboundQueue::Xcmp);
+		assert_eq!(InboundQueue::Xcmp.as_str(), "xcmp");
+	}
+}
@@ -0,0 +1,143 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! End-to-end XCM tests of our runtimes, on a network emulated with `xcm-emulator`.
+//!
+//! The network consists of Kusama, the shell runtime and a [`sibling`] parachain which holds
+//! the shell runtime's TEER as its native currency. Messages sent by one of them are delivered
+//! to the others at the end of every `execute_with`.
+
+use sp_core::storage::Storage;
+use sp_runtime::BuildStorage;
+use xcm_emulator::{decl_test_networks, decl_test_parachains, decl_test_relay_chains};
+
+pub mod sibling;
+
+#[cfg(test)]
+mod tests;
+
+/// The para id of the shell runtime.
+pub const SHELL_ID: u32 = 2267;
+/// The para id of the sibling parachain.
+pub const SIBLING_ID: u32 = 2000;
+/// The account holding TEER on the shell runtime, and receiving it on the sibling.
+pub const ALICE: [u8; 32] = [1; 32];
+/// What [`ALICE`] is endowed with on the shell runtime.
+pub const INITIAL_BALANCE: u128 = 1_000 * shell_runtime::TEER;
+
+/// The relay chain only routes messages between the parachains, so it starts without validators
+/// and any other genesis state than the system's.
+fn kusama_genesis() -> Storage {
+	frame_system::GenesisConfig::default()
+		.build_storage::<kusama_runtime::Runtime>()
+		.unwrap()
+}
+
+fn shell_genesis() -> Storage {
+	shell_runtime::GenesisConfig {
+		balances: shell_runtime::BalancesConfig { balances: vec![(ALICE.into(), INITIAL_BALANCE)] },
+		parachain_info: shell_runtime::ParachainInfoConfig { parachain_id: SHELL_ID.into() },
+		polkadot_xcm: shell_runtime::PolkadotXcmConfig {
+			safe_xcm_version: Some(xcm::prelude::XCM_VERSION),
+		},
+		..Default::default()
+	}
+	.build_storage()
+	.unwrap()
+}
+
+fn sibling_genesis() -> Storage {
+	sibling::GenesisConfig {
+		parachain_info: sibling::ParachainInfoConfig { parachain_id: SIBLING_ID.into() },
+		polkadot_xcm: sibling::PolkadotXcmConfig {
+			safe_xcm_version: Some(xcm::prelude::XCM_VERSION),
+		},
+		..Default::default()
+	}
+	.build_storage()
+	.unwrap()
+}
+
+decl_test_relay_chains! {
+	pub struct Kusama {
+		genesis = kusama_genesis(),
+		on_init = (),
+		runtime = {
+			Runtime: kusama_runtime::Runtime,
+			RuntimeOrigin: kusama_runtime::RuntimeOrigin,
+			RuntimeCall: kusama_runtime::RuntimeCall,
+			RuntimeEvent: kusama_runtime::RuntimeEvent,
+			XcmConfig: kusama_runtime::xcm_config::XcmConfig,
+			SovereignAccountOf: kusama_runtime::xcm_config::SovereignAccountOf,
+			System: kusama_runtime::System,
+			Balances: kusama_runtime::Balances,
+		},
+		pallets_extra = {
+			XcmPallet: kusama_runtime::XcmPallet,
+		}
+	}
+}
+
+decl_test_parachains! {
+	pub struct Shell {
+		genesis = shell_genesis(),
+		on_init = (),
+		runtime = {
+			Runtime: shell_runtime::Runtime,
+			RuntimeOrigin: shell_runtime::RuntimeOrigin,
+			RuntimeCall: shell_runtime::RuntimeCall,
+			RuntimeEvent: shell_runtime::RuntimeEvent,
+			XcmpMessageHandler: shell_runtime::XcmpQueue,
+			DmpMessageHandler: shell_runtime::DmpQueue,
+			LocationToAccountId: shell_runtime::xcm_config::LocationToAccountId,
+			System: shell_runtime::System,
+			Balances: shell_runtime::Balances,
+			ParachainSystem: shell_runtime::ParachainSystem,
+			ParachainInfo: shell_runtime::ParachainInfo,
+		},
+		pallets_extra = {
+			PolkadotXcm: shell_runtime::PolkadotXcm,
+		}
+	},
+	pub struct SiblingPara {
+		genesis = sibling_genesis(),
+		on_init = (),
+		runtime = {
+			Runtime: sibling::Runtime,
+			RuntimeOrigin: sibling::RuntimeOrigin,
+			RuntimeCall: sibling::RuntimeCall,
+			RuntimeEvent: sibling::RuntimeEvent,
+			XcmpMessageHandler: sibling::XcmpQueue,
+			DmpMessageHandler: sibling::DmpQueue,
+			LocationToAccountId: sibling::LocationToAccountId,
+			System: sibling::System,
+			Balances: sibling::Balances,
+			ParachainSystem: sibling::ParachainSystem,
+			ParachainInfo: sibling::ParachainInfo,
+		},
+		pallets_extra = {
+			PolkadotXcm: sibling::PolkadotXcm,
+		}
+	}
+}
+
+decl_test_networks! {
+	pub struct KusamaMockNet {
+		relay_chain = Kusama,
+		parachains = vec![Shell, SiblingPara,],
+	}
+}
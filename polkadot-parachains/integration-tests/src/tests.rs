@@ -0,0 +1,111 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reserve transfers of TEER between the shell runtime and its sibling.
+
+use crate::*;
+use frame_support::{assert_ok, traits::Currency, weights::Weight};
+use parachains_common::AccountId;
+use polkadot_parachain::primitives::Sibling;
+use shell_runtime::{xcm_config::SelfReserve, TEER};
+use sp_runtime::traits::AccountIdConversion;
+use xcm::{latest::prelude::*, VersionedMultiAssets, VersionedMultiLocation, VersionedXcm};
+use xcm_emulator::TestExt;
+
+fn alice() -> AccountId {
+	ALICE.into()
+}
+
+fn alice_location() -> MultiLocation {
+	X1(AccountId32 { network: None, id: ALICE }).into()
+}
+
+/// The sibling's sovereign account on the shell runtime, which holds the TEER it has received.
+fn sibling_sovereign() -> AccountId {
+	Sibling::from(SIBLING_ID).into_account_truncating()
+}
+
+fn send_teer_to_sibling(amount: u128) {
+	Shell::execute_with(|| {
+		let teer: MultiAsset = (SelfReserve::get(), amount).into();
+		assert_ok!(shell_runtime::PolkadotXcm::limited_reserve_transfer_assets(
+			shell_runtime::RuntimeOrigin::signed(alice()),
+			Box::new(VersionedMultiLocation::from(MultiLocation::new(
+				1,
+				X1(Parachain(SIBLING_ID))
+			))),
+			Box::new(VersionedMultiLocation::from(alice_location())),
+			Box::new(VersionedMultiAssets::from(MultiAssets::from(teer))),
+			0,
+			Unlimited,
+		));
+	});
+}
+
+fn return_teer_to_shell(amount: u128) {
+	SiblingPara::execute_with(|| {
+		let teer: MultiAsset = (sibling::ShellTeer::get(), amount).into();
+		let fees: MultiAsset = (SelfReserve::get(), amount).into();
+		let message = Xcm(vec![
+			WithdrawAsset(teer.into()),
+			InitiateReserveWithdraw {
+				assets: Wild(AllCounted(1)),
+				reserve: MultiLocation::new(1, X1(Parachain(SHELL_ID))),
+				xcm: Xcm(vec![
+					BuyExecution { fees, weight_limit: Unlimited },
+					DepositAsset { assets: Wild(AllCounted(1)), beneficiary: alice_location() },
+				]),
+			},
+		]);
+		assert_ok!(sibling::PolkadotXcm::execute(
+			sibling::RuntimeOrigin::signed(alice()),
+			Box::new(VersionedXcm::from(message)),
+			Weight::from_parts(1_000_000_000, 1024 * 1024),
+		));
+	});
+}
+
+#[test]
+fn teer_sent_to_the_sibling_is_held_by_its_sovereign_account() {
+	send_teer_to_sibling(10 * TEER);
+
+	Shell::execute_with(|| {
+		assert_eq!(shell_runtime::Balances::free_balance(sibling_sovereign()), 10 * TEER);
+		assert_eq!(shell_runtime::Balances::free_balance(alice()), INITIAL_BALANCE - 10 * TEER);
+	});
+	SiblingPara::execute_with(|| {
+		assert_eq!(sibling::Balances::free_balance(alice()), 10 * TEER);
+		assert_eq!(sibling::Balances::total_issuance(), 10 * TEER);
+	});
+}
+
+#[test]
+fn teer_returned_from_the_sibling_conserves_issuance() {
+	send_teer_to_sibling(10 * TEER);
+	return_teer_to_shell(10 * TEER);
+
+	SiblingPara::execute_with(|| {
+		assert_eq!(sibling::Balances::total_issuance(), 0);
+	});
+	Shell::execute_with(|| {
+		assert_eq!(shell_runtime::Balances::free_balance(sibling_sovereign()), 0);
+		// Only the fees for executing the returning message are burnt, nothing is minted.
+		let burnt = INITIAL_BALANCE - shell_runtime::Balances::total_issuance();
+		assert!(burnt > 0 && burnt < TEER);
+		assert_eq!(shell_runtime::Balances::free_balance(alice()), INITIAL_BALANCE - burnt);
+	});
+}
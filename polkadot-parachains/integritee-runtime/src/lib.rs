@@ -49,7 +49,7 @@ pub use frame_support::{
 	pallet_prelude::Get,
 	parameter_types,
 	traits::{
-		EitherOfDiverse, Everything, IsInVec, Nothing, PalletInfoAccess, Randomness,
+		Contains, EitherOfDiverse, Everything, IsInVec, Nothing, PalletInfoAccess, Randomness,
 		WithdrawReasons,
 	},
 	weights::{
@@ -104,10 +104,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("integritee-parachain"),
 	impl_name: create_runtime_str!("integritee-full"),
 	authoring_version: 2,
-	spec_version: 38,
+	spec_version: 39,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 6,
+	transaction_version: 7,
 	state_version: 0,
 };
 
@@ -245,6 +245,34 @@ impl pallet_balances::Config for Runtime {
 	type MaxFreezes = ();
 }
 
+orml_traits::parameter_type_with_key! {
+	pub TokensExistentialDeposits: |currency: xcm_config::CurrencyId| -> Balance {
+		match currency {
+			// TEER is held in `Balances`, never in `Tokens`.
+			xcm_config::CurrencyId::TEER => Balance::MAX,
+			// 0.000001 KSM.
+			xcm_config::CurrencyId::KSM => 1_000_000,
+			// 0.001 USDT.
+			xcm_config::CurrencyId::USDT => 1_000,
+		}
+	};
+}
+
+/// Holds the foreign currencies, TEER is held in `Balances`.
+impl orml_tokens::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Balance = Balance;
+	type Amount = i128;
+	type CurrencyId = xcm_config::CurrencyId;
+	type WeightInfo = ();
+	type ExistentialDeposits = TokensExistentialDeposits;
+	type CurrencyHooks = ();
+	type MaxLocks = MaxLocks;
+	type MaxReserves = MaxReserves;
+	type ReserveIdentifier = [u8; 8];
+	type DustRemovalWhitelist = Nothing;
+}
+
 impl pallet_transaction_payment::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type OnChargeTransaction = pallet_transaction_payment::CurrencyAdapter<Balances, DealWithFees>;
@@ -439,7 +467,8 @@ impl cumulus_pallet_parachain_system::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type OnSystemEvent = ();
 	type SelfParaId = parachain_info::Pallet<Runtime>;
-	type DmpMessageHandler = DmpQueue;
+	// Downward messages may be prioritized by governance, see `pallet_xcm_admin::DmpOrder`.
+	type DmpMessageHandler = pallet_xcm_admin::PrioritizedDmp<Runtime, DmpQueue>;
 	type ReservedDmpWeight = ReservedDmpWeight;
 	type OutboundXcmpMessageSource = XcmpQueue;
 	type XcmpMessageHandler = XcmpQueue;
@@ -658,6 +687,28 @@ pub type EnsureRootOrAllTechnicalCommittee = EitherOfDiverse<
 	pallet_collective::EnsureProportionAtLeast<AccountId, TechnicalCommitteeInstance, 1, 1>,
 >;
 
+/// Signed calls which are still accepted in maintenance mode: governance must be able to leave
+/// maintenance mode again.
+pub struct GovernanceCalls;
+impl Contains<RuntimeCall> for GovernanceCalls {
+	fn contains(call: &RuntimeCall) -> bool {
+		matches!(
+			call,
+			RuntimeCall::Democracy(_) |
+				RuntimeCall::Council(_) |
+				RuntimeCall::TechnicalCommittee(_) |
+				RuntimeCall::Preimage(_)
+		)
+	}
+}
+
+impl pallet_maintenance_mode::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type MaintenanceOrigin = EnsureRootOrMoreThanHalfCouncil;
+	type AllowedInMaintenance = GovernanceCalls;
+	type WeightInfo = pallet_maintenance_mode::weights::SubstrateWeight<Runtime>;
+}
+
 parameter_types! {
 	pub const LaunchPeriod: BlockNumber = prod_or_fast!(5 * DAYS, 5 * MINUTES);
 	pub const VotingPeriod: BlockNumber = prod_or_fast!(5 * DAYS, 5 * MINUTES);
@@ -753,6 +804,7 @@ construct_runtime!(
 			pallet_collective::<Instance2>::{Pallet, Call, Storage, Event<T>, Origin<T>, Config<T>} = 16,
 		Bounties: pallet_bounties::{Pallet, Call, Storage, Event<T>} = 18,
 		ChildBounties: pallet_child_bounties = 19,
+		MaintenanceMode: pallet_maintenance_mode = 20,
 
 		// Consensus.
 		Aura: pallet_aura::{Pallet, Storage, Config<T>} = 23,
@@ -766,6 +818,10 @@ construct_runtime!(
 		XTokens: orml_xtokens::{Pallet, Call, Storage, Event<T>} = 34,
 		OrmlXcm: orml_xcm = 35,
 		XcmTransactor: pallet_xcm_transactor = 36,
+		XcmAdmin: pallet_xcm_admin = 37,
+		Tokens: orml_tokens::{Pallet, Call, Storage, Event<T>} = 38,
+		XcmFeeMarkup: pallet_xcm_fee_markup = 39,
+		XcmDedup: pallet_xcm_dedup = 40,
 
 		// Integritee pallets.
 		Teerex: pallet_teerex::{Pallet, Call, Config, Storage, Event<T>} = 50,
@@ -793,6 +849,7 @@ pub type SignedExtra = (
 	frame_system::CheckEra<Runtime>,
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
+	pallet_maintenance_mode::CheckMaintenanceMode<Runtime>,
 	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
 );
 /// Unchecked extrinsic type as expected by this runtime.
@@ -844,6 +901,9 @@ mod benches {
 		[pallet_treasury, Treasury]
 		[pallet_vesting, Vesting]
 		[pallet_xcm, PolkadotXcm]
+		[pallet_xcm_admin, XcmAdmin]
+		[pallet_xcm_dedup, XcmDedup]
+		[pallet_xcm_fee_markup, XcmFeeMarkup]
 		[pallet_utility, Utility]
 	);
 }
@@ -957,6 +1017,158 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl parachains_common::runtime_api::IntegrityCheckApi<Block> for Runtime {
+		fn check_integrity() -> Vec<parachains_common::xcm_config::IntegrityFailure> {
+			xcm_config::integrity_failures()
+		}
+	}
+
+	impl parachains_common::runtime_api::XcmEventsApi<Block> for Runtime {
+		fn xcm_events() -> Vec<Vec<u8>> {
+			System::events()
+				.into_iter()
+				.filter(|record| xcm_config::is_xcm_event(&record.event))
+				.map(|record| record.event.encode())
+				.collect()
+		}
+	}
+
+	impl parachains_common::runtime_api::XcmWeightApi<Block> for Runtime {
+		fn inbound_xcm_weights() -> Vec<parachains_common::xcm_config::InboundXcmWeight> {
+			System::events()
+				.iter()
+				.filter_map(|record| xcm_config::inbound_xcm_weight(&record.event))
+				.collect()
+		}
+
+		fn xcm_instruction_weight() -> Weight {
+			xcm_config::UnitWeightCost::get()
+		}
+	}
+
+	impl parachains_common::runtime_api::XcmpQueueApi<Block> for Runtime {
+		fn xcmp_queue_status() -> parachains_common::xcm_config::XcmpQueueStatus {
+			parachains_common::xcm_config::xcmp_queue_status::<
+				XcmpQueue,
+				<Runtime as cumulus_pallet_xcmp_queue::Config>::ExecuteOverweightOrigin,
+			>()
+		}
+	}
+
+	impl parachains_common::runtime_api::TrappedAssetsApi<Block> for Runtime {
+		fn trapped_assets(
+			origin: xcm::VersionedMultiLocation,
+		) -> Vec<(xcm::VersionedMultiAssets, u32)> {
+			xcm::latest::MultiLocation::try_from(origin)
+				.map(|origin| XcmAdmin::trapped_assets(&origin))
+				.unwrap_or_default()
+		}
+	}
+
+	impl parachains_common::runtime_api::CurrencyRegistryApi<Block> for Runtime {
+		fn batch_resolve_currencies(
+			locations: Vec<xcm::VersionedMultiLocation>,
+		) -> Result<
+			Vec<Option<xcm_config::CurrencyId>>,
+			parachains_common::xcm_config::BatchTooLarge,
+		> {
+			parachains_common::xcm_config::resolve_currencies::<xcm_config::CurrencyIdConvert>(
+				locations,
+			)
+		}
+	}
+
+	impl parachains_common::runtime_api::XcmLocationApi<Block> for Runtime {
+		fn self_reserve_location() -> xcm::VersionedMultiLocation {
+			xcm_config::SelfReserve::get().into()
+		}
+
+		fn currency_location(
+			currency: xcm_config::CurrencyId,
+		) -> Option<xcm::VersionedMultiLocation> {
+			xcm_config::CurrencyIdConvert::convert(currency).map(Into::into)
+		}
+	}
+
+	impl parachains_common::runtime_api::AssetMetadataApi<Block> for Runtime {
+		fn asset_metadata(
+			location: xcm::VersionedMultiLocation,
+		) -> Option<parachains_common::xcm_config::CurrencyMetadata> {
+			parachains_common::xcm_config::currency_metadata::<xcm_config::CurrencyIdConvert>(
+				location,
+			)
+		}
+	}
+
+	impl parachains_common::runtime_api::TransactDryRunApi<Block> for Runtime {
+		fn dry_run_transact(
+			call: Vec<u8>,
+			origin_kind: xcm::latest::OriginKind,
+			origin: xcm::VersionedMultiLocation,
+		) -> Result<sp_runtime::DispatchResult, parachains_common::xcm_config::TransactRejection> {
+			use xcm_executor::Config;
+			type XcmConfig = xcm_config::XcmExecutorConfig;
+			parachains_common::xcm_config::dry_run_transact::<
+				RuntimeCall,
+				<XcmConfig as Config>::SafeCallFilter,
+				<XcmConfig as Config>::OriginConverter,
+				<XcmConfig as Config>::CallDispatcher,
+			>(call, origin_kind, origin)
+		}
+	}
+
+	impl parachains_common::runtime_api::BarrierDryRunApi<Block> for Runtime {
+		fn dry_run_barrier(
+			message: Vec<u8>,
+			origin: xcm::VersionedMultiLocation,
+		) -> Result<(), parachains_common::xcm_config::BarrierRejection> {
+			parachains_common::xcm_config::dry_run_barrier::<
+				RuntimeCall,
+				xcm_config::XcmWeigher,
+				xcm_config::BarrierDenyRules,
+				xcm_config::BarrierAllowRules,
+			>(message, origin)
+		}
+	}
+
+	impl parachains_common::runtime_api::XcmDryRunApi<Block> for Runtime {
+		fn dry_run_xcm(
+			message: Vec<u8>,
+			origin: xcm::VersionedMultiLocation,
+		) -> Result<
+			parachains_common::xcm_config::XcmDryRunEffects,
+			parachains_common::xcm_config::XcmDryRunError,
+		> {
+			parachains_common::xcm_config::dry_run_xcm::<xcm_config::XcmExecutorConfig>(
+				message, origin,
+			)
+		}
+	}
+
+	impl parachains_common::runtime_api::XcmDeliveryFeeApi<Block> for Runtime {
+		fn xcm_delivery_fee(
+			dest: xcm::VersionedMultiLocation,
+			message_size: u32,
+		) -> Option<(xcm_config::CurrencyId, u128)> {
+			let dest = xcm::latest::MultiLocation::try_from(dest).ok()?;
+			parachains_common::xcm_config::delivery_fee::<
+				xcm_config::PriceForParentDelivery,
+				xcm_config::PriceForSiblingDelivery,
+				xcm_config::CurrencyIdConvert,
+			>(&dest, message_size)
+		}
+	}
+
+	impl parachains_common::runtime_api::XtokensFeeApi<Block> for Runtime {
+		fn estimate_xtokens_fee(
+			currency: xcm_config::CurrencyId,
+			dest: xcm::VersionedMultiLocation,
+		) -> Option<(u128, Weight)> {
+			let dest = xcm::latest::MultiLocation::try_from(dest).ok()?;
+			xcm_config::estimate_xtokens_fee(currency, &dest)
+		}
+	}
+
 	impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentCallApi<Block, Balance, RuntimeCall>
 		for Runtime
 	{
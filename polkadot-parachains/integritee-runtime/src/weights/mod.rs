@@ -22,4 +22,5 @@ pub mod pallet_timestamp;
 pub mod pallet_treasury;
 pub mod pallet_utility;
 pub mod pallet_vesting;
-pub mod pallet_xcm;
+pub use parachains_common::weights::pallet_xcm;
+pub use parachains_common::weights::pallet_xcm_transactor;
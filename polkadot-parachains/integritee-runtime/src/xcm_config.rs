@@ -17,47 +17,82 @@
 
 //! XCM configuration for Integritee Runtime.
 //!
+//! ## Foreign assets
+//!
+//! TEER is held in `Balances`, the foreign currencies of [`CurrencyId`] (the relay chain's token
+//! and USDT of the Asset Hub) are held in `Tokens`, each transacted by its own entry of the
+//! [`LocalAssetTransactor`].
+//!
+//! The relay chain's token is reserve-backed: the relay chain holds it in reserve for us and it
+//! is withdrawn back to the relay chain from there. Neither it nor TEER is ever teleported.
+//!
+//! Migration notes: `Tokens` is a new pallet, it starts out empty and needs no migration. TEER
+//! keeps the SCALE encoding of its `CurrencyId`, so stored currencies (e.g. the fee currency
+//! order of `XcmAdmin`) remain valid. The relay chain's token pays XCM fees at the price of
+//! [`DefaultFeePerRefTime`] unless governance set one with `XcmAdmin::set_fee_per_ref_time`, USDT
+//! is no fee currency by default.
+//! Reserve transfers of a new foreign currency are accepted from its reserve as soon as the
+//! runtime knows its location, governance can disable them with
+//! `XcmAdmin::set_reserve_transfer_enabled`.
 
 use super::{
-	AccountId, Balance, Balances, Convert, EnsureRootOrMoreThanHalfCouncil, MaxInstructions,
-	ParachainInfo, ParachainSystem, PolkadotXcm, Runtime, RuntimeCall, RuntimeEvent, RuntimeOrigin,
-	XcmpQueue, TEER,
+	AccountId, Balance, Balances, BlockNumber, Convert, EnsureRootOrMoreThanHalfCouncil,
+	MaxInstructions, MaxScheduledPerBlock, OriginCaller, ParachainInfo, ParachainSystem,
+	PolkadotXcm, Preimage, Runtime, RuntimeCall, RuntimeEvent, RuntimeOrigin, Scheduler, Tokens,
+	XcmAdmin, XcmpQueue, DAYS, MICROTEER, MILLITEER, TEER,
 };
 use crate::weights;
-use codec::{Decode, Encode, MaxEncodedLen};
 use core::marker::PhantomData;
 use cumulus_primitives_core::GlobalConsensus;
 use frame_support::{
+	match_types,
 	pallet_prelude::{Get, Weight},
 	parameter_types,
 	traits::{Everything, Nothing},
-	weights::IdentityFee,
-	RuntimeDebug,
+	weights::constants::RocksDbWeight,
 };
 use frame_system::EnsureRoot;
 use orml_traits::{
 	location::{RelativeReserveProvider, Reserve},
 	parameter_type_with_key,
 };
-use orml_xcm_support::{IsNativeConcrete, MultiNativeAsset};
+use orml_xcm_support::{
+	DepositToAlternative, IsNativeConcrete, MultiCurrencyAdapter, MultiNativeAsset,
+};
 use pallet_xcm::XcmPassthrough;
-use parachains_common::xcm_config::{DenyReserveTransferToRelayChain, DenyThenTry};
+use pallet_xcm_admin::{DeliveryFee, RemainderDestination};
+pub use parachains_common::xcm_config::CurrencyId;
+use parachains_common::{
+	relay_call::{RelayCall, RelayCallIndices},
+	xcm_config::{
+		cached_currency, cached_reserve, check_currency_round_trip, check_weigher, delivery_fee,
+		general_key_matches_symbol, AssetCountWeigher, CachedConversions, ConversionWeigher,
+		DenyAllThenTry, DenyRelayTokenReserveToSelf, DenyReserveTransferToRelayChain,
+		FilteredCurrencies, ForeignCurrencies, InboundQueue, InboundXcmWeight, IntegrityFailure,
+		NativeCurrencies, SizeBasedDelivery, SuperuserOnlyFromRelay, XcmInstructionWeight,
+		USDT_LOCATION,
+	},
+};
 use polkadot_parachain::primitives::Sibling;
-use scale_info::TypeInfo;
-use sp_core::ConstU32;
+use sp_core::{ConstU128, ConstU32};
+use sp_runtime::traits::AccountIdConversion;
 use sp_std::{
 	convert::{From, Into},
 	prelude::*,
+	vec,
 };
 use xcm::latest::prelude::*;
 use xcm_builder::{
 	AccountId32Aliases, AllowKnownQueryResponses, AllowSubscriptionsFrom,
-	AllowTopLevelPaidExecutionFrom, CurrencyAdapter, EnsureXcmOrigin, FixedWeightBounds,
-	ParentAsSuperuser, ParentIsPreset, RelayChainAsNative, SiblingParachainAsNative,
-	SiblingParachainConvertsVia, SignedAccountId32AsNative, SignedToAccountId32,
-	SovereignSignedViaLocation, TakeWeightCredit, UsingComponents,
+	AllowTopLevelPaidExecutionFrom, AllowUnpaidExecutionFrom, CurrencyAdapter, EnsureXcmOrigin,
+	FixedWeightBounds, ParentAsSuperuser, ParentIsPreset, RelayChainAsNative,
+	SiblingParachainAsNative, SiblingParachainConvertsVia, SignedAccountId32AsNative,
+	SignedToAccountId32, SovereignSignedViaLocation, TakeWeightCredit,
+};
+use xcm_executor::{
+	traits::{Convert as XcmConvert, DropAssets, FeeManager, FeeReason, TransactAsset},
+	Assets, XcmExecutor,
 };
-use xcm_executor::XcmExecutor;
 use xcm_transactor_primitives::*;
 
 const fn teer_general_key() -> Junction {
@@ -67,9 +102,18 @@ const fn teer_general_key() -> Junction {
 
 const TEER_GENERAL_KEY: Junction = teer_general_key();
 
+// The general key identifies TEER across chains, it must not diverge from TEER's symbol.
+const _: () = assert!(
+	general_key_matches_symbol(&TEER_GENERAL_KEY, CurrencyId::TEER.symbol()),
+	"TEER's general key does not match its symbol",
+);
+
 parameter_types! {
 	pub const RelayChainLocation: MultiLocation = MultiLocation::parent();
-	pub const RelayNetwork: NetworkId = NetworkId::Kusama;
+	/// The network of the relay chain, unless set otherwise in genesis, see
+	/// [`relay_network_key`].
+	pub const DefaultRelayNetwork: NetworkId = NetworkId::Kusama;
+	pub RelayNetwork: NetworkId = XcmAdmin::relay_network();
 	pub RelayChainOrigin: RuntimeOrigin = cumulus_pallet_xcm::Origin::Relay.into();
 	// The universal location within the global consensus system
 	pub UniversalLocation: InteriorMultiLocation =
@@ -81,22 +125,10 @@ parameter_types! {
 	};
 }
 
-// Supported Currencies.
-#[derive(
-	Encode,
-	Decode,
-	Eq,
-	PartialEq,
-	Copy,
-	Clone,
-	RuntimeDebug,
-	PartialOrd,
-	Ord,
-	TypeInfo,
-	MaxEncodedLen,
-)]
-pub enum CurrencyId {
-	TEER,
+/// The storage key of the network of the relay chain, such that chain specs for other relay
+/// networks can set it in genesis (see the collator's `build-spec --relay`).
+pub fn relay_network_key() -> [u8; 32] {
+	pallet_xcm_admin::RelayNetwork::<Runtime>::hashed_key()
 }
 
 /// Converts a CurrencyId into a Multilocation, used by xtoken for XCMP.
@@ -108,29 +140,37 @@ impl Convert<CurrencyId, Option<MultiLocation>> for CurrencyIdConvert {
 				1,
 				X2(Parachain(ParachainInfo::parachain_id().into()), TEER_GENERAL_KEY),
 			)),
+			CurrencyId::KSM => Some(MultiLocation::parent()),
+			CurrencyId::USDT => Some(USDT_LOCATION),
 		}
 	}
 }
 
 /// Converts a Mulitloaction into a CurrencyId. Used by XCMP LocalAssetTransactor for asset filtering:
 /// we only accept Assets that are convertable to a "CurrencyId".
+///
+/// Cached while a message is executed, see [`CachedConversions`].
 impl Convert<MultiLocation, Option<CurrencyId>> for CurrencyIdConvert {
 	fn convert(location: MultiLocation) -> Option<CurrencyId> {
-		let self_para_id: u32 = ParachainInfo::parachain_id().into();
+		cached_currency(&location, || {
+			let self_para_id: u32 = ParachainInfo::parachain_id().into();
 
-		match location {
-			MultiLocation { parents, interior } if parents == 1 => match interior {
-				X2(Parachain(para_id), junction)
-					if junction == TEER_GENERAL_KEY && para_id == self_para_id =>
-					Some(CurrencyId::TEER),
+			match location {
+				location if location == USDT_LOCATION => Some(CurrencyId::USDT),
+				MultiLocation { parents, interior } if parents == 1 => match interior {
+					Here => Some(CurrencyId::KSM),
+					X2(Parachain(para_id), junction)
+						if junction == TEER_GENERAL_KEY && para_id == self_para_id =>
+						Some(CurrencyId::TEER),
+					_ => None,
+				},
+				MultiLocation { parents, interior } if parents == 0 => match interior {
+					X1(junction) if junction == TEER_GENERAL_KEY => Some(CurrencyId::TEER),
+					_ => None,
+				},
 				_ => None,
-			},
-			MultiLocation { parents, interior } if parents == 0 => match interior {
-				X1(junction) if junction == TEER_GENERAL_KEY => Some(CurrencyId::TEER),
-				_ => None,
-			},
-			_ => None,
-		}
+			}
+		})
 	}
 }
 
@@ -145,11 +185,91 @@ impl Convert<MultiAsset, Option<CurrencyId>> for CurrencyIdConvert {
 	}
 }
 
+/// Maps all locations of a known currency to the one returned by `CurrencyIdConvert`, other
+/// locations are left as they are.
+pub struct CanonicalAssetLocation;
+impl Convert<MultiLocation, MultiLocation> for CanonicalAssetLocation {
+	fn convert(location: MultiLocation) -> MultiLocation {
+		<CurrencyIdConvert as Convert<MultiLocation, Option<CurrencyId>>>::convert(location)
+			.and_then(CurrencyIdConvert::convert)
+			.unwrap_or(location)
+	}
+}
+
+/// Price of delivering a message to the relay chain, free apart from a fee markup set by
+/// governance.
+pub type PriceForParentDelivery = pallet_xcm_fee_markup::MarkedUpDelivery<Runtime, ()>;
+/// Price of delivering a message to a sibling parachain: a base fee plus a fee per byte, and the
+/// fee markup set by governance.
+pub type PriceForSiblingDelivery = pallet_xcm_fee_markup::MarkedUpDelivery<
+	Runtime,
+	SizeBasedDelivery<SelfReserve, SiblingDeliveryBaseFee, SiblingDeliveryByteFee>,
+>;
+
+parameter_types! {
+	/// The TEER charged for delivering a message to a sibling parachain, unless governance set
+	/// another fee with `XcmAdmin::set_sibling_delivery_fee`.
+	pub const DefaultSiblingDeliveryFee: DeliveryFee =
+		DeliveryFee { base: MILLITEER, per_byte: 10 * MICROTEER };
+	/// The TEER charged for delivering any message to a sibling parachain.
+	pub SiblingDeliveryBaseFee: Balance = XcmAdmin::sibling_delivery_fee().base;
+	/// The TEER charged per byte of a message delivered to a sibling parachain.
+	pub SiblingDeliveryByteFee: Balance = XcmAdmin::sibling_delivery_fee().per_byte;
+}
+
+/// Whether `event` is emitted by the XCM machinery, these are streamed to indexers.
+pub fn is_xcm_event(event: &RuntimeEvent) -> bool {
+	use cumulus_pallet_parachain_system::Event as ParachainSystemEvent;
+	matches!(
+		event,
+		RuntimeEvent::XcmpQueue(_) |
+			RuntimeEvent::PolkadotXcm(_) |
+			RuntimeEvent::CumulusXcm(_) |
+			RuntimeEvent::DmpQueue(_) |
+			RuntimeEvent::XTokens(_) |
+			RuntimeEvent::OrmlXcm(_) |
+			RuntimeEvent::XcmAdmin(_) |
+			RuntimeEvent::XcmFeeMarkup(_) |
+			RuntimeEvent::XcmDedup(_) |
+			RuntimeEvent::ParachainSystem(
+				ParachainSystemEvent::UpwardMessageSent { .. } |
+					ParachainSystemEvent::DownwardMessagesReceived { .. } |
+					ParachainSystemEvent::DownwardMessagesProcessed { .. }
+			)
+	)
+}
+
+/// The weight of an inbound message, if `event` is the one metering it.
+pub fn inbound_xcm_weight(event: &RuntimeEvent) -> Option<InboundXcmWeight> {
+	let RuntimeEvent::XcmAdmin(pallet_xcm_admin::Event::InboundMessageMetered {
+		origin,
+		charged,
+		consumed,
+	}) = event
+	else {
+		return None
+	};
+	let origin = MultiLocation::try_from(origin.clone()).ok()?;
+	Some(InboundXcmWeight {
+		queue: InboundQueue::of(&origin),
+		charged: *charged,
+		consumed: *consumed,
+	})
+}
+
 /// The means for routing XCM messages which are not for local execution into the right message
 /// queues.
 pub type XcmRouter = (
 	// Two routers - use UMP to communicate with the relay chain:
-	cumulus_primitives_utility::ParentAsUmp<ParachainSystem, PolkadotXcm, ()>,
+	pallet_xcm_admin::UmpSizeLimit<
+		Runtime,
+		PolkadotXcm,
+		cumulus_primitives_utility::ParentAsUmp<
+			ParachainSystem,
+			PolkadotXcm,
+			PriceForParentDelivery,
+		>,
+	>,
 	// ..and XCMP to communicate with the sibling chains.
 	XcmpQueue,
 );
@@ -166,12 +286,21 @@ pub type LocationToAccountId = (
 	AccountId32Aliases<RelayNetwork, AccountId>,
 );
 
-/// Means for transacting assets on this chain.
-pub type LocalAssetTransactor = CurrencyAdapter<
+/// Means for transacting assets on this chain: TEER in `Balances`, the foreign currencies in
+/// `Tokens`.
+pub type LocalAssetTransactor = (NativeAssetTransactor, ForeignAssetTransactor);
+
+/// Matches concrete fungible assets whose `id` could be converted into a native `CurrencyId`.
+pub type NativeAssetMatcher =
+	IsNativeConcrete<CurrencyId, FilteredCurrencies<CurrencyIdConvert, NativeCurrencies>>;
+
+/// Means for transacting TEER.
+pub type NativeAssetTransactor = CurrencyAdapter<
 	// Use this currency:
 	Balances,
-	// Matcher: matches concrete fungible assets whose `id` could be converted into `CurrencyId`.
-	IsNativeConcrete<CurrencyId, CurrencyIdConvert>,
+	// Matcher: matches concrete fungible assets whose `id` could be converted into a native
+	// `CurrencyId`.
+	NativeAssetMatcher,
 	// Do a simple punn to convert an AccountId32 MultiLocation into a native chain account ID:
 	LocationToAccountId,
 	// Our chain's account ID type (we can't get away without mentioning it explicitly):
@@ -180,10 +309,29 @@ pub type LocalAssetTransactor = CurrencyAdapter<
 	(),
 >;
 
+/// Means for transacting the foreign currencies.
+pub type ForeignAssetTransactor = MultiCurrencyAdapter<
+	Tokens,
+	// Assets which are no currency are not accepted.
+	(),
+	// Matcher: matches concrete fungible assets whose `id` could be converted into a foreign
+	// `CurrencyId`.
+	IsNativeConcrete<CurrencyId, FilteredCurrencies<CurrencyIdConvert, ForeignCurrencies>>,
+	AccountId,
+	LocationToAccountId,
+	CurrencyId,
+	FilteredCurrencies<CurrencyIdConvert, ForeignCurrencies>,
+	// Deposits which fail, e.g. for being below the existential deposit, go to the treasury.
+	DepositToAlternative<TreasuryAccount, Tokens, CurrencyId, AccountId, Balance>,
+>;
+
 /// This is the type we use to convert an (incoming) XCM origin into a local `Origin` instance,
 /// ready for dispatching a transaction with Xcm's `Transact`. There is an `OriginKind` which can
 /// biases the kind of local `Origin` it will become.
-pub type XcmOriginToTransactDispatchOrigin = (
+///
+/// `OriginKind::Superuser` is only ever honoured for the relay chain, no matter which converters
+/// are listed here.
+pub type XcmOriginToTransactDispatchOrigin = SuperuserOnlyFromRelay<(
 	// Sovereign account converter; this attempts to derive an `AccountId` from the origin location
 	// using `LocationToAccountId` and then turn that into the usual `Signed` origin. Useful for
 	// foreign chains who want to have a local sovereign account on this chain which they control.
@@ -202,7 +350,7 @@ pub type XcmOriginToTransactDispatchOrigin = (
 	SignedAccountId32AsNative<RelayNetwork, RuntimeOrigin>,
 	// Xcm origins can be represented natively under the Xcm pallet's Xcm origin.
 	XcmPassthrough<RuntimeOrigin>,
-);
+)>;
 
 /// This struct offers uses RelativeReserveProvider to output relative views of multilocations
 /// However, additionally accepts a MultiLocation that aims at representing the chain part
@@ -210,55 +358,340 @@ pub type XcmOriginToTransactDispatchOrigin = (
 /// If a token reserve matches against this absolute view, we return  Some(MultiLocation::here())
 /// This helps users by preventing errors when they try to transfer a token through xtokens
 /// to our chain (either inserting the relative or the absolute value).
+///
+/// The reserve of relay chain assets is the relay chain, the one of a sibling's assets the
+/// sibling. Only abstract assets and those of other consensus systems have none.
+///
+/// Cached while a message is executed, see [`CachedConversions`]. The cache is keyed by asset id
+/// only, so there must be a single `AbsoluteMultiLocation` per runtime.
 pub struct AbsoluteAndRelativeReserve<AbsoluteMultiLocation>(PhantomData<AbsoluteMultiLocation>);
 impl<AbsoluteMultiLocation> Reserve for AbsoluteAndRelativeReserve<AbsoluteMultiLocation>
 where
 	AbsoluteMultiLocation: Get<MultiLocation>,
 {
 	fn reserve(asset: &MultiAsset) -> Option<MultiLocation> {
-		RelativeReserveProvider::reserve(asset).map(|relative_reserve| {
-			if relative_reserve == AbsoluteMultiLocation::get() {
-				MultiLocation::here()
-			} else {
-				relative_reserve
-			}
+		cached_reserve(&asset.id, || {
+			RelativeReserveProvider::reserve(asset).map(|relative_reserve| {
+				if relative_reserve == AbsoluteMultiLocation::get() {
+					MultiLocation::here()
+				} else {
+					relative_reserve
+				}
+			})
 		})
 	}
 }
 
 parameter_types! {
-	// Weight for one XCM operation. Copied from moonbeam.
-	pub UnitWeightCost: Weight = Weight::from_parts(200_000_000u64, DEFAULT_PROOF_SIZE);
+	// Weight for one XCM operation.
+	pub const UnitWeightCost: Weight = XcmInstructionWeight::get();
 
 	// One TEER buys 1 second of weight.
 	pub const WeightPrice: (MultiLocation, u128) = (MultiLocation::parent(), TEER);
+	/// The weight of converting an asset id to a currency and normalizing its reserve, which read
+	/// the parachain id, unless governance set it with `XcmAdmin::set_xcm_conversion_weight`.
+	pub DefaultXcmConversionWeight: Weight = RocksDbWeight::get().reads(1);
+	/// Bounds the conversion weight governance may set, well below the weight of a block.
+	pub MaxXcmConversionWeight: Weight = RocksDbWeight::get().reads(10);
+	/// The weight of converting an asset id of a message, see [`ConversionWeigher`].
+	pub XcmConversionWeight: Weight = XcmAdmin::xcm_conversion_weight();
 }
 
-pub type Barrier = DenyThenTry<
-	DenyReserveTransferToRelayChain,
-	(
-		TakeWeightCredit,
-		AllowTopLevelPaidExecutionFrom<Everything>,
-		// Expected responses are OK.
-		AllowKnownQueryResponses<PolkadotXcm>,
-		// Subscriptions for version tracking are OK.
-		AllowSubscriptionsFrom<Everything>,
-	),
+/// Weighs every instruction at `UnitWeightCost`, plus as much for every further asset it carries.
+/// Every distinct asset id of a message adds `XcmConversionWeight`, see [`ConversionWeigher`].
+///
+/// pallet-xcm and xtokens execute their messages with the weight determined by their weigher as the
+/// limit, so they have to use the same one as the executor.
+pub type XcmWeigher = ConversionWeigher<
+	AssetCountWeigher<
+		FixedWeightBounds<UnitWeightCost, RuntimeCall, MaxInstructions>,
+		UnitWeightCost,
+	>,
+	XcmConversionWeight,
 >;
 
+/// Messages any of these rules rejects are denied.
+pub type BarrierDenyRules = (
+	DenyReserveTransferToRelayChain,
+	// Reserve transfers of KSM, whose reserve we are not.
+	DenyRelayTokenReserveToSelf,
+	// Messages loading more of an asset into holding than governance allows.
+	pallet_xcm_admin::DenyExcessiveHolding<Runtime>,
+	// Messages weighing less than the minimum set by governance.
+	pallet_xcm_admin::DenyTrivialWeight<Runtime>,
+);
+
+match_types! {
+	pub type ParentLocation: impl Contains<MultiLocation> = {
+		MultiLocation { parents: 1, interior: Here }
+	};
+}
+
+/// Messages which aren't denied are admitted by any of these rules.
+pub type BarrierAllowRules = (
+	TakeWeightCredit,
+	AllowTopLevelPaidExecutionFrom<Everything>,
+	// Expected responses are OK.
+	AllowKnownQueryResponses<PolkadotXcm>,
+	// Subscriptions for version tracking are OK.
+	AllowSubscriptionsFrom<Everything>,
+	// System notifications of the relay chain, e.g. about HRMP channels, are unpaid. They don't
+	// start with `UnpaidExecution`, so `AllowExplicitUnpaidExecutionFrom` would reject them.
+	AllowUnpaidExecutionFrom<ParentLocation>,
+);
+
+pub type Barrier = DenyAllThenTry<BarrierDenyRules, BarrierAllowRules>;
+
+parameter_types! {
+	/// How many blocks ahead the relay chain and siblings may schedule a call with
+	/// `XcmAdmin::schedule_call`. Governance may lower it with `XcmAdmin::set_schedule_delay_limit`.
+	pub const MaxXcmScheduleDelay: BlockNumber = 7 * DAYS;
+	/// Half of the agenda of a block is kept for the calls scheduled by governance.
+	pub const MaxXcmScheduledPerBlock: u32 = MaxScheduledPerBlock::get() / 2;
+}
+
+/// How deep calls may be nested into the call of a `Transact`, by scheduling, batching or
+/// otherwise wrapping them.
+const MAX_NESTED_CALL_DEPTH: u32 = 4;
+
 pub struct SafeCallFilter;
 impl frame_support::traits::Contains<RuntimeCall> for SafeCallFilter {
-	fn contains(_call: &RuntimeCall) -> bool {
+	fn contains(call: &RuntimeCall) -> bool {
 		// This is safe, as we prevent arbitrary xcm-transact executions.
 		// For rationale, see:https://github.com/paritytech/polkadot/blob/19fdd197aff085f7f66e54942999fd536e7df475/runtime/kusama/src/xcm_config.rs#L171
-		true
+		//
+		// The `CallDispatcher` only sees the call of the `Transact` itself, so the calls nested
+		// into it are held to the same rules here.
+		nested_calls_are_permitted(call, 0)
+	}
+}
+
+/// Whether `call`, nested `depth` calls deep into the call of a `Transact`, may be dispatched.
+fn nested_call_is_permitted(call: &RuntimeCall, depth: u32) -> bool {
+	use frame_support::traits::{CallMetadata, GetCallMetadata};
+
+	let CallMetadata { pallet_name, function_name } = call.get_call_metadata();
+	depth <= MAX_NESTED_CALL_DEPTH &&
+		!XcmAdmin::is_transact_blocked(pallet_name, function_name) &&
+		nested_calls_are_permitted(call, depth)
+}
+
+/// Whether the calls `call` wraps may be dispatched, be it by scheduling or batching them, or by
+/// dispatching them from another origin.
+fn nested_calls_are_permitted(call: &RuntimeCall, depth: u32) -> bool {
+	use pallet_collective::Call::{execute, propose};
+	use pallet_multisig::Call::{as_multi, as_multi_threshold_1};
+	use pallet_proxy::Call::{proxy, proxy_announced};
+	use pallet_scheduler::Call::*;
+	use pallet_utility::Call::*;
+	use pallet_xcm_admin::Call::{dispatch_scheduled, schedule_call};
+
+	match call {
+		RuntimeCall::Utility(batch { calls } | batch_all { calls } | force_batch { calls }) =>
+			calls.iter().all(|call| nested_call_is_permitted(call, depth + 1)),
+		RuntimeCall::Utility(
+			as_derivative { call, .. } | dispatch_as { call, .. } | with_weight { call, .. },
+		) |
+		RuntimeCall::Proxy(proxy { call, .. } | proxy_announced { call, .. }) |
+		RuntimeCall::Multisig(as_multi { call, .. } | as_multi_threshold_1 { call, .. }) |
+		RuntimeCall::Council(propose { proposal: call, .. } | execute { proposal: call, .. }) |
+		RuntimeCall::TechnicalCommittee(
+			propose { proposal: call, .. } | execute { proposal: call, .. },
+		) |
+		RuntimeCall::Scheduler(
+			schedule { call, .. } |
+			schedule_named { call, .. } |
+			schedule_after { call, .. } |
+			schedule_named_after { call, .. },
+		) |
+		RuntimeCall::XcmAdmin(schedule_call { call, .. } | dispatch_scheduled { call }) =>
+			nested_call_is_permitted(call, depth + 1),
+		_ => true,
+	}
+}
+
+/// The native origins of the relay chain and siblings, which may schedule calls with
+/// `XcmAdmin::schedule_call`, by their location.
+///
+/// The scheduled call is dispatched from the same origin. Neither the locations passed through by
+/// `XcmPassthrough` nor sovereign accounts can schedule calls, such that the scheduling origins
+/// are few and known.
+pub struct XcmScheduleOrigin;
+impl frame_support::traits::EnsureOrigin<RuntimeOrigin> for XcmScheduleOrigin {
+	type Success = MultiLocation;
+
+	fn try_origin(origin: RuntimeOrigin) -> Result<Self::Success, RuntimeOrigin> {
+		let location = match origin.caller() {
+			OriginCaller::CumulusXcm(cumulus_pallet_xcm::Origin::Relay) =>
+				Some(MultiLocation::parent()),
+			OriginCaller::CumulusXcm(cumulus_pallet_xcm::Origin::SiblingParachain(id)) =>
+				Some(MultiLocation::new(1, X1(Parachain((*id).into())))),
+			_ => None,
+		};
+		location.ok_or(origin)
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn try_successful_origin() -> Result<RuntimeOrigin, ()> {
+		Ok(cumulus_pallet_xcm::Origin::Relay.into())
 	}
 }
 
 parameter_types! {
 	pub const MaxAssetsIntoHolding: u32 = 64;
+	/// One unit of TEER per unit of `ref_time`, like the `IdentityFee` charged before.
+	pub const XcmFeePerRefTime: (u128, u128) = (1, 1);
+	/// The currencies XCM fees may be paid in, unless prioritized by governance in this order.
+	pub FeeCurrencies: Vec<CurrencyId> = CurrencyId::all().collect();
+	/// The price of a unit of `ref_time` in the relay chain's token, a hundredth of the one in
+	/// TEER.
+	pub const RelayTokenFeePerRefTime: (u128, u128) = (1, 100);
+}
+
+/// The location of a fee currency in the holding register, and its price per unit of `ref_time`.
+pub struct FeeCurrency;
+impl Convert<CurrencyId, Option<MultiLocation>> for FeeCurrency {
+	fn convert(id: CurrencyId) -> Option<MultiLocation> {
+		match id {
+			CurrencyId::TEER => Some(SelfReserve::get()),
+			CurrencyId::KSM => Some(RelayChainLocation::get()),
+			CurrencyId::USDT => None,
+		}
+	}
+}
+
+impl Convert<CurrencyId, Option<(u128, u128)>> for FeeCurrency {
+	fn convert(id: CurrencyId) -> Option<(u128, u128)> {
+		XcmAdmin::fee_per_ref_time(id)
+	}
+}
+
+/// The prices per unit of `ref_time` of the fee currencies, unless set by governance with
+/// `XcmAdmin::set_fee_per_ref_time`.
+pub struct DefaultFeePerRefTime;
+impl Convert<CurrencyId, Option<(u128, u128)>> for DefaultFeePerRefTime {
+	fn convert(id: CurrencyId) -> Option<(u128, u128)> {
+		match id {
+			CurrencyId::TEER => Some(XcmFeePerRefTime::get()),
+			CurrencyId::KSM => Some(RelayTokenFeePerRefTime::get()),
+			CurrencyId::USDT => None,
+		}
+	}
+}
+
+parameter_types! {
+	/// The account of the treasury.
+	pub TreasuryAccount: AccountId = crate::TreasuryPalletId::get().into_account_truncating();
+	/// Receives the XCM fees, or what the rounding leaves over if governance splits them. The
+	/// treasury, unless governance set another account with `XcmAdmin::set_xcm_fee_receiver`.
+	pub XcmFeeReceiver: AccountId = XcmAdmin::xcm_fee_receiver();
+}
+
+/// The asset trap and claims of `pallet_xcm`, keeping the trapped assets listable, see
+/// `pallet_xcm_admin::IndexTrappedAssets`.
+pub type TrappedAssets = pallet_xcm_admin::IndexTrappedAssets<Runtime, PolkadotXcm>;
+
+/// Handles the assets left over by a message as set by governance with
+/// `XcmAdmin::set_remainder_policy`.
+///
+/// Refunds need a return path: the origin must convert to an account here, otherwise the assets
+/// are trapped instead. Assets which can't be deposited are trapped as well, so nothing is lost.
+///
+/// Each deposit weighs as much as an instruction carrying the asset, see [`XcmWeigher`].
+pub struct RemainderAssetTrap;
+impl DropAssets for RemainderAssetTrap {
+	fn drop_assets(origin: &MultiLocation, assets: Assets, context: &XcmContext) -> Weight {
+		let beneficiary = match XcmAdmin::remainder_policy() {
+			RemainderDestination::Trap => None,
+			RemainderDestination::RefundToOrigin =>
+				<LocationToAccountId as XcmConvert<MultiLocation, AccountId>>::convert_ref(origin)
+					.ok()
+					.map(|_| *origin),
+			RemainderDestination::Treasury =>
+				Some(AccountIdToMultiLocation::convert(TreasuryAccount::get())),
+		};
+		let Some(beneficiary) = beneficiary else {
+			return TrappedAssets::drop_assets(origin, assets, context)
+		};
+
+		let mut weight = Weight::zero();
+		let mut undeposited = Assets::new();
+		for asset in assets.into_assets_iter() {
+			weight.saturating_accrue(UnitWeightCost::get());
+			if LocalAssetTransactor::deposit_asset(&asset, &beneficiary, context).is_err() {
+				undeposited.subsume(asset);
+			}
+		}
+		if !undeposited.is_empty() {
+			weight.saturating_accrue(TrappedAssets::drop_assets(origin, undeposited, context));
+		}
+		weight
+	}
+}
+
+/// The relay chain and the siblings governance waived the fees of with
+/// `XcmAdmin::set_fee_waived_siblings`.
+pub type FeeWaivedOrigins = pallet_xcm_admin::FeeWaivedOrigins<Runtime>;
+
+/// Handles both the execution fees charged by the trader and the fees of the `FeeManager`.
+pub type XcmFees = pallet_xcm_admin::SplitFees<
+	Runtime,
+	LocalAssetTransactor,
+	AccountIdToMultiLocation,
+	XcmFeeReceiver,
+	FeeWaivedOrigins,
+>;
+
+/// The `FeeManager` of the executor: [`XcmFees`], except that local origins pay no delivery fees
+/// for the messages of their transfers.
+///
+/// The executor takes delivery fees from the holding register, which the assets of a transfer
+/// have already left when its message is sent. Charging them would fail every transfer of a local
+/// account, which pays for it by its transaction fee instead.
+pub struct ExecutorFees;
+impl FeeManager for ExecutorFees {
+	fn is_waived(origin: Option<&MultiLocation>, reason: FeeReason) -> bool {
+		let is_local = origin.map_or(false, |origin| origin.parents == 0);
+		let is_transfer = matches!(
+			reason,
+			FeeReason::TransferReserveAsset |
+				FeeReason::DepositReserveAsset |
+				FeeReason::InitiateReserveWithdraw |
+				FeeReason::InitiateTeleport
+		);
+		(is_local && is_transfer) || XcmFees::is_waived(origin, reason)
+	}
+
+	fn handle_fee(fee: MultiAssets) {
+		XcmFees::handle_fee(fee)
+	}
+}
+
+parameter_types! {
+	/// The fee for exporting a message to another consensus system, in TEER, unless governance
+	/// set another one with `XcmAdmin::set_export_fee`.
+	pub const DefaultExportFee: Balance = TEER;
+	pub ExportPrice: MultiAssets = (SelfReserve::get(), XcmAdmin::export_fee()).into();
+}
+
+parameter_types! {
+	/// The relay chain's native token, which it may lock for use here, e.g. for remote staking.
+	pub RelayLockedAssets: MultiAssetFilter =
+		Wild(AllOf { id: Concrete(MultiLocation::parent()), fun: WildFungible });
+	/// The chains which may lock assets for use here, as pairs of the locker and the assets it
+	/// may lock, unless governance set others with `XcmAdmin::set_trusted_lockers`.
+	pub DefaultTrustedLockers: Vec<(MultiLocation, MultiAssetFilter)> =
+		vec![(MultiLocation::parent(), RelayLockedAssets::get())];
 }
 
+/// Lets the trusted lockers of `XcmAdmin` note the assets they locked for an owner here with
+/// `NoteUnlockable`, such that the owner can use them, e.g. to delegate votes.
+pub type TrustedLockers = pallet_xcm_admin::ListedTrustedLockers<Runtime>;
+
+/// Lets messages arriving over a bridge listed by governance with
+/// `XcmAdmin::set_universal_aliases` take the origin of the bridged network.
+pub type BridgedUniversalAliases = pallet_xcm_admin::ListedUniversalAliases<Runtime>;
+
 pub struct XcmExecutorConfig;
 impl xcm_executor::Config for XcmExecutorConfig {
 	type RuntimeCall = RuntimeCall;
@@ -266,35 +699,145 @@ impl xcm_executor::Config for XcmExecutorConfig {
 	// How to withdraw and deposit an asset.
 	type AssetTransactor = LocalAssetTransactor;
 	type OriginConverter = XcmOriginToTransactDispatchOrigin;
-	type IsReserve = MultiNativeAsset<AbsoluteAndRelativeReserve<SelfLocationAbsolute>>;
+	type IsReserve = pallet_xcm_admin::IsEnabledReserve<
+		Runtime,
+		MultiNativeAsset<AbsoluteAndRelativeReserve<SelfLocationAbsolute>>,
+	>;
 	type IsTeleporter = (); // No teleport for now. Better be safe than sorry.
 	type UniversalLocation = UniversalLocation;
 	type Barrier = Barrier;
-	type Weigher = FixedWeightBounds<UnitWeightCost, RuntimeCall, MaxInstructions>;
-	type Trader = UsingComponents<IdentityFee<Balance>, SelfReserve, AccountId, Balances, ()>;
+	type Weigher = XcmWeigher;
+	// Fees are paid in the currencies in the order and rounded as set by governance, see
+	// `pallet_xcm_admin::MultiCurrencyTrader`.
+	type Trader = pallet_xcm_admin::MultiCurrencyTrader<
+		Runtime,
+		FeeCurrencies,
+		FeeCurrency,
+		FeeCurrency,
+		XcmFees,
+	>;
 	type ResponseHandler = PolkadotXcm;
 	type SubscriptionService = PolkadotXcm;
-	type AssetTrap = PolkadotXcm;
-	type AssetClaims = PolkadotXcm;
-	type CallDispatcher = RuntimeCall;
+	// Left over assets are trapped unless governance routes them elsewhere, see
+	// `RemainderAssetTrap`. What is trapped stays listable, see `TrappedAssets`.
+	type AssetTrap = RemainderAssetTrap;
+	type AssetClaims = TrappedAssets;
+	type CallDispatcher = pallet_xcm_admin::TransactCallPolicy<Runtime, RuntimeCall>;
 	type PalletInstancesInfo = crate::AllPalletsWithSystem;
 	type MaxAssetsIntoHolding = MaxAssetsIntoHolding;
-	type AssetLocker = ();
+	// Locks TEER for remote unlockers, and notes what the `TrustedLockers` locked for our
+	// accounts.
+	type AssetLocker = PolkadotXcm;
 	type AssetExchanger = ();
-	type FeeManager = ();
-	type MessageExporter = ();
-	type UniversalAliases = Nothing;
+	// Fees go to the treasury or are split as set by governance, see
+	// `pallet_xcm_admin::SplitFees`.
+	type FeeManager = ExecutorFees;
+	// Exports to the network set by governance, none by default.
+	type MessageExporter = pallet_xcm_admin::BlobExporter<Runtime, ExportPrice>;
+	type UniversalAliases = BridgedUniversalAliases;
 	type SafeCallFilter = SafeCallFilter;
 }
 
+/// The XCM executor, caching the conversions of asset ids while it executes a message.
+pub type CachingXcmExecutor = CachedConversions<XcmExecutor<XcmExecutorConfig>>;
+
+/// The XCM executor of local origins, charging them the fee markups of the destinations of their
+/// transfers, which pay no delivery fees.
+pub type LocalXcmExecutor = pallet_xcm_fee_markup::MarkedUpExecution<Runtime, CachingXcmExecutor>;
+/// Weighs the messages of local accounts like [`LocalXcmExecutor`] prepares them, including the
+/// weight of charging the fee markup.
+pub type LocalXcmWeigher = pallet_xcm_fee_markup::MarkedUpWeigher<Runtime, XcmWeigher>;
+
 #[cfg(feature = "runtime-benchmarks")]
 parameter_types! {
 	pub ReachableDest: Option<MultiLocation> = Some(Parent.into());
 }
 
+/// Provides the currencies, assets and calls of this runtime to the benchmarks of
+/// `pallet_xcm_admin` and `pallet_xcm_fee_markup`.
+#[cfg(feature = "runtime-benchmarks")]
+pub struct XcmBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_xcm_admin::benchmarking::BenchmarkHelper<CurrencyId, RuntimeCall>
+	for XcmBenchmarkHelper
+{
+	fn fee_currencies() -> Vec<CurrencyId> {
+		CurrencyId::all().collect()
+	}
+
+	fn rescuable_assets() -> Vec<MultiAsset> {
+		let mut assets: Vec<MultiAsset> = CurrencyId::all()
+			.filter_map(<CurrencyIdConvert as Convert<_, Option<MultiLocation>>>::convert)
+			.map(|location| (location, TEER).into())
+			.collect();
+		assets.sort();
+		assets
+	}
+
+	fn trap_assets(origin: &MultiLocation, assets: MultiAssets) {
+		let context = XcmContext { origin: Some(*origin), message_hash: [0; 32], topic: None };
+		TrappedAssets::drop_assets(origin, assets.into(), &context);
+	}
+
+	fn schedulable_call() -> RuntimeCall {
+		RuntimeCall::System(frame_system::Call::remark { remark: vec![] })
+	}
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_xcm_fee_markup::benchmarking::BenchmarkHelper for XcmBenchmarkHelper {
+	fn funded_origin(fee: &MultiAsset) -> MultiLocation {
+		use frame_support::traits::Currency;
+
+		let who: AccountId = frame_benchmarking::account("origin", 0, 0);
+		let amount = match fee.fun {
+			Fungible(amount) => amount,
+			NonFungible(_) => 0,
+		};
+		Balances::make_free_balance_be(&who, amount.saturating_mul(2));
+		AccountIdToMultiLocation::convert(who)
+	}
+
+	fn charge_fees(origin: MultiLocation, fees: MultiAssets) -> XcmResult {
+		CachingXcmExecutor::charge_fees(origin, fees)
+	}
+}
+
 // Converts a Signed Local Origin into a MultiLocation
 pub type LocalOriginToLocation = SignedToAccountId32<RuntimeOrigin, AccountId, RelayNetwork>;
 
+/// How many instructions a program executed with `pallet_xcm::execute` may have.
+const MAX_LOCAL_PROGRAM_LENGTH: usize = 8;
+
+/// The programs local accounts may execute with `pallet_xcm::execute`: a `WithdrawAsset`,
+/// optionally followed by a `BuyExecution`, and then `DepositAsset`s only, of at most
+/// [`MAX_LOCAL_PROGRAM_LENGTH`] instructions. E.g. moving one's own assets to other local
+/// accounts.
+///
+/// Such programs can only move assets the origin withdraws from its own account. They dispatch
+/// no calls (no `Transact`), don't change their origin, don't send messages (transfers to other
+/// chains go through the reserve transfer extrinsics, which are filtered on their own) and can't
+/// claim to receive assets from elsewhere, which would mint them. Anything else is rejected.
+pub struct XcmExecuteFilter;
+impl frame_support::traits::Contains<(MultiLocation, Xcm<RuntimeCall>)> for XcmExecuteFilter {
+	fn contains((origin, message): &(MultiLocation, Xcm<RuntimeCall>)) -> bool {
+		origin.parents == 0 && is_allowed_local_program(&message.0)
+	}
+}
+
+fn is_allowed_local_program(instructions: &[Instruction<RuntimeCall>]) -> bool {
+	if instructions.len() > MAX_LOCAL_PROGRAM_LENGTH {
+		return false
+	}
+	let [WithdrawAsset(_), rest @ ..] = instructions else { return false };
+	let deposits = match rest {
+		[BuyExecution { .. }, deposits @ ..] => deposits,
+		deposits => deposits,
+	};
+	!deposits.is_empty() && deposits.iter().all(|i| matches!(i, DepositAsset { .. }))
+}
+
 // FIXME: We should probably update the configuration here.
 // See acala and moonbeam example : https://github.com/integritee-network/parachain/issues/103
 impl pallet_xcm::Config for Runtime {
@@ -302,19 +845,22 @@ impl pallet_xcm::Config for Runtime {
 	type SendXcmOrigin = EnsureXcmOrigin<RuntimeOrigin, ()>; // Prohibit sending arbitrary XCMs from users of this chain
 	type XcmRouter = XcmRouter;
 	type ExecuteXcmOrigin = EnsureXcmOrigin<RuntimeOrigin, LocalOriginToLocation>; // Allow any local origin in XCM execution.
-	type XcmExecuteFilter = Nothing; // Disable generic XCM execution. This does not affect Teleport or Reserve Transfer.
-	type XcmExecutor = XcmExecutor<XcmExecutorConfig>;
+	type XcmExecuteFilter = XcmExecuteFilter; // Only programs moving local assets, see `XcmExecuteFilter`.
+	type XcmExecutor = LocalXcmExecutor;
+	// Transfers are allowed, unless governance disabled them for an asset.
+	type XcmReserveTransferFilter = pallet_xcm_admin::ReserveTransfersEnabled<Runtime>;
 	type XcmTeleportFilter = Nothing; // Do not allow teleports
-	type XcmReserveTransferFilter = Everything; // Transfer are allowed
-	type Weigher = FixedWeightBounds<UnitWeightCost, RuntimeCall, MaxInstructions>;
+	type Weigher = LocalXcmWeigher;
 	type UniversalLocation = UniversalLocation;
 	type RuntimeOrigin = RuntimeOrigin;
 	type RuntimeCall = RuntimeCall;
+	// Destinations of unknown version we send to are queued for version discovery, this many at
+	// most. They learn `AdvertisedXcmVersion` from our answer to their own subscription.
 	const VERSION_DISCOVERY_QUEUE_SIZE: u32 = 100;
 	type AdvertisedXcmVersion = pallet_xcm::CurrentXcmVersion;
 	type Currency = Balances;
-	type CurrencyMatcher = ();
-	type TrustedLockers = ();
+	type CurrencyMatcher = NativeAssetMatcher;
+	type TrustedLockers = TrustedLockers;
 	type SovereignAccountOf = LocationToAccountId;
 	type MaxLockers = ConstU32<8>;
 	type WeightInfo = weights::pallet_xcm::WeightInfo<Runtime>;
@@ -326,6 +872,15 @@ impl pallet_xcm::Config for Runtime {
 parameter_types! {
 	pub const ShellRuntimeParaId: u32 = 2267u32;
 	pub const IntegriteeKsmParaId: u32 = 2015u32;
+	/// The pallet and call indices of the relay chain calls we encode, see
+	/// [`parachains_common::relay_call`], unless governance set others with
+	/// `XcmAdmin::set_relay_call_indices`, e.g. Polkadot's.
+	pub const DefaultRelayCallIndices: RelayCallIndices = RelayCallIndices::KUSAMA;
+}
+
+/// Encodes `call` for the relay chain, with the indices governance configured.
+pub fn encode_relay_call(call: &RelayCall) -> Vec<u8> {
+	call.encode_with(&XcmAdmin::relay_call_indices())
 }
 
 impl pallet_xcm_transactor::Config for Runtime {
@@ -335,30 +890,187 @@ impl pallet_xcm_transactor::Config for Runtime {
 	type SwapOrigin = EnsureRootOrMoreThanHalfCouncil;
 	type ShellRuntimeParaId = ShellRuntimeParaId;
 	type IntegriteeKsmParaId = IntegriteeKsmParaId;
-	type WeightInfo = ();
+	type WeightInfo = weights::pallet_xcm_transactor::WeightInfo<Runtime>;
+}
+
+/// The relay chain's maximum UMP message size, as announced in its host configuration.
+pub struct RelayMaxUmpMessageSize;
+impl Get<Option<u32>> for RelayMaxUmpMessageSize {
+	fn get() -> Option<u32> {
+		ParachainSystem::host_configuration().map(|config| config.max_upward_message_size)
+	}
+}
+
+impl pallet_xcm_admin::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type AdminOrigin = EnsureRootOrMoreThanHalfCouncil;
+	type XcmPallet = PolkadotXcm;
+	type CanonicalAssetLocation = CanonicalAssetLocation;
+	type RelayMaxUmpMessageSize = RelayMaxUmpMessageSize;
+	type ChannelInfo = ChannelInfo;
+	type FeeCurrencyId = CurrencyId;
+	type MaxFeeCurrencies = ConstU32<8>;
+	type DefaultMinXcmFee = DefaultMinXcmFee;
+	type AssetClaims = TrappedAssets;
+	type AssetTransactor = LocalAssetTransactor;
+	type AccountIdToLocation = AccountIdToMultiLocation;
+	type MaxFeeDestinations = ConstU32<8>;
+	type RuntimeCall = RuntimeCall;
+	type ScheduleOrigin = XcmScheduleOrigin;
+	type Scheduler = Scheduler;
+	type Preimages = Preimage;
+	type ScheduledCallFilter = SafeCallFilter;
+	type MaxScheduledPerOrigin = ConstU32<8>;
+	type MaxScheduledPerBlock = MaxXcmScheduledPerBlock;
+	type MaxScheduleDelay = MaxXcmScheduleDelay;
+	type DefaultXcmFeeReceiver = TreasuryAccount;
+	type MaxFeeWaivedSiblings = ConstU32<16>;
+	type DefaultRelayNetwork = DefaultRelayNetwork;
+	type DefaultFeePerRefTime = DefaultFeePerRefTime;
+	type DefaultSiblingDeliveryFee = DefaultSiblingDeliveryFee;
+	type DefaultXcmConversionWeight = DefaultXcmConversionWeight;
+	type MaxXcmConversionWeight = MaxXcmConversionWeight;
+	type MaxUniversalAliases = ConstU32<8>;
+	type DefaultExportFee = DefaultExportFee;
+	type DefaultAssetsForTransferLimit = DefaultAssetsForTransferLimit;
+	type MaxAssetsForTransferLimit = ConstU32<8>;
+	type DefaultTrustedLockers = DefaultTrustedLockers;
+	type MaxTrustedLockers = ConstU32<8>;
+	type RelayCallIndices = RelayCallIndices;
+	type DefaultRelayCallIndices = DefaultRelayCallIndices;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = XcmBenchmarkHelper;
+	type WeightInfo = pallet_xcm_admin::weights::SubstrateWeight<Runtime>;
+}
+
+impl pallet_xcm_fee_markup::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type AdminOrigin = EnsureRootOrMoreThanHalfCouncil;
+	type FeeMarkupAsset = SelfReserve;
+	type MaxFeeMarkup = ConstU128<{ 10 * TEER }>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = XcmBenchmarkHelper;
+	type WeightInfo = pallet_xcm_fee_markup::weights::SubstrateWeight<Runtime>;
+}
+
+impl pallet_xcm_dedup::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type AdminOrigin = EnsureRootOrMoreThanHalfCouncil;
+	type MaxDedupMessagesPerBlock = ConstU32<512>;
+	type MaxDedupWindow = ConstU32<{ parachains_common::DAYS }>;
+	type WeightInfo = pallet_xcm_dedup::weights::SubstrateWeight<Runtime>;
 }
 
 impl cumulus_pallet_xcm::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
-	type XcmExecutor = XcmExecutor<XcmExecutorConfig>;
+	type XcmExecutor = CachingXcmExecutor;
+}
+
+/// Executor of inbound messages, which drops duplicates and skips unsupported instructions if
+/// governance asks for it, and meters the weight of the messages it executes.
+pub type InboundXcmExecutor = pallet_xcm_dedup::DeduplicateInbound<
+	Runtime,
+	pallet_xcm_admin::SkipUnsupportedInstructions<
+		Runtime,
+		pallet_xcm_admin::MeterInbound<Runtime, CachingXcmExecutor>,
+	>,
+>;
+
+/// The status of our outbound HRMP channels.
+#[cfg(not(any(test, feature = "mock-channel-info")))]
+pub type ChannelInfo = ParachainSystem;
+/// The status of our outbound HRMP channels, which tests can override, see
+/// [`mock_channel_info`].
+#[cfg(any(test, feature = "mock-channel-info"))]
+pub type ChannelInfo = mock_channel_info::MockChannelInfo<ParachainSystem>;
+
+#[cfg(all(feature = "mock-channel-info", not(feature = "std")))]
+compile_error!(
+	"`mock-channel-info` is for native tests only, the on-chain runtime must use the channels \
+	reported by the relay chain. Build with `SKIP_WASM_BUILD=1`."
+);
+
+/// Override the status of our outbound HRMP channels, to test how outbound messages are queued
+/// without a relay chain.
+#[cfg(any(test, feature = "mock-channel-info"))]
+pub mod mock_channel_info {
+	use cumulus_primitives_core::{ChannelStatus, GetChannelInfo, ParaId};
+	use frame_support::parameter_types;
+	use sp_std::marker::PhantomData;
+
+	/// An overridden outbound channel.
+	#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+	pub enum MockChannel {
+		Closed,
+		/// The channel takes `room` more bytes, it is full if there is no room left.
+		Open {
+			room: usize,
+			max_message_size: usize,
+		},
+	}
+
+	parameter_types! {
+		/// The overridden channels, by recipient.
+		pub static Channels: Vec<(ParaId, MockChannel)> = vec![];
+	}
+
+	/// Override the status of the channel to the sibling `para`.
+	pub fn set_channel(para: u32, channel: MockChannel) {
+		let para = ParaId::from(para);
+		Channels::mutate(|channels| {
+			channels.retain(|(id, _)| *id != para);
+			channels.push((para, channel));
+		});
+	}
+
+	fn channel(id: ParaId) -> Option<MockChannel> {
+		Channels::get()
+			.into_iter()
+			.find(|(para, _)| *para == id)
+			.map(|(_, channel)| channel)
+	}
+
+	/// Reports the overridden channels, and asks `Inner` about all others.
+	pub struct MockChannelInfo<Inner>(PhantomData<Inner>);
+
+	impl<Inner: GetChannelInfo> GetChannelInfo for MockChannelInfo<Inner> {
+		fn get_channel_status(id: ParaId) -> ChannelStatus {
+			match channel(id) {
+				Some(MockChannel::Closed) => ChannelStatus::Closed,
+				Some(MockChannel::Open { room: 0, .. }) => ChannelStatus::Full,
+				Some(MockChannel::Open { room, max_message_size }) =>
+					ChannelStatus::Ready(room.min(max_message_size), max_message_size),
+				None => Inner::get_channel_status(id),
+			}
+		}
+
+		fn get_channel_max(id: ParaId) -> Option<usize> {
+			match channel(id) {
+				Some(MockChannel::Closed) => None,
+				Some(MockChannel::Open { max_message_size, .. }) => Some(max_message_size),
+				None => Inner::get_channel_max(id),
+			}
+		}
+	}
 }
 
-// FIXME: Update to PolkadotXcm.
 impl cumulus_pallet_xcmp_queue::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
-	type XcmExecutor = XcmExecutor<XcmExecutorConfig>;
-	type ChannelInfo = ParachainSystem;
-	type VersionWrapper = ();
+	type XcmExecutor = InboundXcmExecutor;
+	type ChannelInfo = ChannelInfo;
+	// Messages to siblings are sent in the XCM version `PolkadotXcm` negotiated with them, or in
+	// its `SafeXcmVersion` until then.
+	type VersionWrapper = PolkadotXcm;
 	type ExecuteOverweightOrigin = EnsureRoot<AccountId>;
 	type ControllerOrigin = EnsureRoot<AccountId>;
 	type ControllerOriginConverter = XcmOriginToTransactDispatchOrigin;
 	type WeightInfo = cumulus_pallet_xcmp_queue::weights::SubstrateWeight<Runtime>;
-	type PriceForSiblingDelivery = ();
+	type PriceForSiblingDelivery = PriceForSiblingDelivery;
 }
 
 impl cumulus_pallet_dmp_queue::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
-	type XcmExecutor = XcmExecutor<XcmExecutorConfig>;
+	type XcmExecutor = InboundXcmExecutor;
 	type ExecuteOverweightOrigin = EnsureRoot<AccountId>;
 }
 
@@ -375,12 +1087,15 @@ parameter_types! {
 	};
 }
 
-/// Copied from moonbeam: https://github.com/PureStake/moonbeam/blob/095031d171b0c163e5649ee35acbc36eef681a82/primitives/xcm/src/ethereum_xcm.rs#L34
-pub const DEFAULT_PROOF_SIZE: u64 = 128 * 1024;
-
 parameter_types! {
-	pub const BaseXcmWeight: Weight= Weight::from_parts(200_000_000u64, DEFAULT_PROOF_SIZE);
-	pub const MaxAssetsForTransfer: usize = 2;
+	pub const BaseXcmWeight: Weight = XcmInstructionWeight::get();
+	/// How many assets `orml_xtokens` transfers at most in one call, unless governance set another
+	/// limit with `XcmAdmin::set_assets_for_transfer_limit`.
+	pub const DefaultAssetsForTransferLimit: u32 = 2;
+	/// The limit set in `XcmAdmin`, in the type `orml_xtokens` expects. Transfers of more assets
+	/// fail with `orml_xtokens::Error::TooManyAssetsBeingSent`, and the limit is exposed as the
+	/// `MaxAssetsForTransfer` constant of `XTokens` in the metadata.
+	pub MaxAssetsForTransfer: usize = XcmAdmin::assets_for_transfer_limit() as usize;
 }
 
 // The min fee amount in fee asset is split into two parts:
@@ -391,11 +1106,26 @@ parameter_types! {
 // https://github.com/open-web3-stack/open-runtime-module-library/tree/master/xtokens#transfer-multiple-currencies
 
 parameter_type_with_key! {
-	pub ParachainMinFee: |_location: MultiLocation| -> Option<u128> {
-		None
+	pub ParachainMinFee: |location: MultiLocation| -> Option<u128> {
+		XcmAdmin::min_xcm_fee(location)
 	};
 }
 
+/// The minimum fees of the relay chain, in KSM, and of the Asset Hub, in USDT, unless set by
+/// governance with `XcmAdmin::set_min_xcm_fee`.
+pub struct DefaultMinXcmFee;
+impl Convert<MultiLocation, Option<u128>> for DefaultMinXcmFee {
+	fn convert(reserve: MultiLocation) -> Option<u128> {
+		match reserve {
+			// 0.004 KSM.
+			MultiLocation { parents: 1, interior: Here } => Some(4_000_000_000),
+			// 0.01 USDT.
+			MultiLocation { parents: 1, interior: X1(Parachain(1000)) } => Some(10_000),
+			_ => None,
+		}
+	}
+}
+
 pub struct AccountIdToMultiLocation;
 impl Convert<AccountId, MultiLocation> for AccountIdToMultiLocation {
 	fn convert(account: AccountId) -> MultiLocation {
@@ -410,12 +1140,1802 @@ impl orml_xtokens::Config for Runtime {
 	type CurrencyIdConvert = CurrencyIdConvert;
 	type AccountIdToMultiLocation = AccountIdToMultiLocation;
 	type SelfLocation = SelfLocation;
-	type XcmExecutor = XcmExecutor<XcmExecutorConfig>;
-	type Weigher = FixedWeightBounds<UnitWeightCost, RuntimeCall, MaxInstructions>;
+	// Buy the default weight set by governance at destinations, unless the caller limits it.
+	type XcmExecutor = pallet_xcm_admin::WithDefaultDestWeight<Runtime, LocalXcmExecutor>;
+	type Weigher = LocalXcmWeigher;
 	type BaseXcmWeight = BaseXcmWeight;
 	type UniversalLocation = UniversalLocation;
 	type MaxAssetsForTransfer = MaxAssetsForTransfer;
 	type MinXcmFee = ParachainMinFee;
-	type MultiLocationsFilter = Everything;
+	// Reject transfers to siblings we have no open channel to before withdrawing anything.
+	type MultiLocationsFilter = pallet_xcm_admin::OpenChannelDestinations<Runtime, Everything>;
 	type ReserveProvider = AbsoluteAndRelativeReserve<SelfLocationAbsolute>;
 }
+
+/// The fee for transferring `currency` to `dest` with `orml_xtokens`, in `currency`, and the
+/// weight of depositing the transferred assets at `dest`.
+///
+/// The destination's weigher and trader aren't known here, so the deposit is weighed by our
+/// `XcmWeigher` and priced like our trader prices it. The delivery fee of the message is added.
+/// Returns `None` for destinations we don't route to, for currencies our trader doesn't accept
+/// and if delivery is charged in another currency.
+pub fn estimate_xtokens_fee(
+	currency: CurrencyId,
+	dest: &MultiLocation,
+) -> Option<(Balance, Weight)> {
+	let asset: MultiAsset = (CurrencyIdConvert::convert(currency)?, 1u128).into();
+	let beneficiary: MultiLocation = X1(AccountId32 { network: None, id: [0; 32] }).into();
+	let mut message = Xcm::<RuntimeCall>(vec![
+		ReserveAssetDeposited(asset.clone().into()),
+		ClearOrigin,
+		BuyExecution { fees: asset, weight_limit: Unlimited },
+		DepositAsset { assets: Wild(AllCounted(1)), beneficiary },
+	]);
+	let weight =
+		<XcmWeigher as xcm_executor::traits::WeightBounds<RuntimeCall>>::weight(&mut message)
+			.ok()?;
+	let price = <FeeCurrency as Convert<CurrencyId, Option<(u128, u128)>>>::convert(currency)?;
+	let execution_fee = pallet_xcm_admin::fee_for(weight, price, XcmAdmin::fee_rounding())?;
+
+	let message_size = codec::Encode::encoded_size(&message) as u32;
+	let delivery_fee =
+		match delivery_fee::<PriceForParentDelivery, PriceForSiblingDelivery, CurrencyIdConvert>(
+			dest,
+			message_size,
+		)? {
+			(_, 0) => 0,
+			(delivery_currency, amount) if delivery_currency == currency => amount,
+			_ => return None,
+		};
+	Some((execution_fee.saturating_add(delivery_fee), weight))
+}
+
+/// Currencies which intentionally don't convert to a location and back, each with the reason.
+const NOT_ROUND_TRIPPING: &[CurrencyId] = &[];
+
+/// The consistency checks of the XCM configuration this runtime fails, see `IntegrityCheckApi`.
+/// The unit tests below check the same in more detail.
+pub fn integrity_failures() -> Vec<IntegrityFailure> {
+	[
+		check_currency_round_trip::<CurrencyIdConvert>(NOT_ROUND_TRIPPING),
+		check_weigher::<XcmWeigher, <Runtime as pallet_xcm::Config>::Weigher, RuntimeCall>(
+			"pallet_xcm",
+			MaxInstructions::get(),
+		),
+		check_weigher::<XcmWeigher, <Runtime as orml_xtokens::Config>::Weigher, RuntimeCall>(
+			"orml_xtokens",
+			MaxInstructions::get(),
+		),
+	]
+	.into_iter()
+	.flatten()
+	.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::XcmFeeMarkup;
+	use codec::Encode;
+	use frame_support::{traits::ProcessMessageError, BoundedVec};
+	use parachains_common::xcm_config::{
+		asymmetric_currencies, dry_run_barrier, representative_messages, weigher_mismatches,
+		BarrierRejection,
+	};
+
+	#[test]
+	fn currencies_round_trip_through_their_location() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			assert_eq!(asymmetric_currencies::<CurrencyIdConvert>(NOT_ROUND_TRIPPING), vec![]);
+		});
+	}
+
+	fn reserve_of(location: MultiLocation) -> Option<MultiLocation> {
+		sp_io::TestExternalities::default().execute_with(|| {
+			AbsoluteAndRelativeReserve::<SelfLocationAbsolute>::reserve(&(location, 1u128).into())
+		})
+	}
+
+	#[test]
+	fn our_assets_are_reserved_here_in_either_view() {
+		let self_para_id = sp_io::TestExternalities::default()
+			.execute_with(|| u32::from(ParachainInfo::parachain_id()));
+		assert_eq!(reserve_of(SelfReserve::get()), Some(MultiLocation::here()));
+		assert_eq!(
+			reserve_of(MultiLocation::new(1, X2(Parachain(self_para_id), TEER_GENERAL_KEY))),
+			Some(MultiLocation::here())
+		);
+	}
+
+	#[test]
+	fn relay_and_sibling_assets_are_reserved_there() {
+		assert_eq!(reserve_of(MultiLocation::parent()), Some(MultiLocation::parent()));
+		assert_eq!(reserve_of(USDT_LOCATION), Some(MultiLocation::new(1, X1(Parachain(1000)))));
+	}
+
+	#[test]
+	fn unknown_assets_have_no_reserve() {
+		let abstract_asset = MultiAsset { id: Abstract([0; 32]), fun: Fungible(1) };
+		assert_eq!(
+			AbsoluteAndRelativeReserve::<SelfLocationAbsolute>::reserve(&abstract_asset),
+			None
+		);
+		assert_eq!(
+			reserve_of(MultiLocation::new(2, X1(GlobalConsensus(NetworkId::Polkadot)))),
+			None
+		);
+	}
+
+	/// Messages sent by `pallet_xcm` must weigh the same as when the executor runs them, up to
+	/// the `MaxInstructions` limit.
+	#[test]
+	fn executor_and_pallet_xcm_weigh_alike() {
+		let messages = representative_messages(MaxInstructions::get());
+		// The conversion weight is kept in `XcmAdmin`.
+		sp_io::TestExternalities::default().execute_with(|| {
+			assert_eq!(
+				weigher_mismatches::<
+					<XcmExecutorConfig as xcm_executor::Config>::Weigher,
+					<Runtime as pallet_xcm::Config>::Weigher,
+					RuntimeCall,
+				>(messages),
+				Vec::<usize>::new()
+			);
+		});
+	}
+
+	#[test]
+	fn unit_and_base_xcm_weights_are_equal() {
+		assert_eq!(UnitWeightCost::get(), BaseXcmWeight::get());
+		assert_eq!(UnitWeightCost::get(), XcmInstructionWeight::get());
+	}
+
+	#[test]
+	fn transactor_weight_scales_with_the_sent_message() {
+		use pallet_xcm::WeightInfo as _;
+		use pallet_xcm_transactor::WeightInfo as _;
+		type TransactorWeights = weights::pallet_xcm_transactor::WeightInfo<Runtime>;
+
+		assert_eq!(
+			TransactorWeights::send_transact(1, 0),
+			weights::pallet_xcm::WeightInfo::<Runtime>::send()
+		);
+		let one = TransactorWeights::send_transact(1, 10);
+		let five = TransactorWeights::send_transact(5, 10);
+		assert!(five.ref_time() > one.ref_time());
+		assert!(TransactorWeights::send_transact(5, 100).all_gt(five));
+	}
+
+	#[test]
+	fn transactor_weighs_the_swap_message_it_sends() {
+		use weights::pallet_xcm_transactor::{SWAP_CALL_LEN, SWAP_UMP_INSTRUCTIONS};
+		type Builder = RelayCallBuilder<IntegriteeKsmParaId>;
+
+		let call =
+			Builder::swap_call(IntegriteeKsmParaId::get().into(), ShellRuntimeParaId::get().into());
+		let message =
+			Builder::construct_transact_xcm(call, TEER, Weight::from_parts(1_000_000_000, 65_536));
+
+		assert_eq!(message.0.len() as u32, SWAP_UMP_INSTRUCTIONS);
+		let call_len = message.0.iter().find_map(|instruction| match instruction {
+			Transact { call, .. } => Some(call.clone().into_encoded().len() as u32),
+			_ => None,
+		});
+		assert_eq!(call_len, Some(SWAP_CALL_LEN));
+	}
+
+	#[test]
+	fn xtokens_fee_of_teer_to_a_sibling_is_nonzero_and_deterministic() {
+		let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+		sp_io::TestExternalities::default().execute_with(|| {
+			let (fee, weight) = estimate_xtokens_fee(CurrencyId::TEER, &sibling).unwrap();
+			// Four instructions, paid one TEER per unit of `ref_time`, delivered for free.
+			assert_eq!(weight, UnitWeightCost::get().saturating_mul(4));
+			assert_eq!(fee, weight.ref_time() as u128);
+			assert!(fee > 0);
+			assert_eq!(estimate_xtokens_fee(CurrencyId::TEER, &sibling), Some((fee, weight)));
+		});
+	}
+
+	#[test]
+	fn executor_and_xtokens_weigh_alike() {
+		let messages = representative_messages(MaxInstructions::get());
+		// The conversion weight is kept in `XcmAdmin`.
+		sp_io::TestExternalities::default().execute_with(|| {
+			assert_eq!(
+				weigher_mismatches::<
+					<XcmExecutorConfig as xcm_executor::Config>::Weigher,
+					<Runtime as orml_xtokens::Config>::Weigher,
+					RuntimeCall,
+				>(messages),
+				Vec::<usize>::new()
+			);
+		});
+	}
+
+	#[test]
+	fn asset_conversions_are_weighed_once_per_asset_id() {
+		use xcm_executor::traits::WeightBounds;
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			let transfer = |ids: Vec<u128>| {
+				let assets: Vec<MultiAsset> = ids
+					.into_iter()
+					.map(|id| (MultiLocation::new(1, X1(GeneralIndex(id))), 100).into())
+					.collect();
+				let mut message = Xcm(assets
+					.into_iter()
+					.map(|asset| WithdrawAsset(asset.into()))
+					.collect::<Vec<Instruction<RuntimeCall>>>());
+				XcmWeigher::weight(&mut message).unwrap()
+			};
+
+			let distinct = transfer((0..8).collect());
+			let repeated = transfer(vec![0; 8]);
+			assert_eq!(distinct, repeated + XcmConversionWeight::get().saturating_mul(7));
+
+			let weight = RocksDbWeight::get().reads(2);
+			assert!(
+				XcmAdmin::set_xcm_conversion_weight(RuntimeOrigin::root(), Some(weight)).is_ok()
+			);
+			assert_eq!(transfer((0..8).collect()) - transfer(vec![0; 8]), weight.saturating_mul(7));
+		});
+	}
+
+	fn dry_run_barrier_from(
+		origin: MultiLocation,
+		message: Xcm<RuntimeCall>,
+	) -> Result<(), BarrierRejection> {
+		sp_io::TestExternalities::default().execute_with(|| {
+			dry_run_barrier::<RuntimeCall, XcmWeigher, BarrierDenyRules, BarrierAllowRules>(
+				xcm::VersionedXcm::from(message).encode(),
+				origin.into(),
+			)
+		})
+	}
+
+	fn dry_run_barrier_from_sibling(message: Xcm<RuntimeCall>) -> Result<(), BarrierRejection> {
+		dry_run_barrier_from(MultiLocation::new(1, X1(Parachain(2000))), message)
+	}
+
+	#[test]
+	fn paid_message_passes_the_barrier() {
+		let message = Xcm(vec![
+			WithdrawAsset((SelfReserve::get(), 1_000_000_000_000u128).into()),
+			BuyExecution {
+				fees: (SelfReserve::get(), 1_000_000_000_000u128).into(),
+				weight_limit: Unlimited,
+			},
+			DepositAsset { assets: AllCounted(1).into(), beneficiary: Parent.into() },
+		]);
+		assert_eq!(dry_run_barrier_from_sibling(message), Ok(()));
+	}
+
+	#[test]
+	fn reserve_transfers_of_ksm_from_here_are_denied() {
+		let ksm = MultiAsset::from((Parent, 1_000_000_000_000u128));
+		let message = Xcm(vec![
+			WithdrawAsset(ksm.clone().into()),
+			BuyExecution { fees: ksm.clone(), weight_limit: Unlimited },
+			DepositReserveAsset {
+				assets: Definite(ksm.into()),
+				dest: MultiLocation::new(1, X1(Parachain(2000))),
+				xcm: Xcm(vec![]),
+			},
+		]);
+		assert_eq!(
+			dry_run_barrier_from(Parent.into(), message),
+			Err(BarrierRejection::Denied {
+				rule: b"DenyRelayTokenReserveToSelf".to_vec(),
+				error: ProcessMessageError::Unsupported
+			})
+		);
+	}
+
+	#[test]
+	fn both_locations_of_teer_count_against_its_holding_cap() {
+		let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+		let load = |assets: Vec<MultiAsset>| {
+			let message = Xcm::<RuntimeCall>(vec![
+				WithdrawAsset(assets.into()),
+				BuyExecution { fees: (SelfReserve::get(), TEER).into(), weight_limit: Unlimited },
+				DepositAsset { assets: AllCounted(2).into(), beneficiary: sibling },
+			]);
+			dry_run_barrier::<RuntimeCall, XcmWeigher, BarrierDenyRules, BarrierAllowRules>(
+				xcm::VersionedXcm::from(message).encode(),
+				sibling.into(),
+			)
+		};
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			let self_para_id = u32::from(ParachainInfo::parachain_id());
+			let relative = SelfReserve::get();
+			let absolute = MultiLocation::new(1, X2(Parachain(self_para_id), TEER_GENERAL_KEY));
+			assert!(XcmAdmin::set_max_holding_amount(
+				RuntimeOrigin::root(),
+				Box::new(relative.into()),
+				Some(10 * TEER)
+			)
+			.is_ok());
+
+			assert_eq!(load(vec![(relative, 6 * TEER).into()]), Ok(()));
+			assert_eq!(load(vec![(absolute, 6 * TEER).into()]), Ok(()));
+			assert_eq!(
+				load(vec![(relative, 6 * TEER).into(), (absolute, 6 * TEER).into()]),
+				Err(BarrierRejection::Denied {
+					rule: b"DenyExcessiveHolding".to_vec(),
+					error: ProcessMessageError::Unsupported
+				})
+			);
+		});
+	}
+
+	#[test]
+	fn unpaid_message_is_rejected_by_every_allow_rule() {
+		let message = Xcm(vec![
+			WithdrawAsset((SelfReserve::get(), 1_000_000_000_000u128).into()),
+			DepositAsset { assets: AllCounted(1).into(), beneficiary: Parent.into() },
+		]);
+		let Err(BarrierRejection::NotAllowed { rejections }) =
+			dry_run_barrier_from_sibling(message)
+		else {
+			panic!("the unpaid message should not be allowed")
+		};
+		let rules: Vec<_> =
+			rejections.iter().map(|(rule, _)| String::from_utf8_lossy(rule)).collect();
+		assert_eq!(
+			rules,
+			[
+				"TakeWeightCredit",
+				"AllowTopLevelPaidExecutionFrom",
+				"AllowKnownQueryResponses",
+				"AllowSubscriptionsFrom",
+				"AllowUnpaidExecutionFrom"
+			]
+		);
+		// Inbound messages have no weight credit.
+		assert!(matches!(rejections[0].1, ProcessMessageError::Overweight(_)));
+	}
+
+	#[test]
+	fn unpaid_notifications_pass_the_barrier_from_the_relay_chain_only() {
+		let notification = || {
+			Xcm(vec![HrmpNewChannelOpenRequest {
+				sender: 2000,
+				max_message_size: 1024,
+				max_capacity: 8,
+			}])
+		};
+		assert_eq!(dry_run_barrier_from(Parent.into(), notification()), Ok(()));
+		assert!(matches!(
+			dry_run_barrier_from_sibling(notification()),
+			Err(BarrierRejection::NotAllowed { .. })
+		));
+	}
+
+	#[test]
+	fn dry_run_of_a_withdrawal_from_an_empty_account_fails() {
+		use parachains_common::xcm_config::{dry_run_xcm, XcmDryRunEffects};
+
+		let teer = || MultiAsset::from((SelfReserve::get(), TEER));
+		let message = Xcm::<RuntimeCall>(vec![
+			WithdrawAsset(teer().into()),
+			BuyExecution { fees: teer(), weight_limit: Unlimited },
+			DepositAsset { assets: AllCounted(1).into(), beneficiary: Parent.into() },
+		]);
+		sp_io::TestExternalities::default().execute_with(|| {
+			let effects = dry_run_xcm::<XcmExecutorConfig>(
+				xcm::VersionedXcm::from(message).encode(),
+				MultiLocation::new(1, X1(Parachain(2000))).into(),
+			);
+			assert!(
+				matches!(
+					effects,
+					Ok(XcmDryRunEffects {
+						outcome: Outcome::Incomplete(_, XcmError::FailedToTransactAsset(_)),
+						ref forwarded,
+					}) if forwarded.is_empty()
+				),
+				"{:?}",
+				effects
+			);
+		});
+	}
+
+	#[test]
+	fn inbound_messages_are_metered_by_queue() {
+		use crate::System;
+		use xcm_executor::traits::{ExecuteXcm, WeightBounds};
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			System::set_block_number(1);
+			let message = || Xcm(vec![ClearOrigin]);
+			let charged =
+				<XcmExecutorConfig as xcm_executor::Config>::Weigher::weight(&mut message())
+					.unwrap();
+			let outcome = InboundXcmExecutor::execute_xcm(Parent, message(), [0; 32], Weight::MAX);
+			assert!(matches!(outcome, Outcome::Complete(_)), "{:?}", outcome);
+
+			let weights: Vec<_> = System::events()
+				.iter()
+				.filter_map(|record| inbound_xcm_weight(&record.event))
+				.collect();
+			assert_eq!(
+				weights,
+				vec![InboundXcmWeight {
+					queue: InboundQueue::Dmp,
+					charged,
+					consumed: outcome.weight_used()
+				}]
+			);
+		});
+	}
+
+	#[test]
+	fn messages_wait_for_room_in_a_near_full_channel() {
+		use cumulus_primitives_core::{ParaId, XcmpMessageSource};
+		use mock_channel_info::{set_channel, MockChannel};
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			let version = Some(xcm::latest::VERSION);
+			assert!(PolkadotXcm::force_default_xcm_version(RuntimeOrigin::root(), version).is_ok());
+			set_channel(2000, MockChannel::Open { room: 4, max_message_size: 1024 });
+			let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+			assert!(send_xcm::<XcmRouter>(sibling, Xcm(vec![ClearOrigin; 8])).is_ok());
+
+			// The page doesn't fit into the channel, it stays queued.
+			assert_eq!(XcmpQueue::take_outbound_messages(usize::MAX), vec![]);
+
+			set_channel(2000, MockChannel::Open { room: 1024, max_message_size: 1024 });
+			let sent: Vec<_> = XcmpQueue::take_outbound_messages(usize::MAX)
+				.into_iter()
+				.map(|(para, _)| para)
+				.collect();
+			assert_eq!(sent, vec![ParaId::from(2000)]);
+		});
+	}
+
+	mod remainder {
+		use super::*;
+		use frame_support::traits::Currency;
+		use sp_runtime::traits::{BlakeTwo256, Hash};
+		use xcm::VersionedMultiAssets;
+
+		const SIBLING: u32 = 2000;
+
+		fn sibling() -> MultiLocation {
+			MultiLocation::new(1, X1(Parachain(SIBLING)))
+		}
+
+		fn sovereign() -> AccountId {
+			Sibling::from(SIBLING).into_account_truncating()
+		}
+
+		fn teer(amount: Balance) -> MultiAsset {
+			(SelfReserve::get(), amount).into()
+		}
+
+		fn traps_of(origin: &MultiLocation, assets: MultiAssets) -> u32 {
+			let assets = VersionedMultiAssets::from(assets);
+			PolkadotXcm::asset_trap(BlakeTwo256::hash_of(&(origin, &assets)))
+		}
+
+		/// Execute a message of the sibling which pays for its execution and then fails, leaving
+		/// its `10 * TEER` minus the fee in the holding register.
+		fn execute_failing_message() {
+			Balances::make_free_balance_be(&sovereign(), 100 * TEER);
+			let message = Xcm(vec![
+				WithdrawAsset(teer(10 * TEER).into()),
+				BuyExecution { fees: teer(10 * TEER), weight_limit: Unlimited },
+				Trap(1),
+				DepositAsset { assets: AllCounted(1).into(), beneficiary: sibling() },
+			]);
+			let outcome = XcmExecutor::<XcmExecutorConfig>::execute_xcm(
+				sibling(),
+				message,
+				[0; 32],
+				Weight::from_parts(100_000_000_000, 1024 * 1024),
+			);
+			assert!(matches!(outcome, Outcome::Incomplete(_, XcmError::Trap(1))));
+			// The withdrawn assets are gone from the sovereign account, whatever the policy.
+			assert!(Balances::free_balance(sovereign()) >= 90 * TEER);
+		}
+
+		/// The execution fee charged, as received by the treasury.
+		fn fee() -> Balance {
+			Balances::free_balance(XcmFeeReceiver::get())
+		}
+
+		#[test]
+		fn remainder_is_trapped_by_default() {
+			sp_io::TestExternalities::default().execute_with(|| {
+				execute_failing_message();
+				assert_eq!(Balances::free_balance(sovereign()), 90 * TEER);
+				assert_eq!(traps_of(&sibling(), teer(10 * TEER - fee()).into()), 1);
+			});
+		}
+
+		#[test]
+		fn remainder_is_refunded_to_the_origin() {
+			sp_io::TestExternalities::default().execute_with(|| {
+				let policy = RemainderDestination::RefundToOrigin;
+				assert!(XcmAdmin::set_remainder_policy(RuntimeOrigin::root(), policy).is_ok());
+				execute_failing_message();
+				assert!(fee() > 0);
+				assert_eq!(Balances::free_balance(sovereign()), 100 * TEER - fee());
+				assert_eq!(traps_of(&sibling(), teer(10 * TEER - fee()).into()), 0);
+			});
+		}
+
+		#[test]
+		fn remainder_goes_to_the_treasury() {
+			sp_io::TestExternalities::default().execute_with(|| {
+				let policy = RemainderDestination::Treasury;
+				assert!(XcmAdmin::set_remainder_policy(RuntimeOrigin::root(), policy).is_ok());
+				execute_failing_message();
+				// Both the fee and the remainder.
+				assert_eq!(Balances::free_balance(TreasuryAccount::get()), 10 * TEER);
+				assert_eq!(Balances::free_balance(sovereign()), 90 * TEER);
+			});
+		}
+
+		#[test]
+		fn deposits_of_the_remainder_are_weighed() {
+			sp_io::TestExternalities::default().execute_with(|| {
+				let policy = RemainderDestination::Treasury;
+				assert!(XcmAdmin::set_remainder_policy(RuntimeOrigin::root(), policy).is_ok());
+				let context = XcmContext { origin: None, message_hash: [0; 32], topic: None };
+
+				assert_eq!(
+					RemainderAssetTrap::drop_assets(&sibling(), teer(TEER).into(), &context),
+					UnitWeightCost::get()
+				);
+				assert_eq!(Balances::free_balance(TreasuryAccount::get()), TEER);
+			});
+		}
+
+		#[test]
+		fn refund_without_return_path_is_trapped() {
+			sp_io::TestExternalities::default().execute_with(|| {
+				let policy = RemainderDestination::RefundToOrigin;
+				assert!(XcmAdmin::set_remainder_policy(RuntimeOrigin::root(), policy).is_ok());
+				// A foreign consensus has no account here.
+				let origin = MultiLocation::new(2, X1(GlobalConsensus(NetworkId::Polkadot)));
+				let context = XcmContext { origin: None, message_hash: [0; 32], topic: None };
+
+				RemainderAssetTrap::drop_assets(&origin, teer(TEER).into(), &context);
+				assert_eq!(traps_of(&origin, teer(TEER).into()), 1);
+			});
+		}
+	}
+
+	/// The versions of the messages sent to sibling 2000 through an open channel.
+	fn versions_sent_to_sibling(messages: usize) -> Vec<xcm::Version> {
+		use codec::Decode;
+		use cumulus_primitives_core::{XcmpMessageFormat, XcmpMessageSource};
+		use mock_channel_info::{set_channel, MockChannel};
+
+		set_channel(2000, MockChannel::Open { room: 1024, max_message_size: 1024 });
+		let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+		for _ in 0..messages {
+			send_xcm::<XcmRouter>(sibling, Xcm(vec![ClearOrigin])).unwrap();
+		}
+
+		let mut versions = Vec::new();
+		for (_, page) in XcmpQueue::take_outbound_messages(usize::MAX) {
+			let mut data = &page[..];
+			assert_eq!(
+				XcmpMessageFormat::decode(&mut data),
+				Ok(XcmpMessageFormat::ConcatenatedVersionedXcm)
+			);
+			while !data.is_empty() {
+				versions.push(match xcm::VersionedXcm::<()>::decode(&mut data).unwrap() {
+					xcm::VersionedXcm::V2(_) => 2,
+					xcm::VersionedXcm::V3(_) => 3,
+				});
+			}
+		}
+		versions
+	}
+
+	#[test]
+	fn siblings_receive_messages_in_their_xcm_version() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+			assert!(
+				PolkadotXcm::force_xcm_version(RuntimeOrigin::root(), Box::new(sibling), 2).is_ok()
+			);
+			assert_eq!(versions_sent_to_sibling(2), vec![2, 2]);
+
+			assert!(
+				PolkadotXcm::force_xcm_version(RuntimeOrigin::root(), Box::new(sibling), 3).is_ok()
+			);
+			assert_eq!(versions_sent_to_sibling(1), vec![3]);
+		});
+	}
+
+	#[test]
+	fn siblings_of_unknown_version_receive_the_safe_version() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			assert!(PolkadotXcm::force_default_xcm_version(RuntimeOrigin::root(), Some(2)).is_ok());
+			assert_eq!(versions_sent_to_sibling(1), vec![2]);
+		});
+	}
+
+	#[test]
+	fn execution_fees_go_to_the_treasury() {
+		use frame_support::traits::Currency;
+		use xcm_executor::traits::ExecuteXcm;
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			let sovereign: AccountId = Sibling::from(2000).into_account_truncating();
+			let beneficiary = AccountId::from([7; 32]);
+			Balances::make_free_balance_be(&sovereign, 10 * TEER);
+			Balances::make_free_balance_be(&XcmFeeReceiver::get(), TEER);
+
+			let message = Xcm(vec![
+				WithdrawAsset((SelfReserve::get(), TEER).into()),
+				BuyExecution { fees: (SelfReserve::get(), TEER).into(), weight_limit: Unlimited },
+				DepositAsset {
+					assets: AllCounted(1).into(),
+					beneficiary: AccountId32 { network: None, id: beneficiary.clone().into() }
+						.into(),
+				},
+			]);
+			let outcome = XcmExecutor::<XcmExecutorConfig>::execute_xcm(
+				MultiLocation::new(1, X1(Parachain(2000))),
+				message,
+				[0; 32],
+				Weight::MAX,
+			);
+			assert!(matches!(outcome, Outcome::Complete(_)), "{:?}", outcome);
+
+			let fee = TEER - Balances::free_balance(&beneficiary);
+			assert!(fee > 0);
+			assert_eq!(Balances::free_balance(&XcmFeeReceiver::get()), TEER + fee);
+		});
+	}
+
+	/// Executes a reserve transfer of `amount` of the relay chain's token to `beneficiary`,
+	/// returning the fee paid.
+	fn receive_relay_token(beneficiary: &AccountId, amount: Balance) -> Balance {
+		use orml_traits::MultiCurrency;
+		use xcm_executor::traits::ExecuteXcm;
+
+		let before = Tokens::free_balance(CurrencyId::KSM, beneficiary);
+		let message = Xcm(vec![
+			ReserveAssetDeposited((Parent, amount).into()),
+			ClearOrigin,
+			BuyExecution { fees: (Parent, amount).into(), weight_limit: Unlimited },
+			DepositAsset {
+				assets: AllCounted(1).into(),
+				beneficiary: AccountId32 { network: None, id: beneficiary.clone().into() }.into(),
+			},
+		]);
+		let outcome =
+			XcmExecutor::<XcmExecutorConfig>::execute_xcm(Parent, message, [0; 32], Weight::MAX);
+		assert!(matches!(outcome, Outcome::Complete(_)), "{:?}", outcome);
+		amount - (Tokens::free_balance(CurrencyId::KSM, beneficiary) - before)
+	}
+
+	#[test]
+	fn reserve_transfers_of_the_relay_token_pay_fees_in_it() {
+		use orml_traits::MultiCurrency;
+
+		const KSM: Balance = 1_000_000_000_000;
+		sp_io::TestExternalities::default().execute_with(|| {
+			let beneficiary = AccountId::from([7; 32]);
+			let fee = receive_relay_token(&beneficiary, 10 * KSM);
+			assert!(fee > 0);
+			assert_eq!(Tokens::free_balance(CurrencyId::KSM, &XcmFeeReceiver::get()), fee);
+			assert_eq!(Balances::free_balance(&beneficiary), 0);
+		});
+	}
+
+	#[test]
+	fn relay_token_fees_follow_the_price_set_by_governance() {
+		const KSM: Balance = 1_000_000_000_000;
+		sp_io::TestExternalities::default().execute_with(|| {
+			let beneficiary = AccountId::from([7; 32]);
+			let default_fee = receive_relay_token(&beneficiary, 10 * KSM);
+
+			assert!(XcmAdmin::set_fee_per_ref_time(
+				RuntimeOrigin::root(),
+				CurrencyId::KSM,
+				Some(Some((1, 1)))
+			)
+			.is_ok());
+			let fee = receive_relay_token(&beneficiary, 10 * KSM);
+			assert_eq!(fee / RelayTokenFeePerRefTime::get().1, default_fee);
+		});
+	}
+
+	#[test]
+	fn reserve_deposited_relay_token_is_withdrawn_to_the_relay_chain() {
+		use orml_traits::MultiCurrency;
+		use xcm_executor::traits::ExecuteXcm;
+
+		const KSM: Balance = 1_000_000_000_000;
+		sp_io::TestExternalities::default().execute_with(|| {
+			frame_system::Pallet::<Runtime>::set_block_number(1);
+			let version = Some(xcm::latest::VERSION);
+			assert!(PolkadotXcm::force_default_xcm_version(RuntimeOrigin::root(), version).is_ok());
+			let alice = AccountId::from([1; 32]);
+			let message = Xcm(vec![
+				ReserveAssetDeposited((Parent, 10 * KSM).into()),
+				ClearOrigin,
+				BuyExecution { fees: (Parent, 10 * KSM).into(), weight_limit: Unlimited },
+				DepositAsset {
+					assets: AllCounted(1).into(),
+					beneficiary: AccountId32 { network: None, id: alice.clone().into() }.into(),
+				},
+			]);
+			let outcome = XcmExecutor::<XcmExecutorConfig>::execute_xcm(
+				Parent,
+				message,
+				[0; 32],
+				Weight::MAX,
+			);
+			assert!(matches!(outcome, Outcome::Complete(_)), "{:?}", outcome);
+			let received = Tokens::free_balance(CurrencyId::KSM, &alice);
+			let issuance = Tokens::total_issuance(CurrencyId::KSM);
+
+			let dest = MultiLocation::new(1, X1(AccountId32 { network: None, id: [1; 32] }));
+			assert!(crate::XTokens::transfer(
+				RuntimeOrigin::signed(alice.clone()),
+				CurrencyId::KSM,
+				5 * KSM,
+				Box::new(dest.into()),
+				Unlimited,
+			)
+			.is_ok());
+			// Withdrawn here, to be released from our sovereign account on the relay chain.
+			assert_eq!(Tokens::free_balance(CurrencyId::KSM, &alice), received - 5 * KSM);
+			assert_eq!(Tokens::total_issuance(CurrencyId::KSM), issuance - 5 * KSM);
+		});
+	}
+
+	#[test]
+	fn foreign_assets_are_deposited_into_local_accounts() {
+		use orml_traits::MultiCurrency;
+		use xcm_executor::traits::ExecuteXcm;
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			let beneficiary = AccountId::from([7; 32]);
+			let message = Xcm(vec![
+				ReserveAssetDeposited((USDT_LOCATION, 10_000).into()),
+				ClearOrigin,
+				DepositAsset {
+					assets: AllCounted(1).into(),
+					beneficiary: AccountId32 { network: None, id: beneficiary.clone().into() }
+						.into(),
+				},
+			]);
+			let outcome = XcmExecutor::<XcmExecutorConfig>::execute_xcm_in_credit(
+				MultiLocation::new(1, X1(Parachain(1000))),
+				message,
+				[0; 32],
+				Weight::MAX,
+				Weight::MAX,
+			);
+			assert!(matches!(outcome, Outcome::Complete(_)), "{:?}", outcome);
+
+			assert_eq!(Tokens::free_balance(CurrencyId::USDT, &beneficiary), 10_000);
+			assert_eq!(Balances::free_balance(&beneficiary), 0);
+		});
+	}
+
+	/// Asserts that `asset` is neither teleported in from the relay chain nor out to it.
+	fn assert_never_teleported(asset: MultiAsset) {
+		use frame_support::traits::Contains;
+		use xcm_executor::traits::ExecuteXcm;
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			let alice = AccountId::from([1; 32]);
+			let alice_location: MultiLocation =
+				AccountId32 { network: None, id: alice.clone().into() }.into();
+			let teleport_in = Xcm(vec![
+				ReceiveTeleportedAsset(asset.clone().into()),
+				DepositAsset { assets: AllCounted(1).into(), beneficiary: alice_location },
+			]);
+			let outcome = XcmExecutor::<XcmExecutorConfig>::execute_xcm_in_credit(
+				Parent,
+				teleport_in,
+				[0; 32],
+				Weight::MAX,
+				Weight::MAX,
+			);
+			assert!(matches!(outcome, Outcome::Incomplete(_, XcmError::UntrustedTeleportLocation)));
+
+			assert_eq!(
+				PolkadotXcm::teleport_assets(
+					RuntimeOrigin::signed(alice.clone()),
+					Box::new(Parent.into()),
+					Box::new(alice_location.into()),
+					Box::new(asset.clone().into()),
+					0,
+				)
+				.map_err(|e| e.error),
+				Err(pallet_xcm::Error::<Runtime>::Filtered.into())
+			);
+
+			// Nor can accounts execute programs teleporting it out.
+			let teleport_out = Xcm(vec![
+				WithdrawAsset(asset.into()),
+				InitiateTeleport {
+					assets: AllCounted(1).into(),
+					dest: Parent.into(),
+					xcm: Xcm(vec![]),
+				},
+			]);
+			assert!(!XcmExecuteFilter::contains(&(alice_location, teleport_out)));
+		});
+	}
+
+	#[test]
+	fn teer_is_never_teleported() {
+		assert_never_teleported((SelfReserve::get(), TEER).into());
+	}
+
+	#[test]
+	fn relay_token_is_never_teleported() {
+		assert_never_teleported((Parent, 1_000_000_000_000u128).into());
+	}
+
+	#[test]
+	fn sibling_delivery_fee_grows_with_the_message_size() {
+		use parachains_common::xcm_config::delivery_fee;
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+			let fee = |size| {
+				delivery_fee::<PriceForParentDelivery, PriceForSiblingDelivery, CurrencyIdConvert>(
+					&sibling, size,
+				)
+				.unwrap()
+			};
+
+			let (currency, small) = fee(100);
+			let (_, large) = fee(1_000);
+			assert_eq!(currency, CurrencyId::TEER);
+			assert!(small >= SiblingDeliveryBaseFee::get() + 100 * SiblingDeliveryByteFee::get());
+			assert!(large > small);
+
+			let doubled = DeliveryFee { base: 2 * MILLITEER, per_byte: 20 * MICROTEER };
+			assert!(
+				XcmAdmin::set_sibling_delivery_fee(RuntimeOrigin::root(), Some(doubled)).is_ok()
+			);
+			assert_eq!(fee(100).1, 2 * small);
+		});
+	}
+
+	#[test]
+	fn local_transfers_pay_no_delivery_fees() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			let local: MultiLocation = AccountId32 { network: None, id: [7; 32] }.into();
+			let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+
+			assert!(ExecutorFees::is_waived(Some(&local), FeeReason::DepositReserveAsset));
+			assert!(ExecutorFees::is_waived(Some(&local), FeeReason::TransferReserveAsset));
+			assert!(!ExecutorFees::is_waived(Some(&local), FeeReason::QueryPallet));
+			assert!(!ExecutorFees::is_waived(Some(&sibling), FeeReason::DepositReserveAsset));
+		});
+	}
+
+	#[test]
+	fn assets_failing_to_deposit_are_listed_as_trapped() {
+		use crate::System;
+		use xcm_executor::traits::ExecuteXcm;
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			System::set_block_number(1);
+			let asset_hub = MultiLocation::new(1, X1(Parachain(1000)));
+			// No currency of ours is known by this location.
+			let unknown = MultiAsset::from((
+				MultiLocation::new(1, X2(Parachain(1000), GeneralIndex(4_242))),
+				10_000,
+			));
+			let message = Xcm(vec![
+				ReserveAssetDeposited(unknown.clone().into()),
+				ClearOrigin,
+				DepositAsset {
+					assets: AllCounted(1).into(),
+					beneficiary: AccountId32 { network: None, id: [7; 32] }.into(),
+				},
+			]);
+			let outcome = XcmExecutor::<XcmExecutorConfig>::execute_xcm_in_credit(
+				asset_hub,
+				message,
+				[0; 32],
+				Weight::MAX,
+				Weight::MAX,
+			);
+			assert!(
+				matches!(outcome, Outcome::Incomplete(_, XcmError::FailedToTransactAsset(_))),
+				"{:?}",
+				outcome
+			);
+
+			assert_eq!(
+				XcmAdmin::trapped_assets(&asset_hub),
+				vec![(MultiAssets::from(unknown).into(), 1)]
+			);
+		});
+	}
+
+	/// Funds an account with KSM, USDT and TEER, and opens the channel to the Asset Hub, such that
+	/// the account can transfer them there with [`transfer_currencies`].
+	fn fund_transfers_to_asset_hub() {
+		use frame_support::traits::Currency;
+		use mock_channel_info::{set_channel, MockChannel};
+		use orml_traits::MultiCurrency;
+
+		let version = Some(xcm::latest::VERSION);
+		assert!(PolkadotXcm::force_default_xcm_version(RuntimeOrigin::root(), version).is_ok());
+		set_channel(1000, MockChannel::Open { room: 64 * 1024, max_message_size: 64 * 1024 });
+		let alice = AccountId::from([1; 32]);
+		assert!(Tokens::deposit(CurrencyId::KSM, &alice, 10_000_000_000_000).is_ok());
+		assert!(Tokens::deposit(CurrencyId::USDT, &alice, 10_000_000).is_ok());
+		Balances::make_free_balance_be(&alice, 10 * TEER);
+	}
+
+	/// Transfers `count` currencies to the Asset Hub, paying the fees in KSM. The USDT go to their
+	/// reserve, the KSM fee through the relay chain.
+	fn transfer_currencies(count: usize) -> sp_runtime::DispatchResult {
+		let currencies = [
+			(CurrencyId::KSM, 1_000_000_000_000),
+			(CurrencyId::USDT, 1_000_000),
+			(CurrencyId::TEER, TEER),
+		]
+		.into_iter()
+		.take(count)
+		.collect();
+		let dest =
+			MultiLocation::new(1, X2(Parachain(1000), AccountId32 { network: None, id: [7; 32] }));
+		crate::XTokens::transfer_multicurrencies(
+			RuntimeOrigin::signed(AccountId::from([1; 32])),
+			currencies,
+			0,
+			Box::new(dest.into()),
+			Unlimited,
+		)
+	}
+
+	#[test]
+	fn transfers_of_too_many_assets_are_rejected() {
+		let too_many = orml_xtokens::Error::<Runtime>::TooManyAssetsBeingSent.into();
+		sp_io::TestExternalities::default().execute_with(|| {
+			fund_transfers_to_asset_hub();
+			assert_eq!(MaxAssetsForTransfer::get(), 2);
+			assert_eq!(transfer_currencies(2), Ok(()));
+			assert_eq!(transfer_currencies(3), Err(too_many));
+		});
+	}
+
+	#[test]
+	fn governance_sets_the_max_assets_for_transfer() {
+		let too_many = orml_xtokens::Error::<Runtime>::TooManyAssetsBeingSent.into();
+		sp_io::TestExternalities::default().execute_with(|| {
+			fund_transfers_to_asset_hub();
+			assert!(XcmAdmin::set_assets_for_transfer_limit(RuntimeOrigin::root(), Some(1)).is_ok());
+			assert_eq!(transfer_currencies(1), Ok(()));
+			assert_eq!(transfer_currencies(2), Err(too_many));
+		});
+	}
+
+	/// Transfers a TEER of an account to an account on the sibling `para`, returning what the
+	/// account paid and what the fee receiver received.
+	fn transfer_teer_to(para: u32) -> (Balance, Balance) {
+		use frame_support::traits::Currency;
+
+		let alice = AccountId::from([1; 32]);
+		let paid = Balances::free_balance(&alice);
+		let received = Balances::free_balance(&XcmFeeReceiver::get());
+		let dest =
+			MultiLocation::new(1, X2(Parachain(para), AccountId32 { network: None, id: [7; 32] }));
+		assert_eq!(
+			crate::XTokens::transfer(
+				RuntimeOrigin::signed(alice.clone()),
+				CurrencyId::TEER,
+				TEER,
+				Box::new(dest.into()),
+				Unlimited,
+			),
+			Ok(())
+		);
+		(
+			paid - Balances::free_balance(&alice),
+			Balances::free_balance(&XcmFeeReceiver::get()) - received,
+		)
+	}
+
+	#[test]
+	fn transfers_to_a_marked_up_destination_pay_the_fee_markup() {
+		use frame_support::traits::Currency;
+		use mock_channel_info::{set_channel, MockChannel};
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			let version = Some(xcm::latest::VERSION);
+			assert!(PolkadotXcm::force_default_xcm_version(RuntimeOrigin::root(), version).is_ok());
+			set_channel(2000, MockChannel::Open { room: 64 * 1024, max_message_size: 64 * 1024 });
+			set_channel(2001, MockChannel::Open { room: 64 * 1024, max_message_size: 64 * 1024 });
+			Balances::make_free_balance_be(&AccountId::from([1; 32]), 10 * TEER);
+			Balances::make_free_balance_be(&XcmFeeReceiver::get(), TEER);
+			let marked_up = Box::new(MultiLocation::new(1, X1(Parachain(2000))).into());
+			assert!(
+				XcmFeeMarkup::set_fee_markup(RuntimeOrigin::root(), marked_up, TEER / 2).is_ok()
+			);
+
+			let (base, received) = transfer_teer_to(2001);
+			assert_eq!(received, 0);
+			assert_eq!(transfer_teer_to(2000), (base + TEER / 2, TEER / 2));
+		});
+	}
+
+	#[test]
+	fn failed_transfers_to_a_marked_up_destination_pay_no_fee_markup() {
+		use frame_support::traits::Currency;
+		use mock_channel_info::{set_channel, MockChannel};
+
+		let alice = AccountId::from([1; 32]);
+		let beneficiary = AccountId32 { network: None, id: [7; 32] };
+		sp_io::TestExternalities::default().execute_with(|| {
+			let version = Some(xcm::latest::VERSION);
+			assert!(PolkadotXcm::force_default_xcm_version(RuntimeOrigin::root(), version).is_ok());
+			set_channel(2000, MockChannel::Open { room: 64 * 1024, max_message_size: 64 * 1024 });
+			Balances::make_free_balance_be(&alice, 10 * TEER);
+			Balances::make_free_balance_be(&XcmFeeReceiver::get(), TEER);
+			let marked_up = MultiLocation::new(1, X1(Parachain(2000)));
+			let markup = Box::new(marked_up.into());
+			assert!(XcmFeeMarkup::set_fee_markup(RuntimeOrigin::root(), markup, TEER / 2).is_ok());
+
+			// More than the account has, the transfer is attempted but fails.
+			assert_eq!(
+				PolkadotXcm::limited_reserve_transfer_assets(
+					RuntimeOrigin::signed(alice.clone()),
+					Box::new(marked_up.into()),
+					Box::new(X1(beneficiary).into()),
+					Box::new((SelfReserve::get(), 20 * TEER).into()),
+					0,
+					Unlimited,
+				),
+				Ok(())
+			);
+			assert_eq!(Balances::free_balance(&alice), 10 * TEER);
+			assert_eq!(Balances::free_balance(&XcmFeeReceiver::get()), TEER);
+		});
+	}
+
+	#[test]
+	fn governance_sets_the_relay_call_indices() {
+		let call = RelayCall::Nominate { targets: vec![AccountId::new([1; 32])] };
+		sp_io::TestExternalities::default().execute_with(|| {
+			assert_eq!(encode_relay_call(&call)[..2], [6, 5]);
+			let indices = Some(RelayCallIndices::POLKADOT);
+			assert!(XcmAdmin::set_relay_call_indices(RuntimeOrigin::root(), indices).is_ok());
+			assert_eq!(encode_relay_call(&call)[..2], [7, 5]);
+		});
+	}
+
+	#[test]
+	fn min_xcm_fees_are_configured_per_reserve() {
+		use orml_traits::GetByKey;
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			let asset_hub = MultiLocation::new(1, X1(Parachain(1000)));
+			let unconfigured = MultiLocation::new(1, X1(Parachain(2000)));
+			assert_eq!(ParachainMinFee::get(&Parent.into()), Some(4_000_000_000));
+			assert_eq!(ParachainMinFee::get(&asset_hub), Some(10_000));
+			assert_eq!(ParachainMinFee::get(&unconfigured), None);
+
+			assert!(XcmAdmin::set_min_xcm_fee(
+				RuntimeOrigin::root(),
+				Box::new(unconfigured.into()),
+				Some(Some(5))
+			)
+			.is_ok());
+			assert_eq!(ParachainMinFee::get(&unconfigured), Some(5));
+		});
+	}
+
+	/// Executes a `UniversalOrigin` of Polkadot from the bridge hub.
+	fn alias_polkadot_from_bridge_hub() -> Outcome {
+		use xcm_executor::traits::ExecuteXcm;
+
+		XcmExecutor::<XcmExecutorConfig>::execute_xcm_in_credit(
+			MultiLocation::new(1, X1(Parachain(1002))),
+			Xcm(vec![UniversalOrigin(GlobalConsensus(NetworkId::Polkadot))]),
+			[0; 32],
+			Weight::MAX,
+			Weight::MAX,
+		)
+	}
+
+	#[test]
+	fn only_listed_bridges_alias_into_their_network() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			assert!(matches!(
+				alias_polkadot_from_bridge_hub(),
+				Outcome::Incomplete(_, XcmError::InvalidLocation)
+			));
+
+			let set_aliases = |bridge: MultiLocation| {
+				XcmAdmin::set_universal_aliases(
+					RuntimeOrigin::root(),
+					BoundedVec::truncate_from(vec![(bridge.into(), NetworkId::Polkadot)]),
+				)
+			};
+			let other_bridge = MultiLocation::new(1, X1(Parachain(2000)));
+			assert!(set_aliases(other_bridge).is_ok());
+			assert!(matches!(
+				alias_polkadot_from_bridge_hub(),
+				Outcome::Incomplete(_, XcmError::InvalidLocation)
+			));
+
+			let bridge_hub = MultiLocation::new(1, X1(Parachain(1002)));
+			assert!(set_aliases(bridge_hub).is_ok());
+			let outcome = alias_polkadot_from_bridge_hub();
+			assert!(matches!(outcome, Outcome::Complete(_)), "{:?}", outcome);
+		});
+	}
+
+	fn note_unlockable_from(locker: MultiLocation, asset: MultiAsset) -> Outcome {
+		use xcm_executor::traits::ExecuteXcm;
+
+		let owner = AccountId32 { network: None, id: [7; 32] }.into();
+		XcmExecutor::<XcmExecutorConfig>::execute_xcm_in_credit(
+			locker,
+			Xcm(vec![NoteUnlockable { asset, owner }]),
+			[0; 32],
+			Weight::MAX,
+			Weight::MAX,
+		)
+	}
+
+	#[test]
+	fn trusted_lockers_note_the_assets_they_locked() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			let ksm = MultiAsset::from((Parent, 1_000_000_000_000));
+			let outcome = note_unlockable_from(Parent.into(), ksm);
+			assert!(matches!(outcome, Outcome::Complete(_)), "{:?}", outcome);
+
+			let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+			let sibling_token = MultiAsset::from((MultiLocation::new(1, X1(Parachain(2000))), 10));
+			assert!(XcmAdmin::set_trusted_lockers(
+				RuntimeOrigin::root(),
+				Some(BoundedVec::truncate_from(vec![(sibling.into(), Wild(All))]))
+			)
+			.is_ok());
+			let outcome = note_unlockable_from(sibling, sibling_token);
+			assert!(matches!(outcome, Outcome::Complete(_)), "{:?}", outcome);
+		});
+	}
+
+	#[test]
+	fn untrusted_lockers_are_rejected() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+			let ksm = MultiAsset::from((Parent, 1_000_000_000_000));
+			assert!(matches!(
+				note_unlockable_from(sibling, ksm.clone()),
+				Outcome::Incomplete(_, XcmError::LockError)
+			));
+
+			// The relay chain is only trusted with its own token.
+			let sibling_token = MultiAsset::from((sibling, 10));
+			assert!(matches!(
+				note_unlockable_from(Parent.into(), sibling_token),
+				Outcome::Incomplete(_, XcmError::LockError)
+			));
+
+			assert!(XcmAdmin::set_trusted_lockers(
+				RuntimeOrigin::root(),
+				Some(BoundedVec::default())
+			)
+			.is_ok());
+			assert!(matches!(
+				note_unlockable_from(Parent.into(), ksm),
+				Outcome::Incomplete(_, XcmError::LockError)
+			));
+		});
+	}
+
+	#[test]
+	fn xcmp_queue_status_reflects_suspended_channels() {
+		use cumulus_primitives_core::{XcmpMessageFormat, XcmpMessageHandler};
+		use parachains_common::xcm_config::{
+			xcmp_queue_status, InboundChannelBacklog, OutboundChannelBacklog, XcmpQueueStatus,
+		};
+
+		let status = || {
+			xcmp_queue_status::<
+				XcmpQueue,
+				<Runtime as cumulus_pallet_xcmp_queue::Config>::ExecuteOverweightOrigin,
+			>()
+		};
+		sp_io::TestExternalities::default().execute_with(|| {
+			let XcmpQueueStatus { suspend_threshold, inbound, execute_overweight_origin, .. } =
+				status();
+			assert!(inbound.is_empty());
+			assert!(String::from_utf8(execute_overweight_origin).unwrap().contains("EnsureRoot"));
+
+			// Without any weight to execute them, the messages pile up in the channel.
+			let sibling = 2000.into();
+			let message = (
+				XcmpMessageFormat::ConcatenatedVersionedXcm,
+				xcm::VersionedXcm::<()>::from(Xcm(vec![ClearOrigin])),
+			)
+				.encode();
+			let messages =
+				(1..=suspend_threshold + 1).map(|sent_at| (sibling, sent_at, &message[..]));
+			XcmpQueue::handle_xcmp_messages(messages, Weight::zero());
+
+			let status = status();
+			assert_eq!(
+				status.inbound,
+				vec![InboundChannelBacklog {
+					sender: sibling,
+					suspended: true,
+					messages: suspend_threshold + 1
+				}]
+			);
+			// The sender is asked to suspend the channel.
+			assert!(matches!(
+				status.outbound[..],
+				[OutboundChannelBacklog { recipient, signals: true, .. }] if recipient == sibling
+			));
+		});
+	}
+
+	fn reserve_transfer_teer_to_sibling() -> sp_runtime::DispatchResult {
+		let beneficiary = AccountId32 { network: None, id: [7; 32] };
+		PolkadotXcm::limited_reserve_transfer_assets(
+			RuntimeOrigin::signed(AccountId::from([1; 32])),
+			Box::new(MultiLocation::new(1, X1(Parachain(2000))).into()),
+			Box::new(X1(beneficiary).into()),
+			Box::new((SelfReserve::get(), TEER).into()),
+			0,
+			Unlimited,
+		)
+	}
+
+	#[test]
+	fn disabled_reserve_transfers_are_filtered() {
+		use frame_support::traits::Currency;
+		use mock_channel_info::{set_channel, MockChannel};
+
+		let filtered = pallet_xcm::Error::<Runtime>::Filtered.into();
+		let alice = AccountId::from([1; 32]);
+		let sovereign: AccountId = Sibling::from(2000).into_account_truncating();
+		sp_io::TestExternalities::default().execute_with(|| {
+			let version = Some(xcm::latest::VERSION);
+			assert!(PolkadotXcm::force_default_xcm_version(RuntimeOrigin::root(), version).is_ok());
+			set_channel(2000, MockChannel::Open { room: 64 * 1024, max_message_size: 64 * 1024 });
+			Balances::make_free_balance_be(&alice, 10 * TEER);
+
+			assert!(XcmAdmin::set_reserve_transfers_enabled(RuntimeOrigin::root(), false).is_ok());
+			assert_eq!(reserve_transfer_teer_to_sibling(), Err(filtered));
+			assert_eq!(Balances::free_balance(&alice), 10 * TEER);
+
+			assert!(XcmAdmin::set_reserve_transfers_enabled(RuntimeOrigin::root(), true).is_ok());
+			assert_eq!(reserve_transfer_teer_to_sibling(), Ok(()));
+			// The TEER moved to the sibling's sovereign account, local accounts pay no fees.
+			assert_eq!(Balances::free_balance(&alice), 9 * TEER);
+			assert_eq!(Balances::free_balance(&sovereign), TEER);
+		});
+	}
+
+	#[test]
+	fn relay_chain_and_waived_siblings_pay_no_fees() {
+		use xcm_executor::traits::{FeeManager, FeeReason};
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+			let reason = FeeReason::TransferReserveAsset;
+			assert!(XcmFees::is_waived(Some(&Parent.into()), reason.clone()));
+			assert!(!XcmFees::is_waived(Some(&sibling), reason.clone()));
+
+			let siblings = BoundedVec::truncate_from(vec![2000]);
+			assert!(XcmAdmin::set_fee_waived_siblings(RuntimeOrigin::root(), siblings).is_ok());
+			assert!(XcmFees::is_waived(Some(&sibling), reason));
+		});
+	}
+
+	/// Lets sibling 2000 `Transact` `call` from its origin of `origin_kind`.
+	fn transact_from_sibling(origin_kind: OriginKind, call: RuntimeCall) -> Outcome {
+		use xcm_executor::traits::ExecuteXcm;
+
+		let message = Xcm(vec![Transact {
+			origin_kind,
+			require_weight_at_most: Weight::from_parts(1_000_000_000, 100_000),
+			call: call.encode().into(),
+		}]);
+		XcmExecutor::<XcmExecutorConfig>::execute_xcm_in_credit(
+			MultiLocation::new(1, X1(Parachain(2000))),
+			message,
+			[0; 32],
+			Weight::MAX,
+			Weight::MAX,
+		)
+	}
+
+	/// Lets sibling 2000 schedule `call` at block `when` by a `Transact` from its native origin.
+	fn schedule_from_sibling(when: BlockNumber, call: RuntimeCall) -> Outcome {
+		let schedule = RuntimeCall::XcmAdmin(pallet_xcm_admin::Call::schedule_call {
+			when,
+			call: Box::new(call),
+		});
+		transact_from_sibling(OriginKind::Native, schedule)
+	}
+
+	fn empty_batch() -> RuntimeCall {
+		RuntimeCall::Utility(pallet_utility::Call::batch { calls: vec![] })
+	}
+
+	fn scheduled_at(when: BlockNumber) -> usize {
+		pallet_scheduler::Agenda::<Runtime>::get(when).iter().flatten().count()
+	}
+
+	fn block_batches() {
+		assert!(XcmAdmin::set_transact_blocked(
+			RuntimeOrigin::root(),
+			b"Utility".to_vec(),
+			Some(b"batch".to_vec()),
+			true,
+		)
+		.is_ok());
+	}
+
+	/// Runs the scheduler in block `n` and returns the events it has deposited.
+	fn run_scheduler(n: BlockNumber) -> Vec<RuntimeEvent> {
+		use frame_support::traits::OnInitialize;
+
+		frame_system::Pallet::<Runtime>::set_block_number(n);
+		Scheduler::on_initialize(n);
+		frame_system::Pallet::<Runtime>::events().into_iter().map(|e| e.event).collect()
+	}
+
+	#[test]
+	fn siblings_schedule_calls_by_transact() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			frame_system::Pallet::<Runtime>::set_block_number(1);
+			let outcome = schedule_from_sibling(5, empty_batch());
+			assert!(matches!(outcome, Outcome::Complete(_)), "{:?}", outcome);
+			assert_eq!(scheduled_at(5), 1);
+
+			let events = run_scheduler(5);
+			assert!(events.contains(&RuntimeEvent::Scheduler(
+				pallet_scheduler::Event::Dispatched { task: (5, 0), id: None, result: Ok(()) }
+			)));
+			assert!(events.contains(&RuntimeEvent::Utility(pallet_utility::Event::BatchCompleted)));
+		});
+	}
+
+	#[test]
+	fn scheduling_distant_or_blocked_calls_by_transact_is_rejected() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			frame_system::Pallet::<Runtime>::set_block_number(1);
+			let last = 1 + MaxXcmScheduleDelay::get();
+			// `schedule_call` fails, which doesn't fail the `Transact`.
+			assert!(matches!(schedule_from_sibling(last + 1, empty_batch()), Outcome::Complete(_)));
+			assert_eq!(scheduled_at(last + 1), 0);
+			assert!(matches!(schedule_from_sibling(last, empty_batch()), Outcome::Complete(_)));
+			assert_eq!(scheduled_at(last), 1);
+
+			block_batches();
+			assert!(matches!(
+				schedule_from_sibling(5, empty_batch()),
+				Outcome::Incomplete(_, XcmError::NoPermission)
+			));
+			assert_eq!(scheduled_at(5), 0);
+		});
+	}
+
+	#[test]
+	fn passed_through_locations_cannot_schedule_calls() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			frame_system::Pallet::<Runtime>::set_block_number(1);
+			let schedule = RuntimeCall::XcmAdmin(pallet_xcm_admin::Call::schedule_call {
+				when: 5,
+				call: Box::new(empty_batch()),
+			});
+			assert!(matches!(
+				transact_from_sibling(OriginKind::Xcm, schedule),
+				Outcome::Complete(_)
+			));
+			let schedule = RuntimeCall::Scheduler(pallet_scheduler::Call::schedule {
+				when: 5,
+				maybe_periodic: None,
+				priority: 0,
+				call: Box::new(empty_batch()),
+			});
+			assert!(matches!(
+				transact_from_sibling(OriginKind::Xcm, schedule),
+				Outcome::Complete(_)
+			));
+			assert_eq!(scheduled_at(5), 0);
+		});
+	}
+
+	#[test]
+	fn calls_blocked_after_being_scheduled_are_not_dispatched() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			frame_system::Pallet::<Runtime>::set_block_number(1);
+			assert!(matches!(schedule_from_sibling(5, empty_batch()), Outcome::Complete(_)));
+			assert_eq!(scheduled_at(5), 1);
+
+			block_batches();
+			let events = run_scheduler(5);
+			assert!(events.contains(&RuntimeEvent::Scheduler(
+				pallet_scheduler::Event::Dispatched {
+					task: (5, 0),
+					id: None,
+					result: Err(pallet_xcm_admin::Error::<Runtime>::TransactCallBlocked.into()),
+				}
+			)));
+			let batch_completed = RuntimeEvent::Utility(pallet_utility::Event::BatchCompleted);
+			assert!(!events.contains(&batch_completed));
+		});
+	}
+
+	/// Whether `wrap`ping a remark passes the `SafeCallFilter`, with remarks blocked or not.
+	fn wrapped_remark_is_permitted(wrap: impl Fn(Box<RuntimeCall>) -> RuntimeCall) -> (bool, bool) {
+		use frame_support::traits::Contains;
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			let remark =
+				|| Box::new(RuntimeCall::System(frame_system::Call::remark { remark: vec![] }));
+			let permitted = SafeCallFilter::contains(&wrap(remark()));
+			assert!(XcmAdmin::set_transact_blocked(
+				RuntimeOrigin::root(),
+				b"System".to_vec(),
+				Some(b"remark".to_vec()),
+				true,
+			)
+			.is_ok());
+			(permitted, SafeCallFilter::contains(&wrap(remark())))
+		})
+	}
+
+	#[test]
+	fn blocked_calls_are_rejected_as_derivative() {
+		let wrap =
+			|call| RuntimeCall::Utility(pallet_utility::Call::as_derivative { index: 0, call });
+		assert_eq!(wrapped_remark_is_permitted(wrap), (true, false));
+	}
+
+	#[test]
+	fn blocked_calls_are_rejected_when_dispatched_as_another_origin() {
+		let wrap = |call| {
+			let as_origin = Box::new(frame_system::RawOrigin::Root.into());
+			RuntimeCall::Utility(pallet_utility::Call::dispatch_as { as_origin, call })
+		};
+		assert_eq!(wrapped_remark_is_permitted(wrap), (true, false));
+	}
+
+	#[test]
+	fn blocked_calls_are_rejected_with_weight() {
+		let wrap = |call| {
+			RuntimeCall::Utility(pallet_utility::Call::with_weight { call, weight: Weight::zero() })
+		};
+		assert_eq!(wrapped_remark_is_permitted(wrap), (true, false));
+	}
+
+	#[test]
+	fn blocked_calls_are_rejected_by_proxy() {
+		let wrap = |call| {
+			let real = AccountId::from([1; 32]).into();
+			RuntimeCall::Proxy(pallet_proxy::Call::proxy { real, force_proxy_type: None, call })
+		};
+		assert_eq!(wrapped_remark_is_permitted(wrap), (true, false));
+	}
+
+	#[test]
+	fn blocked_calls_are_rejected_by_announced_proxy() {
+		let wrap = |call| {
+			RuntimeCall::Proxy(pallet_proxy::Call::proxy_announced {
+				delegate: AccountId::from([1; 32]).into(),
+				real: AccountId::from([2; 32]).into(),
+				force_proxy_type: None,
+				call,
+			})
+		};
+		assert_eq!(wrapped_remark_is_permitted(wrap), (true, false));
+	}
+
+	#[test]
+	fn blocked_calls_are_rejected_as_multisig() {
+		let wrap = |call| {
+			RuntimeCall::Multisig(pallet_multisig::Call::as_multi {
+				threshold: 2,
+				other_signatories: vec![AccountId::from([1; 32])],
+				maybe_timepoint: None,
+				call,
+				max_weight: Weight::zero(),
+			})
+		};
+		assert_eq!(wrapped_remark_is_permitted(wrap), (true, false));
+	}
+
+	#[test]
+	fn blocked_calls_are_rejected_as_multisig_of_threshold_one() {
+		let wrap = |call| {
+			let other_signatories = vec![AccountId::from([1; 32])];
+			RuntimeCall::Multisig(pallet_multisig::Call::as_multi_threshold_1 {
+				other_signatories,
+				call,
+			})
+		};
+		assert_eq!(wrapped_remark_is_permitted(wrap), (true, false));
+	}
+
+	#[test]
+	fn blocked_calls_are_rejected_as_collective_proposals() {
+		let wrap = |proposal| {
+			RuntimeCall::Council(pallet_collective::Call::propose {
+				threshold: 1,
+				proposal,
+				length_bound: u32::MAX,
+			})
+		};
+		assert_eq!(wrapped_remark_is_permitted(wrap), (true, false));
+		let wrap = |proposal| {
+			RuntimeCall::TechnicalCommittee(pallet_collective::Call::execute {
+				proposal,
+				length_bound: u32::MAX,
+			})
+		};
+		assert_eq!(wrapped_remark_is_permitted(wrap), (true, false));
+	}
+
+	#[test]
+	fn teer_is_described_by_its_metadata() {
+		use parachains_common::xcm_config::{currency_metadata, CurrencyMetadata};
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			let teer = Some(CurrencyMetadata { symbol: b"TEER".to_vec(), decimals: 12 });
+			let absolute = CurrencyIdConvert::convert(CurrencyId::TEER).unwrap();
+			assert_eq!(currency_metadata::<CurrencyIdConvert>(SelfReserve::get().into()), teer);
+			assert_eq!(currency_metadata::<CurrencyIdConvert>(absolute.into()), teer);
+			let unknown = MultiLocation::new(1, X1(Parachain(1000)));
+			assert_eq!(currency_metadata::<CurrencyIdConvert>(unknown.into()), None);
+		});
+	}
+
+	mod execute_filter {
+		use super::*;
+		use frame_support::traits::{Contains, Currency};
+
+		fn alice() -> AccountId {
+			AccountId::from([1; 32])
+		}
+
+		fn bob() -> AccountId {
+			AccountId::from([2; 32])
+		}
+
+		fn transfer_to_bob() -> Vec<Instruction<RuntimeCall>> {
+			vec![
+				WithdrawAsset((SelfReserve::get(), TEER).into()),
+				BuyExecution { fees: (SelfReserve::get(), TEER).into(), weight_limit: Unlimited },
+				DepositAsset {
+					assets: AllCounted(1).into(),
+					beneficiary: AccountId32 { network: None, id: bob().into() }.into(),
+				},
+			]
+		}
+
+		fn is_allowed(instructions: Vec<Instruction<RuntimeCall>>) -> bool {
+			let alice = AccountId32 { network: None, id: alice().into() }.into();
+			XcmExecuteFilter::contains(&(alice, Xcm(instructions)))
+		}
+
+		#[test]
+		fn transfers_between_local_accounts_are_allowed() {
+			assert!(is_allowed(transfer_to_bob()));
+
+			let mut without_buy_execution = transfer_to_bob();
+			without_buy_execution.remove(1);
+			assert!(is_allowed(without_buy_execution));
+		}
+
+		#[test]
+		fn programs_with_transact_are_rejected() {
+			let mut message = transfer_to_bob();
+			message.insert(
+				2,
+				Transact {
+					origin_kind: OriginKind::SovereignAccount,
+					require_weight_at_most: Weight::from_parts(1_000_000, 0),
+					call: RuntimeCall::System(frame_system::Call::remark { remark: vec![] })
+						.encode()
+						.into(),
+				},
+			);
+			assert!(!is_allowed(message));
+		}
+
+		#[test]
+		fn other_programs_are_rejected() {
+			let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+			let mut minting = transfer_to_bob();
+			minting[0] = ReserveAssetDeposited((SelfReserve::get(), TEER).into());
+			let mut sending = transfer_to_bob();
+			sending[2] = DepositReserveAsset {
+				assets: AllCounted(1).into(),
+				dest: sibling,
+				xcm: Xcm(vec![]),
+			};
+			let mut too_long = transfer_to_bob();
+			too_long.extend(transfer_to_bob()[2..].iter().cloned().cycle().take(6));
+
+			assert!(!is_allowed(minting));
+			assert!(!is_allowed(sending));
+			assert!(!is_allowed(too_long));
+			assert!(!is_allowed(transfer_to_bob()[..2].to_vec()));
+			// Remote origins execute by messages, never by `pallet_xcm::execute`.
+			assert!(!XcmExecuteFilter::contains(&(sibling, Xcm(transfer_to_bob()))));
+		}
+
+		#[test]
+		fn local_accounts_execute_allowed_programs() {
+			sp_io::TestExternalities::default().execute_with(|| {
+				Balances::make_free_balance_be(&alice(), 10 * TEER);
+				let execute = |instructions| {
+					PolkadotXcm::execute(
+						RuntimeOrigin::signed(alice()),
+						Box::new(xcm::VersionedXcm::V3(Xcm(instructions))),
+						Weight::from_parts(100_000_000_000, 1024 * 1024),
+					)
+				};
+
+				assert!(execute(transfer_to_bob()).is_ok());
+				assert!(Balances::free_balance(&bob()) > 0);
+
+				let remark = RuntimeCall::System(frame_system::Call::remark { remark: vec![] });
+				let transact = vec![Transact {
+					origin_kind: OriginKind::SovereignAccount,
+					require_weight_at_most: Weight::from_parts(1_000_000, 0),
+					call: remark.encode().into(),
+				}];
+				assert_eq!(
+					execute(transact).map_err(|e| e.error),
+					Err(pallet_xcm::Error::<Runtime>::Filtered.into())
+				);
+			});
+		}
+	}
+
+	mod maintenance_mode {
+		use super::*;
+		use crate::{Council, MaintenanceMode, System};
+		use frame_support::{dispatch::GetDispatchInfo, traits::Currency};
+		use pallet_maintenance_mode::{CheckMaintenanceMode, MAINTENANCE_MODE_ERROR};
+		use sp_runtime::{
+			traits::{BlakeTwo256, Dispatchable, Hash, SignedExtension},
+			transaction_validity::{InvalidTransaction, TransactionValidityError},
+		};
+
+		fn enter_maintenance_mode() {
+			assert!(MaintenanceMode::set_maintenance_mode(RuntimeOrigin::root(), true).is_ok());
+		}
+
+		/// Submits `call` signed by `who`, checked by `CheckMaintenanceMode` before it is
+		/// dispatched.
+		fn submit(who: &AccountId, call: RuntimeCall) -> Result<(), TransactionValidityError> {
+			let info = call.get_dispatch_info();
+			CheckMaintenanceMode::<Runtime>::new().pre_dispatch(
+				who,
+				&call,
+				&info,
+				call.encoded_size(),
+			)?;
+			assert!(call.dispatch(RuntimeOrigin::signed(who.clone())).is_ok());
+			Ok(())
+		}
+
+		fn remark() -> RuntimeCall {
+			RuntimeCall::System(frame_system::Call::remark { remark: vec![] })
+		}
+
+		#[test]
+		fn inbound_xcm_is_executed_in_maintenance_mode() {
+			use cumulus_primitives_core::{XcmpMessageFormat, XcmpMessageHandler};
+
+			sp_io::TestExternalities::default().execute_with(|| {
+				enter_maintenance_mode();
+				let sibling: u32 = 2000;
+				let sovereign: AccountId = Sibling::from(sibling).into_account_truncating();
+				let beneficiary = AccountId::from([7; 32]);
+				Balances::make_free_balance_be(&sovereign, 100 * TEER);
+				assert_eq!(
+					submit(&sovereign, remark()),
+					Err(InvalidTransaction::Custom(MAINTENANCE_MODE_ERROR).into())
+				);
+
+				let teer: MultiAsset = (SelfReserve::get(), 10 * TEER).into();
+				let message = Xcm::<()>(vec![
+					WithdrawAsset(teer.clone().into()),
+					BuyExecution { fees: teer, weight_limit: Unlimited },
+					DepositAsset {
+						assets: AllCounted(1).into(),
+						beneficiary: AccountId32 { network: None, id: beneficiary.clone().into() }
+							.into(),
+					},
+				]);
+				let message =
+					(XcmpMessageFormat::ConcatenatedVersionedXcm, xcm::VersionedXcm::from(message))
+						.encode();
+				// Whatever the queue's default limit, the message is executed right away and not
+				// set aside as overweight.
+				assert!(XcmpQueue::update_xcmp_max_individual_weight(
+					RuntimeOrigin::root(),
+					Weight::from_parts(100_000_000_000, 4 * 1024 * 1024),
+				)
+				.is_ok());
+				XcmpQueue::handle_xcmp_messages(
+					[(sibling.into(), 1, &message[..])].into_iter(),
+					Weight::MAX,
+				);
+
+				assert!(MaintenanceMode::maintenance_mode());
+				assert_eq!(Balances::free_balance(&sovereign), 90 * TEER);
+				assert!(Balances::free_balance(&beneficiary) > 0);
+			});
+		}
+
+		#[test]
+		fn council_motion_leaves_maintenance_mode() {
+			sp_io::TestExternalities::default().execute_with(|| {
+				System::set_block_number(1);
+				let members: Vec<_> = (1..=3).map(|i| AccountId::from([i; 32])).collect();
+				assert!(
+					Council::set_members(RuntimeOrigin::root(), members.clone(), None, 0).is_ok()
+				);
+				enter_maintenance_mode();
+				assert_eq!(
+					submit(&members[0], remark()),
+					Err(InvalidTransaction::Custom(MAINTENANCE_MODE_ERROR).into())
+				);
+
+				let proposal = RuntimeCall::MaintenanceMode(
+					pallet_maintenance_mode::Call::set_maintenance_mode { enabled: false },
+				);
+				let proposal_hash = BlakeTwo256::hash_of(&proposal);
+				let proposal_weight_bound = proposal.get_dispatch_info().weight;
+				let length_bound = proposal.encoded_size() as u32;
+				let motion = [
+					pallet_collective::Call::propose {
+						threshold: 2,
+						proposal: Box::new(proposal),
+						length_bound,
+					},
+					pallet_collective::Call::vote {
+						proposal: proposal_hash,
+						index: 0,
+						approve: true,
+					},
+					pallet_collective::Call::close {
+						proposal_hash,
+						index: 0,
+						proposal_weight_bound,
+						length_bound,
+					},
+				];
+				for (member, call) in [0, 1, 1].into_iter().zip(motion) {
+					assert_eq!(submit(&members[member], RuntimeCall::Council(call)), Ok(()));
+				}
+
+				assert!(!MaintenanceMode::maintenance_mode());
+				assert_eq!(submit(&members[0], remark()), Ok(()));
+			});
+		}
+	}
+
+	#[test]
+	fn integrity_checks_pass() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			assert_eq!(integrity_failures(), vec![]);
+		});
+	}
+}
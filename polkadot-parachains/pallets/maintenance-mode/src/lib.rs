@@ -0,0 +1,163 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Governable maintenance mode.
+//!
+//! During upgrades or incidents, governance can stop the chain from accepting user extrinsics
+//! while it keeps producing blocks and processing XCM. In maintenance mode, the
+//! [`CheckMaintenanceMode`] signed extension rejects all signed extrinsics, apart from the calls in
+//! [`Config::AllowedInMaintenance`]. These must include the governance calls needed to leave
+//! maintenance mode again.
+//!
+//! Inherents and unsigned extrinsics don't pass through signed extensions, and neither do the
+//! dispatches of inbound XCM. They are not affected.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_support::traits::Contains;
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{DispatchInfoOf, SignedExtension},
+	transaction_validity::{
+		InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction,
+	},
+};
+use sp_std::marker::PhantomData;
+
+pub use pallet::*;
+pub use weights::WeightInfo;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+/// `InvalidTransaction::Custom` code of extrinsics rejected because of the maintenance mode.
+pub const MAINTENANCE_MODE_ERROR: u8 = 100;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(0);
+
+	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// Origin allowed to enter and leave maintenance mode.
+		type MaintenanceOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Signed calls which are still accepted in maintenance mode.
+		type AllowedInMaintenance: Contains<<Self as frame_system::Config>::RuntimeCall>;
+
+		type WeightInfo: WeightInfo;
+	}
+
+	/// Whether the chain is in maintenance mode.
+	#[pallet::storage]
+	#[pallet::getter(fn maintenance_mode)]
+	pub type MaintenanceMode<T> = StorageValue<_, bool, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The maintenance mode has been entered or left.
+		MaintenanceModeSet { enabled: bool },
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Enter or leave maintenance mode.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::set_maintenance_mode())]
+		pub fn set_maintenance_mode(origin: OriginFor<T>, enabled: bool) -> DispatchResult {
+			T::MaintenanceOrigin::ensure_origin(origin)?;
+			MaintenanceMode::<T>::put(enabled);
+			Self::deposit_event(Event::MaintenanceModeSet { enabled });
+			Ok(())
+		}
+	}
+}
+
+/// Rejects signed extrinsics in maintenance mode, unless their call is allowed by
+/// [`Config::AllowedInMaintenance`].
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct CheckMaintenanceMode<T>(PhantomData<T>);
+
+impl<T> CheckMaintenanceMode<T> {
+	/// Create a new instance.
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T> Default for CheckMaintenanceMode<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T> sp_std::fmt::Debug for CheckMaintenanceMode<T> {
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "CheckMaintenanceMode")
+	}
+}
+
+impl<T: Config + Send + Sync> SignedExtension for CheckMaintenanceMode<T> {
+	const IDENTIFIER: &'static str = "CheckMaintenanceMode";
+	type AccountId = T::AccountId;
+	type Call = <T as frame_system::Config>::RuntimeCall;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		_who: &Self::AccountId,
+		call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> TransactionValidity {
+		if MaintenanceMode::<T>::get() && !T::AllowedInMaintenance::contains(call) {
+			return Err(InvalidTransaction::Custom(MAINTENANCE_MODE_ERROR).into())
+		}
+		Ok(ValidTransaction::default())
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<(), TransactionValidityError> {
+		self.validate(who, call, info, len).map(|_| ())
+	}
+}
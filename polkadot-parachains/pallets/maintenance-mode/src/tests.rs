@@ -0,0 +1,81 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{mock::*, CheckMaintenanceMode, Event, MAINTENANCE_MODE_ERROR};
+use frame_support::{assert_noop, assert_ok, dispatch::DispatchInfo};
+use sp_runtime::{
+	traits::SignedExtension,
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+	DispatchError,
+};
+
+fn user_call() -> RuntimeCall {
+	RuntimeCall::System(frame_system::Call::remark { remark: vec![1, 2, 3] })
+}
+
+fn leave_maintenance_call() -> RuntimeCall {
+	RuntimeCall::MaintenanceMode(crate::Call::set_maintenance_mode { enabled: false })
+}
+
+fn validate(call: &RuntimeCall) -> Result<(), TransactionValidityError> {
+	let info = DispatchInfo::default();
+	CheckMaintenanceMode::<Test>::new().validate(&1, call, &info, 0)?;
+	CheckMaintenanceMode::<Test>::new().pre_dispatch(&1, call, &info, 0)
+}
+
+#[test]
+fn user_extrinsics_are_accepted_by_default() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(validate(&user_call()));
+	});
+}
+
+#[test]
+fn user_extrinsics_are_rejected_in_maintenance_mode() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(MaintenanceMode::set_maintenance_mode(RuntimeOrigin::root(), true));
+		System::assert_last_event(Event::MaintenanceModeSet { enabled: true }.into());
+
+		assert_eq!(
+			validate(&user_call()),
+			Err(InvalidTransaction::Custom(MAINTENANCE_MODE_ERROR).into())
+		);
+	});
+}
+
+#[test]
+fn allowed_calls_can_leave_maintenance_mode() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(MaintenanceMode::set_maintenance_mode(RuntimeOrigin::root(), true));
+		assert_ok!(validate(&leave_maintenance_call()));
+
+		assert_ok!(MaintenanceMode::set_maintenance_mode(RuntimeOrigin::root(), false));
+		System::assert_last_event(Event::MaintenanceModeSet { enabled: false }.into());
+		assert!(!MaintenanceMode::maintenance_mode());
+		assert_ok!(validate(&user_call()));
+	});
+}
+
+#[test]
+fn set_maintenance_mode_requires_maintenance_origin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			MaintenanceMode::set_maintenance_mode(RuntimeOrigin::signed(1), true),
+			DispatchError::BadOrigin
+		);
+	});
+}
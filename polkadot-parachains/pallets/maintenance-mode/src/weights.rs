@@ -0,0 +1,30 @@
+//! Weights for `pallet_maintenance_mode`.
+//!
+//! The pallet is not benchmarked yet, these are conservative estimates based on the storage
+//! accesses of each call.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::Weight};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for `pallet_maintenance_mode`.
+pub trait WeightInfo {
+	fn set_maintenance_mode() -> Weight;
+}
+
+/// Weights for `pallet_maintenance_mode` using the database weights of the runtime.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: MaintenanceMode MaintenanceMode (r:0 w:1)
+	fn set_maintenance_mode() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+}
+
+impl WeightInfo for () {
+	fn set_maintenance_mode() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+}
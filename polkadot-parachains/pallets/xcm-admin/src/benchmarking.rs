@@ -0,0 +1,352 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarks of the calls of pallet-xcm-admin.
+
+use super::*;
+use frame_benchmarking::v1::{account, benchmarks, BenchmarkError};
+use frame_support::traits::EnsureOrigin;
+
+/// The bound of `pallet_xcm`'s version discovery queue.
+const VERSION_DISCOVERY_QUEUE_SIZE: u32 = 100;
+
+/// Provides the values of the runtime the benchmarks depend on.
+pub trait BenchmarkHelper<FeeCurrencyId, RuntimeCall> {
+	/// All fee currencies, without duplicates.
+	fn fee_currencies() -> Vec<FeeCurrencyId>;
+
+	/// Distinct assets the [`Config::AssetTransactor`] can deposit into an account.
+	fn rescuable_assets() -> Vec<MultiAsset>;
+
+	/// Trap `assets` for `origin`, such that the [`Config::AssetClaims`] can claim them.
+	fn trap_assets(origin: &MultiLocation, assets: MultiAssets);
+
+	/// A call the [`Config::ScheduledCallFilter`] admits, which the successful origin of the
+	/// [`Config::ScheduleOrigin`] can dispatch.
+	fn schedulable_call() -> RuntimeCall;
+}
+
+fn admin_origin<T: Config>() -> Result<T::RuntimeOrigin, BenchmarkError> {
+	T::AdminOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)
+}
+
+fn schedule_origin<T: Config>() -> Result<T::RuntimeOrigin, BenchmarkError> {
+	T::ScheduleOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)
+}
+
+fn sibling(id: u32) -> MultiLocation {
+	MultiLocation::new(1, X1(Parachain(id)))
+}
+
+benchmarks! {
+	clear_version_discovery_queue {
+		let origin = admin_origin::<T>()?;
+		let queue: Vec<_> = (0..VERSION_DISCOVERY_QUEUE_SIZE)
+			.map(|id| (VersionedMultiLocation::from(sibling(id)), 1u32))
+			.collect();
+		unhashed::put(&version_discovery_queue_key::<T>(), &queue);
+	}: _<T::RuntimeOrigin>(origin, Box::new(sibling(0).into()))
+	verify {
+		assert_eq!(
+			Pallet::<T>::version_discovery_queue().len() as u32,
+			VERSION_DISCOVERY_QUEUE_SIZE - 1
+		);
+	}
+
+	set_reserve_transfer_enabled {
+		let origin = admin_origin::<T>()?;
+	}: _<T::RuntimeOrigin>(origin, Box::new(MultiLocation::parent().into()), false)
+	verify {
+		let asset = T::CanonicalAssetLocation::convert(MultiLocation::parent());
+		assert!(ReserveTransferDisabled::<T>::contains_key(asset));
+	}
+
+	set_max_ump_message_size {
+		let origin = admin_origin::<T>()?;
+	}: _<T::RuntimeOrigin>(origin, Some(1024))
+	verify {
+		assert_eq!(MaxUmpMessageSize::<T>::get(), Some(1024));
+	}
+
+	set_transact_blocked {
+		let origin = admin_origin::<T>()?;
+		let pallet_name = vec![b'p'; CallName::bound()];
+		let function_name = vec![b'f'; CallName::bound()];
+	}: _<T::RuntimeOrigin>(origin, pallet_name, Some(function_name), true)
+	verify {
+		let pallet_name = "p".repeat(CallName::bound());
+		let function_name = "f".repeat(CallName::bound());
+		assert!(Pallet::<T>::is_transact_blocked(&pallet_name, &function_name));
+	}
+
+	set_unsupported_instruction_policy {
+		let origin = admin_origin::<T>()?;
+	}: _<T::RuntimeOrigin>(origin, UnsupportedInstructionPolicy::Skip)
+	verify {
+		assert_eq!(UnsupportedInstructions::<T>::get(), UnsupportedInstructionPolicy::Skip);
+	}
+
+	set_fee_rounding {
+		let origin = admin_origin::<T>()?;
+	}: _<T::RuntimeOrigin>(origin, FeeRounding::Nearest)
+	verify {
+		assert_eq!(FeeRoundingMode::<T>::get(), FeeRounding::Nearest);
+	}
+
+	set_dmp_order {
+		let origin = admin_origin::<T>()?;
+	}: _<T::RuntimeOrigin>(origin, DmpOrder::Priority)
+	verify {
+		assert_eq!(DmpProcessingOrder::<T>::get(), DmpOrder::Priority);
+	}
+
+	set_fee_currency_priority {
+		let n in 0 .. T::BenchmarkHelper::fee_currencies().len() as u32;
+		let origin = admin_origin::<T>()?;
+		let priority: Vec<_> =
+			T::BenchmarkHelper::fee_currencies().into_iter().take(n as usize).collect();
+		let priority = BoundedVec::try_from(priority)
+			.map_err(|_| BenchmarkError::Stop("more fee currencies than `MaxFeeCurrencies`"))?;
+	}: _<T::RuntimeOrigin>(origin, priority)
+	verify {
+		assert_eq!(FeeCurrencyPriority::<T>::get().len() as u32, n);
+	}
+
+	set_max_holding_amount {
+		let origin = admin_origin::<T>()?;
+	}: _<T::RuntimeOrigin>(origin, Box::new(MultiLocation::parent().into()), Some(1))
+	verify {
+		let asset = T::CanonicalAssetLocation::convert(MultiLocation::parent());
+		assert_eq!(MaxHoldingAmounts::<T>::get(asset), Some(1));
+	}
+
+	set_min_execution_weight {
+		let origin = admin_origin::<T>()?;
+		let weight = Weight::from_parts(1_000, 1_000);
+	}: _<T::RuntimeOrigin>(origin, Some(weight))
+	verify {
+		assert_eq!(MinExecutionWeight::<T>::get(), Some(weight));
+	}
+
+	set_default_dest_weight {
+		let origin = admin_origin::<T>()?;
+		let weight = Weight::from_parts(1_000, 1_000);
+	}: _<T::RuntimeOrigin>(origin, DestinationKind::Sibling, Some(weight))
+	verify {
+		assert_eq!(DefaultDestWeight::<T>::get(DestinationKind::Sibling), Some(weight));
+	}
+
+	set_fee_split {
+		let n in 0 .. T::MaxFeeDestinations::get();
+		let origin = admin_origin::<T>()?;
+		// Equal shares, the last one takes what the division leaves over.
+		let share = Perbill::one().deconstruct().checked_div(n).unwrap_or_default();
+		let split: Vec<_> = (0..n)
+			.map(|i| {
+				let share = if i + 1 == n {
+					Perbill::one().deconstruct() - share * (n - 1)
+				} else {
+					share
+				};
+				(FeeDestination::Account(account("receiver", i, 0)), Perbill::from_parts(share))
+			})
+			.collect();
+		let split = BoundedVec::truncate_from(split);
+	}: _<T::RuntimeOrigin>(origin, split)
+	verify {
+		assert_eq!(FeeSplit::<T>::get().len() as u32, n);
+	}
+
+	rescue_trapped_assets {
+		let n in 1 .. T::BenchmarkHelper::rescuable_assets().len() as u32;
+		let origin = admin_origin::<T>()?;
+		let trapped_by = sibling(1000);
+		let assets = MultiAssets::from_sorted_and_deduplicated(
+			T::BenchmarkHelper::rescuable_assets().into_iter().take(n as usize).collect(),
+		)
+		.map_err(|()| BenchmarkError::Stop("rescuable assets not sorted or duplicated"))?;
+		T::BenchmarkHelper::trap_assets(&trapped_by, assets.clone());
+		let assets = VersionedMultiAssets::from(assets);
+		let ticket = BlakeTwo256::hash_of(&(trapped_by, &assets));
+		let beneficiary: T::AccountId = account("beneficiary", 0, 0);
+	}: _<T::RuntimeOrigin>(
+		origin,
+		ticket,
+		Box::new(trapped_by.into()),
+		Box::new(assets),
+		beneficiary
+	)
+	verify {
+		assert!(Pallet::<T>::trapped_assets(&trapped_by).is_empty());
+	}
+
+	set_min_xcm_fee {
+		let origin = admin_origin::<T>()?;
+	}: _<T::RuntimeOrigin>(origin, Box::new(MultiLocation::parent().into()), Some(Some(1)))
+	verify {
+		assert_eq!(Pallet::<T>::min_xcm_fee(&MultiLocation::parent()), Some(1));
+	}
+
+	set_export_network {
+		let origin = admin_origin::<T>()?;
+	}: _<T::RuntimeOrigin>(origin, Some(NetworkId::Polkadot))
+	verify {
+		assert_eq!(ExportNetwork::<T>::get(), Some(NetworkId::Polkadot));
+	}
+
+	set_reserve_transfers_enabled {
+		let origin = admin_origin::<T>()?;
+	}: _<T::RuntimeOrigin>(origin, false)
+	verify {
+		assert!(ReserveTransfersPaused::<T>::get());
+	}
+
+	schedule_call {
+		let origin = schedule_origin::<T>()?;
+		let location = T::ScheduleOrigin::ensure_origin(origin.clone())
+			.map_err(|_| BenchmarkError::Stop("the successful schedule origin fails"))?;
+		let when = frame_system::Pallet::<T>::block_number() + 1u32.into();
+		// The pending calls of the origin are all kept, but the one scheduled now.
+		let pending = vec![when; T::MaxScheduledPerOrigin::get().saturating_sub(1) as usize];
+		ScheduledCalls::<T>::insert(location, BoundedVec::truncate_from(pending));
+		let call = T::BenchmarkHelper::schedulable_call();
+	}: _<T::RuntimeOrigin>(origin, when, Box::new(call))
+	verify {
+		assert_eq!(ScheduledCallsAt::<T>::get(when), 1);
+	}
+
+	dispatch_scheduled {
+		let origin = schedule_origin::<T>()?;
+		let call = T::BenchmarkHelper::schedulable_call();
+	}: _<T::RuntimeOrigin>(origin, Box::new(call))
+
+	set_xcm_fee_receiver {
+		let origin = admin_origin::<T>()?;
+		let receiver: T::AccountId = account("receiver", 0, 0);
+	}: _<T::RuntimeOrigin>(origin, Some(receiver.clone()))
+	verify {
+		assert_eq!(XcmFeeReceiver::<T>::get(), Some(receiver));
+	}
+
+	set_fee_waived_siblings {
+		let n in 0 .. T::MaxFeeWaivedSiblings::get();
+		let origin = admin_origin::<T>()?;
+		// In reverse, such that they have to be sorted.
+		let siblings = BoundedVec::truncate_from((0..n).rev().collect());
+	}: _<T::RuntimeOrigin>(origin, siblings)
+	verify {
+		assert_eq!(FeeWaivedSiblings::<T>::get().len() as u32, n);
+	}
+
+	set_relay_network {
+		let origin = admin_origin::<T>()?;
+	}: _<T::RuntimeOrigin>(origin, Some(NetworkId::Westend))
+	verify {
+		assert_eq!(RelayNetwork::<T>::get(), Some(NetworkId::Westend));
+	}
+
+	set_remainder_policy {
+		let origin = admin_origin::<T>()?;
+	}: _<T::RuntimeOrigin>(origin, RemainderDestination::Treasury)
+	verify {
+		assert_eq!(RemainderPolicy::<T>::get(), RemainderDestination::Treasury);
+	}
+
+	set_schedule_delay_limit {
+		let origin = admin_origin::<T>()?;
+		let limit = T::MaxScheduleDelay::get();
+	}: _<T::RuntimeOrigin>(origin, Some(limit))
+	verify {
+		assert_eq!(ScheduleDelayLimit::<T>::get(), Some(limit));
+	}
+
+	set_fee_per_ref_time {
+		let origin = admin_origin::<T>()?;
+		let currency = T::BenchmarkHelper::fee_currencies()
+			.into_iter()
+			.next()
+			.ok_or(BenchmarkError::Stop("no fee currencies"))?;
+	}: _<T::RuntimeOrigin>(origin, currency.clone(), Some(Some((1, 1))))
+	verify {
+		assert_eq!(FeePricesPerRefTime::<T>::get(currency), Some(Some((1, 1))));
+	}
+
+	set_sibling_delivery_fee {
+		let origin = admin_origin::<T>()?;
+		let fee = DeliveryFee { base: 1, per_byte: 1 };
+	}: _<T::RuntimeOrigin>(origin, Some(fee))
+	verify {
+		assert_eq!(SiblingDeliveryFee::<T>::get(), Some(fee));
+	}
+
+	set_xcm_conversion_weight {
+		let origin = admin_origin::<T>()?;
+		let weight = T::MaxXcmConversionWeight::get();
+	}: _<T::RuntimeOrigin>(origin, Some(weight))
+	verify {
+		assert_eq!(XcmConversionWeight::<T>::get(), Some(weight));
+	}
+
+	set_universal_aliases {
+		let n in 0 .. T::MaxUniversalAliases::get();
+		let origin = admin_origin::<T>()?;
+		let aliases = (0..n)
+			.map(|id| (sibling(id).into(), NetworkId::Ethereum { chain_id: id.into() }))
+			.collect();
+		let aliases = BoundedVec::truncate_from(aliases);
+	}: _<T::RuntimeOrigin>(origin, aliases)
+	verify {
+		assert_eq!(UniversalAliases::<T>::get().len() as u32, n);
+	}
+
+	set_export_fee {
+		let origin = admin_origin::<T>()?;
+	}: _<T::RuntimeOrigin>(origin, Some(1))
+	verify {
+		assert_eq!(ExportFee::<T>::get(), Some(1));
+	}
+
+	set_assets_for_transfer_limit {
+		let origin = admin_origin::<T>()?;
+		let limit = T::MaxAssetsForTransferLimit::get();
+	}: _<T::RuntimeOrigin>(origin, Some(limit))
+	verify {
+		assert_eq!(AssetsForTransferLimit::<T>::get(), Some(limit));
+	}
+
+	set_trusted_lockers {
+		let n in 0 .. T::MaxTrustedLockers::get();
+		let origin = admin_origin::<T>()?;
+		let lockers = (0..n)
+			.map(|id| (sibling(id).into(), MultiAssetFilter::Wild(WildMultiAsset::All)))
+			.collect();
+		let lockers = BoundedVec::truncate_from(lockers);
+	}: _<T::RuntimeOrigin>(origin, Some(lockers))
+	verify {
+		assert_eq!(TrustedLockers::<T>::get().map(|lockers| lockers.len() as u32), Some(n));
+	}
+
+	set_relay_call_indices {
+		let origin = admin_origin::<T>()?;
+		let indices = T::DefaultRelayCallIndices::get();
+	}: _<T::RuntimeOrigin>(origin, Some(indices.clone()))
+	verify {
+		assert_eq!(RelayChainCallIndices::<T>::get(), Some(indices));
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,2464 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Governance controls for the XCM configuration of the Integritee runtimes.
+//!
+//! ## Version discovery queue
+//!
+//! `pallet_xcm` queues destinations whose XCM version is unknown for version negotiation. Entries
+//! of unreachable destinations stay there forever and use up the negotiation attempts. The queue
+//! can be inspected with [`Pallet::version_discovery_queue`] and pruned by governance. Removing an
+//! entry of a reachable destination is harmless, the next message to it queues it again.
+//!
+//! ## Per-asset reserve transfer toggles
+//!
+//! Governance can disable reserve transfers of single assets. Both the inbound `IsReserve` path
+//! ([`IsEnabledReserve`]) and the outbound `XcmReserveTransferFilter` of `pallet_xcm`
+//! ([`ReserveTransfersEnabled`]) consult the toggles. Claiming trapped assets doesn't involve
+//! either of them and is therefore not affected.
+//!
+//! During incidents, governance can also pause all outbound reserve transfers of `pallet_xcm` at
+//! once with [`Pallet::set_reserve_transfers_enabled`], without a runtime upgrade. Inbound reserve
+//! transfers are not paused, their assets would only be trapped.
+//!
+//! ## Open channel check for reserve transfers
+//!
+//! A reserve transfer to a sibling without an open outbound HRMP channel withdraws the assets,
+//! but the message can't be delivered. Used as the `MultiLocationsFilter` of `orml_xtokens`,
+//! [`OpenChannelDestinations`] rejects such transfers before anything is executed. Transfers to
+//! the relay chain go via UMP and need no channel.
+//!
+//! ## UMP message size limit
+//!
+//! Messages to the relay chain exceeding its size limit would only fail once they're submitted.
+//! Wrapping the UMP router into an [`UmpSizeLimit`] rejects them when they are sent instead, with
+//! an [`Event::UmpMessageTooBig`]. The limit is the lower of the relay chain's one and an optional
+//! limit set by governance.
+//!
+//! ## Unsupported instruction policy
+//!
+//! By default, an inbound message fails at the first instruction our executor doesn't support.
+//! Governance can switch to skipping such instructions instead, if wrapping the `XcmExecutor` of
+//! the message queues into a [`SkipUnsupportedInstructions`]. Skipped instructions are recorded
+//! with an [`Event::UnsupportedInstructionsSkipped`]. Only instructions which neither handle
+//! assets, fees nor the origin are ever skipped (see [`skippable_instruction`]), so a message
+//! lacking fee payment fails just as before.
+//!
+//! ## Transact call policy
+//!
+//! Wrapping the `CallDispatcher` of the XCM executor into a [`TransactCallPolicy`] lets
+//! governance block whole pallets or single calls from being dispatched by `Transact`, regardless
+//! of the origin. Blocked calls fail with [`Error::TransactCallBlocked`].
+//!
+//! ## Scheduled calls
+//!
+//! The relay chain and siblings may schedule a call for a later block with
+//! [`Pallet::schedule_call`], e.g. by a `Transact`, to be dispatched from their origin. The call
+//! is checked against the blocked calls and [`Config::ScheduledCallFilter`] both when it is
+//! scheduled and when it is dispatched, so governance can still block a call which has been
+//! scheduled already. Each origin may have [`Config::MaxScheduledPerOrigin`] calls pending, and
+//! at most [`Config::MaxScheduledPerBlock`] calls may be scheduled this way for the same block,
+//! such that the agenda of the scheduler keeps room for the enactments of governance.
+//!
+//! ## Fee rounding
+//!
+//! Converting weight into a fee rarely results in a whole amount. The [`RoundingTrader`] rounds
+//! the fees it charges as configured by governance ([`FeeRounding`], down by default). Refunds
+//! always round down and never exceed what has been charged, whatever the mode.
+//!
+//! ## Fee currency priority
+//!
+//! A message may carry several assets the [`MultiCurrencyTrader`] accepts as fees. It pays in
+//! the first currency whose amount in the payment suffices, trying the currencies prioritized by
+//! governance first, in their order, and the remaining ones after, in the runtime's order (see
+//! [`Pallet::fee_currency_order`]). An insufficient amount of a currency is no error, the trader
+//! falls through to the next currency.
+//!
+//! ## DMP processing order
+//!
+//! Downward messages are processed in the order they arrive by default. With
+//! [`DmpOrder::Priority`], a [`PrioritizedDmp`] wrapped around the DMP queue hands over the
+//! messages of relay governance and the relay's system notifications (see [`is_priority_dmp`])
+//! before the other messages received in the same block. Messages which don't fit into the block
+//! are queued by the DMP queue and processed in order before any later message, so a low-priority
+//! message is never overtaken by more than the messages of its own batch.
+//!
+//! ## Inbound message metering
+//!
+//! The node can't observe how much weight the execution of an inbound message consumes compared
+//! to what the `Weigher` of the executor charged for it. A [`MeterInbound`] wrapped around the
+//! executor of the message queues records both with an [`Event::InboundMessageMetered`] for each
+//! message it executes, which the node turns into metrics. Messages the barrier rejects consume
+//! nothing and aren't metered.
+//!
+//! ## Holding register cap
+//!
+//! A crafted message could load amounts close to `u128::MAX` into the holding register, risking
+//! overflows in the arithmetic done on them later (fees, swaps, ...). With caps set by governance
+//! for single assets, the [`DenyExcessiveHolding`] barrier rejects messages which load more than
+//! the cap of an asset, summed over all of their instructions loading assets, nested ones
+//! included. Each asset has its own cap, as their amounts are of different magnitudes. Amounts
+//! are summed up by the canonical location of their asset, such that loading an asset under
+//! several of its locations (e.g. relative and absolute) doesn't multiply its cap. There are no
+//! caps by default.
+//!
+//! ## Minimum execution weight
+//!
+//! Paying for a message of next to no weight is cheap, so such messages could be used to spam
+//! events. With a minimum set by governance, the [`DenyTrivialWeight`] barrier rejects messages
+//! weighing less, whatever they pay. Messages consisting of query responses and version
+//! subscriptions only are legitimately tiny and always pass (see [`is_exempt_from_min_weight`]).
+//! There is no minimum by default.
+//!
+//! ## Default destination weight
+//!
+//! `orml_xtokens` buys the weight the caller passes at the destination of a transfer. Governance
+//! can set a default weight for transfers to the relay chain and to siblings, which is bought
+//! instead of `Unlimited`, i.e. if the caller didn't specify a weight. A weight limit given by the
+//! caller is always honored. Used as the `XcmExecutor` of `orml_xtokens`,
+//! [`WithDefaultDestWeight`] applies the defaults to the messages sent to other chains.
+//!
+//! ## Minimum XCM fees
+//!
+//! `orml_xtokens` needs to know the minimum fee of a reserve chain to route a transfer through it
+//! when the fee is paid in an asset of another reserve. Governance can set the minimum fee of a
+//! reserve chain or remove it (see [`MinXcmFees`]), falling back to the runtime's
+//! [`Config::DefaultMinXcmFee`] for reserves it hasn't configured. [`Pallet::min_xcm_fee`] is
+//! meant to back the `MinXcmFee` of `orml_xtokens`.
+//!
+//! ## XCM fee split
+//!
+//! With [`SplitFees`] as the `FeeManager` of the executor, and as the revenue handler of its
+//! trader, the fees the XCM executor collects go to a designated account, e.g. the treasury.
+//! Governance can split them among accounts and a burnt fraction by shares summing up to 100%
+//! instead (see [`FeeSplit`]). Each share is rounded down, what the rounding leaves over goes to
+//! the designated account. Origins can be exempt from the fees the `FeeManager` handles.
+//!
+//! ## Trapped asset rescue
+//!
+//! Assets left in the holding register of a failed message are trapped by `pallet_xcm`, and only
+//! their origin can claim them. If that origin can't send messages anymore, governance can rescue
+//! the assets of a single trap with [`Pallet::rescue_trapped_assets`], which deposits them into an
+//! account of its choice and records the rescue with an [`Event::TrappedAssetsRescued`].
+//!
+//! ## Trapped asset index
+//!
+//! `pallet_xcm` only stores the hashes of trapped assets, their origin has to remember what has
+//! been trapped to claim it. Used as both the `AssetTrap` and the `AssetClaims` of the executor,
+//! an [`IndexTrappedAssets`] keeps the trapped assets by origin in [`TrappedAssetIndex`] until
+//! they are claimed, such that [`Pallet::trapped_assets`] can list them. Assets trapped before
+//! the index has been set up aren't listed.
+//!
+//! ## Message export
+//!
+//! An `ExportMessage` hands a message to the `MessageExporter` of the executor, to be bridged to
+//! another consensus system. Governance sets the network messages may be exported to (see
+//! [`ExportNetwork`]), there is none by default. Used as the `MessageExporter`, a
+//! [`BlobExporter`] encodes the messages for that network as blobs like `HaulBlobExporter` does,
+//! and hauls them with an [`Event::MessageExported`], for a bridge relayer to pick up. The export
+//! fee is charged by the executor through its `FeeManager`.
+//!
+//! ## XCM parameters
+//!
+//! The parameters of the runtime's XCM configuration which need to follow prices and other
+//! chains are kept in the storage of this pallet, such that governance can adjust them without a
+//! runtime upgrade. Each has a setter for the admin origin depositing an event, lists are bounded,
+//! and a parameter governance hasn't set falls back to a default of the runtime.
+//!
+//! - [`XcmFeeReceiver`], the account the XCM fees go to, see [`Pallet::xcm_fee_receiver`].
+//! - [`FeeWaivedSiblings`], which pay no XCM fees like the relay chain, see [`FeeWaivedOrigins`].
+//! - [`RelayNetwork`], the network of the relay chain our universal location is in. Chain specs
+//!   for another relay network than the runtime's default set it in genesis.
+//! - [`RemainderPolicy`], where the runtime's asset trap puts the assets a message leaves over.
+//! - [`ScheduleDelayLimit`], how far ahead [`Pallet::schedule_call`] schedules calls. Governance
+//!   may only lower the runtime's [`Config::MaxScheduleDelay`].
+//! - [`FeePricesPerRefTime`], the price per unit of `ref_time` of each fee currency, see
+//!   [`Pallet::fee_per_ref_time`]. It follows the exchange rate of the currency to ours.
+//! - [`SiblingDeliveryFee`], the base and per byte fee of delivering a message to a sibling
+//!   parachain.
+//! - [`XcmConversionWeight`], what the runtime's weigher charges for converting an asset id of a
+//!   message, up to [`Config::MaxXcmConversionWeight`].
+//! - [`UniversalAliases`], the bridges whose messages may take the origin of a bridged network
+//!   with `UniversalOrigin`, see [`ListedUniversalAliases`].
+//! - [`ExportFee`], the amount the runtime charges for exporting a message, see
+//!   [`Pallet::export_fee`].
+//! - [`AssetsForTransferLimit`], how many assets a transfer of the runtime's token pallet may
+//!   carry, between one and [`Config::MaxAssetsForTransferLimit`].
+//! - [`TrustedLockers`], the chains which may note assets they locked for an owner here, see
+//!   [`ListedTrustedLockers`].
+//! - [`RelayChainCallIndices`], the pallet and call indices the runtime encodes relay chain
+//!   calls with, should the relay chain reorder its pallets or calls.
+//!
+//! ## Version negotiation across migrations
+//!
+//! The [`migrations`] module exports and re-imports the XCM version negotiation state of
+//! `pallet_xcm`, such that a migration doesn't make us renegotiate with every chain.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{DecodeLimit, Encode};
+use cumulus_primitives_core::{
+	relay_chain::BlockNumber as RelayBlockNumber, ChannelStatus, DmpMessageHandler, GetChannelInfo,
+	ParaId,
+};
+use frame_support::{
+	dispatch::{
+		extract_actual_weight, CallMetadata, DispatchErrorWithPostInfo, GetCallMetadata,
+		GetDispatchInfo, PostDispatchInfo,
+	},
+	storage::unhashed,
+	traits::{
+		schedule::{v3::Anon as ScheduleAnon, DispatchTime, LOWEST_PRIORITY},
+		ConstU32, Contains, ContainsPair, Get, OriginTrait, PalletInfoAccess, ProcessMessageError,
+		QueryPreimage, StorePreimage,
+	},
+	weights::Weight,
+	BoundedVec,
+};
+use sp_core::H256;
+use sp_runtime::{
+	helpers_128bit::multiply_by_rational_with_rounding,
+	traits::{BlakeTwo256, Convert, Dispatchable, Hash, Zero},
+	Perbill, Rounding,
+};
+use sp_std::{collections::btree_map::BTreeMap, marker::PhantomData, prelude::*};
+use xcm::{
+	latest::{prelude::*, XcmHash},
+	VersionedMultiAssets, VersionedMultiLocation, VersionedXcm, WrapVersion, MAX_XCM_DECODE_DEPTH,
+};
+use xcm_builder::{BridgeMessage, TakeRevenue};
+use xcm_executor::{
+	traits::{
+		CallDispatcher, ClaimAssets, DropAssets, ExportXcm, FeeManager, FeeReason, ShouldExecute,
+		TransactAsset, WeightTrader,
+	},
+	Assets,
+};
+
+pub use pallet::*;
+pub use weights::WeightInfo;
+
+#[cfg(feature = "runtime-benchmarks")]
+pub mod benchmarking;
+pub mod migrations;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+const LOG_TARGET: &str = "xcm::admin";
+
+/// The calls which may be scheduled with [`Pallet::schedule_call`].
+pub type CallOf<T> = <T as Config>::RuntimeCall;
+
+/// The name of a pallet or call in the runtime metadata, as stored in [`TransactBlocked`].
+pub type CallName = BoundedVec<u8, ConstU32<64>>;
+
+/// The origins the scheduler dispatches calls from.
+pub type PalletsOriginOf<T> =
+	<<T as frame_system::Config>::RuntimeOrigin as OriginTrait>::PalletsOrigin;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(0);
+
+	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// Origin allowed to change the parameters of this pallet.
+		type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// The `pallet_xcm` instance of the runtime, whose version discovery queue is managed.
+		type XcmPallet: PalletInfoAccess;
+
+		/// Maps the different locations of an asset (e.g. relative and absolute) to a single one,
+		/// under which its reserve transfer toggle is stored.
+		type CanonicalAssetLocation: Convert<MultiLocation, MultiLocation>;
+
+		/// The relay chain's maximum UMP message size, if known.
+		type RelayMaxUmpMessageSize: Get<Option<u32>>;
+
+		/// The status of our outbound HRMP channels.
+		type ChannelInfo: GetChannelInfo;
+
+		/// The currencies the [`MultiCurrencyTrader`] accepts as fees.
+		type FeeCurrencyId: Parameter + MaxEncodedLen;
+
+		/// Maximum number of currencies governance may prioritize.
+		#[pallet::constant]
+		type MaxFeeCurrencies: Get<u32>;
+
+		/// The minimum fee of the reserve chains governance hasn't set one for.
+		type DefaultMinXcmFee: Convert<MultiLocation, Option<u128>>;
+
+		/// Claims the assets trapped by the XCM executor, i.e. `pallet_xcm`.
+		type AssetClaims: ClaimAssets;
+
+		/// Deposits the trapped assets rescued by governance.
+		type AssetTransactor: TransactAsset;
+
+		/// The location of the account rescued trapped assets are deposited into.
+		type AccountIdToLocation: Convert<Self::AccountId, MultiLocation>;
+
+		/// Maximum number of destinations XCM fees may be split among.
+		#[pallet::constant]
+		type MaxFeeDestinations: Get<u32>;
+
+		/// The calls which may be scheduled with [`Pallet::schedule_call`].
+		type RuntimeCall: Parameter
+			+ Dispatchable<
+				RuntimeOrigin = <Self as frame_system::Config>::RuntimeOrigin,
+				PostInfo = PostDispatchInfo,
+			> + GetDispatchInfo
+			+ GetCallMetadata
+			+ From<Call<Self>>;
+
+		/// The origins which may schedule calls, e.g. the relay chain and siblings, by their
+		/// location.
+		type ScheduleOrigin: EnsureOrigin<Self::RuntimeOrigin, Success = MultiLocation>;
+
+		/// Schedules the calls of [`Pallet::schedule_call`].
+		type Scheduler: ScheduleAnon<BlockNumberFor<Self>, CallOf<Self>, PalletsOriginOf<Self>>;
+
+		/// Stores the scheduled calls which are too big to be kept in the agenda.
+		type Preimages: QueryPreimage + StorePreimage;
+
+		/// The calls which may be scheduled, checked when they are dispatched as well.
+		type ScheduledCallFilter: Contains<CallOf<Self>>;
+
+		/// Maximum number of calls an origin may have scheduled and not yet dispatched.
+		#[pallet::constant]
+		type MaxScheduledPerOrigin: Get<u32>;
+
+		/// Maximum number of calls which may be scheduled for the same block, by all origins.
+		#[pallet::constant]
+		type MaxScheduledPerBlock: Get<u32>;
+
+		/// How many blocks ahead a call may be scheduled, unless governance lowered it in
+		/// [`ScheduleDelayLimit`].
+		#[pallet::constant]
+		type MaxScheduleDelay: Get<BlockNumberFor<Self>>;
+
+		/// The account the XCM fees go to, unless governance set [`XcmFeeReceiver`].
+		type DefaultXcmFeeReceiver: Get<Self::AccountId>;
+
+		/// Maximum number of siblings governance may waive the XCM fees of.
+		#[pallet::constant]
+		type MaxFeeWaivedSiblings: Get<u32>;
+
+		/// The network of the relay chain, unless set in [`RelayNetwork`].
+		type DefaultRelayNetwork: Get<NetworkId>;
+
+		/// The price (numerator and denominator) per unit of `ref_time` of the fee currencies
+		/// governance hasn't set one for in [`FeePricesPerRefTime`], `None` for currencies which
+		/// can't pay fees.
+		type DefaultFeePerRefTime: Convert<Self::FeeCurrencyId, Option<(u128, u128)>>;
+
+		/// The fee of delivering a message to a sibling parachain, unless governance set
+		/// [`SiblingDeliveryFee`].
+		type DefaultSiblingDeliveryFee: Get<DeliveryFee>;
+
+		/// The weight of converting an asset id of a message, unless governance set
+		/// [`XcmConversionWeight`].
+		type DefaultXcmConversionWeight: Get<Weight>;
+
+		/// The most governance may set [`XcmConversionWeight`] to.
+		#[pallet::constant]
+		type MaxXcmConversionWeight: Get<Weight>;
+
+		/// Maximum number of bridges in [`UniversalAliases`].
+		#[pallet::constant]
+		type MaxUniversalAliases: Get<u32>;
+
+		/// The fee for exporting a message, unless governance set [`ExportFee`].
+		type DefaultExportFee: Get<u128>;
+
+		/// How many assets a transfer may carry, unless governance set [`AssetsForTransferLimit`].
+		type DefaultAssetsForTransferLimit: Get<u32>;
+
+		/// The most governance may set [`AssetsForTransferLimit`] to.
+		#[pallet::constant]
+		type MaxAssetsForTransferLimit: Get<u32>;
+
+		/// The chains which may lock assets for use here, with the assets they may lock, unless
+		/// governance set [`TrustedLockers`].
+		type DefaultTrustedLockers: Get<Vec<(MultiLocation, MultiAssetFilter)>>;
+
+		/// Maximum number of chains in [`TrustedLockers`].
+		#[pallet::constant]
+		type MaxTrustedLockers: Get<u32>;
+
+		/// The pallet and call indices of the relay chain calls the runtime encodes.
+		type RelayCallIndices: Parameter + MaxEncodedLen;
+
+		/// The relay chain call indices, unless governance set [`RelayChainCallIndices`].
+		type DefaultRelayCallIndices: Get<Self::RelayCallIndices>;
+
+		/// Provides the values of the runtime the benchmarks depend on.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: benchmarking::BenchmarkHelper<Self::FeeCurrencyId, CallOf<Self>>;
+
+		type WeightInfo: WeightInfo;
+	}
+
+	/// Assets whose reserve transfers are disabled, by their canonical location.
+	///
+	/// Keyed by the location in the latest XCM version, the keys need to be migrated along with
+	/// it.
+	#[pallet::storage]
+	pub type ReserveTransferDisabled<T> =
+		StorageMap<_, Blake2_128Concat, MultiLocation, (), OptionQuery>;
+
+	/// Whether all outbound reserve transfers are paused, whatever the toggles of their assets.
+	#[pallet::storage]
+	#[pallet::getter(fn reserve_transfers_paused)]
+	pub type ReserveTransfersPaused<T> = StorageValue<_, bool, ValueQuery>;
+
+	/// Pallets, or single calls of them, which must not be dispatched by `Transact`.
+	///
+	/// Keyed by the pallet name and the call name, `None` for the whole pallet.
+	#[pallet::storage]
+	pub type TransactBlocked<T> =
+		StorageMap<_, Blake2_128Concat, (CallName, Option<CallName>), (), OptionQuery>;
+
+	/// Maximum size of messages to the relay chain set by governance, on top of the relay chain's
+	/// own limit.
+	#[pallet::storage]
+	#[pallet::getter(fn max_ump_message_size)]
+	pub type MaxUmpMessageSize<T> = StorageValue<_, u32, OptionQuery>;
+
+	/// How inbound messages with unsupported instructions are handled.
+	#[derive(
+		Clone, Copy, Default, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen,
+	)]
+	pub enum UnsupportedInstructionPolicy {
+		/// Leave the message as it is, it fails at the unsupported instruction.
+		#[default]
+		FailFast,
+		/// Remove unsupported instructions before execution, if they may be skipped.
+		Skip,
+	}
+
+	/// How inbound messages with unsupported instructions are handled.
+	#[pallet::storage]
+	#[pallet::getter(fn unsupported_instruction_policy)]
+	pub type UnsupportedInstructions<T> = StorageValue<_, UnsupportedInstructionPolicy, ValueQuery>;
+
+	/// How fees are rounded to whole amounts.
+	#[derive(
+		Clone, Copy, Default, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen,
+	)]
+	pub enum FeeRounding {
+		/// Round down, in favor of the payer.
+		#[default]
+		Down,
+		/// Round up, in favor of the chain.
+		Up,
+		/// Round to the nearest amount, halves up.
+		Nearest,
+	}
+
+	/// How the fees charged by the [`RoundingTrader`] are rounded.
+	#[pallet::storage]
+	#[pallet::getter(fn fee_rounding)]
+	pub type FeeRoundingMode<T> = StorageValue<_, FeeRounding, ValueQuery>;
+
+	/// Maximum amount of an asset a message may load into the holding register, by the canonical
+	/// location of the asset.
+	///
+	/// Keyed by the location in the latest XCM version, the keys need to be migrated along with
+	/// it.
+	#[pallet::storage]
+	pub type MaxHoldingAmounts<T> =
+		StorageMap<_, Blake2_128Concat, MultiLocation, u128, OptionQuery>;
+
+	/// Minimum weight of a message admitted by the [`DenyTrivialWeight`] barrier.
+	#[pallet::storage]
+	#[pallet::getter(fn min_execution_weight)]
+	pub type MinExecutionWeight<T> = StorageValue<_, Weight, OptionQuery>;
+
+	/// The currencies the [`MultiCurrencyTrader`] tries first, in order.
+	#[pallet::storage]
+	#[pallet::getter(fn fee_currency_priority)]
+	pub type FeeCurrencyPriority<T: Config> =
+		StorageValue<_, BoundedVec<T::FeeCurrencyId, T::MaxFeeCurrencies>, ValueQuery>;
+
+	/// The order in which downward messages are processed.
+	#[derive(
+		Clone, Copy, Default, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen,
+	)]
+	pub enum DmpOrder {
+		/// In the order they arrive.
+		#[default]
+		Fifo,
+		/// Governance and system messages first, see [`is_priority_dmp`].
+		Priority,
+	}
+
+	/// The order in which the [`PrioritizedDmp`] hands over downward messages.
+	#[pallet::storage]
+	#[pallet::getter(fn dmp_order)]
+	pub type DmpProcessingOrder<T> = StorageValue<_, DmpOrder, ValueQuery>;
+
+	/// The kinds of chains a default destination weight can be set for.
+	#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub enum DestinationKind {
+		/// The relay chain.
+		Relay,
+		/// A sibling parachain.
+		Sibling,
+	}
+
+	impl DestinationKind {
+		/// The kind of the chain `dest`, if it is the relay chain or a sibling.
+		pub fn of(dest: &MultiLocation) -> Option<Self> {
+			match dest {
+				MultiLocation { parents: 1, interior: Here } => Some(Self::Relay),
+				MultiLocation { parents: 1, interior: X1(Parachain(_)) } => Some(Self::Sibling),
+				_ => None,
+			}
+		}
+	}
+
+	/// The weight bought at a destination of a kind by [`WithDefaultDestWeight`], if the sender
+	/// doesn't limit it.
+	#[pallet::storage]
+	#[pallet::getter(fn default_dest_weight)]
+	pub type DefaultDestWeight<T> =
+		StorageMap<_, Twox64Concat, DestinationKind, Weight, OptionQuery>;
+
+	/// Where a share of the XCM fees goes.
+	#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+	pub enum FeeDestination<AccountId> {
+		/// Deposited into an account.
+		Account(AccountId),
+		/// Burnt.
+		Burn,
+	}
+
+	/// The shares of the XCM fees handled by [`SplitFees`] by destination, summing up to 100%.
+	/// Fees go to the remainder account of [`SplitFees`] if empty.
+	#[pallet::storage]
+	#[pallet::getter(fn fee_split)]
+	pub type FeeSplit<T: Config> = StorageValue<
+		_,
+		BoundedVec<(FeeDestination<T::AccountId>, Perbill), T::MaxFeeDestinations>,
+		ValueQuery,
+	>;
+
+	/// The minimum fee of a reserve chain, in the fee asset of transfers through it, overriding
+	/// [`Config::DefaultMinXcmFee`]. `None` if governance removed the minimum fee of the reserve.
+	///
+	/// Keyed by the location in the latest XCM version, the keys need to be migrated along with
+	/// it.
+	#[pallet::storage]
+	pub type MinXcmFees<T> =
+		StorageMap<_, Blake2_128Concat, MultiLocation, Option<u128>, OptionQuery>;
+
+	/// The consensus system messages may be exported to by a [`BlobExporter`], none if unset.
+	#[pallet::storage]
+	#[pallet::getter(fn export_network)]
+	pub type ExportNetwork<T> = StorageValue<_, NetworkId, OptionQuery>;
+
+	/// The fee for exporting a message, in the asset the runtime charges it in, overriding
+	/// [`Config::DefaultExportFee`].
+	#[pallet::storage]
+	pub type ExportFee<T> = StorageValue<_, u128, OptionQuery>;
+
+	/// How many assets a transfer may carry, overriding [`Config::DefaultAssetsForTransferLimit`].
+	#[pallet::storage]
+	pub type AssetsForTransferLimit<T> = StorageValue<_, u32, OptionQuery>;
+
+	/// The pallet and call indices of the relay chain calls the runtime encodes, overriding
+	/// [`Config::DefaultRelayCallIndices`].
+	#[pallet::storage]
+	pub type RelayChainCallIndices<T: Config> = StorageValue<_, T::RelayCallIndices, OptionQuery>;
+
+	/// The chains which may lock assets for use here, as pairs of the locker and the assets it
+	/// may lock, overriding [`Config::DefaultTrustedLockers`].
+	///
+	/// In the latest XCM version, the locations need to be migrated along with it.
+	#[pallet::storage]
+	pub type TrustedLockers<T: Config> = StorageValue<
+		_,
+		BoundedVec<(MultiLocation, MultiAssetFilter), T::MaxTrustedLockers>,
+		OptionQuery,
+	>;
+
+	/// The assets trapped by each origin, by the hash `pallet_xcm` traps them under, with the
+	/// number of times they have been trapped and not claimed yet.
+	///
+	/// In the latest XCM version, the origins and assets need to be migrated along with it.
+	#[pallet::storage]
+	pub type TrappedAssetIndex<T> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		MultiLocation,
+		Identity,
+		H256,
+		(MultiAssets, u32),
+		OptionQuery,
+	>;
+
+	/// The blocks the calls an origin has scheduled are due at, see [`Pallet::schedule_call`].
+	/// Blocks which have passed may not have been pruned yet.
+	///
+	/// Keyed by the location in the latest XCM version, the keys need to be migrated along with
+	/// it.
+	#[pallet::storage]
+	pub type ScheduledCalls<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		MultiLocation,
+		BoundedVec<BlockNumberFor<T>, T::MaxScheduledPerOrigin>,
+		ValueQuery,
+	>;
+
+	/// The number of calls scheduled for a block with [`Pallet::schedule_call`].
+	#[pallet::storage]
+	pub type ScheduledCallsAt<T: Config> =
+		StorageMap<_, Twox64Concat, BlockNumberFor<T>, u32, ValueQuery>;
+
+	/// How many blocks ahead a call may be scheduled, at most [`Config::MaxScheduleDelay`].
+	#[pallet::storage]
+	pub type ScheduleDelayLimit<T: Config> = StorageValue<_, BlockNumberFor<T>, OptionQuery>;
+
+	/// The price per unit of `ref_time` of a fee currency, overriding
+	/// [`Config::DefaultFeePerRefTime`]. `None` if governance took the currency off the fee
+	/// currencies.
+	#[pallet::storage]
+	pub type FeePricesPerRefTime<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::FeeCurrencyId, Option<(u128, u128)>, OptionQuery>;
+
+	/// The account the XCM fees go to, overriding [`Config::DefaultXcmFeeReceiver`].
+	#[pallet::storage]
+	pub type XcmFeeReceiver<T: Config> = StorageValue<_, T::AccountId, OptionQuery>;
+
+	/// The parachain ids of the siblings paying no XCM fees, sorted, see [`FeeWaivedOrigins`].
+	#[pallet::storage]
+	#[pallet::getter(fn fee_waived_siblings)]
+	pub type FeeWaivedSiblings<T: Config> =
+		StorageValue<_, BoundedVec<u32, T::MaxFeeWaivedSiblings>, ValueQuery>;
+
+	/// Where the assets left in the holding register at the end of a message go, e.g. the unused
+	/// fees of a message failing after `BuyExecution`. Up to the `AssetTrap` of the runtime.
+	#[derive(
+		Clone, Copy, Default, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen,
+	)]
+	pub enum RemainderDestination {
+		/// Trapped by `pallet_xcm`, the origin can claim them with `ClaimAsset`.
+		#[default]
+		Trap,
+		/// Deposited into the account of the origin here, trapped if it has none.
+		RefundToOrigin,
+		/// Deposited into the treasury.
+		Treasury,
+	}
+
+	/// The fee of delivering a message, in the asset the router charges it in.
+	#[derive(
+		Clone, Copy, Default, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, MaxEncodedLen,
+	)]
+	pub struct DeliveryFee {
+		/// Charged for any message.
+		pub base: u128,
+		/// Charged per byte of the encoded message.
+		pub per_byte: u128,
+	}
+
+	/// The fee of delivering a message to a sibling parachain, overriding
+	/// [`Config::DefaultSiblingDeliveryFee`].
+	#[pallet::storage]
+	pub type SiblingDeliveryFee<T> = StorageValue<_, DeliveryFee, OptionQuery>;
+
+	/// The weight of converting an asset id of a message, overriding
+	/// [`Config::DefaultXcmConversionWeight`].
+	#[pallet::storage]
+	pub type XcmConversionWeight<T> = StorageValue<_, Weight, OptionQuery>;
+
+	/// The bridges which may alias into a global consensus system with `UniversalOrigin`, as
+	/// pairs of the bridge location and the bridged network.
+	///
+	/// Keyed by the location in the latest XCM version, the locations need to be migrated along
+	/// with it.
+	#[pallet::storage]
+	#[pallet::getter(fn universal_aliases)]
+	pub type UniversalAliases<T: Config> =
+		StorageValue<_, BoundedVec<(MultiLocation, NetworkId), T::MaxUniversalAliases>, ValueQuery>;
+
+	/// Where the assets a message leaves over go.
+	#[pallet::storage]
+	#[pallet::getter(fn remainder_policy)]
+	pub type RemainderPolicy<T> = StorageValue<_, RemainderDestination, ValueQuery>;
+
+	/// The network of the relay chain, overriding [`Config::DefaultRelayNetwork`]. Set in the
+	/// genesis of chains on another relay network.
+	#[pallet::storage]
+	pub type RelayNetwork<T> = StorageValue<_, NetworkId, OptionQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// Entries of a destination have been removed from the version discovery queue.
+		VersionDiscoveryEntriesCleared { dest: VersionedMultiLocation, removed: u32 },
+		/// Reserve transfers of an asset have been enabled or disabled.
+		ReserveTransferToggled { asset: VersionedMultiLocation, enabled: bool },
+		/// The maximum size of messages to the relay chain has been set or removed.
+		MaxUmpMessageSizeSet { limit: Option<u32> },
+		/// A message to the relay chain exceeded the size limit and has not been sent.
+		UmpMessageTooBig { size: u32, limit: u32 },
+		/// A pallet or call has been blocked or unblocked from being dispatched by `Transact`.
+		TransactBlockedSet { pallet_name: Vec<u8>, function_name: Option<Vec<u8>>, blocked: bool },
+		/// The handling of unsupported instructions has been changed.
+		UnsupportedInstructionPolicySet { policy: UnsupportedInstructionPolicy },
+		/// Unsupported instructions have been removed from an inbound message, by name.
+		UnsupportedInstructionsSkipped { instructions: Vec<Vec<u8>> },
+		/// The rounding of XCM fees has been changed.
+		FeeRoundingSet { rounding: FeeRounding },
+		/// The priority of the fee currencies has been changed.
+		FeeCurrencyPrioritySet { priority: Vec<T::FeeCurrencyId> },
+		/// The maximum amount of an asset in the holding register has been set or removed.
+		MaxHoldingAmountSet { asset: VersionedMultiLocation, limit: Option<u128> },
+		/// The minimum weight of an inbound message has been set or removed.
+		MinExecutionWeightSet { weight: Option<Weight> },
+		/// The default weight bought at destinations of a kind has been set or removed.
+		DefaultDestWeightSet { kind: DestinationKind, weight: Option<Weight> },
+		/// The processing order of downward messages has been changed.
+		DmpOrderSet { order: DmpOrder },
+		/// An inbound message from `origin` has been executed, consuming `consumed` of the
+		/// `charged` weight.
+		InboundMessageMetered { origin: VersionedMultiLocation, charged: Weight, consumed: Weight },
+		/// The split of the XCM fees has been changed, empty if fees are burnt.
+		FeeSplitSet { split: Vec<(FeeDestination<T::AccountId>, Perbill)> },
+		/// Governance rescued the assets `trapped_by` trapped under `ticket`.
+		TrappedAssetsRescued {
+			ticket: H256,
+			trapped_by: VersionedMultiLocation,
+			assets: VersionedMultiAssets,
+			beneficiary: T::AccountId,
+		},
+		/// The minimum fee of a reserve chain has been set, `None` if reset to the default.
+		MinXcmFeeSet { reserve: VersionedMultiLocation, fee: Option<Option<u128>> },
+		/// The network messages may be exported to has been set, `None` if exports are disabled.
+		ExportNetworkSet { network: Option<NetworkId> },
+		/// All outbound reserve transfers have been paused or resumed.
+		ReserveTransfersToggled { enabled: bool },
+		/// A message has been exported as `blob`, an encoded `BridgeMessage`, for a bridge
+		/// relayer to pick up.
+		MessageExported { message_id: XcmHash, blob: Vec<u8> },
+		/// `origin` has scheduled a call for block `when`.
+		CallScheduled { origin: VersionedMultiLocation, when: BlockNumberFor<T> },
+		/// The account the XCM fees go to has been set, `None` if reset to the default.
+		XcmFeeReceiverSet { receiver: Option<T::AccountId> },
+		/// The siblings paying no XCM fees have been set.
+		FeeWaivedSiblingsSet { siblings: Vec<u32> },
+		/// The network of the relay chain has been set, `None` if reset to the default.
+		RelayNetworkSet { network: Option<NetworkId> },
+		/// Where the assets a message leaves over go has been changed.
+		RemainderPolicySet { policy: RemainderDestination },
+		/// How many blocks ahead a call may be scheduled has been set, `None` if reset to the
+		/// maximum.
+		ScheduleDelayLimitSet { limit: Option<BlockNumberFor<T>> },
+		/// The price per unit of `ref_time` of a fee currency has been set, `None` if reset to
+		/// the default.
+		FeePerRefTimeSet { currency: T::FeeCurrencyId, price: Option<Option<(u128, u128)>> },
+		/// The fee of delivering a message to a sibling parachain has been set, `None` if reset
+		/// to the default.
+		SiblingDeliveryFeeSet { fee: Option<DeliveryFee> },
+		/// The weight of converting an asset id has been set, `None` if reset to the default.
+		XcmConversionWeightSet { weight: Option<Weight> },
+		/// The bridges which may alias into a global consensus system have been replaced.
+		UniversalAliasesSet { aliases: Vec<(VersionedMultiLocation, NetworkId)> },
+		/// The fee for exporting a message has been set, `None` if reset to the default.
+		ExportFeeSet { fee: Option<u128> },
+		/// How many assets a transfer may carry has been set, `None` if reset to the default.
+		AssetsForTransferLimitSet { limit: Option<u32> },
+		/// The chains which may lock assets for use here have been replaced, `None` if reset to
+		/// the default.
+		TrustedLockersSet { lockers: Option<Vec<(VersionedMultiLocation, MultiAssetFilter)>> },
+		/// The relay chain call indices have been set, `None` if reset to the default.
+		RelayCallIndicesSet { indices: Option<T::RelayCallIndices> },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The location could not be converted to the latest XCM version.
+		BadVersion,
+		/// The destination is not in the version discovery queue.
+		NotInVersionDiscoveryQueue,
+		/// The call must not be dispatched by `Transact`.
+		TransactCallBlocked,
+		/// There is no open outbound HRMP channel to the destination parachain.
+		NoOpenChannel,
+		/// A fee currency has been prioritized more than once.
+		DuplicateFeeCurrency,
+		/// The shares of the fee split don't sum up to 100%.
+		FeeSharesNotWhole,
+		/// The ticket is not the one of the given origin and assets.
+		TicketMismatch,
+		/// No assets are trapped under the ticket.
+		NoTrappedAssets,
+		/// The rescued assets could not be deposited into the beneficiary's account.
+		RescueDepositFailed,
+		/// The call is scheduled for the current block or further ahead than
+		/// [`Config::MaxScheduleDelay`].
+		InvalidScheduleTime,
+		/// The origin has [`Config::MaxScheduledPerOrigin`] calls pending already.
+		TooManyScheduledCalls,
+		/// [`Config::MaxScheduledPerBlock`] calls have been scheduled for the block already.
+		ScheduleFull,
+		/// The network is not one a relay chain can be in.
+		NotARelayNetwork,
+		/// The schedule delay exceeds [`Config::MaxScheduleDelay`].
+		ScheduleDelayTooLong,
+		/// The denominator of a price is zero.
+		ZeroPriceDenominator,
+		/// The weight exceeds [`Config::MaxXcmConversionWeight`].
+		XcmConversionWeightTooHigh,
+		/// A bridge would alias into the network of our relay chain.
+		AliasIntoOwnNetwork,
+		/// The limit is zero or exceeds [`Config::MaxAssetsForTransferLimit`].
+		InvalidAssetsForTransferLimit,
+		/// The pallet or call name is longer than a [`CallName`] may be.
+		NameTooLong,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(n: BlockNumberFor<T>) -> Weight {
+			ScheduledCallsAt::<T>::remove(n);
+			T::DbWeight::get().writes(1)
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Remove all entries of `dest` from `pallet_xcm`'s version discovery queue.
+		///
+		/// Meant to prune unreachable destinations. If `dest` is reachable, the next message to
+		/// it simply starts a new version discovery.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::WeightInfo::clear_version_discovery_queue())]
+		pub fn clear_version_discovery_queue(
+			origin: OriginFor<T>,
+			dest: Box<VersionedMultiLocation>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			let location: MultiLocation =
+				(*dest.clone()).try_into().map_err(|()| Error::<T>::BadVersion)?;
+
+			let mut queue = Self::version_discovery_queue();
+			let len = queue.len();
+			queue.retain(|(entry, _)| MultiLocation::try_from(entry.clone()) != Ok(location));
+			let removed = (len - queue.len()) as u32;
+			ensure!(removed > 0, Error::<T>::NotInVersionDiscoveryQueue);
+
+			// We only removed entries, so the queue still fits into `pallet_xcm`'s bound.
+			unhashed::put(&version_discovery_queue_key::<T>(), &queue);
+			Self::deposit_event(Event::VersionDiscoveryEntriesCleared { dest: *dest, removed });
+			Ok(())
+		}
+
+		/// Enable or disable reserve transfers of the asset at `asset`, in both directions.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::WeightInfo::set_reserve_transfer_enabled())]
+		pub fn set_reserve_transfer_enabled(
+			origin: OriginFor<T>,
+			asset: Box<VersionedMultiLocation>,
+			enabled: bool,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			let location: MultiLocation =
+				(*asset).try_into().map_err(|()| Error::<T>::BadVersion)?;
+			let asset = T::CanonicalAssetLocation::convert(location);
+
+			if enabled {
+				ReserveTransferDisabled::<T>::remove(asset);
+			} else {
+				ReserveTransferDisabled::<T>::insert(asset, ());
+			}
+			Self::deposit_event(Event::ReserveTransferToggled { asset: asset.into(), enabled });
+			Ok(())
+		}
+
+		/// Set the maximum size of messages to the relay chain, `None` to only apply the relay
+		/// chain's limit.
+		///
+		/// A limit above the relay chain's one has no effect.
+		#[pallet::call_index(3)]
+		#[pallet::weight(T::WeightInfo::set_max_ump_message_size())]
+		pub fn set_max_ump_message_size(
+			origin: OriginFor<T>,
+			limit: Option<u32>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			MaxUmpMessageSize::<T>::set(limit);
+			Self::deposit_event(Event::MaxUmpMessageSizeSet { limit });
+			Ok(())
+		}
+
+		/// Block or unblock the call `function_name` of the pallet `pallet_name` from being
+		/// dispatched by `Transact`. Without a call name, this applies to the whole pallet.
+		///
+		/// The names are the ones in the runtime metadata, e.g. `Balances` and `force_transfer`.
+		#[pallet::call_index(4)]
+		#[pallet::weight(T::WeightInfo::set_transact_blocked())]
+		pub fn set_transact_blocked(
+			origin: OriginFor<T>,
+			pallet_name: Vec<u8>,
+			function_name: Option<Vec<u8>>,
+			blocked: bool,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			let key = (
+				CallName::try_from(pallet_name.clone()).map_err(|_| Error::<T>::NameTooLong)?,
+				function_name
+					.clone()
+					.map(CallName::try_from)
+					.transpose()
+					.map_err(|_| Error::<T>::NameTooLong)?,
+			);
+			if blocked {
+				TransactBlocked::<T>::insert(key, ());
+			} else {
+				TransactBlocked::<T>::remove(key);
+			}
+			Self::deposit_event(Event::TransactBlockedSet { pallet_name, function_name, blocked });
+			Ok(())
+		}
+
+		/// Set whether unsupported instructions in inbound messages fail the message or are
+		/// skipped.
+		#[pallet::call_index(5)]
+		#[pallet::weight(T::WeightInfo::set_unsupported_instruction_policy())]
+		pub fn set_unsupported_instruction_policy(
+			origin: OriginFor<T>,
+			policy: UnsupportedInstructionPolicy,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			UnsupportedInstructions::<T>::put(policy);
+			Self::deposit_event(Event::UnsupportedInstructionPolicySet { policy });
+			Ok(())
+		}
+
+		/// Set how the fees charged for XCM execution are rounded. Refunds always round down.
+		#[pallet::call_index(6)]
+		#[pallet::weight(T::WeightInfo::set_fee_rounding())]
+		pub fn set_fee_rounding(origin: OriginFor<T>, rounding: FeeRounding) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			FeeRoundingMode::<T>::put(rounding);
+			Self::deposit_event(Event::FeeRoundingSet { rounding });
+			Ok(())
+		}
+
+		/// Set the order in which downward messages are processed.
+		#[pallet::call_index(7)]
+		#[pallet::weight(T::WeightInfo::set_dmp_order())]
+		pub fn set_dmp_order(origin: OriginFor<T>, order: DmpOrder) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			DmpProcessingOrder::<T>::put(order);
+			Self::deposit_event(Event::DmpOrderSet { order });
+			Ok(())
+		}
+
+		/// Set the currencies the [`MultiCurrencyTrader`] tries first to pay fees, in order. The
+		/// currencies not listed are tried after them.
+		#[pallet::call_index(10)]
+		#[pallet::weight(T::WeightInfo::set_fee_currency_priority(priority.len() as u32))]
+		pub fn set_fee_currency_priority(
+			origin: OriginFor<T>,
+			priority: BoundedVec<T::FeeCurrencyId, T::MaxFeeCurrencies>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			for (i, currency) in priority.iter().enumerate() {
+				ensure!(!priority[..i].contains(currency), Error::<T>::DuplicateFeeCurrency);
+			}
+			FeeCurrencyPriority::<T>::put(&priority);
+			Self::deposit_event(Event::FeeCurrencyPrioritySet { priority: priority.into_inner() });
+			Ok(())
+		}
+
+		/// Set the maximum amount of the asset at `asset` a message may load into the holding
+		/// register, `None` for no limit.
+		#[pallet::call_index(11)]
+		#[pallet::weight(T::WeightInfo::set_max_holding_amount())]
+		pub fn set_max_holding_amount(
+			origin: OriginFor<T>,
+			asset: Box<VersionedMultiLocation>,
+			limit: Option<u128>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			let location: MultiLocation =
+				(*asset).try_into().map_err(|()| Error::<T>::BadVersion)?;
+			let asset = T::CanonicalAssetLocation::convert(location);
+
+			MaxHoldingAmounts::<T>::set(asset, limit);
+			Self::deposit_event(Event::MaxHoldingAmountSet { asset: asset.into(), limit });
+			Ok(())
+		}
+
+		/// Set the minimum weight of a message to be admitted, `None` for no minimum.
+		#[pallet::call_index(12)]
+		#[pallet::weight(T::WeightInfo::set_min_execution_weight())]
+		pub fn set_min_execution_weight(
+			origin: OriginFor<T>,
+			weight: Option<Weight>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			MinExecutionWeight::<T>::set(weight);
+			Self::deposit_event(Event::MinExecutionWeightSet { weight });
+			Ok(())
+		}
+
+		/// Set the weight bought at destinations of `kind` if the sender doesn't limit it, `None`
+		/// to buy as much as the destination requires.
+		#[pallet::call_index(13)]
+		#[pallet::weight(T::WeightInfo::set_default_dest_weight())]
+		pub fn set_default_dest_weight(
+			origin: OriginFor<T>,
+			kind: DestinationKind,
+			weight: Option<Weight>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			DefaultDestWeight::<T>::set(kind, weight);
+			Self::deposit_event(Event::DefaultDestWeightSet { kind, weight });
+			Ok(())
+		}
+
+		/// Split the XCM fees among destinations by shares summing up to 100%, empty to deposit
+		/// them into the remainder account of [`SplitFees`].
+		#[pallet::call_index(14)]
+		#[pallet::weight(T::WeightInfo::set_fee_split(split.len() as u32))]
+		pub fn set_fee_split(
+			origin: OriginFor<T>,
+			split: BoundedVec<(FeeDestination<T::AccountId>, Perbill), T::MaxFeeDestinations>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			let total: u64 = split.iter().map(|(_, share)| u64::from(share.deconstruct())).sum();
+			ensure!(
+				split.is_empty() || total == u64::from(Perbill::one().deconstruct()),
+				Error::<T>::FeeSharesNotWhole
+			);
+			FeeSplit::<T>::put(&split);
+			Self::deposit_event(Event::FeeSplitSet { split: split.into_inner() });
+			Ok(())
+		}
+
+		/// Rescue the `assets` trapped by `trapped_by` under `ticket`, depositing them into the
+		/// account `beneficiary`.
+		///
+		/// Meant for assets their origin can't claim itself anymore. `ticket` is the hash of the
+		/// `AssetsTrapped` event of `pallet_xcm`, it has to match `trapped_by` and `assets`, such
+		/// that governance approves exactly the trap it names. If the same assets have been
+		/// trapped several times, a single trap is rescued.
+		#[pallet::call_index(15)]
+		#[pallet::weight(T::WeightInfo::rescue_trapped_assets(asset_count(assets)))]
+		pub fn rescue_trapped_assets(
+			origin: OriginFor<T>,
+			ticket: H256,
+			trapped_by: Box<VersionedMultiLocation>,
+			assets: Box<VersionedMultiAssets>,
+			beneficiary: T::AccountId,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			let origin = MultiLocation::try_from(*trapped_by.clone())
+				.map_err(|()| Error::<T>::BadVersion)?;
+			ensure!(
+				BlakeTwo256::hash_of(&(origin, &*assets)) == ticket,
+				Error::<T>::TicketMismatch
+			);
+			let latest =
+				MultiAssets::try_from(*assets.clone()).map_err(|()| Error::<T>::BadVersion)?;
+
+			// `pallet_xcm` hashes the assets in the version they have been trapped in, which the
+			// claim ticket names.
+			let version = match *assets {
+				VersionedMultiAssets::V2(_) => 2,
+				VersionedMultiAssets::V3(_) => 3,
+			};
+			let claim_ticket = MultiLocation::new(0, X1(GeneralIndex(version)));
+			let context = XcmContext {
+				origin: Some(origin),
+				message_hash: ticket.to_fixed_bytes(),
+				topic: None,
+			};
+			ensure!(
+				T::AssetClaims::claim_assets(&origin, &claim_ticket, &latest, &context),
+				Error::<T>::NoTrappedAssets
+			);
+
+			let location = T::AccountIdToLocation::convert(beneficiary.clone());
+			for asset in latest.inner() {
+				T::AssetTransactor::deposit_asset(asset, &location, &context)
+					.map_err(|_| Error::<T>::RescueDepositFailed)?;
+			}
+
+			Self::deposit_event(Event::TrappedAssetsRescued {
+				ticket,
+				trapped_by: *trapped_by,
+				assets: *assets,
+				beneficiary,
+			});
+			Ok(())
+		}
+
+		/// Set the minimum fee of the reserve chain `reserve`, `Some(None)` if it has none, or
+		/// `None` to fall back to [`Config::DefaultMinXcmFee`].
+		#[pallet::call_index(16)]
+		#[pallet::weight(T::WeightInfo::set_min_xcm_fee())]
+		pub fn set_min_xcm_fee(
+			origin: OriginFor<T>,
+			reserve: Box<VersionedMultiLocation>,
+			fee: Option<Option<u128>>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			let reserve: MultiLocation =
+				(*reserve).try_into().map_err(|()| Error::<T>::BadVersion)?;
+			MinXcmFees::<T>::set(reserve, fee);
+			Self::deposit_event(Event::MinXcmFeeSet { reserve: reserve.into(), fee });
+			Ok(())
+		}
+
+		/// Set the network messages may be exported to, `None` to disable exports.
+		#[pallet::call_index(17)]
+		#[pallet::weight(T::WeightInfo::set_export_network())]
+		pub fn set_export_network(
+			origin: OriginFor<T>,
+			network: Option<NetworkId>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ExportNetwork::<T>::set(network);
+			Self::deposit_event(Event::ExportNetworkSet { network });
+			Ok(())
+		}
+
+		/// Resume or pause all outbound reserve transfers, whatever the toggles of their assets.
+		#[pallet::call_index(18)]
+		#[pallet::weight(T::WeightInfo::set_reserve_transfers_enabled())]
+		pub fn set_reserve_transfers_enabled(
+			origin: OriginFor<T>,
+			enabled: bool,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ReserveTransfersPaused::<T>::set(!enabled);
+			Self::deposit_event(Event::ReserveTransfersToggled { enabled });
+			Ok(())
+		}
+
+		/// Schedule `call` to be dispatched from the calling origin at block `when`.
+		///
+		/// Meant for the relay chain and siblings, e.g. by a `Transact`. The call is checked again
+		/// when it is dispatched, see [`Pallet::dispatch_scheduled`].
+		#[pallet::call_index(19)]
+		#[pallet::weight(T::WeightInfo::schedule_call())]
+		pub fn schedule_call(
+			origin: OriginFor<T>,
+			when: BlockNumberFor<T>,
+			call: Box<CallOf<T>>,
+		) -> DispatchResult {
+			let location = T::ScheduleOrigin::ensure_origin(origin.clone())?;
+			let now = frame_system::Pallet::<T>::block_number();
+			ensure!(
+				when > now && when - now <= Self::schedule_delay_limit(),
+				Error::<T>::InvalidScheduleTime
+			);
+			ensure!(Self::may_dispatch_scheduled(&call), Error::<T>::TransactCallBlocked);
+
+			ScheduledCalls::<T>::try_mutate(location, |pending| {
+				pending.retain(|due| *due > now);
+				pending.try_push(when).map_err(|_| Error::<T>::TooManyScheduledCalls)
+			})?;
+			ScheduledCallsAt::<T>::try_mutate(when, |count| {
+				ensure!(*count < T::MaxScheduledPerBlock::get(), Error::<T>::ScheduleFull);
+				*count += 1;
+				Ok::<_, Error<T>>(())
+			})?;
+
+			let call = CallOf::<T>::from(Call::<T>::dispatch_scheduled { call });
+			T::Scheduler::schedule(
+				DispatchTime::At(when),
+				None,
+				LOWEST_PRIORITY,
+				origin.caller().clone(),
+				T::Preimages::bound(call)?,
+			)?;
+			Self::deposit_event(Event::CallScheduled { origin: location.into(), when });
+			Ok(())
+		}
+
+		/// Dispatch `call`, scheduled with [`Pallet::schedule_call`], from the origin which
+		/// scheduled it.
+		///
+		/// Fails with [`Error::TransactCallBlocked`] if governance has blocked the call since it
+		/// has been scheduled.
+		#[pallet::call_index(20)]
+		#[pallet::weight({
+			let info = call.get_dispatch_info();
+			(T::WeightInfo::dispatch_scheduled().saturating_add(info.weight), info.class)
+		})]
+		pub fn dispatch_scheduled(
+			origin: OriginFor<T>,
+			call: Box<CallOf<T>>,
+		) -> DispatchResultWithPostInfo {
+			T::ScheduleOrigin::ensure_origin(origin.clone())?;
+			ensure!(Self::may_dispatch_scheduled(&call), Error::<T>::TransactCallBlocked);
+
+			let info = call.get_dispatch_info();
+			let result = call.dispatch(origin);
+			let weight = T::WeightInfo::dispatch_scheduled()
+				.saturating_add(extract_actual_weight(&result, &info));
+			result
+				.map_err(|mut error| {
+					error.post_info = Some(weight).into();
+					error
+				})
+				.map(|_| Some(weight).into())
+		}
+
+		/// Set the account the XCM fees go to, `None` to fall back to
+		/// [`Config::DefaultXcmFeeReceiver`].
+		#[pallet::call_index(21)]
+		#[pallet::weight(T::WeightInfo::set_xcm_fee_receiver())]
+		pub fn set_xcm_fee_receiver(
+			origin: OriginFor<T>,
+			receiver: Option<T::AccountId>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			XcmFeeReceiver::<T>::set(receiver.clone());
+			Self::deposit_event(Event::XcmFeeReceiverSet { receiver });
+			Ok(())
+		}
+
+		/// Set the siblings paying no XCM fees, by parachain id. Replaces the previous ones.
+		#[pallet::call_index(22)]
+		#[pallet::weight(T::WeightInfo::set_fee_waived_siblings(siblings.len() as u32))]
+		pub fn set_fee_waived_siblings(
+			origin: OriginFor<T>,
+			siblings: BoundedVec<u32, T::MaxFeeWaivedSiblings>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			let mut siblings = siblings.into_inner();
+			siblings.sort_unstable();
+			siblings.dedup();
+			// Sorting and removing duplicates can't exceed the bound.
+			FeeWaivedSiblings::<T>::put(BoundedVec::truncate_from(siblings.clone()));
+			Self::deposit_event(Event::FeeWaivedSiblingsSet { siblings });
+			Ok(())
+		}
+
+		/// Set the network of the relay chain, `None` to fall back to
+		/// [`Config::DefaultRelayNetwork`].
+		///
+		/// Meant to correct the network of a chain spec. The network is part of our universal
+		/// location and of the locations of local accounts, changing it changes how other chains
+		/// see us.
+		#[pallet::call_index(23)]
+		#[pallet::weight(T::WeightInfo::set_relay_network())]
+		pub fn set_relay_network(
+			origin: OriginFor<T>,
+			network: Option<NetworkId>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(network.map_or(true, is_relay_network), Error::<T>::NotARelayNetwork);
+			RelayNetwork::<T>::set(network);
+			Self::deposit_event(Event::RelayNetworkSet { network });
+			Ok(())
+		}
+
+		/// Set where the assets a message leaves over go.
+		#[pallet::call_index(24)]
+		#[pallet::weight(T::WeightInfo::set_remainder_policy())]
+		pub fn set_remainder_policy(
+			origin: OriginFor<T>,
+			policy: RemainderDestination,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			RemainderPolicy::<T>::put(policy);
+			Self::deposit_event(Event::RemainderPolicySet { policy });
+			Ok(())
+		}
+
+		/// Set how many blocks ahead a call may be scheduled with [`Pallet::schedule_call`],
+		/// `None` for [`Config::MaxScheduleDelay`]. Calls which have been scheduled already stay.
+		#[pallet::call_index(25)]
+		#[pallet::weight(T::WeightInfo::set_schedule_delay_limit())]
+		pub fn set_schedule_delay_limit(
+			origin: OriginFor<T>,
+			limit: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(
+				limit.map_or(true, |limit| limit <= T::MaxScheduleDelay::get()),
+				Error::<T>::ScheduleDelayTooLong
+			);
+			ScheduleDelayLimit::<T>::set(limit);
+			Self::deposit_event(Event::ScheduleDelayLimitSet { limit });
+			Ok(())
+		}
+
+		/// Set the price (numerator and denominator) per unit of `ref_time` of the fee currency
+		/// `currency`, `Some(None)` if it can't pay fees, or `None` to fall back to
+		/// [`Config::DefaultFeePerRefTime`].
+		#[pallet::call_index(26)]
+		#[pallet::weight(T::WeightInfo::set_fee_per_ref_time())]
+		pub fn set_fee_per_ref_time(
+			origin: OriginFor<T>,
+			currency: T::FeeCurrencyId,
+			price: Option<Option<(u128, u128)>>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(!matches!(price, Some(Some((_, 0)))), Error::<T>::ZeroPriceDenominator);
+			FeePricesPerRefTime::<T>::set(currency.clone(), price);
+			Self::deposit_event(Event::FeePerRefTimeSet { currency, price });
+			Ok(())
+		}
+
+		/// Set the fee of delivering a message to a sibling parachain, `None` to fall back to
+		/// [`Config::DefaultSiblingDeliveryFee`].
+		#[pallet::call_index(27)]
+		#[pallet::weight(T::WeightInfo::set_sibling_delivery_fee())]
+		pub fn set_sibling_delivery_fee(
+			origin: OriginFor<T>,
+			fee: Option<DeliveryFee>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			SiblingDeliveryFee::<T>::set(fee);
+			Self::deposit_event(Event::SiblingDeliveryFeeSet { fee });
+			Ok(())
+		}
+
+		/// Set the weight of converting an asset id of a message, `None` to fall back to
+		/// [`Config::DefaultXcmConversionWeight`].
+		#[pallet::call_index(28)]
+		#[pallet::weight(T::WeightInfo::set_xcm_conversion_weight())]
+		pub fn set_xcm_conversion_weight(
+			origin: OriginFor<T>,
+			weight: Option<Weight>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(
+				weight.map_or(true, |weight| weight.all_lte(T::MaxXcmConversionWeight::get())),
+				Error::<T>::XcmConversionWeightTooHigh
+			);
+			XcmConversionWeight::<T>::set(weight);
+			Self::deposit_event(Event::XcmConversionWeightSet { weight });
+			Ok(())
+		}
+
+		/// Set the bridges which may alias into a global consensus system, as pairs of the bridge
+		/// location and the bridged network. Replaces the previous ones.
+		#[pallet::call_index(29)]
+		#[pallet::weight(T::WeightInfo::set_universal_aliases(aliases.len() as u32))]
+		pub fn set_universal_aliases(
+			origin: OriginFor<T>,
+			aliases: BoundedVec<(VersionedMultiLocation, NetworkId), T::MaxUniversalAliases>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			let relay_network = Self::relay_network();
+			let mut converted = Vec::with_capacity(aliases.len());
+			for (bridge, network) in aliases.iter() {
+				ensure!(*network != relay_network, Error::<T>::AliasIntoOwnNetwork);
+				let bridge: MultiLocation =
+					bridge.clone().try_into().map_err(|()| Error::<T>::BadVersion)?;
+				converted.push((bridge, *network));
+			}
+			// As many as the bound of `aliases`.
+			UniversalAliases::<T>::put(BoundedVec::truncate_from(converted));
+			Self::deposit_event(Event::UniversalAliasesSet { aliases: aliases.into_inner() });
+			Ok(())
+		}
+
+		/// Set the fee for exporting a message, `None` to fall back to
+		/// [`Config::DefaultExportFee`].
+		#[pallet::call_index(30)]
+		#[pallet::weight(T::WeightInfo::set_export_fee())]
+		pub fn set_export_fee(origin: OriginFor<T>, fee: Option<u128>) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ExportFee::<T>::set(fee);
+			Self::deposit_event(Event::ExportFeeSet { fee });
+			Ok(())
+		}
+
+		/// Set how many assets a transfer may carry, `None` to fall back to
+		/// [`Config::DefaultAssetsForTransferLimit`].
+		#[pallet::call_index(31)]
+		#[pallet::weight(T::WeightInfo::set_assets_for_transfer_limit())]
+		pub fn set_assets_for_transfer_limit(
+			origin: OriginFor<T>,
+			limit: Option<u32>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(
+				limit.map_or(true, |limit| limit > 0 &&
+					limit <= T::MaxAssetsForTransferLimit::get()),
+				Error::<T>::InvalidAssetsForTransferLimit
+			);
+			AssetsForTransferLimit::<T>::set(limit);
+			Self::deposit_event(Event::AssetsForTransferLimitSet { limit });
+			Ok(())
+		}
+
+		/// Set the chains which may lock assets for use here, as pairs of the locker and the
+		/// assets it may lock, `None` to fall back to [`Config::DefaultTrustedLockers`]. Assets
+		/// noted before stay unlockable.
+		#[pallet::call_index(32)]
+		#[pallet::weight(T::WeightInfo::set_trusted_lockers(
+			lockers.as_ref().map_or(0, |lockers| lockers.len() as u32)
+		))]
+		pub fn set_trusted_lockers(
+			origin: OriginFor<T>,
+			lockers: Option<
+				BoundedVec<(VersionedMultiLocation, MultiAssetFilter), T::MaxTrustedLockers>,
+			>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			let converted = match &lockers {
+				Some(lockers) => {
+					let mut converted = Vec::with_capacity(lockers.len());
+					for (locker, assets) in lockers.iter() {
+						let locker: MultiLocation =
+							locker.clone().try_into().map_err(|()| Error::<T>::BadVersion)?;
+						converted.push((locker, assets.clone()));
+					}
+					// As many as the bound of `lockers`.
+					Some(BoundedVec::truncate_from(converted))
+				},
+				None => None,
+			};
+			TrustedLockers::<T>::set(converted);
+			Self::deposit_event(Event::TrustedLockersSet {
+				lockers: lockers.map(BoundedVec::into_inner),
+			});
+			Ok(())
+		}
+
+		/// Set the pallet and call indices the runtime encodes relay chain calls with, `None` to
+		/// fall back to [`Config::DefaultRelayCallIndices`].
+		#[pallet::call_index(33)]
+		#[pallet::weight(T::WeightInfo::set_relay_call_indices())]
+		pub fn set_relay_call_indices(
+			origin: OriginFor<T>,
+			indices: Option<T::RelayCallIndices>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			RelayChainCallIndices::<T>::set(indices.clone());
+			Self::deposit_event(Event::RelayCallIndicesSet { indices });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Whether `call` may be scheduled with [`Pallet::schedule_call`], checked again when it is
+		/// dispatched.
+		fn may_dispatch_scheduled(call: &CallOf<T>) -> bool {
+			let CallMetadata { pallet_name, function_name } = call.get_call_metadata();
+			!Self::is_transact_blocked(pallet_name, function_name) &&
+				T::ScheduledCallFilter::contains(call)
+		}
+
+		/// The destinations in `pallet_xcm`'s version discovery queue, together with the number
+		/// of times they have been noted.
+		pub fn version_discovery_queue() -> Vec<(VersionedMultiLocation, u32)> {
+			unhashed::get_or_default(&version_discovery_queue_key::<T>())
+		}
+
+		/// The order in which the [`MultiCurrencyTrader`] tries `currencies`: the prioritized
+		/// ones first, in order of their priority, then the others in the order given.
+		pub fn fee_currency_order(mut currencies: Vec<T::FeeCurrencyId>) -> Vec<T::FeeCurrencyId> {
+			let mut ordered: Vec<_> = Self::fee_currency_priority()
+				.into_iter()
+				.filter(|currency| currencies.contains(currency))
+				.collect();
+			currencies.retain(|currency| !ordered.contains(currency));
+			ordered.append(&mut currencies);
+			ordered
+		}
+
+		/// The assets trapped by `origin` which haven't been claimed yet, with the number of times
+		/// they have been trapped.
+		pub fn trapped_assets(origin: &MultiLocation) -> Vec<(VersionedMultiAssets, u32)> {
+			TrappedAssetIndex::<T>::iter_prefix_values(origin)
+				.map(|(assets, count)| (assets.into(), count))
+				.collect()
+		}
+
+		/// The minimum fee of the reserve chain `reserve`, `None` if it has none.
+		pub fn min_xcm_fee(reserve: &MultiLocation) -> Option<u128> {
+			MinXcmFees::<T>::get(reserve).unwrap_or_else(|| T::DefaultMinXcmFee::convert(*reserve))
+		}
+
+		/// The maximum amount of `asset` a message may load into the holding register, `None` if
+		/// there is no limit. Abstract assets have none.
+		pub fn max_holding_amount(asset: &AssetId) -> Option<u128> {
+			match asset {
+				Concrete(location) =>
+					MaxHoldingAmounts::<T>::get(T::CanonicalAssetLocation::convert(*location)),
+				Abstract(_) => None,
+			}
+		}
+
+		/// Whether reserve transfers of `asset` are enabled. Abstract assets are always enabled.
+		pub fn is_reserve_transfer_enabled(asset: &MultiAsset) -> bool {
+			match asset.id {
+				Concrete(location) => !ReserveTransferDisabled::<T>::contains_key(
+					T::CanonicalAssetLocation::convert(location),
+				),
+				Abstract(_) => true,
+			}
+		}
+
+		/// Whether `Transact` must not dispatch the call `function_name` of `pallet_name`.
+		pub fn is_transact_blocked(pallet_name: &str, function_name: &str) -> bool {
+			// Names too long to be stored can't have been blocked.
+			let Ok(pallet_name) = CallName::try_from(pallet_name.as_bytes().to_vec()) else {
+				return false
+			};
+			let function_name = CallName::try_from(function_name.as_bytes().to_vec()).ok();
+			TransactBlocked::<T>::contains_key((pallet_name.clone(), None::<CallName>)) ||
+				function_name.map_or(false, |function_name| {
+					TransactBlocked::<T>::contains_key((pallet_name, Some(function_name)))
+				})
+		}
+
+		/// Ensure that messages to `dest` can be delivered, as far as HRMP channels are concerned.
+		///
+		/// Destinations on a sibling parachain need an open outbound channel to it, anything else
+		/// is accepted.
+		pub fn ensure_open_channel(dest: &MultiLocation) -> Result<(), Error<T>> {
+			match (dest.parents, dest.interior.first()) {
+				(1, Some(Parachain(id))) =>
+					match T::ChannelInfo::get_channel_status(ParaId::from(*id)) {
+						ChannelStatus::Closed => Err(Error::<T>::NoOpenChannel),
+						ChannelStatus::Full | ChannelStatus::Ready(..) => Ok(()),
+					},
+				_ => Ok(()),
+			}
+		}
+
+		/// The size limit applied to messages to the relay chain, if any.
+		pub fn ump_message_size_limit() -> Option<u32> {
+			match (Self::max_ump_message_size(), T::RelayMaxUmpMessageSize::get()) {
+				(Some(limit), Some(relay_limit)) => Some(limit.min(relay_limit)),
+				(limit, relay_limit) => limit.or(relay_limit),
+			}
+		}
+
+		/// The account the XCM fees go to.
+		pub fn xcm_fee_receiver() -> T::AccountId {
+			XcmFeeReceiver::<T>::get().unwrap_or_else(T::DefaultXcmFeeReceiver::get)
+		}
+
+		/// How many blocks ahead a call may be scheduled with [`Pallet::schedule_call`].
+		pub fn schedule_delay_limit() -> BlockNumberFor<T> {
+			ScheduleDelayLimit::<T>::get().unwrap_or_else(T::MaxScheduleDelay::get)
+		}
+
+		/// The price (numerator and denominator) per unit of `ref_time` of the fee currency
+		/// `currency`, `None` if it can't pay fees.
+		pub fn fee_per_ref_time(currency: T::FeeCurrencyId) -> Option<(u128, u128)> {
+			FeePricesPerRefTime::<T>::get(&currency)
+				.unwrap_or_else(|| T::DefaultFeePerRefTime::convert(currency))
+		}
+
+		/// The fee of delivering a message to a sibling parachain.
+		pub fn sibling_delivery_fee() -> DeliveryFee {
+			SiblingDeliveryFee::<T>::get().unwrap_or_else(T::DefaultSiblingDeliveryFee::get)
+		}
+
+		/// The weight of converting an asset id of a message.
+		pub fn xcm_conversion_weight() -> Weight {
+			XcmConversionWeight::<T>::get().unwrap_or_else(T::DefaultXcmConversionWeight::get)
+		}
+
+		/// The fee for exporting a message.
+		pub fn export_fee() -> u128 {
+			ExportFee::<T>::get().unwrap_or_else(T::DefaultExportFee::get)
+		}
+
+		/// How many assets a transfer may carry.
+		pub fn assets_for_transfer_limit() -> u32 {
+			AssetsForTransferLimit::<T>::get().unwrap_or_else(T::DefaultAssetsForTransferLimit::get)
+		}
+
+		/// The chains which may lock assets for use here, with the assets they may lock.
+		pub fn trusted_lockers() -> Vec<(MultiLocation, MultiAssetFilter)> {
+			TrustedLockers::<T>::get()
+				.map(BoundedVec::into_inner)
+				.unwrap_or_else(T::DefaultTrustedLockers::get)
+		}
+
+		/// The pallet and call indices of the relay chain calls the runtime encodes.
+		pub fn relay_call_indices() -> T::RelayCallIndices {
+			RelayChainCallIndices::<T>::get().unwrap_or_else(T::DefaultRelayCallIndices::get)
+		}
+
+		/// The network of the relay chain.
+		pub fn relay_network() -> NetworkId {
+			RelayNetwork::<T>::get().unwrap_or_else(T::DefaultRelayNetwork::get)
+		}
+
+		/// Whether the XCM fees of the sibling `para_id` are waived.
+		pub fn is_fee_waived_sibling(para_id: u32) -> bool {
+			Self::fee_waived_siblings().binary_search(&para_id).is_ok()
+		}
+	}
+}
+
+/// `pallet_xcm::VersionDiscoveryQueue` is private to `pallet_xcm`, so we access it by its key. Its
+/// `BoundedVec` encodes just like a `Vec`.
+fn version_discovery_queue_key<T: Config>() -> [u8; 32] {
+	frame_support::storage::storage_prefix(
+		T::XcmPallet::name().as_bytes(),
+		b"VersionDiscoveryQueue",
+	)
+}
+
+/// `IsReserve` filter which only accepts assets whose reserve transfers are enabled, and that
+/// `Inner` accepts.
+pub struct IsEnabledReserve<T, Inner>(PhantomData<(T, Inner)>);
+impl<T: Config, Inner: ContainsPair<MultiAsset, MultiLocation>>
+	ContainsPair<MultiAsset, MultiLocation> for IsEnabledReserve<T, Inner>
+{
+	fn contains(asset: &MultiAsset, origin: &MultiLocation) -> bool {
+		Pallet::<T>::is_reserve_transfer_enabled(asset) && Inner::contains(asset, origin)
+	}
+}
+
+/// `XcmReserveTransferFilter` for `pallet_xcm`, which rejects all transfers while they are paused,
+/// and transfers containing an asset whose reserve transfers are disabled.
+pub struct ReserveTransfersEnabled<T>(PhantomData<T>);
+impl<T: Config> Contains<(MultiLocation, Vec<MultiAsset>)> for ReserveTransfersEnabled<T> {
+	fn contains((_origin, assets): &(MultiLocation, Vec<MultiAsset>)) -> bool {
+		!ReserveTransfersPaused::<T>::get() &&
+			assets.iter().all(Pallet::<T>::is_reserve_transfer_enabled)
+	}
+}
+
+/// Destination filter for reserve transfers, which rejects siblings we have no open outbound
+/// channel to, and destinations `Inner` rejects.
+///
+/// Intended to be used as the `MultiLocationsFilter` of `orml_xtokens`.
+pub struct OpenChannelDestinations<T, Inner>(PhantomData<(T, Inner)>);
+impl<T: Config, Inner: Contains<MultiLocation>> Contains<MultiLocation>
+	for OpenChannelDestinations<T, Inner>
+{
+	fn contains(dest: &MultiLocation) -> bool {
+		if let Err(e) = Pallet::<T>::ensure_open_channel(dest) {
+			log::warn!(target: LOG_TARGET, "Rejecting reserve transfer to {:?}: {:?}", dest, e);
+			return false
+		}
+		Inner::contains(dest)
+	}
+}
+
+/// The amounts of the fungible assets `message` loads into the holding register, by asset. Concrete
+/// assets are keyed by their location as mapped by `Canonical`, such that the different locations
+/// of an asset add up. Amounts loaded by several instructions are summed up, saturating.
+pub fn holding_amounts<Canonical, Call>(message: &[Instruction<Call>]) -> BTreeMap<AssetId, u128>
+where
+	Canonical: Convert<MultiLocation, MultiLocation>,
+{
+	fn add<Canonical: Convert<MultiLocation, MultiLocation>, Call>(
+		amounts: &mut BTreeMap<AssetId, u128>,
+		message: &[Instruction<Call>],
+	) {
+		for instruction in message {
+			let assets = match instruction {
+				WithdrawAsset(assets) |
+				ReserveAssetDeposited(assets) |
+				ReceiveTeleportedAsset(assets) |
+				ClaimAsset { assets, .. } => assets,
+				SetErrorHandler(xcm) | SetAppendix(xcm) => {
+					add::<Canonical, _>(amounts, &xcm.0);
+					continue
+				},
+				_ => continue,
+			};
+			for asset in assets.inner() {
+				if let Fungible(amount) = asset.fun {
+					let id = match asset.id {
+						Concrete(location) => Concrete(Canonical::convert(location)),
+						id @ Abstract(_) => id,
+					};
+					let total = amounts.entry(id).or_default();
+					*total = total.saturating_add(amount);
+				}
+			}
+		}
+	}
+
+	let mut amounts = BTreeMap::new();
+	add::<Canonical, _>(&mut amounts, message);
+	amounts
+}
+
+/// Barrier rejecting messages which load more than the [`Pallet::max_holding_amount`] of an asset
+/// into the holding register, see [`holding_amounts`]. Lets everything else pass.
+///
+/// Intended to be used as the `Deny` part of a `DenyThenTry` barrier.
+pub struct DenyExcessiveHolding<T>(PhantomData<T>);
+impl<T: Config> ShouldExecute for DenyExcessiveHolding<T> {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		message: &mut [Instruction<RuntimeCall>],
+		_max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ProcessMessageError> {
+		for (asset, amount) in holding_amounts::<T::CanonicalAssetLocation, _>(message) {
+			let Some(limit) = Pallet::<T>::max_holding_amount(&asset) else { continue };
+			if amount > limit {
+				log::warn!(
+					target: LOG_TARGET,
+					"Rejecting message from {:?} loading {} of {:?} into holding, the limit is {}",
+					origin,
+					amount,
+					asset,
+					limit,
+				);
+				return Err(ProcessMessageError::Unsupported)
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Whether `message` consists of instructions which are legitimately tiny only: query responses
+/// and version subscriptions.
+pub fn is_exempt_from_min_weight<Call>(message: &[Instruction<Call>]) -> bool {
+	!message.is_empty() &&
+		message.iter().all(|instruction| {
+			matches!(
+				instruction,
+				QueryResponse { .. } | SubscribeVersion { .. } | UnsubscribeVersion | SetTopic(_)
+			)
+		})
+}
+
+/// Barrier rejecting messages weighing less than [`Pallet::min_execution_weight`] in any
+/// dimension, unless [`is_exempt_from_min_weight`]. Lets everything else pass.
+///
+/// Intended to be used as the `Deny` part of a `DenyThenTry` barrier.
+pub struct DenyTrivialWeight<T>(PhantomData<T>);
+impl<T: Config> ShouldExecute for DenyTrivialWeight<T> {
+	fn should_execute<RuntimeCall>(
+		origin: &MultiLocation,
+		message: &mut [Instruction<RuntimeCall>],
+		max_weight: Weight,
+		_weight_credit: &mut Weight,
+	) -> Result<(), ProcessMessageError> {
+		let Some(min_weight) = Pallet::<T>::min_execution_weight() else { return Ok(()) };
+		if max_weight.any_lt(min_weight) && !is_exempt_from_min_weight(message) {
+			log::debug!(
+				target: LOG_TARGET,
+				"Rejecting message from {:?} weighing {:?}, the minimum is {:?}",
+				origin,
+				max_weight,
+				min_weight,
+			);
+			return Err(ProcessMessageError::Unsupported)
+		}
+		Ok(())
+	}
+}
+
+/// Wraps the UMP router, rejecting messages to the relay chain which exceed
+/// [`Pallet::ump_message_size_limit`].
+///
+/// `W` must be the version wrapper of the inner router, such that we measure the message as it
+/// will be sent.
+pub struct UmpSizeLimit<T, W, Inner>(PhantomData<(T, W, Inner)>);
+
+impl<T: Config, W: WrapVersion, Inner: SendXcm> SendXcm for UmpSizeLimit<T, W, Inner> {
+	type Ticket = Inner::Ticket;
+
+	fn validate(
+		dest: &mut Option<MultiLocation>,
+		msg: &mut Option<Xcm<()>>,
+	) -> SendResult<Self::Ticket> {
+		if let (Some(MultiLocation { parents: 1, interior: Here }), Some(message), Some(limit)) =
+			(dest.as_ref(), msg.as_ref(), Pallet::<T>::ump_message_size_limit())
+		{
+			let size = W::wrap_version(&Parent.into(), message.clone())
+				.map_err(|()| SendError::DestinationUnsupported)?
+				.encoded_size() as u32;
+			if size > limit {
+				log::warn!(
+					target: LOG_TARGET,
+					"Not sending a message of {} bytes to the relay chain, the limit is {} bytes",
+					size,
+					limit,
+				);
+				Pallet::<T>::deposit_event(Event::UmpMessageTooBig { size, limit });
+				return Err(SendError::ExceedsMaxMessageSize)
+			}
+		}
+		Inner::validate(dest, msg)
+	}
+
+	fn deliver(ticket: Self::Ticket) -> Result<XcmHash, SendError> {
+		Inner::deliver(ticket)
+	}
+}
+
+/// The name of `instruction` if it isn't supported by the executor and may be skipped.
+///
+/// These are the HRMP channel notifications of the relay chain, which the executor doesn't
+/// implement. Instructions handling assets or fees (e.g. `BuyExecution`, `ExchangeAsset`,
+/// `LockAsset`) or changing the origin (e.g. `UniversalOrigin`) are never skipped, even if our
+/// configuration doesn't support them: skipping them could lead to free execution or to running
+/// the rest of the message with the wrong assets or privileges.
+pub fn skippable_instruction<Call>(instruction: &Instruction<Call>) -> Option<&'static str> {
+	match instruction {
+		HrmpNewChannelOpenRequest { .. } => Some("HrmpNewChannelOpenRequest"),
+		HrmpChannelAccepted { .. } => Some("HrmpChannelAccepted"),
+		HrmpChannelClosing { .. } => Some("HrmpChannelClosing"),
+		_ => None,
+	}
+}
+
+/// Wraps the XCM executor, removing unsupported instructions from messages before they are
+/// executed if [`UnsupportedInstructionPolicy::Skip`] is set.
+///
+/// Intended to be used as the `XcmExecutor` of the inbound message queues.
+pub struct SkipUnsupportedInstructions<T, Inner>(PhantomData<(T, Inner)>);
+
+impl<T: Config, Call, Inner: ExecuteXcm<Call>> ExecuteXcm<Call>
+	for SkipUnsupportedInstructions<T, Inner>
+{
+	type Prepared = Inner::Prepared;
+
+	fn prepare(mut message: Xcm<Call>) -> Result<Self::Prepared, Xcm<Call>> {
+		if Pallet::<T>::unsupported_instruction_policy() == UnsupportedInstructionPolicy::Skip {
+			let mut skipped = Vec::new();
+			message.0.retain(|instruction| match skippable_instruction(instruction) {
+				Some(name) => {
+					skipped.push(name.as_bytes().to_vec());
+					false
+				},
+				None => true,
+			});
+			if !skipped.is_empty() {
+				log::debug!(
+					target: LOG_TARGET,
+					"Skipped {} unsupported instructions of an inbound message",
+					skipped.len(),
+				);
+				Pallet::<T>::deposit_event(Event::UnsupportedInstructionsSkipped {
+					instructions: skipped,
+				});
+			}
+		}
+		Inner::prepare(message)
+	}
+
+	fn execute(
+		origin: impl Into<MultiLocation>,
+		pre: Self::Prepared,
+		hash: XcmHash,
+		weight_credit: Weight,
+	) -> Outcome {
+		Inner::execute(origin, pre, hash, weight_credit)
+	}
+
+	fn charge_fees(location: impl Into<MultiLocation>, fees: MultiAssets) -> XcmResult {
+		Inner::charge_fees(location, fees)
+	}
+}
+
+/// Limit the unlimited `BuyExecution`s of the messages `message` sends to other chains to the
+/// [`Pallet::default_dest_weight`] of their destination, nested messages included.
+pub fn apply_default_dest_weights<T: Config, Call>(message: &mut [Instruction<Call>]) {
+	for instruction in message {
+		let (dest, xcm) = match instruction {
+			TransferReserveAsset { dest, xcm, .. } |
+			DepositReserveAsset { dest, xcm, .. } |
+			InitiateTeleport { dest, xcm, .. } => (dest, xcm),
+			InitiateReserveWithdraw { reserve, xcm, .. } => (reserve, xcm),
+			_ => continue,
+		};
+		if let Some(weight) = DestinationKind::of(dest).and_then(Pallet::<T>::default_dest_weight) {
+			for instruction in xcm.0.iter_mut() {
+				if let BuyExecution { weight_limit: weight_limit @ Unlimited, .. } = instruction {
+					*weight_limit = Limited(weight);
+				}
+			}
+		}
+		apply_default_dest_weights::<T, ()>(&mut xcm.0);
+	}
+}
+
+/// Wraps the XCM executor, applying the default destination weights to the messages sent to
+/// other chains, see [`apply_default_dest_weights`].
+///
+/// Intended to be used as the `XcmExecutor` of `orml_xtokens`.
+pub struct WithDefaultDestWeight<T, Inner>(PhantomData<(T, Inner)>);
+
+impl<T: Config, Call, Inner: ExecuteXcm<Call>> ExecuteXcm<Call>
+	for WithDefaultDestWeight<T, Inner>
+{
+	type Prepared = Inner::Prepared;
+
+	fn prepare(mut message: Xcm<Call>) -> Result<Self::Prepared, Xcm<Call>> {
+		apply_default_dest_weights::<T, Call>(&mut message.0);
+		Inner::prepare(message)
+	}
+
+	fn execute(
+		origin: impl Into<MultiLocation>,
+		pre: Self::Prepared,
+		hash: XcmHash,
+		weight_credit: Weight,
+	) -> Outcome {
+		Inner::execute(origin, pre, hash, weight_credit)
+	}
+
+	fn charge_fees(location: impl Into<MultiLocation>, fees: MultiAssets) -> XcmResult {
+		Inner::charge_fees(location, fees)
+	}
+}
+
+/// Wraps the XCM executor, recording the weight each executed message has been charged and the
+/// weight it consumed with an [`Event::InboundMessageMetered`].
+///
+/// Intended to be used as the `XcmExecutor` of the inbound message queues, innermost, such that
+/// messages dropped by the other wrappers aren't metered.
+pub struct MeterInbound<T, Inner>(PhantomData<(T, Inner)>);
+
+impl<T: Config, Call, Inner: ExecuteXcm<Call>> ExecuteXcm<Call> for MeterInbound<T, Inner> {
+	type Prepared = Inner::Prepared;
+
+	fn prepare(message: Xcm<Call>) -> Result<Self::Prepared, Xcm<Call>> {
+		Inner::prepare(message)
+	}
+
+	fn execute(
+		origin: impl Into<MultiLocation>,
+		pre: Self::Prepared,
+		hash: XcmHash,
+		weight_credit: Weight,
+	) -> Outcome {
+		let origin = origin.into();
+		let charged = pre.weight_of();
+		let outcome = Inner::execute(origin, pre, hash, weight_credit);
+		if !matches!(outcome, Outcome::Error(_)) {
+			Pallet::<T>::deposit_event(Event::InboundMessageMetered {
+				origin: origin.into(),
+				charged,
+				consumed: outcome.weight_used(),
+			});
+		}
+		outcome
+	}
+
+	fn charge_fees(location: impl Into<MultiLocation>, fees: MultiAssets) -> XcmResult {
+		Inner::charge_fees(location, fees)
+	}
+}
+
+/// Wraps the asset trap and claims of the executor, i.e. `pallet_xcm`, indexing the trapped
+/// assets by origin in [`TrappedAssetIndex`] until they are claimed.
+///
+/// Intended to be used as both the `AssetTrap` and the `AssetClaims` of the executor, and as the
+/// [`Config::AssetClaims`] of this pallet.
+pub struct IndexTrappedAssets<T, Inner>(PhantomData<(T, Inner)>);
+
+impl<T: Config, Inner: DropAssets> DropAssets for IndexTrappedAssets<T, Inner> {
+	fn drop_assets(origin: &MultiLocation, assets: Assets, context: &XcmContext) -> Weight {
+		if assets.is_empty() {
+			return Inner::drop_assets(origin, assets, context)
+		}
+		// The same hash `pallet_xcm` traps the assets under.
+		let trapped = MultiAssets::from(assets.clone());
+		let hash = BlakeTwo256::hash_of(&(origin, VersionedMultiAssets::from(trapped.clone())));
+		let weight = Inner::drop_assets(origin, assets, context);
+		TrappedAssetIndex::<T>::mutate(origin, hash, |entry| {
+			let count = entry.as_ref().map_or(0, |(_, count)| *count);
+			*entry = Some((trapped, count.saturating_add(1)));
+		});
+		weight.saturating_add(T::DbWeight::get().reads_writes(1, 1))
+	}
+}
+
+impl<T: Config, Inner: ClaimAssets> ClaimAssets for IndexTrappedAssets<T, Inner> {
+	fn claim_assets(
+		origin: &MultiLocation,
+		ticket: &MultiLocation,
+		what: &MultiAssets,
+		context: &XcmContext,
+	) -> bool {
+		if !Inner::claim_assets(origin, ticket, what, context) {
+			return false
+		}
+		// `pallet_xcm` hashes the assets in the XCM version the ticket names.
+		let versioned = VersionedMultiAssets::from(what.clone());
+		let versioned = match ticket {
+			MultiLocation { parents: 0, interior: X1(GeneralIndex(version)) } =>
+				versioned.into_version(*version as u32),
+			MultiLocation { parents: 0, interior: Here } => Ok(versioned),
+			_ => Err(()),
+		};
+		if let Ok(versioned) = versioned {
+			let hash = BlakeTwo256::hash_of(&(origin, &versioned));
+			TrappedAssetIndex::<T>::mutate_exists(origin, hash, |entry| match entry {
+				Some((_, count)) if *count > 1 => *count -= 1,
+				_ => *entry = None,
+			});
+		}
+		true
+	}
+}
+
+/// Split `amount` into the shares of `split`, each rounded down. What the rounding leaves over
+/// goes to `remainder`, such that the parts sum up to `amount`.
+pub fn split_fee<AccountId: Clone>(
+	amount: u128,
+	split: &[(FeeDestination<AccountId>, Perbill)],
+	remainder: AccountId,
+) -> Vec<(FeeDestination<AccountId>, u128)> {
+	let mut parts: Vec<_> = split
+		.iter()
+		.map(|(dest, share)| (dest.clone(), share.mul_floor(amount)))
+		.collect();
+	let rest = parts.iter().fold(amount, |rest, (_, part)| rest.saturating_sub(*part));
+	if !rest.is_zero() {
+		parts.push((FeeDestination::Account(remainder), rest));
+	}
+	parts
+}
+
+/// `FeeManager` splitting the fees collected by the XCM executor as set by governance (see
+/// [`FeeSplit`]), depositing them into the accounts with `AssetTransactor`.
+///
+/// What the rounding of the shares leaves over goes to `RemainderAccount`, and so do non-fungible
+/// fees. All fees go there as long as no split is set. Messages from `WaivedOrigins` pay no fees
+/// to the `FeeManager`.
+///
+/// As a `TakeRevenue`, it handles the fees the trader charges for execution alike.
+pub struct SplitFees<T, AssetTransactor, AccountToLocation, RemainderAccount, WaivedOrigins>(
+	PhantomData<(T, AssetTransactor, AccountToLocation, RemainderAccount, WaivedOrigins)>,
+);
+
+impl<T, AssetTransactor, AccountToLocation, RemainderAccount, WaivedOrigins> FeeManager
+	for SplitFees<T, AssetTransactor, AccountToLocation, RemainderAccount, WaivedOrigins>
+where
+	T: Config,
+	AssetTransactor: TransactAsset,
+	AccountToLocation: Convert<T::AccountId, MultiLocation>,
+	RemainderAccount: Get<T::AccountId>,
+	WaivedOrigins: Contains<MultiLocation>,
+{
+	fn is_waived(origin: Option<&MultiLocation>, _reason: FeeReason) -> bool {
+		origin.map_or(false, WaivedOrigins::contains)
+	}
+
+	fn handle_fee(fee: MultiAssets) {
+		let split = Pallet::<T>::fee_split();
+
+		// Fees are not handled in the context of the message they have been paid for.
+		let context = XcmContext { origin: None, message_hash: [0; 32], topic: None };
+		for asset in fee.into_inner() {
+			let deposits: Vec<(T::AccountId, MultiAsset)> = match asset.fun {
+				Fungible(amount) => split_fee(amount, &split, RemainderAccount::get())
+					.into_iter()
+					.filter_map(|(dest, part)| match dest {
+						FeeDestination::Account(who) if !part.is_zero() =>
+							Some((who, (asset.id, part).into())),
+						_ => None,
+					})
+					.collect(),
+				NonFungible(_) => vec![(RemainderAccount::get(), asset.clone())],
+			};
+			for (who, part) in deposits {
+				let who = AccountToLocation::convert(who);
+				if let Err(e) = AssetTransactor::deposit_asset(&part, &who, &context) {
+					log::error!(
+						target: LOG_TARGET,
+						"Failed to deposit the XCM fee {:?} into {:?}: {:?}",
+						part,
+						who,
+						e,
+					);
+				}
+			}
+		}
+	}
+}
+
+impl<T, AssetTransactor, AccountToLocation, RemainderAccount, WaivedOrigins> TakeRevenue
+	for SplitFees<T, AssetTransactor, AccountToLocation, RemainderAccount, WaivedOrigins>
+where
+	T: Config,
+	AssetTransactor: TransactAsset,
+	AccountToLocation: Convert<T::AccountId, MultiLocation>,
+	RemainderAccount: Get<T::AccountId>,
+	WaivedOrigins: Contains<MultiLocation>,
+{
+	fn take_revenue(revenue: MultiAsset) {
+		Self::handle_fee(revenue.into())
+	}
+}
+
+/// The relay chain and the [`FeeWaivedSiblings`]. Meant as the waived origins of [`SplitFees`].
+pub struct FeeWaivedOrigins<T>(PhantomData<T>);
+impl<T: Config> Contains<MultiLocation> for FeeWaivedOrigins<T> {
+	fn contains(location: &MultiLocation) -> bool {
+		match location {
+			MultiLocation { parents: 1, interior: Here } => true,
+			MultiLocation { parents: 1, interior: X1(Parachain(id)) } =>
+				Pallet::<T>::is_fee_waived_sibling(*id),
+			_ => false,
+		}
+	}
+}
+
+/// The pairs of a bridge and the `GlobalConsensus` junction of a bridged network listed in
+/// [`UniversalAliases`]. Meant as the `UniversalAliases` of the XCM executor.
+pub struct ListedUniversalAliases<T>(PhantomData<T>);
+impl<T: Config> Contains<(MultiLocation, Junction)> for ListedUniversalAliases<T> {
+	fn contains((bridge, alias): &(MultiLocation, Junction)) -> bool {
+		let GlobalConsensus(network) = alias else { return false };
+		Pallet::<T>::universal_aliases().contains(&(*bridge, *network))
+	}
+}
+
+/// The lockers and the assets they may lock of [`Pallet::trusted_lockers`]. Meant as the
+/// `TrustedLockers` of the XCM executor, letting them note the assets they locked for an owner
+/// here with `NoteUnlockable`.
+pub struct ListedTrustedLockers<T>(PhantomData<T>);
+impl<T: Config> ContainsPair<MultiLocation, MultiAsset> for ListedTrustedLockers<T> {
+	fn contains(locker: &MultiLocation, asset: &MultiAsset) -> bool {
+		Pallet::<T>::trusted_lockers()
+			.iter()
+			.any(|(trusted, assets)| trusted == locker && assets.matches(asset))
+	}
+}
+
+/// Whether a relay chain can be in `network`, as opposed to e.g. Ethereum.
+pub fn is_relay_network(network: NetworkId) -> bool {
+	matches!(
+		network,
+		NetworkId::ByGenesis(_) |
+			NetworkId::Polkadot |
+			NetworkId::Kusama |
+			NetworkId::Westend |
+			NetworkId::Rococo |
+			NetworkId::Wococo
+	)
+}
+
+/// The fee for `weight` at a price of `numerator / denominator` per unit of `ref_time`, rounded as
+/// `rounding` says. `None` if it overflows or the denominator is zero.
+pub fn fee_for(
+	weight: Weight,
+	(numerator, denominator): (u128, u128),
+	rounding: FeeRounding,
+) -> Option<u128> {
+	let rounding = match rounding {
+		FeeRounding::Down => Rounding::Down,
+		FeeRounding::Up => Rounding::Up,
+		FeeRounding::Nearest => Rounding::NearestPrefUp,
+	};
+	multiply_by_rational_with_rounding(weight.ref_time().into(), numerator, denominator, rounding)
+}
+
+/// `WeightTrader` charging for weight in the asset at `AssetLocation`, at a price of `Price`
+/// (numerator and denominator) per unit of `ref_time`.
+///
+/// Fees are rounded according to [`FeeRounding`], refunds are rounded down and capped to the fees
+/// charged so far. The fees collected are handed to `Revenue` once the trader is dropped.
+pub struct RoundingTrader<T, AssetLocation: Get<MultiLocation>, Price, Revenue: TakeRevenue> {
+	weight: Weight,
+	paid: u128,
+	_phantom: PhantomData<(T, AssetLocation, Price, Revenue)>,
+}
+
+impl<T, AssetLocation, Price, Revenue> WeightTrader
+	for RoundingTrader<T, AssetLocation, Price, Revenue>
+where
+	T: Config,
+	AssetLocation: Get<MultiLocation>,
+	Price: Get<(u128, u128)>,
+	Revenue: TakeRevenue,
+{
+	fn new() -> Self {
+		Self { weight: Weight::zero(), paid: 0, _phantom: PhantomData }
+	}
+
+	fn buy_weight(&mut self, weight: Weight, payment: Assets) -> Result<Assets, XcmError> {
+		let amount =
+			fee_for(weight, Price::get(), Pallet::<T>::fee_rounding()).ok_or(XcmError::Overflow)?;
+		let unused = if amount == 0 {
+			payment
+		} else {
+			let required: MultiAsset = (Concrete(AssetLocation::get()), amount).into();
+			payment.checked_sub(required).map_err(|_| XcmError::TooExpensive)?
+		};
+		self.weight = self.weight.saturating_add(weight);
+		self.paid = self.paid.saturating_add(amount);
+		Ok(unused)
+	}
+
+	fn refund_weight(&mut self, weight: Weight) -> Option<MultiAsset> {
+		let weight = weight.min(self.weight);
+		// Round in our favor, a message must never get back more than it paid.
+		let amount = fee_for(weight, Price::get(), FeeRounding::Down).unwrap_or(0).min(self.paid);
+		self.weight -= weight;
+		self.paid -= amount;
+		(amount > 0).then(|| (Concrete(AssetLocation::get()), amount).into())
+	}
+}
+
+impl<T, AssetLocation: Get<MultiLocation>, Price, Revenue: TakeRevenue> Drop
+	for RoundingTrader<T, AssetLocation, Price, Revenue>
+{
+	fn drop(&mut self) {
+		if self.paid > 0 {
+			Revenue::take_revenue((Concrete(AssetLocation::get()), self.paid).into());
+		}
+	}
+}
+
+/// `WeightTrader` charging for weight in one of the currencies `Currencies`, tried in the order
+/// of [`Pallet::fee_currency_order`]. The fee is paid in the first currency whose amount in the
+/// payment suffices.
+///
+/// `CurrencyLocation` gives the location of a currency, `Price` its price (numerator and
+/// denominator) per unit of `ref_time`; currencies lacking either are not accepted. Once a
+/// currency has been chosen, further purchases of the same message must pay in it, too. Fees are
+/// rounded like the ones of the [`RoundingTrader`], and handed to `Revenue` once the trader is
+/// dropped.
+pub struct MultiCurrencyTrader<T, Currencies, CurrencyLocation, Price, Revenue: TakeRevenue> {
+	weight: Weight,
+	/// The location and price of the currency fees are paid in, once chosen.
+	currency: Option<(MultiLocation, (u128, u128))>,
+	paid: u128,
+	_phantom: PhantomData<(T, Currencies, CurrencyLocation, Price, Revenue)>,
+}
+
+impl<T, Currencies, CurrencyLocation, Price, Revenue> WeightTrader
+	for MultiCurrencyTrader<T, Currencies, CurrencyLocation, Price, Revenue>
+where
+	T: Config,
+	Currencies: Get<Vec<T::FeeCurrencyId>>,
+	CurrencyLocation: Convert<T::FeeCurrencyId, Option<MultiLocation>>,
+	Price: Convert<T::FeeCurrencyId, Option<(u128, u128)>>,
+	Revenue: TakeRevenue,
+{
+	fn new() -> Self {
+		Self { weight: Weight::zero(), currency: None, paid: 0, _phantom: PhantomData }
+	}
+
+	fn buy_weight(&mut self, weight: Weight, payment: Assets) -> Result<Assets, XcmError> {
+		let candidates = match self.currency {
+			Some(currency) => vec![currency],
+			None => Pallet::<T>::fee_currency_order(Currencies::get())
+				.into_iter()
+				.filter_map(|id| {
+					Some((CurrencyLocation::convert(id.clone())?, Price::convert(id)?))
+				})
+				.collect(),
+		};
+		let rounding = Pallet::<T>::fee_rounding();
+
+		for (location, price) in candidates {
+			let amount = fee_for(weight, price, rounding).ok_or(XcmError::Overflow)?;
+			let unused = if amount == 0 {
+				payment
+			} else {
+				let required: MultiAsset = (Concrete(location), amount).into();
+				// Not enough of this currency, fall through to the next one.
+				let Ok(unused) = payment.clone().checked_sub(required) else { continue };
+				unused
+			};
+			self.currency = Some((location, price));
+			self.weight = self.weight.saturating_add(weight);
+			self.paid = self.paid.saturating_add(amount);
+			return Ok(unused)
+		}
+		Err(XcmError::TooExpensive)
+	}
+
+	fn refund_weight(&mut self, weight: Weight) -> Option<MultiAsset> {
+		let (location, price) = self.currency?;
+		let weight = weight.min(self.weight);
+		// Round in our favor, a message must never get back more than it paid.
+		let amount = fee_for(weight, price, FeeRounding::Down).unwrap_or(0).min(self.paid);
+		self.weight -= weight;
+		self.paid -= amount;
+		(amount > 0).then(|| (Concrete(location), amount).into())
+	}
+}
+
+impl<T, Currencies, CurrencyLocation, Price, Revenue: TakeRevenue> Drop
+	for MultiCurrencyTrader<T, Currencies, CurrencyLocation, Price, Revenue>
+{
+	fn drop(&mut self) {
+		if let (Some((location, _)), true) = (self.currency, self.paid > 0) {
+			Revenue::take_revenue((Concrete(location), self.paid).into());
+		}
+	}
+}
+
+/// Whether the downward message `message` is a governance or system message of the relay chain.
+///
+/// These are messages starting with a superuser `Transact` or with `UnpaidExecution`, as sent by
+/// relay governance, as well as HRMP channel notifications, query responses and version
+/// subscriptions. Anything else, including messages which can't be decoded, is an ordinary
+/// message.
+pub fn is_priority_dmp(message: &[u8]) -> bool {
+	let Ok(message) =
+		VersionedXcm::<()>::decode_all_with_depth_limit(MAX_XCM_DECODE_DEPTH, &mut &message[..])
+	else {
+		return false
+	};
+	let Ok(Xcm(instructions)) = Xcm::<()>::try_from(message) else { return false };
+	matches!(
+		instructions.first(),
+		Some(
+			Transact { origin_kind: OriginKind::Superuser, .. } |
+				UnpaidExecution { .. } |
+				HrmpNewChannelOpenRequest { .. } |
+				HrmpChannelAccepted { .. } |
+				HrmpChannelClosing { .. } |
+				QueryResponse { .. } |
+				SubscribeVersion { .. } |
+				UnsubscribeVersion
+		)
+	)
+}
+
+/// Wraps the DMP queue, handing over the priority messages of each batch of downward messages
+/// first if [`DmpOrder::Priority`] is set. Messages of the same priority keep their order.
+///
+/// `Inner` must process or queue all the messages it is handed in order, like
+/// `cumulus_pallet_dmp_queue` does.
+pub struct PrioritizedDmp<T, Inner>(PhantomData<(T, Inner)>);
+
+impl<T: Config, Inner: DmpMessageHandler> DmpMessageHandler for PrioritizedDmp<T, Inner> {
+	fn handle_dmp_messages(
+		iter: impl Iterator<Item = (RelayBlockNumber, Vec<u8>)>,
+		max_weight: Weight,
+	) -> Weight {
+		match Pallet::<T>::dmp_order() {
+			DmpOrder::Fifo => Inner::handle_dmp_messages(iter, max_weight),
+			DmpOrder::Priority => {
+				let (mut messages, ordinary): (Vec<_>, Vec<_>) =
+					iter.partition(|(_, message)| is_priority_dmp(message));
+				messages.extend(ordinary);
+				Inner::handle_dmp_messages(messages.into_iter(), max_weight)
+			},
+		}
+	}
+}
+
+/// `MessageExporter` of the executor, which exports messages to the [`ExportNetwork`] set by
+/// governance, charging `Price` for each. Messages to other networks are not applicable.
+///
+/// The message is prefixed with our universal origin and encoded into a `BridgeMessage` blob like
+/// `HaulBlobExporter` does, which is hauled by depositing an [`Event::MessageExported`].
+pub struct BlobExporter<T, Price>(PhantomData<(T, Price)>);
+
+impl<T: Config, Price: Get<MultiAssets>> ExportXcm for BlobExporter<T, Price> {
+	type Ticket = (Vec<u8>, XcmHash);
+
+	fn validate(
+		network: NetworkId,
+		_channel: u32,
+		universal_source: &mut Option<InteriorMultiLocation>,
+		destination: &mut Option<InteriorMultiLocation>,
+		message: &mut Option<Xcm<()>>,
+	) -> SendResult<Self::Ticket> {
+		if ExportNetwork::<T>::get() != Some(network) {
+			return Err(SendError::NotApplicable)
+		}
+		let dest = destination.take().ok_or(SendError::MissingArgument)?;
+		let universal_dest = match dest.pushed_front_with(GlobalConsensus(network)) {
+			Ok(universal_dest) => universal_dest,
+			Err((dest, _)) => {
+				*destination = Some(dest);
+				return Err(SendError::NotApplicable)
+			},
+		};
+		let (local_network, local_location) = universal_source
+			.take()
+			.ok_or(SendError::MissingArgument)?
+			.split_global()
+			.map_err(|()| SendError::Unroutable)?;
+		let mut message = message.take().ok_or(SendError::MissingArgument)?;
+		message.0.insert(0, DescendOrigin(local_location));
+		message.0.insert(0, UniversalOrigin(GlobalConsensus(local_network)));
+
+		let message = VersionedXcm::from(message);
+		let message_id = message.using_encoded(sp_io::hashing::blake2_256);
+		let blob = BridgeMessage { universal_dest: universal_dest.into(), message }.encode();
+		Ok(((blob, message_id), Price::get()))
+	}
+
+	fn deliver((blob, message_id): Self::Ticket) -> Result<XcmHash, SendError> {
+		Pallet::<T>::deposit_event(Event::MessageExported { message_id, blob });
+		Ok(message_id)
+	}
+}
+
+/// The number of assets in `assets`, whatever their version.
+fn asset_count(assets: &VersionedMultiAssets) -> u32 {
+	match assets {
+		VersionedMultiAssets::V2(assets) => assets.len() as u32,
+		VersionedMultiAssets::V3(assets) => assets.len() as u32,
+	}
+}
+
+/// `CallDispatcher` of the XCM executor, which rejects the calls blocked by governance before
+/// handing the others to `Inner`.
+pub struct TransactCallPolicy<T, Inner>(PhantomData<(T, Inner)>);
+
+impl<T, Inner, Call> CallDispatcher<Call> for TransactCallPolicy<T, Inner>
+where
+	T: Config,
+	Inner: CallDispatcher<Call>,
+	Call: Dispatchable<PostInfo = PostDispatchInfo> + GetCallMetadata,
+{
+	fn dispatch(
+		call: Call,
+		origin: Call::RuntimeOrigin,
+	) -> Result<PostDispatchInfo, DispatchErrorWithPostInfo<PostDispatchInfo>> {
+		let CallMetadata { pallet_name, function_name } = call.get_call_metadata();
+		if Pallet::<T>::is_transact_blocked(pallet_name, function_name) {
+			log::warn!(
+				target: LOG_TARGET,
+				"Rejecting blocked call {}::{} dispatched by Transact",
+				pallet_name,
+				function_name,
+			);
+			return Err(DispatchErrorWithPostInfo {
+				post_info: Some(Weight::zero()).into(),
+				error: Error::<T>::TransactCallBlocked.into(),
+			})
+		}
+		Inner::dispatch(call, origin)
+	}
+}
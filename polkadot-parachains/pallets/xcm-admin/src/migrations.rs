@@ -0,0 +1,191 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Helpers to preserve the XCM version negotiation state of `pallet_xcm` across migrations.
+//!
+//! Losing `SupportedVersion`, `VersionNotifiers` or `VersionNotifyTargets` makes us renegotiate
+//! the XCM version with every chain we talk to. The state can be exported before and imported
+//! after a migration, or a migration can be wrapped into a [`PreserveVersionNegotiation`].
+
+use super::*;
+use codec::Decode;
+use frame_support::{
+	storage::types::{OptionQuery, StorageDoubleMap},
+	traits::{Get, OnRuntimeUpgrade, StorageInstance},
+	Blake2_128Concat, RuntimeDebug, Twox64Concat,
+};
+use scale_info::TypeInfo;
+use xcm::{
+	latest::{QueryId, VERSION as XCM_VERSION},
+	Version as XcmVersion,
+};
+
+/// Declares the double map `$name` of `pallet_xcm`, which is private to `pallet_xcm`.
+macro_rules! xcm_pallet_double_map {
+	($name:ident, $prefix:ident, $value:ty) => {
+		struct $prefix<T>(PhantomData<T>);
+
+		impl<T: Config> StorageInstance for $prefix<T> {
+			fn pallet_prefix() -> &'static str {
+				T::XcmPallet::name()
+			}
+			const STORAGE_PREFIX: &'static str = stringify!($name);
+		}
+
+		type $name<T> = StorageDoubleMap<
+			$prefix<T>,
+			Twox64Concat,
+			XcmVersion,
+			Blake2_128Concat,
+			VersionedMultiLocation,
+			$value,
+			OptionQuery,
+		>;
+	};
+}
+
+xcm_pallet_double_map!(SupportedVersion, SupportedVersionPrefix, XcmVersion);
+xcm_pallet_double_map!(VersionNotifiers, VersionNotifiersPrefix, QueryId);
+xcm_pallet_double_map!(
+	VersionNotifyTargets,
+	VersionNotifyTargetsPrefix,
+	(QueryId, Weight, XcmVersion)
+);
+
+/// A snapshot of the version negotiation state of `pallet_xcm`, by destination.
+#[derive(Clone, Default, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub struct VersionNegotiationState {
+	/// The XCM version each destination supports.
+	pub supported_versions: Vec<(VersionedMultiLocation, XcmVersion)>,
+	/// The query ids of our version subscriptions at each destination.
+	pub version_notifiers: Vec<(VersionedMultiLocation, QueryId)>,
+	/// The destinations subscribed to our version, with their query id, the maximum weight of a
+	/// notification and the last version they have been notified of.
+	pub version_notify_targets: Vec<(VersionedMultiLocation, (QueryId, Weight, XcmVersion))>,
+}
+
+impl VersionNegotiationState {
+	/// The number of entries in the snapshot.
+	pub fn len(&self) -> usize {
+		self.supported_versions.len() +
+			self.version_notifiers.len() +
+			self.version_notify_targets.len()
+	}
+
+	/// Whether the snapshot has no entries.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+}
+
+/// Export the version negotiation state of `pallet_xcm`, whatever XCM version its destinations
+/// are stored under.
+pub fn export_version_negotiation<T: Config>() -> VersionNegotiationState {
+	VersionNegotiationState {
+		supported_versions: SupportedVersion::<T>::iter()
+			.map(|(_, dest, version)| (dest, version))
+			.collect(),
+		version_notifiers: VersionNotifiers::<T>::iter()
+			.map(|(_, dest, query_id)| (dest, query_id))
+			.collect(),
+		version_notify_targets: VersionNotifyTargets::<T>::iter()
+			.map(|(_, dest, target)| (dest, target))
+			.collect(),
+	}
+}
+
+/// Replace the version negotiation state of `pallet_xcm` with `state`, stored under the current
+/// XCM version.
+///
+/// Entries of destinations which can't be converted to the current XCM version or which
+/// `IsValid` doesn't accept are dropped. Returns the number of dropped entries.
+pub fn import_version_negotiation<T: Config, IsValid: Contains<MultiLocation>>(
+	state: VersionNegotiationState,
+) -> u32 {
+	let _ = SupportedVersion::<T>::clear(u32::MAX, None);
+	let _ = VersionNotifiers::<T>::clear(u32::MAX, None);
+	let _ = VersionNotifyTargets::<T>::clear(u32::MAX, None);
+
+	let mut dropped = 0;
+	let mut valid = |dest: VersionedMultiLocation| {
+		let dest = MultiLocation::try_from(dest)
+			.ok()
+			.filter(IsValid::contains)
+			.map(VersionedMultiLocation::from);
+		if dest.is_none() {
+			dropped += 1;
+		}
+		dest
+	};
+
+	for (dest, version) in state.supported_versions {
+		if let Some(dest) = valid(dest) {
+			SupportedVersion::<T>::insert(XCM_VERSION, dest, version);
+		}
+	}
+	for (dest, query_id) in state.version_notifiers {
+		if let Some(dest) = valid(dest) {
+			VersionNotifiers::<T>::insert(XCM_VERSION, dest, query_id);
+		}
+	}
+	for (dest, target) in state.version_notify_targets {
+		if let Some(dest) = valid(dest) {
+			VersionNotifyTargets::<T>::insert(XCM_VERSION, dest, target);
+		}
+	}
+
+	if dropped > 0 {
+		log::info!(
+			target: LOG_TARGET,
+			"Dropped {} version negotiation entries of invalid destinations",
+			dropped,
+		);
+	}
+	dropped
+}
+
+/// Runs the migration `Inner`, preserving the version negotiation state of `pallet_xcm` across
+/// it. Destinations `IsValid` doesn't accept anymore are dropped.
+pub struct PreserveVersionNegotiation<T, Inner, IsValid>(PhantomData<(T, Inner, IsValid)>);
+
+impl<T, Inner, IsValid> OnRuntimeUpgrade for PreserveVersionNegotiation<T, Inner, IsValid>
+where
+	T: Config,
+	Inner: OnRuntimeUpgrade,
+	IsValid: Contains<MultiLocation>,
+{
+	fn on_runtime_upgrade() -> Weight {
+		let state = export_version_negotiation::<T>();
+		let weight = Inner::on_runtime_upgrade();
+		let entries = state.len() as u64;
+		import_version_negotiation::<T, IsValid>(state);
+
+		log::info!(target: LOG_TARGET, "Preserved {} version negotiation entries", entries);
+		// Reading all entries, clearing them and writing the valid ones back.
+		weight.saturating_add(T::DbWeight::get().reads_writes(entries, 2 * entries + 3))
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+		Inner::pre_upgrade()
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(state: Vec<u8>) -> Result<(), &'static str> {
+		Inner::post_upgrade(state)
+	}
+}
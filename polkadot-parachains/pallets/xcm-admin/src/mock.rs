@@ -0,0 +1,510 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate as pallet_xcm_admin;
+use codec::Encode;
+use cumulus_primitives_core::{
+	relay_chain::BlockNumber as RelayBlockNumber, ChannelStatus, DmpMessageHandler, GetChannelInfo,
+	ParaId,
+};
+use frame_support::{
+	parameter_types,
+	traits::{
+		ConstU128, ConstU32, ConstU64, CrateVersion, EnsureOrigin, EqualPrivilegeOnly, Everything,
+		PalletInfoAccess,
+	},
+	weights::Weight,
+};
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, Convert, Hash, Identity, IdentityLookup},
+};
+use sp_std::{cell::RefCell, collections::vec_deque::VecDeque};
+use xcm::{
+	latest::{prelude::*, XcmHash},
+	VersionedMultiAssets,
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		XcmAdmin: pallet_xcm_admin::{Pallet, Call, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		Preimage: pallet_preimage::{Pallet, Call, Storage, Event<T>},
+		Scheduler: pallet_scheduler::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type RuntimeEvent = RuntimeEvent;
+	type BlockHashCount = frame_support::traits::ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+thread_local! {
+	/// Messages that have been delivered.
+	pub static SENT_XCM: RefCell<Vec<(MultiLocation, Xcm<()>)>> = RefCell::new(Vec::new());
+}
+
+pub fn sent_xcm() -> Vec<(MultiLocation, Xcm<()>)> {
+	SENT_XCM.with(|s| s.borrow().clone())
+}
+
+pub struct MockRouter;
+impl SendXcm for MockRouter {
+	type Ticket = (MultiLocation, Xcm<()>);
+
+	fn validate(
+		dest: &mut Option<MultiLocation>,
+		msg: &mut Option<Xcm<()>>,
+	) -> SendResult<Self::Ticket> {
+		let dest = dest.take().ok_or(SendError::MissingArgument)?;
+		let msg = msg.take().ok_or(SendError::MissingArgument)?;
+		Ok(((dest, msg), MultiAssets::new()))
+	}
+
+	fn deliver((dest, msg): Self::Ticket) -> Result<XcmHash, SendError> {
+		let hash = msg.using_encoded(sp_io::hashing::blake2_256);
+		SENT_XCM.with(|s| s.borrow_mut().push((dest, msg)));
+		Ok(hash)
+	}
+}
+
+parameter_types! {
+	/// Outbound channels and whether they are full, all other channels are closed.
+	pub static OpenChannels: Vec<(u32, bool)> = vec![];
+}
+
+pub struct MockChannelInfo;
+impl GetChannelInfo for MockChannelInfo {
+	fn get_channel_status(id: ParaId) -> ChannelStatus {
+		match OpenChannels::get().into_iter().find(|(para, _)| ParaId::from(*para) == id) {
+			Some((_, true)) => ChannelStatus::Full,
+			Some((_, false)) => ChannelStatus::Ready(1024, 1024),
+			None => ChannelStatus::Closed,
+		}
+	}
+
+	fn get_channel_max(id: ParaId) -> Option<usize> {
+		(!matches!(Self::get_channel_status(id), ChannelStatus::Closed)).then_some(1024)
+	}
+}
+
+thread_local! {
+	/// Messages that have been executed by the [`MockExecutor`].
+	pub static EXECUTED_XCM: RefCell<Vec<Xcm<RuntimeCall>>> = RefCell::new(Vec::new());
+}
+
+pub fn executed_xcm() -> Vec<Xcm<RuntimeCall>> {
+	EXECUTED_XCM.with(|e| e.borrow().clone())
+}
+
+/// Weighs one unit of `ref_time` per instruction.
+pub struct MockPrepared(Xcm<RuntimeCall>);
+impl PreparedMessage for MockPrepared {
+	fn weight_of(&self) -> Weight {
+		Weight::from_parts(self.0 .0.len() as u64, 0)
+	}
+}
+
+/// Executes only messages which pay for their execution, like the barrier of the runtimes.
+pub struct MockExecutor;
+impl ExecuteXcm<RuntimeCall> for MockExecutor {
+	type Prepared = MockPrepared;
+
+	fn prepare(message: Xcm<RuntimeCall>) -> Result<Self::Prepared, Xcm<RuntimeCall>> {
+		Ok(MockPrepared(message))
+	}
+
+	fn execute(
+		_origin: impl Into<MultiLocation>,
+		MockPrepared(message): Self::Prepared,
+		_hash: XcmHash,
+		_weight_credit: Weight,
+	) -> Outcome {
+		if !message.0.iter().any(|instruction| matches!(instruction, BuyExecution { .. })) {
+			return Outcome::Error(XcmError::Barrier)
+		}
+		EXECUTED_XCM.with(|e| e.borrow_mut().push(message));
+		Outcome::Complete(Weight::zero())
+	}
+
+	fn charge_fees(_location: impl Into<MultiLocation>, _fees: MultiAssets) -> XcmResult {
+		Ok(())
+	}
+}
+
+parameter_types! {
+	/// One unit of the fee asset per three units of `ref_time`.
+	pub const FeePrice: (u128, u128) = (1, 3);
+	pub FeeAsset: MultiLocation = MultiLocation::here();
+}
+
+parameter_types! {
+	/// The fee currencies, in the runtime's order. Currency 2 has no price.
+	pub FeeCurrencies: Vec<u32> = vec![0, 1, 2];
+}
+
+/// Fee currency `id` is at `GeneralIndex(id)`.
+pub struct MockCurrencyLocation;
+impl Convert<u32, Option<MultiLocation>> for MockCurrencyLocation {
+	fn convert(id: u32) -> Option<MultiLocation> {
+		Some(MultiLocation::new(0, X1(GeneralIndex(id.into()))))
+	}
+}
+
+/// Currency 0 costs one unit, currency 1 two units per unit of `ref_time`.
+pub struct MockCurrencyPrice;
+impl Convert<u32, Option<(u128, u128)>> for MockCurrencyPrice {
+	fn convert(id: u32) -> Option<(u128, u128)> {
+		match id {
+			0 => Some((1, 1)),
+			1 => Some((2, 1)),
+			_ => None,
+		}
+	}
+}
+
+thread_local! {
+	/// Fees handed to the [`MockRevenue`].
+	pub static REVENUE: RefCell<Vec<MultiAsset>> = RefCell::new(Vec::new());
+}
+
+pub fn revenue() -> Vec<MultiAsset> {
+	REVENUE.with(|r| r.borrow().clone())
+}
+
+pub struct MockRevenue;
+impl xcm_builder::TakeRevenue for MockRevenue {
+	fn take_revenue(revenue: MultiAsset) {
+		REVENUE.with(|r| r.borrow_mut().push(revenue));
+	}
+}
+
+thread_local! {
+	/// Downward messages waiting in the [`MockDmpQueue`].
+	pub static DMP_QUEUE: RefCell<VecDeque<Vec<u8>>> = RefCell::new(VecDeque::new());
+	/// Downward messages processed by the [`MockDmpQueue`], in order.
+	pub static PROCESSED_DMP: RefCell<Vec<Vec<u8>>> = RefCell::new(Vec::new());
+}
+
+pub fn processed_dmp() -> Vec<Vec<u8>> {
+	PROCESSED_DMP.with(|p| p.borrow().clone())
+}
+
+/// Processes one message per unit of `ref_time`, queued messages first, and queues the messages
+/// exceeding the limit, like `cumulus_pallet_dmp_queue` does.
+pub struct MockDmpQueue;
+impl DmpMessageHandler for MockDmpQueue {
+	fn handle_dmp_messages(
+		iter: impl Iterator<Item = (RelayBlockNumber, Vec<u8>)>,
+		max_weight: Weight,
+	) -> Weight {
+		DMP_QUEUE.with(|queue| {
+			let mut queue = queue.borrow_mut();
+			queue.extend(iter.map(|(_, message)| message));
+			let processed = (max_weight.ref_time() as usize).min(queue.len());
+			PROCESSED_DMP.with(|p| p.borrow_mut().extend(queue.drain(..processed)));
+			Weight::from_parts(processed as u64, 0)
+		})
+	}
+}
+
+thread_local! {
+	/// Assets deposited by the [`MockAssetTransactor`], with the location deposited into.
+	pub static DEPOSITED: RefCell<Vec<(MultiLocation, MultiAsset)>> = RefCell::new(Vec::new());
+}
+
+pub fn deposited() -> Vec<(MultiLocation, MultiAsset)> {
+	DEPOSITED.with(|d| d.borrow().clone())
+}
+
+pub struct MockAssetTransactor;
+impl xcm_executor::traits::TransactAsset for MockAssetTransactor {
+	fn deposit_asset(what: &MultiAsset, who: &MultiLocation, _context: &XcmContext) -> XcmResult {
+		DEPOSITED.with(|d| d.borrow_mut().push((*who, what.clone())));
+		Ok(())
+	}
+}
+
+/// Account `index` is at `AccountIndex64 { index }`.
+pub struct AccountToLocation;
+impl Convert<u64, MultiLocation> for AccountToLocation {
+	fn convert(index: u64) -> MultiLocation {
+		MultiLocation::new(0, X1(AccountIndex64 { network: None, index }))
+	}
+}
+
+parameter_types! {
+	pub const TreasuryAccount: u64 = 99;
+	pub const KusamaNetwork: NetworkId = NetworkId::Kusama;
+}
+
+parameter_types! {
+	pub ExportPrice: MultiAssets = vec![(Here, XcmAdmin::export_fee()).into()].into();
+}
+
+parameter_types! {
+	/// The tickets of the trapped assets, as many times as they have been trapped.
+	pub static TrappedAssets: Vec<H256> = vec![];
+}
+
+/// Trap `assets` of `origin` like `pallet_xcm` does, returning the ticket.
+pub fn trap_assets(origin: MultiLocation, assets: VersionedMultiAssets) -> H256 {
+	let ticket = BlakeTwo256::hash_of(&(origin, assets));
+	TrappedAssets::mutate(|trapped| trapped.push(ticket));
+	ticket
+}
+
+/// Claims the assets trapped with [`trap_assets`], like `pallet_xcm` does.
+pub struct MockAssetClaims;
+impl xcm_executor::traits::ClaimAssets for MockAssetClaims {
+	fn claim_assets(
+		origin: &MultiLocation,
+		ticket: &MultiLocation,
+		what: &MultiAssets,
+		_context: &XcmContext,
+	) -> bool {
+		let versioned = VersionedMultiAssets::from(what.clone());
+		let versioned = match ticket.interior {
+			X1(GeneralIndex(version)) => versioned.into_version(version as u32),
+			Here => Ok(versioned),
+			_ => Err(()),
+		};
+		let Ok(versioned) = versioned else { return false };
+		let ticket = BlakeTwo256::hash_of(&(origin, versioned));
+		TrappedAssets::mutate(|trapped| {
+			let index = trapped.iter().position(|trapped| *trapped == ticket);
+			index.map(|index| trapped.remove(index)).is_some()
+		})
+	}
+}
+
+impl xcm_executor::traits::DropAssets for MockAssetClaims {
+	fn drop_assets(
+		origin: &MultiLocation,
+		assets: xcm_executor::Assets,
+		_context: &XcmContext,
+	) -> Weight {
+		if !assets.is_empty() {
+			trap_assets(*origin, VersionedMultiAssets::from(MultiAssets::from(assets)));
+		}
+		Weight::zero()
+	}
+}
+
+/// Stands in for `pallet_xcm`, whose storage we only access by key.
+pub struct XcmPallet;
+impl PalletInfoAccess for XcmPallet {
+	fn index() -> usize {
+		31
+	}
+	fn name() -> &'static str {
+		"PolkadotXcm"
+	}
+	fn module_name() -> &'static str {
+		"pallet_xcm"
+	}
+	fn crate_version() -> CrateVersion {
+		CrateVersion::new(0, 9, 42)
+	}
+}
+
+/// The relay chain has a minimum fee of 10 by default, other reserves have none.
+pub struct MockDefaultMinXcmFee;
+impl Convert<MultiLocation, Option<u128>> for MockDefaultMinXcmFee {
+	fn convert(reserve: MultiLocation) -> Option<u128> {
+		(reserve == MultiLocation::parent()).then_some(10)
+	}
+}
+
+parameter_types! {
+	pub const MaxFeeCurrencies: u32 = 3;
+	pub const MaxFeeDestinations: u32 = 3;
+	pub const MaxScheduledPerOrigin: u32 = 2;
+	pub const MaxScheduledPerBlock: u32 = 3;
+	pub const MaxScheduleDelay: u64 = 10;
+	pub static RelayMaxUmpMessageSize: Option<u32> = None;
+	pub const DefaultDeliveryFee: pallet_xcm_admin::DeliveryFee =
+		pallet_xcm_admin::DeliveryFee { base: 10, per_byte: 1 };
+	pub RelayLockedAssets: MultiAssetFilter =
+		Wild(AllOf { id: Concrete(MultiLocation::parent()), fun: WildFungible });
+	pub RelayLocker: Vec<(MultiLocation, MultiAssetFilter)> =
+		vec![(MultiLocation::parent(), RelayLockedAssets::get())];
+	/// The indices of the relay chain's `Balances` pallet and its `transfer_keep_alive`.
+	pub const KusamaCallIndices: (u8, u8) = (4, 3);
+	pub const DefaultXcmConversionWeight: Weight = Weight::from_parts(1_000, 0);
+	pub const MaxXcmConversionWeight: Weight = Weight::from_parts(10_000, 0);
+}
+
+impl pallet_xcm_admin::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type AdminOrigin = EnsureRoot<u64>;
+	type XcmPallet = XcmPallet;
+	type CanonicalAssetLocation = Identity;
+	type RelayMaxUmpMessageSize = RelayMaxUmpMessageSize;
+	type ChannelInfo = MockChannelInfo;
+	type FeeCurrencyId = u32;
+	type MaxFeeCurrencies = MaxFeeCurrencies;
+	type DefaultMinXcmFee = MockDefaultMinXcmFee;
+	type AssetClaims = MockAssetClaims;
+	type AssetTransactor = MockAssetTransactor;
+	type AccountIdToLocation = AccountToLocation;
+	type MaxFeeDestinations = MaxFeeDestinations;
+	type RuntimeCall = RuntimeCall;
+	type ScheduleOrigin = SiblingScheduleOrigin;
+	type Scheduler = Scheduler;
+	type Preimages = Preimage;
+	type ScheduledCallFilter = Everything;
+	type MaxScheduledPerOrigin = MaxScheduledPerOrigin;
+	type MaxScheduledPerBlock = MaxScheduledPerBlock;
+	type MaxScheduleDelay = MaxScheduleDelay;
+	type DefaultXcmFeeReceiver = TreasuryAccount;
+	type MaxFeeWaivedSiblings = ConstU32<3>;
+	type DefaultRelayNetwork = KusamaNetwork;
+	type DefaultFeePerRefTime = MockCurrencyPrice;
+	type DefaultSiblingDeliveryFee = DefaultDeliveryFee;
+	type DefaultXcmConversionWeight = DefaultXcmConversionWeight;
+	type MaxXcmConversionWeight = MaxXcmConversionWeight;
+	type MaxUniversalAliases = ConstU32<2>;
+	type DefaultExportFee = ConstU128<10>;
+	type DefaultAssetsForTransferLimit = ConstU32<2>;
+	type MaxAssetsForTransferLimit = ConstU32<4>;
+	type DefaultTrustedLockers = RelayLocker;
+	type MaxTrustedLockers = ConstU32<2>;
+	type RelayCallIndices = (u8, u8);
+	type DefaultRelayCallIndices = KusamaCallIndices;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = MockBenchmarkHelper;
+	type WeightInfo = ();
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type Balance = u64;
+	type RuntimeEvent = RuntimeEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU64<1>;
+	type AccountStore = System;
+	type WeightInfo = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type HoldIdentifier = ();
+	type FreezeIdentifier = ();
+	type MaxHolds = ();
+	type MaxFreezes = ();
+}
+
+impl pallet_preimage::Config for Test {
+	type WeightInfo = ();
+	type RuntimeEvent = RuntimeEvent;
+	type Currency = Balances;
+	type ManagerOrigin = EnsureRoot<u64>;
+	type BaseDeposit = ConstU64<0>;
+	type ByteDeposit = ConstU64<0>;
+}
+
+parameter_types! {
+	pub MaximumSchedulerWeight: Weight = Weight::from_parts(1_000_000_000_000, u64::MAX);
+}
+
+impl pallet_scheduler::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeOrigin = RuntimeOrigin;
+	type PalletsOrigin = OriginCaller;
+	type RuntimeCall = RuntimeCall;
+	type MaximumWeight = MaximumSchedulerWeight;
+	type ScheduleOrigin = EnsureRoot<u64>;
+	type MaxScheduledPerBlock = ConstU32<10>;
+	type WeightInfo = ();
+	type OriginPrivilegeCmp = EqualPrivilegeOnly;
+	type Preimages = Preimage;
+}
+
+/// Signed origins schedule calls as the sibling with their account id as parachain id.
+pub struct SiblingScheduleOrigin;
+impl EnsureOrigin<RuntimeOrigin> for SiblingScheduleOrigin {
+	type Success = MultiLocation;
+
+	fn try_origin(origin: RuntimeOrigin) -> Result<Self::Success, RuntimeOrigin> {
+		let who = frame_system::ensure_signed(origin.clone()).map_err(|_| origin)?;
+		Ok(MultiLocation::new(1, X1(Parachain(who as u32))))
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn try_successful_origin() -> Result<RuntimeOrigin, ()> {
+		Ok(RuntimeOrigin::signed(1000))
+	}
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+pub struct MockBenchmarkHelper;
+#[cfg(feature = "runtime-benchmarks")]
+impl crate::benchmarking::BenchmarkHelper<u32, RuntimeCall> for MockBenchmarkHelper {
+	fn fee_currencies() -> Vec<u32> {
+		(0..MaxFeeCurrencies::get()).collect()
+	}
+
+	fn rescuable_assets() -> Vec<MultiAsset> {
+		(0..4).map(|index| (GeneralIndex(index), 1).into()).collect()
+	}
+
+	fn trap_assets(origin: &MultiLocation, assets: MultiAssets) {
+		trap_assets(*origin, assets.into());
+	}
+
+	fn schedulable_call() -> RuntimeCall {
+		RuntimeCall::System(frame_system::Call::remark { remark: vec![] })
+	}
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
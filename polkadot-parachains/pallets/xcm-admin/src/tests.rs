@@ -0,0 +1,2070 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{
+	apply_default_dest_weights, holding_amounts, is_priority_dmp,
+	migrations::{export_version_negotiation, import_version_negotiation},
+	mock::*,
+	split_fee, version_discovery_queue_key, BlobExporter, DeliveryFee, DenyExcessiveHolding,
+	DenyTrivialWeight, DestinationKind, DmpOrder, Error, Event, FeeDestination, FeeRounding,
+	FeeWaivedOrigins, IndexTrappedAssets, IsEnabledReserve, ListedUniversalAliases, MeterInbound,
+	MultiCurrencyTrader, OpenChannelDestinations, PrioritizedDmp, RemainderDestination,
+	ReserveTransfersEnabled, RoundingTrader, SkipUnsupportedInstructions, SplitFees,
+	TransactCallPolicy, UmpSizeLimit, UnsupportedInstructionPolicy, WithDefaultDestWeight,
+};
+use codec::Encode;
+use cumulus_primitives_core::DmpMessageHandler;
+use frame_support::{
+	assert_noop, assert_ok,
+	dispatch::GetCallName,
+	storage::unhashed,
+	traits::{
+		Contains, ContainsPair, Everything, Get, Hooks, ProcessMessageError, UnfilteredDispatchable,
+	},
+	weights::Weight,
+	Blake2_128Concat, BoundedVec, Twox64Concat,
+};
+use sp_core::H256;
+use sp_runtime::{
+	traits::{Convert, Identity},
+	DispatchError, DispatchResult, Perbill,
+};
+use xcm::{
+	latest::{prelude::*, XcmHash},
+	Version as XcmVersion, VersionedMultiAssets, VersionedMultiLocation, VersionedXcm,
+};
+use xcm_builder::{AllowTopLevelPaidExecutionFrom, BridgeMessage, TakeRevenue};
+use xcm_executor::{
+	traits::{
+		export_xcm, CallDispatcher, ClaimAssets, DropAssets, FeeManager, FeeReason, ShouldExecute,
+		WeightTrader,
+	},
+	Assets,
+};
+
+fn relay_call() -> Xcm<()> {
+	Xcm(vec![ClearOrigin])
+}
+
+fn last_event() -> RuntimeEvent {
+	System::events().pop().expect("an event has been deposited").event
+}
+
+fn sibling(para_id: u32) -> VersionedMultiLocation {
+	MultiLocation::new(1, X1(Parachain(para_id))).into()
+}
+
+fn set_version_discovery_queue(queue: Vec<(VersionedMultiLocation, u32)>) {
+	unhashed::put(&version_discovery_queue_key::<Test>(), &queue);
+}
+
+#[test]
+fn clear_version_discovery_queue_removes_only_given_destination() {
+	new_test_ext().execute_with(|| {
+		set_version_discovery_queue(vec![(sibling(1000), 3), (sibling(2000), 1)]);
+
+		assert_ok!(XcmAdmin::clear_version_discovery_queue(
+			RuntimeOrigin::root(),
+			Box::new(sibling(1000))
+		));
+
+		assert_eq!(XcmAdmin::version_discovery_queue(), vec![(sibling(2000), 1)]);
+		assert_eq!(
+			last_event(),
+			Event::VersionDiscoveryEntriesCleared { dest: sibling(1000), removed: 1 }.into()
+		);
+	});
+}
+
+#[test]
+fn clear_version_discovery_queue_fails_for_unknown_destination() {
+	new_test_ext().execute_with(|| {
+		set_version_discovery_queue(vec![(sibling(2000), 1)]);
+
+		assert_noop!(
+			XcmAdmin::clear_version_discovery_queue(RuntimeOrigin::root(), Box::new(sibling(1000))),
+			Error::<Test>::NotInVersionDiscoveryQueue
+		);
+	});
+}
+
+/// A call of each admin setter, all the calls but the ones of the scheduling origin.
+fn admin_calls() -> Vec<crate::Call<Test>> {
+	use crate::Call::*;
+	let parent = || Box::new(VersionedMultiLocation::from(MultiLocation::parent()));
+	let assets = VersionedMultiAssets::from(MultiAssets::from(vec![(Here, 100).into()]));
+	vec![
+		clear_version_discovery_queue { dest: Box::new(sibling(1000)) },
+		set_reserve_transfer_enabled { asset: Box::new(teer().into()), enabled: false },
+		set_max_ump_message_size { limit: Some(50) },
+		set_transact_blocked {
+			pallet_name: b"System".to_vec(),
+			function_name: None,
+			blocked: true,
+		},
+		set_unsupported_instruction_policy { policy: UnsupportedInstructionPolicy::Skip },
+		set_fee_rounding { rounding: FeeRounding::Up },
+		set_dmp_order { order: DmpOrder::Priority },
+		set_fee_currency_priority { priority: BoundedVec::truncate_from(vec![1]) },
+		set_max_holding_amount { asset: parent(), limit: Some(1) },
+		set_min_execution_weight { weight: None },
+		set_default_dest_weight { kind: DestinationKind::Relay, weight: None },
+		set_fee_split { split: BoundedVec::default() },
+		rescue_trapped_assets {
+			ticket: trap_assets(Parent.into(), assets.clone()),
+			trapped_by: parent(),
+			assets: Box::new(assets),
+			beneficiary: 7,
+		},
+		set_min_xcm_fee { reserve: parent(), fee: Some(Some(1)) },
+		set_export_network { network: Some(Polkadot) },
+		set_reserve_transfers_enabled { enabled: false },
+		set_xcm_fee_receiver { receiver: Some(7) },
+		set_fee_waived_siblings { siblings: BoundedVec::truncate_from(vec![2000]) },
+		set_relay_network { network: Some(NetworkId::Polkadot) },
+		set_remainder_policy { policy: RemainderDestination::Treasury },
+		set_schedule_delay_limit { limit: Some(3) },
+		set_fee_per_ref_time { currency: 1, price: Some(Some((3, 2))) },
+		set_sibling_delivery_fee { fee: None },
+		set_xcm_conversion_weight { weight: None },
+		set_universal_aliases { aliases: BoundedVec::default() },
+		set_export_fee { fee: Some(25) },
+		set_assets_for_transfer_limit { limit: Some(3) },
+		set_trusted_lockers { lockers: None },
+		set_relay_call_indices { indices: Some((5, 3)) },
+	]
+}
+
+#[test]
+fn admin_calls_require_admin_origin() {
+	new_test_ext().execute_with(|| {
+		set_version_discovery_queue(vec![(sibling(1000), 1)]);
+
+		let calls = admin_calls();
+		let mut names: Vec<_> = calls.iter().map(|call| call.get_call_name()).collect();
+		names.extend(["schedule_call", "dispatch_scheduled"]);
+		names.sort();
+		let mut all_names = crate::Call::<Test>::get_call_names().to_vec();
+		all_names.sort();
+		assert_eq!(names, all_names, "every admin call is checked");
+
+		for call in calls {
+			assert_noop!(
+				call.dispatch_bypass_filter(RuntimeOrigin::signed(1)).map_err(|e| e.error),
+				DispatchError::BadOrigin
+			);
+		}
+	});
+}
+
+fn asset(location: MultiLocation) -> MultiAsset {
+	(location, 1_000u128).into()
+}
+
+fn teer() -> MultiLocation {
+	MultiLocation::new(0, X1(GeneralIndex(0)))
+}
+
+fn ksm() -> MultiLocation {
+	MultiLocation::parent()
+}
+
+type IsReserve = IsEnabledReserve<Test, Everything>;
+
+#[test]
+fn disabling_reserve_transfer_blocks_only_that_asset() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAdmin::set_reserve_transfer_enabled(
+			RuntimeOrigin::root(),
+			Box::new(teer().into()),
+			false
+		));
+		assert_eq!(
+			last_event(),
+			Event::ReserveTransferToggled { asset: teer().into(), enabled: false }.into()
+		);
+
+		let sibling = MultiLocation::new(1, X1(Parachain(1000)));
+		assert!(!IsReserve::contains(&asset(teer()), &sibling));
+		assert!(IsReserve::contains(&asset(ksm()), &sibling));
+
+		assert!(!ReserveTransfersEnabled::<Test>::contains(&(
+			Here.into(),
+			vec![asset(ksm()), asset(teer())]
+		)));
+		assert!(ReserveTransfersEnabled::<Test>::contains(&(Here.into(), vec![asset(ksm())])));
+	});
+}
+
+#[test]
+fn reserve_transfer_can_be_enabled_again() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAdmin::set_reserve_transfer_enabled(
+			RuntimeOrigin::root(),
+			Box::new(teer().into()),
+			false
+		));
+		assert_ok!(XcmAdmin::set_reserve_transfer_enabled(
+			RuntimeOrigin::root(),
+			Box::new(teer().into()),
+			true
+		));
+
+		assert!(IsReserve::contains(&asset(teer()), &Parent.into()));
+		assert!(ReserveTransfersEnabled::<Test>::contains(&(Here.into(), vec![asset(teer())])));
+	});
+}
+
+#[test]
+fn pausing_reserve_transfers_blocks_all_assets() {
+	new_test_ext().execute_with(|| {
+		let transfer = (Here.into(), vec![asset(ksm())]);
+		assert!(ReserveTransfersEnabled::<Test>::contains(&transfer));
+
+		assert_ok!(XcmAdmin::set_reserve_transfers_enabled(RuntimeOrigin::root(), false));
+		assert_eq!(last_event(), Event::ReserveTransfersToggled { enabled: false }.into());
+		assert!(!ReserveTransfersEnabled::<Test>::contains(&transfer));
+		// Inbound reserve transfers are not paused.
+		assert!(IsReserve::contains(&asset(ksm()), &Parent.into()));
+
+		assert_ok!(XcmAdmin::set_reserve_transfers_enabled(RuntimeOrigin::root(), true));
+		assert!(ReserveTransfersEnabled::<Test>::contains(&transfer));
+	});
+}
+
+type OpenChannelDests = OpenChannelDestinations<Test, Everything>;
+
+fn beneficiary_on(para_id: u32) -> MultiLocation {
+	MultiLocation::new(1, X2(Parachain(para_id), AccountId32 { network: None, id: [1; 32] }))
+}
+
+#[test]
+fn reserve_transfer_to_sibling_without_channel_is_rejected() {
+	new_test_ext().execute_with(|| {
+		OpenChannels::set(vec![(1000, false)]);
+
+		assert_eq!(
+			XcmAdmin::ensure_open_channel(&beneficiary_on(2000)),
+			Err(Error::<Test>::NoOpenChannel)
+		);
+		assert!(!OpenChannelDests::contains(&beneficiary_on(2000)));
+	});
+}
+
+#[test]
+fn reserve_transfer_to_sibling_with_channel_proceeds() {
+	new_test_ext().execute_with(|| {
+		OpenChannels::set(vec![(1000, false), (2000, true)]);
+
+		assert!(OpenChannelDests::contains(&beneficiary_on(1000)));
+		// A full channel is still open, the message is queued.
+		assert!(OpenChannelDests::contains(&beneficiary_on(2000)));
+	});
+}
+
+#[test]
+fn reserve_transfer_to_relay_chain_needs_no_channel() {
+	new_test_ext().execute_with(|| {
+		let beneficiary = MultiLocation::new(1, X1(AccountId32 { network: None, id: [1; 32] }));
+		assert!(OpenChannelDests::contains(&beneficiary));
+		assert!(OpenChannelDests::contains(&Parent.into()));
+	});
+}
+
+type UmpRouter = UmpSizeLimit<Test, (), MockRouter>;
+
+/// A message which encodes to exactly `size` bytes, as sent by the [`UmpRouter`].
+fn message_of_size(size: u32) -> Xcm<()> {
+	let message = |len| Xcm(vec![ClearTopic; len]);
+	let overhead = VersionedXcm::from(message(0)).encoded_size() as u32;
+	let sized = message((size - overhead) as usize);
+	assert_eq!(VersionedXcm::from(sized.clone()).encoded_size() as u32, size);
+	sized
+}
+
+#[test]
+fn ump_messages_are_not_limited_by_default() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(send_xcm::<UmpRouter>(Parent.into(), message_of_size(60)));
+	});
+}
+
+#[test]
+fn ump_messages_up_to_the_limit_pass_unchanged() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAdmin::set_max_ump_message_size(RuntimeOrigin::root(), Some(50)));
+		System::assert_last_event(Event::MaxUmpMessageSizeSet { limit: Some(50) }.into());
+
+		assert_ok!(send_xcm::<UmpRouter>(Parent.into(), message_of_size(50)));
+		assert_eq!(sent_xcm(), vec![(Parent.into(), message_of_size(50))]);
+	});
+}
+
+#[test]
+fn oversized_ump_message_is_rejected_with_event() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAdmin::set_max_ump_message_size(RuntimeOrigin::root(), Some(50)));
+
+		assert_eq!(
+			send_xcm::<UmpRouter>(Parent.into(), message_of_size(51)),
+			Err(SendError::ExceedsMaxMessageSize)
+		);
+		System::assert_last_event(Event::UmpMessageTooBig { size: 51, limit: 50 }.into());
+		assert!(sent_xcm().is_empty());
+	});
+}
+
+#[test]
+fn relay_limit_applies_if_lower() {
+	new_test_ext().execute_with(|| {
+		RelayMaxUmpMessageSize::set(Some(40));
+		assert_ok!(XcmAdmin::set_max_ump_message_size(RuntimeOrigin::root(), Some(50)));
+
+		assert_eq!(
+			send_xcm::<UmpRouter>(Parent.into(), message_of_size(41)),
+			Err(SendError::ExceedsMaxMessageSize)
+		);
+		System::assert_last_event(Event::UmpMessageTooBig { size: 41, limit: 40 }.into());
+	});
+}
+
+#[test]
+fn ump_limit_does_not_apply_to_siblings() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAdmin::set_max_ump_message_size(RuntimeOrigin::root(), Some(50)));
+
+		let sibling = MultiLocation::new(1, X1(Parachain(1000)));
+		assert_ok!(send_xcm::<UmpRouter>(sibling, message_of_size(60)));
+	});
+}
+
+type Dispatcher = TransactCallPolicy<Test, RuntimeCall>;
+
+fn remark() -> RuntimeCall {
+	RuntimeCall::System(frame_system::Call::remark { remark: vec![] })
+}
+
+fn remark_with_event() -> RuntimeCall {
+	RuntimeCall::System(frame_system::Call::remark_with_event { remark: vec![] })
+}
+
+fn set_transact_blocked(function_name: Option<&str>, blocked: bool) {
+	assert_ok!(XcmAdmin::set_transact_blocked(
+		RuntimeOrigin::root(),
+		b"System".to_vec(),
+		function_name.map(|name| name.as_bytes().to_vec()),
+		blocked
+	));
+}
+
+#[test]
+fn transact_calls_are_allowed_by_default() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Dispatcher::dispatch(remark(), RuntimeOrigin::signed(1)));
+	});
+}
+
+#[test]
+fn blocked_pallet_cannot_be_dispatched() {
+	new_test_ext().execute_with(|| {
+		set_transact_blocked(None, true);
+		System::assert_last_event(
+			Event::TransactBlockedSet {
+				pallet_name: b"System".to_vec(),
+				function_name: None,
+				blocked: true,
+			}
+			.into(),
+		);
+
+		for call in [remark(), remark_with_event()] {
+			let error = Dispatcher::dispatch(call, RuntimeOrigin::root()).unwrap_err();
+			assert_eq!(error.error, Error::<Test>::TransactCallBlocked.into());
+		}
+	});
+}
+
+#[test]
+fn blocked_call_does_not_block_the_rest_of_the_pallet() {
+	new_test_ext().execute_with(|| {
+		set_transact_blocked(Some("remark"), true);
+
+		let error = Dispatcher::dispatch(remark(), RuntimeOrigin::signed(1)).unwrap_err();
+		assert_eq!(error.error, Error::<Test>::TransactCallBlocked.into());
+		assert_ok!(Dispatcher::dispatch(remark_with_event(), RuntimeOrigin::signed(1)));
+	});
+}
+
+#[test]
+fn unblocked_pallet_can_be_dispatched_again() {
+	new_test_ext().execute_with(|| {
+		set_transact_blocked(None, true);
+		set_transact_blocked(None, false);
+
+		assert_ok!(Dispatcher::dispatch(remark(), RuntimeOrigin::signed(1)));
+	});
+}
+
+#[test]
+fn transact_blocked_names_are_bounded() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			XcmAdmin::set_transact_blocked(RuntimeOrigin::root(), vec![b'a'; 65], None, true),
+			Error::<Test>::NameTooLong
+		);
+		assert_noop!(
+			XcmAdmin::set_transact_blocked(
+				RuntimeOrigin::root(),
+				b"System".to_vec(),
+				Some(vec![b'a'; 65]),
+				true
+			),
+			Error::<Test>::NameTooLong
+		);
+	});
+}
+
+type Executor = SkipUnsupportedInstructions<Test, MockExecutor>;
+
+fn execute(message: Xcm<RuntimeCall>) -> Outcome {
+	Executor::execute_xcm(Parent, message, [0; 32], Weight::MAX)
+}
+
+fn set_policy(policy: UnsupportedInstructionPolicy) {
+	assert_ok!(XcmAdmin::set_unsupported_instruction_policy(RuntimeOrigin::root(), policy));
+}
+
+fn channel_accepted() -> Instruction<RuntimeCall> {
+	HrmpChannelAccepted { recipient: 1000 }
+}
+
+/// A message paying for its execution, with `instructions` in the middle.
+fn paid_message(instructions: Vec<Instruction<RuntimeCall>>) -> Xcm<RuntimeCall> {
+	let mut message = vec![
+		ReserveAssetDeposited(asset(ksm()).into()),
+		BuyExecution { fees: asset(ksm()), weight_limit: Unlimited },
+	];
+	message.extend(instructions);
+	message.push(DepositAsset { assets: AllCounted(1).into(), beneficiary: Here.into() });
+	Xcm(message)
+}
+
+fn skipped_events() -> Vec<Vec<Vec<u8>>> {
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			RuntimeEvent::XcmAdmin(Event::UnsupportedInstructionsSkipped { instructions }) =>
+				Some(instructions),
+			_ => None,
+		})
+		.collect()
+}
+
+#[test]
+fn unsupported_instructions_fail_fast_by_default() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			XcmAdmin::unsupported_instruction_policy(),
+			UnsupportedInstructionPolicy::FailFast
+		);
+
+		let message = paid_message(vec![channel_accepted()]);
+		assert_eq!(execute(message.clone()), Outcome::Complete(Weight::zero()));
+		assert_eq!(executed_xcm(), vec![message]);
+		assert!(skipped_events().is_empty());
+	});
+}
+
+#[test]
+fn skip_mode_skips_benign_unsupported_instruction_with_event() {
+	new_test_ext().execute_with(|| {
+		set_policy(UnsupportedInstructionPolicy::Skip);
+		assert_eq!(
+			last_event(),
+			Event::UnsupportedInstructionPolicySet { policy: UnsupportedInstructionPolicy::Skip }
+				.into()
+		);
+
+		let message = paid_message(vec![channel_accepted()]);
+		assert_eq!(execute(message), Outcome::Complete(Weight::zero()));
+		assert_eq!(executed_xcm(), vec![paid_message(vec![])]);
+		assert_eq!(skipped_events(), vec![vec![b"HrmpChannelAccepted".to_vec()]]);
+	});
+}
+
+#[test]
+fn skip_mode_never_skips_asset_handling_instructions() {
+	new_test_ext().execute_with(|| {
+		set_policy(UnsupportedInstructionPolicy::Skip);
+
+		let exchange =
+			ExchangeAsset { give: AllCounted(1).into(), want: asset(teer()).into(), maximal: true };
+		let message = paid_message(vec![exchange]);
+		execute(message.clone());
+
+		assert_eq!(executed_xcm(), vec![message]);
+		assert!(skipped_events().is_empty());
+	});
+}
+
+#[test]
+fn skip_mode_does_not_waive_fee_payment() {
+	new_test_ext().execute_with(|| {
+		set_policy(UnsupportedInstructionPolicy::Skip);
+
+		let unpaid = Xcm(vec![channel_accepted(), ClearOrigin]);
+		assert_eq!(execute(unpaid), Outcome::Error(XcmError::Barrier));
+		assert!(executed_xcm().is_empty());
+	});
+}
+
+type Trader = RoundingTrader<Test, FeeAsset, FeePrice, MockRevenue>;
+
+fn ref_time(ref_time: u64) -> Weight {
+	Weight::from_parts(ref_time, 0)
+}
+
+fn fee(amount: u128) -> MultiAsset {
+	(FeeAsset::get(), amount).into()
+}
+
+/// Buy `weight` with a payment of 10 units, returning what has been charged.
+fn charge(trader: &mut Trader, weight: Weight) -> u128 {
+	let unused = trader.buy_weight(weight, fee(10).into()).expect("payment suffices");
+	match unused.fungible.get(&Concrete(FeeAsset::get())) {
+		Some(left) => 10 - left,
+		None => 10,
+	}
+}
+
+#[test]
+fn fees_are_rounded_down_by_default() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(XcmAdmin::fee_rounding(), FeeRounding::Down);
+		// 4 / 3 and 5 / 3 units.
+		assert_eq!(charge(&mut Trader::new(), ref_time(4)), 1);
+		assert_eq!(charge(&mut Trader::new(), ref_time(5)), 1);
+	});
+}
+
+#[test]
+fn fees_are_rounded_as_configured() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAdmin::set_fee_rounding(RuntimeOrigin::root(), FeeRounding::Up));
+		assert_eq!(last_event(), Event::FeeRoundingSet { rounding: FeeRounding::Up }.into());
+		assert_eq!(charge(&mut Trader::new(), ref_time(4)), 2);
+		assert_eq!(charge(&mut Trader::new(), ref_time(5)), 2);
+		assert_eq!(charge(&mut Trader::new(), ref_time(6)), 2);
+
+		assert_ok!(XcmAdmin::set_fee_rounding(RuntimeOrigin::root(), FeeRounding::Nearest));
+		assert_eq!(charge(&mut Trader::new(), ref_time(4)), 1);
+		assert_eq!(charge(&mut Trader::new(), ref_time(5)), 2);
+	});
+}
+
+#[test]
+fn refunds_never_exceed_the_charge() {
+	new_test_ext().execute_with(|| {
+		for rounding in [FeeRounding::Down, FeeRounding::Up, FeeRounding::Nearest] {
+			assert_ok!(XcmAdmin::set_fee_rounding(RuntimeOrigin::root(), rounding));
+			for weight in 1..=9 {
+				let mut trader = Trader::new();
+				let charged = charge(&mut trader, ref_time(weight));
+				let refunded = trader
+					.refund_weight(ref_time(weight))
+					.map(|asset| match asset.fun {
+						Fungible(amount) => amount,
+						NonFungible(_) => unreachable!("fees are fungible"),
+					})
+					.unwrap_or(0);
+				assert!(
+					refunded <= charged,
+					"{:?}: refunded {} of {}",
+					rounding,
+					refunded,
+					charged
+				);
+				// Refunds round down.
+				assert_eq!(refunded, (weight / 3) as u128);
+			}
+		}
+	});
+}
+
+#[test]
+fn refund_is_capped_to_the_fees_paid() {
+	new_test_ext().execute_with(|| {
+		// Two purchases of 2 / 3 units are each rounded down to nothing.
+		let mut trader = Trader::new();
+		assert_eq!(charge(&mut trader, ref_time(2)), 0);
+		assert_eq!(charge(&mut trader, ref_time(2)), 0);
+
+		// Refunding all of it would be 4 / 3 units, rounded down.
+		assert_eq!(trader.refund_weight(ref_time(4)), None);
+	});
+}
+
+#[test]
+fn collected_fees_go_to_revenue() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAdmin::set_fee_rounding(RuntimeOrigin::root(), FeeRounding::Up));
+		let mut trader = Trader::new();
+		charge(&mut trader, ref_time(5));
+		assert_eq!(trader.refund_weight(ref_time(3)), Some(fee(1)));
+		drop(trader);
+
+		assert_eq!(revenue(), vec![fee(1)]);
+	});
+}
+
+type CurrencyTrader =
+	MultiCurrencyTrader<Test, FeeCurrencies, MockCurrencyLocation, MockCurrencyPrice, MockRevenue>;
+
+fn currency(id: u32, amount: u128) -> MultiAsset {
+	(MultiLocation::new(0, X1(GeneralIndex(id.into()))), amount).into()
+}
+
+fn payment(assets: Vec<MultiAsset>) -> xcm_executor::Assets {
+	MultiAssets::from(assets).into()
+}
+
+fn prioritize(priority: Vec<u32>) -> DispatchResult {
+	XcmAdmin::set_fee_currency_priority(RuntimeOrigin::root(), BoundedVec::truncate_from(priority))
+}
+
+#[test]
+fn fees_are_paid_in_the_first_currency_by_default() {
+	new_test_ext().execute_with(|| {
+		let unused = CurrencyTrader::new()
+			.buy_weight(ref_time(4), payment(vec![currency(0, 10), currency(1, 10)]))
+			.unwrap();
+		assert_eq!(unused, payment(vec![currency(0, 6), currency(1, 10)]));
+	});
+}
+
+#[test]
+fn fees_are_paid_in_the_prioritized_currency() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(prioritize(vec![1, 0]));
+		assert_eq!(last_event(), Event::FeeCurrencyPrioritySet { priority: vec![1, 0] }.into());
+
+		let mut trader = CurrencyTrader::new();
+		let unused = trader
+			.buy_weight(ref_time(4), payment(vec![currency(0, 10), currency(1, 10)]))
+			.unwrap();
+		assert_eq!(unused, payment(vec![currency(0, 10), currency(1, 2)]));
+		assert_eq!(trader.refund_weight(ref_time(1)), Some(currency(1, 2)));
+		drop(trader);
+
+		assert_eq!(revenue(), vec![currency(1, 6)]);
+	});
+}
+
+#[test]
+fn insufficient_prioritized_currency_falls_through() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(prioritize(vec![1, 0]));
+
+		let unused = CurrencyTrader::new()
+			.buy_weight(ref_time(4), payment(vec![currency(0, 10), currency(1, 7)]))
+			.unwrap();
+		assert_eq!(unused, payment(vec![currency(0, 6), currency(1, 7)]));
+	});
+}
+
+#[test]
+fn unprioritized_currencies_are_tried_last() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(prioritize(vec![1]));
+		assert_eq!(XcmAdmin::fee_currency_order(vec![0, 1, 2]), vec![1, 0, 2]);
+
+		let unused = CurrencyTrader::new()
+			.buy_weight(ref_time(4), payment(vec![currency(0, 10), currency(1, 1)]))
+			.unwrap();
+		assert_eq!(unused, payment(vec![currency(0, 6), currency(1, 1)]));
+	});
+}
+
+#[test]
+fn insufficient_currencies_are_too_expensive() {
+	new_test_ext().execute_with(|| {
+		// Currency 2 has no price, it is not accepted at all.
+		let error = CurrencyTrader::new()
+			.buy_weight(
+				ref_time(4),
+				payment(vec![currency(0, 3), currency(1, 7), currency(2, 100)]),
+			)
+			.unwrap_err();
+		assert_eq!(error, XcmError::TooExpensive);
+	});
+}
+
+#[test]
+fn further_purchases_pay_in_the_chosen_currency() {
+	new_test_ext().execute_with(|| {
+		let mut trader = CurrencyTrader::new();
+		trader.buy_weight(ref_time(4), payment(vec![currency(0, 10)])).unwrap();
+		assert_eq!(
+			trader.buy_weight(ref_time(1), payment(vec![currency(1, 10)])),
+			Err(XcmError::TooExpensive)
+		);
+	});
+}
+
+#[test]
+fn duplicate_fee_currency_is_rejected() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(prioritize(vec![1, 0, 1]), Error::<Test>::DuplicateFeeCurrency);
+	});
+}
+
+#[test]
+fn fee_per_ref_time_overrides_the_default() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(XcmAdmin::fee_per_ref_time(1), Some((2, 1)));
+
+		assert_ok!(XcmAdmin::set_fee_per_ref_time(RuntimeOrigin::root(), 1, Some(Some((3, 2)))));
+		assert_eq!(
+			last_event(),
+			Event::FeePerRefTimeSet { currency: 1, price: Some(Some((3, 2))) }.into()
+		);
+		assert_eq!(XcmAdmin::fee_per_ref_time(1), Some((3, 2)));
+
+		assert_ok!(XcmAdmin::set_fee_per_ref_time(RuntimeOrigin::root(), 0, Some(None)));
+		assert_eq!(XcmAdmin::fee_per_ref_time(0), None);
+		assert_ok!(XcmAdmin::set_fee_per_ref_time(RuntimeOrigin::root(), 2, Some(Some((1, 1)))));
+		assert_eq!(XcmAdmin::fee_per_ref_time(2), Some((1, 1)));
+
+		assert_ok!(XcmAdmin::set_fee_per_ref_time(RuntimeOrigin::root(), 1, None));
+		assert_eq!(XcmAdmin::fee_per_ref_time(1), Some((2, 1)));
+	});
+}
+
+#[test]
+fn fee_per_ref_time_with_a_zero_denominator_is_rejected() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			XcmAdmin::set_fee_per_ref_time(RuntimeOrigin::root(), 1, Some(Some((1, 0)))),
+			Error::<Test>::ZeroPriceDenominator
+		);
+	});
+}
+
+#[test]
+fn sibling_delivery_fee_overrides_the_default() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(XcmAdmin::sibling_delivery_fee(), DefaultDeliveryFee::get());
+
+		let fee = DeliveryFee { base: 20, per_byte: 3 };
+		assert_ok!(XcmAdmin::set_sibling_delivery_fee(RuntimeOrigin::root(), Some(fee)));
+		assert_eq!(last_event(), Event::SiblingDeliveryFeeSet { fee: Some(fee) }.into());
+		assert_eq!(XcmAdmin::sibling_delivery_fee(), fee);
+
+		assert_ok!(XcmAdmin::set_sibling_delivery_fee(RuntimeOrigin::root(), None));
+		assert_eq!(XcmAdmin::sibling_delivery_fee(), DefaultDeliveryFee::get());
+	});
+}
+
+#[test]
+fn xcm_conversion_weight_overrides_the_default() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(XcmAdmin::xcm_conversion_weight(), DefaultXcmConversionWeight::get());
+
+		assert_noop!(
+			XcmAdmin::set_xcm_conversion_weight(
+				RuntimeOrigin::root(),
+				Some(MaxXcmConversionWeight::get().saturating_add(Weight::from_parts(1, 0)))
+			),
+			Error::<Test>::XcmConversionWeightTooHigh
+		);
+
+		let weight = MaxXcmConversionWeight::get();
+		assert_ok!(XcmAdmin::set_xcm_conversion_weight(RuntimeOrigin::root(), Some(weight)));
+		assert_eq!(last_event(), Event::XcmConversionWeightSet { weight: Some(weight) }.into());
+		assert_eq!(XcmAdmin::xcm_conversion_weight(), weight);
+
+		assert_ok!(XcmAdmin::set_xcm_conversion_weight(RuntimeOrigin::root(), None));
+		assert_eq!(XcmAdmin::xcm_conversion_weight(), DefaultXcmConversionWeight::get());
+	});
+}
+
+fn set_universal_aliases(aliases: Vec<(MultiLocation, NetworkId)>) -> DispatchResult {
+	XcmAdmin::set_universal_aliases(
+		RuntimeOrigin::root(),
+		BoundedVec::truncate_from(
+			aliases.into_iter().map(|(bridge, network)| (bridge.into(), network)).collect(),
+		),
+	)
+}
+
+#[test]
+fn only_listed_bridges_alias_into_their_network() {
+	new_test_ext().execute_with(|| {
+		let bridge_hub = MultiLocation::new(1, X1(Parachain(1002)));
+		let alias = (bridge_hub, GlobalConsensus(NetworkId::Polkadot));
+		assert!(!ListedUniversalAliases::<Test>::contains(&alias));
+
+		assert_ok!(set_universal_aliases(vec![(bridge_hub, NetworkId::Polkadot)]));
+		assert_eq!(
+			last_event(),
+			Event::UniversalAliasesSet { aliases: vec![(bridge_hub.into(), NetworkId::Polkadot)] }
+				.into()
+		);
+		assert!(ListedUniversalAliases::<Test>::contains(&alias));
+		assert!(!ListedUniversalAliases::<Test>::contains(&(
+			bridge_hub,
+			GlobalConsensus(NetworkId::Westend)
+		)));
+		assert!(!ListedUniversalAliases::<Test>::contains(&(bridge_hub, Parachain(1000))));
+		let other_bridge = MultiLocation::new(1, X1(Parachain(2000)));
+		assert!(!ListedUniversalAliases::<Test>::contains(&(
+			other_bridge,
+			GlobalConsensus(NetworkId::Polkadot)
+		)));
+
+		assert_ok!(set_universal_aliases(vec![]));
+		assert!(!ListedUniversalAliases::<Test>::contains(&alias));
+	});
+}
+
+#[test]
+fn bridges_may_not_alias_into_our_own_network() {
+	new_test_ext().execute_with(|| {
+		let bridge_hub = MultiLocation::new(1, X1(Parachain(1002)));
+		assert_noop!(
+			set_universal_aliases(vec![(bridge_hub, KusamaNetwork::get())]),
+			Error::<Test>::AliasIntoOwnNetwork
+		);
+	});
+}
+
+#[test]
+fn assets_for_transfer_limit_overrides_the_default() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(XcmAdmin::assets_for_transfer_limit(), 2);
+
+		for invalid in [0, 5] {
+			assert_noop!(
+				XcmAdmin::set_assets_for_transfer_limit(RuntimeOrigin::root(), Some(invalid)),
+				Error::<Test>::InvalidAssetsForTransferLimit
+			);
+		}
+
+		assert_ok!(XcmAdmin::set_assets_for_transfer_limit(RuntimeOrigin::root(), Some(4)));
+		assert_eq!(last_event(), Event::AssetsForTransferLimitSet { limit: Some(4) }.into());
+		assert_eq!(XcmAdmin::assets_for_transfer_limit(), 4);
+
+		assert_ok!(XcmAdmin::set_assets_for_transfer_limit(RuntimeOrigin::root(), None));
+		assert_eq!(XcmAdmin::assets_for_transfer_limit(), 2);
+	});
+}
+
+#[test]
+fn trusted_lockers_override_the_default() {
+	new_test_ext().execute_with(|| {
+		let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+		let ksm: MultiAsset = (Parent, 100).into();
+		let sibling_token: MultiAsset = (sibling, 100).into();
+		assert!(ListedTrustedLockers::<Test>::contains(&Parent.into(), &ksm));
+		assert!(!ListedTrustedLockers::<Test>::contains(&Parent.into(), &sibling_token));
+		assert!(!ListedTrustedLockers::<Test>::contains(&sibling, &sibling_token));
+
+		let lockers = vec![(sibling.into(), Wild(All))];
+		assert_ok!(XcmAdmin::set_trusted_lockers(
+			RuntimeOrigin::root(),
+			Some(BoundedVec::truncate_from(lockers.clone()))
+		));
+		assert_eq!(last_event(), Event::TrustedLockersSet { lockers: Some(lockers) }.into());
+		assert!(ListedTrustedLockers::<Test>::contains(&sibling, &sibling_token));
+		assert!(!ListedTrustedLockers::<Test>::contains(&Parent.into(), &ksm));
+
+		assert_ok!(XcmAdmin::set_trusted_lockers(
+			RuntimeOrigin::root(),
+			Some(BoundedVec::default())
+		));
+		assert!(!ListedTrustedLockers::<Test>::contains(&Parent.into(), &ksm));
+
+		assert_ok!(XcmAdmin::set_trusted_lockers(RuntimeOrigin::root(), None));
+		assert!(ListedTrustedLockers::<Test>::contains(&Parent.into(), &ksm));
+	});
+}
+
+#[test]
+fn relay_call_indices_override_the_default() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(XcmAdmin::relay_call_indices(), KusamaCallIndices::get());
+
+		assert_ok!(XcmAdmin::set_relay_call_indices(RuntimeOrigin::root(), Some((5, 3))));
+		assert_eq!(last_event(), Event::RelayCallIndicesSet { indices: Some((5, 3)) }.into());
+		assert_eq!(XcmAdmin::relay_call_indices(), (5, 3));
+
+		assert_ok!(XcmAdmin::set_relay_call_indices(RuntimeOrigin::root(), None));
+		assert_eq!(XcmAdmin::relay_call_indices(), KusamaCallIndices::get());
+	});
+}
+
+/// A reserve transfer of `amount` from the relay chain, loading `amount` into holding.
+fn relay_transfer(amount: u128) -> Xcm<()> {
+	Xcm(vec![
+		ReserveAssetDeposited((Parent, amount).into()),
+		ClearOrigin,
+		BuyExecution { fees: (Parent, 1).into(), weight_limit: Unlimited },
+		DepositAsset { assets: Wild(AllCounted(1)), beneficiary: Parent.into() },
+	])
+}
+
+/// Caps the amount of `asset` in the holding register at `limit`.
+fn cap_holding(asset: impl Into<MultiLocation>, limit: u128) -> DispatchResult {
+	let asset = Box::new(VersionedMultiLocation::from(asset.into()));
+	XcmAdmin::set_max_holding_amount(RuntimeOrigin::root(), asset, Some(limit))
+}
+
+fn holding_barrier(mut message: Xcm<()>) -> Result<(), ProcessMessageError> {
+	DenyExcessiveHolding::<Test>::should_execute(
+		&Parent.into(),
+		&mut message.0,
+		Weight::MAX,
+		&mut Weight::zero(),
+	)
+}
+
+#[test]
+fn holding_is_not_capped_by_default() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(holding_barrier(relay_transfer(u128::MAX)));
+	});
+}
+
+#[test]
+fn large_transfer_under_the_cap_passes() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(cap_holding(Parent, 1_000_000));
+		let asset = VersionedMultiLocation::from(MultiLocation::parent());
+		assert_eq!(
+			last_event(),
+			Event::MaxHoldingAmountSet { asset, limit: Some(1_000_000) }.into()
+		);
+
+		assert_ok!(holding_barrier(relay_transfer(1_000_000)));
+	});
+}
+
+#[test]
+fn implausibly_large_amount_is_rejected() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(cap_holding(Parent, 1_000_000));
+
+		assert_eq!(
+			holding_barrier(relay_transfer(u128::MAX)),
+			Err(ProcessMessageError::Unsupported)
+		);
+		assert_eq!(
+			holding_barrier(relay_transfer(1_000_001)),
+			Err(ProcessMessageError::Unsupported)
+		);
+	});
+}
+
+#[test]
+fn amounts_of_an_asset_are_summed_up() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(cap_holding(Parent, 1_000_000));
+		assert_ok!(cap_holding(Here, 1_000_000));
+
+		// Each instruction on its own is under the cap, the nested one included.
+		let mut message = relay_transfer(600_000);
+		message.0.push(SetAppendix(Xcm(vec![WithdrawAsset((Parent, 600_000).into())])));
+		assert_eq!(holding_barrier(message), Err(ProcessMessageError::Unsupported));
+
+		// Different assets are capped separately.
+		let mut message = relay_transfer(600_000);
+		message.0.push(WithdrawAsset((Here, 600_000).into()));
+		assert_ok!(holding_barrier(message));
+	});
+}
+
+#[test]
+fn each_asset_has_its_own_cap() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(cap_holding(Parent, 1_000_000));
+		assert_ok!(cap_holding(Here, 10));
+
+		let mut message = relay_transfer(600_000);
+		message.0.push(WithdrawAsset((Here, 10).into()));
+		assert_ok!(holding_barrier(message));
+
+		let mut message = relay_transfer(600_000);
+		message.0.push(WithdrawAsset((Here, 11).into()));
+		assert_eq!(holding_barrier(message), Err(ProcessMessageError::Unsupported));
+
+		// Assets without a cap aren't capped.
+		let mut message = relay_transfer(600_000);
+		message.0.push(WithdrawAsset(((Parent, Parachain(1000)), u128::MAX).into()));
+		assert_ok!(holding_barrier(message));
+	});
+}
+
+#[test]
+fn removing_the_cap_of_an_asset_lifts_it() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(cap_holding(Parent, 1_000_000));
+		let asset = Box::new(VersionedMultiLocation::from(MultiLocation::parent()));
+		assert_ok!(XcmAdmin::set_max_holding_amount(RuntimeOrigin::root(), asset, None));
+		assert_eq!(XcmAdmin::max_holding_amount(&Concrete(Parent.into())), None);
+
+		assert_ok!(holding_barrier(relay_transfer(u128::MAX)));
+	});
+}
+
+#[test]
+fn holding_amounts_saturate() {
+	let message = Xcm::<()>(vec![
+		WithdrawAsset((Parent, u128::MAX).into()),
+		ReceiveTeleportedAsset((Parent, u128::MAX).into()),
+		ClaimAsset { assets: (Here, 5).into(), ticket: Here.into() },
+	]);
+	let amounts = holding_amounts::<Identity, _>(&message.0);
+	assert_eq!(amounts.get(&Concrete(Parent.into())), Some(&u128::MAX));
+	assert_eq!(amounts.get(&Concrete(Here.into())), Some(&5));
+}
+
+/// Maps `Here` to the relay chain, as if they were two locations of the same asset.
+struct HereIsParent;
+impl Convert<MultiLocation, MultiLocation> for HereIsParent {
+	fn convert(location: MultiLocation) -> MultiLocation {
+		if location == Here.into() {
+			Parent.into()
+		} else {
+			location
+		}
+	}
+}
+
+#[test]
+fn holding_amounts_sum_up_the_locations_of_an_asset() {
+	let message = Xcm::<()>(vec![
+		WithdrawAsset((Parent, 7).into()),
+		SetAppendix(Xcm(vec![ClaimAsset { assets: (Here, 5).into(), ticket: Here.into() }])),
+	]);
+	let amounts = holding_amounts::<HereIsParent, _>(&message.0);
+	assert_eq!(amounts.get(&Concrete(Parent.into())), Some(&12));
+	assert_eq!(amounts.get(&Concrete(Here.into())), None);
+}
+
+fn weight_barrier(mut message: Xcm<()>, weight: u64) -> Result<(), ProcessMessageError> {
+	DenyTrivialWeight::<Test>::should_execute(
+		&Parent.into(),
+		&mut message.0,
+		Weight::from_parts(weight, weight),
+		&mut Weight::zero(),
+	)
+}
+
+fn query_response() -> Xcm<()> {
+	Xcm(vec![QueryResponse {
+		query_id: 1,
+		response: Response::Null,
+		max_weight: Weight::zero(),
+		querier: Some(Here.into()),
+	}])
+}
+
+#[test]
+fn execution_weight_has_no_minimum_by_default() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(weight_barrier(Xcm(vec![ClearOrigin]), 0));
+	});
+}
+
+#[test]
+fn trivial_weight_message_is_rejected() {
+	new_test_ext().execute_with(|| {
+		let min_weight = Weight::from_parts(1_000, 1_000);
+		assert_ok!(XcmAdmin::set_min_execution_weight(RuntimeOrigin::root(), Some(min_weight)));
+		assert_eq!(last_event(), Event::MinExecutionWeightSet { weight: Some(min_weight) }.into());
+
+		assert_eq!(
+			weight_barrier(Xcm(vec![ClearOrigin]), 999),
+			Err(ProcessMessageError::Unsupported)
+		);
+		assert_ok!(weight_barrier(relay_transfer(1), 1_000));
+	});
+}
+
+#[test]
+fn minimal_query_response_is_admitted() {
+	new_test_ext().execute_with(|| {
+		let min_weight = Weight::from_parts(1_000, 1_000);
+		assert_ok!(XcmAdmin::set_min_execution_weight(RuntimeOrigin::root(), Some(min_weight)));
+
+		assert_ok!(weight_barrier(query_response(), 1));
+		assert_ok!(weight_barrier(
+			Xcm(vec![SubscribeVersion { query_id: 1, max_response_weight: Weight::zero() }]),
+			1
+		));
+
+		// Appending other instructions to a response forfeits the exemption.
+		let mut message = query_response();
+		message.0.push(ClearOrigin);
+		assert_eq!(weight_barrier(message, 1), Err(ProcessMessageError::Unsupported));
+	});
+}
+
+fn sibling() -> MultiLocation {
+	MultiLocation::new(1, X1(Parachain(2000)))
+}
+
+/// A reserve transfer of TEER to `dest` as `orml_xtokens` executes it, with the fee paid locally.
+fn xtokens_transfer(dest: MultiLocation, weight_limit: WeightLimit) -> Xcm<RuntimeCall> {
+	Xcm(vec![
+		WithdrawAsset(asset(teer()).into()),
+		BuyExecution { fees: asset(teer()), weight_limit: Unlimited },
+		TransferReserveAsset {
+			assets: asset(teer()).into(),
+			dest,
+			xcm: Xcm(vec![
+				BuyExecution { fees: asset(teer()), weight_limit },
+				DepositAsset { assets: AllCounted(1).into(), beneficiary: Here.into() },
+			]),
+		},
+	])
+}
+
+/// Execute `message` with the default destination weights, returning the message sent to the
+/// destination as the destination receives it.
+fn sent_to_dest(message: Xcm<RuntimeCall>) -> Xcm<()> {
+	let outcome = WithDefaultDestWeight::<Test, MockExecutor>::execute_xcm(
+		Here,
+		message,
+		[0; 32],
+		Weight::MAX,
+	);
+	assert_eq!(outcome, Outcome::Complete(Weight::zero()));
+	let executed = executed_xcm().pop().unwrap();
+	// The weight bought locally is left as it is.
+	assert!(matches!(executed.0[1], BuyExecution { weight_limit: Unlimited, .. }));
+	let TransferReserveAsset { xcm, .. } = &executed.0[2] else { panic!("not a transfer") };
+	let mut received = vec![ReserveAssetDeposited(asset(teer()).into()), ClearOrigin];
+	received.extend(xcm.0.iter().cloned());
+	Xcm(received)
+}
+
+fn bought_weight(message: &Xcm<()>) -> WeightLimit {
+	match message.0[2] {
+		BuyExecution { ref weight_limit, .. } => weight_limit.clone(),
+		_ => panic!("no BuyExecution"),
+	}
+}
+
+#[test]
+fn dest_weight_is_unlimited_by_default() {
+	new_test_ext().execute_with(|| {
+		let message = sent_to_dest(xtokens_transfer(sibling(), Unlimited));
+		assert_eq!(bought_weight(&message), Unlimited);
+	});
+}
+
+#[test]
+fn default_dest_weight_is_bought_if_unspecified() {
+	new_test_ext().execute_with(|| {
+		let weight = Weight::from_parts(4_000_000_000, 65_536);
+		assert_ok!(XcmAdmin::set_default_dest_weight(
+			RuntimeOrigin::root(),
+			DestinationKind::Sibling,
+			Some(weight)
+		));
+		assert_eq!(
+			last_event(),
+			Event::DefaultDestWeightSet { kind: DestinationKind::Sibling, weight: Some(weight) }
+				.into()
+		);
+
+		let mut message = sent_to_dest(xtokens_transfer(sibling(), Unlimited));
+		assert_eq!(bought_weight(&message), Limited(weight));
+		// The destination admits the transfer as long as it doesn't weigh more than the default.
+		assert_ok!(AllowTopLevelPaidExecutionFrom::<Everything>::should_execute(
+			&sibling(),
+			&mut message.0,
+			weight,
+			&mut Weight::zero(),
+		));
+
+		// The default for the relay chain is a separate one.
+		let message = sent_to_dest(xtokens_transfer(ksm(), Unlimited));
+		assert_eq!(bought_weight(&message), Unlimited);
+	});
+}
+
+#[test]
+fn explicit_dest_weight_overrides_the_default() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAdmin::set_default_dest_weight(
+			RuntimeOrigin::root(),
+			DestinationKind::Relay,
+			Some(Weight::from_parts(4_000_000_000, 65_536))
+		));
+
+		let explicit = Limited(Weight::from_parts(1_000_000_000, 0));
+		let message = sent_to_dest(xtokens_transfer(ksm(), explicit.clone()));
+		assert_eq!(bought_weight(&message), explicit);
+	});
+}
+
+#[test]
+fn default_dest_weight_applies_to_nested_messages() {
+	new_test_ext().execute_with(|| {
+		let weight = Weight::from_parts(4_000_000_000, 65_536);
+		assert_ok!(XcmAdmin::set_default_dest_weight(
+			RuntimeOrigin::root(),
+			DestinationKind::Sibling,
+			Some(weight)
+		));
+
+		// A transfer of KSM to a sibling, via the relay chain as the reserve.
+		let mut message = vec![InitiateReserveWithdraw {
+			assets: AllCounted(1).into(),
+			reserve: ksm(),
+			xcm: Xcm(vec![
+				BuyExecution { fees: asset(Here.into()), weight_limit: Unlimited },
+				DepositReserveAsset {
+					assets: AllCounted(1).into(),
+					dest: Parachain(2000).into(),
+					xcm: Xcm(vec![BuyExecution { fees: asset(ksm()), weight_limit: Unlimited }]),
+				},
+			]),
+		}];
+		apply_default_dest_weights::<Test, ()>(&mut message);
+
+		let InitiateReserveWithdraw { xcm, .. } = &message[0] else { unreachable!() };
+		// There is no default for the relay chain.
+		assert!(matches!(xcm.0[0], BuyExecution { weight_limit: Unlimited, .. }));
+		let DepositReserveAsset { xcm, .. } = &xcm.0[1] else { unreachable!() };
+		assert_eq!(xcm.0[0], BuyExecution { fees: asset(ksm()), weight_limit: Limited(weight) });
+	});
+}
+
+/// A downward transfer of `amount`, an ordinary message.
+fn dmp_transfer(amount: u128) -> Vec<u8> {
+	VersionedXcm::<()>::from(Xcm(vec![
+		ReserveAssetDeposited(vec![(Parent, amount).into()].into()),
+		ClearOrigin,
+		BuyExecution { fees: (Parent, amount).into(), weight_limit: Unlimited },
+	]))
+	.encode()
+}
+
+/// A downward governance message.
+fn dmp_governance() -> Vec<u8> {
+	VersionedXcm::<()>::from(Xcm(vec![
+		UnpaidExecution { weight_limit: Unlimited, check_origin: None },
+		Transact {
+			origin_kind: OriginKind::Superuser,
+			require_weight_at_most: Weight::from_parts(1_000, 0),
+			call: vec![0, 1].into(),
+		},
+	]))
+	.encode()
+}
+
+fn handle_dmp(messages: &[Vec<u8>], limit: u64) {
+	PrioritizedDmp::<Test, MockDmpQueue>::handle_dmp_messages(
+		messages.iter().cloned().map(|message| (1, message)),
+		Weight::from_parts(limit, 0),
+	);
+}
+
+#[test]
+fn governance_and_system_messages_have_priority() {
+	assert!(is_priority_dmp(&dmp_governance()));
+	let channel_accepted =
+		VersionedXcm::<()>::from(Xcm(vec![HrmpChannelAccepted { recipient: 1000 }]));
+	assert!(is_priority_dmp(&channel_accepted.encode()));
+
+	assert!(!is_priority_dmp(&dmp_transfer(1)));
+	assert!(!is_priority_dmp(&[0xff, 0x00]));
+}
+
+#[test]
+fn downward_messages_are_processed_in_order_by_default() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(XcmAdmin::dmp_order(), DmpOrder::Fifo);
+		let messages = vec![dmp_transfer(1), dmp_transfer(2), dmp_governance()];
+		handle_dmp(&messages, 10);
+		assert_eq!(processed_dmp(), messages);
+	});
+}
+
+#[test]
+fn priority_messages_are_processed_first() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAdmin::set_dmp_order(RuntimeOrigin::root(), DmpOrder::Priority));
+		assert_eq!(last_event(), Event::DmpOrderSet { order: DmpOrder::Priority }.into());
+
+		handle_dmp(&[dmp_transfer(1), dmp_transfer(2), dmp_governance()], 10);
+		assert_eq!(processed_dmp(), vec![dmp_governance(), dmp_transfer(1), dmp_transfer(2)]);
+	});
+}
+
+#[test]
+fn low_priority_messages_are_not_starved() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAdmin::set_dmp_order(RuntimeOrigin::root(), DmpOrder::Priority));
+
+		// Only the governance message fits into the first block.
+		handle_dmp(&[dmp_transfer(1), dmp_governance()], 1);
+		assert_eq!(processed_dmp(), vec![dmp_governance()]);
+
+		// The queued transfer goes before the priority messages of later blocks.
+		handle_dmp(&[dmp_governance()], 1);
+		assert_eq!(processed_dmp(), vec![dmp_governance(), dmp_transfer(1)]);
+		handle_dmp(&[], 1);
+		assert_eq!(processed_dmp(), vec![dmp_governance(), dmp_transfer(1), dmp_governance()]);
+	});
+}
+
+// `pallet_xcm`'s version negotiation storage, as seen from outside of it.
+#[frame_support::storage_alias]
+type SupportedVersion = StorageDoubleMap<
+	PolkadotXcm,
+	Twox64Concat,
+	XcmVersion,
+	Blake2_128Concat,
+	VersionedMultiLocation,
+	XcmVersion,
+>;
+#[frame_support::storage_alias]
+type VersionNotifiers = StorageDoubleMap<
+	PolkadotXcm,
+	Twox64Concat,
+	XcmVersion,
+	Blake2_128Concat,
+	VersionedMultiLocation,
+	u64,
+>;
+#[frame_support::storage_alias]
+type VersionNotifyTargets = StorageDoubleMap<
+	PolkadotXcm,
+	Twox64Concat,
+	XcmVersion,
+	Blake2_128Concat,
+	VersionedMultiLocation,
+	(u64, Weight, XcmVersion),
+>;
+
+fn versioned(location: impl Into<MultiLocation>) -> VersionedMultiLocation {
+	VersionedMultiLocation::from(location.into())
+}
+
+/// Rejects the parachain 2000, which has been offboarded.
+pub struct NotOffboarded;
+impl Contains<MultiLocation> for NotOffboarded {
+	fn contains(location: &MultiLocation) -> bool {
+		*location != (Parent, Parachain(2000)).into()
+	}
+}
+
+fn note_negotiated_versions() {
+	SupportedVersion::insert(3, versioned(Parent), 3);
+	SupportedVersion::insert(3, versioned((Parent, Parachain(1000))), 2);
+	SupportedVersion::insert(3, versioned((Parent, Parachain(2000))), 3);
+	VersionNotifiers::insert(3, versioned(Parent), 7);
+	VersionNotifyTargets::insert(3, versioned((Parent, Parachain(2000))), (8, Weight::zero(), 3));
+}
+
+#[test]
+fn version_negotiation_state_round_trips() {
+	new_test_ext().execute_with(|| {
+		note_negotiated_versions();
+		let state = export_version_negotiation::<Test>();
+		assert_eq!(state.len(), 5);
+
+		assert_eq!(import_version_negotiation::<Test, Everything>(state.clone()), 0);
+		assert_eq!(export_version_negotiation::<Test>(), state);
+		assert_eq!(SupportedVersion::get(3, versioned((Parent, Parachain(1000)))), Some(2));
+	});
+}
+
+#[test]
+fn version_negotiation_state_is_imported_under_the_current_version() {
+	new_test_ext().execute_with(|| {
+		let dest = (Parent, Parachain(1000));
+		let v2_dest = VersionedMultiLocation::V2(xcm::v2::MultiLocation::new(
+			1,
+			xcm::v2::Junctions::X1(xcm::v2::Junction::Parachain(1000)),
+		));
+		SupportedVersion::insert(2, v2_dest, 2);
+
+		let state = export_version_negotiation::<Test>();
+		assert_eq!(import_version_negotiation::<Test, Everything>(state), 0);
+		assert_eq!(SupportedVersion::iter().collect::<Vec<_>>(), vec![(3, versioned(dest), 2)]);
+	});
+}
+
+#[test]
+fn invalid_destinations_are_dropped_on_import() {
+	new_test_ext().execute_with(|| {
+		note_negotiated_versions();
+		let state = export_version_negotiation::<Test>();
+
+		assert_eq!(import_version_negotiation::<Test, NotOffboarded>(state), 2);
+		assert_eq!(SupportedVersion::get(3, versioned((Parent, Parachain(2000)))), None);
+		assert_eq!(VersionNotifyTargets::iter().count(), 0);
+		assert_eq!(SupportedVersion::get(3, versioned(Parent)), Some(3));
+		assert_eq!(VersionNotifiers::get(3, versioned(Parent)), Some(7));
+	});
+}
+
+struct WeighedMessage(Xcm<RuntimeCall>);
+impl PreparedMessage for WeighedMessage {
+	fn weight_of(&self) -> Weight {
+		Weight::from_parts(10 * self.0.len() as u64, 0)
+	}
+}
+
+/// Charges 10 per instruction, but consumes only 6 of them, up to the first `Trap`. Rejects
+/// empty messages, like a barrier.
+struct WeighingExecutor;
+impl ExecuteXcm<RuntimeCall> for WeighingExecutor {
+	type Prepared = WeighedMessage;
+
+	fn prepare(message: Xcm<RuntimeCall>) -> Result<Self::Prepared, Xcm<RuntimeCall>> {
+		Ok(WeighedMessage(message))
+	}
+
+	fn execute(
+		_origin: impl Into<MultiLocation>,
+		WeighedMessage(message): Self::Prepared,
+		_hash: XcmHash,
+		_weight_credit: Weight,
+	) -> Outcome {
+		if message.0.is_empty() {
+			return Outcome::Error(XcmError::Barrier)
+		}
+		match message.0.iter().position(|instruction| matches!(instruction, Trap(_))) {
+			Some(trap) =>
+				Outcome::Incomplete(Weight::from_parts(6 * trap as u64 + 6, 0), XcmError::Trap(0)),
+			None => Outcome::Complete(Weight::from_parts(6 * message.0.len() as u64, 0)),
+		}
+	}
+
+	fn charge_fees(_location: impl Into<MultiLocation>, _fees: MultiAssets) -> XcmResult {
+		Ok(())
+	}
+}
+
+type Metering = MeterInbound<Test, WeighingExecutor>;
+
+fn metered_events() -> Vec<(VersionedMultiLocation, Weight, Weight)> {
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			RuntimeEvent::XcmAdmin(Event::InboundMessageMetered { origin, charged, consumed }) =>
+				Some((origin, charged, consumed)),
+			_ => None,
+		})
+		.collect()
+}
+
+#[test]
+fn executed_messages_are_metered() {
+	new_test_ext().execute_with(|| {
+		let message = Xcm(vec![ClearOrigin, ClearOrigin, ClearOrigin]);
+		assert_eq!(
+			Metering::execute_xcm(Parent, message, [0; 32], Weight::MAX),
+			Outcome::Complete(Weight::from_parts(18, 0))
+		);
+		let message = Xcm(vec![ClearOrigin, Trap(0), ClearOrigin, ClearOrigin]);
+		Metering::execute_xcm((Parent, Parachain(1000)), message, [0; 32], Weight::MAX);
+
+		assert_eq!(
+			metered_events(),
+			vec![
+				(Parent.into(), Weight::from_parts(30, 0), Weight::from_parts(18, 0)),
+				(
+					(Parent, Parachain(1000)).into(),
+					Weight::from_parts(40, 0),
+					Weight::from_parts(12, 0)
+				),
+			]
+		);
+	});
+}
+
+#[test]
+fn rejected_messages_are_not_metered() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			Metering::execute_xcm(Parent, Xcm(vec![]), [0; 32], Weight::MAX),
+			Outcome::Error(XcmError::Barrier)
+		);
+		assert!(metered_events().is_empty());
+	});
+}
+
+/// Waives the fees of messages from the relay chain.
+struct IsRelayChain;
+impl Contains<MultiLocation> for IsRelayChain {
+	fn contains(location: &MultiLocation) -> bool {
+		*location == MultiLocation::parent()
+	}
+}
+
+type TestFeeManager =
+	SplitFees<Test, MockAssetTransactor, AccountToLocation, TreasuryAccount, IsRelayChain>;
+
+fn set_fee_split(split: Vec<(FeeDestination<u64>, Perbill)>) -> DispatchResult {
+	XcmAdmin::set_fee_split(RuntimeOrigin::root(), BoundedVec::truncate_from(split))
+}
+
+fn account(index: u64) -> MultiLocation {
+	AccountToLocation::convert(index)
+}
+
+#[test]
+fn set_fee_split_works() {
+	new_test_ext().execute_with(|| {
+		let split = vec![
+			(FeeDestination::Account(1), Perbill::from_percent(70)),
+			(FeeDestination::Burn, Perbill::from_percent(30)),
+		];
+		assert_ok!(set_fee_split(split.clone()));
+		assert_eq!(XcmAdmin::fee_split().into_inner(), split);
+		assert_eq!(last_event(), Event::FeeSplitSet { split }.into());
+
+		assert_ok!(set_fee_split(vec![]));
+		assert!(XcmAdmin::fee_split().is_empty());
+	});
+}
+
+#[test]
+fn fee_shares_must_sum_up_to_100_percent() {
+	new_test_ext().execute_with(|| {
+		let short = vec![
+			(FeeDestination::Account(1), Perbill::from_percent(50)),
+			(FeeDestination::Account(2), Perbill::from_percent(40)),
+		];
+		assert_noop!(set_fee_split(short), Error::<Test>::FeeSharesNotWhole);
+
+		let excess = vec![
+			(FeeDestination::Account(1), Perbill::from_percent(60)),
+			(FeeDestination::Burn, Perbill::from_percent(60)),
+		];
+		assert_noop!(set_fee_split(excess), Error::<Test>::FeeSharesNotWhole);
+	});
+}
+
+#[test]
+fn fee_is_split_with_the_remainder_to_the_treasury() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(set_fee_split(vec![
+			(FeeDestination::Account(1), Perbill::from_percent(50)),
+			(FeeDestination::Account(2), Perbill::from_percent(30)),
+			(FeeDestination::Burn, Perbill::from_percent(20)),
+		]));
+
+		// 50.5, 30.3 and 20.2 are rounded down, leaving 1 over.
+		TestFeeManager::handle_fee((Here, 101).into());
+		assert_eq!(
+			deposited(),
+			vec![
+				(account(1), (Here, 50).into()),
+				(account(2), (Here, 30).into()),
+				(account(TreasuryAccount::get()), (Here, 1).into()),
+			]
+		);
+	});
+}
+
+#[test]
+fn fees_go_to_the_treasury_without_split() {
+	new_test_ext().execute_with(|| {
+		TestFeeManager::handle_fee((Here, 100).into());
+		assert_eq!(deposited(), vec![(account(TreasuryAccount::get()), (Here, 100).into())]);
+	});
+}
+
+#[test]
+fn fees_are_burnt_by_a_burn_split() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(set_fee_split(vec![(FeeDestination::Burn, Perbill::one())]));
+		TestFeeManager::handle_fee((Here, 100).into());
+		assert!(deposited().is_empty());
+	});
+}
+
+#[test]
+fn trader_revenue_is_split_like_fees() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(set_fee_split(vec![
+			(FeeDestination::Account(1), Perbill::from_percent(50)),
+			(FeeDestination::Burn, Perbill::from_percent(50)),
+		]));
+		TestFeeManager::take_revenue((Here, 100).into());
+		assert_eq!(deposited(), vec![(account(1), (Here, 50).into())]);
+	});
+}
+
+#[test]
+fn fees_of_waived_origins_are_waived() {
+	let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+	assert!(TestFeeManager::is_waived(Some(&Parent.into()), FeeReason::TransferReserveAsset));
+	assert!(!TestFeeManager::is_waived(Some(&sibling), FeeReason::TransferReserveAsset));
+	assert!(!TestFeeManager::is_waived(None, FeeReason::Report));
+}
+
+#[test]
+fn split_parts_sum_up_to_the_fee() {
+	let split = [
+		(FeeDestination::Account(1), Perbill::from_rational(1u32, 3)),
+		(FeeDestination::Account(2), Perbill::from_rational(2u32, 3)),
+	];
+	for amount in [0, 1, 2, 7, 1_000_000_007, u128::MAX] {
+		let parts = split_fee(amount, &split, 99);
+		assert_eq!(parts.iter().map(|(_, part)| part).sum::<u128>(), amount);
+	}
+}
+
+fn rescue(ticket: H256, trapped_by: MultiLocation, assets: VersionedMultiAssets) -> DispatchResult {
+	XcmAdmin::rescue_trapped_assets(
+		RuntimeOrigin::root(),
+		ticket,
+		Box::new(trapped_by.into()),
+		Box::new(assets),
+		7,
+	)
+}
+
+#[test]
+fn rescue_trapped_assets_works() {
+	new_test_ext().execute_with(|| {
+		let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+		let assets = VersionedMultiAssets::from(MultiAssets::from(vec![(Here, 100).into()]));
+		let ticket = trap_assets(sibling, assets.clone());
+
+		assert_ok!(rescue(ticket, sibling, assets.clone()));
+		assert_eq!(deposited(), vec![(account(7), (Here, 100).into())]);
+		assert!(TrappedAssets::get().is_empty());
+		assert_eq!(
+			last_event(),
+			Event::TrappedAssetsRescued {
+				ticket,
+				trapped_by: sibling.into(),
+				assets,
+				beneficiary: 7
+			}
+			.into()
+		);
+	});
+}
+
+#[test]
+fn rescue_trapped_assets_requires_the_matching_trap() {
+	new_test_ext().execute_with(|| {
+		let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+		let assets = VersionedMultiAssets::from(MultiAssets::from(vec![(Here, 100).into()]));
+		let more = VersionedMultiAssets::from(MultiAssets::from(vec![(Here, 200).into()]));
+		let ticket = trap_assets(sibling, assets.clone());
+
+		assert_noop!(rescue(ticket, sibling, more), Error::<Test>::TicketMismatch);
+		assert_noop!(rescue(ticket, Parent.into(), assets.clone()), Error::<Test>::TicketMismatch);
+
+		assert_ok!(rescue(ticket, sibling, assets.clone()));
+		assert_noop!(rescue(ticket, sibling, assets), Error::<Test>::NoTrappedAssets);
+		assert_eq!(deposited().len(), 1);
+	});
+}
+
+type IndexedTrap = IndexTrappedAssets<Test, MockAssetClaims>;
+
+fn trap_context() -> XcmContext {
+	XcmContext { origin: None, message_hash: [0; 32], topic: None }
+}
+
+#[test]
+fn trapped_assets_are_indexed_by_origin() {
+	new_test_ext().execute_with(|| {
+		let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+		let assets = || Assets::from(vec![MultiAsset::from((Here, 100))]);
+		IndexedTrap::drop_assets(&sibling, assets(), &trap_context());
+		IndexedTrap::drop_assets(&sibling, assets(), &trap_context());
+		IndexedTrap::drop_assets(&Parent.into(), Assets::new(), &trap_context());
+
+		let versioned = VersionedMultiAssets::from(MultiAssets::from(vec![(Here, 100).into()]));
+		assert_eq!(XcmAdmin::trapped_assets(&sibling), vec![(versioned, 2)]);
+		assert_eq!(TrappedAssets::get().len(), 2);
+		assert!(XcmAdmin::trapped_assets(&Parent.into()).is_empty());
+	});
+}
+
+#[test]
+fn claimed_assets_leave_the_index() {
+	new_test_ext().execute_with(|| {
+		let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+		let assets = MultiAssets::from(vec![(Here, 100).into()]);
+		IndexedTrap::drop_assets(&sibling, assets.clone().into(), &trap_context());
+
+		let ticket = MultiLocation::new(0, X1(GeneralIndex(3)));
+		let other = MultiAssets::from(vec![(Here, 200).into()]);
+		assert!(!IndexedTrap::claim_assets(&sibling, &ticket, &other, &trap_context()));
+		assert_eq!(XcmAdmin::trapped_assets(&sibling).len(), 1);
+
+		assert!(IndexedTrap::claim_assets(&sibling, &ticket, &assets, &trap_context()));
+		assert!(XcmAdmin::trapped_assets(&sibling).is_empty());
+		assert!(!IndexedTrap::claim_assets(&sibling, &ticket, &assets, &trap_context()));
+	});
+}
+
+#[test]
+fn set_min_xcm_fee_overrides_the_default() {
+	new_test_ext().execute_with(|| {
+		let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+		assert_eq!(XcmAdmin::min_xcm_fee(&Parent.into()), Some(10));
+		assert_eq!(XcmAdmin::min_xcm_fee(&sibling), None);
+
+		for (reserve, fee) in
+			[(sibling, Some(20)), (Parent.into(), Some(30)), (Parent.into(), None)]
+		{
+			assert_ok!(XcmAdmin::set_min_xcm_fee(
+				RuntimeOrigin::root(),
+				Box::new(reserve.into()),
+				Some(fee)
+			));
+			assert_eq!(XcmAdmin::min_xcm_fee(&reserve), fee);
+			assert_eq!(
+				last_event(),
+				Event::MinXcmFeeSet { reserve: reserve.into(), fee: Some(fee) }.into()
+			);
+		}
+
+		assert_ok!(XcmAdmin::set_min_xcm_fee(RuntimeOrigin::root(), Box::new(Parent.into()), None));
+		assert_eq!(XcmAdmin::min_xcm_fee(&Parent.into()), Some(10));
+	});
+}
+
+fn export_from_sibling(network: NetworkId) -> Result<(XcmHash, MultiAssets), SendError> {
+	export_xcm::<BlobExporter<Test, ExportPrice>>(
+		network,
+		0,
+		X2(GlobalConsensus(Kusama), Parachain(2000)),
+		X1(Parachain(1000)),
+		Xcm(vec![ClearOrigin]),
+	)
+}
+
+#[test]
+fn messages_are_exported_as_blobs() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAdmin::set_export_network(RuntimeOrigin::root(), Some(Polkadot)));
+		assert_eq!(last_event(), Event::ExportNetworkSet { network: Some(Polkadot) }.into());
+
+		let (message_id, price) = export_from_sibling(Polkadot).unwrap();
+		assert_eq!(price, ExportPrice::get());
+
+		let message = VersionedXcm::from(Xcm::<()>(vec![
+			UniversalOrigin(GlobalConsensus(Kusama)),
+			DescendOrigin(X1(Parachain(2000))),
+			ClearOrigin,
+		]));
+		let blob = BridgeMessage {
+			universal_dest: X2(GlobalConsensus(Polkadot), Parachain(1000)).into(),
+			message,
+		}
+		.encode();
+		assert_eq!(last_event(), Event::MessageExported { message_id, blob }.into());
+	});
+}
+
+#[test]
+fn messages_are_only_exported_to_the_export_network() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(export_from_sibling(Polkadot), Err(SendError::NotApplicable));
+
+		assert_ok!(XcmAdmin::set_export_network(RuntimeOrigin::root(), Some(Polkadot)));
+		assert_eq!(export_from_sibling(Kusama), Err(SendError::NotApplicable));
+
+		assert_ok!(XcmAdmin::set_export_network(RuntimeOrigin::root(), None));
+		assert_eq!(export_from_sibling(Polkadot), Err(SendError::NotApplicable));
+		assert!(System::events().iter().all(|record| !matches!(
+			record.event,
+			RuntimeEvent::XcmAdmin(Event::MessageExported { .. })
+		)));
+	});
+}
+
+#[test]
+fn export_fee_overrides_the_default() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAdmin::set_export_network(RuntimeOrigin::root(), Some(Polkadot)));
+		assert_eq!(
+			export_from_sibling(Polkadot).unwrap().1,
+			MultiAssets::from(vec![(Here, 10).into()])
+		);
+
+		assert_ok!(XcmAdmin::set_export_fee(RuntimeOrigin::root(), Some(25)));
+		assert_eq!(last_event(), Event::ExportFeeSet { fee: Some(25) }.into());
+		assert_eq!(
+			export_from_sibling(Polkadot).unwrap().1,
+			MultiAssets::from(vec![(Here, 25).into()])
+		);
+
+		assert_ok!(XcmAdmin::set_export_fee(RuntimeOrigin::root(), None));
+		assert_eq!(XcmAdmin::export_fee(), 10);
+	});
+}
+
+fn remark(remark: u8) -> Box<RuntimeCall> {
+	Box::new(RuntimeCall::System(frame_system::Call::remark_with_event { remark: vec![remark] }))
+}
+
+/// Whether the remark `remark` has been dispatched from `sender`.
+fn remarked(sender: u64, remark: u8) -> bool {
+	use sp_runtime::traits::{BlakeTwo256, Hash};
+
+	let hash = BlakeTwo256::hash(&[remark]);
+	System::events().iter().any(|record| {
+		record.event == RuntimeEvent::System(frame_system::Event::Remarked { sender, hash })
+	})
+}
+
+fn run_scheduler(n: u64) {
+	System::set_block_number(n);
+	Scheduler::on_initialize(n);
+	XcmAdmin::on_initialize(n);
+}
+
+#[test]
+fn scheduled_calls_are_dispatched_from_the_scheduling_origin() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAdmin::schedule_call(RuntimeOrigin::signed(2000), 5, remark(1)));
+		assert_eq!(
+			last_event(),
+			Event::CallScheduled {
+				origin: MultiLocation::new(1, X1(Parachain(2000))).into(),
+				when: 5
+			}
+			.into()
+		);
+
+		run_scheduler(4);
+		assert!(!remarked(2000, 1));
+		run_scheduler(5);
+		assert!(remarked(2000, 1));
+	});
+}
+
+#[test]
+fn scheduled_calls_are_checked_again_when_dispatched() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAdmin::schedule_call(RuntimeOrigin::signed(2000), 5, remark(1)));
+		assert_ok!(XcmAdmin::set_transact_blocked(
+			RuntimeOrigin::root(),
+			b"System".to_vec(),
+			Some(b"remark_with_event".to_vec()),
+			true,
+		));
+
+		run_scheduler(5);
+		assert!(!remarked(2000, 1));
+		assert!(System::events().iter().any(|record| matches!(
+			&record.event,
+			RuntimeEvent::Scheduler(pallet_scheduler::Event::Dispatched { result: Err(error), .. })
+				if *error == Error::<Test>::TransactCallBlocked.into()
+		)));
+	});
+}
+
+#[test]
+fn blocked_calls_are_not_scheduled() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAdmin::set_transact_blocked(
+			RuntimeOrigin::root(),
+			b"System".to_vec(),
+			None,
+			true
+		));
+		assert_noop!(
+			XcmAdmin::schedule_call(RuntimeOrigin::signed(2000), 5, remark(1)),
+			Error::<Test>::TransactCallBlocked
+		);
+	});
+}
+
+#[test]
+fn calls_are_only_scheduled_within_the_schedule_delay() {
+	new_test_ext().execute_with(|| {
+		let last = 1 + MaxScheduleDelay::get();
+		assert_noop!(
+			XcmAdmin::schedule_call(RuntimeOrigin::signed(2000), 1, remark(1)),
+			Error::<Test>::InvalidScheduleTime
+		);
+		assert_noop!(
+			XcmAdmin::schedule_call(RuntimeOrigin::signed(2000), last + 1, remark(1)),
+			Error::<Test>::InvalidScheduleTime
+		);
+		assert_ok!(XcmAdmin::schedule_call(RuntimeOrigin::signed(2000), last, remark(1)));
+	});
+}
+
+#[test]
+fn governance_may_lower_the_schedule_delay() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			XcmAdmin::set_schedule_delay_limit(
+				RuntimeOrigin::root(),
+				Some(MaxScheduleDelay::get() + 1)
+			),
+			Error::<Test>::ScheduleDelayTooLong
+		);
+
+		assert_ok!(XcmAdmin::set_schedule_delay_limit(RuntimeOrigin::root(), Some(3)));
+		assert_eq!(last_event(), Event::ScheduleDelayLimitSet { limit: Some(3) }.into());
+		assert_noop!(
+			XcmAdmin::schedule_call(RuntimeOrigin::signed(2000), 5, remark(1)),
+			Error::<Test>::InvalidScheduleTime
+		);
+		assert_ok!(XcmAdmin::schedule_call(RuntimeOrigin::signed(2000), 4, remark(1)));
+
+		assert_ok!(XcmAdmin::set_schedule_delay_limit(RuntimeOrigin::root(), None));
+		assert_eq!(XcmAdmin::schedule_delay_limit(), MaxScheduleDelay::get());
+	});
+}
+
+#[test]
+fn scheduled_calls_are_limited_per_origin_until_dispatched() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmAdmin::schedule_call(RuntimeOrigin::signed(2000), 2, remark(1)));
+		assert_ok!(XcmAdmin::schedule_call(RuntimeOrigin::signed(2000), 3, remark(2)));
+		assert_noop!(
+			XcmAdmin::schedule_call(RuntimeOrigin::signed(2000), 3, remark(3)),
+			Error::<Test>::TooManyScheduledCalls
+		);
+		assert_ok!(XcmAdmin::schedule_call(RuntimeOrigin::signed(2001), 3, remark(3)));
+
+		run_scheduler(2);
+		assert_ok!(XcmAdmin::schedule_call(RuntimeOrigin::signed(2000), 4, remark(3)));
+	});
+}
+
+#[test]
+fn scheduled_calls_are_limited_per_block() {
+	new_test_ext().execute_with(|| {
+		for sibling in 2000..2000 + MaxScheduledPerBlock::get() {
+			assert_ok!(XcmAdmin::schedule_call(
+				RuntimeOrigin::signed(sibling.into()),
+				5,
+				remark(1)
+			));
+		}
+		assert_noop!(
+			XcmAdmin::schedule_call(RuntimeOrigin::signed(3000), 5, remark(1)),
+			Error::<Test>::ScheduleFull
+		);
+		assert_ok!(XcmAdmin::schedule_call(RuntimeOrigin::signed(3000), 6, remark(1)));
+	});
+}
+
+#[test]
+fn schedule_call_requires_schedule_origin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			XcmAdmin::schedule_call(RuntimeOrigin::root(), 5, remark(1)),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			XcmAdmin::dispatch_scheduled(RuntimeOrigin::root(), remark(1)),
+			DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn set_xcm_fee_receiver_overrides_the_default() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(XcmAdmin::xcm_fee_receiver(), TreasuryAccount::get());
+
+		assert_ok!(XcmAdmin::set_xcm_fee_receiver(RuntimeOrigin::root(), Some(7)));
+		assert_eq!(XcmAdmin::xcm_fee_receiver(), 7);
+		assert_eq!(last_event(), Event::XcmFeeReceiverSet { receiver: Some(7) }.into());
+
+		assert_ok!(XcmAdmin::set_xcm_fee_receiver(RuntimeOrigin::root(), None));
+		assert_eq!(XcmAdmin::xcm_fee_receiver(), TreasuryAccount::get());
+	});
+}
+
+#[test]
+fn fee_waived_siblings_are_kept_sorted() {
+	new_test_ext().execute_with(|| {
+		let sibling = MultiLocation::new(1, X1(Parachain(2000)));
+		assert!(FeeWaivedOrigins::<Test>::contains(&MultiLocation::parent()));
+		assert!(!FeeWaivedOrigins::<Test>::contains(&sibling));
+
+		let siblings = BoundedVec::truncate_from(vec![2001, 2000, 2001]);
+		assert_ok!(XcmAdmin::set_fee_waived_siblings(RuntimeOrigin::root(), siblings));
+		assert_eq!(XcmAdmin::fee_waived_siblings().into_inner(), vec![2000, 2001]);
+		assert_eq!(last_event(), Event::FeeWaivedSiblingsSet { siblings: vec![2000, 2001] }.into());
+		assert!(FeeWaivedOrigins::<Test>::contains(&sibling));
+		assert!(!FeeWaivedOrigins::<Test>::contains(&MultiLocation::new(1, X1(Parachain(2002)))));
+		// Only the siblings themselves pay no fees, not their accounts.
+		assert!(!FeeWaivedOrigins::<Test>::contains(&MultiLocation::new(
+			1,
+			X2(Parachain(2000), AccountIndex64 { network: None, index: 1 })
+		)));
+	});
+}
+
+#[test]
+fn set_relay_network_overrides_the_default() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(XcmAdmin::relay_network(), NetworkId::Kusama);
+
+		let network = NetworkId::ByGenesis([7; 32]);
+		assert_ok!(XcmAdmin::set_relay_network(RuntimeOrigin::root(), Some(network)));
+		assert_eq!(XcmAdmin::relay_network(), network);
+		assert_eq!(last_event(), Event::RelayNetworkSet { network: Some(network) }.into());
+
+		assert_ok!(XcmAdmin::set_relay_network(RuntimeOrigin::root(), None));
+		assert_eq!(XcmAdmin::relay_network(), NetworkId::Kusama);
+	});
+}
+
+#[test]
+fn relay_network_must_be_one_of_a_relay_chain() {
+	new_test_ext().execute_with(|| {
+		let ethereum = NetworkId::Ethereum { chain_id: 1 };
+		assert_noop!(
+			XcmAdmin::set_relay_network(RuntimeOrigin::root(), Some(ethereum)),
+			Error::<Test>::NotARelayNetwork
+		);
+	});
+}
+
+#[test]
+fn set_remainder_policy_works() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(XcmAdmin::remainder_policy(), RemainderDestination::Trap);
+
+		assert_ok!(XcmAdmin::set_remainder_policy(
+			RuntimeOrigin::root(),
+			RemainderDestination::RefundToOrigin
+		));
+		assert_eq!(XcmAdmin::remainder_policy(), RemainderDestination::RefundToOrigin);
+		assert_eq!(
+			last_event(),
+			Event::RemainderPolicySet { policy: RemainderDestination::RefundToOrigin }.into()
+		);
+	});
+}
@@ -0,0 +1,298 @@
+//! Weights for `pallet_xcm_admin`.
+//!
+//! These weights are estimates, not benchmark results: they are written by hand, as conservative
+//! estimates based on the storage accesses of each call listed in their docs. Replace this file
+//! with the output of `benchmark pallet` for the benchmarks in [`crate::benchmarking`].
+
+use frame_support::{traits::Get, weights::Weight};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for `pallet_xcm_admin`.
+pub trait WeightInfo {
+	fn clear_version_discovery_queue() -> Weight;
+	fn set_reserve_transfer_enabled() -> Weight;
+	fn set_max_ump_message_size() -> Weight;
+	fn set_transact_blocked() -> Weight;
+	fn set_unsupported_instruction_policy() -> Weight;
+	fn set_fee_rounding() -> Weight;
+	fn set_dmp_order() -> Weight;
+	fn set_fee_currency_priority(n: u32) -> Weight;
+	fn set_max_holding_amount() -> Weight;
+	fn set_min_execution_weight() -> Weight;
+	fn set_default_dest_weight() -> Weight;
+	fn set_fee_split(n: u32) -> Weight;
+	fn rescue_trapped_assets(n: u32) -> Weight;
+	fn set_min_xcm_fee() -> Weight;
+	fn set_export_network() -> Weight;
+	fn set_reserve_transfers_enabled() -> Weight;
+	fn schedule_call() -> Weight;
+	fn dispatch_scheduled() -> Weight;
+	fn set_xcm_fee_receiver() -> Weight;
+	fn set_fee_waived_siblings(n: u32) -> Weight;
+	fn set_relay_network() -> Weight;
+	fn set_remainder_policy() -> Weight;
+	fn set_schedule_delay_limit() -> Weight;
+	fn set_fee_per_ref_time() -> Weight;
+	fn set_sibling_delivery_fee() -> Weight;
+	fn set_xcm_conversion_weight() -> Weight;
+	fn set_universal_aliases(n: u32) -> Weight;
+	fn set_export_fee() -> Weight;
+	fn set_assets_for_transfer_limit() -> Weight;
+	fn set_trusted_lockers(n: u32) -> Weight;
+	fn set_relay_call_indices() -> Weight;
+}
+
+/// Weights for `pallet_xcm_admin` using the database weights of the runtime.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Reads and writes `PolkadotXcm::VersionDiscoveryQueue`.
+	fn clear_version_discovery_queue() -> Weight {
+		Weight::from_parts(20_000_000, 2_000)
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::ReserveTransferDisabled`.
+	fn set_reserve_transfer_enabled() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::MaxUmpMessageSize`.
+	fn set_max_ump_message_size() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::TransactBlocked`.
+	fn set_transact_blocked() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::UnsupportedInstructions`.
+	fn set_unsupported_instruction_policy() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::FeeRoundingMode`.
+	fn set_fee_rounding() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::DmpProcessingOrder`.
+	fn set_dmp_order() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::FeeCurrencyPriority`.
+	fn set_fee_currency_priority(n: u32) -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(Weight::from_parts(500_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::MaxHoldingAmounts`.
+	fn set_max_holding_amount() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::MinExecutionWeight`.
+	fn set_min_execution_weight() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::DefaultDestWeight`.
+	fn set_default_dest_weight() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::FeeSplit`.
+	fn set_fee_split(n: u32) -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(Weight::from_parts(500_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Reads and writes `PolkadotXcm::AssetTraps`.
+	/// Depositing an asset into an account is estimated at 2 reads and 2 writes per asset.
+	fn rescue_trapped_assets(n: u32) -> Weight {
+		Weight::from_parts(30_000_000, 3_000)
+			.saturating_add(Weight::from_parts(50_000_000, 4_000).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(1_u64.saturating_add(2 * n as u64)))
+			.saturating_add(T::DbWeight::get().writes(1_u64.saturating_add(2 * n as u64)))
+	}
+	/// Writes `XcmAdmin::MinXcmFees`.
+	fn set_min_xcm_fee() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::ExportNetwork`.
+	fn set_export_network() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::ReserveTransfersPaused`.
+	fn set_reserve_transfers_enabled() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Reads `XcmAdmin::TransactBlocked` twice.
+	/// Reads `XcmAdmin::ScheduleDelayLimit`.
+	/// Reads and writes `XcmAdmin::ScheduledCalls`.
+	/// Reads and writes `XcmAdmin::ScheduledCallsAt`.
+	/// Scheduling the call is estimated at 2 reads and 2 writes, for the agenda and a preimage.
+	fn schedule_call() -> Weight {
+		Weight::from_parts(40_000_000, 8_000)
+			.saturating_add(T::DbWeight::get().reads(7))
+			.saturating_add(T::DbWeight::get().writes(4))
+	}
+	/// Reads `XcmAdmin::TransactBlocked` twice.
+	fn dispatch_scheduled() -> Weight {
+		Weight::from_parts(15_000_000, 0).saturating_add(T::DbWeight::get().reads(2))
+	}
+	/// Writes `XcmAdmin::XcmFeeReceiver`.
+	fn set_xcm_fee_receiver() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::FeeWaivedSiblings`.
+	fn set_fee_waived_siblings(n: u32) -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(Weight::from_parts(500_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::RelayNetwork`.
+	fn set_relay_network() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::RemainderPolicy`.
+	fn set_remainder_policy() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::ScheduleDelayLimit`.
+	fn set_schedule_delay_limit() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::FeePricesPerRefTime`.
+	fn set_fee_per_ref_time() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::SiblingDeliveryFee`.
+	fn set_sibling_delivery_fee() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::XcmConversionWeight`.
+	fn set_xcm_conversion_weight() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Reads `XcmAdmin::RelayNetwork`.
+	/// Writes `XcmAdmin::UniversalAliases`.
+	fn set_universal_aliases(n: u32) -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(Weight::from_parts(500_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::ExportFee`.
+	fn set_export_fee() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::AssetsForTransferLimit`.
+	fn set_assets_for_transfer_limit() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::TrustedLockers`.
+	fn set_trusted_lockers(n: u32) -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(Weight::from_parts(500_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Writes `XcmAdmin::RelayChainCallIndices`.
+	fn set_relay_call_indices() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+}
+
+impl WeightInfo for () {
+	fn clear_version_discovery_queue() -> Weight {
+		Weight::from_parts(20_000_000, 2_000)
+	}
+	fn set_reserve_transfer_enabled() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_max_ump_message_size() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_transact_blocked() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_unsupported_instruction_policy() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_fee_rounding() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_dmp_order() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_fee_currency_priority(n: u32) -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(Weight::from_parts(500_000, 0).saturating_mul(n.into()))
+	}
+	fn set_max_holding_amount() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_min_execution_weight() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_default_dest_weight() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_fee_split(n: u32) -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(Weight::from_parts(500_000, 0).saturating_mul(n.into()))
+	}
+	fn rescue_trapped_assets(n: u32) -> Weight {
+		Weight::from_parts(30_000_000, 3_000)
+			.saturating_add(Weight::from_parts(50_000_000, 4_000).saturating_mul(n.into()))
+	}
+	fn set_min_xcm_fee() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_export_network() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_reserve_transfers_enabled() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn schedule_call() -> Weight {
+		Weight::from_parts(40_000_000, 8_000)
+	}
+	fn dispatch_scheduled() -> Weight {
+		Weight::from_parts(15_000_000, 0)
+	}
+	fn set_xcm_fee_receiver() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_fee_waived_siblings(n: u32) -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(Weight::from_parts(500_000, 0).saturating_mul(n.into()))
+	}
+	fn set_relay_network() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_remainder_policy() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_schedule_delay_limit() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_fee_per_ref_time() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_sibling_delivery_fee() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_xcm_conversion_weight() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_universal_aliases(n: u32) -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(Weight::from_parts(500_000, 0).saturating_mul(n.into()))
+	}
+	fn set_export_fee() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_assets_for_transfer_limit() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn set_trusted_lockers(n: u32) -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(Weight::from_parts(500_000, 0).saturating_mul(n.into()))
+	}
+	fn set_relay_call_indices() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+}
@@ -0,0 +1,55 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarks of pallet-xcm-dedup.
+
+use super::*;
+use frame_benchmarking::v1::{benchmarks, BenchmarkError};
+use frame_support::{
+	traits::{EnsureOrigin, Get, Hooks},
+	BoundedVec,
+};
+use sp_std::prelude::*;
+
+benchmarks! {
+	set_dedup_window {
+		let origin = T::AdminOrigin::try_successful_origin()
+			.map_err(|_| BenchmarkError::Weightless)?;
+		let window = T::MaxDedupWindow::get();
+	}: _<T::RuntimeOrigin>(origin, Some(window))
+	verify {
+		assert_eq!(DedupWindow::<T>::get(), Some(window));
+	}
+
+	expire_seen_messages {
+		let n in 0 .. T::MaxDedupMessagesPerBlock::get();
+		let expiry = frame_system::Pallet::<T>::block_number() + 1u32.into();
+		let keys: Vec<[u8; 32]> =
+			(0..n).map(|i| i.using_encoded(sp_io::hashing::blake2_256)).collect();
+		for key in &keys {
+			SeenMessages::<T>::insert(key, expiry);
+		}
+		SeenMessagesExpiry::<T>::insert(expiry, BoundedVec::truncate_from(keys));
+	}: {
+		Pallet::<T>::on_initialize(expiry);
+	}
+	verify {
+		assert_eq!(SeenMessages::<T>::iter().count(), 0);
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,251 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+//! Deduplication of inbound XCM messages.
+//!
+//! A buggy sender may deliver a message twice. With a deduplication window set by governance, a
+//! [`DeduplicateInbound`] wrapped around the executor of the message queues drops messages whose
+//! message id (their `SetTopic`) has already been seen from the same origin within the window,
+//! with an [`Event::DuplicateMessageDropped`]. Messages without a `SetTopic` are never dropped, as
+//! identical content doesn't make a message a duplicate: a sender may well intend to send the
+//! same message twice. Deduplication is off by default.
+//!
+//! At most [`Config::MaxDedupMessagesPerBlock`] messages are remembered per block, further ones
+//! are executed but not remembered.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Encode;
+use frame_support::weights::Weight;
+use sp_std::marker::PhantomData;
+use xcm::latest::{prelude::*, XcmHash};
+
+pub use pallet::*;
+pub use weights::WeightInfo;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+const LOG_TARGET: &str = "xcm::dedup";
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::Zero;
+	use xcm::VersionedMultiLocation;
+
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(0);
+
+	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// Origin allowed to set the deduplication window.
+		type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Maximum number of inbound messages remembered for deduplication per block.
+		#[pallet::constant]
+		type MaxDedupMessagesPerBlock: Get<u32>;
+
+		/// Maximum deduplication window governance may set.
+		#[pallet::constant]
+		type MaxDedupWindow: Get<BlockNumberFor<Self>>;
+
+		type WeightInfo: WeightInfo;
+	}
+
+	/// For how many blocks inbound messages are remembered for deduplication, off if unset.
+	#[pallet::storage]
+	#[pallet::getter(fn dedup_window)]
+	pub type DedupWindow<T: Config> = StorageValue<_, BlockNumberFor<T>, OptionQuery>;
+
+	/// The inbound messages seen within the deduplication window, by the hash of their origin and
+	/// message id, with the block they are forgotten at.
+	#[pallet::storage]
+	pub type SeenMessages<T: Config> =
+		StorageMap<_, Identity, [u8; 32], BlockNumberFor<T>, OptionQuery>;
+
+	/// The keys of [`SeenMessages`] to forget at a block.
+	#[pallet::storage]
+	pub type SeenMessagesExpiry<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		BlockNumberFor<T>,
+		BoundedVec<[u8; 32], T::MaxDedupMessagesPerBlock>,
+		ValueQuery,
+	>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The deduplication window of inbound messages has been set, `None` if turned off.
+		DedupWindowSet { window: Option<BlockNumberFor<T>> },
+		/// An inbound message has been dropped, as it has already been seen.
+		DuplicateMessageDropped { origin: VersionedMultiLocation, message_id: XcmHash },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The deduplication window exceeds [`Config::MaxDedupWindow`].
+		DedupWindowTooLong,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(n: BlockNumberFor<T>) -> Weight {
+			let expired = SeenMessagesExpiry::<T>::take(n);
+			for key in &expired {
+				SeenMessages::<T>::remove(key);
+			}
+			T::WeightInfo::expire_seen_messages(expired.len() as u32)
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Set for how many blocks inbound messages are remembered to drop duplicates, `None` or
+		/// zero to turn deduplication off.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::set_dedup_window())]
+		pub fn set_dedup_window(
+			origin: OriginFor<T>,
+			window: Option<BlockNumberFor<T>>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			let window = window.filter(|window| !window.is_zero());
+			ensure!(
+				window.map_or(true, |window| window <= T::MaxDedupWindow::get()),
+				Error::<T>::DedupWindowTooLong
+			);
+			DedupWindow::<T>::set(window);
+			Self::deposit_event(Event::DedupWindowSet { window });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Whether the message `message_id` from `origin` has already been seen within the
+		/// deduplication window, see [`Self::note_seen`].
+		pub fn is_duplicate(origin: &MultiLocation, message_id: XcmHash) -> bool {
+			Self::dedup_window().is_some() &&
+				SeenMessages::<T>::contains_key(Self::seen_message_key(origin, message_id))
+		}
+
+		/// Remembers the message `message_id` from `origin` for the deduplication window, if
+		/// deduplication is on.
+		pub fn note_seen(origin: &MultiLocation, message_id: XcmHash) {
+			let Some(window) = Self::dedup_window() else { return };
+			let key = Self::seen_message_key(origin, message_id);
+			let expiry = frame_system::Pallet::<T>::block_number().saturating_add(window);
+			if SeenMessagesExpiry::<T>::try_append(expiry, key).is_ok() {
+				SeenMessages::<T>::insert(key, expiry);
+			} else {
+				log::warn!(
+					target: LOG_TARGET,
+					"Too many inbound messages to remember for deduplication, not remembering {:?}",
+					message_id,
+				);
+			}
+		}
+
+		fn seen_message_key(origin: &MultiLocation, message_id: XcmHash) -> [u8; 32] {
+			(origin, message_id).using_encoded(sp_io::hashing::blake2_256)
+		}
+	}
+}
+
+/// The message id of `message`, as set by a `SetTopic` instruction.
+pub fn message_id<Call>(message: &Xcm<Call>) -> Option<XcmHash> {
+	message.0.iter().rev().find_map(|instruction| match instruction {
+		SetTopic(id) => Some(*id),
+		_ => None,
+	})
+}
+
+/// Wraps the XCM executor, dropping inbound messages which have already been seen within the
+/// deduplication window, see [`Pallet::is_duplicate`]. Only messages with a message id are
+/// deduplicated.
+///
+/// A message is only seen once it has been executed, completely or not. Messages which haven't
+/// been executed at all, e.g. because they exceeded the weight limit, are kept by the queues to be
+/// serviced later and must not be dropped then.
+///
+/// Intended to be used as the `XcmExecutor` of the inbound message queues.
+pub struct DeduplicateInbound<T, Inner>(PhantomData<(T, Inner)>);
+
+impl<T: Config, Call, Inner: ExecuteXcm<Call>> ExecuteXcm<Call> for DeduplicateInbound<T, Inner> {
+	type Prepared = Inner::Prepared;
+
+	fn prepare(message: Xcm<Call>) -> Result<Self::Prepared, Xcm<Call>> {
+		Inner::prepare(message)
+	}
+
+	fn execute(
+		origin: impl Into<MultiLocation>,
+		pre: Self::Prepared,
+		hash: XcmHash,
+		weight_credit: Weight,
+	) -> Outcome {
+		Inner::execute(origin, pre, hash, weight_credit)
+	}
+
+	fn execute_xcm_in_credit(
+		origin: impl Into<MultiLocation>,
+		message: Xcm<Call>,
+		hash: XcmHash,
+		weight_limit: Weight,
+		weight_credit: Weight,
+	) -> Outcome {
+		let origin = origin.into();
+		let id = message_id(&message);
+		if let Some(message_id) = id {
+			if Pallet::<T>::is_duplicate(&origin, message_id) {
+				log::debug!(
+					target: LOG_TARGET,
+					"Dropping duplicate message {:?} from {:?}",
+					message_id,
+					origin,
+				);
+				Pallet::<T>::deposit_event(Event::DuplicateMessageDropped {
+					origin: origin.into(),
+					message_id,
+				});
+				return Outcome::Complete(Weight::zero())
+			}
+		}
+		let outcome =
+			Inner::execute_xcm_in_credit(origin, message, hash, weight_limit, weight_credit);
+		if let (Some(message_id), Outcome::Complete(_) | Outcome::Incomplete(..)) = (id, &outcome) {
+			Pallet::<T>::note_seen(&origin, message_id);
+		}
+		outcome
+	}
+
+	fn charge_fees(location: impl Into<MultiLocation>, fees: MultiAssets) -> XcmResult {
+		Inner::charge_fees(location, fees)
+	}
+}
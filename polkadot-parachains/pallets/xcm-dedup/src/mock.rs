@@ -0,0 +1,131 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+use crate as pallet_xcm_dedup;
+use frame_support::{parameter_types, traits::Everything, weights::Weight};
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+use sp_std::cell::RefCell;
+use xcm::latest::{prelude::*, XcmHash};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		XcmDedup: pallet_xcm_dedup::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type RuntimeEvent = RuntimeEvent;
+	type BlockHashCount = frame_support::traits::ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+thread_local! {
+	/// Messages that have been executed by the [`MockExecutor`].
+	pub static EXECUTED_XCM: RefCell<Vec<Xcm<RuntimeCall>>> = RefCell::new(Vec::new());
+}
+
+pub fn executed_xcm() -> Vec<Xcm<RuntimeCall>> {
+	EXECUTED_XCM.with(|e| e.borrow().clone())
+}
+
+/// Weighs one unit of `ref_time` per instruction.
+pub struct MockPrepared(Xcm<RuntimeCall>);
+impl PreparedMessage for MockPrepared {
+	fn weight_of(&self) -> Weight {
+		Weight::from_parts(self.0 .0.len() as u64, 0)
+	}
+}
+
+/// Executes only messages which pay for their execution, like the barrier of the runtimes.
+pub struct MockExecutor;
+impl ExecuteXcm<RuntimeCall> for MockExecutor {
+	type Prepared = MockPrepared;
+
+	fn prepare(message: Xcm<RuntimeCall>) -> Result<Self::Prepared, Xcm<RuntimeCall>> {
+		Ok(MockPrepared(message))
+	}
+
+	fn execute(
+		_origin: impl Into<MultiLocation>,
+		MockPrepared(message): Self::Prepared,
+		_hash: XcmHash,
+		_weight_credit: Weight,
+	) -> Outcome {
+		if !message.0.iter().any(|instruction| matches!(instruction, BuyExecution { .. })) {
+			return Outcome::Error(XcmError::Barrier)
+		}
+		EXECUTED_XCM.with(|e| e.borrow_mut().push(message));
+		Outcome::Complete(Weight::zero())
+	}
+
+	fn charge_fees(_location: impl Into<MultiLocation>, _fees: MultiAssets) -> XcmResult {
+		Ok(())
+	}
+}
+
+parameter_types! {
+	pub const MaxDedupMessagesPerBlock: u32 = 2;
+	pub const MaxDedupWindow: u64 = 10;
+}
+
+impl pallet_xcm_dedup::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type AdminOrigin = EnsureRoot<u64>;
+	type MaxDedupMessagesPerBlock = MaxDedupMessagesPerBlock;
+	type MaxDedupWindow = MaxDedupWindow;
+	type WeightInfo = ();
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
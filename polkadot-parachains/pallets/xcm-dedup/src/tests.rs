@@ -0,0 +1,155 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+use crate::{message_id, mock::*, DeduplicateInbound, Error, Event};
+use frame_support::{assert_noop, assert_ok, traits::Hooks, weights::Weight};
+use sp_runtime::DispatchError;
+use xcm::latest::prelude::*;
+
+fn last_event() -> RuntimeEvent {
+	System::events().pop().expect("an event has been deposited").event
+}
+
+type Deduplicating = DeduplicateInbound<Test, MockExecutor>;
+
+/// A paid message with the message id `id`, if any.
+fn message_with_id(id: Option<u8>) -> Xcm<RuntimeCall> {
+	let mut message =
+		Xcm(vec![BuyExecution { fees: (Here, 1u128).into(), weight_limit: Unlimited }]);
+	if let Some(id) = id {
+		message.0.push(SetTopic([id; 32]));
+	}
+	message
+}
+
+fn execute_from(origin: impl Into<MultiLocation>, message: Xcm<RuntimeCall>) -> Outcome {
+	Deduplicating::execute_xcm(origin, message, [0; 32], Weight::MAX)
+}
+
+#[test]
+fn message_id_is_the_topic() {
+	assert_eq!(message_id(&message_with_id(Some(1))), Some([1; 32]));
+	assert_eq!(message_id(&message_with_id(None)), None);
+}
+
+#[test]
+fn duplicates_are_processed_without_dedup_window() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(XcmDedup::dedup_window(), None);
+		execute_from(Parent, message_with_id(Some(1)));
+		execute_from(Parent, message_with_id(Some(1)));
+		assert_eq!(executed_xcm().len(), 2);
+	});
+}
+
+#[test]
+fn replayed_message_is_dropped() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmDedup::set_dedup_window(RuntimeOrigin::root(), Some(5)));
+		assert_eq!(last_event(), Event::DedupWindowSet { window: Some(5) }.into());
+
+		assert_eq!(
+			execute_from(Parent, message_with_id(Some(1))),
+			Outcome::Complete(Weight::zero())
+		);
+		assert_eq!(
+			execute_from(Parent, message_with_id(Some(1))),
+			Outcome::Complete(Weight::zero())
+		);
+		assert_eq!(executed_xcm(), vec![message_with_id(Some(1))]);
+		assert_eq!(
+			last_event(),
+			Event::DuplicateMessageDropped { origin: Parent.into(), message_id: [1; 32] }.into()
+		);
+	});
+}
+
+#[test]
+fn messages_exceeding_the_weight_limit_are_not_seen() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmDedup::set_dedup_window(RuntimeOrigin::root(), Some(5)));
+
+		let outcome =
+			Deduplicating::execute_xcm(Parent, message_with_id(Some(1)), [0; 32], Weight::zero());
+		assert!(matches!(outcome, Outcome::Error(XcmError::WeightLimitReached(_))));
+		assert!(executed_xcm().is_empty());
+
+		// Retried by the queue, or serviced as an overweight message.
+		assert_eq!(
+			execute_from(Parent, message_with_id(Some(1))),
+			Outcome::Complete(Weight::zero())
+		);
+		assert_eq!(executed_xcm(), vec![message_with_id(Some(1))]);
+		execute_from(Parent, message_with_id(Some(1)));
+		assert_eq!(executed_xcm().len(), 1);
+	});
+}
+
+#[test]
+fn identical_messages_without_id_are_processed() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmDedup::set_dedup_window(RuntimeOrigin::root(), Some(5)));
+		execute_from(Parent, message_with_id(None));
+		execute_from(Parent, message_with_id(None));
+		assert_eq!(executed_xcm().len(), 2);
+	});
+}
+
+#[test]
+fn same_content_with_distinct_ids_or_origins_is_processed() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmDedup::set_dedup_window(RuntimeOrigin::root(), Some(5)));
+		execute_from(Parent, message_with_id(Some(1)));
+		execute_from(Parent, message_with_id(Some(2)));
+		execute_from((Parent, Parachain(1000)), message_with_id(Some(1)));
+		assert_eq!(executed_xcm().len(), 3);
+	});
+}
+
+#[test]
+fn messages_are_forgotten_after_the_dedup_window() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(XcmDedup::set_dedup_window(RuntimeOrigin::root(), Some(5)));
+		execute_from(Parent, message_with_id(Some(1)));
+
+		System::set_block_number(6);
+		XcmDedup::on_initialize(6);
+		execute_from(Parent, message_with_id(Some(1)));
+		assert_eq!(executed_xcm().len(), 2);
+	});
+}
+
+#[test]
+fn dedup_window_is_bounded() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			XcmDedup::set_dedup_window(RuntimeOrigin::root(), Some(MaxDedupWindow::get() + 1)),
+			Error::<Test>::DedupWindowTooLong
+		);
+		assert_ok!(XcmDedup::set_dedup_window(RuntimeOrigin::root(), Some(0)));
+		assert_eq!(XcmDedup::dedup_window(), None);
+	});
+}
+
+#[test]
+fn set_dedup_window_requires_admin_origin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			XcmDedup::set_dedup_window(RuntimeOrigin::signed(1), Some(5)),
+			DispatchError::BadOrigin
+		);
+	});
+}
@@ -0,0 +1,43 @@
+//! Weights for `pallet_xcm_dedup`.
+//!
+//! These are conservative estimates based on the storage accesses of each call, until they are
+//! generated from the benchmarks in [`crate::benchmarking`].
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::Weight};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for `pallet_xcm_dedup`.
+pub trait WeightInfo {
+	fn set_dedup_window() -> Weight;
+	fn expire_seen_messages(n: u32) -> Weight;
+}
+
+/// Weights for `pallet_xcm_dedup` using the database weights of the runtime.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: XcmDedup DedupWindow (r:0 w:1)
+	fn set_dedup_window() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Storage: XcmDedup SeenMessagesExpiry (r:1 w:1)
+	/// Storage: XcmDedup SeenMessages (r:0 w:n)
+	fn expire_seen_messages(n: u32) -> Weight {
+		Weight::from_parts(3_000_000, 0)
+			.saturating_add(Weight::from_parts(1_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads_writes(1, 1))
+			.saturating_add(T::DbWeight::get().writes(n.into()))
+	}
+}
+
+impl WeightInfo for () {
+	fn set_dedup_window() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn expire_seen_messages(n: u32) -> Weight {
+		Weight::from_parts(3_000_000, 0)
+			.saturating_add(Weight::from_parts(1_000_000, 0).saturating_mul(n.into()))
+	}
+}
@@ -0,0 +1,52 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarks of pallet-xcm-fee-markup.
+
+use super::*;
+use frame_benchmarking::v1::{benchmarks, BenchmarkError};
+use frame_support::traits::{EnsureOrigin, Get};
+
+/// Provides the parts of the runtime charging the fee markup depends on.
+pub trait BenchmarkHelper {
+	/// An origin which can pay `fee`.
+	fn funded_origin(fee: &MultiAsset) -> MultiLocation;
+
+	/// Charge `origin` the `fees`, like the executor wrapped into the [`MarkedUpExecution`] does.
+	fn charge_fees(origin: MultiLocation, fees: MultiAssets) -> XcmResult;
+}
+
+benchmarks! {
+	set_fee_markup {
+		let origin = T::AdminOrigin::try_successful_origin()
+			.map_err(|_| BenchmarkError::Weightless)?;
+		let markup = T::MaxFeeMarkup::get();
+	}: _<T::RuntimeOrigin>(origin, Box::new(MultiLocation::parent().into()), markup)
+	verify {
+		assert_eq!(Pallet::<T>::fee_markup(&MultiLocation::parent()), markup);
+	}
+
+	charge_fee_markup {
+		let fee: MultiAsset = (T::FeeMarkupAsset::get(), T::MaxFeeMarkup::get()).into();
+		let origin = T::BenchmarkHelper::funded_origin(&fee);
+	}: {
+		T::BenchmarkHelper::charge_fees(origin, fee.into())
+			.map_err(|_| BenchmarkError::Stop("the origin can't pay the fee markup"))?;
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
+}
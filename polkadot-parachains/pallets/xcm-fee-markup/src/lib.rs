@@ -0,0 +1,293 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+//! Per-destination markups on the XCM fees.
+//!
+//! Governance can charge a markup, an amount of [`Config::FeeMarkupAsset`] of at most
+//! [`Config::MaxFeeMarkup`], on the delivery of messages to single destinations, e.g. to recover
+//! the costs of less trusted routes. Wrapping the delivery prices of the routers into a
+//! [`MarkedUpDelivery`] adds the markup to the price of every message to such a destination,
+//! which the executor charges to the origin of the message with the delivery fees.
+//!
+//! Local accounts pay no delivery fees for their transfers, which leave the holding register
+//! before their messages are sent. A [`MarkedUpExecution`] wrapped around the executor of
+//! `pallet_xcm` and `orml_xtokens` charges them the markups of the destinations of their
+//! transfers, with `charge_fees`, once the transfers executed completely. Failed transfers pay no
+//! markup. The pallets must weigh the messages with a [`MarkedUpWeigher`], which accounts for
+//! charging the markup. Destinations without a markup pay the base fees.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use cumulus_primitives_core::ParaId;
+use cumulus_primitives_utility::PriceForParentDelivery;
+use frame_support::{
+	storage::{with_transaction, TransactionOutcome},
+	weights::Weight,
+};
+use polkadot_runtime_common::xcm_sender::PriceForParachainDelivery;
+use sp_runtime::DispatchError;
+use sp_std::{marker::PhantomData, prelude::*};
+use xcm::{
+	latest::{prelude::*, XcmHash},
+	VersionedMultiLocation,
+};
+use xcm_executor::traits::WeightBounds;
+
+pub use pallet::*;
+pub use weights::WeightInfo;
+
+#[cfg(feature = "runtime-benchmarks")]
+pub mod benchmarking;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+const LOG_TARGET: &str = "xcm::fee-markup";
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(0);
+
+	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
+	pub struct Pallet<T>(PhantomData<T>);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// Origin allowed to set the fee markups.
+		type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// The asset fee markups are charged in.
+		type FeeMarkupAsset: Get<MultiLocation>;
+
+		/// Maximum fee markup governance may set for a destination.
+		#[pallet::constant]
+		type MaxFeeMarkup: Get<u128>;
+
+		/// Provides the parts of the runtime the benchmarks depend on.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: benchmarking::BenchmarkHelper;
+
+		type WeightInfo: WeightInfo;
+	}
+
+	/// Amount of [`Config::FeeMarkupAsset`] added to the fees of messages to a destination, see
+	/// [`MarkedUpDelivery`] and [`MarkedUpExecution`].
+	///
+	/// Keyed by the location in the latest XCM version, the keys need to be migrated along with
+	/// it.
+	#[pallet::storage]
+	pub type FeeMarkups<T> = StorageMap<_, Blake2_128Concat, MultiLocation, u128, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The fee markup of a destination has been set, zero if removed.
+		FeeMarkupSet { dest: VersionedMultiLocation, markup: u128 },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The destination can't be converted into the latest XCM version.
+		BadVersion,
+		/// The fee markup exceeds [`Config::MaxFeeMarkup`].
+		FeeMarkupTooHigh,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Set the markup charged on top of the fees of messages to `dest`. A markup of zero
+		/// removes it.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::set_fee_markup())]
+		pub fn set_fee_markup(
+			origin: OriginFor<T>,
+			dest: Box<VersionedMultiLocation>,
+			markup: u128,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(markup <= T::MaxFeeMarkup::get(), Error::<T>::FeeMarkupTooHigh);
+			let dest: MultiLocation = (*dest).try_into().map_err(|()| Error::<T>::BadVersion)?;
+			if markup == 0 {
+				FeeMarkups::<T>::remove(dest);
+			} else {
+				FeeMarkups::<T>::insert(dest, markup);
+			}
+			Self::deposit_event(Event::FeeMarkupSet { dest: dest.into(), markup });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The fee markup of messages to `dest`, zero if there is none.
+		pub fn fee_markup(dest: &MultiLocation) -> u128 {
+			FeeMarkups::<T>::get(dest)
+		}
+	}
+}
+
+/// Wraps the delivery price `Inner` of a router, adding the fee markup of the destination.
+pub struct MarkedUpDelivery<T, Inner>(PhantomData<(T, Inner)>);
+
+impl<T: Config, Inner> MarkedUpDelivery<T, Inner> {
+	fn mark_up(dest: MultiLocation, mut price: MultiAssets) -> MultiAssets {
+		let markup = Pallet::<T>::fee_markup(&dest);
+		if markup > 0 {
+			price.push((T::FeeMarkupAsset::get(), markup).into());
+		}
+		price
+	}
+}
+
+impl<T: Config, Inner: PriceForParentDelivery> PriceForParentDelivery
+	for MarkedUpDelivery<T, Inner>
+{
+	fn price_for_parent_delivery(message: &Xcm<()>) -> MultiAssets {
+		Self::mark_up(MultiLocation::parent(), Inner::price_for_parent_delivery(message))
+	}
+}
+
+impl<T: Config, Inner: PriceForParachainDelivery> PriceForParachainDelivery
+	for MarkedUpDelivery<T, Inner>
+{
+	fn price_for_parachain_delivery(para: ParaId, message: &Xcm<()>) -> MultiAssets {
+		Self::mark_up(
+			MultiLocation::new(1, X1(Parachain(para.into()))),
+			Inner::price_for_parachain_delivery(para, message),
+		)
+	}
+}
+
+/// The sum of the fee markups of the destinations `message` transfers assets to.
+fn transfer_markup<T: Config, Call>(message: &[Instruction<Call>]) -> u128 {
+	message
+		.iter()
+		.filter_map(|instruction| match instruction {
+			TransferReserveAsset { dest, .. } |
+			DepositReserveAsset { dest, .. } |
+			InitiateTeleport { dest, .. } => Some(dest),
+			InitiateReserveWithdraw { reserve, .. } => Some(reserve),
+			_ => None,
+		})
+		.fold(0, |markup, dest| markup.saturating_add(Pallet::<T>::fee_markup(dest)))
+}
+
+/// Weighs messages like `Inner`, plus the weight of charging the fee markup of their transfers if
+/// there is one, such that they weigh what [`MarkedUpExecution`] prepares them to.
+///
+/// Intended to be used as the `Weigher` of the pallets using [`MarkedUpExecution`], which compare
+/// the weight of the prepared message to the weight their `Weigher` computed for it.
+pub struct MarkedUpWeigher<T, Inner>(PhantomData<(T, Inner)>);
+
+impl<T: Config, Call, Inner: WeightBounds<Call>> WeightBounds<Call> for MarkedUpWeigher<T, Inner> {
+	fn weight(message: &mut Xcm<Call>) -> Result<Weight, ()> {
+		let weight = Inner::weight(message)?;
+		Ok(if transfer_markup::<T, Call>(&message.0) > 0 {
+			weight.saturating_add(T::WeightInfo::charge_fee_markup())
+		} else {
+			weight
+		})
+	}
+
+	fn instr_weight(instruction: &Instruction<Call>) -> Result<Weight, ()> {
+		Inner::instr_weight(instruction)
+	}
+}
+
+/// A message prepared by [`MarkedUpExecution`], with the fee markup of its transfers.
+///
+/// Weighs as much as the message plus charging the markup, see [`MarkedUpWeigher`].
+pub struct MarkedUpMessage<Prepared> {
+	inner: Prepared,
+	markup: u128,
+	/// The weight of charging the markup.
+	weight: Weight,
+}
+
+impl<Prepared: PreparedMessage> PreparedMessage for MarkedUpMessage<Prepared> {
+	fn weight_of(&self) -> Weight {
+		self.inner.weight_of().saturating_add(self.weight)
+	}
+}
+
+/// Wraps the XCM executor, charging the origin of a message the fee markups of the destinations
+/// it transfers assets to, in [`Config::FeeMarkupAsset`], once the message executed completely.
+///
+/// The markup is charged with `Inner::charge_fees`, so it goes to the `FeeManager` of the
+/// executor and origins it waives the fees of pay none. Messages which fail to execute completely
+/// pay no markup. If the origin can't pay it, the effects of the message are rolled back and the
+/// outcome is incomplete.
+///
+/// Intended to be used as the `XcmExecutor` of `pallet_xcm` and `orml_xtokens`, whose local
+/// origins pay no delivery fees, and so no [`MarkedUpDelivery`], for their transfers.
+pub struct MarkedUpExecution<T, Inner>(PhantomData<(T, Inner)>);
+
+impl<T: Config, Call, Inner: ExecuteXcm<Call>> ExecuteXcm<Call> for MarkedUpExecution<T, Inner> {
+	type Prepared = MarkedUpMessage<Inner::Prepared>;
+
+	fn prepare(message: Xcm<Call>) -> Result<Self::Prepared, Xcm<Call>> {
+		let markup = transfer_markup::<T, Call>(&message.0);
+		let weight = if markup > 0 { T::WeightInfo::charge_fee_markup() } else { Weight::zero() };
+		Ok(MarkedUpMessage { inner: Inner::prepare(message)?, markup, weight })
+	}
+
+	fn execute(
+		origin: impl Into<MultiLocation>,
+		pre: Self::Prepared,
+		hash: XcmHash,
+		weight_credit: Weight,
+	) -> Outcome {
+		let origin = origin.into();
+		let MarkedUpMessage { inner, markup, weight } = pre;
+		if markup == 0 {
+			return Inner::execute(origin, inner, hash, weight_credit)
+		}
+
+		with_transaction(|| match Inner::execute(origin, inner, hash, weight_credit) {
+			Outcome::Complete(used) => {
+				let used = used.saturating_add(weight);
+				let fee: MultiAsset = (T::FeeMarkupAsset::get(), markup).into();
+				match Inner::charge_fees(origin, fee.into()) {
+					Ok(()) => TransactionOutcome::Commit(Ok(Outcome::Complete(used))),
+					Err(error) => {
+						log::debug!(
+							target: LOG_TARGET,
+							"{:?} can't pay the fee markup {} of its message: {:?}",
+							origin,
+							markup,
+							error,
+						);
+						TransactionOutcome::Rollback(Ok(Outcome::Incomplete(used, error)))
+					},
+				}
+			},
+			outcome => TransactionOutcome::Commit(Ok(outcome)),
+		})
+		.unwrap_or_else(|_: DispatchError| Outcome::Error(XcmError::ExceedsStackLimit))
+	}
+
+	fn charge_fees(location: impl Into<MultiLocation>, fees: MultiAssets) -> XcmResult {
+		Inner::charge_fees(location, fees)
+	}
+}
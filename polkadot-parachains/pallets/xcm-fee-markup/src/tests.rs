@@ -0,0 +1,265 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+use crate::{
+	mock::*, Error, Event, MarkedUpDelivery, MarkedUpExecution, MarkedUpWeigher, WeightInfo,
+};
+use cumulus_primitives_core::ParaId;
+use cumulus_primitives_utility::PriceForParentDelivery;
+use frame_support::{assert_noop, assert_ok, storage::unhashed, traits::Get, weights::Weight};
+use polkadot_runtime_common::xcm_sender::PriceForParachainDelivery;
+use sp_runtime::DispatchError;
+use xcm::{
+	latest::{prelude::*, XcmHash},
+	VersionedMultiLocation,
+};
+use xcm_executor::traits::WeightBounds;
+
+fn last_event() -> RuntimeEvent {
+	System::events().pop().expect("an event has been deposited").event
+}
+
+fn fee(amount: u128) -> MultiAsset {
+	(FeeAsset::get(), amount).into()
+}
+
+fn relay_call() -> Xcm<()> {
+	Xcm(vec![ClearOrigin])
+}
+
+/// Charges 10 units of the fee asset per message.
+pub struct BasePrice;
+impl PriceForParentDelivery for BasePrice {
+	fn price_for_parent_delivery(_message: &Xcm<()>) -> MultiAssets {
+		fee(10).into()
+	}
+}
+
+impl PriceForParachainDelivery for BasePrice {
+	fn price_for_parachain_delivery(_para: ParaId, _message: &Xcm<()>) -> MultiAssets {
+		fee(10).into()
+	}
+}
+
+type MarkedUp = MarkedUpDelivery<Test, BasePrice>;
+
+fn sibling_price(para: u32) -> MultiAssets {
+	MarkedUp::price_for_parachain_delivery(para.into(), &relay_call())
+}
+
+#[test]
+fn fee_markup_is_added_to_the_delivery_price() {
+	new_test_ext().execute_with(|| {
+		let dest = VersionedMultiLocation::from(MultiLocation::new(1, X1(Parachain(2000))));
+		assert_ok!(XcmFeeMarkup::set_fee_markup(RuntimeOrigin::root(), Box::new(dest.clone()), 5));
+		assert_eq!(last_event(), Event::FeeMarkupSet { dest, markup: 5 }.into());
+
+		assert_eq!(sibling_price(2000), fee(15).into());
+		assert_eq!(sibling_price(1000), fee(10).into());
+		assert_eq!(MarkedUp::price_for_parent_delivery(&relay_call()), fee(10).into());
+	});
+}
+
+#[test]
+fn zero_fee_markup_leaves_the_price_unchanged() {
+	new_test_ext().execute_with(|| {
+		let dest = VersionedMultiLocation::from(MultiLocation::parent());
+		assert_ok!(XcmFeeMarkup::set_fee_markup(RuntimeOrigin::root(), Box::new(dest.clone()), 5));
+		assert_eq!(MarkedUp::price_for_parent_delivery(&relay_call()), fee(15).into());
+
+		assert_ok!(XcmFeeMarkup::set_fee_markup(RuntimeOrigin::root(), Box::new(dest), 0));
+		assert_eq!(crate::FeeMarkups::<Test>::iter().count(), 0);
+		assert_eq!(MarkedUp::price_for_parent_delivery(&relay_call()), fee(10).into());
+	});
+}
+
+#[test]
+fn fee_markup_is_bounded() {
+	new_test_ext().execute_with(|| {
+		let dest = Box::new(VersionedMultiLocation::from(MultiLocation::parent()));
+		assert_noop!(
+			XcmFeeMarkup::set_fee_markup(
+				RuntimeOrigin::root(),
+				dest.clone(),
+				MaxFeeMarkup::get() + 1
+			),
+			Error::<Test>::FeeMarkupTooHigh
+		);
+		assert_ok!(XcmFeeMarkup::set_fee_markup(RuntimeOrigin::root(), dest, MaxFeeMarkup::get()));
+	});
+}
+
+#[test]
+fn set_fee_markup_requires_admin_origin() {
+	new_test_ext().execute_with(|| {
+		let dest = Box::new(VersionedMultiLocation::from(MultiLocation::parent()));
+		assert_noop!(
+			XcmFeeMarkup::set_fee_markup(RuntimeOrigin::signed(1), dest, 5),
+			DispatchError::BadOrigin
+		);
+	});
+}
+
+type MarkedUpExecutor = MarkedUpExecution<Test, MockExecutor>;
+
+fn local_account() -> MultiLocation {
+	MultiLocation::new(0, X1(AccountId32 { network: None, id: [1; 32] }))
+}
+
+fn transfer_to(para: u32) -> Xcm<RuntimeCall> {
+	Xcm(vec![
+		BuyExecution { fees: fee(1), weight_limit: Unlimited },
+		TransferReserveAsset {
+			assets: fee(10).into(),
+			dest: MultiLocation::new(1, X1(Parachain(para))),
+			xcm: Xcm(vec![]),
+		},
+	])
+}
+
+#[test]
+fn fee_markup_is_charged_for_transfers_to_the_destination() {
+	new_test_ext().execute_with(|| {
+		let dest = VersionedMultiLocation::from(MultiLocation::new(1, X1(Parachain(2000))));
+		assert_ok!(XcmFeeMarkup::set_fee_markup(RuntimeOrigin::root(), Box::new(dest), 5));
+
+		assert_eq!(
+			MarkedUpExecutor::execute_xcm(local_account(), transfer_to(2000), [0; 32], Weight::MAX),
+			Outcome::Complete(<() as WeightInfo>::charge_fee_markup())
+		);
+		assert_eq!(charged_fees(), vec![(local_account(), fee(5).into())]);
+		assert_eq!(executed_xcm(), vec![transfer_to(2000)]);
+	});
+}
+
+#[test]
+fn transfers_to_other_destinations_pay_no_fee_markup() {
+	new_test_ext().execute_with(|| {
+		let dest = VersionedMultiLocation::from(MultiLocation::new(1, X1(Parachain(2000))));
+		assert_ok!(XcmFeeMarkup::set_fee_markup(RuntimeOrigin::root(), Box::new(dest), 5));
+
+		assert_eq!(
+			MarkedUpExecutor::execute_xcm(local_account(), transfer_to(1000), [0; 32], Weight::MAX),
+			Outcome::Complete(Weight::zero())
+		);
+		assert_eq!(charged_fees(), vec![]);
+		assert_eq!(executed_xcm(), vec![transfer_to(1000)]);
+	});
+}
+
+#[test]
+fn failed_transfers_pay_no_fee_markup() {
+	new_test_ext().execute_with(|| {
+		let dest = VersionedMultiLocation::from(MultiLocation::new(1, X1(Parachain(2000))));
+		assert_ok!(XcmFeeMarkup::set_fee_markup(RuntimeOrigin::root(), Box::new(dest), 5));
+		let mut unpaid = transfer_to(2000);
+		unpaid.0.remove(0);
+
+		assert_eq!(
+			MarkedUpExecutor::execute_xcm(local_account(), unpaid, [0; 32], Weight::MAX),
+			Outcome::Error(XcmError::Barrier)
+		);
+		assert_eq!(charged_fees(), vec![]);
+	});
+}
+
+const EXECUTED_KEY: &[u8] = b"executed";
+
+/// Executes like the [`MockExecutor`], noting the execution in storage, but can't charge fees.
+struct InsolventExecutor;
+impl ExecuteXcm<RuntimeCall> for InsolventExecutor {
+	type Prepared = MockPrepared;
+
+	fn prepare(message: Xcm<RuntimeCall>) -> Result<Self::Prepared, Xcm<RuntimeCall>> {
+		MockExecutor::prepare(message)
+	}
+
+	fn execute(
+		origin: impl Into<MultiLocation>,
+		pre: Self::Prepared,
+		hash: XcmHash,
+		weight_credit: Weight,
+	) -> Outcome {
+		unhashed::put(EXECUTED_KEY, &true);
+		MockExecutor::execute(origin, pre, hash, weight_credit)
+	}
+
+	fn charge_fees(_location: impl Into<MultiLocation>, _fees: MultiAssets) -> XcmResult {
+		Err(XcmError::NotHoldingFees)
+	}
+}
+
+#[test]
+fn transfers_whose_origin_cannot_pay_the_fee_markup_are_rolled_back() {
+	new_test_ext().execute_with(|| {
+		let dest = VersionedMultiLocation::from(MultiLocation::new(1, X1(Parachain(2000))));
+		assert_ok!(XcmFeeMarkup::set_fee_markup(RuntimeOrigin::root(), Box::new(dest.clone()), 5));
+
+		assert_eq!(
+			MarkedUpExecution::<Test, InsolventExecutor>::execute_xcm(
+				local_account(),
+				transfer_to(2000),
+				[0; 32],
+				Weight::MAX
+			),
+			Outcome::Incomplete(<() as WeightInfo>::charge_fee_markup(), XcmError::NotHoldingFees)
+		);
+		assert_eq!(unhashed::get::<bool>(EXECUTED_KEY), None);
+
+		// Without a markup, the message is executed as it is.
+		assert_ok!(XcmFeeMarkup::set_fee_markup(RuntimeOrigin::root(), Box::new(dest), 0));
+		assert_eq!(
+			MarkedUpExecution::<Test, InsolventExecutor>::execute_xcm(
+				local_account(),
+				transfer_to(2000),
+				[0; 32],
+				Weight::MAX
+			),
+			Outcome::Complete(Weight::zero())
+		);
+		assert_eq!(unhashed::get::<bool>(EXECUTED_KEY), Some(true));
+	});
+}
+
+/// Weighs one unit of `ref_time` per instruction, like the [`MockExecutor`].
+struct InstructionCount;
+impl WeightBounds<RuntimeCall> for InstructionCount {
+	fn weight(message: &mut Xcm<RuntimeCall>) -> Result<Weight, ()> {
+		Ok(Weight::from_parts(message.0.len() as u64, 0))
+	}
+
+	fn instr_weight(_instruction: &Instruction<RuntimeCall>) -> Result<Weight, ()> {
+		Ok(Weight::from_parts(1, 0))
+	}
+}
+
+#[test]
+fn marked_up_messages_weigh_what_they_are_prepared_to() {
+	type Weigher = MarkedUpWeigher<Test, InstructionCount>;
+	new_test_ext().execute_with(|| {
+		let dest = VersionedMultiLocation::from(MultiLocation::new(1, X1(Parachain(2000))));
+		assert_ok!(XcmFeeMarkup::set_fee_markup(RuntimeOrigin::root(), Box::new(dest), 5));
+
+		for para in [1000, 2000] {
+			let prepared = MarkedUpExecutor::prepare(transfer_to(para)).ok().unwrap();
+			assert_eq!(Weigher::weight(&mut transfer_to(para)), Ok(prepared.weight_of()));
+		}
+		assert_eq!(
+			Weigher::weight(&mut transfer_to(2000)),
+			Ok(Weight::from_parts(2, 0).saturating_add(<() as WeightInfo>::charge_fee_markup()))
+		);
+	});
+}
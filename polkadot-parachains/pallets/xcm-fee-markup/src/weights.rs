@@ -0,0 +1,41 @@
+//! Weights for `pallet_xcm_fee_markup`.
+//!
+//! These are conservative estimates based on the storage accesses of each call, until they are
+//! generated from the benchmarks in [`crate::benchmarking`].
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::Weight};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for `pallet_xcm_fee_markup`.
+pub trait WeightInfo {
+	fn set_fee_markup() -> Weight;
+	fn charge_fee_markup() -> Weight;
+}
+
+/// Weights for `pallet_xcm_fee_markup` using the database weights of the runtime.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: XcmFeeMarkup FeeMarkups (r:0 w:1)
+	fn set_fee_markup() -> Weight {
+		Weight::from_parts(10_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Withdrawing the markup from the origin and depositing it to the fee receivers is estimated
+	/// at 2 reads and 2 writes.
+	fn charge_fee_markup() -> Weight {
+		Weight::from_parts(20_000_000, 0)
+			.saturating_add(T::DbWeight::get().reads(2))
+			.saturating_add(T::DbWeight::get().writes(2))
+	}
+}
+
+impl WeightInfo for () {
+	fn set_fee_markup() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+	}
+	fn charge_fee_markup() -> Weight {
+		Weight::from_parts(20_000_000, 0)
+	}
+}
@@ -23,6 +23,7 @@
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
+use codec::Encode;
 use cumulus_pallet_parachain_system::RelayNumberStrictlyIncreases;
 use pallet_transaction_payment::{FeeDetails, RuntimeDispatchInfo};
 use sp_api::impl_runtime_apis;
@@ -63,6 +64,7 @@ pub use sp_runtime::{Perbill, Permill};
 // XCM imports
 use frame_support::{traits::Contains, weights::ConstantMultiplier};
 
+pub mod weights;
 pub mod xcm_config;
 
 pub type SessionHandlers = ();
@@ -335,6 +337,18 @@ pub type Executive = frame_executive::Executive<
 	(),
 >;
 
+#[cfg(feature = "runtime-benchmarks")]
+#[macro_use]
+extern crate frame_benchmarking;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benches {
+	define_benchmarks!(
+		[frame_system, SystemBench::<Runtime>]
+		[pallet_xcm, PolkadotXcm]
+	);
+}
+
 impl_runtime_apis! {
 	impl sp_consensus_aura::AuraApi<Block, AuraId> for Runtime {
 		fn slot_duration() -> sp_consensus_aura::SlotDuration {
@@ -444,6 +458,155 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl parachains_common::runtime_api::IntegrityCheckApi<Block> for Runtime {
+		fn check_integrity() -> Vec<parachains_common::xcm_config::IntegrityFailure> {
+			xcm_config::integrity_failures()
+		}
+	}
+
+	impl parachains_common::runtime_api::XcmEventsApi<Block> for Runtime {
+		fn xcm_events() -> Vec<Vec<u8>> {
+			System::events()
+				.into_iter()
+				.filter(|record| xcm_config::is_xcm_event(&record.event))
+				.map(|record| record.event.encode())
+				.collect()
+		}
+	}
+
+	impl parachains_common::runtime_api::XcmWeightApi<Block> for Runtime {
+		fn inbound_xcm_weights() -> Vec<parachains_common::xcm_config::InboundXcmWeight> {
+			// The shell runtime doesn't meter its inbound messages.
+			Vec::new()
+		}
+
+		fn xcm_instruction_weight() -> Weight {
+			xcm_config::UnitWeightCost::get()
+		}
+	}
+
+	impl parachains_common::runtime_api::XcmpQueueApi<Block> for Runtime {
+		fn xcmp_queue_status() -> parachains_common::xcm_config::XcmpQueueStatus {
+			parachains_common::xcm_config::xcmp_queue_status::<
+				XcmpQueue,
+				<Runtime as cumulus_pallet_xcmp_queue::Config>::ExecuteOverweightOrigin,
+			>()
+		}
+	}
+
+	impl parachains_common::runtime_api::TrappedAssetsApi<Block> for Runtime {
+		fn trapped_assets(
+			_origin: xcm::VersionedMultiLocation,
+		) -> Vec<(xcm::VersionedMultiAssets, u32)> {
+			// The shell runtime doesn't index its trapped assets.
+			Vec::new()
+		}
+	}
+
+	impl parachains_common::runtime_api::CurrencyRegistryApi<Block> for Runtime {
+		fn batch_resolve_currencies(
+			locations: Vec<xcm::VersionedMultiLocation>,
+		) -> Result<
+			Vec<Option<xcm_config::CurrencyId>>,
+			parachains_common::xcm_config::BatchTooLarge,
+		> {
+			parachains_common::xcm_config::resolve_currencies::<xcm_config::CurrencyIdConvert>(
+				locations,
+			)
+		}
+	}
+
+	impl parachains_common::runtime_api::XcmLocationApi<Block> for Runtime {
+		fn self_reserve_location() -> xcm::VersionedMultiLocation {
+			xcm_config::SelfReserve::get().into()
+		}
+
+		fn currency_location(
+			currency: xcm_config::CurrencyId,
+		) -> Option<xcm::VersionedMultiLocation> {
+			xcm_config::CurrencyIdConvert::convert(currency).map(Into::into)
+		}
+	}
+
+	impl parachains_common::runtime_api::AssetMetadataApi<Block> for Runtime {
+		fn asset_metadata(
+			location: xcm::VersionedMultiLocation,
+		) -> Option<parachains_common::xcm_config::CurrencyMetadata> {
+			parachains_common::xcm_config::currency_metadata::<xcm_config::CurrencyIdConvert>(
+				location,
+			)
+		}
+	}
+
+	impl parachains_common::runtime_api::TransactDryRunApi<Block> for Runtime {
+		fn dry_run_transact(
+			call: Vec<u8>,
+			origin_kind: xcm::latest::OriginKind,
+			origin: xcm::VersionedMultiLocation,
+		) -> Result<sp_runtime::DispatchResult, parachains_common::xcm_config::TransactRejection> {
+			use xcm_executor::Config;
+			type XcmConfig = xcm_config::XcmExecutorConfig;
+			parachains_common::xcm_config::dry_run_transact::<
+				RuntimeCall,
+				<XcmConfig as Config>::SafeCallFilter,
+				<XcmConfig as Config>::OriginConverter,
+				<XcmConfig as Config>::CallDispatcher,
+			>(call, origin_kind, origin)
+		}
+	}
+
+	impl parachains_common::runtime_api::BarrierDryRunApi<Block> for Runtime {
+		fn dry_run_barrier(
+			message: Vec<u8>,
+			origin: xcm::VersionedMultiLocation,
+		) -> Result<(), parachains_common::xcm_config::BarrierRejection> {
+			parachains_common::xcm_config::dry_run_barrier::<
+				RuntimeCall,
+				xcm_config::XcmWeigher,
+				xcm_config::BarrierDenyRules,
+				xcm_config::BarrierAllowRules,
+			>(message, origin)
+		}
+	}
+
+	impl parachains_common::runtime_api::XcmDryRunApi<Block> for Runtime {
+		fn dry_run_xcm(
+			message: Vec<u8>,
+			origin: xcm::VersionedMultiLocation,
+		) -> Result<
+			parachains_common::xcm_config::XcmDryRunEffects,
+			parachains_common::xcm_config::XcmDryRunError,
+		> {
+			parachains_common::xcm_config::dry_run_xcm::<xcm_config::XcmExecutorConfig>(
+				message, origin,
+			)
+		}
+	}
+
+	impl parachains_common::runtime_api::XcmDeliveryFeeApi<Block> for Runtime {
+		fn xcm_delivery_fee(
+			dest: xcm::VersionedMultiLocation,
+			message_size: u32,
+		) -> Option<(xcm_config::CurrencyId, u128)> {
+			let dest = xcm::latest::MultiLocation::try_from(dest).ok()?;
+			parachains_common::xcm_config::delivery_fee::<
+				xcm_config::PriceForParentDelivery,
+				xcm_config::PriceForSiblingDelivery,
+				xcm_config::CurrencyIdConvert,
+			>(&dest, message_size)
+		}
+	}
+
+	impl parachains_common::runtime_api::XtokensFeeApi<Block> for Runtime {
+		fn estimate_xtokens_fee(
+			_currency: xcm_config::CurrencyId,
+			_dest: xcm::VersionedMultiLocation,
+		) -> Option<(u128, Weight)> {
+			// The shell runtime has no `orml_xtokens`.
+			None
+		}
+	}
+
 	impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentCallApi<Block, Balance, RuntimeCall>
 		for Runtime
 	{
@@ -466,6 +629,80 @@ impl_runtime_apis! {
 			ParachainSystem::collect_collation_info(header)
 		}
 	}
+
+	#[cfg(feature = "try-runtime")]
+	impl frame_try_runtime::TryRuntime<Block> for Runtime {
+		fn on_runtime_upgrade(checks: frame_try_runtime::UpgradeCheckSelect) -> (Weight, Weight) {
+			let weight = Executive::try_runtime_upgrade(checks).unwrap();
+			(weight, RuntimeBlockWeights::get().max_block)
+		}
+
+		fn execute_block(
+			block: Block,
+			state_root_check: bool,
+			signature_check: bool,
+			select: frame_try_runtime::TryStateSelect,
+		) -> Weight {
+			// NOTE: intentional unwrap: we don't want to propagate the error backwards, and want to
+			// have a backtrace here.
+			Executive::try_execute_block(block, state_root_check, signature_check, select).unwrap()
+		}
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	impl frame_benchmarking::Benchmark<Block> for Runtime {
+		fn benchmark_metadata(extra: bool) -> (
+			Vec<frame_benchmarking::BenchmarkList>,
+			Vec<frame_support::traits::StorageInfo>,
+		) {
+			use frame_benchmarking::{Benchmarking, BenchmarkList};
+			use frame_support::traits::StorageInfoTrait;
+			use frame_system_benchmarking::Pallet as SystemBench;
+
+			let mut list = Vec::<BenchmarkList>::new();
+			list_benchmarks!(list, extra);
+
+			let storage_info = AllPalletsWithSystem::storage_info();
+			return (list, storage_info)
+		}
+
+		fn dispatch_benchmark(
+			config: frame_benchmarking::BenchmarkConfig
+		) -> Result<Vec<frame_benchmarking::BenchmarkBatch>, sp_runtime::RuntimeString> {
+			use frame_benchmarking::{Benchmarking, BenchmarkBatch, TrackedStorageKey};
+
+			use frame_system_benchmarking::Pallet as SystemBench;
+			impl frame_system_benchmarking::Config for Runtime {}
+
+			// Whitelisted keys to be ignored in benchmarking DB-access tracking.
+			//
+			// Reasoning:
+			// 		Previously accessed storage keys are stored in the `StorageOverlay`, i.e. the runtime cache.
+			// 		A cache with the life-time of one block.
+			// 		Accessing these keys afterwards in the same block is considered as negligible overhead.
+			// 		Hence, we whitelist storage keys that are accessed every block anyhow because accessing them
+			// 		in our pallet can be considered free.
+			let whitelist: Vec<TrackedStorageKey> = vec![
+				// Block Number
+				hex_literal::hex!("26aa394eea5630e07c48ae0c9558cef702a5c1b19ab7a04f536c519aca4983ac").to_vec().into(),
+				// Total Issuance
+				hex_literal::hex!("c2261276cc9d1f8598ea4b6a74b15c2f57c875e4cff74148e4628f264b974c80").to_vec().into(),
+				// Execution Phase
+				hex_literal::hex!("26aa394eea5630e07c48ae0c9558cef7ff553b5a9862a516939d82b3d3d8661a").to_vec().into(),
+				// Event Count
+				hex_literal::hex!("26aa394eea5630e07c48ae0c9558cef70a98fdbe9ce6c55837576c60c7af3850").to_vec().into(),
+				// System Events
+				hex_literal::hex!("26aa394eea5630e07c48ae0c9558cef780d41e5e16056765bc8461851072c9d7").to_vec().into(),
+			];
+
+			let mut batches = Vec::<BenchmarkBatch>::new();
+			let params = (&config, &whitelist);
+			add_benchmarks!(params, batches);
+
+			if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
+			Ok(batches)
+		}
+	}
 }
 
 struct CheckInherents;
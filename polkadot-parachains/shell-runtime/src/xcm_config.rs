@@ -22,15 +22,14 @@ use super::{
 	AccountId, Balance, Balances, Convert, MaxInstructions, ParachainInfo, ParachainSystem,
 	PolkadotXcm, Runtime, RuntimeCall, RuntimeEvent, RuntimeOrigin, XcmpQueue, TEER,
 };
-use codec::{Decode, Encode, MaxEncodedLen};
 use core::marker::PhantomData;
 use cumulus_primitives_core::GlobalConsensus;
 use frame_support::{
+	match_types,
 	pallet_prelude::{Get, Weight},
 	parameter_types,
 	traits::{Everything, Nothing},
-	weights::IdentityFee,
-	RuntimeDebug,
+	weights::{constants::RocksDbWeight, IdentityFee},
 };
 use frame_system::EnsureRoot;
 use orml_traits::{
@@ -39,9 +38,15 @@ use orml_traits::{
 };
 use orml_xcm_support::{IsNativeConcrete, MultiNativeAsset};
 use pallet_xcm::XcmPassthrough;
-use parachains_common::xcm_config::{DenyReserveTransferToRelayChain, DenyThenTry};
+pub use parachains_common::xcm_config::CurrencyId;
+use parachains_common::xcm_config::{
+	cached_currency, cached_reserve, check_currency_round_trip, check_weigher,
+	general_key_matches_symbol, AssetCountWeigher, CachedConversions, ConversionWeigher,
+	DenyAllThenTry, DenyRelayTokenReserveToSelf, DenyReserveTransferToRelayChain,
+	FilteredCurrencies, IntegrityFailure, NativeCurrencies, SuperuserOnlyFromRelay,
+	XcmInstructionWeight, USDT_LOCATION,
+};
 use polkadot_parachain::primitives::Sibling;
-use scale_info::TypeInfo;
 use sp_core::ConstU32;
 use sp_std::{
 	convert::{From, Into},
@@ -50,10 +55,10 @@ use sp_std::{
 use xcm::latest::prelude::*;
 use xcm_builder::{
 	AccountId32Aliases, AllowKnownQueryResponses, AllowSubscriptionsFrom,
-	AllowTopLevelPaidExecutionFrom, CurrencyAdapter, EnsureXcmOrigin, FixedWeightBounds,
-	ParentAsSuperuser, ParentIsPreset, RelayChainAsNative, SiblingParachainAsNative,
-	SiblingParachainConvertsVia, SignedAccountId32AsNative, SignedToAccountId32,
-	SovereignSignedViaLocation, TakeWeightCredit, UsingComponents,
+	AllowTopLevelPaidExecutionFrom, AllowUnpaidExecutionFrom, CurrencyAdapter, EnsureXcmOrigin,
+	FixedWeightBounds, ParentAsSuperuser, ParentIsPreset, RelayChainAsNative,
+	SiblingParachainAsNative, SiblingParachainConvertsVia, SignedAccountId32AsNative,
+	SignedToAccountId32, SovereignSignedViaLocation, TakeWeightCredit, UsingComponents,
 };
 use xcm_executor::XcmExecutor;
 use xcm_transactor_primitives::*;
@@ -65,6 +70,12 @@ const fn teer_general_key() -> Junction {
 
 const TEER_GENERAL_KEY: Junction = teer_general_key();
 
+// The general key identifies TEER across chains, it must not diverge from TEER's symbol.
+const _: () = assert!(
+	general_key_matches_symbol(&TEER_GENERAL_KEY, CurrencyId::TEER.symbol()),
+	"TEER's general key does not match its symbol",
+);
+
 parameter_types! {
 	pub const RelayChainLocation: MultiLocation = MultiLocation::parent();
 	pub const RelayNetwork: NetworkId = NetworkId::Kusama;
@@ -79,24 +90,6 @@ parameter_types! {
 	};
 }
 
-// Supported Currencies.
-#[derive(
-	Encode,
-	Decode,
-	Eq,
-	PartialEq,
-	Copy,
-	Clone,
-	RuntimeDebug,
-	PartialOrd,
-	Ord,
-	TypeInfo,
-	MaxEncodedLen,
-)]
-pub enum CurrencyId {
-	TEER,
-}
-
 /// Converts a CurrencyId into a Multilocation, used by xtoken for XCMP.
 pub struct CurrencyIdConvert;
 impl Convert<CurrencyId, Option<MultiLocation>> for CurrencyIdConvert {
@@ -106,29 +99,37 @@ impl Convert<CurrencyId, Option<MultiLocation>> for CurrencyIdConvert {
 				1,
 				X2(Parachain(ParachainInfo::parachain_id().into()), TEER_GENERAL_KEY),
 			)),
+			CurrencyId::KSM => Some(MultiLocation::parent()),
+			CurrencyId::USDT => Some(USDT_LOCATION),
 		}
 	}
 }
 
 /// Converts a Mulitloaction into a CurrencyId. Used by XCMP LocalAssetTransactor for asset filtering:
 /// we only accept Assets that are convertable to a "CurrencyId".
+///
+/// Cached while a message is executed, see [`CachedConversions`].
 impl Convert<MultiLocation, Option<CurrencyId>> for CurrencyIdConvert {
 	fn convert(location: MultiLocation) -> Option<CurrencyId> {
-		let self_para_id: u32 = ParachainInfo::parachain_id().into();
-
-		match location {
-			MultiLocation { parents, interior } if parents == 1 => match interior {
-				X2(Parachain(para_id), junction)
-					if junction == TEER_GENERAL_KEY && para_id == self_para_id =>
-					Some(CurrencyId::TEER),
+		cached_currency(&location, || {
+			let self_para_id: u32 = ParachainInfo::parachain_id().into();
+
+			match location {
+				location if location == USDT_LOCATION => Some(CurrencyId::USDT),
+				MultiLocation { parents, interior } if parents == 1 => match interior {
+					Here => Some(CurrencyId::KSM),
+					X2(Parachain(para_id), junction)
+						if junction == TEER_GENERAL_KEY && para_id == self_para_id =>
+						Some(CurrencyId::TEER),
+					_ => None,
+				},
+				MultiLocation { parents, interior } if parents == 0 => match interior {
+					X1(junction) if junction == TEER_GENERAL_KEY => Some(CurrencyId::TEER),
+					_ => None,
+				},
 				_ => None,
-			},
-			MultiLocation { parents, interior } if parents == 0 => match interior {
-				X1(junction) if junction == TEER_GENERAL_KEY => Some(CurrencyId::TEER),
-				_ => None,
-			},
-			_ => None,
-		}
+			}
+		})
 	}
 }
 
@@ -143,11 +144,33 @@ impl Convert<MultiAsset, Option<CurrencyId>> for CurrencyIdConvert {
 	}
 }
 
+/// Price of delivering a message to the relay chain, currently free.
+pub type PriceForParentDelivery = ();
+/// Price of delivering a message to a sibling parachain, currently free.
+pub type PriceForSiblingDelivery = ();
+
+/// Whether `event` is emitted by the XCM machinery, these are streamed to indexers.
+pub fn is_xcm_event(event: &RuntimeEvent) -> bool {
+	use cumulus_pallet_parachain_system::Event as ParachainSystemEvent;
+	matches!(
+		event,
+		RuntimeEvent::XcmpQueue(_) |
+			RuntimeEvent::PolkadotXcm(_) |
+			RuntimeEvent::CumulusXcm(_) |
+			RuntimeEvent::DmpQueue(_) |
+			RuntimeEvent::ParachainSystem(
+				ParachainSystemEvent::UpwardMessageSent { .. } |
+					ParachainSystemEvent::DownwardMessagesReceived { .. } |
+					ParachainSystemEvent::DownwardMessagesProcessed { .. }
+			)
+	)
+}
+
 /// The means for routing XCM messages which are not for local execution into the right message
 /// queues.
 pub type XcmRouter = (
 	// Two routers - use UMP to communicate with the relay chain:
-	cumulus_primitives_utility::ParentAsUmp<ParachainSystem, PolkadotXcm, ()>,
+	cumulus_primitives_utility::ParentAsUmp<ParachainSystem, PolkadotXcm, PriceForParentDelivery>,
 	// ..and XCMP to communicate with the sibling chains.
 	XcmpQueue,
 );
@@ -164,12 +187,14 @@ pub type LocationToAccountId = (
 	AccountId32Aliases<RelayNetwork, AccountId>,
 );
 
-/// Means for transacting assets on this chain.
+/// Means for transacting assets on this chain. The shell runtime holds TEER only, it doesn't
+/// accept foreign currencies.
 pub type LocalAssetTransactor = CurrencyAdapter<
 	// Use this currency:
 	Balances,
-	// Matcher: matches concrete fungible assets whose `id` could be converted into `CurrencyId`.
-	IsNativeConcrete<CurrencyId, CurrencyIdConvert>,
+	// Matcher: matches concrete fungible assets whose `id` could be converted into a native
+	// `CurrencyId`.
+	IsNativeConcrete<CurrencyId, FilteredCurrencies<CurrencyIdConvert, NativeCurrencies>>,
 	// Do a simple punn to convert an AccountId32 MultiLocation into a native chain account ID:
 	LocationToAccountId,
 	// Our chain's account ID type (we can't get away without mentioning it explicitly):
@@ -181,7 +206,10 @@ pub type LocalAssetTransactor = CurrencyAdapter<
 /// This is the type we use to convert an (incoming) XCM origin into a local `Origin` instance,
 /// ready for dispatching a transaction with Xcm's `Transact`. There is an `OriginKind` which can
 /// biases the kind of local `Origin` it will become.
-pub type XcmOriginToTransactDispatchOrigin = (
+///
+/// `OriginKind::Superuser` is only ever honoured for the relay chain, no matter which converters
+/// are listed here.
+pub type XcmOriginToTransactDispatchOrigin = SuperuserOnlyFromRelay<(
 	// Sovereign account converter; this attempts to derive an `AccountId` from the origin location
 	// using `LocationToAccountId` and then turn that into the usual `Signed` origin. Useful for
 	// foreign chains who want to have a local sovereign account on this chain which they control.
@@ -200,7 +228,7 @@ pub type XcmOriginToTransactDispatchOrigin = (
 	SignedAccountId32AsNative<RelayNetwork, RuntimeOrigin>,
 	// Xcm origins can be represented natively under the Xcm pallet's Xcm origin.
 	XcmPassthrough<RuntimeOrigin>,
-);
+)>;
 
 /// This struct offers uses RelativeReserveProvider to output relative views of multilocations
 /// However, additionally accepts a MultiLocation that aims at representing the chain part
@@ -208,47 +236,100 @@ pub type XcmOriginToTransactDispatchOrigin = (
 /// If a token reserve matches against this absolute view, we return  Some(MultiLocation::here())
 /// This helps users by preventing errors when they try to transfer a token through xtokens
 /// to our chain (either inserting the relative or the absolute value).
+///
+/// The reserve of relay chain assets is the relay chain, the one of a sibling's assets the
+/// sibling. Only abstract assets and those of other consensus systems have none.
+///
+/// Cached while a message is executed, see [`CachedConversions`]. The cache is keyed by asset id
+/// only, so there must be a single `AbsoluteMultiLocation` per runtime.
 pub struct AbsoluteAndRelativeReserve<AbsoluteMultiLocation>(PhantomData<AbsoluteMultiLocation>);
 impl<AbsoluteMultiLocation> Reserve for AbsoluteAndRelativeReserve<AbsoluteMultiLocation>
 where
 	AbsoluteMultiLocation: Get<MultiLocation>,
 {
 	fn reserve(asset: &MultiAsset) -> Option<MultiLocation> {
-		RelativeReserveProvider::reserve(asset).map(|relative_reserve| {
-			if relative_reserve == AbsoluteMultiLocation::get() {
-				MultiLocation::here()
-			} else {
-				relative_reserve
-			}
+		cached_reserve(&asset.id, || {
+			RelativeReserveProvider::reserve(asset).map(|relative_reserve| {
+				if relative_reserve == AbsoluteMultiLocation::get() {
+					MultiLocation::here()
+				} else {
+					relative_reserve
+				}
+			})
 		})
 	}
 }
 
 parameter_types! {
-	// Weight for one XCM operation. Copied from moonbeam.
-	pub UnitWeightCost: Weight = Weight::from_parts(200_000_000u64, DEFAULT_PROOF_SIZE);
+	// Weight for one XCM operation.
+	pub const UnitWeightCost: Weight = XcmInstructionWeight::get();
 	// One TEER buys 1 second of weight.
 	pub const WeightPrice: (MultiLocation, u128) = (MultiLocation::parent(), TEER);
+	/// The weight of converting an asset id to a currency and normalizing its reserve, which read
+	/// the parachain id.
+	pub XcmConversionWeight: Weight = RocksDbWeight::get().reads(1);
 }
 
-pub type Barrier = DenyThenTry<
-	DenyReserveTransferToRelayChain,
-	(
-		TakeWeightCredit,
-		AllowTopLevelPaidExecutionFrom<Everything>,
-		// Expected responses are OK.
-		AllowKnownQueryResponses<PolkadotXcm>,
-		// Subscriptions for version tracking are OK.
-		AllowSubscriptionsFrom<Everything>,
-	),
+/// Weighs every instruction at `UnitWeightCost`, plus as much for every further asset it carries.
+/// Every distinct asset id of a message adds `XcmConversionWeight`, see [`ConversionWeigher`].
+///
+/// pallet-xcm executes its messages with the weight determined by its weigher as the limit, so it
+/// has to use the same one as the executor.
+pub type XcmWeigher = ConversionWeigher<
+	AssetCountWeigher<
+		FixedWeightBounds<UnitWeightCost, RuntimeCall, MaxInstructions>,
+		UnitWeightCost,
+	>,
+	XcmConversionWeight,
 >;
 
+/// Messages any of these rules rejects are denied.
+pub type BarrierDenyRules = (
+	DenyReserveTransferToRelayChain,
+	// Reserve transfers of KSM, whose reserve we are not.
+	DenyRelayTokenReserveToSelf,
+);
+
+match_types! {
+	pub type ParentLocation: impl Contains<MultiLocation> = {
+		MultiLocation { parents: 1, interior: Here }
+	};
+}
+
+/// Messages which aren't denied are admitted by any of these rules.
+pub type BarrierAllowRules = (
+	TakeWeightCredit,
+	AllowTopLevelPaidExecutionFrom<Everything>,
+	// Expected responses are OK.
+	AllowKnownQueryResponses<PolkadotXcm>,
+	// Subscriptions for version tracking are OK.
+	AllowSubscriptionsFrom<Everything>,
+	// System notifications of the relay chain, e.g. about HRMP channels, are unpaid. They don't
+	// start with `UnpaidExecution`, so `AllowExplicitUnpaidExecutionFrom` would reject them.
+	AllowUnpaidExecutionFrom<ParentLocation>,
+);
+
+pub type Barrier = DenyAllThenTry<BarrierDenyRules, BarrierAllowRules>;
+
+/// The calls `Transact` may dispatch, whatever the origin.
+///
+/// As the relay chain is converted into a superuser origin, anything not listed here could be
+/// dispatched with root privileges. Calls are still subject to the `BaseCallFilter`, which
+/// disables balance transfers while running the shell runtime. For the rationale, see Kusama's
+/// filter: https://github.com/paritytech/polkadot/blob/19fdd197aff085f7f66e54942999fd536e7df475/runtime/kusama/src/xcm_config.rs#L171
 pub struct SafeCallFilter;
 impl frame_support::traits::Contains<RuntimeCall> for SafeCallFilter {
-	fn contains(_call: &RuntimeCall) -> bool {
-		// This is safe, as we prevent arbitrary xcm-transact executions.
-		// For rationale, see:https://github.com/paritytech/polkadot/blob/19fdd197aff085f7f66e54942999fd536e7df475/runtime/kusama/src/xcm_config.rs#L171
-		true
+	fn contains(call: &RuntimeCall) -> bool {
+		matches!(
+			call,
+			RuntimeCall::System(
+				frame_system::Call::remark { .. } | frame_system::Call::remark_with_event { .. }
+			) | RuntimeCall::Balances(
+				pallet_balances::Call::transfer { .. } |
+					pallet_balances::Call::transfer_keep_alive { .. } |
+					pallet_balances::Call::transfer_all { .. }
+			) | RuntimeCall::XcmTransactor(..)
+		)
 	}
 }
 
@@ -267,7 +348,7 @@ impl xcm_executor::Config for XcmExecutorConfig {
 	type IsTeleporter = (); // No teleport for now. Better be safe than sorry.
 	type UniversalLocation = UniversalLocation;
 	type Barrier = Barrier;
-	type Weigher = FixedWeightBounds<UnitWeightCost, RuntimeCall, MaxInstructions>;
+	type Weigher = XcmWeigher;
 	type Trader = UsingComponents<IdentityFee<Balance>, SelfReserve, AccountId, Balances, ()>;
 	type ResponseHandler = PolkadotXcm;
 	type SubscriptionService = PolkadotXcm;
@@ -284,6 +365,9 @@ impl xcm_executor::Config for XcmExecutorConfig {
 	type SafeCallFilter = SafeCallFilter;
 }
 
+/// The XCM executor, caching the conversions of asset ids while it executes a message.
+pub type CachingXcmExecutor = CachedConversions<XcmExecutor<XcmExecutorConfig>>;
+
 #[cfg(feature = "runtime-benchmarks")]
 parameter_types! {
 	pub ReachableDest: Option<MultiLocation> = Some(Parent.into());
@@ -300,13 +384,15 @@ impl pallet_xcm::Config for Runtime {
 	type XcmRouter = XcmRouter;
 	type ExecuteXcmOrigin = EnsureXcmOrigin<RuntimeOrigin, LocalOriginToLocation>; // Allow any local origin in XCM execution.
 	type XcmExecuteFilter = Nothing; // Disable generic XCM execution. This does not affect Teleport or Reserve Transfer.
-	type XcmExecutor = XcmExecutor<XcmExecutorConfig>;
+	type XcmExecutor = CachingXcmExecutor;
 	type XcmTeleportFilter = Nothing; // Do not allow teleports
 	type XcmReserveTransferFilter = Everything; // Transfer are allowed
-	type Weigher = FixedWeightBounds<UnitWeightCost, RuntimeCall, MaxInstructions>;
+	type Weigher = XcmWeigher;
 	type UniversalLocation = UniversalLocation;
 	type RuntimeOrigin = RuntimeOrigin;
 	type RuntimeCall = RuntimeCall;
+	// Destinations of unknown version we send to are queued for version discovery, this many at
+	// most. They learn `AdvertisedXcmVersion` from our answer to their own subscription.
 	const VERSION_DISCOVERY_QUEUE_SIZE: u32 = 100;
 	type AdvertisedXcmVersion = pallet_xcm::CurrentXcmVersion;
 	type Currency = Balances;
@@ -314,8 +400,7 @@ impl pallet_xcm::Config for Runtime {
 	type TrustedLockers = ();
 	type SovereignAccountOf = LocationToAccountId;
 	type MaxLockers = ConstU32<8>;
-	// TODO pallet-xcm weights
-	type WeightInfo = pallet_xcm::TestWeightInfo;
+	type WeightInfo = crate::weights::pallet_xcm::WeightInfo<Runtime>;
 	#[cfg(feature = "runtime-benchmarks")]
 	type ReachableDest = ReachableDest;
 	type AdminOrigin = EnsureRoot<AccountId>;
@@ -333,30 +418,31 @@ impl pallet_xcm_transactor::Config for Runtime {
 	type SwapOrigin = EnsureRoot<AccountId>;
 	type ShellRuntimeParaId = ShellRuntimeParaId;
 	type IntegriteeKsmParaId = IntegriteeKsmParaId;
-	type WeightInfo = ();
+	type WeightInfo = crate::weights::pallet_xcm_transactor::WeightInfo<Runtime>;
 }
 
 impl cumulus_pallet_xcm::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
-	type XcmExecutor = XcmExecutor<XcmExecutorConfig>;
+	type XcmExecutor = CachingXcmExecutor;
 }
 
-// FIXME: Update to PolkadotXcm.
 impl cumulus_pallet_xcmp_queue::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
-	type XcmExecutor = XcmExecutor<XcmExecutorConfig>;
+	type XcmExecutor = CachingXcmExecutor;
 	type ChannelInfo = ParachainSystem;
-	type VersionWrapper = ();
+	// Messages to siblings are sent in the XCM version `PolkadotXcm` negotiated with them, or in
+	// its `SafeXcmVersion` until then.
+	type VersionWrapper = PolkadotXcm;
 	type ExecuteOverweightOrigin = EnsureRoot<AccountId>;
 	type ControllerOrigin = EnsureRoot<AccountId>;
 	type ControllerOriginConverter = XcmOriginToTransactDispatchOrigin;
 	type WeightInfo = cumulus_pallet_xcmp_queue::weights::SubstrateWeight<Runtime>;
-	type PriceForSiblingDelivery = ();
+	type PriceForSiblingDelivery = PriceForSiblingDelivery;
 }
 
 impl cumulus_pallet_dmp_queue::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
-	type XcmExecutor = XcmExecutor<XcmExecutorConfig>;
+	type XcmExecutor = CachingXcmExecutor;
 	type ExecuteOverweightOrigin = EnsureRoot<AccountId>;
 }
 
@@ -373,11 +459,8 @@ parameter_types! {
 	};
 }
 
-/// Copied from moonbeam: https://github.com/PureStake/moonbeam/blob/095031d171b0c163e5649ee35acbc36eef681a82/primitives/xcm/src/ethereum_xcm.rs#L34
-pub const DEFAULT_PROOF_SIZE: u64 = 128 * 1024;
-
 parameter_types! {
-	pub const BaseXcmWeight: Weight= Weight::from_parts(200_000_000u64, DEFAULT_PROOF_SIZE);
+	pub const BaseXcmWeight: Weight = XcmInstructionWeight::get();
 	pub const MaxAssetsForTransfer: usize = 2;
 }
 
@@ -400,3 +483,113 @@ impl Convert<AccountId, MultiLocation> for AccountIdToMultiLocation {
 		X1(AccountId32 { network: None, id: account.into() }).into()
 	}
 }
+
+/// Currencies which intentionally don't convert to a location and back, each with the reason.
+const NOT_ROUND_TRIPPING: &[CurrencyId] = &[];
+
+/// The consistency checks of the XCM configuration this runtime fails, see `IntegrityCheckApi`.
+/// The unit tests below check the same in more detail.
+pub fn integrity_failures() -> Vec<IntegrityFailure> {
+	[
+		check_currency_round_trip::<CurrencyIdConvert>(NOT_ROUND_TRIPPING),
+		check_weigher::<XcmWeigher, <Runtime as pallet_xcm::Config>::Weigher, RuntimeCall>(
+			"pallet_xcm",
+			MaxInstructions::get(),
+		),
+	]
+	.into_iter()
+	.flatten()
+	.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use parachains_common::xcm_config::{
+		asymmetric_currencies, representative_messages, weigher_mismatches,
+	};
+
+	#[test]
+	fn currencies_round_trip_through_their_location() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			assert_eq!(asymmetric_currencies::<CurrencyIdConvert>(NOT_ROUND_TRIPPING), vec![]);
+		});
+	}
+
+	/// Messages sent by `pallet_xcm` must weigh the same as when the executor runs them, up to
+	/// the `MaxInstructions` limit.
+	#[test]
+	fn executor_and_pallet_xcm_weigh_alike() {
+		let messages = representative_messages(MaxInstructions::get());
+		assert_eq!(
+			weigher_mismatches::<
+				<XcmExecutorConfig as xcm_executor::Config>::Weigher,
+				<Runtime as pallet_xcm::Config>::Weigher,
+				RuntimeCall,
+			>(messages),
+			Vec::<usize>::new()
+		);
+	}
+
+	mod safe_call_filter {
+		use super::*;
+		use frame_support::traits::Contains;
+		use sp_runtime::MultiAddress;
+
+		fn account(byte: u8) -> MultiAddress<AccountId, ()> {
+			MultiAddress::Id(AccountId::new([byte; 32]))
+		}
+
+		#[test]
+		fn remarks_and_transfers_are_allowed() {
+			let calls = [
+				RuntimeCall::System(frame_system::Call::remark { remark: vec![1] }),
+				RuntimeCall::System(frame_system::Call::remark_with_event { remark: vec![1] }),
+				RuntimeCall::Balances(pallet_balances::Call::transfer {
+					dest: account(1),
+					value: TEER,
+				}),
+				RuntimeCall::Balances(pallet_balances::Call::transfer_keep_alive {
+					dest: account(1),
+					value: TEER,
+				}),
+				RuntimeCall::Balances(pallet_balances::Call::transfer_all {
+					dest: account(1),
+					keep_alive: true,
+				}),
+			];
+			for call in calls {
+				assert!(SafeCallFilter::contains(&call), "{:?} should be allowed", call);
+			}
+		}
+
+		#[test]
+		fn privileged_calls_are_rejected() {
+			let remark = RuntimeCall::System(frame_system::Call::remark { remark: vec![1] });
+			let calls = [
+				RuntimeCall::System(frame_system::Call::set_code { code: vec![] }),
+				RuntimeCall::System(frame_system::Call::set_storage { items: vec![] }),
+				RuntimeCall::Sudo(pallet_sudo::Call::sudo { call: Box::new(remark.clone()) }),
+				RuntimeCall::Sudo(pallet_sudo::Call::set_key { new: account(1) }),
+				RuntimeCall::Balances(pallet_balances::Call::force_transfer {
+					source: account(1),
+					dest: account(2),
+					value: TEER,
+				}),
+				RuntimeCall::PolkadotXcm(pallet_xcm::Call::force_default_xcm_version {
+					maybe_xcm_version: None,
+				}),
+			];
+			for call in calls {
+				assert!(!SafeCallFilter::contains(&call), "{:?} should be rejected", call);
+			}
+		}
+	}
+
+	#[test]
+	fn integrity_checks_pass() {
+		sp_io::TestExternalities::default().execute_with(|| {
+			assert_eq!(integrity_failures(), vec![]);
+		});
+	}
+}
@@ -0,0 +1,192 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Guard the switch from the relay chain consensus to Aura by the version of the `AuraApi`.
+//!
+//! Both the shell and the full runtime implement the `AuraApi`, so for them the upgrade changes
+//! nothing about the consensus. Only a chain started with a runtime lacking the API, like the
+//! original shell runtime, switches from the relay chain consensus to Aura, once a runtime upgrade
+//! brings the API. Rather than switching on the mere presence of the API, the node requires the
+//! runtime to implement at least the version the node has been built against. Older versions keep
+//! using the relay chain consensus; newer ones switch, but are reported as they might be
+//! incompatible.
+//!
+//! The switch itself is logged and sent to telemetry once, such that it can be confirmed that the
+//! upgrade took effect.
+
+use codec::Codec;
+use parachains_common::{Block, BlockNumber, Hash};
+use sc_telemetry::{telemetry, TelemetryHandle, CONSENSUS_INFO};
+use sp_api::{ApiExt, ProvideRuntimeApi, RuntimeApiInfo};
+use sp_consensus_aura::AuraApi;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+const LOG_TARGET: &str = "aura-version";
+
+/// The version of the `AuraApi` this node has been built against.
+pub fn expected_aura_api_version<AuraId: Codec>() -> u32 {
+	<dyn AuraApi<Block, AuraId> as RuntimeApiInfo>::VERSION
+}
+
+/// The version of the `AuraApi` the runtime at `hash` implements, `None` if it doesn't.
+pub fn aura_api_version<Client, AuraId>(client: &Client, hash: Hash) -> Option<u32>
+where
+	Client: ProvideRuntimeApi<Block>,
+	Client::Api: AuraApi<Block, AuraId>,
+	AuraId: Codec,
+{
+	client
+		.runtime_api()
+		.api_version::<dyn AuraApi<Block, AuraId>>(hash)
+		.ok()
+		.flatten()
+}
+
+/// Decides whether to use Aura for a given `AuraApi` version, reporting unexpected versions.
+pub struct AuraVersionGuard {
+	expected: u32,
+	/// The last unexpected version reported, to report each of them only once in a row.
+	reported: AtomicU32,
+	/// What the guard decides for, `import` or `authoring`, to tell their switch reports apart.
+	context: &'static str,
+	telemetry: Option<TelemetryHandle>,
+	/// Whether a runtime without Aura has been seen, such that using Aura is a switch.
+	relay_chain_seen: AtomicBool,
+	/// Whether the switch to Aura has been reported, to report it only once.
+	switch_reported: AtomicBool,
+}
+
+impl AuraVersionGuard {
+	/// Require at least version `expected` of the `AuraApi`, reporting the switch to Aura for
+	/// `context` to `telemetry`.
+	pub fn new(expected: u32, context: &'static str, telemetry: Option<TelemetryHandle>) -> Self {
+		Self {
+			expected,
+			reported: AtomicU32::new(expected),
+			context,
+			telemetry,
+			relay_chain_seen: AtomicBool::new(false),
+			switch_reported: AtomicBool::new(false),
+		}
+	}
+
+	/// Like [`Self::use_aura`], for block `number`, reporting the first switch from the relay
+	/// chain consensus to Aura.
+	pub fn use_aura_at(&self, version: Option<u32>, number: BlockNumber) -> bool {
+		let aura = self.use_aura(version);
+		if !aura {
+			self.relay_chain_seen.store(true, Ordering::Relaxed);
+		} else if self.relay_chain_seen.load(Ordering::Relaxed) &&
+			!self.switch_reported.swap(true, Ordering::Relaxed)
+		{
+			log::info!(
+				target: LOG_TARGET,
+				"Switched to Aura consensus at block #{} ({})",
+				number,
+				self.context,
+			);
+			telemetry!(
+				self.telemetry;
+				CONSENSUS_INFO;
+				"aura.switched_to_aura";
+				"number" => number,
+				"context" => self.context
+			);
+		}
+		aura
+	}
+
+	/// Whether to use Aura for a runtime implementing `version` of the `AuraApi`, `None` if it
+	/// doesn't implement it at all.
+	pub fn use_aura(&self, version: Option<u32>) -> bool {
+		let Some(version) = version else { return false };
+		if version != self.expected && self.reported.swap(version, Ordering::Relaxed) != version {
+			if version < self.expected {
+				log::warn!(
+					target: LOG_TARGET,
+					"The runtime implements AuraApi version {}, but at least version {} is \
+					required. Staying with the relay chain consensus.",
+					version,
+					self.expected,
+				);
+			} else {
+				log::warn!(
+					target: LOG_TARGET,
+					"The runtime implements AuraApi version {}, newer than the expected version \
+					{}. Using Aura anyway, consider upgrading the node.",
+					version,
+					self.expected,
+				);
+			}
+		}
+		version >= self.expected
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn expected_version_switches_to_aura() {
+		let guard = AuraVersionGuard::new(2, "test", None);
+		assert!(guard.use_aura(Some(2)));
+	}
+
+	#[test]
+	fn missing_api_keeps_relay_chain_consensus() {
+		let guard = AuraVersionGuard::new(2, "test", None);
+		assert!(!guard.use_aura(None));
+	}
+
+	#[test]
+	fn older_version_keeps_relay_chain_consensus() {
+		let guard = AuraVersionGuard::new(2, "test", None);
+		assert!(!guard.use_aura(Some(1)));
+		assert_eq!(guard.reported.load(Ordering::Relaxed), 1);
+	}
+
+	#[test]
+	fn newer_version_switches_to_aura() {
+		let guard = AuraVersionGuard::new(2, "test", None);
+		assert!(guard.use_aura(Some(3)));
+		assert_eq!(guard.reported.load(Ordering::Relaxed), 3);
+	}
+
+	#[test]
+	fn switch_to_aura_is_reported_once() {
+		let guard = AuraVersionGuard::new(2, "test", None);
+		assert!(!guard.use_aura_at(None, 1));
+		assert!(!guard.switch_reported.load(Ordering::Relaxed));
+
+		assert!(guard.use_aura_at(Some(2), 2));
+		assert!(guard.switch_reported.load(Ordering::Relaxed));
+		assert!(guard.use_aura_at(Some(2), 3));
+	}
+
+	#[test]
+	fn starting_with_aura_is_no_switch() {
+		let guard = AuraVersionGuard::new(2, "test", None);
+		assert!(guard.use_aura_at(Some(2), 1));
+		assert!(!guard.switch_reported.load(Ordering::Relaxed));
+	}
+
+	#[test]
+	fn node_expects_the_version_it_is_built_against() {
+		assert_eq!(expected_aura_api_version::<parachains_common::AuraId>(), 1);
+	}
+}
@@ -0,0 +1,272 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Export authoring statistics of the collator to a JSON file.
+//!
+//! Meant for collator competitions and external monitoring. The block proposer is wrapped in a
+//! [`StatsProposerFactory`]: Aura only asks for a proposer in slots we are the author of, so every
+//! proposal that succeeds counts as an authored block and every one that fails or is abandoned
+//! (e.g. because the slot ended) as a missed slot. [`run`] periodically writes the statistics to
+//! a file.
+
+use futures::{future::BoxFuture, FutureExt};
+use serde::{Deserialize, Serialize};
+use sp_consensus::{Environment, Proposal, Proposer};
+use sp_inherents::InherentData;
+use sp_runtime::{traits::Block as BlockT, Digest};
+use std::{
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex},
+	time::{Duration, Instant, SystemTime},
+};
+
+const LOG_TARGET: &str = "authoring-stats";
+
+/// Statistics of the blocks we authored, shared between the proposers and the writer task.
+#[derive(Clone, Default)]
+pub struct AuthoringStats(Arc<Mutex<Counters>>);
+
+#[derive(Default)]
+struct Counters {
+	blocks_authored: u64,
+	slots_missed: u64,
+	total_proposal_time: Duration,
+}
+
+/// The statistics as written to the file.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+	pub blocks_authored: u64,
+	pub slots_missed: u64,
+	/// Average time it took to propose an authored block, in milliseconds.
+	pub average_proposal_time_ms: u64,
+	/// When the statistics have been written, in seconds since the unix epoch.
+	pub updated_at: u64,
+}
+
+impl AuthoringStats {
+	fn note_authored(&self, proposal_time: Duration) {
+		let mut counters = self.0.lock().expect("lock is never poisoned; qed");
+		counters.blocks_authored += 1;
+		counters.total_proposal_time += proposal_time;
+	}
+
+	fn note_missed(&self) {
+		self.0.lock().expect("lock is never poisoned; qed").slots_missed += 1;
+	}
+
+	fn snapshot(&self) -> Snapshot {
+		let counters = self.0.lock().expect("lock is never poisoned; qed");
+		let average_proposal_time = counters
+			.total_proposal_time
+			.checked_div(counters.blocks_authored as u32)
+			.unwrap_or_default();
+		Snapshot {
+			blocks_authored: counters.blocks_authored,
+			slots_missed: counters.slots_missed,
+			average_proposal_time_ms: average_proposal_time.as_millis() as u64,
+			updated_at: SystemTime::now()
+				.duration_since(SystemTime::UNIX_EPOCH)
+				.unwrap_or_default()
+				.as_secs(),
+		}
+	}
+}
+
+/// Counts a slot as missed, unless [`Self::finish`] is called before it is dropped.
+struct PendingProposal {
+	stats: AuthoringStats,
+	started: Instant,
+	finished: bool,
+}
+
+impl PendingProposal {
+	fn new(stats: AuthoringStats) -> Self {
+		Self { stats, started: Instant::now(), finished: false }
+	}
+
+	fn finish(mut self, authored: bool) {
+		self.finished = true;
+		if authored {
+			self.stats.note_authored(self.started.elapsed());
+		} else {
+			self.stats.note_missed();
+		}
+	}
+}
+
+impl Drop for PendingProposal {
+	fn drop(&mut self) {
+		if !self.finished {
+			self.stats.note_missed();
+		}
+	}
+}
+
+/// Proposer factory recording the outcome of every proposal in [`AuthoringStats`].
+///
+/// Without stats, it behaves exactly like the inner factory.
+pub struct StatsProposerFactory<PF> {
+	inner: PF,
+	stats: Option<AuthoringStats>,
+}
+
+impl<PF> StatsProposerFactory<PF> {
+	/// Wrap `inner`, recording to `stats` if given.
+	pub fn new(inner: PF, stats: Option<AuthoringStats>) -> Self {
+		Self { inner, stats }
+	}
+}
+
+impl<B: BlockT, PF: Environment<B>> Environment<B> for StatsProposerFactory<PF> {
+	type Proposer = StatsProposer<PF::Proposer>;
+	type CreateProposer = BoxFuture<'static, Result<Self::Proposer, Self::Error>>;
+	type Error = PF::Error;
+
+	fn init(&mut self, parent_header: &B::Header) -> Self::CreateProposer {
+		let pending = self.stats.clone().map(PendingProposal::new);
+		self.inner
+			.init(parent_header)
+			.map(move |proposer| match proposer {
+				Ok(inner) => Ok(StatsProposer { inner, pending }),
+				Err(e) => {
+					if let Some(pending) = pending {
+						pending.finish(false);
+					}
+					Err(e)
+				},
+			})
+			.boxed()
+	}
+}
+
+/// Proposer created by the [`StatsProposerFactory`].
+pub struct StatsProposer<P> {
+	inner: P,
+	pending: Option<PendingProposal>,
+}
+
+impl<B: BlockT, P: Proposer<B>> Proposer<B> for StatsProposer<P> {
+	type Error = P::Error;
+	type Transaction = P::Transaction;
+	type Proposal = BoxFuture<'static, Result<Proposal<B, P::Transaction, P::Proof>, P::Error>>;
+	type ProofRecording = P::ProofRecording;
+	type Proof = P::Proof;
+
+	fn propose(
+		self,
+		inherent_data: InherentData,
+		inherent_digests: Digest,
+		max_duration: Duration,
+		block_size_limit: Option<usize>,
+	) -> Self::Proposal {
+		let Self { inner, pending } = self;
+		inner
+			.propose(inherent_data, inherent_digests, max_duration, block_size_limit)
+			.map(move |proposal| {
+				if let Some(pending) = pending {
+					pending.finish(proposal.is_ok());
+				}
+				proposal
+			})
+			.boxed()
+	}
+}
+
+fn write_stats(path: &Path, snapshot: &Snapshot) -> std::io::Result<()> {
+	// Write to a temporary file first, such that readers never see a partially written file.
+	let tmp_path = path.with_extension("tmp");
+	std::fs::write(&tmp_path, serde_json::to_vec_pretty(snapshot)?)?;
+	std::fs::rename(&tmp_path, path)
+}
+
+/// Write the statistics to `path` every `interval`. Failing writes are logged and retried at
+/// the next interval.
+pub async fn run(stats: AuthoringStats, path: PathBuf, interval: Duration) {
+	loop {
+		futures_timer::Delay::new(interval).await;
+		if let Err(e) = write_stats(&path, &stats.snapshot()) {
+			log::warn!(
+				target: LOG_TARGET,
+				"Could not write the authoring statistics to {}: {}",
+				path.display(),
+				e,
+			);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn proposals_are_counted() {
+		let stats = AuthoringStats::default();
+		PendingProposal::new(stats.clone()).finish(true);
+		PendingProposal::new(stats.clone()).finish(true);
+		PendingProposal::new(stats.clone()).finish(false);
+
+		let snapshot = stats.snapshot();
+		assert_eq!(snapshot.blocks_authored, 2);
+		assert_eq!(snapshot.slots_missed, 1);
+	}
+
+	#[test]
+	fn abandoned_proposal_is_counted_as_missed() {
+		let stats = AuthoringStats::default();
+		drop(PendingProposal::new(stats.clone()));
+
+		assert_eq!(stats.snapshot().blocks_authored, 0);
+		assert_eq!(stats.snapshot().slots_missed, 1);
+	}
+
+	#[test]
+	fn average_proposal_time_is_over_authored_blocks() {
+		let stats = AuthoringStats::default();
+		assert_eq!(stats.snapshot().average_proposal_time_ms, 0);
+
+		stats.note_authored(Duration::from_millis(100));
+		stats.note_authored(Duration::from_millis(300));
+		stats.note_missed();
+
+		assert_eq!(stats.snapshot().average_proposal_time_ms, 200);
+	}
+
+	#[test]
+	fn stats_are_written_as_json() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("stats.json");
+		let stats = AuthoringStats::default();
+		stats.note_authored(Duration::from_millis(10));
+
+		write_stats(&path, &stats.snapshot()).unwrap();
+		let written: Snapshot = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+		assert_eq!(written.blocks_authored, 1);
+		assert_eq!(written.slots_missed, 0);
+		assert_eq!(written.average_proposal_time_ms, 10);
+	}
+
+	#[test]
+	fn failing_write_is_reported() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("missing").join("stats.json");
+
+		assert!(write_stats(&path, &AuthoringStats::default().snapshot()).is_err());
+	}
+}
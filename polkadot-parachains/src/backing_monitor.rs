@@ -0,0 +1,275 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Warn if the parachain candidates don't get backed by the relay chain.
+//!
+//! The relay chain does not report back why it rejected a candidate (invalid PoV, stale relay
+//! parent, ...). What we can observe is the parachain head included in the relay chain: if the
+//! local chain is ahead of it and the included head does not follow for a number of relay blocks,
+//! the candidates are being rejected. Stalls that coincide with a relay chain reorg are reported
+//! as transient, everything else hints at a persistent problem, e.g. a misconfiguration.
+
+use crate::metric_prefix::MetricPrefix;
+use codec::Decode;
+use cumulus_primitives_core::{
+	relay_chain::{Hash as PHash, OccupiedCoreAssumption},
+	ParaId,
+};
+use cumulus_relay_chain_interface::RelayChainInterface;
+use futures::StreamExt;
+use parachains_common::{Block, BlockNumber, Header};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::Header as HeaderT;
+use std::sync::Arc;
+use substrate_prometheus_endpoint::{register, CounterVec, Opts, PrometheusError, Registry, U64};
+
+const LOG_TARGET: &str = "backing-monitor";
+
+/// Number of relay blocks the included parachain head may lag behind before we warn.
+const STALL_THRESHOLD: u32 = 5;
+
+/// Why the candidates are not getting backed, as far as we can tell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rejection {
+	/// The relay chain reorged while the candidates were pending, this usually resolves itself.
+	Transient,
+	/// The candidates are rejected without any reorg, e.g. because of an invalid PoV.
+	Persistent,
+}
+
+impl Rejection {
+	fn as_str(&self) -> &'static str {
+		match self {
+			Rejection::Transient => "transient",
+			Rejection::Persistent => "persistent",
+		}
+	}
+}
+
+/// Tracks the included parachain head over the relay chain's best blocks.
+#[derive(Default)]
+struct BackingTracker {
+	last_relay_hash: Option<PHash>,
+	last_included: BlockNumber,
+	stalled_for: u32,
+	reorg_seen: bool,
+}
+
+impl BackingTracker {
+	/// Note a new best relay block. Returns a rejection once the included head has been stalled
+	/// for [`STALL_THRESHOLD`] relay blocks while the local chain is ahead of it.
+	fn on_relay_block(
+		&mut self,
+		relay_hash: PHash,
+		relay_parent: PHash,
+		included: BlockNumber,
+		local_best: BlockNumber,
+	) -> Option<Rejection> {
+		let reorg = self.last_relay_hash.map_or(false, |last| last != relay_parent);
+		self.last_relay_hash = Some(relay_hash);
+
+		if included > self.last_included || local_best <= included {
+			self.last_included = included;
+			self.stalled_for = 0;
+			self.reorg_seen = false;
+			return None
+		}
+
+		self.reorg_seen |= reorg;
+		self.stalled_for += 1;
+		if self.stalled_for < STALL_THRESHOLD {
+			return None
+		}
+
+		self.stalled_for = 0;
+		if std::mem::take(&mut self.reorg_seen) {
+			Some(Rejection::Transient)
+		} else {
+			Some(Rejection::Persistent)
+		}
+	}
+}
+
+/// Prometheus metrics of the backing monitor.
+#[derive(Clone)]
+pub struct Metrics {
+	candidates_not_backed: CounterVec<U64>,
+}
+
+impl Metrics {
+	/// Register the metrics at the given registry, their names prefixed with `prefix`.
+	pub fn register(registry: &Registry, prefix: &MetricPrefix) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			candidates_not_backed: register(
+				CounterVec::new(
+					Opts::new(
+						prefix.apply("integritee_parachain_candidates_not_backed_total"),
+						"Number of times the parachain candidates have not been backed by the \
+						relay chain for several relay blocks",
+					),
+					&["kind"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+/// Follow the relay chain and warn whenever the parachain candidates are not getting backed.
+pub async fn run<C>(
+	client: Arc<C>,
+	relay_chain_interface: Arc<dyn RelayChainInterface>,
+	para_id: ParaId,
+	metrics: Option<Metrics>,
+) where
+	C: HeaderBackend<Block>,
+{
+	let mut relay_blocks = match relay_chain_interface.new_best_notification_stream().await {
+		Ok(stream) => stream,
+		Err(e) => {
+			log::error!(target: LOG_TARGET, "Could not follow the relay chain: {:?}", e);
+			return
+		},
+	};
+
+	let mut tracker = BackingTracker::default();
+	while let Some(relay_header) = relay_blocks.next().await {
+		let relay_hash = relay_header.hash();
+		let included = match relay_chain_interface
+			.persisted_validation_data(relay_hash, para_id, OccupiedCoreAssumption::TimedOut)
+			.await
+		{
+			Ok(Some(validation_data)) =>
+				match Header::decode(&mut &validation_data.parent_head.0[..]) {
+					Ok(header) => header.number,
+					Err(_) => continue,
+				},
+			_ => continue,
+		};
+		let local_best = client.info().best_number;
+
+		let Some(rejection) =
+			tracker.on_relay_block(relay_hash, *relay_header.parent_hash(), included, local_best)
+		else {
+			continue
+		};
+
+		match rejection {
+			Rejection::Transient => log::warn!(
+				target: LOG_TARGET,
+				"Parachain candidates not backed during a relay chain reorg: included head #{}, \
+				local best #{}. This should resolve itself.",
+				included,
+				local_best,
+			),
+			Rejection::Persistent => log::warn!(
+				target: LOG_TARGET,
+				"Parachain candidates rejected by the relay chain for {} relay blocks: included \
+				head #{}, local best #{}. Check the PoV size, the relay parent and the \
+				collator's configuration.",
+				STALL_THRESHOLD,
+				included,
+				local_best,
+			),
+		}
+		if let Some(metrics) = &metrics {
+			metrics.candidates_not_backed.with_label_values(&[rejection.as_str()]).inc();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn hash(n: u8) -> PHash {
+		PHash::repeat_byte(n)
+	}
+
+	/// Feed a linear relay chain to the tracker, starting at relay block `from`.
+	fn feed(
+		tracker: &mut BackingTracker,
+		from: u8,
+		count: u8,
+		included: BlockNumber,
+		local_best: BlockNumber,
+	) -> Vec<Rejection> {
+		(from..from + count)
+			.filter_map(|n| tracker.on_relay_block(hash(n), hash(n - 1), included, local_best))
+			.collect()
+	}
+
+	#[test]
+	fn progressing_chain_is_not_reported() {
+		let mut tracker = BackingTracker::default();
+		for n in 1..20u8 {
+			assert_eq!(tracker.on_relay_block(hash(n), hash(n - 1), n as u32, n as u32 + 1), None);
+		}
+	}
+
+	#[test]
+	fn idle_chain_is_not_reported() {
+		let mut tracker = BackingTracker::default();
+		assert!(feed(&mut tracker, 1, 20, 5, 5).is_empty());
+	}
+
+	#[test]
+	fn stalled_chain_is_reported_as_persistent() {
+		let mut tracker = BackingTracker::default();
+		assert!(feed(&mut tracker, 1, 1, 5, 5).is_empty());
+
+		assert_eq!(feed(&mut tracker, 2, STALL_THRESHOLD as u8, 5, 6), vec![Rejection::Persistent]);
+	}
+
+	#[test]
+	fn stall_during_reorg_is_reported_as_transient() {
+		let mut tracker = BackingTracker::default();
+		assert!(feed(&mut tracker, 1, 1, 5, 5).is_empty());
+
+		// Relay block 11 does not build on the previous best block.
+		assert_eq!(tracker.on_relay_block(hash(11), hash(10), 5, 6), None);
+		assert_eq!(
+			feed(&mut tracker, 12, STALL_THRESHOLD as u8 - 1, 5, 6),
+			vec![Rejection::Transient]
+		);
+	}
+
+	#[test]
+	fn metrics_count_rejections_by_kind() {
+		let registry = Registry::new();
+		let metrics = Metrics::register(&registry, &MetricPrefix::default()).unwrap();
+
+		metrics
+			.candidates_not_backed
+			.with_label_values(&[Rejection::Persistent.as_str()])
+			.inc();
+
+		assert_eq!(metrics.candidates_not_backed.with_label_values(&["persistent"]).get(), 1);
+		assert_eq!(metrics.candidates_not_backed.with_label_values(&["transient"]).get(), 0);
+	}
+
+	#[test]
+	fn metric_names_are_prefixed() {
+		let registry = Registry::new();
+		let metrics = Metrics::register(&registry, &"collator".parse().unwrap()).unwrap();
+		metrics.candidates_not_backed.with_label_values(&["persistent"]).inc();
+
+		let names: Vec<_> =
+			registry.gather().iter().map(|family| family.get_name().to_owned()).collect();
+		assert_eq!(names, ["collator_integritee_parachain_candidates_not_backed_total"]);
+	}
+}
@@ -0,0 +1,234 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reject block announcements that don't correspond to a relay parent we know.
+//!
+//! A block announcement of a parachain block carries the candidate receipt it was backed with,
+//! which names the relay parent of the candidate. [`RelayParentAnnounceValidator`] looks the relay
+//! parent up via the relay chain interface before handing the announcement to the regular cumulus
+//! validator, so announcements of junk are not gossiped any further.
+//!
+//! While the relay chain is still doing its initial sync, the relay parent of a perfectly valid
+//! announcement may simply not be imported yet. The check is lenient then and leaves the decision
+//! to the inner validator. Announcements without a candidate receipt are left to the inner
+//! validator too.
+
+use codec::Decode;
+use cumulus_primitives_core::relay_chain::Hash as PHash;
+use cumulus_relay_chain_interface::{RelayChainInterface, RelayChainResult};
+use futures::{future::BoxFuture, FutureExt};
+use parachains_common::Header;
+use polkadot_primitives::CandidateReceipt;
+use sp_consensus::block_validation::{BlockAnnounceValidator, Validation};
+use sp_runtime::generic::BlockId;
+use std::sync::Arc;
+
+const LOG_TARGET: &str = "block-announce";
+
+/// What the validator needs to know about the relay chain.
+#[async_trait::async_trait]
+pub trait RelayParents: Clone + Send + Sync + 'static {
+	/// Whether the relay chain has a block with this hash.
+	async fn is_known(&self, relay_parent: PHash) -> RelayChainResult<bool>;
+
+	/// Whether the relay chain is still doing its initial sync.
+	async fn is_major_syncing(&self) -> RelayChainResult<bool>;
+}
+
+#[async_trait::async_trait]
+impl RelayParents for Arc<dyn RelayChainInterface> {
+	async fn is_known(&self, relay_parent: PHash) -> RelayChainResult<bool> {
+		Ok(self.header(BlockId::Hash(relay_parent)).await?.is_some())
+	}
+
+	async fn is_major_syncing(&self) -> RelayChainResult<bool> {
+		RelayChainInterface::is_major_syncing(&**self).await
+	}
+}
+
+/// The relay parent of the candidate an announcement carries, if it carries one.
+///
+/// The announcement data starts with the candidate receipt; the seconded statement following it
+/// is verified by the inner validator.
+fn announced_relay_parent(data: &[u8]) -> Option<PHash> {
+	if data.is_empty() {
+		return None
+	}
+	CandidateReceipt::<PHash>::decode(&mut &data[..])
+		.ok()
+		.map(|receipt| receipt.descriptor.relay_parent)
+}
+
+/// Whether an announcement's relay parent allows passing it on to the inner validator.
+async fn relay_parent_acceptable<R: RelayParents>(relay_parents: &R, relay_parent: PHash) -> bool {
+	match relay_parents.is_known(relay_parent).await {
+		Ok(true) => true,
+		Ok(false) => relay_parents.is_major_syncing().await.unwrap_or(true),
+		Err(e) => {
+			// Don't punish peers for our own relay chain connection problems.
+			log::debug!(
+				target: LOG_TARGET,
+				"Could not look up relay parent {}: {}",
+				relay_parent,
+				e
+			);
+			true
+		},
+	}
+}
+
+/// [`BlockAnnounceValidator`] wrapper checking the relay parent of announced candidates.
+pub struct RelayParentAnnounceValidator<R> {
+	inner: Box<dyn BlockAnnounceValidator<parachains_common::Block> + Send>,
+	relay_parents: R,
+}
+
+impl<R: RelayParents> RelayParentAnnounceValidator<R> {
+	/// Wrap `inner`, checking relay parents against `relay_parents`.
+	pub fn new(
+		inner: Box<dyn BlockAnnounceValidator<parachains_common::Block> + Send>,
+		relay_parents: R,
+	) -> Self {
+		Self { inner, relay_parents }
+	}
+}
+
+impl<R: RelayParents> BlockAnnounceValidator<parachains_common::Block>
+	for RelayParentAnnounceValidator<R>
+{
+	fn validate(
+		&mut self,
+		header: &Header,
+		data: &[u8],
+	) -> BoxFuture<'static, Result<Validation, Box<dyn std::error::Error + Send>>> {
+		let inner = self.inner.validate(header, data);
+		let Some(relay_parent) = announced_relay_parent(data) else { return inner };
+
+		let relay_parents = self.relay_parents.clone();
+		let number = header.number;
+		async move {
+			if !relay_parent_acceptable(&relay_parents, relay_parent).await {
+				log::debug!(
+					target: LOG_TARGET,
+					"Rejecting announcement of block #{} with unknown relay parent {}",
+					number,
+					relay_parent,
+				);
+				return Ok(Validation::Failure { disconnect: false })
+			}
+			inner.await
+		}
+		.boxed()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use cumulus_primitives_core::{
+		relay_chain::{CandidateDescriptor, CollatorId, CollatorSignature},
+		ParaId,
+	};
+	use futures::executor::block_on;
+	use sp_consensus::block_validation::DefaultBlockAnnounceValidator;
+	use sp_core::sr25519;
+
+	#[derive(Clone)]
+	struct MockRelay {
+		known: Vec<PHash>,
+		major_syncing: bool,
+	}
+
+	#[async_trait::async_trait]
+	impl RelayParents for MockRelay {
+		async fn is_known(&self, relay_parent: PHash) -> RelayChainResult<bool> {
+			Ok(self.known.contains(&relay_parent))
+		}
+
+		async fn is_major_syncing(&self) -> RelayChainResult<bool> {
+			Ok(self.major_syncing)
+		}
+	}
+
+	fn validator(relay: MockRelay) -> RelayParentAnnounceValidator<MockRelay> {
+		RelayParentAnnounceValidator::new(Box::new(DefaultBlockAnnounceValidator), relay)
+	}
+
+	fn header() -> Header {
+		Header {
+			parent_hash: Default::default(),
+			number: 1,
+			state_root: Default::default(),
+			extrinsics_root: Default::default(),
+			digest: Default::default(),
+		}
+	}
+
+	fn announce_data(relay_parent: PHash) -> Vec<u8> {
+		let descriptor = CandidateDescriptor {
+			para_id: ParaId::from(2015),
+			relay_parent,
+			collator: CollatorId::from(sr25519::Public([0; 32])),
+			persisted_validation_data_hash: Default::default(),
+			pov_hash: Default::default(),
+			erasure_root: Default::default(),
+			signature: CollatorSignature::from(sr25519::Signature([0; 64])),
+			para_head: Default::default(),
+			validation_code_hash: PHash::zero().into(),
+		};
+		let receipt = CandidateReceipt { descriptor, commitments_hash: Default::default() };
+		// The statement following the receipt is not looked at.
+		(receipt, [0u8; 16]).encode()
+	}
+
+	#[test]
+	fn unknown_relay_parent_is_rejected() {
+		let relay = MockRelay { known: vec![PHash::repeat_byte(1)], major_syncing: false };
+		let data = announce_data(PHash::repeat_byte(2));
+
+		let validation = block_on(validator(relay).validate(&header(), &data)).unwrap();
+		assert!(matches!(validation, Validation::Failure { disconnect: false }));
+	}
+
+	#[test]
+	fn known_relay_parent_propagates() {
+		let relay = MockRelay { known: vec![PHash::repeat_byte(1)], major_syncing: false };
+		let data = announce_data(PHash::repeat_byte(1));
+
+		let validation = block_on(validator(relay).validate(&header(), &data)).unwrap();
+		assert!(matches!(validation, Validation::Success { .. }));
+	}
+
+	#[test]
+	fn unknown_relay_parent_is_tolerated_during_initial_sync() {
+		let relay = MockRelay { known: vec![], major_syncing: true };
+		let data = announce_data(PHash::repeat_byte(2));
+
+		let validation = block_on(validator(relay).validate(&header(), &data)).unwrap();
+		assert!(matches!(validation, Validation::Success { .. }));
+	}
+
+	#[test]
+	fn announcements_without_candidate_are_left_to_inner_validator() {
+		let relay = MockRelay { known: vec![], major_syncing: false };
+
+		assert_eq!(announced_relay_parent(&[]), None);
+		let validation = block_on(validator(relay).validate(&header(), &[])).unwrap();
+		assert!(matches!(validation, Validation::Success { .. }));
+	}
+}
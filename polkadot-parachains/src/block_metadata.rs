@@ -0,0 +1,322 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Append the metadata of every imported block to a file, for lightweight explorers.
+//!
+//! Every imported block is written as a line of JSON (see [`BlockMetadata`]), such that the file
+//! can be tailed. The author and the timestamp are read from the state directly, like the
+//! collator status is, so they are merely absent with runtimes lacking Aura or the timestamp
+//! pallet. Once the file would exceed its size limit, it is moved to `<file>.1`, replacing the
+//! previous one, and a new file is started. Failing writes are logged and never affect the node.
+
+use crate::collator_status::{decode_value, storage_value_key, ClientState, RawState};
+use futures::StreamExt;
+use parachains_common::{AuraId, Block, BlockNumber, Hash, Header};
+use sc_client_api::{Backend, BlockBackend, BlockchainEvents, StorageProvider};
+use serde::{Deserialize, Serialize};
+use sp_consensus_aura::{Slot, AURA_ENGINE_ID};
+use sp_core::{
+	crypto::{ByteArray, Ss58Codec},
+	sr25519,
+};
+use sp_runtime::traits::Header as HeaderT;
+use std::{
+	fs::{self, File, OpenOptions},
+	io::{self, Write},
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+const LOG_TARGET: &str = "block-metadata";
+
+/// The metadata of a block, as written to the file.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockMetadata {
+	pub number: BlockNumber,
+	pub hash: Hash,
+	pub parent_hash: Hash,
+	/// The SS58 address of the Aura key which authored the block, if it has been authored with
+	/// Aura.
+	pub author: Option<String>,
+	pub extrinsics: usize,
+	/// The timestamp of the block, in milliseconds since the unix epoch.
+	pub timestamp: Option<u64>,
+}
+
+/// The metadata of the block `header` with `extrinsics` extrinsics.
+pub fn block_metadata(state: &impl RawState, header: &Header, extrinsics: usize) -> BlockMetadata {
+	let hash = header.hash();
+	BlockMetadata {
+		number: header.number,
+		hash,
+		parent_hash: header.parent_hash,
+		author: aura_author(state, header),
+		extrinsics,
+		timestamp: decode_value(state, hash, &storage_value_key(b"Timestamp", b"Now")),
+	}
+}
+
+/// The Aura authority owning the slot in the pre-runtime digest of `header`.
+fn aura_author(state: &impl RawState, header: &Header) -> Option<String> {
+	let slot = header
+		.digest
+		.logs()
+		.iter()
+		.find_map(|item| item.pre_runtime_try_to::<Slot>(&AURA_ENGINE_ID))?;
+	// The slot has been assigned by the authorities of the parent block.
+	let authorities: Vec<AuraId> =
+		decode_value(state, header.parent_hash, &storage_value_key(b"Aura", b"Authorities"))?;
+	if authorities.is_empty() {
+		return None
+	}
+	let author = &authorities[(*slot % authorities.len() as u64) as usize];
+	Some(sr25519::Public::from_slice(author.as_slice()).ok()?.to_ss58check())
+}
+
+/// The file `path` is moved to when it is rotated.
+pub fn rotated_path(path: &Path) -> PathBuf {
+	let mut rotated = path.as_os_str().to_owned();
+	rotated.push(".1");
+	rotated.into()
+}
+
+/// Appends to the file at `path`, rotating it once it would exceed `max_bytes`.
+pub struct RotatingFile {
+	path: PathBuf,
+	max_bytes: u64,
+	/// The open file and its size, `None` until opened or after a failed write.
+	file: Option<(File, u64)>,
+}
+
+impl RotatingFile {
+	/// Append to `path`, keeping it below `max_bytes` unless a single line exceeds it.
+	pub fn new(path: PathBuf, max_bytes: u64) -> Self {
+		Self { path, max_bytes, file: None }
+	}
+
+	/// Append `line`. After a failure, the file is opened again on the next call.
+	pub fn append(&mut self, line: &[u8]) -> io::Result<()> {
+		let result = self.try_append(line);
+		if result.is_err() {
+			self.file = None;
+		}
+		result
+	}
+
+	fn try_append(&mut self, line: &[u8]) -> io::Result<()> {
+		if self.file.is_none() {
+			let file = open_append(&self.path)?;
+			let size = file.metadata()?.len();
+			self.file = Some((file, size));
+		}
+		let (file, size) = self.file.as_mut().expect("opened above; qed");
+
+		let len = line.len() as u64;
+		if *size > 0 && size.saturating_add(len) > self.max_bytes {
+			fs::rename(&self.path, rotated_path(&self.path))?;
+			*file = open_append(&self.path)?;
+			*size = 0;
+		}
+		file.write_all(line)?;
+		*size += len;
+		Ok(())
+	}
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+	OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Append the metadata of every imported block to the file at `path`, rotating it at
+/// `max_bytes`.
+pub async fn run<C, B>(client: Arc<C>, path: PathBuf, max_bytes: u64)
+where
+	C: BlockchainEvents<Block> + BlockBackend<Block> + StorageProvider<Block, B>,
+	B: Backend<Block>,
+{
+	let state = ClientState::new(client.clone());
+	let mut file = RotatingFile::new(path.clone(), max_bytes);
+	let mut failing = false;
+
+	let mut imported = client.import_notification_stream();
+	while let Some(notification) = imported.next().await {
+		let extrinsics =
+			client.block_body(notification.hash).ok().flatten().map_or(0, |body| body.len());
+		let metadata = block_metadata(&state, &notification.header, extrinsics);
+		let mut line = serde_json::to_vec(&metadata).expect("metadata serializes to JSON; qed");
+		line.push(b'\n');
+
+		// Only report the first of consecutive failures, there is one per block.
+		match file.append(&line) {
+			Ok(()) if failing => {
+				log::info!(target: LOG_TARGET, "Writing to {} again", path.display());
+				failing = false;
+			},
+			Err(e) if !failing => {
+				log::warn!(
+					target: LOG_TARGET,
+					"Could not write the block metadata to {}: {}",
+					path.display(),
+					e,
+				);
+				failing = true;
+			},
+			_ => {},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use sp_runtime::{Digest, DigestItem};
+	use std::collections::HashMap;
+
+	/// State by block.
+	#[derive(Default)]
+	struct MockState(HashMap<(Hash, Vec<u8>), Vec<u8>>);
+
+	impl RawState for MockState {
+		fn storage(&self, at: Hash, key: &[u8]) -> Option<Vec<u8>> {
+			self.0.get(&(at, key.to_vec())).cloned()
+		}
+	}
+
+	fn key(n: u8) -> AuraId {
+		sr25519::Public::from_raw([n; 32]).into()
+	}
+
+	fn header(slot: Option<u64>) -> Header {
+		let logs = slot
+			.map(|slot| DigestItem::PreRuntime(AURA_ENGINE_ID, Slot::from(slot).encode()))
+			.into_iter()
+			.collect();
+		Header {
+			parent_hash: Hash::repeat_byte(1),
+			number: 7,
+			state_root: Default::default(),
+			extrinsics_root: Default::default(),
+			digest: Digest { logs },
+		}
+	}
+
+	fn read_lines(path: &Path) -> Vec<BlockMetadata> {
+		fs::read_to_string(path)
+			.unwrap()
+			.lines()
+			.map(|line| serde_json::from_str(line).unwrap())
+			.collect()
+	}
+
+	#[test]
+	fn metadata_is_read_from_header_and_state() {
+		let header = header(Some(5));
+		let mut state = MockState::default();
+		state.0.insert(
+			(header.parent_hash, storage_value_key(b"Aura", b"Authorities")),
+			vec![key(1), key(2)].encode(),
+		);
+		state
+			.0
+			.insert((header.hash(), storage_value_key(b"Timestamp", b"Now")), 1_234u64.encode());
+
+		assert_eq!(
+			block_metadata(&state, &header, 3),
+			BlockMetadata {
+				number: 7,
+				hash: header.hash(),
+				parent_hash: Hash::repeat_byte(1),
+				// Slot 5 of two authorities.
+				author: Some(sr25519::Public::from_raw([2; 32]).to_ss58check()),
+				extrinsics: 3,
+				timestamp: Some(1_234),
+			}
+		);
+	}
+
+	#[test]
+	fn author_and_timestamp_are_optional() {
+		let metadata = block_metadata(&MockState::default(), &header(None), 2);
+		assert_eq!(metadata.author, None);
+		assert_eq!(metadata.timestamp, None);
+
+		// Aura digest, but no authorities.
+		assert_eq!(block_metadata(&MockState::default(), &header(Some(5)), 2).author, None);
+	}
+
+	fn line(metadata: &BlockMetadata) -> Vec<u8> {
+		let mut line = serde_json::to_vec(metadata).unwrap();
+		line.push(b'\n');
+		line
+	}
+
+	#[test]
+	fn one_line_per_block() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("blocks.jsonl");
+		let mut file = RotatingFile::new(path.clone(), 1 << 20);
+
+		let blocks: Vec<_> = (0..3)
+			.map(|extrinsics| block_metadata(&MockState::default(), &header(None), extrinsics))
+			.collect();
+		for metadata in &blocks {
+			file.append(&line(metadata)).unwrap();
+		}
+		assert_eq!(read_lines(&path), blocks);
+	}
+
+	#[test]
+	fn file_is_rotated_at_max_size() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("blocks.jsonl");
+		let metadata = block_metadata(&MockState::default(), &header(None), 1);
+		let line = line(&metadata);
+		let mut file = RotatingFile::new(path.clone(), 2 * line.len() as u64);
+
+		for _ in 0..5 {
+			file.append(&line).unwrap();
+		}
+		assert_eq!(read_lines(&path).len(), 1);
+		assert_eq!(read_lines(&rotated_path(&path)).len(), 2);
+		assert!(fs::metadata(&path).unwrap().len() <= 2 * line.len() as u64);
+	}
+
+	#[test]
+	fn existing_file_counts_towards_the_size() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("blocks.jsonl");
+		fs::write(&path, vec![b' '; 100]).unwrap();
+
+		RotatingFile::new(path.clone(), 100).append(b"{}\n").unwrap();
+		assert_eq!(fs::read(&path).unwrap(), b"{}\n");
+		assert_eq!(fs::metadata(rotated_path(&path)).unwrap().len(), 100);
+	}
+
+	#[test]
+	fn write_errors_are_recovered_from() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("missing").join("blocks.jsonl");
+		let mut file = RotatingFile::new(path.clone(), 1 << 20);
+
+		assert!(file.append(b"{}\n").is_err());
+		fs::create_dir(dir.path().join("missing")).unwrap();
+		file.append(b"{}\n").unwrap();
+		assert_eq!(fs::read(&path).unwrap(), b"{}\n");
+	}
+}
@@ -0,0 +1,225 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Retry the relay chain requests of producing a candidate after transient failures.
+//!
+//! Producing a candidate reads the relay chain state proof and the messages for the parachain
+//! from the relay chain, to build the parachain inherent. If the relay chain interface has a
+//! hiccup, the candidate can't be built and the collator loses its slot.
+//! [`retry_on_relay_parent`] retries with exponential backoff instead, as long as the relay parent
+//! is still the relay chain's best block: a candidate on a superseded relay parent would be stale,
+//! so it is abandoned.
+
+use crate::relay_parent_age::RelayBest;
+use cumulus_primitives_core::relay_chain::BlockNumber as RelayBlockNumber;
+use std::{future::Future, time::Duration};
+
+const LOG_TARGET: &str = "candidate-retry";
+
+/// Default number of retries after a failed attempt.
+pub const DEFAULT_MAX_RETRIES: u32 = 2;
+
+/// Default backoff before the first retry, in milliseconds.
+pub const DEFAULT_INITIAL_BACKOFF_MS: u64 = 250;
+
+/// How to retry producing a candidate, or reaching the relay chain RPC endpoints at startup.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryConfig {
+	/// Number of retries after the first attempt, 0 disables retrying.
+	pub max_retries: u32,
+	/// Backoff before the first retry, doubled for every further one.
+	pub initial_backoff: Duration,
+}
+
+impl Default for RetryConfig {
+	fn default() -> Self {
+		Self {
+			max_retries: DEFAULT_MAX_RETRIES,
+			initial_backoff: Duration::from_millis(DEFAULT_INITIAL_BACKOFF_MS),
+		}
+	}
+}
+
+impl RetryConfig {
+	/// The backoff before retry number `retry`, counting from 0.
+	pub fn backoff(&self, retry: u32) -> Duration {
+		self.initial_backoff.saturating_mul(2u32.saturating_pow(retry))
+	}
+}
+
+/// Whether the relay chain's best block is past `relay_parent_number`.
+async fn is_superseded<R: RelayBest>(relay: &R, relay_parent_number: RelayBlockNumber) -> bool {
+	// An unreachable relay chain may well be the transient failure we are retrying.
+	matches!(relay.best_number().await, Ok(Some(best)) if best > relay_parent_number)
+}
+
+/// Run `attempt` until it succeeds, retrying as configured while the relay parent
+/// `relay_parent_number` is still the relay chain's best block.
+pub async fn retry_on_relay_parent<R, T, F, Fut>(
+	config: RetryConfig,
+	relay: &R,
+	relay_parent_number: RelayBlockNumber,
+	mut attempt: F,
+) -> Option<T>
+where
+	R: RelayBest,
+	F: FnMut() -> Fut,
+	Fut: Future<Output = Option<T>>,
+{
+	let mut retries = 0;
+	loop {
+		if let Some(result) = attempt().await {
+			if retries > 0 {
+				log::info!(
+					target: LOG_TARGET,
+					"Succeeded on relay parent #{} after {} retries",
+					relay_parent_number,
+					retries,
+				);
+			}
+			return Some(result)
+		}
+		if retries >= config.max_retries {
+			log::debug!(
+				target: LOG_TARGET,
+				"Giving up on relay parent #{} after {} retries",
+				relay_parent_number,
+				retries,
+			);
+			return None
+		}
+
+		futures_timer::Delay::new(config.backoff(retries)).await;
+		retries += 1;
+		if is_superseded(relay, relay_parent_number).await {
+			log::debug!(
+				target: LOG_TARGET,
+				"Relay parent #{} has been superseded, abandoning the candidate",
+				relay_parent_number,
+			);
+			return None
+		}
+		log::debug!(
+			target: LOG_TARGET,
+			"Retrying on relay parent #{} ({}/{})",
+			relay_parent_number,
+			retries,
+			config.max_retries,
+		);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use cumulus_relay_chain_interface::RelayChainResult;
+	use futures::executor::block_on;
+	use std::{
+		cell::Cell,
+		sync::{
+			atomic::{AtomicU32, Ordering},
+			Arc,
+		},
+	};
+
+	/// A relay chain whose best block advances by one with every query after `advance_after`
+	/// queries.
+	#[derive(Clone)]
+	struct MockRelay {
+		best: RelayBlockNumber,
+		advance_after: u32,
+		queries: Arc<AtomicU32>,
+	}
+
+	impl MockRelay {
+		fn new(best: RelayBlockNumber, advance_after: u32) -> Self {
+			Self { best, advance_after, queries: Default::default() }
+		}
+	}
+
+	#[async_trait::async_trait]
+	impl RelayBest for MockRelay {
+		async fn best_number(&self) -> RelayChainResult<Option<RelayBlockNumber>> {
+			let queries = self.queries.fetch_add(1, Ordering::Relaxed) + 1;
+			Ok(Some(self.best + queries.saturating_sub(self.advance_after)))
+		}
+	}
+
+	fn config(max_retries: u32) -> RetryConfig {
+		RetryConfig { max_retries, initial_backoff: Duration::from_millis(1) }
+	}
+
+	/// Attempts failing `failures` times, then succeeding, counting them in `attempts`.
+	fn submit(attempts: &Cell<u32>, failures: u32) -> impl Future<Output = Option<u32>> {
+		attempts.set(attempts.get() + 1);
+		let attempt = attempts.get();
+		async move { (attempt > failures).then_some(attempt) }
+	}
+
+	#[test]
+	fn transient_failure_is_retried() {
+		let relay = MockRelay::new(10, u32::MAX);
+		let attempts = Cell::new(0);
+
+		let result =
+			block_on(retry_on_relay_parent(config(2), &relay, 10, || submit(&attempts, 1)));
+		assert_eq!(result, Some(2));
+		assert_eq!(attempts.get(), 2);
+	}
+
+	#[test]
+	fn superseded_relay_parent_is_abandoned() {
+		// The relay chain moves on while backing off.
+		let relay = MockRelay::new(10, 0);
+		let attempts = Cell::new(0);
+
+		let result =
+			block_on(retry_on_relay_parent(config(2), &relay, 10, || submit(&attempts, 1)));
+		assert_eq!(result, None);
+		assert_eq!(attempts.get(), 1);
+	}
+
+	#[test]
+	fn retries_are_bounded() {
+		let relay = MockRelay::new(10, u32::MAX);
+		let attempts = Cell::new(0);
+
+		let result =
+			block_on(retry_on_relay_parent(config(2), &relay, 10, || submit(&attempts, 5)));
+		assert_eq!(result, None);
+		assert_eq!(attempts.get(), 3);
+	}
+
+	#[test]
+	fn zero_retries_disable_retrying() {
+		let relay = MockRelay::new(10, u32::MAX);
+		let attempts = Cell::new(0);
+
+		let result =
+			block_on(retry_on_relay_parent(config(0), &relay, 10, || submit(&attempts, 1)));
+		assert_eq!(result, None);
+		assert_eq!(attempts.get(), 1);
+		assert_eq!(relay.queries.load(Ordering::Relaxed), 0);
+	}
+
+	#[test]
+	fn backoff_doubles() {
+		let config = RetryConfig { max_retries: 3, initial_backoff: Duration::from_millis(100) };
+		assert_eq!(config.backoff(0), Duration::from_millis(100));
+		assert_eq!(config.backoff(2), Duration::from_millis(400));
+	}
+}
@@ -16,14 +16,16 @@
 // along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
 #![allow(clippy::inconsistent_digit_grouping)]
 
+use codec::Encode;
 use cumulus_primitives_core::ParaId;
 use parachain_runtime::{CouncilConfig, DemocracyConfig, TechnicalCommitteeConfig, TeerexConfig};
-use parachains_common::{AccountId, AuraId};
+use parachains_common::{xcm_config::CurrencyId, AccountId, AuraId};
 use sc_chain_spec::{ChainSpecExtension, ChainSpecGroup};
-use sc_service::{ChainType, GenericChainSpec};
+use sc_service::{ChainSpec, ChainType, GenericChainSpec};
 use serde::{Deserialize, Serialize};
-use sp_core::{crypto::Ss58Codec, sr25519, Public};
+use sp_core::{crypto::Ss58Codec, hexdisplay::HexDisplay, sr25519, Public};
 use sp_keyring::AccountKeyring::{Alice, Bob, Dave, Eve};
+use sp_runtime::BuildStorage;
 use std::str::FromStr;
 
 /// Specialized `ChainSpec` for the normal parachain runtime.
@@ -62,6 +64,10 @@ where
 	TPublic::from_ss58check(ss58).expect("supply valid ss58!")
 }
 
+/// The XCM version sent to destinations before their version is known, see
+/// `pallet_xcm::SafeXcmVersion`.
+const SAFE_XCM_VERSION: u32 = xcm::prelude::XCM_VERSION;
+
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum GenesisKeys {
 	/// Use integriTEE keys.
@@ -176,6 +182,31 @@ pub fn integritee_chain_spec(
 	)
 }
 
+/// Generate a fresh integritee chain spec for `para_id` on the given relay chain.
+///
+/// The relay chain's network id is written into the genesis storage of `XcmAdmin`'s
+/// `RelayNetwork`, such that the XCM configuration refers to the same relay network as
+/// the chain spec. The genesis storage is hardcoded into the chain spec for that reason.
+pub fn integritee_chain_spec_for_relay(
+	para_id: ParaId,
+	relay_chain: RelayChain,
+) -> Result<IntegriteeChainSpec, String> {
+	let network = match relay_chain {
+		RelayChain::Kusama => xcm::latest::NetworkId::Kusama,
+		RelayChain::Polkadot => xcm::latest::NetworkId::Polkadot,
+		RelayChain::ByGenesis(hash) => xcm::latest::NetworkId::ByGenesis(hash),
+		_ => return Err("Only kusama, polkadot or a relay chain by genesis are supported".into()),
+	};
+
+	let mut spec = integritee_chain_spec(para_id, GenesisKeys::Integritee, relay_chain);
+	let mut storage = spec.build_storage()?;
+	storage
+		.top
+		.insert(parachain_runtime::xcm_config::relay_network_key().to_vec(), network.encode());
+	spec.set_storage(storage);
+	Ok(spec)
+}
+
 fn chain_spec<F: Fn() -> GenesisConfig + 'static + Send + Sync, GenesisConfig>(
 	chain_name: &str,
 	testnet_constructor: F,
@@ -196,13 +227,11 @@ fn chain_spec<F: Fn() -> GenesisConfig + 'static + Send + Sync, GenesisConfig>(
 		None,
 		// properties
 		Some(
-			serde_json::from_str(
-				r#"{
+			serde_json::from_value(serde_json::json!({
 				"ss58Format": 13,
-				"tokenDecimals": 12,
-				"tokenSymbol": "TEER"
-				}"#,
-			)
+				"tokenDecimals": CurrencyId::TEER.decimals(),
+				"tokenSymbol": CurrencyId::TEER.symbol(),
+			}))
 			.unwrap(),
 		),
 		Extensions { relay_chain: relay_chain.to_string(), para_id: para_id.into() },
@@ -235,7 +264,9 @@ fn integritee_genesis_config(
 		aura: parachain_runtime::AuraConfig { authorities: initial_authorities },
 		aura_ext: Default::default(),
 		parachain_system: Default::default(),
-		polkadot_xcm: Default::default(),
+		polkadot_xcm: parachain_runtime::PolkadotXcmConfig {
+			safe_xcm_version: Some(SAFE_XCM_VERSION),
+		},
 		teerex: TeerexConfig { allow_sgx_debug_mode: true, allow_skipping_attestation: true },
 		claims: Default::default(),
 		treasury: Default::default(),
@@ -263,7 +294,7 @@ fn shell_genesis_config(
 		parachain_system: Default::default(),
 		aura: shell_runtime::AuraConfig { authorities: initial_authorities },
 		aura_ext: Default::default(),
-		polkadot_xcm: Default::default(),
+		polkadot_xcm: shell_runtime::PolkadotXcmConfig { safe_xcm_version: Some(SAFE_XCM_VERSION) },
 	}
 }
 
@@ -277,6 +308,8 @@ pub enum RelayChain {
 	Kusama,
 	Polkadot,
 	Moonbase,
+	/// A relay network identified by its genesis hash.
+	ByGenesis([u8; 32]),
 }
 
 pub fn shell_rococo_config() -> Result<ShellChainSpec, String> {
@@ -321,6 +354,7 @@ impl ToString for RelayChain {
 			RelayChain::Kusama => "kusama".into(),
 			RelayChain::Polkadot => "polkadot".into(),
 			RelayChain::Moonbase => "westend_moonbase_relay_testnet".into(),
+			RelayChain::ByGenesis(hash) => format!("0x{:?}", HexDisplay::from(hash)),
 		}
 	}
 }
@@ -337,6 +371,7 @@ impl RelayChain {
 			RelayChain::Kusama => ChainType::Live,
 			RelayChain::Polkadot => ChainType::Live,
 			RelayChain::Moonbase => ChainType::Live,
+			RelayChain::ByGenesis(_) => ChainType::Live,
 		}
 	}
 	fn protocol_id(&self) -> &str {
@@ -350,6 +385,53 @@ impl RelayChain {
 			RelayChain::Kusama => "teer-k",
 			RelayChain::Polkadot => "teer-p",
 			RelayChain::Moonbase => "teer-m",
+			RelayChain::ByGenesis(_) => "teer-g",
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Decode;
+	use sp_core::H256;
+	use xcm::latest::NetworkId;
+
+	/// The network of the relay chain in the genesis storage of `spec`.
+	fn relay_network(spec: &IntegriteeChainSpec) -> Option<NetworkId> {
+		let storage = spec.build_storage().unwrap();
+		let key = parachain_runtime::xcm_config::relay_network_key();
+		storage
+			.top
+			.get(&key[..])
+			.map(|network| NetworkId::decode(&mut &network[..]).unwrap())
+	}
+
+	#[test]
+	fn relay_network_is_derived_from_the_relay_chain() {
+		let spec = integritee_chain_spec_for_relay(2015.into(), RelayChain::Polkadot).unwrap();
+		assert_eq!(relay_network(&spec), Some(NetworkId::Polkadot));
+		assert_eq!(spec.extensions().relay_chain, "polkadot");
+
+		let spec = integritee_chain_spec_for_relay(2015.into(), RelayChain::Kusama).unwrap();
+		assert_eq!(relay_network(&spec), Some(NetworkId::Kusama));
+	}
+
+	#[test]
+	fn relay_network_by_genesis_is_derived_from_the_genesis_hash() {
+		let hash = H256::repeat_byte(0xab);
+		let spec =
+			integritee_chain_spec_for_relay(2015.into(), RelayChain::ByGenesis(hash.0)).unwrap();
+		assert_eq!(relay_network(&spec), Some(NetworkId::ByGenesis(hash.0)));
+
+		// The relay chain is the full genesis hash in hex, which parses back to the hash.
+		let relay_chain = &spec.extensions().relay_chain;
+		assert_eq!(relay_chain, &format!("0x{}", "ab".repeat(32)));
+		assert_eq!(H256::from_str(relay_chain).ok(), Some(hash));
+	}
+
+	#[test]
+	fn other_relay_chains_have_no_relay_network() {
+		assert!(integritee_chain_spec_for_relay(2015.into(), RelayChain::Rococo).is_err());
+	}
+}
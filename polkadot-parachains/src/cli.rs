@@ -14,6 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with Cumulus.  If not, see <http://www.gnu.org/licenses/>.
 
+use crate::{chain_spec::RelayChain, metric_prefix::MetricPrefix};
+use sc_network::config::MultiaddrWithPeerId;
 use std::path::PathBuf;
 
 /// Sub-commands supported by the collator.
@@ -24,7 +26,7 @@ pub enum Subcommand {
 	Key(sc_cli::KeySubcommand),
 
 	/// Build a chain specification.
-	BuildSpec(sc_cli::BuildSpecCmd),
+	BuildSpec(BuildSpecCmd),
 
 	/// Validate blocks.
 	CheckBlock(sc_cli::CheckBlockCmd),
@@ -64,6 +66,51 @@ pub enum Subcommand {
 	TryRuntime,
 }
 
+/// Relay networks a chain spec can be generated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum RelayNetwork {
+	Kusama,
+	Polkadot,
+	/// A relay network identified by its genesis hash, see `--relay-genesis`.
+	ByGenesis,
+}
+
+/// The `build-spec` command, which can also generate a fresh chain spec for a para id and relay
+/// network.
+#[derive(Debug, clap::Parser)]
+pub struct BuildSpecCmd {
+	#[command(flatten)]
+	pub base: sc_cli::BuildSpecCmd,
+
+	/// Generate a fresh integritee chain spec for this para id instead of loading `--chain`.
+	#[arg(long, requires = "relay", value_parser = clap::value_parser!(u32).range(2000..))]
+	pub para_id: Option<u32>,
+
+	/// The relay network of the generated chain spec.
+	#[arg(long, value_enum, requires = "para_id")]
+	pub relay: Option<RelayNetwork>,
+
+	/// The genesis hash of the relay network, if `--relay by-genesis` is used.
+	#[arg(long, value_name = "HASH", required_if_eq("relay", "by-genesis"))]
+	pub relay_genesis: Option<sp_core::H256>,
+}
+
+impl BuildSpecCmd {
+	/// The relay chain of the chain spec to generate, if any.
+	pub fn relay_chain(&self) -> Result<Option<RelayChain>, String> {
+		match (self.relay, self.relay_genesis) {
+			(None, _) => Ok(None),
+			(Some(RelayNetwork::ByGenesis), Some(hash)) => Ok(Some(RelayChain::ByGenesis(hash.0))),
+			(Some(RelayNetwork::ByGenesis), None) =>
+				Err("`--relay by-genesis` requires `--relay-genesis`".into()),
+			(Some(_), Some(_)) =>
+				Err("`--relay-genesis` can only be used with `--relay by-genesis`".into()),
+			(Some(RelayNetwork::Kusama), None) => Ok(Some(RelayChain::Kusama)),
+			(Some(RelayNetwork::Polkadot), None) => Ok(Some(RelayChain::Polkadot)),
+		}
+	}
+}
+
 #[derive(Debug, clap::Parser)]
 #[command(
 	propagate_version = true,
@@ -87,6 +134,217 @@ pub struct Cli {
 	#[arg(long)]
 	pub no_hardware_benchmarks: bool,
 
+	/// Limit the number of user extrinsics a collator includes per block.
+	///
+	/// Meant for load tests only, where blocks should be produced at a specific throughput
+	/// instead of being filled greedily. Inherents are always included.
+	#[arg(long, value_name = "COUNT")]
+	pub max_extrinsics_per_block: Option<usize>,
+
+	/// Accept imported blocks whose timestamp is up to this many milliseconds ahead of the local
+	/// clock, on top of the drift the runtime allows anyway.
+	///
+	/// Mitigates spurious block rejections caused by a slightly skewed local clock.
+	#[arg(
+		long,
+		value_name = "MS",
+		default_value_t = 0,
+		value_parser = clap::value_parser!(u64).range(..=crate::service::MAX_TIMESTAMP_GRACE_MS),
+	)]
+	pub timestamp_grace_ms: u64,
+
+	/// Capacity of the telemetry worker's message channel.
+	///
+	/// When it is full, the newest telemetry messages are dropped. Connection messages are never
+	/// dropped.
+	#[arg(
+		long,
+		value_name = "MESSAGES",
+		default_value_t = crate::service::DEFAULT_TELEMETRY_BUFFER_SIZE,
+		value_parser = clap::value_parser!(u64).range(1..).map(|size| size as usize),
+	)]
+	pub telemetry_buffer_size: usize,
+
+	/// Periodically write the authoring statistics of this collator (blocks authored, slots
+	/// missed, average proposal time) to this JSON file.
+	#[arg(long, value_name = "PATH")]
+	pub authoring_stats_file: Option<PathBuf>,
+
+	/// How often the authoring statistics are written, in seconds.
+	#[arg(
+		long,
+		value_name = "SECONDS",
+		default_value_t = 60,
+		value_parser = clap::value_parser!(u64).range(1..),
+	)]
+	pub authoring_stats_interval: u64,
+
+	/// Append the metadata of every imported block (number, hash, parent, author, extrinsic count,
+	/// timestamp) to this file, as a line of JSON each.
+	#[arg(long, value_name = "PATH")]
+	pub block_metadata_file: Option<PathBuf>,
+
+	/// Size in bytes at which the block metadata file is moved to `<file>.1`, replacing the
+	/// previous one, and a new file is started.
+	#[arg(
+		long,
+		value_name = "BYTES",
+		default_value_t = 64 * 1024 * 1024,
+		value_parser = clap::value_parser!(u64).range(1..),
+	)]
+	pub block_metadata_max_bytes: u64,
+
+	/// Don't reject block announcements whose candidate references a relay parent unknown to the
+	/// relay chain node.
+	///
+	/// The check is lenient anyway while the relay chain is doing its initial sync.
+	#[arg(long)]
+	pub no_announce_relay_parent_check: bool,
+
+	/// Verify blocks close to the runtime upgrade introducing Aura with both the Aura and the relay
+	/// chain verifier, and log any disagreement between them.
+	///
+	/// Diagnostics only, the import decision is not affected.
+	#[arg(long)]
+	pub verifier_cross_check: bool,
+
+	/// Reserve a connection to this collator, given as multiaddress with peer id. Can be repeated.
+	///
+	/// Reserved connections come on top of the regular `--in-peers` and `--out-peers` slots, so
+	/// they are kept even when the peer table is full, without crowding out full nodes.
+	#[arg(long = "collator-peer", value_name = "MULTIADDR")]
+	pub collator_peers: Vec<MultiaddrWithPeerId>,
+
+	/// Restrict the block gossip of the parachain network to this collator, given as multiaddress
+	/// with peer id. Can be repeated.
+	///
+	/// Once given, the node only gossips with the listed collators and denies all other peers, for
+	/// private collator networks. Peers are identified by their peer id, which is verified on
+	/// every connection.
+	#[arg(long = "allowed-collator", value_name = "MULTIADDR")]
+	pub allowed_collators: Vec<MultiaddrWithPeerId>,
+
+	/// Prefix the names of this node's own Prometheus metrics, e.g. with its role, such that
+	/// several nodes can report to the same Prometheus without their metrics colliding.
+	///
+	/// Must match `[a-zA-Z_][a-zA-Z0-9_]*`. It is separated from the names by an underscore.
+	#[arg(long, value_name = "PREFIX")]
+	pub metric_prefix: Option<MetricPrefix>,
+
+	/// Slot duration of the relay chain, in milliseconds.
+	///
+	/// Only needs to be set for relay chains with a slot duration other than the one of Kusama and
+	/// Polkadot, e.g. local testnets.
+	#[arg(
+		long,
+		value_name = "MS",
+		default_value_t = crate::service::DEFAULT_RELAY_CHAIN_SLOT_DURATION_MS,
+		value_parser = clap::value_parser!(u64).range(1..),
+	)]
+	pub relay_chain_slot_duration_ms: u64,
+
+	/// Portion of the Aura slot spent proposing a block, in `(0, 1]`.
+	///
+	/// The default leaves around 500ms in a 12s slot.
+	#[arg(
+		long,
+		value_name = "PORTION",
+		default_value_t = crate::proposal_slot::DEFAULT_BLOCK_PROPOSAL_SLOT_PORTION,
+	)]
+	pub block_proposal_slot_portion: f32,
+
+	/// Maximum portion of the Aura slot spent proposing a block if slots have been skipped, in
+	/// `(0, 1]`.
+	///
+	/// Must not be smaller than `--block-proposal-slot-portion`, the node refuses to start
+	/// otherwise. The default leaves up to 750ms in a 12s slot.
+	#[arg(
+		long,
+		value_name = "PORTION",
+		default_value_t = crate::proposal_slot::DEFAULT_MAX_BLOCK_PROPOSAL_SLOT_PORTION,
+	)]
+	pub max_block_proposal_slot_portion: f32,
+
+	/// Reject announced blocks whose relay parent lags more than this many relay blocks behind
+	/// the relay chain's best block, as a defense against replays of old candidates.
+	///
+	/// Blocks imported during the initial sync are not checked.
+	#[arg(long, value_name = "BLOCKS")]
+	pub max_relay_parent_age: Option<u32>,
+
+	/// Retry building a candidate this many times after the relay chain failed to provide the
+	/// data of its parachain inherent. 0 disables retrying.
+	///
+	/// Retrying stops as soon as the relay parent is no longer the relay chain's best block.
+	#[arg(
+		long,
+		value_name = "COUNT",
+		default_value_t = crate::candidate_retry::DEFAULT_MAX_RETRIES,
+	)]
+	pub candidate_retries: u32,
+
+	/// Backoff before the first retry of building a candidate, doubled for every further retry.
+	#[arg(
+		long,
+		value_name = "MS",
+		default_value_t = crate::candidate_retry::DEFAULT_INITIAL_BACKOFF_MS,
+	)]
+	pub candidate_retry_backoff_ms: u64,
+
+	/// Retry reaching the endpoints given with `--relay-chain-rpc-urls` this many times at startup
+	/// if none of them is reachable, before giving up. 0 disables retrying.
+	#[arg(
+		long,
+		value_name = "COUNT",
+		default_value_t = crate::relay_rpc::DEFAULT_CONNECT_RETRIES,
+	)]
+	pub relay_chain_rpc_retries: u32,
+
+	/// Backoff before the first retry of reaching the relay chain RPC endpoints, doubled for every
+	/// further retry.
+	#[arg(
+		long,
+		value_name = "MS",
+		default_value_t = crate::relay_rpc::DEFAULT_CONNECT_BACKOFF_MS,
+	)]
+	pub relay_chain_rpc_backoff_ms: u64,
+
+	/// Skip producing a candidate if its relay parent lags more than this many relay blocks
+	/// behind the relay chain's best block. 0 only builds on the relay chain's best block.
+	///
+	/// Lower values refuse to build on stale validation data, higher ones keep the parachain live
+	/// while the relay chain node lags behind. Unlimited by default.
+	#[arg(long, value_name = "BLOCKS")]
+	pub max_validation_data_staleness: Option<u32>,
+
+	/// Recover at most this many PoVs from the relay chain at a time, queueing the others.
+	///
+	/// A node catching up recovers the PoVs of all the blocks it misses at once, which can
+	/// overwhelm the relay chain and the node. Queued recoveries are started newest first, such
+	/// that the recovery of the current best block doesn't wait for the historical ones.
+	/// Unlimited by default.
+	#[arg(
+		long,
+		value_name = "COUNT",
+		value_parser = clap::value_parser!(u64).range(1..).map(|count| count as usize),
+	)]
+	pub max_concurrent_pov_recoveries: Option<usize>,
+
+	/// Run the runtime's integrity checks of its XCM configuration at startup, and refuse to start
+	/// if any of them fails.
+	///
+	/// The checks take next to no time. Runtimes without integrity checks pass.
+	#[arg(long)]
+	pub check_integrity: bool,
+
+	/// Run a development node sealing its own blocks every this many milliseconds, without a relay
+	/// chain. With 0, a block is sealed whenever a transaction arrives.
+	///
+	/// Blocks can be sealed and finalized on demand with the `engine_createBlock` and
+	/// `engine_finalizeBlock` RPCs. For local testing only, the relay chain is mocked.
+	#[arg(long, value_name = "MS")]
+	pub dev_block_time: Option<u64>,
+
 	/// Relay chain arguments
 	#[arg(raw = true)]
 	pub relaychain_args: Vec<String>,
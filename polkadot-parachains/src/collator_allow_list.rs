@@ -0,0 +1,113 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Restrict the block gossip of the parachain network to an allow-list of nodes.
+//!
+//! Private collator networks may only want authorized nodes to take part in block gossip. The
+//! nodes given with `--allowed-collator` become the only peers of the parachain's default peer
+//! set: they are reserved, and the node denies all other peers, like with `--reserved-only`. A
+//! node's identity is its peer id, which libp2p verifies on every connection by the signature of
+//! the node key in the handshake, so a peer can't pretend to be a listed node.
+//!
+//! Reserved nodes given otherwise, e.g. with `--reserved-nodes` or `--collator-peer`, are dropped
+//! unless their peer id is on the list. Without an allow-list nothing changes, public networks are
+//! not affected.
+
+use sc_network::config::{MultiaddrWithPeerId, NetworkConfiguration, NonReservedPeerMode};
+
+const LOG_TARGET: &str = "collator-allow-list";
+
+/// Allow only the collators `allowed` in the block gossip of the parachain `network`.
+pub fn restrict(network: &mut NetworkConfiguration, allowed: &[MultiaddrWithPeerId]) {
+	if allowed.is_empty() {
+		return
+	}
+
+	let peers_set = &mut network.default_peers_set;
+	peers_set.reserved_nodes.retain(|node| {
+		let is_allowed = allowed.iter().any(|allowed| allowed.peer_id == node.peer_id);
+		if !is_allowed {
+			log::warn!(
+				target: LOG_TARGET,
+				"Dropping the reserved node {}, it is not on the collator allow-list",
+				node,
+			);
+		}
+		is_allowed
+	});
+	for node in allowed {
+		if !peers_set.reserved_nodes.contains(node) {
+			peers_set.reserved_nodes.push(node.clone());
+		}
+	}
+	peers_set.non_reserved_mode = NonReservedPeerMode::Deny;
+	log::info!(
+		target: LOG_TARGET,
+		"Block gossip is restricted to the {} collators on the allow-list",
+		allowed.len(),
+	);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_network::PeerId;
+
+	fn peer(port: u16) -> MultiaddrWithPeerId {
+		MultiaddrWithPeerId {
+			multiaddr: format!("/ip4/127.0.0.1/tcp/{}", port).parse().unwrap(),
+			peer_id: PeerId::random(),
+		}
+	}
+
+	#[test]
+	fn only_listed_collators_take_part_in_gossip() {
+		let mut network = NetworkConfiguration::new_local();
+		let allowed = vec![peer(30334), peer(30335)];
+
+		restrict(&mut network, &allowed);
+
+		assert_eq!(network.default_peers_set.reserved_nodes, allowed);
+		assert_eq!(network.default_peers_set.non_reserved_mode, NonReservedPeerMode::Deny);
+	}
+
+	#[test]
+	fn unlisted_reserved_nodes_are_dropped() {
+		let mut network = NetworkConfiguration::new_local();
+		let listed = peer(30334);
+		// Listed under another address, the peer id identifies the node.
+		let listed_elsewhere = MultiaddrWithPeerId { peer_id: listed.peer_id, ..peer(40334) };
+		let unlisted = peer(30336);
+		network.default_peers_set.reserved_nodes = vec![listed_elsewhere.clone(), unlisted];
+
+		restrict(&mut network, &[listed.clone()]);
+
+		assert_eq!(network.default_peers_set.reserved_nodes, vec![listed_elsewhere, listed]);
+	}
+
+	#[test]
+	fn no_allow_list_changes_nothing() {
+		let mut network = NetworkConfiguration::new_local();
+		let reserved = peer(30334);
+		network.default_peers_set.reserved_nodes.push(reserved.clone());
+
+		restrict(&mut network, &[]);
+
+		assert_eq!(network.default_peers_set.reserved_nodes, vec![reserved]);
+		assert_eq!(network.default_peers_set.non_reserved_mode, NonReservedPeerMode::Accept);
+	}
+}
@@ -0,0 +1,117 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reserve connections to known peer collators.
+//!
+//! Collators propagate blocks faster when they are well connected to each other. The peers given
+//! with `--collator-peer` become reserved nodes of the parachain network: the node keeps connecting
+//! to them and accepts their connections even when all of its regular slots are taken. Reserved
+//! nodes come on top of the `--in-peers` and `--out-peers` slots, so ordinary full nodes are still
+//! served, which is why the collator peers never switch the node to `--reserved-only`.
+
+use sc_network::config::{MultiaddrWithPeerId, NetworkConfiguration, NonReservedPeerMode};
+
+const LOG_TARGET: &str = "collator-peers";
+
+/// Reserve connections to the collators `peers` in the parachain `network`.
+pub fn reserve(network: &mut NetworkConfiguration, peers: &[MultiaddrWithPeerId]) {
+	if peers.is_empty() {
+		return
+	}
+
+	let peers_set = &mut network.default_peers_set;
+	for peer in peers {
+		if !peers_set.reserved_nodes.contains(peer) {
+			peers_set.reserved_nodes.push(peer.clone());
+		}
+	}
+	log::info!(
+		target: LOG_TARGET,
+		"Reserved connections to {} collator peers, on top of {} inbound and {} outbound slots",
+		peers.len(),
+		peers_set.in_peers,
+		peers_set.out_peers,
+	);
+	if peers_set.non_reserved_mode == NonReservedPeerMode::Deny || peers_set.in_peers == 0 {
+		log::warn!(
+			target: LOG_TARGET,
+			"This node doesn't accept connections from ordinary full nodes, only from reserved \
+			ones. Drop `--reserved-only` and allow `--in-peers` to serve them.",
+		);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_network::PeerId;
+
+	fn peer(port: u16) -> MultiaddrWithPeerId {
+		MultiaddrWithPeerId {
+			multiaddr: format!("/ip4/127.0.0.1/tcp/{}", port).parse().unwrap(),
+			peer_id: PeerId::random(),
+		}
+	}
+
+	#[test]
+	fn collator_peers_are_reserved() {
+		let mut network = NetworkConfiguration::new_local();
+		let operator_reserved = peer(30333);
+		network.default_peers_set.reserved_nodes.push(operator_reserved.clone());
+
+		let collators = vec![peer(30334), operator_reserved.clone(), peer(30335)];
+		reserve(&mut network, &collators);
+
+		assert_eq!(
+			network.default_peers_set.reserved_nodes,
+			vec![operator_reserved, collators[0].clone(), collators[2].clone()]
+		);
+	}
+
+	#[test]
+	fn regular_slots_are_kept_for_full_nodes() {
+		let mut network = NetworkConfiguration::new_local();
+		let (in_peers, out_peers) =
+			(network.default_peers_set.in_peers, network.default_peers_set.out_peers);
+
+		reserve(&mut network, &[peer(30334), peer(30335)]);
+
+		// Reserved nodes don't use up the regular slots, full nodes can still connect.
+		assert_eq!(network.default_peers_set.in_peers, in_peers);
+		assert_eq!(network.default_peers_set.out_peers, out_peers);
+		assert_eq!(network.default_peers_set.non_reserved_mode, NonReservedPeerMode::Accept);
+	}
+
+	#[test]
+	fn collator_peers_are_reserved_even_with_full_peer_table() {
+		let mut network = NetworkConfiguration::new_local();
+		// No regular slot is free.
+		network.default_peers_set.in_peers = 0;
+		network.default_peers_set.out_peers = 0;
+
+		let collator = peer(30334);
+		reserve(&mut network, &[collator.clone()]);
+		assert_eq!(network.default_peers_set.reserved_nodes, vec![collator]);
+	}
+
+	#[test]
+	fn no_collator_peers_change_nothing() {
+		let mut network = NetworkConfiguration::new_local();
+		reserve(&mut network, &[]);
+		assert!(network.default_peers_set.reserved_nodes.is_empty());
+	}
+}
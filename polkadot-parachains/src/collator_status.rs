@@ -0,0 +1,370 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Report whether this node's authoring key takes part in the collator election.
+//!
+//! A node is an active collator if one of its Aura keys is in the current Aura authorities, and a
+//! candidate if the account owning the key (as registered in `Session::KeyOwner`) is a candidate or
+//! an invulnerable of the collator selection pallet. The state is read from storage directly
+//! instead of through a runtime API, so that it works with any runtime: if a runtime lacks the
+//! session or the collator selection pallet, the node is merely never reported as a candidate.
+//!
+//! The status is reevaluated whenever the session changes, or on every new best block if the
+//! runtime has no session pallet.
+
+use crate::metric_prefix::MetricPrefix;
+use codec::{Decode, Encode};
+use futures::StreamExt;
+use parachains_common::{AccountId, AuraId, Balance, Block, Hash};
+use sc_client_api::{Backend, BlockchainEvents, StorageProvider};
+use sp_core::{
+	crypto::{key_types::AURA, ByteArray},
+	hashing::{twox_128, twox_64},
+	storage::StorageKey,
+};
+use sp_keystore::KeystorePtr;
+use std::{marker::PhantomData, sync::Arc};
+use substrate_prometheus_endpoint::{register, GaugeVec, Opts, PrometheusError, Registry, U64};
+
+const LOG_TARGET: &str = "collator-status";
+
+/// This node's role in the collator election.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollatorStatus {
+	/// One of the node's keys is an Aura authority.
+	Active,
+	/// The node's account is a registered candidate or invulnerable, but not an authority.
+	Candidate,
+	/// The node takes no part in the election.
+	None,
+}
+
+impl CollatorStatus {
+	const ALL: [CollatorStatus; 3] =
+		[CollatorStatus::Active, CollatorStatus::Candidate, CollatorStatus::None];
+
+	fn as_str(&self) -> &'static str {
+		match self {
+			CollatorStatus::Active => "active",
+			CollatorStatus::Candidate => "candidate",
+			CollatorStatus::None => "none",
+		}
+	}
+}
+
+/// Read access to the raw state of a block.
+pub trait RawState {
+	/// The value at `key` in the state of block `at`, `None` if there is none or it can't be read.
+	fn storage(&self, at: Hash, key: &[u8]) -> Option<Vec<u8>>;
+}
+
+/// [`RawState`] of a client.
+pub struct ClientState<C, B> {
+	client: Arc<C>,
+	_phantom: PhantomData<B>,
+}
+
+impl<C, B> ClientState<C, B> {
+	/// Read the state through `client`.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _phantom: PhantomData }
+	}
+}
+
+impl<C, B> RawState for ClientState<C, B>
+where
+	C: StorageProvider<Block, B>,
+	B: Backend<Block>,
+{
+	fn storage(&self, at: Hash, key: &[u8]) -> Option<Vec<u8>> {
+		match self.client.storage(at, &StorageKey(key.to_vec())) {
+			Ok(value) => value.map(|data| data.0),
+			Err(e) => {
+				log::debug!(target: LOG_TARGET, "Could not read the state of {:?}: {:?}", at, e);
+				None
+			},
+		}
+	}
+}
+
+pub(crate) fn storage_value_key(pallet: &[u8], item: &[u8]) -> Vec<u8> {
+	[twox_128(pallet), twox_128(item)].concat()
+}
+
+/// Decode the value at `key`, treating absent or undecodable values as absent.
+pub(crate) fn decode_value<T: Decode>(state: &impl RawState, at: Hash, key: &[u8]) -> Option<T> {
+	state.storage(at, key).and_then(|value| T::decode(&mut &value[..]).ok())
+}
+
+/// The current session index, `None` if the runtime has no session pallet.
+pub fn session_index(state: &impl RawState, at: Hash) -> Option<u32> {
+	decode_value(state, at, &storage_value_key(b"Session", b"CurrentIndex"))
+}
+
+fn key_owner_key(key: &AuraId) -> Vec<u8> {
+	let encoded = (AURA, key.to_raw_vec()).encode();
+	[storage_value_key(b"Session", b"KeyOwner"), twox_64(&encoded).to_vec(), encoded].concat()
+}
+
+/// The account owning the Aura `key` according to `Session::KeyOwner`.
+fn key_owner(state: &impl RawState, at: Hash, key: &AuraId) -> Option<AccountId> {
+	decode_value(state, at, &key_owner_key(key))
+}
+
+/// The candidates and invulnerables of the collator selection pallet.
+fn collator_candidates(state: &impl RawState, at: Hash) -> Vec<AccountId> {
+	let candidates: Vec<(AccountId, Balance)> =
+		decode_value(state, at, &storage_value_key(b"CollatorSelection", b"Candidates"))
+			.unwrap_or_default();
+	let invulnerables: Vec<AccountId> =
+		decode_value(state, at, &storage_value_key(b"CollatorSelection", b"Invulnerables"))
+			.unwrap_or_default();
+	candidates.into_iter().map(|(who, _deposit)| who).chain(invulnerables).collect()
+}
+
+/// The status of the node holding the Aura `keys` in the state of block `at`.
+pub fn collator_status(state: &impl RawState, at: Hash, keys: &[AuraId]) -> CollatorStatus {
+	let authorities: Vec<AuraId> =
+		decode_value(state, at, &storage_value_key(b"Aura", b"Authorities")).unwrap_or_default();
+	if keys.iter().any(|key| authorities.contains(key)) {
+		return CollatorStatus::Active
+	}
+
+	let candidates = collator_candidates(state, at);
+	if keys
+		.iter()
+		.filter_map(|key| key_owner(state, at, key))
+		.any(|owner| candidates.contains(&owner))
+	{
+		CollatorStatus::Candidate
+	} else {
+		CollatorStatus::None
+	}
+}
+
+/// Prometheus metrics of the collator status.
+#[derive(Clone)]
+pub struct Metrics {
+	status: GaugeVec<U64>,
+}
+
+impl Metrics {
+	/// Register the metrics at the given registry, their names prefixed with `prefix`.
+	pub fn register(registry: &Registry, prefix: &MetricPrefix) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			status: register(
+				GaugeVec::new(
+					Opts::new(
+						prefix.apply("integritee_parachain_collator_status"),
+						"Whether this node's authoring key is an active collator, a candidate or \
+						neither, 1 for the current status and 0 for the others",
+					),
+					&["status"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	fn report(&self, status: CollatorStatus) {
+		for other in CollatorStatus::ALL {
+			self.status.with_label_values(&[other.as_str()]).set((other == status) as u64);
+		}
+	}
+}
+
+fn aura_keys(keystore: &KeystorePtr) -> Vec<AuraId> {
+	keystore.sr25519_public_keys(AURA).into_iter().map(Into::into).collect()
+}
+
+/// Follow the best blocks and report the collator status of the node whenever the session changes.
+pub async fn run<C, B>(client: Arc<C>, keystore: KeystorePtr, metrics: Option<Metrics>)
+where
+	C: BlockchainEvents<Block> + StorageProvider<Block, B>,
+	B: Backend<Block>,
+{
+	let state = ClientState::new(client.clone());
+	let mut best_blocks = client
+		.import_notification_stream()
+		.filter(|notification| futures::future::ready(notification.is_new_best));
+
+	let mut last_session = None;
+	let mut last_status = None;
+	while let Some(notification) = best_blocks.next().await {
+		let session = session_index(&state, notification.hash);
+		if session.is_some() && session == last_session {
+			continue
+		}
+		last_session = session;
+
+		// Keys may be inserted into the keystore at any time, so look them up again.
+		let status = collator_status(&state, notification.hash, &aura_keys(&keystore));
+		if last_status != Some(status) {
+			log::info!(
+				target: LOG_TARGET,
+				"Collator status at block #{} (session {:?}): {}",
+				notification.header.number,
+				session,
+				status.as_str(),
+			);
+			last_status = Some(status);
+		}
+		if let Some(metrics) = &metrics {
+			metrics.report(status);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::sr25519;
+	use std::collections::HashMap;
+
+	#[derive(Default)]
+	struct MockState(HashMap<Vec<u8>, Vec<u8>>);
+
+	impl RawState for MockState {
+		fn storage(&self, _at: Hash, key: &[u8]) -> Option<Vec<u8>> {
+			self.0.get(key).cloned()
+		}
+	}
+
+	impl MockState {
+		fn with_authorities(mut self, authorities: &[AuraId]) -> Self {
+			self.0.insert(storage_value_key(b"Aura", b"Authorities"), authorities.encode());
+			self
+		}
+
+		fn with_key_owner(mut self, key: &AuraId, owner: AccountId) -> Self {
+			self.0.insert(key_owner_key(key), owner.encode());
+			self
+		}
+
+		fn with_candidates(mut self, candidates: &[AccountId]) -> Self {
+			let candidates: Vec<(AccountId, Balance)> =
+				candidates.iter().map(|who| (who.clone(), 1_000)).collect();
+			self.0.insert(
+				storage_value_key(b"CollatorSelection", b"Candidates"),
+				candidates.encode(),
+			);
+			self
+		}
+
+		fn with_invulnerables(mut self, invulnerables: &[AccountId]) -> Self {
+			self.0.insert(
+				storage_value_key(b"CollatorSelection", b"Invulnerables"),
+				invulnerables.encode(),
+			);
+			self
+		}
+	}
+
+	fn key(n: u8) -> AuraId {
+		sr25519::Public::from_raw([n; 32]).into()
+	}
+
+	fn account(n: u8) -> AccountId {
+		AccountId::new([n; 32])
+	}
+
+	fn status(state: &MockState, keys: &[AuraId]) -> CollatorStatus {
+		collator_status(state, Hash::zero(), keys)
+	}
+
+	#[test]
+	fn authority_is_active() {
+		let state = MockState::default()
+			.with_authorities(&[key(1), key(2)])
+			.with_key_owner(&key(2), account(2))
+			.with_candidates(&[account(2)]);
+
+		assert_eq!(status(&state, &[key(2)]), CollatorStatus::Active);
+		assert_eq!(status(&state, &[key(9), key(1)]), CollatorStatus::Active);
+	}
+
+	#[test]
+	fn registered_account_is_candidate() {
+		let state = MockState::default()
+			.with_authorities(&[key(1)])
+			.with_key_owner(&key(2), account(2))
+			.with_key_owner(&key(3), account(3))
+			.with_candidates(&[account(2)])
+			.with_invulnerables(&[account(3)]);
+
+		assert_eq!(status(&state, &[key(2)]), CollatorStatus::Candidate);
+		assert_eq!(status(&state, &[key(3)]), CollatorStatus::Candidate);
+	}
+
+	#[test]
+	fn unknown_key_is_none() {
+		let state = MockState::default()
+			.with_authorities(&[key(1)])
+			.with_key_owner(&key(2), account(2))
+			.with_candidates(&[account(3)]);
+
+		assert_eq!(status(&state, &[key(2)]), CollatorStatus::None);
+		// The key is not registered in the session pallet.
+		assert_eq!(status(&state, &[key(3)]), CollatorStatus::None);
+		assert_eq!(status(&state, &[]), CollatorStatus::None);
+	}
+
+	#[test]
+	fn runtime_without_collator_selection_degrades_gracefully() {
+		let state = MockState::default().with_authorities(&[key(1)]);
+
+		assert_eq!(status(&state, &[key(1)]), CollatorStatus::Active);
+		assert_eq!(status(&state, &[key(2)]), CollatorStatus::None);
+		assert_eq!(session_index(&state, Hash::zero()), None);
+
+		// Not even Aura, like the shell runtime.
+		assert_eq!(status(&MockState::default(), &[key(1)]), CollatorStatus::None);
+	}
+
+	#[test]
+	fn session_index_is_read() {
+		let mut state = MockState::default();
+		state.0.insert(storage_value_key(b"Session", b"CurrentIndex"), 7u32.encode());
+
+		assert_eq!(session_index(&state, Hash::zero()), Some(7));
+	}
+
+	#[test]
+	fn metric_reflects_status() {
+		let registry = Registry::new();
+		let metrics = Metrics::register(&registry, &MetricPrefix::default()).unwrap();
+		let gauge = |status: &str| metrics.status.with_label_values(&[status]).get();
+
+		for status in CollatorStatus::ALL {
+			metrics.report(status);
+			assert_eq!(gauge("active"), (status == CollatorStatus::Active) as u64);
+			assert_eq!(gauge("candidate"), (status == CollatorStatus::Candidate) as u64);
+			assert_eq!(gauge("none"), (status == CollatorStatus::None) as u64);
+		}
+	}
+
+	#[test]
+	fn metric_names_are_prefixed() {
+		let registry = Registry::new();
+		let metrics = Metrics::register(&registry, &"collator".parse().unwrap()).unwrap();
+		metrics.report(CollatorStatus::Active);
+
+		let names: Vec<_> =
+			registry.gather().iter().map(|family| family.get_name().to_owned()).collect();
+		assert_eq!(names, ["collator_integritee_parachain_collator_status"]);
+	}
+}
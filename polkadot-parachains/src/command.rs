@@ -16,6 +16,7 @@
 // along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
 
 use crate::{
+	candidate_retry::RetryConfig,
 	chain_spec,
 	chain_spec::{
 		integritee_chain_spec, integritee_moonbase_config, shell_chain_spec, shell_kusama_config,
@@ -23,8 +24,11 @@ use crate::{
 		shell_rococo_config, shell_westend_config, GenesisKeys, RelayChain, ShellChainSpec,
 	},
 	cli::{Cli, RelayChainCli, Subcommand},
+	proposal_slot::ProposalSlotConfig,
+	relay_rpc,
 	service::{
-		new_partial, Block, IntegriteeParachainRuntimeExecutor, ShellParachainRuntimeExecutor,
+		new_partial, Block, IntegriteeParachainRuntimeExecutor, NodeExtraArgs,
+		ShellParachainRuntimeExecutor,
 	},
 };
 use codec::Encode;
@@ -37,10 +41,10 @@ use sc_cli::{
 	ChainSpec, CliConfiguration, DefaultConfigurationValues, ImportParams, KeystoreParams,
 	NetworkParams, Result, RuntimeVersion, SharedParams, SubstrateCli,
 };
-use sc_service::config::{BasePath, PrometheusConfig};
+use sc_service::config::{BasePath, NetworkConfiguration, PrometheusConfig};
 use sp_core::hexdisplay::HexDisplay;
 use sp_runtime::traits::{AccountIdConversion, Block as BlockT};
-use std::net::SocketAddr;
+use std::{net::SocketAddr, time::Duration};
 
 const LOCAL_PARA_ID: u32 = 2015;
 const ROCOCO_PARA_ID: u32 = 2015;
@@ -50,20 +54,56 @@ const KUSAMA_SWAP_PARA_ID: u32 = 2267;
 const POLKADOT_PARA_ID: u32 = 2039;
 const MOONBASE_PARA_ID: u32 = 2015;
 
+/// The runtimes this binary has a native executor for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChainRuntime {
+	Integritee,
+	Shell,
+}
+
 trait IdentifyChain {
 	fn is_shell(&self) -> bool;
+
+	/// The runtime of the chain, failing for specs that match none of ours.
+	fn runtime(&self) -> std::result::Result<ChainRuntime, String>;
 }
 
 impl IdentifyChain for dyn sc_service::ChainSpec {
 	fn is_shell(&self) -> bool {
 		self.name().starts_with("Integritee Shell")
 	}
+
+	fn runtime(&self) -> std::result::Result<ChainRuntime, String> {
+		chain_runtime(self.name()).ok_or_else(|| {
+			format!(
+				"Unknown runtime for chain spec '{}' ({}): expected a name starting with \
+				'Integritee Shell' for the shell runtime or 'Integritee' for the integritee runtime",
+				self.name(),
+				self.id(),
+			)
+		})
+	}
 }
 
 impl<T: sc_service::ChainSpec + 'static> IdentifyChain for T {
 	fn is_shell(&self) -> bool {
 		<dyn sc_service::ChainSpec>::is_shell(self)
 	}
+
+	fn runtime(&self) -> std::result::Result<ChainRuntime, String> {
+		<dyn sc_service::ChainSpec>::runtime(self)
+	}
+}
+
+/// Resolves the runtime from the name of a chain spec.
+fn chain_runtime(name: &str) -> Option<ChainRuntime> {
+	if name.starts_with("Integritee Shell") {
+		Some(ChainRuntime::Shell)
+	} else if name.starts_with("Integritee") {
+		Some(ChainRuntime::Integritee)
+	} else {
+		None
+	}
 }
 
 // If we don't skipp here, each cmd expands to 5 lines. I think we have better overview like this.
@@ -214,18 +254,23 @@ impl SubstrateCli for RelayChainCli {
 /// Creates partial components for the runtimes that are supported by the benchmarks.
 macro_rules! construct_benchmark_partials {
 	($config:expr, |$partials:ident| $code:expr) => {
-		if $config.chain_spec.is_shell() {
-			let $partials = new_partial::<shell_runtime::RuntimeApi, _>(
-				&$config,
-				crate::service::aura_build_import_queue::<_, AuraId>,
-			)?;
-			$code
-		} else {
-			let $partials = new_partial::<parachain_runtime::RuntimeApi, _>(
-				&$config,
-				crate::service::aura_build_import_queue::<_, AuraId>,
-			)?;
-			$code
+		match $config.chain_spec.runtime()? {
+			ChainRuntime::Shell => {
+				let $partials = new_partial::<shell_runtime::RuntimeApi, _>(
+					&$config,
+					crate::service::aura_build_import_queue::<_, AuraId>,
+					crate::service::DEFAULT_TELEMETRY_BUFFER_SIZE,
+				)?;
+				$code
+			},
+			ChainRuntime::Integritee => {
+				let $partials = new_partial::<parachain_runtime::RuntimeApi, _>(
+					&$config,
+					crate::service::aura_build_import_queue::<_, AuraId>,
+					crate::service::DEFAULT_TELEMETRY_BUFFER_SIZE,
+				)?;
+				$code
+			},
 		}
 	};
 }
@@ -238,6 +283,7 @@ macro_rules! construct_async_run {
 				let $components = new_partial::<shell_runtime::RuntimeApi, _>(
 					&$config,
 					crate::service::aura_build_import_queue::<_, AuraId>,
+					crate::service::DEFAULT_TELEMETRY_BUFFER_SIZE,
 				)?;
 				let task_manager = $components.task_manager;
 				{ $( $code )* }.map(|v| (v, task_manager))
@@ -247,6 +293,7 @@ macro_rules! construct_async_run {
 			let $components = new_partial::<parachain_runtime::RuntimeApi, _>(
 				&$config,
 				crate::service::aura_build_import_queue::<_, AuraId>,
+				crate::service::DEFAULT_TELEMETRY_BUFFER_SIZE,
 			)?;
 			let task_manager = $components.task_manager;
 			{ $( $code )* }.map(|v| (v, task_manager))
@@ -261,8 +308,15 @@ pub fn run() -> Result<()> {
 
 	match &cli.subcommand {
 		Some(Subcommand::BuildSpec(cmd)) => {
-			let runner = cli.create_runner(cmd)?;
-			runner.sync_run(|config| cmd.run(config.chain_spec, config.network))
+			if let (Some(para_id), Some(relay_chain)) = (cmd.para_id, cmd.relay_chain()?) {
+				// The generated chain spec doesn't need `--chain`, hence we can't use the runner.
+				let spec =
+					chain_spec::integritee_chain_spec_for_relay(para_id.into(), relay_chain)?;
+				return Ok(cmd.base.run(Box::new(spec), NetworkConfiguration::new_local())?)
+			}
+
+			let runner = cli.create_runner(&cmd.base)?;
+			runner.sync_run(|config| cmd.base.run(config.chain_spec, config.network))
 		},
 		Some(Subcommand::CheckBlock(cmd)) => {
 			construct_async_run!(|components, cli, cmd, config| {
@@ -328,12 +382,11 @@ pub fn run() -> Result<()> {
 			match cmd {
 				BenchmarkCmd::Pallet(cmd) =>
 					if cfg!(feature = "runtime-benchmarks") {
-						runner.sync_run(|config| {
-							if config.chain_spec.is_shell() {
-								cmd.run::<Block, ShellParachainRuntimeExecutor>(config)
-							} else {
-								cmd.run::<Block, IntegriteeParachainRuntimeExecutor>(config)
-							}
+						runner.sync_run(|config| match config.chain_spec.runtime()? {
+							ChainRuntime::Shell =>
+								cmd.run::<Block, ShellParachainRuntimeExecutor>(config),
+							ChainRuntime::Integritee =>
+								cmd.run::<Block, IntegriteeParachainRuntimeExecutor>(config),
 						})
 					} else {
 						Err("Benchmarking wasn't enabled when building the node. \
@@ -371,16 +424,14 @@ pub fn run() -> Result<()> {
 		#[cfg(feature = "try-runtime")]
 		Some(Subcommand::TryRuntime(cmd)) => {
 			if cfg!(feature = "try-runtime") {
-				use parachain_runtime::MILLISECS_PER_BLOCK;
+				// The integritee and the shell runtime share the block type and the block time, so
+				// the same command covers both, the runtime is taken from the state or `--runtime`.
+				use crate::service::HostFunctions;
+				use parachains_common::MILLISECS_PER_BLOCK;
 				use try_runtime_cli::block_building_info::timestamp_with_aura_info;
 
 				let runner = cli.create_runner(cmd)?;
 
-				type HostFunctions = (
-					sp_io::SubstrateHostFunctions,
-					frame_benchmarking::benchmarking::HostFunctions,
-				);
-
 				// grab the task manager.
 				let registry = &runner.config().prometheus_config.as_ref().map(|cfg| &cfg.registry);
 				let task_manager =
@@ -416,6 +467,23 @@ pub fn run() -> Result<()> {
 					.map(|e| e.para_id)
 					.ok_or_else(|| "Could not find parachain extension in chain-spec.")?;
 
+				if let Some(block_time) = cli.dev_block_time {
+					if config.chain_spec.is_shell() {
+						return Err(
+							"Only the integritee runtime can run as development node.".into()
+						)
+					}
+					return crate::service::start_dev_node::<parachain_runtime::RuntimeApi>(
+						config,
+						ParaId::from(para_id),
+						Duration::from_millis(block_time),
+						cli.telemetry_buffer_size,
+					)
+					.await
+					.map(|r| r.0)
+					.map_err(Into::into)
+				}
+
 				let polkadot_cli = RelayChainCli::new(
 					&config,
 					[RelayChainCli::executable_name()].iter().chain(cli.relaychain_args.iter()),
@@ -443,10 +511,54 @@ pub fn run() -> Result<()> {
 				info!("Parachain genesis state: {}", genesis_state);
 				info!("Is collating: {}", if config.role.is_authority() { "yes" } else { "no" });
 
-				if !collator_options.relay_chain_rpc_urls.is_empty() && cli.relaychain_args.len() > 0 {
-					warn!("Detected relay chain node arguments together with --relay-chain-rpc-url. This command starts a minimal Polkadot node that only uses a network-related subset of all relay chain CLI options.");
+				let relay_rpc_urls = &collator_options.relay_chain_rpc_urls;
+				if relay_rpc_urls.is_empty() {
+					info!("Relay chain: embedded node");
+				} else {
+					let urls: Vec<_> = relay_rpc_urls.iter().map(|url| url.as_str()).collect();
+					info!("Relay chain: minimal node on RPC endpoints {}", urls.join(", "));
+					if cli.relaychain_args.len() > 0 {
+						warn!("Detected relay chain node arguments together with --relay-chain-rpc-url. This command starts a minimal Polkadot node that only uses a network-related subset of all relay chain CLI options.");
+					}
+					relay_rpc::validate_endpoints(relay_rpc_urls)?;
+					let retry = RetryConfig {
+						max_retries: cli.relay_chain_rpc_retries,
+						initial_backoff: Duration::from_millis(cli.relay_chain_rpc_backoff_ms),
+					};
+					relay_rpc::wait_for_endpoints(relay_rpc_urls, retry, relay_rpc::is_reachable)
+						.await?;
 				}
 
+				let node_extra_args = NodeExtraArgs {
+					max_extrinsics_per_block: cli.max_extrinsics_per_block,
+					timestamp_grace_ms: cli.timestamp_grace_ms,
+					telemetry_buffer_size: cli.telemetry_buffer_size,
+					authoring_stats_file: cli.authoring_stats_file.clone(),
+					authoring_stats_interval: Duration::from_secs(cli.authoring_stats_interval),
+					announce_relay_parent_check: !cli.no_announce_relay_parent_check,
+					verifier_cross_check: cli.verifier_cross_check,
+					collator_peers: cli.collator_peers.clone(),
+					allowed_collators: cli.allowed_collators.clone(),
+					block_metadata_file: cli.block_metadata_file.clone(),
+					block_metadata_max_bytes: cli.block_metadata_max_bytes,
+					metric_prefix: cli.metric_prefix.clone().unwrap_or_default(),
+					relay_chain_slot_duration: Duration::from_millis(
+						cli.relay_chain_slot_duration_ms,
+					),
+					proposal_slot: ProposalSlotConfig {
+						normal: cli.block_proposal_slot_portion,
+						max: Some(cli.max_block_proposal_slot_portion),
+					},
+					max_relay_parent_age: cli.max_relay_parent_age,
+					check_integrity: cli.check_integrity,
+					candidate_retry: RetryConfig {
+						max_retries: cli.candidate_retries,
+						initial_backoff: Duration::from_millis(cli.candidate_retry_backoff_ms),
+					},
+					max_validation_data_staleness: cli.max_validation_data_staleness,
+					max_concurrent_pov_recoveries: cli.max_concurrent_pov_recoveries,
+				};
+
 				if config.chain_spec.is_shell() {
 					crate::service::start_generic_aura_node::<shell_runtime::RuntimeApi, AuraId>(
 						config,
@@ -454,15 +566,20 @@ pub fn run() -> Result<()> {
 						collator_options,
 						id,
 						hwbench,
+						node_extra_args,
 					)
 					.await
 					.map(|r| r.0)
 					.map_err(Into::into)
 				} else {
-					crate::service::start_generic_aura_node::<
-						parachain_runtime::RuntimeApi,
-						AuraId,
-					>(config, polkadot_config, collator_options, id, hwbench)
+					crate::service::start_integritee_node(
+						config,
+						polkadot_config,
+						collator_options,
+						id,
+						hwbench,
+						node_extra_args,
+					)
 					.await
 					.map(|r| r.0)
 					.map_err(Into::into)
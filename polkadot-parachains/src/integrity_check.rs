@@ -0,0 +1,131 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Run the runtime's integrity checks at startup.
+//!
+//! The runtimes check the consistency of their XCM configuration in unit tests, which don't cover
+//! the runtime a node actually runs. [`check`] runs the same checks against the runtime at the
+//! best block via the `IntegrityCheckApi` and refuses to start the node if any of them fails. The
+//! checks only weigh a few messages and convert the currencies, they take next to no time.
+
+use parachains_common::{
+	runtime_api::IntegrityCheckApi, xcm_config::IntegrityFailure, Block, Hash,
+};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+
+const LOG_TARGET: &str = "integrity-check";
+
+/// The name of the check `failure` failed.
+fn check_name(failure: &IntegrityFailure) -> String {
+	match failure {
+		IntegrityFailure::AsymmetricCurrencies(_) => "currency round trip".into(),
+		IntegrityFailure::WeigherMismatch { pallet, .. } =>
+			format!("{} weigher", String::from_utf8_lossy(pallet)),
+	}
+}
+
+/// What is inconsistent about the runtime, according to `failure`.
+fn describe(failure: &IntegrityFailure) -> String {
+	match failure {
+		IntegrityFailure::AsymmetricCurrencies(currencies) =>
+			format!("the currencies {:?} don't convert to their location and back", currencies),
+		IntegrityFailure::WeigherMismatch { pallet, messages } => format!(
+			"{} weighs the representative messages {:?} unlike the XCM executor",
+			String::from_utf8_lossy(pallet),
+			messages
+		),
+	}
+}
+
+/// Log every failed check, failing if there is any.
+fn report(failures: &[IntegrityFailure]) -> Result<(), String> {
+	if failures.is_empty() {
+		log::info!(target: LOG_TARGET, "The runtime passed its integrity checks");
+		return Ok(())
+	}
+
+	for failure in failures {
+		log::error!(
+			target: LOG_TARGET,
+			"Integrity check \"{}\" failed: {}",
+			check_name(failure),
+			describe(failure)
+		);
+	}
+	let names = failures.iter().map(check_name).collect::<Vec<_>>();
+	Err(format!("The runtime failed the integrity checks: {}", names.join(", ")))
+}
+
+/// Run the integrity checks of the runtime at `at`.
+///
+/// Runtimes which don't implement the `IntegrityCheckApi` yet are reported, but pass.
+pub fn check<Client>(client: &Client, at: Hash) -> Result<(), sc_service::Error>
+where
+	Client: ProvideRuntimeApi<Block>,
+	Client::Api: IntegrityCheckApi<Block>,
+{
+	let api = client.runtime_api();
+	if !api.has_api::<dyn IntegrityCheckApi<Block>>(at).unwrap_or(false) {
+		log::warn!(
+			target: LOG_TARGET,
+			"The runtime at {} has no integrity checks, skipping them",
+			at
+		);
+		return Ok(())
+	}
+	let failures = api
+		.check_integrity(at)
+		.map_err(|e| format!("Unable to run the runtime's integrity checks: {}", e))?;
+	report(&failures).map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use parachains_common::xcm_config::CurrencyId;
+
+	#[test]
+	fn consistent_runtime_passes() {
+		assert_eq!(report(&[]), Ok(()));
+	}
+
+	#[test]
+	fn failed_checks_are_named() {
+		let failures = [
+			IntegrityFailure::AsymmetricCurrencies(vec![CurrencyId::TEER]),
+			IntegrityFailure::WeigherMismatch {
+				pallet: b"orml_xtokens".to_vec(),
+				messages: vec![2, 4],
+			},
+		];
+		assert_eq!(
+			report(&failures),
+			Err("The runtime failed the integrity checks: currency round trip, orml_xtokens weigher"
+				.into())
+		);
+	}
+
+	#[test]
+	fn failures_are_described() {
+		let failure =
+			IntegrityFailure::WeigherMismatch { pallet: b"pallet_xcm".to_vec(), messages: vec![6] };
+		assert_eq!(
+			describe(&failure),
+			"pallet_xcm weighs the representative messages [6] unlike the XCM executor"
+		);
+	}
+}
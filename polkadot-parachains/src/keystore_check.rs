@@ -0,0 +1,282 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Validate the local keystore at startup.
+//!
+//! The keystore reads its key files only once a key is used, so a corrupted file would only make
+//! consensus fail to sign, without telling which file is at fault. [`check`] reads every key file
+//! up front: an authority refuses to start with a corrupted keystore, as it couldn't author,
+//! while other nodes only report the corrupted files and start anyway.
+
+use sp_core::{
+	bytes::from_hex,
+	crypto::{ExposeSecret, KeyTypeId, Pair, SecretString},
+	ecdsa, ed25519, sr25519,
+};
+use std::{
+	fmt, fs, io,
+	path::{Path, PathBuf},
+};
+
+const LOG_TARGET: &str = "keystore-check";
+
+/// What is wrong with a key file.
+#[derive(Debug)]
+pub enum Problem {
+	/// The file can't be read.
+	Unreadable(io::Error),
+	/// The file name isn't the hex encoded key type followed by the public key.
+	MalformedName,
+	/// The file doesn't contain a JSON encoded secret phrase or seed.
+	MalformedSecret,
+	/// The secret doesn't belong to the public key in the file name, or the password is wrong.
+	KeyMismatch,
+}
+
+impl fmt::Display for Problem {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Problem::Unreadable(e) => write!(f, "the file can't be read: {}", e),
+			Problem::MalformedName =>
+				write!(f, "the file name is not a hex encoded key type and public key"),
+			Problem::MalformedSecret =>
+				write!(f, "the file doesn't contain a JSON encoded secret phrase or seed"),
+			Problem::KeyMismatch => write!(
+				f,
+				"the secret doesn't match the public key in the file name, or the keystore \
+				password is wrong"
+			),
+		}
+	}
+}
+
+/// A corrupted key file.
+#[derive(Debug)]
+pub struct CorruptedKey {
+	pub path: PathBuf,
+	/// The key type of the key, if the file name could be parsed.
+	pub key_type: Option<KeyTypeId>,
+	pub problem: Problem,
+}
+
+impl fmt::Display for CorruptedKey {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self.key_type {
+			Some(key_type) => write!(
+				f,
+				"{} key {}: {}",
+				String::from_utf8_lossy(&key_type.0),
+				self.path.display(),
+				self.problem
+			),
+			None => write!(f, "{}: {}", self.path.display(), self.problem),
+		}
+	}
+}
+
+/// The corrupted key files in the keystore directory `path`. A missing directory is an empty
+/// keystore.
+pub fn corrupted_keys(
+	path: &Path,
+	password: Option<&SecretString>,
+) -> io::Result<Vec<CorruptedKey>> {
+	let entries = match fs::read_dir(path) {
+		Ok(entries) => entries,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+		Err(e) => return Err(e),
+	};
+	let password = password.map(|password| password.expose_secret().as_str());
+
+	let mut corrupted = Vec::new();
+	for entry in entries {
+		let path = entry?.path();
+		if !path.is_file() {
+			continue
+		}
+		let (key_type, problem) = check_key_file(&path, password);
+		if let Some(problem) = problem {
+			corrupted.push(CorruptedKey { path, key_type, problem });
+		}
+	}
+	corrupted.sort_by(|a, b| a.path.cmp(&b.path));
+	Ok(corrupted)
+}
+
+fn check_key_file(path: &Path, password: Option<&str>) -> (Option<KeyTypeId>, Option<Problem>) {
+	let name = path
+		.file_name()
+		.and_then(|name| name.to_str())
+		.and_then(|name| from_hex(name).ok())
+		.filter(|name| name.len() > 4);
+	let Some(name) = name else { return (None, Some(Problem::MalformedName)) };
+	let (key_type, public) = name.split_at(4);
+	let key_type = KeyTypeId(key_type.try_into().expect("split at 4 bytes; qed"));
+
+	let problem = match fs::read(path) {
+		Err(e) => Some(Problem::Unreadable(e)),
+		Ok(content) => match serde_json::from_slice::<String>(&content) {
+			Err(_) => Some(Problem::MalformedSecret),
+			Ok(secret) =>
+				(!matches_public(&secret, password, public)).then_some(Problem::KeyMismatch),
+		},
+	};
+	(Some(key_type), problem)
+}
+
+/// Whether `secret` derives the public key `public` in any of the schemes the keystore supports.
+fn matches_public(secret: &str, password: Option<&str>, public: &[u8]) -> bool {
+	fn derives<P: Pair>(secret: &str, password: Option<&str>, public: &[u8]) -> bool {
+		P::from_string(secret, password).map_or(false, |pair| pair.public().as_ref() == public)
+	}
+
+	derives::<sr25519::Pair>(secret, password, public) ||
+		derives::<ed25519::Pair>(secret, password, public) ||
+		derives::<ecdsa::Pair>(secret, password, public)
+}
+
+/// Check the keystore at `path`, logging every corrupted key file.
+///
+/// Fails if the node is an `authority`, as it couldn't author blocks with a corrupted keystore.
+/// Other nodes start anyway.
+pub fn check(
+	path: &Path,
+	password: Option<&SecretString>,
+	authority: bool,
+) -> Result<(), sc_service::Error> {
+	let corrupted = corrupted_keys(path, password)
+		.map_err(|e| format!("Unable to read the keystore at {}: {}", path.display(), e))?;
+	if corrupted.is_empty() {
+		return Ok(())
+	}
+
+	for key in &corrupted {
+		log::error!(target: LOG_TARGET, "Corrupted keystore entry, {}", key);
+	}
+	let advice =
+		"Move the corrupted files out of the keystore and insert the keys again, e.g. with \
+		the `key insert` subcommand or the `author_insertKey` RPC.";
+	if authority {
+		Err(format!(
+			"The keystore at {} has {} corrupted entries, unable to author blocks. {}",
+			path.display(),
+			corrupted.len(),
+			advice
+		)
+		.into())
+	} else {
+		log::warn!(
+			target: LOG_TARGET,
+			"Starting with {} corrupted keystore entries, as this node doesn't author blocks. {}",
+			corrupted.len(),
+			advice
+		);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::bytes::to_hex;
+
+	const AURA: KeyTypeId = KeyTypeId(*b"aura");
+
+	fn file_name(key_type: KeyTypeId, public: &[u8]) -> String {
+		to_hex(&[&key_type.0[..], public].concat(), false)
+			.trim_start_matches("0x")
+			.to_owned()
+	}
+
+	fn insert(dir: &Path, name: &str, content: &[u8]) -> PathBuf {
+		let path = dir.join(name);
+		fs::write(&path, content).unwrap();
+		path
+	}
+
+	fn insert_alice(dir: &Path) -> PathBuf {
+		let public = sr25519::Pair::from_string("//Alice", None).unwrap().public();
+		insert(dir, &file_name(AURA, public.as_ref()), br#""//Alice""#)
+	}
+
+	#[test]
+	fn missing_keystore_is_empty() {
+		let dir = tempfile::tempdir().unwrap();
+		assert!(corrupted_keys(&dir.path().join("keystore"), None).unwrap().is_empty());
+	}
+
+	#[test]
+	fn valid_keys_pass() {
+		let dir = tempfile::tempdir().unwrap();
+		insert_alice(dir.path());
+		assert!(corrupted_keys(dir.path(), None).unwrap().is_empty());
+		assert!(check(dir.path(), None, true).is_ok());
+	}
+
+	#[test]
+	fn truncated_key_file_is_reported() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = insert_alice(dir.path());
+		fs::write(&path, br#""//Ali"#).unwrap();
+
+		let corrupted = corrupted_keys(dir.path(), None).unwrap();
+		assert_eq!(corrupted.len(), 1);
+		assert_eq!(corrupted[0].path, path);
+		assert_eq!(corrupted[0].key_type, Some(AURA));
+		assert!(matches!(corrupted[0].problem, Problem::MalformedSecret));
+	}
+
+	#[test]
+	fn secret_of_another_key_is_reported() {
+		let dir = tempfile::tempdir().unwrap();
+		let public = sr25519::Pair::from_string("//Bob", None).unwrap().public();
+		insert(dir.path(), &file_name(AURA, public.as_ref()), br#""//Alice""#);
+
+		let corrupted = corrupted_keys(dir.path(), None).unwrap();
+		assert!(matches!(corrupted[..], [CorruptedKey { problem: Problem::KeyMismatch, .. }]));
+	}
+
+	#[test]
+	fn malformed_file_name_is_reported() {
+		let dir = tempfile::tempdir().unwrap();
+		insert(dir.path(), "not-a-key", br#""//Alice""#);
+
+		let corrupted = corrupted_keys(dir.path(), None).unwrap();
+		assert!(matches!(
+			corrupted[..],
+			[CorruptedKey { key_type: None, problem: Problem::MalformedName, .. }]
+		));
+	}
+
+	#[test]
+	fn authority_with_corrupted_keystore_fails() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = insert_alice(dir.path());
+		fs::write(&path, b"garbage").unwrap();
+
+		let error = check(dir.path(), None, true).unwrap_err().to_string();
+		assert!(error.contains("1 corrupted entries"), "{}", error);
+	}
+
+	#[test]
+	fn full_node_with_corrupted_keystore_starts() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = insert_alice(dir.path());
+		fs::write(&path, b"garbage").unwrap();
+
+		assert!(check(dir.path(), None, false).is_ok());
+	}
+}
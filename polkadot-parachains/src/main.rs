@@ -19,12 +19,35 @@
 #![warn(missing_docs)]
 #![warn(unused_extern_crates)]
 
+mod aura_version;
+mod authoring_stats;
+mod backing_monitor;
+mod block_announce;
+mod block_metadata;
+mod candidate_retry;
 mod chain_spec;
+mod collator_allow_list;
+mod collator_peers;
+mod collator_status;
 #[macro_use]
 mod service;
 mod cli;
 mod command;
+mod integrity_check;
+mod keystore_check;
+mod metric_prefix;
+mod pov_recovery_limit;
+mod proposal_slot;
+mod relay_parent_age;
+mod relay_resync;
+mod relay_rpc;
 mod rpc;
+mod stale_validation_data;
+mod throttle;
+mod verifier_cross_check;
+mod wait_for_aura;
+mod warp_sync;
+mod xcm_weight_metrics;
 
 fn main() -> sc_cli::Result<()> {
 	command::run()
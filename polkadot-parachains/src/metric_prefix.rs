@@ -0,0 +1,108 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Prefix the names of the node's own Prometheus metrics.
+//!
+//! A collator and a full node reporting to the same Prometheus or dashboard export metrics of the
+//! same names. With a [`MetricPrefix`] per role or instance, e.g. `collator`, the node's own
+//! metrics become `collator_integritee_parachain_...`. The metrics of Substrate are left as they
+//! are, they carry the node's name as a label anyway.
+
+use std::{fmt, str::FromStr};
+
+/// A prefix of metric names, a legal metric name component. Empty by default.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MetricPrefix(String);
+
+impl MetricPrefix {
+	/// The metric `name` with the prefix, separated by an underscore.
+	pub fn apply(&self, name: &str) -> String {
+		if self.0.is_empty() {
+			name.to_owned()
+		} else {
+			format!("{}_{}", self.0, name)
+		}
+	}
+}
+
+impl FromStr for MetricPrefix {
+	type Err = String;
+
+	/// Accepts what Prometheus accepts as the start of a metric name, apart from the colons
+	/// reserved for recording rules.
+	fn from_str(prefix: &str) -> Result<Self, Self::Err> {
+		let mut chars = prefix.chars();
+		let legal_start = chars.next().map_or(true, |c| c.is_ascii_alphabetic() || c == '_');
+		if !legal_start || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_') {
+			return Err(format!(
+				"`{}` is not a legal metric name prefix, it must match [a-zA-Z_][a-zA-Z0-9_]*",
+				prefix
+			))
+		}
+		Ok(Self(prefix.to_owned()))
+	}
+}
+
+impl fmt::Display for MetricPrefix {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use substrate_prometheus_endpoint::Registry;
+
+	#[test]
+	fn empty_prefix_keeps_names() {
+		let prefix = MetricPrefix::default();
+		assert_eq!(
+			prefix.apply("integritee_parachain_collator_status"),
+			"integritee_parachain_collator_status"
+		);
+		assert_eq!("".parse(), Ok(prefix));
+	}
+
+	#[test]
+	fn prefix_is_separated_by_an_underscore() {
+		let prefix: MetricPrefix = "collator_1".parse().unwrap();
+		assert_eq!(
+			prefix.apply("integritee_parachain_collator_status"),
+			"collator_1_integritee_parachain_collator_status"
+		);
+	}
+
+	#[test]
+	fn illegal_prefixes_are_rejected() {
+		for prefix in ["1collator", "collator-1", "collator:1", "collator 1", "kollätor"] {
+			assert!(prefix.parse::<MetricPrefix>().is_err(), "{}", prefix);
+		}
+	}
+
+	#[test]
+	fn roles_sharing_a_registry_dont_collide() {
+		let registry = Registry::new();
+		for role in ["collator", "full_node"] {
+			let prefix = role.parse().unwrap();
+			crate::backing_monitor::Metrics::register(&registry, &prefix).unwrap();
+			crate::collator_status::Metrics::register(&registry, &prefix).unwrap();
+		}
+		assert!(crate::collator_status::Metrics::register(&registry, &"collator".parse().unwrap())
+			.is_err());
+	}
+}
@@ -0,0 +1,217 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Cap the number of PoV recoveries in flight.
+//!
+//! A full node catching up asks the availability recovery for the PoVs of all the candidates it
+//! misses at once, which can overwhelm the relay chain and the node itself. [`LimitedRecovery`]
+//! forwards at most a given number of recoveries at a time and queues the others.
+//!
+//! Queued recoveries are forwarded newest first: candidates are recovered in the order they are
+//! included on the relay chain, so the latest request is the one for the current best block, which
+//! is more urgent than the historical recoveries queued before it.
+
+use cumulus_client_pov_recovery::RecoveryHandle;
+use futures::{
+	channel::{mpsc, oneshot},
+	stream::FuturesUnordered,
+	Future, StreamExt,
+};
+use polkadot_node_subsystem::messages::AvailabilityRecoveryMessage;
+
+const LOG_TARGET: &str = "pov-recovery-limit";
+
+/// A recovery request, with the name of the subsystem sending it.
+type Request = (AvailabilityRecoveryMessage, &'static str);
+
+/// A [`RecoveryHandle`] queueing the recoveries for the future returned by [`Self::new`].
+pub struct LimitedRecovery {
+	requests: mpsc::UnboundedSender<Request>,
+}
+
+impl LimitedRecovery {
+	/// Forward at most `max_in_flight` recoveries at a time to `inner`.
+	///
+	/// The returned future does the forwarding, it has to be spawned.
+	pub fn new(
+		inner: Box<dyn RecoveryHandle>,
+		max_in_flight: usize,
+	) -> (Self, impl Future<Output = ()> + Send) {
+		let (sender, receiver) = mpsc::unbounded();
+		(Self { requests: sender }, forward(inner, receiver, max_in_flight.max(1)))
+	}
+}
+
+#[async_trait::async_trait]
+impl RecoveryHandle for LimitedRecovery {
+	async fn send_message(&mut self, message: AvailabilityRecoveryMessage, origin: &'static str) {
+		if self.requests.unbounded_send((message, origin)).is_err() {
+			log::warn!(target: LOG_TARGET, "Dropping a PoV recovery, as the node is shutting down");
+		}
+	}
+}
+
+async fn forward(
+	mut inner: Box<dyn RecoveryHandle>,
+	mut requests: mpsc::UnboundedReceiver<Request>,
+	max_in_flight: usize,
+) {
+	let mut queued = Vec::new();
+	let mut in_flight = FuturesUnordered::new();
+	loop {
+		while in_flight.len() < max_in_flight {
+			let Some((message, origin)) = queued.pop() else { break };
+			let AvailabilityRecoveryMessage::RecoverAvailableData(
+				receipt,
+				session,
+				group,
+				response,
+			) = message;
+			// Track the recovery by answering it ourselves, passing the result on.
+			let (sender, receiver) = oneshot::channel();
+			let message =
+				AvailabilityRecoveryMessage::RecoverAvailableData(receipt, session, group, sender);
+			inner.send_message(message, origin).await;
+			in_flight.push(async move {
+				// A dropped recovery is dropped for the requester as well.
+				if let Ok(result) = receiver.await {
+					let _ = response.send(result);
+				}
+			});
+		}
+		if !queued.is_empty() {
+			log::debug!(
+				target: LOG_TARGET,
+				"{} PoV recoveries in flight, {} queued",
+				in_flight.len(),
+				queued.len(),
+			);
+		}
+
+		futures::select! {
+			request = requests.next() => match request {
+				Some(request) => queued.push(request),
+				None => break,
+			},
+			_ = in_flight.select_next_some() => {},
+		}
+	}
+	// Let the recoveries in flight answer their requester, the queued ones are dropped.
+	while in_flight.next().await.is_some() {}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Decode;
+	use futures::{executor::LocalPool, task::LocalSpawnExt};
+	use polkadot_node_primitives::AvailableData;
+	use polkadot_node_subsystem::errors::RecoveryError;
+	use polkadot_primitives::{CandidateReceipt, SessionIndex};
+	use std::sync::{Arc, Mutex};
+
+	type Response = oneshot::Sender<Result<AvailableData, RecoveryError>>;
+
+	/// Records the recoveries it receives, by session index.
+	#[derive(Clone, Default)]
+	struct MockRecovery {
+		received: Arc<Mutex<Vec<(SessionIndex, Response)>>>,
+	}
+
+	impl MockRecovery {
+		fn sessions(&self) -> Vec<SessionIndex> {
+			self.received.lock().unwrap().iter().map(|(session, _)| *session).collect()
+		}
+
+		fn fail(&self, session: SessionIndex) {
+			let mut received = self.received.lock().unwrap();
+			let index = received.iter().position(|(s, _)| *s == session).unwrap();
+			let (_, response) = received.remove(index);
+			response.send(Err(RecoveryError::Unavailable)).unwrap();
+		}
+	}
+
+	#[async_trait::async_trait]
+	impl RecoveryHandle for MockRecovery {
+		async fn send_message(&mut self, message: AvailabilityRecoveryMessage, _: &'static str) {
+			let AvailabilityRecoveryMessage::RecoverAvailableData(_, session, _, response) =
+				message;
+			self.received.lock().unwrap().push((session, response));
+		}
+	}
+
+	/// Request a recovery, telling recoveries apart by their session index.
+	fn recover(
+		handle: &mut LimitedRecovery,
+		session: SessionIndex,
+	) -> oneshot::Receiver<Result<AvailableData, RecoveryError>> {
+		// The mock doesn't look at the candidate, zeroes decode to one.
+		let receipt = CandidateReceipt::decode(&mut &[0u8; 512][..]).unwrap();
+		let (sender, receiver) = oneshot::channel();
+		let message =
+			AvailabilityRecoveryMessage::RecoverAvailableData(receipt, session, None, sender);
+		futures::executor::block_on(handle.send_message(message, "test"));
+		receiver
+	}
+
+	fn start(max_in_flight: usize) -> (LocalPool, LimitedRecovery, MockRecovery) {
+		let mock = MockRecovery::default();
+		let (handle, forward) = LimitedRecovery::new(Box::new(mock.clone()), max_in_flight);
+		let pool = LocalPool::new();
+		pool.spawner().spawn_local(forward).unwrap();
+		(pool, handle, mock)
+	}
+
+	#[test]
+	fn recoveries_in_flight_are_capped() {
+		let (mut pool, mut handle, mock) = start(2);
+		let _responses: Vec<_> = (0..10).map(|session| recover(&mut handle, session)).collect();
+		pool.run_until_stalled();
+		assert_eq!(mock.sessions(), vec![0, 1]);
+
+		mock.fail(0);
+		pool.run_until_stalled();
+		assert_eq!(mock.sessions().len(), 2);
+	}
+
+	#[test]
+	fn latest_recovery_goes_first() {
+		let (mut pool, mut handle, mock) = start(2);
+		let _responses: Vec<_> = (0..10).map(|session| recover(&mut handle, session)).collect();
+		pool.run_until_stalled();
+
+		mock.fail(0);
+		pool.run_until_stalled();
+		assert_eq!(mock.sessions(), vec![1, 9]);
+
+		mock.fail(9);
+		pool.run_until_stalled();
+		assert_eq!(mock.sessions(), vec![1, 8]);
+	}
+
+	#[test]
+	fn results_reach_the_requester() {
+		let (mut pool, mut handle, mock) = start(1);
+		let mut response = recover(&mut handle, 0);
+		pool.run_until_stalled();
+		assert!(matches!(response.try_recv(), Ok(None)));
+
+		mock.fail(0);
+		pool.run_until_stalled();
+		assert!(matches!(response.try_recv(), Ok(Some(Err(RecoveryError::Unavailable)))));
+	}
+}
@@ -0,0 +1,146 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The portions of an Aura slot a collator may spend proposing a block.
+//!
+//! The defaults leave around 500ms for proposing in a 12s slot, and up to 750ms if slots have been
+//! skipped. Collators of parachains with heavier blocks can raise them, at the risk of missing
+//! the relay chain block their candidate was built for.
+
+use cumulus_client_consensus_aura::SlotProportion;
+use std::fmt;
+
+/// Default portion of the slot spent proposing a block.
+pub const DEFAULT_BLOCK_PROPOSAL_SLOT_PORTION: f32 = 1f32 / 24f32;
+/// Default maximum portion of the slot spent proposing a block if slots have been skipped.
+pub const DEFAULT_MAX_BLOCK_PROPOSAL_SLOT_PORTION: f32 = 1f32 / 16f32;
+
+/// The portions of the slot spent proposing a block.
+///
+/// Each portion must be in `(0, 1]`, and the maximum must not be smaller than the normal portion.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProposalSlotConfig {
+	/// Portion of the slot spent proposing a block.
+	pub normal: f32,
+	/// Maximum portion of the slot spent proposing a block if slots have been skipped. `None`
+	/// lets the proposal time grow with the number of skipped slots.
+	pub max: Option<f32>,
+}
+
+impl Default for ProposalSlotConfig {
+	fn default() -> Self {
+		Self {
+			normal: DEFAULT_BLOCK_PROPOSAL_SLOT_PORTION,
+			max: Some(DEFAULT_MAX_BLOCK_PROPOSAL_SLOT_PORTION),
+		}
+	}
+}
+
+/// Why a [`ProposalSlotConfig`] is rejected.
+#[derive(Debug, PartialEq)]
+pub enum InvalidProposalSlotConfig {
+	/// The normal portion is not in `(0, 1]`.
+	Normal(f32),
+	/// The maximum portion is not in `(0, 1]`.
+	Max(f32),
+	/// The maximum portion is smaller than the normal portion.
+	MaxBelowNormal { normal: f32, max: f32 },
+}
+
+impl fmt::Display for InvalidProposalSlotConfig {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Normal(portion) =>
+				write!(f, "the block proposal slot portion {} is not in (0, 1]", portion),
+			Self::Max(portion) =>
+				write!(f, "the maximum block proposal slot portion {} is not in (0, 1]", portion),
+			Self::MaxBelowNormal { normal, max } => write!(
+				f,
+				"the maximum block proposal slot portion {} is smaller than the block proposal \
+				slot portion {}",
+				max, normal
+			),
+		}
+	}
+}
+
+fn is_valid_portion(portion: f32) -> bool {
+	portion > 0.0 && portion <= 1.0
+}
+
+impl ProposalSlotConfig {
+	/// Check that both portions are in `(0, 1]` and that the maximum is not below the normal
+	/// portion.
+	pub fn validate(&self) -> Result<(), InvalidProposalSlotConfig> {
+		if !is_valid_portion(self.normal) {
+			return Err(InvalidProposalSlotConfig::Normal(self.normal))
+		}
+		match self.max {
+			Some(max) if !is_valid_portion(max) => Err(InvalidProposalSlotConfig::Max(max)),
+			Some(max) if max < self.normal =>
+				Err(InvalidProposalSlotConfig::MaxBelowNormal { normal: self.normal, max }),
+			_ => Ok(()),
+		}
+	}
+
+	/// The portion of the slot spent proposing a block, as expected by Aura.
+	pub fn block_proposal_slot_portion(&self) -> SlotProportion {
+		SlotProportion::new(self.normal)
+	}
+
+	/// The maximum portion of the slot spent proposing a block, as expected by Aura.
+	pub fn max_block_proposal_slot_portion(&self) -> Option<SlotProportion> {
+		self.max.map(SlotProportion::new)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn default_is_valid() {
+		assert_eq!(ProposalSlotConfig::default().validate(), Ok(()));
+	}
+
+	#[test]
+	fn whole_slot_without_max_is_valid() {
+		assert_eq!(ProposalSlotConfig { normal: 1.0, max: None }.validate(), Ok(()));
+		assert_eq!(ProposalSlotConfig { normal: 0.5, max: Some(0.5) }.validate(), Ok(()));
+	}
+
+	#[test]
+	fn portions_out_of_range_are_rejected() {
+		for normal in [0.0, -0.1, 1.1, f32::NAN] {
+			let config = ProposalSlotConfig { normal, max: None };
+			assert!(matches!(config.validate(), Err(InvalidProposalSlotConfig::Normal(_))));
+		}
+		for max in [0.0, 1.1, f32::NAN] {
+			let config = ProposalSlotConfig { normal: 0.1, max: Some(max) };
+			assert!(matches!(config.validate(), Err(InvalidProposalSlotConfig::Max(_))));
+		}
+	}
+
+	#[test]
+	fn max_below_normal_is_rejected() {
+		let config = ProposalSlotConfig { normal: 0.5, max: Some(0.25) };
+		assert_eq!(
+			config.validate(),
+			Err(InvalidProposalSlotConfig::MaxBelowNormal { normal: 0.5, max: 0.25 })
+		);
+	}
+}
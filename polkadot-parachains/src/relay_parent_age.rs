@@ -0,0 +1,225 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reject imported blocks built on very old relay parents.
+//!
+//! Symmetric to the collator not building on stale relay parents (see [`crate::relay_resync`]),
+//! the import queue can refuse blocks whose relay parent lags more than a maximum number of relay
+//! blocks behind the relay chain's best block, as a defense against replays of very old
+//! candidates. The relay parent is taken from the parachain inherent, the first extrinsic of every
+//! block.
+//!
+//! Only blocks announced to us are checked: blocks imported during the initial sync are historical
+//! by nature, and neither own blocks nor blocks imported from a file are subject to the check.
+//! Blocks are never rejected for our own relay chain connection problems.
+
+use codec::{Decode, DecodeAll, Encode};
+use cumulus_primitives_core::relay_chain::BlockNumber as RelayBlockNumber;
+use cumulus_primitives_parachain_inherent::ParachainInherentData;
+use cumulus_relay_chain_interface::{RelayChainInterface, RelayChainResult};
+use parachains_common::Block;
+use sc_consensus::BlockImportParams;
+use sp_consensus::BlockOrigin;
+use sp_runtime::{generic::BlockId, OpaqueExtrinsic};
+use std::sync::{Arc, Mutex};
+
+const LOG_TARGET: &str = "relay-parent-age";
+
+/// Version byte of an unsigned extrinsic.
+const UNSIGNED_EXTRINSIC_V4: u8 = 4;
+
+/// What the check needs to know about the relay chain.
+#[async_trait::async_trait]
+pub trait RelayBest: Clone + Send + Sync + 'static {
+	/// The number of the relay chain's best block.
+	async fn best_number(&self) -> RelayChainResult<Option<RelayBlockNumber>>;
+}
+
+#[async_trait::async_trait]
+impl RelayBest for Arc<dyn RelayChainInterface> {
+	async fn best_number(&self) -> RelayChainResult<Option<RelayBlockNumber>> {
+		let best_hash = self.best_block_hash().await?;
+		Ok(self.header(BlockId::Hash(best_hash)).await?.map(|header| header.number))
+	}
+}
+
+/// The relay parent number of a block with the extrinsics `body`.
+///
+/// The parachain inherent is the first extrinsic: an unsigned call of `set_validation_data`,
+/// whichever pallet and call index it has, with the inherent data as its only argument.
+pub fn relay_parent_number(body: &[OpaqueExtrinsic]) -> Option<RelayBlockNumber> {
+	let extrinsic = body.first()?.encode();
+	let extrinsic = Vec::<u8>::decode(&mut &extrinsic[..]).ok()?;
+	match &extrinsic[..] {
+		[UNSIGNED_EXTRINSIC_V4, _pallet, _call, data @ ..] =>
+			ParachainInherentData::decode_all(&mut &data[..])
+				.ok()
+				.map(|data| data.validation_data.relay_parent_number),
+		_ => None,
+	}
+}
+
+/// Whether a relay parent is more than `max_age` relay blocks behind the relay chain's best block.
+pub fn is_too_old(
+	relay_parent_number: RelayBlockNumber,
+	best_number: RelayBlockNumber,
+	max_age: RelayBlockNumber,
+) -> bool {
+	relay_parent_number.saturating_add(max_age) < best_number
+}
+
+/// Checks the age of the relay parent of imported blocks.
+///
+/// The import queue is built before the relay chain interface, which is handed to the check with
+/// [`RelayParentAgeCheck::connect`] once it exists. Blocks imported before pass unchecked.
+#[derive(Clone)]
+pub struct RelayParentAgeCheck<R> {
+	max_age: RelayBlockNumber,
+	relay: Arc<Mutex<Option<R>>>,
+}
+
+impl<R: RelayBest> RelayParentAgeCheck<R> {
+	/// Reject blocks whose relay parent lags more than `max_age` relay blocks behind.
+	pub fn new(max_age: RelayBlockNumber) -> Self {
+		Self { max_age, relay: Default::default() }
+	}
+
+	/// Start checking against `relay`.
+	pub fn connect(&self, relay: R) {
+		*self.relay.lock().expect("never poisoned; qed") = Some(relay);
+	}
+
+	fn relay(&self) -> Option<R> {
+		self.relay.lock().expect("never poisoned; qed").clone()
+	}
+
+	/// Check the relay parent of `block`, failing if it is too old.
+	pub async fn check(&self, block: &BlockImportParams<Block, ()>) -> Result<(), String> {
+		if block.origin != BlockOrigin::NetworkBroadcast {
+			return Ok(())
+		}
+		let Some(relay) = self.relay() else { return Ok(()) };
+		let Some(relay_parent_number) = block.body.as_deref().and_then(relay_parent_number) else {
+			// Such a block fails on execution anyway.
+			return Ok(())
+		};
+
+		let best_number = match relay.best_number().await {
+			Ok(Some(best_number)) => best_number,
+			Ok(None) => return Ok(()),
+			Err(e) => {
+				log::debug!(
+					target: LOG_TARGET,
+					"Could not look up the relay chain's best block: {}",
+					e
+				);
+				return Ok(())
+			},
+		};
+		if is_too_old(relay_parent_number, best_number, self.max_age) {
+			return Err(format!(
+				"Block #{} is built on relay parent #{}, more than {} relay blocks behind the \
+				relay chain's best block #{}",
+				block.header.number, relay_parent_number, self.max_age, best_number,
+			))
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use cumulus_primitives_core::PersistedValidationData;
+	use futures::executor::block_on;
+	use parachains_common::Header;
+
+	#[derive(Clone)]
+	struct MockRelay(RelayBlockNumber);
+
+	#[async_trait::async_trait]
+	impl RelayBest for MockRelay {
+		async fn best_number(&self) -> RelayChainResult<Option<RelayBlockNumber>> {
+			Ok(Some(self.0))
+		}
+	}
+
+	fn parachain_inherent(relay_parent_number: RelayBlockNumber) -> OpaqueExtrinsic {
+		let validation_data = PersistedValidationData { relay_parent_number, ..Default::default() };
+		// `ParachainSystem::set_validation_data`. The storage proof, the downward and the
+		// horizontal messages following the validation data are all empty.
+		let extrinsic =
+			[&[UNSIGNED_EXTRINSIC_V4, 1, 0][..], &validation_data.encode(), &[0, 0, 0]].concat();
+		OpaqueExtrinsic::from_bytes(&extrinsic.encode()).unwrap()
+	}
+
+	fn block(
+		origin: BlockOrigin,
+		relay_parent_number: RelayBlockNumber,
+	) -> BlockImportParams<Block, ()> {
+		let header = Header {
+			parent_hash: Default::default(),
+			number: 7,
+			state_root: Default::default(),
+			extrinsics_root: Default::default(),
+			digest: Default::default(),
+		};
+		let mut block = BlockImportParams::new(origin, header);
+		block.body = Some(vec![parachain_inherent(relay_parent_number)]);
+		block
+	}
+
+	fn check(best_number: RelayBlockNumber) -> RelayParentAgeCheck<MockRelay> {
+		let check = RelayParentAgeCheck::new(10);
+		check.connect(MockRelay(best_number));
+		check
+	}
+
+	#[test]
+	fn relay_parent_is_read_from_the_parachain_inherent() {
+		assert_eq!(relay_parent_number(&[parachain_inherent(42)]), Some(42));
+		assert_eq!(relay_parent_number(&[]), None);
+		let signed = OpaqueExtrinsic::from_bytes(&vec![0x84, 1, 0].encode()).unwrap();
+		assert_eq!(relay_parent_number(&[signed]), None);
+	}
+
+	#[test]
+	fn recent_relay_parent_is_accepted() {
+		let block = block(BlockOrigin::NetworkBroadcast, 90);
+		assert_eq!(block_on(check(100).check(&block)), Ok(()));
+	}
+
+	#[test]
+	fn old_relay_parent_is_rejected_once_synced() {
+		let block = block(BlockOrigin::NetworkBroadcast, 89);
+		let error = block_on(check(100).check(&block)).unwrap_err();
+		assert!(error.contains("relay parent #89"), "{}", error);
+	}
+
+	#[test]
+	fn historical_blocks_import_during_initial_sync() {
+		let block = block(BlockOrigin::NetworkInitialSync, 1);
+		assert_eq!(block_on(check(100).check(&block)), Ok(()));
+	}
+
+	#[test]
+	fn blocks_pass_until_the_relay_chain_is_connected() {
+		let block = block(BlockOrigin::NetworkBroadcast, 1);
+		let check = RelayParentAgeCheck::<MockRelay>::new(10);
+		assert_eq!(block_on(check.check(&block)), Ok(()));
+	}
+}
@@ -0,0 +1,260 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Don't produce candidates on stale relay chain data after the relay chain connection dropped.
+//!
+//! The relay chain interfaces reconnect by themselves, but the collator may still be handed relay
+//! parents from before the disconnection. [`ResyncingConsensus`] checks every relay parent against
+//! the relay chain's current best block. After a long period without any relay parent, it does not
+//! trust anything until the relay chain is synced again and hands out its best block.
+//!
+//! Should the collator still get stuck on old relay parents, an operator can reset the tracking
+//! through a [`ResyncHandle`] (see the `collator_resyncRelayParent` RPC): it forgets the stale
+//! state and only accepts relay parents from the relay chain's current best block on. This is in
+//! memory only, the parachain's blocks, finalized or not, are not touched.
+
+use cumulus_client_consensus_common::{ParachainCandidate, ParachainConsensus};
+use cumulus_primitives_core::relay_chain::{Hash as PHash, PersistedValidationData};
+use cumulus_relay_chain_interface::RelayChainInterface;
+use futures::lock::Mutex;
+use parachains_common::{Block, BlockNumber, Header};
+use sp_runtime::generic::BlockId;
+use std::{
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+const LOG_TARGET: &str = "relay-resync";
+
+/// A gap between two relay parents after which we consider the relay connection to have been
+/// lost, that's ten relay blocks.
+const LONG_DISCONNECT: Duration = Duration::from_secs(60);
+
+/// Number of relay blocks a relay parent may lag behind the relay chain's best block.
+const MAX_RELAY_PARENT_LAG: BlockNumber = 2;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Decision {
+	Produce,
+	/// The relay parent lags too far behind the relay chain's best block.
+	SkipStale,
+	/// We are waiting for fresh relay chain data after a long disconnection.
+	SkipResyncing,
+}
+
+#[derive(Default)]
+struct ResyncState {
+	last_relay_parent_at: Option<Instant>,
+	resyncing: bool,
+	/// The relay chain's best block when the tracking was last reset by an operator, older relay
+	/// parents are stale.
+	reset_at: Option<BlockNumber>,
+}
+
+impl ResyncState {
+	/// Forget everything we know, accepting relay parents from `best_number` on.
+	fn reset(&mut self, best_number: BlockNumber) {
+		*self = ResyncState { reset_at: Some(best_number), ..Default::default() };
+	}
+
+	fn on_relay_parent(
+		&mut self,
+		now: Instant,
+		relay_parent_number: BlockNumber,
+		best_number: BlockNumber,
+		major_syncing: bool,
+	) -> Decision {
+		let gap = self.last_relay_parent_at.map(|at| now.saturating_duration_since(at));
+		self.last_relay_parent_at = Some(now);
+
+		if gap.map_or(false, |gap| gap > LONG_DISCONNECT) {
+			log::warn!(
+				target: LOG_TARGET,
+				"No relay parent for {:?}, resyncing with the relay chain before building blocks",
+				gap.unwrap_or_default(),
+			);
+			self.resyncing = true;
+		}
+
+		if self.resyncing {
+			if major_syncing || relay_parent_number < best_number {
+				return Decision::SkipResyncing
+			}
+			log::info!(target: LOG_TARGET, "Resynced with the relay chain at #{}", best_number);
+			self.resyncing = false;
+		}
+
+		if relay_parent_number.saturating_add(MAX_RELAY_PARENT_LAG) < best_number ||
+			self.reset_at.map_or(false, |reset_at| relay_parent_number < reset_at)
+		{
+			return Decision::SkipStale
+		}
+
+		Decision::Produce
+	}
+}
+
+/// The relay parent tracking of a [`ResyncingConsensus`], shared with the operator's controls.
+#[derive(Clone)]
+pub struct ResyncHandle {
+	relay_chain_interface: Arc<dyn RelayChainInterface>,
+	state: Arc<Mutex<ResyncState>>,
+}
+
+impl ResyncHandle {
+	/// Track the relay parents against `relay_chain_interface`.
+	pub fn new(relay_chain_interface: Arc<dyn RelayChainInterface>) -> Self {
+		Self { relay_chain_interface, state: Default::default() }
+	}
+
+	/// Reset the tracking to the relay chain's current best block, returning its number. `None`
+	/// if the relay chain is unreachable.
+	pub async fn resync_to_best(&self) -> Option<BlockNumber> {
+		let best_number = self.best_relay_number().await?;
+		self.state.lock().await.reset(best_number);
+		log::info!(
+			target: LOG_TARGET,
+			"Relay parent tracking reset by the operator, producing from relay block #{} on",
+			best_number,
+		);
+		Some(best_number)
+	}
+
+	async fn best_relay_number(&self) -> Option<BlockNumber> {
+		let best_hash = self.relay_chain_interface.best_block_hash().await.ok()?;
+		let best_header =
+			self.relay_chain_interface.header(BlockId::Hash(best_hash)).await.ok()??;
+		Some(best_header.number)
+	}
+}
+
+/// [`ParachainConsensus`] wrapper which only produces candidates on fresh relay parents.
+#[derive(Clone)]
+pub struct ResyncingConsensus {
+	inner: Box<dyn ParachainConsensus<Block>>,
+	handle: ResyncHandle,
+}
+
+impl ResyncingConsensus {
+	/// Wrap `inner`, tracking the relay parents with `handle`.
+	pub fn new(inner: Box<dyn ParachainConsensus<Block>>, handle: ResyncHandle) -> Self {
+		Self { inner, handle }
+	}
+}
+
+#[async_trait::async_trait]
+impl ParachainConsensus<Block> for ResyncingConsensus {
+	async fn produce_candidate(
+		&mut self,
+		parent: &Header,
+		relay_parent: PHash,
+		validation_data: &PersistedValidationData,
+	) -> Option<ParachainCandidate<Block>> {
+		let Some(best_number) = self.handle.best_relay_number().await else {
+			log::debug!(target: LOG_TARGET, "Relay chain unreachable, not producing a candidate");
+			return None
+		};
+		let major_syncing =
+			self.handle.relay_chain_interface.is_major_syncing().await.unwrap_or(true);
+
+		let decision = self.handle.state.lock().await.on_relay_parent(
+			Instant::now(),
+			validation_data.relay_parent_number,
+			best_number,
+			major_syncing,
+		);
+
+		match decision {
+			Decision::Produce =>
+				self.inner.produce_candidate(parent, relay_parent, validation_data).await,
+			Decision::SkipStale => {
+				log::debug!(
+					target: LOG_TARGET,
+					"Relay parent #{} is stale, relay chain is at #{}",
+					validation_data.relay_parent_number,
+					best_number,
+				);
+				None
+			},
+			Decision::SkipResyncing => None,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn fresh_relay_parents_produce() {
+		let mut state = ResyncState::default();
+		let start = Instant::now();
+		for n in 0..10 {
+			let now = start + Duration::from_secs(6 * n as u64);
+			assert_eq!(state.on_relay_parent(now, n, n, false), Decision::Produce);
+		}
+	}
+
+	#[test]
+	fn stale_relay_parent_is_skipped() {
+		let mut state = ResyncState::default();
+		assert_eq!(state.on_relay_parent(Instant::now(), 10, 12, false), Decision::Produce);
+		assert_eq!(state.on_relay_parent(Instant::now(), 10, 13, false), Decision::SkipStale);
+	}
+
+	#[test]
+	fn long_disconnect_waits_for_fresh_relay_data() {
+		let mut state = ResyncState::default();
+		let start = Instant::now();
+		assert_eq!(state.on_relay_parent(start, 10, 10, false), Decision::Produce);
+
+		// Reconnected after a long time, but the relay chain is still syncing.
+		let reconnected = start + LONG_DISCONNECT + Duration::from_secs(1);
+		assert_eq!(state.on_relay_parent(reconnected, 11, 30, true), Decision::SkipResyncing);
+		// Synced, but handed a relay parent from before the disconnection.
+		let later = reconnected + Duration::from_secs(6);
+		assert_eq!(state.on_relay_parent(later, 12, 31, false), Decision::SkipResyncing);
+		// Fresh relay parent: production resumes.
+		let later = later + Duration::from_secs(6);
+		assert_eq!(state.on_relay_parent(later, 32, 32, false), Decision::Produce);
+		let later = later + Duration::from_secs(6);
+		assert_eq!(state.on_relay_parent(later, 33, 33, false), Decision::Produce);
+	}
+
+	#[test]
+	fn reset_resumes_production_on_the_current_relay_parent() {
+		let mut state = ResyncState::default();
+		let start = Instant::now();
+		assert_eq!(state.on_relay_parent(start, 10, 10, false), Decision::Produce);
+
+		// Stuck: handed relay parents which are always behind the relay chain's best block.
+		let mut now = start + LONG_DISCONNECT + Duration::from_secs(1);
+		for n in 11..20 {
+			assert_eq!(state.on_relay_parent(now, n, n + 1, false), Decision::SkipResyncing);
+			now += Duration::from_secs(6);
+		}
+
+		state.reset(20);
+		assert!(!state.resyncing);
+		// Relay parents from before the reset are still stale.
+		assert_eq!(state.on_relay_parent(now, 19, 20, false), Decision::SkipStale);
+		now += Duration::from_secs(6);
+		assert_eq!(state.on_relay_parent(now, 20, 21, false), Decision::Produce);
+		now += Duration::from_secs(6);
+		assert_eq!(state.on_relay_parent(now, 21, 22, false), Decision::Produce);
+	}
+}
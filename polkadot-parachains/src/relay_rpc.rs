@@ -0,0 +1,177 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Startup checks of the relay chain RPC endpoints given with `--relay-chain-rpc-urls`.
+//!
+//! With RPC endpoints, the node runs a minimal relay chain node talking to an external relay
+//! chain node instead of embedding a full one, which saves a lot of resources. The minimal node
+//! gives up at once if none of the endpoints is reachable, so an endpoint restarting at the same
+//! time as the collator fails the startup. [`wait_for_endpoints`] waits for an endpoint to become
+//! reachable first, retrying with exponential backoff.
+
+use crate::candidate_retry::RetryConfig;
+use std::{collections::HashSet, future::Future, time::Duration};
+use url::Url;
+
+const LOG_TARGET: &str = "relay-rpc";
+
+/// Default number of retries if none of the endpoints is reachable.
+pub const DEFAULT_CONNECT_RETRIES: u32 = 5;
+
+/// Default backoff before the first retry of reaching the endpoints, in milliseconds.
+pub const DEFAULT_CONNECT_BACKOFF_MS: u64 = 1000;
+
+/// Timeout of a single attempt to reach an endpoint.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Check that no endpoint is given twice, which would make a failing endpoint count double.
+///
+/// That they are `ws` or `wss` URLs, and hence have a host and a port, is checked by the CLI
+/// parser already.
+pub fn validate_endpoints(urls: &[Url]) -> Result<(), String> {
+	let mut seen = HashSet::new();
+	for url in urls {
+		if !seen.insert(url) {
+			return Err(format!("Relay chain RPC endpoint {} is given more than once", url))
+		}
+	}
+	Ok(())
+}
+
+/// Whether a TCP connection to `url` can be established.
+pub async fn is_reachable(url: Url) -> bool {
+	let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+		return false
+	};
+	let connect = tokio::net::TcpStream::connect((host, port));
+	match tokio::time::timeout(CONNECT_TIMEOUT, connect).await {
+		Ok(Ok(_)) => true,
+		Ok(Err(e)) => {
+			log::debug!(target: LOG_TARGET, "Relay chain RPC endpoint {} unreachable: {}", url, e);
+			false
+		},
+		Err(_) => {
+			log::debug!(target: LOG_TARGET, "Relay chain RPC endpoint {} timed out", url);
+			false
+		},
+	}
+}
+
+/// Wait until `probe` reaches any of the endpoints `urls`, retrying as configured.
+pub async fn wait_for_endpoints<F, Fut>(
+	urls: &[Url],
+	config: RetryConfig,
+	mut probe: F,
+) -> Result<(), String>
+where
+	F: FnMut(Url) -> Fut,
+	Fut: Future<Output = bool>,
+{
+	let mut retries = 0;
+	loop {
+		for url in urls {
+			if probe(url.clone()).await {
+				log::debug!(target: LOG_TARGET, "Relay chain RPC endpoint {} is reachable", url);
+				return Ok(())
+			}
+		}
+		if retries >= config.max_retries {
+			return Err(format!(
+				"None of the relay chain RPC endpoints is reachable after {} retries",
+				retries,
+			))
+		}
+
+		let backoff = config.backoff(retries);
+		log::warn!(
+			target: LOG_TARGET,
+			"None of the relay chain RPC endpoints is reachable, retrying in {:?} ({}/{})",
+			backoff,
+			retries + 1,
+			config.max_retries,
+		);
+		futures_timer::Delay::new(backoff).await;
+		retries += 1;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::executor::block_on;
+	use std::cell::Cell;
+
+	fn urls(urls: &[&str]) -> Vec<Url> {
+		urls.iter().map(|url| Url::parse(url).unwrap()).collect()
+	}
+
+	fn config(max_retries: u32) -> RetryConfig {
+		RetryConfig { max_retries, initial_backoff: Duration::from_millis(1) }
+	}
+
+	#[test]
+	fn distinct_endpoints_are_valid() {
+		let urls = urls(&["ws://127.0.0.1:9944", "wss://rpc.example.com"]);
+		assert_eq!(validate_endpoints(&urls), Ok(()));
+	}
+
+	#[test]
+	fn duplicate_endpoints_are_rejected() {
+		let urls = urls(&["ws://127.0.0.1:9944", "ws://127.0.0.1:9944"]);
+		assert!(validate_endpoints(&urls).is_err());
+	}
+
+	#[test]
+	fn any_reachable_endpoint_suffices() {
+		let urls = urls(&["ws://127.0.0.1:9944", "ws://127.0.0.1:9945"]);
+		let probes = Cell::new(0);
+
+		let result = block_on(wait_for_endpoints(&urls, config(2), |url| {
+			probes.set(probes.get() + 1);
+			async move { url.port() == Some(9945) }
+		}));
+		assert_eq!(result, Ok(()));
+		assert_eq!(probes.get(), 2);
+	}
+
+	#[test]
+	fn unreachable_endpoints_are_retried() {
+		let urls = urls(&["ws://127.0.0.1:9944"]);
+		let probes = Cell::new(0);
+
+		let result = block_on(wait_for_endpoints(&urls, config(2), |_| {
+			probes.set(probes.get() + 1);
+			let reachable = probes.get() > 2;
+			async move { reachable }
+		}));
+		assert_eq!(result, Ok(()));
+		assert_eq!(probes.get(), 3);
+	}
+
+	#[test]
+	fn retries_are_bounded() {
+		let urls = urls(&["ws://127.0.0.1:9944", "ws://127.0.0.1:9945"]);
+		let probes = Cell::new(0);
+
+		let result = block_on(wait_for_endpoints(&urls, config(2), |_| {
+			probes.set(probes.get() + 1);
+			async { false }
+		}));
+		assert!(result.is_err());
+		assert_eq!(probes.get(), 6);
+	}
+}
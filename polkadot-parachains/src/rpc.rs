@@ -20,8 +20,21 @@
 
 use std::sync::Arc;
 
-use parachains_common::{AccountId, Balance, Block, Index as Nonce};
+pub mod collator;
+pub mod parachain_xcm;
+pub mod runtime_mode;
+pub mod xcm_barrier;
+pub mod xcm_currencies;
+pub mod xcm_dry_run;
+pub mod xcm_events;
+pub mod xcm_fee;
+pub mod xcm_transact;
+pub mod xcm_trapped_assets;
+pub mod xcmp_queue;
+
+use parachains_common::{AccountId, Balance, Block, Hash, Index as Nonce};
 use sc_client_api::AuxStore;
+use sc_consensus_manual_seal::EngineCommand;
 pub use sc_rpc::{DenyUnsafe, SubscriptionTaskExecutor};
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
@@ -39,6 +52,12 @@ pub struct FullDeps<C, P> {
 	pub pool: Arc<P>,
 	/// Whether to deny unsafe calls
 	pub deny_unsafe: DenyUnsafe,
+	/// Executor for the subscription tasks.
+	pub subscription_executor: SubscriptionTaskExecutor,
+	/// The relay parent tracking of the collator, `None` if the node doesn't collate.
+	pub relay_resync: Option<crate::relay_resync::ResyncHandle>,
+	/// The command sink of the development node's manual seal, `None` for regular nodes.
+	pub manual_seal: Option<futures::channel::mpsc::Sender<EngineCommand<Hash>>>,
 }
 
 /// Instantiate all RPC extensions.
@@ -50,27 +69,70 @@ where
 	C: ProvideRuntimeApi<Block>
 		+ HeaderBackend<Block>
 		+ AuxStore
+		+ sc_client_api::BlockchainEvents<Block>
 		+ HeaderMetadata<Block, Error = BlockChainError>
+		+ sp_api::CallApiAt<Block>
 		+ Send
 		+ Sync
 		+ 'static,
 	C::Api: frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: BlockBuilder<Block>,
+	C::Api: parachains_common::runtime_api::XcmDeliveryFeeApi<Block>,
+	C::Api: parachains_common::runtime_api::XtokensFeeApi<Block>,
+	C::Api: parachains_common::runtime_api::XcmWeightApi<Block>,
+	C::Api: parachains_common::runtime_api::XcmEventsApi<Block>,
+	C::Api: parachains_common::runtime_api::CurrencyRegistryApi<Block>,
+	C::Api: parachains_common::runtime_api::TransactDryRunApi<Block>,
+	C::Api: parachains_common::runtime_api::BarrierDryRunApi<Block>,
+	C::Api: parachains_common::runtime_api::XcmDryRunApi<Block>,
+	C::Api: parachains_common::runtime_api::TrappedAssetsApi<Block>,
+	C::Api: parachains_common::runtime_api::XcmpQueueApi<Block>,
+	C::Api: parachains_common::runtime_api::XcmLocationApi<Block>,
+	C::Api: parachains_common::runtime_api::AssetMetadataApi<Block>,
 	P: TransactionPool + Sync + Send + 'static,
 	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
 	B::State: sc_client_api::backend::StateBackend<sp_runtime::traits::HashFor<Block>>,
 {
+	use collator::{Collator, CollatorApiServer};
 	use frame_rpc_system::{System, SystemApiServer};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
+	use parachain_xcm::{ParachainXcm, ParachainXcmApiServer};
+	use runtime_mode::{ParachainRuntimeMode, RuntimeModeApiServer};
+	use sc_consensus_manual_seal::rpc::{ManualSeal, ManualSealApiServer};
 	use substrate_state_trie_migration_rpc::{StateMigration, StateMigrationApiServer};
+	use xcm_barrier::{XcmBarrier, XcmBarrierApiServer};
+	use xcm_currencies::{XcmCurrencies, XcmCurrenciesApiServer};
+	use xcm_dry_run::{XcmDryRun, XcmDryRunApiServer};
+	use xcm_events::{XcmEvents, XcmEventsApiServer};
+	use xcm_fee::{XcmFee, XcmFeeApiServer};
+	use xcm_transact::{XcmTransact, XcmTransactApiServer};
+	use xcm_trapped_assets::{XcmTrappedAssets, XcmTrappedAssetsApiServer};
+	use xcmp_queue::{XcmpQueue, XcmpQueueApiServer};
 
 	let mut module = RpcExtension::new(());
-	let FullDeps { client, pool, deny_unsafe } = deps;
+	let FullDeps { client, pool, deny_unsafe, subscription_executor, relay_resync, manual_seal } =
+		deps;
 
 	module.merge(System::new(client.clone(), pool, deny_unsafe).into_rpc())?;
 	module.merge(TransactionPayment::new(client.clone()).into_rpc())?;
 	module.merge(StateMigration::new(client.clone(), backend, deny_unsafe).into_rpc())?;
+	module.merge(XcmFee::new(client.clone()).into_rpc())?;
+	module.merge(XcmCurrencies::new(client.clone()).into_rpc())?;
+	module.merge(XcmTransact::new(client.clone()).into_rpc())?;
+	module.merge(XcmBarrier::new(client.clone()).into_rpc())?;
+	module.merge(XcmDryRun::new(client.clone()).into_rpc())?;
+	module.merge(XcmTrappedAssets::new(client.clone()).into_rpc())?;
+	module.merge(XcmpQueue::new(client.clone()).into_rpc())?;
+	module.merge(ParachainXcm::new(client.clone()).into_rpc())?;
+	module.merge(ParachainRuntimeMode::new(client.clone()).into_rpc())?;
+	module.merge(XcmEvents::new(client, subscription_executor).into_rpc())?;
+	if let Some(relay_resync) = relay_resync {
+		module.merge(Collator::new(relay_resync, deny_unsafe).into_rpc())?;
+	}
+	if let Some(manual_seal) = manual_seal {
+		module.merge(ManualSeal::new(manual_seal).into_rpc())?;
+	}
 
 	Ok(module)
 }
@@ -0,0 +1,71 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Operator RPC to recover a collator stuck on stale relay parents, see
+//! [`crate::relay_resync`].
+
+use crate::relay_resync::ResyncHandle;
+use jsonrpsee::{
+	core::{async_trait, RpcResult},
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use polkadot_primitives::BlockNumber as RelayBlockNumber;
+use sc_rpc::DenyUnsafe;
+
+/// The relay chain could not be queried.
+const RELAY_CHAIN_ERROR: i32 = 1;
+
+/// Collator RPC methods.
+#[rpc(client, server)]
+pub trait CollatorApi {
+	/// Reset the relay parent tracking of the collator to the relay chain's current best block,
+	/// returning its number. Relay parents older than it are not built on anymore.
+	///
+	/// Only the collator's in-memory tracking is reset, the parachain's blocks are not touched.
+	#[method(name = "collator_resyncRelayParent")]
+	async fn resync_relay_parent(&self) -> RpcResult<RelayBlockNumber>;
+}
+
+/// Implements [`CollatorApiServer`] on top of a [`ResyncHandle`].
+pub struct Collator {
+	resync: ResyncHandle,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl Collator {
+	/// Create a new instance.
+	pub fn new(resync: ResyncHandle, deny_unsafe: DenyUnsafe) -> Self {
+		Self { resync, deny_unsafe }
+	}
+}
+
+#[async_trait]
+impl CollatorApiServer for Collator {
+	async fn resync_relay_parent(&self) -> RpcResult<RelayBlockNumber> {
+		self.deny_unsafe.check_if_safe()?;
+
+		self.resync.resync_to_best().await.ok_or_else(|| {
+			CallError::Custom(ErrorObject::owned(
+				RELAY_CHAIN_ERROR,
+				"Unable to query the relay chain's best block.",
+				None::<()>,
+			))
+			.into()
+		})
+	}
+}
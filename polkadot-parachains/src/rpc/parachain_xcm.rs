@@ -0,0 +1,187 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC to discover the locations of our currencies, see [`XcmLocationApi`], and to describe
+//! assets by their metadata, see [`AssetMetadataApi`].
+//!
+//! Wallets need the location of TEER to build `orml_xtokens` transfers, which they'd otherwise
+//! have to hardcode, and its symbol and decimals to display amounts.
+
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use parachains_common::{
+	runtime_api::{AssetMetadataApi, XcmLocationApi},
+	xcm_config::{CurrencyId, CurrencyMetadata},
+	Block, Hash,
+};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use std::sync::Arc;
+use xcm::VersionedMultiLocation;
+
+/// The runtime could not provide the location.
+const RUNTIME_ERROR: i32 = 1;
+
+/// XCM location RPC methods.
+#[rpc(client, server)]
+pub trait ParachainXcmApi {
+	/// The location of TEER, relative to this chain.
+	#[method(name = "xcm_selfReserveLocation")]
+	fn self_reserve_location(&self, at: Option<Hash>) -> RpcResult<VersionedMultiLocation>;
+
+	/// The location of `currency` relative to this chain, `None` if it has no location.
+	#[method(name = "xcm_currencyToLocation")]
+	fn currency_to_location(
+		&self,
+		currency: CurrencyId,
+		at: Option<Hash>,
+	) -> RpcResult<Option<VersionedMultiLocation>>;
+
+	/// The symbol and decimals of the asset at `location`, relative to this chain. Assets which
+	/// are no currency we know are marked as having no metadata.
+	#[method(name = "xcm_describeAsset")]
+	fn describe_asset(
+		&self,
+		location: VersionedMultiLocation,
+		at: Option<Hash>,
+	) -> RpcResult<AssetDescription>;
+}
+
+/// The metadata of an asset, as far as we know it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AssetMetadata {
+	/// The asset is one of our currencies.
+	Known {
+		/// Its token symbol.
+		symbol: String,
+		/// The number of decimals of its amounts.
+		decimals: u8,
+	},
+	/// The asset is no currency we know, it can only be displayed by its location.
+	NoMetadata,
+}
+
+/// An asset, described for wallets.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetDescription {
+	/// The location of the asset, as requested.
+	pub location: VersionedMultiLocation,
+	/// The metadata of the asset.
+	pub metadata: AssetMetadata,
+}
+
+impl AssetDescription {
+	fn new(location: VersionedMultiLocation, metadata: Option<CurrencyMetadata>) -> Self {
+		let metadata = match metadata {
+			Some(CurrencyMetadata { symbol, decimals }) => AssetMetadata::Known {
+				symbol: String::from_utf8_lossy(&symbol).into_owned(),
+				decimals,
+			},
+			None => AssetMetadata::NoMetadata,
+		};
+		Self { location, metadata }
+	}
+}
+
+/// Implements [`ParachainXcmApiServer`] on top of the [`XcmLocationApi`].
+pub struct ParachainXcm<C> {
+	client: Arc<C>,
+}
+
+impl<C> ParachainXcm<C> {
+	/// Create a new instance.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+fn runtime_error(e: sp_api::ApiError) -> CallError {
+	CallError::Custom(ErrorObject::owned(
+		RUNTIME_ERROR,
+		"Unable to query the location.",
+		Some(e.to_string()),
+	))
+}
+
+impl<C> ParachainXcmApiServer for ParachainXcm<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: XcmLocationApi<Block> + AssetMetadataApi<Block>,
+{
+	fn self_reserve_location(&self, at: Option<Hash>) -> RpcResult<VersionedMultiLocation> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		Ok(self.client.runtime_api().self_reserve_location(at).map_err(runtime_error)?)
+	}
+
+	fn currency_to_location(
+		&self,
+		currency: CurrencyId,
+		at: Option<Hash>,
+	) -> RpcResult<Option<VersionedMultiLocation>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		Ok(self
+			.client
+			.runtime_api()
+			.currency_location(at, currency)
+			.map_err(runtime_error)?)
+	}
+
+	fn describe_asset(
+		&self,
+		location: VersionedMultiLocation,
+		at: Option<Hash>,
+	) -> RpcResult<AssetDescription> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let metadata = self
+			.client
+			.runtime_api()
+			.asset_metadata(at, location.clone())
+			.map_err(runtime_error)?;
+		Ok(AssetDescription::new(location, metadata))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+	use xcm::latest::prelude::*;
+
+	#[test]
+	fn currencies_are_described_by_symbol_and_decimals() {
+		let teer = MultiLocation::new(0, X1(GeneralKey { length: 4, data: [0; 32] }));
+		let description = AssetDescription::new(teer.into(), Some(CurrencyId::TEER.metadata()));
+		assert_eq!(
+			serde_json::to_value(description).unwrap()["metadata"],
+			json!({ "known": { "symbol": "TEER", "decimals": 12 } })
+		);
+	}
+
+	#[test]
+	fn unknown_assets_are_marked() {
+		let unknown = MultiLocation::new(1, X1(Parachain(1000)));
+		let description = AssetDescription::new(unknown.into(), None);
+		let json = serde_json::to_value(&description).unwrap();
+		assert_eq!(json["metadata"], json!("noMetadata"));
+		assert_eq!(serde_json::from_value::<AssetDescription>(json).unwrap(), description);
+	}
+}
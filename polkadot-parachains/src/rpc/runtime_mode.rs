@@ -0,0 +1,361 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC telling whether the chain still runs the shell runtime, for orchestrating the upgrade to
+//! Aura.
+//!
+//! Both runtimes share their spec name and implement the `AuraApi`, the shell runtime is told
+//! apart by its implementation name. Runtimes without the `AuraApi`, like the shell runtime the
+//! chain may have started with, count as the shell runtime as well. Once upgraded, the RPC also
+//! reports the block which enacted the upgrade, found by bisecting the runtimes of the ancestors of
+//! the queried block.
+
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use parachains_common::{AuraId, Block, BlockNumber, Hash};
+use serde::{Deserialize, Serialize};
+use sp_api::{CallApiAt, RuntimeApiInfo, RuntimeVersion};
+use sp_blockchain::HeaderBackend;
+use sp_consensus_aura::AuraApi;
+use std::sync::Arc;
+
+/// The spec name of both the shell and the full runtime.
+const SPEC_NAME: &str = "integritee-parachain";
+/// The implementation name of the shell runtime.
+const SHELL_IMPL_NAME: &str = "integritee-shell";
+
+/// The runtime could not be queried.
+const RUNTIME_ERROR: i32 = 1;
+/// The block is unknown.
+const UNKNOWN_BLOCK: i32 = 2;
+
+/// The consensus the runtime of a block is built for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuntimeMode {
+	/// The shell runtime, or a runtime without the `AuraApi`.
+	Shell,
+	/// A runtime authored with Aura, other than the shell runtime.
+	Aura,
+}
+
+impl RuntimeMode {
+	/// The mode of the runtime of `version`.
+	pub fn of(version: &RuntimeVersion) -> Self {
+		let is_shell = version.spec_name == SPEC_NAME && version.impl_name == SHELL_IMPL_NAME;
+		let aura_api = <dyn AuraApi<Block, AuraId> as RuntimeApiInfo>::ID;
+		if is_shell || !version.has_api_with(&aura_api, |_| true) {
+			Self::Shell
+		} else {
+			Self::Aura
+		}
+	}
+}
+
+/// The runtime of a block.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeModeInfo {
+	/// Whether the runtime has been upgraded to Aura.
+	pub mode: RuntimeMode,
+	/// The spec name of the runtime.
+	pub spec_name: String,
+	/// The implementation name of the runtime, which tells the runtimes apart.
+	pub impl_name: String,
+	/// The spec version of the runtime.
+	pub spec_version: u32,
+	/// The block which enacted the upgrade to Aura, `0` if the chain started with Aura.
+	///
+	/// `None` while running the shell runtime, or if the state of the blocks around the upgrade
+	/// has been pruned.
+	pub transition_block: Option<BlockNumber>,
+}
+
+/// Parachain runtime RPC methods.
+#[rpc(client, server)]
+pub trait RuntimeModeApi {
+	/// Whether the runtime at block `at`, the best block by default, is the shell runtime or has
+	/// been upgraded to Aura.
+	#[method(name = "parachain_runtimeMode")]
+	fn runtime_mode(&self, at: Option<Hash>) -> RpcResult<RuntimeModeInfo>;
+}
+
+/// The runtimes of the blocks of a chain.
+pub trait Runtimes {
+	/// The hash of the block at height `number` among the ancestors of block `at`, `at` included.
+	fn ancestor(&self, at: Hash, number: BlockNumber) -> Option<Hash>;
+
+	/// The mode of the runtime in the state of block `hash`, `None` if that can't be told, e.g.
+	/// because the state has been pruned.
+	fn mode(&self, hash: Hash) -> Option<RuntimeMode>;
+}
+
+impl<C> Runtimes for C
+where
+	C: CallApiAt<Block> + HeaderBackend<Block>,
+{
+	fn ancestor(&self, mut at: Hash, number: BlockNumber) -> Option<Hash> {
+		let mut header = self.header(at).ok()??;
+		while header.number > number {
+			// Below a block of the canonical chain, all ancestors are canonical.
+			if self.hash(header.number).ok()? == Some(at) {
+				return self.hash(number).ok()?
+			}
+			at = header.parent_hash;
+			header = self.header(at).ok()??;
+		}
+		(header.number == number).then_some(at)
+	}
+
+	fn mode(&self, hash: Hash) -> Option<RuntimeMode> {
+		self.runtime_version_at(hash).ok().as_ref().map(RuntimeMode::of)
+	}
+}
+
+/// The mode of the runtime at block `at` of height `number`, and the block which enacted the
+/// upgrade to Aura, see [`RuntimeModeInfo::transition_block`]. `None` if the runtime at `at` can't
+/// be queried.
+pub fn mode_at(
+	runtimes: &impl Runtimes,
+	at: Hash,
+	number: BlockNumber,
+) -> Option<(RuntimeMode, Option<BlockNumber>)> {
+	match runtimes.mode(at)? {
+		RuntimeMode::Aura => Some((RuntimeMode::Aura, transition_block(runtimes, at, number))),
+		RuntimeMode::Shell => Some((RuntimeMode::Shell, None)),
+	}
+}
+
+/// The first ancestor of block `at` of height `number` whose runtime is authored with Aura, given
+/// that the runtime at `at` is.
+///
+/// The shell runtime is only ever upgraded to Aura, never back, so the ancestors are bisected.
+pub fn transition_block(
+	runtimes: &impl Runtimes,
+	at: Hash,
+	number: BlockNumber,
+) -> Option<BlockNumber> {
+	let (mut shell, mut aura) = (0, number);
+	while shell < aura {
+		let middle = shell + (aura - shell) / 2;
+		if runtimes.mode(runtimes.ancestor(at, middle)?)? == RuntimeMode::Aura {
+			aura = middle;
+		} else {
+			shell = middle + 1;
+		}
+	}
+	Some(aura)
+}
+
+/// Implements [`RuntimeModeApiServer`] on top of the client.
+pub struct ParachainRuntimeMode<C> {
+	client: Arc<C>,
+}
+
+impl<C> ParachainRuntimeMode<C> {
+	/// Create a new instance.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+fn runtime_error(e: sp_api::ApiError) -> CallError {
+	CallError::Custom(ErrorObject::owned(
+		RUNTIME_ERROR,
+		"Unable to query the runtime.",
+		Some(e.to_string()),
+	))
+}
+
+impl<C> RuntimeModeApiServer for ParachainRuntimeMode<C>
+where
+	C: CallApiAt<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+{
+	fn runtime_mode(&self, at: Option<Hash>) -> RpcResult<RuntimeModeInfo> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let number = self.client.number(at).ok().flatten().ok_or_else(|| {
+			CallError::Custom(ErrorObject::owned(
+				UNKNOWN_BLOCK,
+				"Unknown block.",
+				Some(format!("{:?}", at)),
+			))
+		})?;
+
+		let version = self.client.runtime_version_at(at).map_err(runtime_error)?;
+		let (mode, transition_block) = mode_at(&*self.client, at, number).ok_or_else(|| {
+			CallError::Custom(ErrorObject::owned(
+				RUNTIME_ERROR,
+				"Unable to query the runtime.",
+				Some("The runtime's APIs are unavailable."),
+			))
+		})?;
+
+		Ok(RuntimeModeInfo {
+			mode,
+			spec_name: version.spec_name.to_string(),
+			impl_name: version.impl_name.to_string(),
+			spec_version: version.spec_version,
+			transition_block,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::borrow::Cow;
+
+	/// A chain whose runtime is upgraded to Aura at block `upgrade`, with the state of the blocks
+	/// before `pruned` gone. A fork branches off after block `fork_at`, upgraded at `fork_upgrade`.
+	///
+	/// The hash of a block is its number, plus `FORK` for the blocks of the fork.
+	struct Chain {
+		upgrade: BlockNumber,
+		pruned: BlockNumber,
+		fork_at: BlockNumber,
+		fork_upgrade: BlockNumber,
+	}
+
+	const FORK: u64 = 1 << 32;
+
+	fn block(number: BlockNumber) -> Hash {
+		Hash::from_low_u64_be(number.into())
+	}
+
+	fn fork_block(number: BlockNumber) -> Hash {
+		Hash::from_low_u64_be(FORK + u64::from(number))
+	}
+
+	impl Chain {
+		fn new(upgrade: BlockNumber, pruned: BlockNumber) -> Self {
+			Self { upgrade, pruned, fork_at: BlockNumber::MAX, fork_upgrade: BlockNumber::MAX }
+		}
+
+		/// The number of block `hash`, and whether it is on the fork.
+		fn number(hash: Hash) -> (BlockNumber, bool) {
+			let id = hash.to_low_u64_be();
+			((id % FORK) as BlockNumber, id >= FORK)
+		}
+	}
+
+	impl Runtimes for Chain {
+		fn ancestor(&self, at: Hash, number: BlockNumber) -> Option<Hash> {
+			let (at_number, on_fork) = Self::number(at);
+			(number <= at_number).then(|| {
+				if on_fork && number > self.fork_at {
+					fork_block(number)
+				} else {
+					block(number)
+				}
+			})
+		}
+
+		fn mode(&self, hash: Hash) -> Option<RuntimeMode> {
+			let (number, on_fork) = Self::number(hash);
+			let upgrade = if on_fork { self.fork_upgrade } else { self.upgrade };
+			let mode = if number >= upgrade { RuntimeMode::Aura } else { RuntimeMode::Shell };
+			(number >= self.pruned).then_some(mode)
+		}
+	}
+
+	#[test]
+	fn shell_before_and_aura_after_the_upgrade() {
+		let chain = Chain::new(1234, 0);
+		assert_eq!(mode_at(&chain, block(0), 0), Some((RuntimeMode::Shell, None)));
+		assert_eq!(mode_at(&chain, block(1233), 1233), Some((RuntimeMode::Shell, None)));
+		assert_eq!(mode_at(&chain, block(1234), 1234), Some((RuntimeMode::Aura, Some(1234))));
+		assert_eq!(mode_at(&chain, block(5000), 5000), Some((RuntimeMode::Aura, Some(1234))));
+	}
+
+	#[test]
+	fn finds_the_block_which_enacted_the_upgrade() {
+		let chain = Chain::new(1234, 0);
+		for at in [1234, 1235, 5000] {
+			assert_eq!(transition_block(&chain, block(at), at), Some(1234));
+		}
+	}
+
+	#[test]
+	fn finds_the_upgrade_among_the_ancestors_of_the_queried_block() {
+		let chain = Chain { upgrade: 1234, pruned: 0, fork_at: 1000, fork_upgrade: 1100 };
+		assert_eq!(transition_block(&chain, fork_block(1500), 1500), Some(1100));
+		assert_eq!(transition_block(&chain, block(1500), 1500), Some(1234));
+		assert_eq!(mode_at(&chain, fork_block(1150), 1150), Some((RuntimeMode::Aura, Some(1100))));
+		assert_eq!(mode_at(&chain, block(1150), 1150), Some((RuntimeMode::Shell, None)));
+	}
+
+	#[test]
+	fn chain_started_with_aura() {
+		let chain = Chain::new(0, 0);
+		assert_eq!(transition_block(&chain, block(0), 0), Some(0));
+		assert_eq!(transition_block(&chain, block(100), 100), Some(0));
+	}
+
+	#[test]
+	fn pruned_upgrade_is_unknown() {
+		let chain = Chain::new(1234, 2000);
+		assert_eq!(mode_at(&chain, block(5000), 5000), Some((RuntimeMode::Aura, None)));
+		assert_eq!(mode_at(&chain, block(1000), 1000), None);
+	}
+
+	fn version(impl_name: &'static str, aura_api: bool) -> RuntimeVersion {
+		let apis = aura_api
+			.then(|| (<dyn AuraApi<Block, AuraId> as RuntimeApiInfo>::ID, 1))
+			.into_iter()
+			.collect::<Vec<_>>();
+		RuntimeVersion {
+			spec_name: SPEC_NAME.into(),
+			impl_name: impl_name.into(),
+			apis: Cow::Owned(apis),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn shell_runtime_is_told_apart_by_its_version() {
+		assert_eq!(RuntimeMode::of(&version(SHELL_IMPL_NAME, true)), RuntimeMode::Shell);
+		assert_eq!(RuntimeMode::of(&version("integritee-full", true)), RuntimeMode::Aura);
+	}
+
+	#[test]
+	fn runtimes_without_aura_are_shell_runtimes() {
+		assert_eq!(RuntimeMode::of(&version("integritee-full", false)), RuntimeMode::Shell);
+	}
+
+	#[test]
+	fn mode_is_reported_in_camel_case() {
+		let info = RuntimeModeInfo {
+			mode: RuntimeMode::Aura,
+			spec_name: "integritee-parachain".into(),
+			impl_name: "integritee-full".into(),
+			spec_version: 42,
+			transition_block: Some(1234),
+		};
+		assert_eq!(
+			serde_json::to_value(info).unwrap(),
+			serde_json::json!({
+				"mode": "Aura",
+				"specName": "integritee-parachain",
+				"implName": "integritee-full",
+				"specVersion": 42,
+				"transitionBlock": 1234,
+			})
+		);
+	}
+}
@@ -0,0 +1,120 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC to check whether our XCM `Barrier` would admit a message, see [`BarrierDryRunApi`].
+
+use codec::Decode;
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use parachains_common::{runtime_api::BarrierDryRunApi, xcm_config::BarrierRejection, Block, Hash};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use std::sync::Arc;
+use xcm::VersionedMultiLocation;
+
+/// The runtime could not dry-run the barrier.
+const RUNTIME_ERROR: i32 = 1;
+/// The origin could not be decoded.
+const DECODE_ERROR: i32 = 2;
+/// The barrier would not admit the message.
+const BARRIER_REJECTED: i32 = 3;
+
+/// XCM barrier RPC methods.
+#[rpc(client, server)]
+pub trait XcmBarrierApi {
+	/// Check whether the `Barrier` would admit the SCALE encoded `VersionedXcm` `message` from
+	/// the SCALE encoded `VersionedMultiLocation` `origin`. Nothing is executed.
+	///
+	/// Fails with the rule which rejected the message if it would not be admitted.
+	#[method(name = "xcm_dryRunBarrier")]
+	fn dry_run_barrier(&self, message: Bytes, origin: Bytes, at: Option<Hash>) -> RpcResult<()>;
+}
+
+/// Implements [`XcmBarrierApiServer`] on top of the [`BarrierDryRunApi`].
+pub struct XcmBarrier<C> {
+	client: Arc<C>,
+}
+
+impl<C> XcmBarrier<C> {
+	/// Create a new instance.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+/// A human readable description of why the barrier would not admit a message.
+fn describe(rejection: &BarrierRejection) -> String {
+	match rejection {
+		BarrierRejection::UndecodableMessage =>
+			"the message is no VersionedXcm of a version we support".into(),
+		BarrierRejection::BadOrigin => "the origin is of an XCM version we don't support".into(),
+		BarrierRejection::Unweighable =>
+			"the message can't be weighed, e.g. it has too many instructions".into(),
+		BarrierRejection::Denied { rule, error } =>
+			format!("denied by {}: {:?}", String::from_utf8_lossy(rule), error),
+		BarrierRejection::NotAllowed { rejections } => format!(
+			"not allowed by any rule: {}",
+			rejections
+				.iter()
+				.map(|(rule, error)| format!("{}: {:?}", String::from_utf8_lossy(rule), error))
+				.collect::<Vec<_>>()
+				.join(", ")
+		),
+	}
+}
+
+impl<C> XcmBarrierApiServer for XcmBarrier<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: BarrierDryRunApi<Block>,
+{
+	fn dry_run_barrier(&self, message: Bytes, origin: Bytes, at: Option<Hash>) -> RpcResult<()> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let origin = VersionedMultiLocation::decode(&mut &*origin).map_err(|e| {
+			CallError::Custom(ErrorObject::owned(
+				DECODE_ERROR,
+				"Unable to decode the origin.",
+				Some(e.to_string()),
+			))
+		})?;
+
+		let result = self
+			.client
+			.runtime_api()
+			.dry_run_barrier(at, message.to_vec(), origin)
+			.map_err(|e| {
+				CallError::Custom(ErrorObject::owned(
+					RUNTIME_ERROR,
+					"Unable to dry-run the barrier.",
+					Some(e.to_string()),
+				))
+			})?;
+
+		result.map_err(|rejection| {
+			CallError::Custom(ErrorObject::owned(
+				BARRIER_REJECTED,
+				"The barrier would not admit the message.",
+				Some(describe(&rejection)),
+			))
+			.into()
+		})
+	}
+}
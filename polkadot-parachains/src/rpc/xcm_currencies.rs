@@ -0,0 +1,118 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC to resolve asset locations to currencies in batches, see [`CurrencyRegistryApi`].
+
+use codec::Decode;
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use parachains_common::{
+	runtime_api::CurrencyRegistryApi,
+	xcm_config::{CurrencyId, MAX_CURRENCY_BATCH},
+	Block, Hash,
+};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use std::sync::Arc;
+use xcm::VersionedMultiLocation;
+
+/// The runtime could not resolve the locations.
+const RUNTIME_ERROR: i32 = 1;
+/// A location could not be decoded.
+const DECODE_ERROR: i32 = 2;
+/// The batch exceeds [`MAX_CURRENCY_BATCH`].
+const BATCH_TOO_LARGE: i32 = 3;
+
+/// XCM currency RPC methods.
+#[rpc(client, server)]
+pub trait XcmCurrenciesApi {
+	/// The currency at each of the SCALE encoded `VersionedMultiLocation`s `locations`, in order.
+	/// `None` for locations which are no known currency.
+	///
+	/// At most [`MAX_CURRENCY_BATCH`] locations are resolved at once.
+	#[method(name = "xcm_batchResolveCurrencies")]
+	fn batch_resolve_currencies(
+		&self,
+		locations: Vec<Bytes>,
+		at: Option<Hash>,
+	) -> RpcResult<Vec<Option<CurrencyId>>>;
+}
+
+/// Implements [`XcmCurrenciesApiServer`] on top of the [`CurrencyRegistryApi`].
+pub struct XcmCurrencies<C> {
+	client: Arc<C>,
+}
+
+impl<C> XcmCurrencies<C> {
+	/// Create a new instance.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+fn batch_too_large() -> CallError {
+	CallError::Custom(ErrorObject::owned(
+		BATCH_TOO_LARGE,
+		"Too many locations.",
+		Some(format!("At most {} locations can be resolved at once.", MAX_CURRENCY_BATCH)),
+	))
+}
+
+impl<C> XcmCurrenciesApiServer for XcmCurrencies<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: CurrencyRegistryApi<Block>,
+{
+	fn batch_resolve_currencies(
+		&self,
+		locations: Vec<Bytes>,
+		at: Option<Hash>,
+	) -> RpcResult<Vec<Option<CurrencyId>>> {
+		// Don't bother decoding batches the runtime is going to reject anyway.
+		if locations.len() > MAX_CURRENCY_BATCH as usize {
+			return Err(batch_too_large().into())
+		}
+
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let locations = locations
+			.iter()
+			.map(|location| VersionedMultiLocation::decode(&mut &**location))
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(|e| {
+				CallError::Custom(ErrorObject::owned(
+					DECODE_ERROR,
+					"Unable to decode a location.",
+					Some(e.to_string()),
+				))
+			})?;
+
+		let currencies =
+			self.client.runtime_api().batch_resolve_currencies(at, locations).map_err(|e| {
+				CallError::Custom(ErrorObject::owned(
+					RUNTIME_ERROR,
+					"Unable to resolve the locations.",
+					Some(e.to_string()),
+				))
+			})?;
+
+		currencies.map_err(|_| batch_too_large().into())
+	}
+}
@@ -0,0 +1,206 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC to dry-run inbound XCM messages, see [`XcmDryRunRuntimeApi`].
+
+use codec::{Decode, Encode};
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use parachains_common::{
+	runtime_api::XcmDryRunApi as XcmDryRunRuntimeApi,
+	xcm_config::{XcmDryRunEffects, XcmDryRunError},
+	Block, Hash,
+};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use std::sync::Arc;
+use xcm::{
+	latest::{Outcome, Weight},
+	VersionedMultiLocation,
+};
+
+/// The runtime could not dry-run the message.
+const RUNTIME_ERROR: i32 = 1;
+/// The message or the origin could not be decoded.
+const DECODE_ERROR: i32 = 2;
+
+/// XCM dry-run RPC methods.
+#[rpc(client, server)]
+pub trait XcmDryRunApi {
+	/// What executing the SCALE encoded `VersionedXcm` `message` from the SCALE encoded
+	/// `VersionedMultiLocation` `origin` would do, like an inbound message. Nothing is committed
+	/// and no message is sent.
+	#[method(name = "xcm_dryRunXcm")]
+	fn dry_run_xcm(
+		&self,
+		message: Bytes,
+		origin: Bytes,
+		at: Option<Hash>,
+	) -> RpcResult<XcmDryRunResult>;
+}
+
+/// How far a message would be executed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DryRunOutcome {
+	/// The message would be executed completely.
+	Complete,
+	/// The execution would stop at a failing instruction.
+	Incomplete {
+		/// Why the instruction fails.
+		error: String,
+	},
+	/// The message would not be executed at all, e.g. because the barrier rejects it.
+	Error {
+		/// Why the message would not be executed.
+		error: String,
+	},
+}
+
+/// What executing a message would do.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XcmDryRunResult {
+	/// How far the message would be executed.
+	pub outcome: DryRunOutcome,
+	/// The weight the execution would consume.
+	pub weight_used: Weight,
+	/// The messages the execution would send, with their destination. The messages are SCALE
+	/// encoded `VersionedXcm`s.
+	pub forwarded: Vec<(VersionedMultiLocation, Bytes)>,
+}
+
+impl From<XcmDryRunEffects> for XcmDryRunResult {
+	fn from(effects: XcmDryRunEffects) -> Self {
+		let weight_used = effects.outcome.weight_used();
+		let outcome = match effects.outcome {
+			Outcome::Complete(_) => DryRunOutcome::Complete,
+			Outcome::Incomplete(_, error) =>
+				DryRunOutcome::Incomplete { error: format!("{:?}", error) },
+			Outcome::Error(error) => DryRunOutcome::Error { error: format!("{:?}", error) },
+		};
+		let forwarded = effects
+			.forwarded
+			.into_iter()
+			.map(|(dest, message)| (dest, Bytes(message.encode())))
+			.collect();
+		Self { outcome, weight_used, forwarded }
+	}
+}
+
+/// Implements [`XcmDryRunApiServer`] on top of the [`XcmDryRunRuntimeApi`].
+pub struct XcmDryRun<C> {
+	client: Arc<C>,
+}
+
+impl<C> XcmDryRun<C> {
+	/// Create a new instance.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+fn decode_error(what: &str, details: String) -> CallError {
+	CallError::Custom(ErrorObject::owned(
+		DECODE_ERROR,
+		format!("Unable to decode the {}.", what),
+		Some(details),
+	))
+}
+
+impl<C> XcmDryRunApiServer for XcmDryRun<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: XcmDryRunRuntimeApi<Block>,
+{
+	fn dry_run_xcm(
+		&self,
+		message: Bytes,
+		origin: Bytes,
+		at: Option<Hash>,
+	) -> RpcResult<XcmDryRunResult> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let origin = VersionedMultiLocation::decode(&mut &*origin)
+			.map_err(|e| decode_error("origin", e.to_string()))?;
+
+		let effects =
+			self.client
+				.runtime_api()
+				.dry_run_xcm(at, message.to_vec(), origin)
+				.map_err(|e| {
+					CallError::Custom(ErrorObject::owned(
+						RUNTIME_ERROR,
+						"Unable to dry-run the message.",
+						Some(e.to_string()),
+					))
+				})?;
+
+		match effects {
+			Ok(effects) => Ok(effects.into()),
+			Err(XcmDryRunError::UndecodableMessage) => Err(decode_error(
+				"message",
+				"the message is no VersionedXcm of a version we support".into(),
+			)
+			.into()),
+			Err(XcmDryRunError::BadOrigin) => Err(decode_error(
+				"origin",
+				"the origin is of an XCM version we don't support".into(),
+			)
+			.into()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+	use xcm::{latest::prelude::*, VersionedXcm};
+
+	#[test]
+	fn failed_instructions_are_reported() {
+		let result = XcmDryRunResult::from(XcmDryRunEffects {
+			outcome: Outcome::Incomplete(Weight::from_parts(10, 0), XcmError::TooExpensive),
+			forwarded: vec![],
+		});
+		assert_eq!(result.outcome, DryRunOutcome::Incomplete { error: "TooExpensive".into() });
+		assert_eq!(result.weight_used, Weight::from_parts(10, 0));
+		assert_eq!(
+			serde_json::to_value(&result).unwrap()["outcome"],
+			json!({ "incomplete": { "error": "TooExpensive" } })
+		);
+	}
+
+	#[test]
+	fn forwarded_messages_are_scale_encoded() {
+		let message = VersionedXcm::from(Xcm::<()>(vec![ClearOrigin]));
+		let result = XcmDryRunResult::from(XcmDryRunEffects {
+			outcome: Outcome::Complete(Weight::zero()),
+			forwarded: vec![(MultiLocation::parent().into(), message.clone())],
+		});
+		assert_eq!(result.outcome, DryRunOutcome::Complete);
+		assert_eq!(
+			result.forwarded,
+			vec![(MultiLocation::parent().into(), Bytes(message.encode()))]
+		);
+	}
+}
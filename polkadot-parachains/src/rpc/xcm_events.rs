@@ -0,0 +1,200 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Subscription to the XCM events of the best chain, see [`XcmEventsApi`].
+
+use futures::{future, stream, FutureExt, StreamExt};
+use jsonrpsee::{proc_macros::rpc, types::SubscriptionResult, SubscriptionSink};
+use parachains_common::{runtime_api::XcmEventsApi as XcmEventsRuntimeApi, Block, Hash};
+use sc_client_api::{BlockImportNotification, BlockchainEvents};
+use sc_rpc::SubscriptionTaskExecutor;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_core::Bytes;
+use std::sync::Arc;
+
+const LOG_TARGET: &str = "rpc::xcm-events";
+
+/// A notification of the `xcm_subscribeEvents` subscription.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum XcmEventsNotification {
+	/// A block which emitted XCM events became part of the best chain.
+	#[serde(rename_all = "camelCase")]
+	Enacted {
+		block_hash: Hash,
+		/// The SCALE encoded `RuntimeEvent`s.
+		events: Vec<Bytes>,
+	},
+	/// A reorg removed a block from the best chain, its events should be dropped.
+	#[serde(rename_all = "camelCase")]
+	Retracted { block_hash: Hash },
+}
+
+/// XCM events RPC methods.
+#[rpc(client, server)]
+pub trait XcmEventsApi {
+	/// Stream the XCM events of the blocks entering the best chain.
+	///
+	/// Blocks without XCM events are skipped. On a reorg, the blocks leaving the best chain are
+	/// announced before the events of the new best chain.
+	#[subscription(
+		name = "xcm_subscribeEvents" => "xcm_events",
+		unsubscribe = "xcm_unsubscribeEvents",
+		item = XcmEventsNotification,
+	)]
+	fn subscribe_events(&self);
+}
+
+/// Implements [`XcmEventsApiServer`] on top of the [`XcmEventsRuntimeApi`].
+pub struct XcmEvents<C> {
+	client: Arc<C>,
+	executor: SubscriptionTaskExecutor,
+}
+
+impl<C> XcmEvents<C> {
+	/// Create a new instance.
+	pub fn new(client: Arc<C>, executor: SubscriptionTaskExecutor) -> Self {
+		Self { client, executor }
+	}
+}
+
+impl<C> XcmEventsApiServer for XcmEvents<C>
+where
+	C: ProvideRuntimeApi<Block> + BlockchainEvents<Block> + Send + Sync + 'static,
+	C::Api: XcmEventsRuntimeApi<Block>,
+{
+	fn subscribe_events(&self, mut sink: SubscriptionSink) -> SubscriptionResult {
+		let client = self.client.clone();
+		let stream = self
+			.client
+			.import_notification_stream()
+			.filter(|notification| future::ready(notification.is_new_best))
+			.flat_map(move |notification| {
+				stream::iter(notifications(&notification, |hash| xcm_events(&*client, hash)))
+			});
+
+		let fut = async move {
+			sink.pipe_from_stream(stream).await;
+		};
+		self.executor.spawn("xcm-events-subscription", Some("rpc"), fut.boxed());
+		Ok(())
+	}
+}
+
+fn xcm_events<C>(client: &C, hash: Hash) -> Vec<Bytes>
+where
+	C: ProvideRuntimeApi<Block>,
+	C::Api: XcmEventsRuntimeApi<Block>,
+{
+	match client.runtime_api().xcm_events(hash) {
+		Ok(events) => events.into_iter().map(Bytes).collect(),
+		Err(e) => {
+			log::warn!(target: LOG_TARGET, "Could not read the XCM events of {:?}: {:?}", hash, e);
+			Vec::new()
+		},
+	}
+}
+
+/// The notifications for a new best block: retractions of the blocks the reorg removed, highest
+/// first, then the events of the blocks which became part of the best chain, ending with the new
+/// block.
+fn notifications(
+	notification: &BlockImportNotification<Block>,
+	events_of: impl Fn(Hash) -> Vec<Bytes>,
+) -> Vec<XcmEventsNotification> {
+	let (retracted, enacted) = match &notification.tree_route {
+		Some(route) => (
+			route.retracted().iter().map(|block| block.hash).collect(),
+			route.enacted().iter().map(|block| block.hash).collect(),
+		),
+		None => (Vec::new(), Vec::new()),
+	};
+	reorg_notifications(retracted, enacted, notification.hash, events_of)
+}
+
+fn reorg_notifications(
+	retracted: Vec<Hash>,
+	enacted: Vec<Hash>,
+	new_best: Hash,
+	events_of: impl Fn(Hash) -> Vec<Bytes>,
+) -> Vec<XcmEventsNotification> {
+	let retractions = retracted
+		.into_iter()
+		.map(|block_hash| XcmEventsNotification::Retracted { block_hash });
+	let enactments = enacted.into_iter().chain(Some(new_best)).filter_map(|block_hash| {
+		let events = events_of(block_hash);
+		(!events.is_empty()).then_some(XcmEventsNotification::Enacted { block_hash, events })
+	});
+	retractions.chain(enactments).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn hash(n: u8) -> Hash {
+		Hash::repeat_byte(n)
+	}
+
+	/// Blocks with an even first byte emitted one XCM event.
+	fn events_of(block_hash: Hash) -> Vec<Bytes> {
+		if block_hash[0] % 2 == 0 {
+			vec![Bytes(vec![block_hash[0]])]
+		} else {
+			Vec::new()
+		}
+	}
+
+	fn enacted(n: u8) -> XcmEventsNotification {
+		XcmEventsNotification::Enacted { block_hash: hash(n), events: vec![Bytes(vec![n])] }
+	}
+
+	fn retracted(n: u8) -> XcmEventsNotification {
+		XcmEventsNotification::Retracted { block_hash: hash(n) }
+	}
+
+	#[test]
+	fn new_best_block_events_are_streamed() {
+		assert_eq!(reorg_notifications(vec![], vec![], hash(2), events_of), vec![enacted(2)]);
+	}
+
+	#[test]
+	fn blocks_without_xcm_events_are_skipped() {
+		assert!(reorg_notifications(vec![], vec![], hash(3), events_of).is_empty());
+	}
+
+	#[test]
+	fn reorg_retracts_old_blocks_before_enacting_new_ones() {
+		assert_eq!(
+			reorg_notifications(
+				vec![hash(11), hash(10)],
+				vec![hash(20), hash(21)],
+				hash(22),
+				events_of,
+			),
+			vec![retracted(11), retracted(10), enacted(20), enacted(22)]
+		);
+	}
+
+	#[test]
+	fn notifications_are_tagged_in_json() {
+		let json = serde_json::to_value(retracted(1)).unwrap();
+		assert_eq!(json["type"], "retracted");
+		assert_eq!(json["blockHash"], serde_json::to_value(hash(1)).unwrap());
+	}
+}
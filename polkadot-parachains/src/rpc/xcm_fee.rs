@@ -0,0 +1,167 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC to query the XCM delivery fee, see [`XcmDeliveryFeeApi`], the fee of `orml_xtokens`
+//! transfers, see [`XtokensFeeApi`], and the weight of each XCM instruction, see
+//! [`XcmWeightApi`].
+
+use codec::Decode;
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use parachains_common::{
+	runtime_api::{
+		XcmDeliveryFeeApi as XcmDeliveryFeeRuntimeApi, XcmWeightApi as XcmWeightRuntimeApi,
+		XtokensFeeApi as XtokensFeeRuntimeApi,
+	},
+	xcm_config::CurrencyId,
+	Block, Hash,
+};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_rpc::number::NumberOrHex;
+use std::sync::Arc;
+use xcm::{latest::Weight, VersionedMultiLocation};
+
+/// The runtime could not compute the fee.
+const RUNTIME_ERROR: i32 = 1;
+/// The destination could not be decoded.
+const DECODE_ERROR: i32 = 2;
+
+/// XCM fee RPC methods.
+#[rpc(client, server)]
+pub trait XcmFeeApi {
+	/// The fee for delivering a message of `message_size` bytes to the SCALE encoded
+	/// `VersionedMultiLocation` `dest`. `None` if we don't route to `dest`.
+	#[method(name = "xcm_deliveryFee")]
+	fn delivery_fee(
+		&self,
+		dest: Bytes,
+		message_size: u32,
+		at: Option<Hash>,
+	) -> RpcResult<Option<(CurrencyId, NumberOrHex)>>;
+
+	/// The execution and delivery fee of transferring `currency` with `orml_xtokens` to the SCALE
+	/// encoded `VersionedMultiLocation` `dest`, in `currency`, and the weight of executing the
+	/// transfer at `dest`. `None` if the fee can't be estimated.
+	#[method(name = "xcm_estimateXtokensFee")]
+	fn estimate_xtokens_fee(
+		&self,
+		currency: CurrencyId,
+		dest: Bytes,
+		at: Option<Hash>,
+	) -> RpcResult<Option<(NumberOrHex, Weight)>>;
+
+	/// The weight charged for executing each XCM instruction. Multiplied by the number of
+	/// instructions of a message, this is the weight to buy for executing it here.
+	#[method(name = "xcm_instructionWeight")]
+	fn instruction_weight(&self, at: Option<Hash>) -> RpcResult<Weight>;
+}
+
+/// Implements [`XcmFeeApiServer`] on top of the [`XcmDeliveryFeeRuntimeApi`], the
+/// [`XtokensFeeRuntimeApi`] and the [`XcmWeightRuntimeApi`].
+pub struct XcmFee<C> {
+	client: Arc<C>,
+}
+
+impl<C> XcmFee<C> {
+	/// Create a new instance.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+impl<C> XcmFeeApiServer for XcmFee<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api:
+		XcmDeliveryFeeRuntimeApi<Block> + XtokensFeeRuntimeApi<Block> + XcmWeightRuntimeApi<Block>,
+{
+	fn delivery_fee(
+		&self,
+		dest: Bytes,
+		message_size: u32,
+		at: Option<Hash>,
+	) -> RpcResult<Option<(CurrencyId, NumberOrHex)>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let dest = decode_dest(&dest)?;
+
+		let fee =
+			self.client
+				.runtime_api()
+				.xcm_delivery_fee(at, dest, message_size)
+				.map_err(|e| {
+					CallError::Custom(ErrorObject::owned(
+						RUNTIME_ERROR,
+						"Unable to query the delivery fee.",
+						Some(e.to_string()),
+					))
+				})?;
+
+		Ok(fee.map(|(currency, amount)| (currency, amount.into())))
+	}
+
+	fn estimate_xtokens_fee(
+		&self,
+		currency: CurrencyId,
+		dest: Bytes,
+		at: Option<Hash>,
+	) -> RpcResult<Option<(NumberOrHex, Weight)>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let dest = decode_dest(&dest)?;
+
+		let fee =
+			self.client
+				.runtime_api()
+				.estimate_xtokens_fee(at, currency, dest)
+				.map_err(|e| {
+					CallError::Custom(ErrorObject::owned(
+						RUNTIME_ERROR,
+						"Unable to estimate the transfer fee.",
+						Some(e.to_string()),
+					))
+				})?;
+
+		Ok(fee.map(|(amount, weight)| (amount.into(), weight)))
+	}
+
+	fn instruction_weight(&self, at: Option<Hash>) -> RpcResult<Weight> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client.runtime_api().xcm_instruction_weight(at).map_err(|e| {
+			CallError::Custom(ErrorObject::owned(
+				RUNTIME_ERROR,
+				"Unable to query the instruction weight.",
+				Some(e.to_string()),
+			))
+			.into()
+		})
+	}
+}
+
+fn decode_dest(dest: &Bytes) -> RpcResult<VersionedMultiLocation> {
+	VersionedMultiLocation::decode(&mut &dest[..]).map_err(|e| {
+		CallError::Custom(ErrorObject::owned(
+			DECODE_ERROR,
+			"Unable to decode the destination.",
+			Some(e.to_string()),
+		))
+		.into()
+	})
+}
@@ -0,0 +1,119 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC to dry-run the calls of XCM `Transact` instructions, see [`TransactDryRunApi`].
+
+use codec::Decode;
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use parachains_common::{runtime_api::TransactDryRunApi, Block, Hash};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::DispatchResult;
+use std::sync::Arc;
+use xcm::{latest::OriginKind, VersionedMultiLocation};
+
+/// The runtime could not dry-run the call.
+const RUNTIME_ERROR: i32 = 1;
+/// The origin kind or the origin could not be decoded.
+const DECODE_ERROR: i32 = 2;
+/// The XCM executor would not dispatch the call at all.
+const TRANSACT_REJECTED: i32 = 3;
+
+/// XCM `Transact` RPC methods.
+#[rpc(client, server)]
+pub trait XcmTransactApi {
+	/// The result of dispatching the SCALE encoded `RuntimeCall` `call` like a `Transact` from the
+	/// SCALE encoded `VersionedMultiLocation` `origin` with the SCALE encoded `OriginKind`
+	/// `origin_kind` would. Nothing is committed.
+	///
+	/// Fails with the reason if the call would not be dispatched at all, e.g. because it is not
+	/// allowed by the `SafeCallFilter`.
+	#[method(name = "xcm_dryRunTransact")]
+	fn dry_run_transact(
+		&self,
+		call: Bytes,
+		origin_kind: Bytes,
+		origin: Bytes,
+		at: Option<Hash>,
+	) -> RpcResult<DispatchResult>;
+}
+
+/// Implements [`XcmTransactApiServer`] on top of the [`TransactDryRunApi`].
+pub struct XcmTransact<C> {
+	client: Arc<C>,
+}
+
+impl<C> XcmTransact<C> {
+	/// Create a new instance.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+fn decode<T: Decode>(bytes: &Bytes, what: &str) -> Result<T, CallError> {
+	T::decode(&mut &**bytes).map_err(|e| {
+		CallError::Custom(ErrorObject::owned(
+			DECODE_ERROR,
+			format!("Unable to decode the {}.", what),
+			Some(e.to_string()),
+		))
+	})
+}
+
+impl<C> XcmTransactApiServer for XcmTransact<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: TransactDryRunApi<Block>,
+{
+	fn dry_run_transact(
+		&self,
+		call: Bytes,
+		origin_kind: Bytes,
+		origin: Bytes,
+		at: Option<Hash>,
+	) -> RpcResult<DispatchResult> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let origin_kind: OriginKind = decode(&origin_kind, "origin kind")?;
+		let origin: VersionedMultiLocation = decode(&origin, "origin")?;
+
+		let result = self
+			.client
+			.runtime_api()
+			.dry_run_transact(at, call.to_vec(), origin_kind, origin)
+			.map_err(|e| {
+				CallError::Custom(ErrorObject::owned(
+					RUNTIME_ERROR,
+					"Unable to dry-run the call.",
+					Some(e.to_string()),
+				))
+			})?;
+
+		result.map_err(|rejection| {
+			CallError::Custom(ErrorObject::owned(
+				TRANSACT_REJECTED,
+				"The call would not be dispatched.",
+				Some(format!("{:?}", rejection)),
+			))
+			.into()
+		})
+	}
+}
@@ -0,0 +1,93 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC to list the assets the XCM executor trapped, see [`TrappedAssetsRuntimeApi`].
+//!
+//! Assets left in the holding register when a message fails, e.g. because a `DepositAsset` to an
+//! unknown currency fails, are trapped by `pallet_xcm`. The origin they have been trapped for can
+//! reclaim them with a message claiming them by the assets and version listed here:
+//!
+//! ```text
+//! ClaimAsset { assets, ticket: (0, GeneralIndex(version)) }
+//! BuyExecution { fees, weight_limit: Unlimited }
+//! DepositAsset { assets: AllCounted(n), beneficiary }
+//! ```
+//!
+//! where `assets` are the listed assets converted to the latest XCM version and `version` is the
+//! XCM version they are listed in. The message has to be sent from the same origin, assets
+//! trapped `count` times can be claimed as often.
+
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use parachains_common::{runtime_api::TrappedAssetsApi as TrappedAssetsRuntimeApi, Block, Hash};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use std::sync::Arc;
+use xcm::{VersionedMultiAssets, VersionedMultiLocation};
+
+/// The runtime could not list the trapped assets.
+const RUNTIME_ERROR: i32 = 1;
+
+/// XCM trapped assets RPC methods.
+#[rpc(client, server)]
+pub trait XcmTrappedAssetsApi {
+	/// The assets trapped by `origin` which haven't been claimed yet, with the number of times
+	/// they have been trapped.
+	#[method(name = "xcm_trappedAssets")]
+	fn trapped_assets(
+		&self,
+		origin: VersionedMultiLocation,
+		at: Option<Hash>,
+	) -> RpcResult<Vec<(VersionedMultiAssets, u32)>>;
+}
+
+/// Implements [`XcmTrappedAssetsApiServer`] on top of the [`TrappedAssetsRuntimeApi`].
+pub struct XcmTrappedAssets<C> {
+	client: Arc<C>,
+}
+
+impl<C> XcmTrappedAssets<C> {
+	/// Create a new instance.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+impl<C> XcmTrappedAssetsApiServer for XcmTrappedAssets<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: TrappedAssetsRuntimeApi<Block>,
+{
+	fn trapped_assets(
+		&self,
+		origin: VersionedMultiLocation,
+		at: Option<Hash>,
+	) -> RpcResult<Vec<(VersionedMultiAssets, u32)>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client.runtime_api().trapped_assets(at, origin).map_err(|e| {
+			CallError::Custom(ErrorObject::owned(
+				RUNTIME_ERROR,
+				"Unable to list the trapped assets.",
+				Some(e.to_string()),
+			))
+			.into()
+		})
+	}
+}
@@ -0,0 +1,188 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC to query the congestion of the XCMP queue, see [`XcmpQueueRuntimeApi`].
+//!
+//! Once a sibling's messages pile up in our queue beyond the suspend threshold, e.g. because
+//! they are overweight, we ask it to suspend the channel, and drop its messages beyond the drop
+//! threshold. The status lets operators notice this before messages are lost.
+
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use parachains_common::{
+	runtime_api::XcmpQueueApi as XcmpQueueRuntimeApi,
+	xcm_config::{
+		InboundChannelBacklog, OutboundChannelBacklog, XcmpQueueStatus as RuntimeXcmpQueueStatus,
+	},
+	Block, Hash,
+};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use std::sync::Arc;
+
+/// The runtime could not report the status.
+const RUNTIME_ERROR: i32 = 1;
+
+/// XCMP queue RPC methods.
+#[rpc(client, server)]
+pub trait XcmpQueueApi {
+	/// The thresholds of the XCMP queue and the backlogs of its channels.
+	#[method(name = "xcmp_queueStatus")]
+	fn xcmp_queue_status(&self, at: Option<Hash>) -> RpcResult<XcmpQueueStatus>;
+}
+
+/// The backlog of an inbound channel.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InboundChannel {
+	/// The sibling sending over the channel.
+	pub sender: u32,
+	/// Whether we asked the sender to suspend the channel.
+	pub suspended: bool,
+	/// The number of messages received but not executed yet.
+	pub messages: u32,
+}
+
+/// The backlog of an outbound channel.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboundChannel {
+	/// The sibling receiving over the channel.
+	pub recipient: u32,
+	/// Whether the recipient asked us to suspend the channel.
+	pub suspended: bool,
+	/// Whether signals are waiting to be sent.
+	pub signals: bool,
+	/// The number of pages of messages waiting to be sent.
+	pub pages: u16,
+}
+
+/// The congestion of the XCMP queue.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XcmpQueueStatus {
+	/// The number of pending messages at which an inbound channel is suspended.
+	pub suspend_threshold: u32,
+	/// The number of pending messages at which further messages of an inbound channel are
+	/// dropped.
+	pub drop_threshold: u32,
+	/// The number of pending messages at which a suspended inbound channel is resumed.
+	pub resume_threshold: u32,
+	/// The inbound channels with a backlog.
+	pub inbound: Vec<InboundChannel>,
+	/// The outbound channels with a backlog.
+	pub outbound: Vec<OutboundChannel>,
+	/// The origin which may execute overweight messages, e.g. `EnsureRoot`.
+	pub execute_overweight_origin: String,
+}
+
+impl From<RuntimeXcmpQueueStatus> for XcmpQueueStatus {
+	fn from(status: RuntimeXcmpQueueStatus) -> Self {
+		let inbound = status
+			.inbound
+			.into_iter()
+			.map(|InboundChannelBacklog { sender, suspended, messages }| InboundChannel {
+				sender: sender.into(),
+				suspended,
+				messages,
+			})
+			.collect();
+		let outbound = status
+			.outbound
+			.into_iter()
+			.map(|OutboundChannelBacklog { recipient, suspended, signals, pages }| {
+				OutboundChannel { recipient: recipient.into(), suspended, signals, pages }
+			})
+			.collect();
+		Self {
+			suspend_threshold: status.suspend_threshold,
+			drop_threshold: status.drop_threshold,
+			resume_threshold: status.resume_threshold,
+			inbound,
+			outbound,
+			execute_overweight_origin: String::from_utf8_lossy(&status.execute_overweight_origin)
+				.into_owned(),
+		}
+	}
+}
+
+/// Implements [`XcmpQueueApiServer`] on top of the [`XcmpQueueRuntimeApi`].
+pub struct XcmpQueue<C> {
+	client: Arc<C>,
+}
+
+impl<C> XcmpQueue<C> {
+	/// Create a new instance.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+impl<C> XcmpQueueApiServer for XcmpQueue<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: XcmpQueueRuntimeApi<Block>,
+{
+	fn xcmp_queue_status(&self, at: Option<Hash>) -> RpcResult<XcmpQueueStatus> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let status = self.client.runtime_api().xcmp_queue_status(at).map_err(|e| {
+			CallError::Custom(ErrorObject::owned(
+				RUNTIME_ERROR,
+				"Unable to query the XCMP queue status.",
+				Some(e.to_string()),
+			))
+		})?;
+		Ok(status.into())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn suspended_channels_are_reported() {
+		let status = XcmpQueueStatus::from(RuntimeXcmpQueueStatus {
+			suspend_threshold: 2,
+			drop_threshold: 5,
+			resume_threshold: 1,
+			inbound: vec![InboundChannelBacklog {
+				sender: 2000.into(),
+				suspended: true,
+				messages: 3,
+			}],
+			outbound: vec![],
+			execute_overweight_origin: b"frame_system::EnsureRoot<AccountId32>".to_vec(),
+		});
+		assert_eq!(
+			serde_json::to_value(&status).unwrap(),
+			json!({
+				"suspendThreshold": 2,
+				"dropThreshold": 5,
+				"resumeThreshold": 1,
+				"inbound": [{ "sender": 2000, "suspended": true, "messages": 3 }],
+				"outbound": [],
+				"executeOverweightOrigin": "frame_system::EnsureRoot<AccountId32>",
+			})
+		);
+	}
+}
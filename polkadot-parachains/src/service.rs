@@ -17,52 +17,94 @@
 
 use codec::Codec;
 use cumulus_client_cli::CollatorOptions;
-use cumulus_client_consensus_aura::{AuraConsensus, BuildAuraConsensusParams, SlotProportion};
+use cumulus_client_consensus_aura::{AuraConsensus, BuildAuraConsensusParams};
 use cumulus_client_consensus_common::{
-	ParachainBlockImport as TParachainBlockImport, ParachainCandidate, ParachainConsensus,
+	ParachainBlockImport as TParachainBlockImport, ParachainConsensus,
 };
 use cumulus_client_service::{
-	build_network, build_relay_chain_interface, prepare_node_config, start_collator,
-	start_full_node, StartCollatorParams, StartFullNodeParams,
+	build_relay_chain_interface, prepare_node_config, start_collator, start_full_node,
+	StartCollatorParams, StartFullNodeParams,
 };
 use cumulus_primitives_core::{
-	relay_chain::{Hash as PHash, PersistedValidationData},
+	relay_chain::{BlockNumber as RelayBlockNumber, Hash as PHash, PersistedValidationData},
 	ParaId,
 };
+use cumulus_primitives_parachain_inherent::{
+	MockValidationDataInherentDataProvider, MockXcmConfig, ParachainInherentData,
+};
 use cumulus_relay_chain_interface::{RelayChainError, RelayChainInterface};
 use sp_core::Pair;
 
 use jsonrpsee::RpcModule;
 
-use crate::rpc;
+use crate::{
+	aura_version::{self, AuraVersionGuard},
+	authoring_stats::{self, AuthoringStats, StatsProposerFactory},
+	backing_monitor,
+	block_announce::RelayParentAnnounceValidator,
+	block_metadata,
+	candidate_retry::{self, RetryConfig},
+	collator_allow_list, collator_peers, collator_status, integrity_check, keystore_check,
+	metric_prefix::MetricPrefix,
+	pov_recovery_limit::LimitedRecovery,
+	proposal_slot::ProposalSlotConfig,
+	relay_parent_age::RelayParentAgeCheck,
+	relay_resync::{ResyncHandle, ResyncingConsensus},
+	rpc, stale_validation_data,
+	throttle::ThrottledTransactionPool,
+	verifier_cross_check,
+	wait_for_aura::{BuildOnAccess, ClientAuraApiVersion, WaitForAuraConsensus},
+	warp_sync::{self, SyncSelection},
+	xcm_weight_metrics,
+};
 pub use parachains_common::{AccountId, Balance, Block, BlockNumber, Hash, Header, Index as Nonce};
 
 use cumulus_client_consensus_relay_chain::Verifier as RelayChainVerifier;
-use futures::lock::Mutex;
+use cumulus_client_network::RequireSecondedInBlockAnnounce;
+use cumulus_client_pov_recovery::RecoveryHandle;
+use futures::{channel::oneshot, StreamExt};
 use sc_consensus::{
 	import_queue::{BasicQueue, Verifier as VerifierT},
-	BlockImportParams, ImportQueue,
+	BlockImportParams, ImportQueue, LongestChain,
+};
+use sc_consensus_manual_seal::{
+	consensus::aura::AuraConsensusDataProvider, EngineCommand, ManualSealParams,
 };
 use sc_executor::{HeapAllocStrategy, WasmExecutor, DEFAULT_HEAP_ALLOC_STRATEGY};
-use sc_network::NetworkBlock;
-use sc_network_sync::SyncingService;
-use sc_service::{Configuration, PartialComponents, TFullBackend, TFullClient, TaskManager};
+use sc_network::{config::MultiaddrWithPeerId, NetworkBlock};
+use sc_network_sync::{warp::WarpSyncParams, SyncingService};
+use sc_service::{
+	config::KeystoreConfig, Configuration, PartialComponents, TFullBackend, TFullClient,
+	TaskManager,
+};
 use sc_telemetry::{Telemetry, TelemetryHandle, TelemetryWorker, TelemetryWorkerHandle};
-use sp_api::{ApiExt, ConstructRuntimeApi};
+use sc_transaction_pool_api::TransactionPool;
+use sp_api::ConstructRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus::block_validation::BlockAnnounceValidator;
 use sp_consensus_aura::AuraApi;
 use sp_keystore::KeystorePtr;
 use sp_runtime::{
 	app_crypto::AppCrypto,
 	traits::{BlakeTwo256, Header as HeaderT},
 };
-use std::{marker::PhantomData, sync::Arc, time::Duration};
+use std::{
+	marker::PhantomData,
+	path::PathBuf,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
 use substrate_prometheus_endpoint::Registry;
 
-#[cfg(not(feature = "runtime-benchmarks"))]
-type HostFunctions = sp_io::SubstrateHostFunctions;
+#[cfg(not(any(feature = "runtime-benchmarks", feature = "try-runtime")))]
+pub(crate) type HostFunctions = sp_io::SubstrateHostFunctions;
 
-#[cfg(feature = "runtime-benchmarks")]
-type HostFunctions =
+/// Runtimes built for benchmarks or try-runtime may call the benchmarking host functions.
+#[cfg(any(feature = "runtime-benchmarks", feature = "try-runtime"))]
+pub(crate) type HostFunctions =
 	(sp_io::SubstrateHostFunctions, frame_benchmarking::benchmarking::HostFunctions);
 
 type ParachainClient<RuntimeApi> = TFullClient<Block, RuntimeApi, WasmExecutor<HostFunctions>>;
@@ -102,6 +144,15 @@ impl sc_executor::NativeExecutionDispatch for ShellParachainRuntimeExecutor {
 	}
 }
 
+/// Default capacity of the telemetry worker's message channel.
+///
+/// When the channel is full, `sc_telemetry` drops the newest messages, this can't be configured.
+/// Connection messages are registered through a separate channel and are never dropped.
+pub const DEFAULT_TELEMETRY_BUFFER_SIZE: usize = 16;
+
+/// Default slot duration of the relay chain, the one of Kusama and Polkadot.
+pub const DEFAULT_RELAY_CHAIN_SLOT_DURATION_MS: u64 = 6_000;
+
 /// Starts a `ServiceBuilder` for a full service.
 ///
 /// Use this macro if you don't actually need the full service, but just the builder in order to
@@ -109,6 +160,7 @@ impl sc_executor::NativeExecutionDispatch for ShellParachainRuntimeExecutor {
 pub fn new_partial<RuntimeApi, BIQ>(
 	config: &Configuration,
 	build_import_queue: BIQ,
+	telemetry_buffer_size: usize,
 ) -> Result<
 	PartialComponents<
 		ParachainClient<RuntimeApi>,
@@ -147,7 +199,7 @@ where
 		.clone()
 		.filter(|x| !x.is_empty())
 		.map(|endpoints| -> Result<_, sc_telemetry::Error> {
-			let worker = TelemetryWorker::new(16)?;
+			let worker = TelemetryWorker::new(telemetry_buffer_size)?;
 			let telemetry = worker.handle().new_telemetry(endpoints);
 			Ok((worker, telemetry))
 		})
@@ -224,6 +276,15 @@ async fn start_node_impl<RuntimeApi, RB, BIQ, BIC>(
 	build_import_queue: BIQ,
 	build_consensus: BIC,
 	hwbench: Option<sc_sysinfo::HwBench>,
+	telemetry_buffer_size: usize,
+	authoring_stats_export: Option<(AuthoringStats, PathBuf, Duration)>,
+	announce_relay_parent_check: bool,
+	block_metadata_export: Option<(PathBuf, u64)>,
+	metric_prefix: MetricPrefix,
+	relay_chain_slot_duration: Duration,
+	relay_parent_age: Option<RelayParentAgeCheck<Arc<dyn RelayChainInterface>>>,
+	check_integrity: bool,
+	max_concurrent_pov_recoveries: Option<usize>,
 ) -> sc_service::error::Result<(TaskManager, Arc<ParachainClient<RuntimeApi>>)>
 where
 	RuntimeApi: ConstructRuntimeApi<Block, ParachainClient<RuntimeApi>> + Send + Sync + 'static,
@@ -237,7 +298,20 @@ where
 		+ sp_block_builder::BlockBuilder<Block>
 		+ cumulus_primitives_core::CollectCollationInfo<Block>
 		+ pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>
-		+ frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
+		+ frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>
+		+ parachains_common::runtime_api::XcmDeliveryFeeApi<Block>
+		+ parachains_common::runtime_api::XtokensFeeApi<Block>
+		+ parachains_common::runtime_api::XcmEventsApi<Block>
+		+ parachains_common::runtime_api::CurrencyRegistryApi<Block>
+		+ parachains_common::runtime_api::TransactDryRunApi<Block>
+		+ parachains_common::runtime_api::BarrierDryRunApi<Block>
+		+ parachains_common::runtime_api::XcmDryRunApi<Block>
+		+ parachains_common::runtime_api::TrappedAssetsApi<Block>
+		+ parachains_common::runtime_api::XcmpQueueApi<Block>
+		+ parachains_common::runtime_api::XcmLocationApi<Block>
+		+ parachains_common::runtime_api::AssetMetadataApi<Block>
+		+ parachains_common::runtime_api::IntegrityCheckApi<Block>
+		+ parachains_common::runtime_api::XcmWeightApi<Block>,
 	sc_client_api::StateBackendFor<ParachainBackend, Block>: sp_api::StateBackend<BlakeTwo256>,
 	RB: Fn(Arc<ParachainClient<RuntimeApi>>) -> Result<jsonrpsee::RpcModule<()>, sc_service::Error>,
 	BIQ: FnOnce(
@@ -263,14 +337,29 @@ where
 		bool,
 	) -> Result<Box<dyn ParachainConsensus<Block>>, sc_service::Error>,
 {
-	let parachain_config = prepare_node_config(parachain_config);
+	let mut parachain_config = prepare_node_config(parachain_config);
+	let relay_chain = polkadot_config.chain_spec.id().to_string();
+
+	// Fail early and tell which key is at fault, rather than when consensus first signs.
+	if let KeystoreConfig::Path { path, password } = &parachain_config.keystore {
+		keystore_check::check(path, password.as_ref(), parachain_config.role.is_authority())?;
+	}
 
-	let params = new_partial::<RuntimeApi, BIQ>(&parachain_config, build_import_queue)?;
+	let params = new_partial::<RuntimeApi, BIQ>(
+		&parachain_config,
+		build_import_queue,
+		telemetry_buffer_size,
+	)?;
 	let (block_import, mut telemetry, telemetry_worker_handle) = params.other;
 
 	let client = params.client.clone();
 	let backend = params.backend.clone();
 
+	// Before connecting to anything, a mis-built runtime shouldn't get to author or serve RPCs.
+	if check_integrity {
+		integrity_check::check(&*client, client.info().best_hash)?;
+	}
+
 	let mut task_manager = params.task_manager;
 	let (relay_chain_interface, collator_key) = build_relay_chain_interface(
 		polkadot_config,
@@ -285,35 +374,78 @@ where
 		RelayChainError::Application(x) => x,
 		s => s.to_string().into(),
 	})?;
+	if let Some(relay_parent_age) = &relay_parent_age {
+		relay_parent_age.connect(relay_chain_interface.clone());
+	}
 
 	let force_authoring = parachain_config.force_authoring;
 	let validator = parachain_config.role.is_authority();
+	let role = node_role(&parachain_config.role);
 	let prometheus_registry = parachain_config.prometheus_registry().cloned();
 	let transaction_pool = params.transaction_pool.clone();
 	let import_queue_service = params.import_queue.service();
 
+	let sync_selection = warp_sync::select(
+		parachain_config.network.sync_mode,
+		client.info().best_number,
+		&relay_chain_interface,
+		para_id,
+	)
+	.await;
+	match sync_selection {
+		SyncSelection::Full(_) => log::warn!("Selected {}", sync_selection),
+		_ => log::info!("Selected {}", sync_selection),
+	}
+	parachain_config.network.sync_mode = sync_selection.sync_mode();
+	let warp_sync_params = matches!(sync_selection, SyncSelection::Warp).then(|| {
+		let (sender, receiver) = oneshot::channel();
+		task_manager.spawn_handle().spawn(
+			"warp-sync-target",
+			None,
+			warp_sync::wait_for_target(relay_chain_interface.clone(), para_id, sender),
+		);
+		WarpSyncParams::WaitForTarget(receiver)
+	});
+
+	let require_seconded: Box<dyn BlockAnnounceValidator<Block> + Send> =
+		Box::new(RequireSecondedInBlockAnnounce::new(relay_chain_interface.clone(), para_id));
+	let block_announce_validator: Box<dyn BlockAnnounceValidator<Block> + Send> =
+		if announce_relay_parent_check {
+			Box::new(RelayParentAnnounceValidator::new(
+				require_seconded,
+				relay_chain_interface.clone(),
+			))
+		} else {
+			require_seconded
+		};
 	let (network, system_rpc_tx, tx_handler_controller, start_network, sync_service) =
-		build_network(cumulus_client_service::BuildNetworkParams {
-			parachain_config: &parachain_config,
+		sc_service::build_network(sc_service::BuildNetworkParams {
+			config: &parachain_config,
 			client: client.clone(),
 			transaction_pool: transaction_pool.clone(),
-			para_id,
 			spawn_handle: task_manager.spawn_handle(),
-			relay_chain_interface: relay_chain_interface.clone(),
 			import_queue: params.import_queue,
-		})
-		.await?;
+			block_announce_validator_builder: Some(Box::new(move |_| block_announce_validator)),
+			warp_sync_params,
+		})?;
+
+	// Shared with the RPC, so the operator can recover a collator stuck on stale relay parents.
+	let relay_resync = validator.then(|| ResyncHandle::new(relay_chain_interface.clone()));
 
 	let rpc_builder = {
 		let client = client.clone();
 		let transaction_pool = transaction_pool.clone();
+		let relay_resync = relay_resync.clone();
 
 		let backend_for_rpc = backend.clone();
-		Box::new(move |deny_unsafe, _| {
+		Box::new(move |deny_unsafe, subscription_executor| {
 			let deps = rpc::FullDeps {
 				client: client.clone(),
 				pool: transaction_pool.clone(),
 				deny_unsafe,
+				subscription_executor,
+				relay_resync: relay_resync.clone(),
+				manual_seal: None,
 			};
 
 			rpc::create_full(deps, backend_for_rpc.clone()).map_err(Into::into)
@@ -335,10 +467,12 @@ where
 		telemetry: telemetry.as_mut(),
 	})?;
 
+	let meets_reference_hardware = hwbench.as_ref().map(meets_reference_hardware);
+
 	if let Some(hwbench) = hwbench {
 		sc_sysinfo::print_hwbench(&hwbench);
-		if validator {
-			warn_if_slow_hardware(&hwbench);
+		if validator && meets_reference_hardware == Some(false) {
+			warn_slow_hardware();
 		}
 
 		if let Some(ref mut telemetry) = telemetry {
@@ -351,17 +485,82 @@ where
 		}
 	}
 
+	log_startup_summary(
+		para_id,
+		role,
+		&relay_chain,
+		relay_chain_slot_duration,
+		meets_reference_hardware,
+	);
+
+	if let Some((path, max_bytes)) = block_metadata_export {
+		log::info!("Appending the metadata of imported blocks to {}", path.display());
+		task_manager.spawn_handle().spawn(
+			"block-metadata",
+			None,
+			block_metadata::run(client.clone(), path, max_bytes),
+		);
+	}
+
 	let announce_block = {
 		let sync_service = sync_service.clone();
 		Arc::new(move |hash, data| sync_service.announce_block(hash, data))
 	};
 
-	let relay_chain_slot_duration = Duration::from_secs(6);
-
 	let overseer_handle = relay_chain_interface
 		.overseer_handle()
 		.map_err(|e| sc_service::Error::Application(Box::new(e)))?;
+	let recovery_handle: Box<dyn RecoveryHandle> = match max_concurrent_pov_recoveries {
+		Some(max_in_flight) => {
+			let (recovery_handle, forward) =
+				LimitedRecovery::new(Box::new(overseer_handle), max_in_flight);
+			task_manager.spawn_handle().spawn("pov-recovery-limit", None, forward);
+			Box::new(recovery_handle)
+		},
+		None => Box::new(overseer_handle),
+	};
+	if let Some(registry) = prometheus_registry.as_ref() {
+		match xcm_weight_metrics::Metrics::register(registry, &metric_prefix) {
+			Ok(metrics) => task_manager.spawn_handle().spawn(
+				"xcm-weight-metrics",
+				None,
+				xcm_weight_metrics::run(client.clone(), metrics),
+			),
+			Err(e) => log::warn!("Failed to register XCM weight metrics: {:?}", e),
+		}
+	}
 	if validator {
+		let metrics = prometheus_registry.as_ref().and_then(|registry| {
+			backing_monitor::Metrics::register(registry, &metric_prefix)
+				.map_err(|e| log::warn!("Failed to register backing monitor metrics: {:?}", e))
+				.ok()
+		});
+		task_manager.spawn_handle().spawn(
+			"backing-monitor",
+			None,
+			backing_monitor::run(client.clone(), relay_chain_interface.clone(), para_id, metrics),
+		);
+
+		let metrics = prometheus_registry.as_ref().and_then(|registry| {
+			collator_status::Metrics::register(registry, &metric_prefix)
+				.map_err(|e| log::warn!("Failed to register collator status metrics: {:?}", e))
+				.ok()
+		});
+		task_manager.spawn_handle().spawn(
+			"collator-status",
+			None,
+			collator_status::run(client.clone(), params.keystore_container.keystore(), metrics),
+		);
+
+		if let Some((stats, path, interval)) = authoring_stats_export {
+			log::info!("Writing authoring statistics to {} every {:?}", path.display(), interval);
+			task_manager.spawn_handle().spawn(
+				"authoring-stats",
+				None,
+				authoring_stats::run(stats, path, interval),
+			);
+		}
+
 		let parachain_consensus = build_consensus(
 			client.clone(),
 			block_import,
@@ -374,6 +573,9 @@ where
 			params.keystore_container.keystore(),
 			force_authoring,
 		)?;
+		let relay_resync = relay_resync.expect("created for validators; qed");
+		let parachain_consensus =
+			Box::new(ResyncingConsensus::new(parachain_consensus, relay_resync));
 
 		let spawner = task_manager.spawn_handle();
 
@@ -390,7 +592,7 @@ where
 			collator_key: collator_key.expect("Command line arguments do not allow this. qed"),
 			sync_service: sync_service.clone(),
 			relay_chain_slot_duration,
-			recovery_handle: Box::new(overseer_handle),
+			recovery_handle,
 		};
 
 		start_collator(params).await?;
@@ -403,7 +605,7 @@ where
 			relay_chain_interface,
 			relay_chain_slot_duration,
 			import_queue: import_queue_service,
-			recovery_handle: Box::new(overseer_handle),
+			recovery_handle,
 			sync_service: sync_service.clone(),
 		};
 
@@ -415,90 +617,22 @@ where
 	Ok((task_manager, client))
 }
 
-enum BuildOnAccess<R> {
-	Uninitialized(Option<Box<dyn FnOnce() -> R + Send + Sync>>),
-	Initialized(R),
-}
-
-impl<R> BuildOnAccess<R> {
-	fn get_mut(&mut self) -> &mut R {
-		loop {
-			match self {
-				Self::Uninitialized(f) => {
-					*self = Self::Initialized((f.take().unwrap())());
-				},
-				Self::Initialized(ref mut r) => return r,
-			}
-		}
-	}
-}
-
-/// Special [`ParachainConsensus`] implementation that waits for the upgrade from
-/// shell to a parachain runtime that implements Aura.
-struct WaitForAuraConsensus<Client, AuraId> {
-	client: Arc<Client>,
-	aura_consensus: Arc<Mutex<BuildOnAccess<Box<dyn ParachainConsensus<Block>>>>>,
-	relay_chain_consensus: Arc<Mutex<Box<dyn ParachainConsensus<Block>>>>,
-	_phantom: PhantomData<AuraId>,
-}
-
-impl<Client, AuraId> Clone for WaitForAuraConsensus<Client, AuraId> {
-	fn clone(&self) -> Self {
-		Self {
-			client: self.client.clone(),
-			aura_consensus: self.aura_consensus.clone(),
-			relay_chain_consensus: self.relay_chain_consensus.clone(),
-			_phantom: PhantomData,
-		}
-	}
-}
-
-#[async_trait::async_trait]
-impl<Client, AuraId> ParachainConsensus<Block> for WaitForAuraConsensus<Client, AuraId>
-where
-	Client: sp_api::ProvideRuntimeApi<Block> + Send + Sync,
-	Client::Api: AuraApi<Block, AuraId>,
-	AuraId: Send + Codec + Sync,
-{
-	async fn produce_candidate(
-		&mut self,
-		parent: &Header,
-		relay_parent: PHash,
-		validation_data: &PersistedValidationData,
-	) -> Option<ParachainCandidate<Block>> {
-		if self
-			.client
-			.runtime_api()
-			.has_api::<dyn AuraApi<Block, AuraId>>(parent.hash())
-			.unwrap_or(false)
-		{
-			self.aura_consensus
-				.lock()
-				.await
-				.get_mut()
-				.produce_candidate(parent, relay_parent, validation_data)
-				.await
-		} else {
-			self.relay_chain_consensus
-				.lock()
-				.await
-				.produce_candidate(parent, relay_parent, validation_data)
-				.await
-		}
-	}
-}
-
 struct Verifier<Client, AuraId> {
 	client: Arc<Client>,
 	aura_verifier: BuildOnAccess<Box<dyn VerifierT<Block>>>,
 	relay_chain_verifier: Box<dyn VerifierT<Block>>,
+	/// Verify blocks close to the Aura upgrade with both verifiers, see [`verifier_cross_check`].
+	cross_check: bool,
+	/// Reject announced blocks built on too old relay parents, see [`crate::relay_parent_age`].
+	relay_parent_age: Option<RelayParentAgeCheck<Arc<dyn RelayChainInterface>>>,
+	aura_version: AuraVersionGuard,
 	_phantom: PhantomData<AuraId>,
 }
 
 #[async_trait::async_trait]
 impl<Client, AuraId> VerifierT<Block> for Verifier<Client, AuraId>
 where
-	Client: sp_api::ProvideRuntimeApi<Block> + Send + Sync,
+	Client: sp_api::ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync,
 	Client::Api: AuraApi<Block, AuraId>,
 	AuraId: Send + Sync + Codec,
 {
@@ -506,12 +640,35 @@ where
 		&mut self,
 		block_import: BlockImportParams<Block, ()>,
 	) -> Result<BlockImportParams<Block, ()>, String> {
-		if self
-			.client
-			.runtime_api()
-			.has_api::<dyn AuraApi<Block, AuraId>>(*block_import.header.parent_hash())
-			.unwrap_or(false)
-		{
+		if let Some(relay_parent_age) = &self.relay_parent_age {
+			relay_parent_age.check(&block_import).await?;
+		}
+
+		let parent = *block_import.header.parent_hash();
+		let has_aura = |hash| {
+			self.aura_version
+				.use_aura(aura_version::aura_api_version::<_, AuraId>(&*self.client, hash))
+		};
+		let aura = self.aura_version.use_aura_at(
+			aura_version::aura_api_version::<_, AuraId>(&*self.client, parent),
+			*block_import.header.number(),
+		);
+
+		if self.cross_check &&
+			verifier_cross_check::near_aura_upgrade(parent, has_aura, |hash| {
+				self.client.header(hash).ok().flatten().map(|header| header.parent_hash)
+			}) {
+			let aura_verifier = ("Aura", &mut **self.aura_verifier.get_mut());
+			let relay_chain_verifier = ("relay chain", &mut *self.relay_chain_verifier);
+			let (primary, shadow) = if aura {
+				(aura_verifier, relay_chain_verifier)
+			} else {
+				(relay_chain_verifier, aura_verifier)
+			};
+			return verifier_cross_check::verify_both(primary, shadow, block_import).await.0
+		}
+
+		if aura {
 			self.aura_verifier.get_mut().verify(block_import).await
 		} else {
 			self.relay_chain_verifier.verify(block_import).await
@@ -519,6 +676,19 @@ where
 	}
 }
 
+/// Upper bound of the timestamp grace window, see [`aura_build_import_queue_with_timestamp_grace`].
+pub const MAX_TIMESTAMP_GRACE_MS: u64 = 2_000;
+
+/// The timestamp the runtime checks the timestamps of imported blocks against at `now`. Pretending
+/// our clock to be ahead by the grace window, capped at [`MAX_TIMESTAMP_GRACE_MS`], extends the
+/// accepted drift by it.
+fn graced_timestamp(
+	now: sp_timestamp::Timestamp,
+	timestamp_grace_ms: u64,
+) -> sp_timestamp::Timestamp {
+	now + timestamp_grace_ms.min(MAX_TIMESTAMP_GRACE_MS)
+}
+
 /// Build the import queue for Statemint and other Aura-based runtimes.
 ///
 /// Note: The integritee-runtime and the shell-runtime belong to these.
@@ -529,6 +699,53 @@ pub fn aura_build_import_queue<RuntimeApi, AuraId: AppCrypto>(
 	telemetry_handle: Option<TelemetryHandle>,
 	task_manager: &TaskManager,
 ) -> Result<sc_consensus::DefaultImportQueue<Block, ParachainClient<RuntimeApi>>, sc_service::Error>
+where
+	RuntimeApi: ConstructRuntimeApi<Block, ParachainClient<RuntimeApi>> + Send + Sync + 'static,
+	RuntimeApi::RuntimeApi: sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block>
+		+ sp_api::Metadata<Block>
+		+ sp_session::SessionKeys<Block>
+		+ sp_api::ApiExt<
+			Block,
+			StateBackend = sc_client_api::StateBackendFor<ParachainBackend, Block>,
+		> + sp_offchain::OffchainWorkerApi<Block>
+		+ sp_block_builder::BlockBuilder<Block>
+		+ sp_consensus_aura::AuraApi<Block, <<AuraId as AppCrypto>::Pair as Pair>::Public>,
+	sc_client_api::StateBackendFor<ParachainBackend, Block>: sp_api::StateBackend<BlakeTwo256>,
+	<<AuraId as AppCrypto>::Pair as Pair>::Signature:
+		TryFrom<Vec<u8>> + std::hash::Hash + sp_runtime::traits::Member + Codec,
+{
+	aura_build_import_queue_with_timestamp_grace::<RuntimeApi, AuraId>(
+		client,
+		block_import,
+		config,
+		telemetry_handle,
+		task_manager,
+		0,
+		false,
+		None,
+	)
+}
+
+/// Like [`aura_build_import_queue`], but accepts blocks whose timestamp is up to
+/// `timestamp_grace_ms` ahead of the local clock, on top of the drift the runtime allows anyway.
+///
+/// This avoids rejecting valid blocks on nodes with a slightly skewed clock. The grace is capped
+/// at [`MAX_TIMESTAMP_GRACE_MS`], blocks further ahead are still rejected.
+///
+/// With `verifier_cross_check`, blocks close to the Aura upgrade are verified by both the Aura and
+/// the relay chain verifier and disagreements are logged.
+///
+/// With `relay_parent_age`, announced blocks built on too old relay parents are rejected.
+pub fn aura_build_import_queue_with_timestamp_grace<RuntimeApi, AuraId: AppCrypto>(
+	client: Arc<ParachainClient<RuntimeApi>>,
+	block_import: ParachainBlockImport<RuntimeApi>,
+	config: &Configuration,
+	telemetry_handle: Option<TelemetryHandle>,
+	task_manager: &TaskManager,
+	timestamp_grace_ms: u64,
+	verifier_cross_check: bool,
+	relay_parent_age: Option<RelayParentAgeCheck<Arc<dyn RelayChainInterface>>>,
+) -> Result<sc_consensus::DefaultImportQueue<Block, ParachainClient<RuntimeApi>>, sc_service::Error>
 where
 	RuntimeApi: ConstructRuntimeApi<Block, ParachainClient<RuntimeApi>> + Send + Sync + 'static,
 	RuntimeApi::RuntimeApi: sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block>
@@ -545,6 +762,7 @@ where
 		TryFrom<Vec<u8>> + std::hash::Hash + sp_runtime::traits::Member + Codec,
 {
 	let client2 = client.clone();
+	let switch_telemetry = telemetry_handle.clone();
 
 	let aura_verifier = move || {
 		let slot_duration = cumulus_client_consensus_aura::slot_duration(&*client2).unwrap();
@@ -557,7 +775,10 @@ where
 		>(cumulus_client_consensus_aura::BuildVerifierParams {
 			client: client2.clone(),
 			create_inherent_data_providers: move |_, _| async move {
-				let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+				let timestamp = sp_timestamp::InherentDataProvider::new(graced_timestamp(
+					sp_timestamp::Timestamp::current(),
+					timestamp_grace_ms,
+				));
 
 				let slot =
 							sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
@@ -578,6 +799,14 @@ where
 		client: client.clone(),
 		relay_chain_verifier,
 		aura_verifier: BuildOnAccess::Uninitialized(Some(Box::new(aura_verifier))),
+		cross_check: verifier_cross_check,
+		relay_parent_age,
+		aura_version: AuraVersionGuard::new(
+			aura_version::expected_aura_api_version::<<<AuraId as AppCrypto>::Pair as Pair>::Public>(
+			),
+			"import",
+			switch_telemetry,
+		),
 		_phantom: PhantomData,
 	};
 
@@ -587,6 +816,90 @@ where
 	Ok(BasicQueue::new(verifier, Box::new(block_import), None, &spawner, registry))
 }
 
+/// Create the parachain inherent of a candidate on `relay_parent`, retrying as configured while
+/// the relay parent is current, see [`crate::candidate_retry`].
+///
+/// Fails if the relay parent lags more than `max_staleness` relay blocks behind, see
+/// [`crate::stale_validation_data`].
+async fn create_parachain_inherent(
+	relay_parent: PHash,
+	relay_chain_interface: &Arc<dyn RelayChainInterface>,
+	validation_data: &PersistedValidationData,
+	para_id: ParaId,
+	retry: RetryConfig,
+	max_staleness: Option<RelayBlockNumber>,
+) -> Result<ParachainInherentData, Box<dyn std::error::Error + Send + Sync>> {
+	if let Some(max_staleness) = max_staleness {
+		stale_validation_data::check(
+			relay_chain_interface,
+			validation_data.relay_parent_number,
+			max_staleness,
+		)
+		.await?;
+	}
+
+	candidate_retry::retry_on_relay_parent(
+		retry,
+		relay_chain_interface,
+		validation_data.relay_parent_number,
+		|| {
+			ParachainInherentData::create_at(
+				relay_parent,
+				relay_chain_interface,
+				validation_data,
+				para_id,
+			)
+		},
+	)
+	.await
+	.ok_or_else(|| "Failed to create parachain inherent".into())
+}
+
+/// Node options that go beyond the standard cumulus `RunCmd`.
+#[derive(Clone, Debug)]
+pub struct NodeExtraArgs {
+	/// Maximum number of user extrinsics included in an authored block, for load tests only.
+	pub max_extrinsics_per_block: Option<usize>,
+	/// How far a block's timestamp may be ahead of the local clock on import, in milliseconds.
+	pub timestamp_grace_ms: u64,
+	/// Capacity of the telemetry worker's message channel.
+	pub telemetry_buffer_size: usize,
+	/// File the authoring statistics are written to, if any.
+	pub authoring_stats_file: Option<PathBuf>,
+	/// How often the authoring statistics are written.
+	pub authoring_stats_interval: Duration,
+	/// Whether block announcements with an unknown relay parent are rejected.
+	pub announce_relay_parent_check: bool,
+	/// Whether blocks close to the Aura upgrade are verified by both verifiers, for diagnostics.
+	pub verifier_cross_check: bool,
+	/// Collators to reserve connections to.
+	pub collator_peers: Vec<MultiaddrWithPeerId>,
+	/// Collators block gossip is restricted to, if any.
+	pub allowed_collators: Vec<MultiaddrWithPeerId>,
+	/// File the metadata of imported blocks is appended to, if any.
+	pub block_metadata_file: Option<PathBuf>,
+	/// Size at which the block metadata file is rotated.
+	pub block_metadata_max_bytes: u64,
+	/// Prefix of the names of the node's own metrics.
+	pub metric_prefix: MetricPrefix,
+	/// Slot duration of the relay chain.
+	pub relay_chain_slot_duration: Duration,
+	/// Portions of the slot spent proposing a block.
+	pub proposal_slot: ProposalSlotConfig,
+	/// Number of relay blocks the relay parent of an announced block may lag behind the relay
+	/// chain's best block, unchecked if `None`.
+	pub max_relay_parent_age: Option<u32>,
+	/// Whether to run the runtime's integrity checks at startup, refusing to start on failure.
+	pub check_integrity: bool,
+	/// How to retry building a candidate after the relay chain failed to provide its data.
+	pub candidate_retry: RetryConfig,
+	/// Number of relay blocks the relay parent of a produced candidate may lag behind the relay
+	/// chain's best block, unchecked if `None`.
+	pub max_validation_data_staleness: Option<u32>,
+	/// Number of PoV recoveries in flight at a time, unlimited if `None`.
+	pub max_concurrent_pov_recoveries: Option<usize>,
+}
+
 /// Start an aura powered parachain node.
 /// (collective-polkadot and statemine/t use this)
 pub async fn start_generic_aura_node<RuntimeApi, AuraId: AppCrypto>(
@@ -595,6 +908,87 @@ pub async fn start_generic_aura_node<RuntimeApi, AuraId: AppCrypto>(
 	collator_options: CollatorOptions,
 	para_id: ParaId,
 	hwbench: Option<sc_sysinfo::HwBench>,
+	node_extra_args: NodeExtraArgs,
+) -> sc_service::error::Result<(TaskManager, Arc<ParachainClient<RuntimeApi>>)>
+where
+	RuntimeApi: ConstructRuntimeApi<Block, ParachainClient<RuntimeApi>> + Send + Sync + 'static,
+	RuntimeApi::RuntimeApi: sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block>
+		+ sp_api::Metadata<Block>
+		+ sp_session::SessionKeys<Block>
+		+ sp_api::ApiExt<
+			Block,
+			StateBackend = sc_client_api::StateBackendFor<ParachainBackend, Block>,
+		> + sp_offchain::OffchainWorkerApi<Block>
+		+ sp_block_builder::BlockBuilder<Block>
+		+ cumulus_primitives_core::CollectCollationInfo<Block>
+		+ sp_consensus_aura::AuraApi<Block, <<AuraId as AppCrypto>::Pair as Pair>::Public>
+		+ pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>
+		+ frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>
+		+ parachains_common::runtime_api::XcmDeliveryFeeApi<Block>
+		+ parachains_common::runtime_api::XtokensFeeApi<Block>
+		+ parachains_common::runtime_api::XcmEventsApi<Block>
+		+ parachains_common::runtime_api::CurrencyRegistryApi<Block>
+		+ parachains_common::runtime_api::TransactDryRunApi<Block>
+		+ parachains_common::runtime_api::BarrierDryRunApi<Block>
+		+ parachains_common::runtime_api::XcmDryRunApi<Block>
+		+ parachains_common::runtime_api::TrappedAssetsApi<Block>
+		+ parachains_common::runtime_api::XcmpQueueApi<Block>
+		+ parachains_common::runtime_api::XcmLocationApi<Block>
+		+ parachains_common::runtime_api::AssetMetadataApi<Block>
+		+ parachains_common::runtime_api::IntegrityCheckApi<Block>
+		+ parachains_common::runtime_api::XcmWeightApi<Block>,
+	sc_client_api::StateBackendFor<ParachainBackend, Block>: sp_api::StateBackend<BlakeTwo256>,
+	<<AuraId as AppCrypto>::Pair as Pair>::Signature:
+		TryFrom<Vec<u8>> + std::hash::Hash + sp_runtime::traits::Member + Codec,
+{
+	start_aura_node_impl::<RuntimeApi, AuraId>(
+		parachain_config,
+		polkadot_config,
+		collator_options,
+		para_id,
+		hwbench,
+		node_extra_args,
+		false,
+	)
+	.await
+}
+
+/// Start a node of the integritee runtime.
+///
+/// Unlike [`start_generic_aura_node`], the node authors with the relay chain consensus until the
+/// runtime implements Aura, so it keeps authoring across the upgrade from the shell runtime.
+pub async fn start_integritee_node(
+	parachain_config: Configuration,
+	polkadot_config: Configuration,
+	collator_options: CollatorOptions,
+	para_id: ParaId,
+	hwbench: Option<sc_sysinfo::HwBench>,
+	node_extra_args: NodeExtraArgs,
+) -> sc_service::error::Result<(TaskManager, Arc<ParachainClient<parachain_runtime::RuntimeApi>>)> {
+	start_aura_node_impl::<parachain_runtime::RuntimeApi, parachains_common::AuraId>(
+		parachain_config,
+		polkadot_config,
+		collator_options,
+		para_id,
+		hwbench,
+		node_extra_args,
+		true,
+	)
+	.await
+}
+
+/// Start a node authoring with Aura.
+///
+/// With `wait_for_aura`, the node authors with the relay chain consensus as long as the runtime
+/// doesn't implement Aura, see [`WaitForAuraConsensus`].
+async fn start_aura_node_impl<RuntimeApi, AuraId: AppCrypto>(
+	mut parachain_config: Configuration,
+	polkadot_config: Configuration,
+	collator_options: CollatorOptions,
+	para_id: ParaId,
+	hwbench: Option<sc_sysinfo::HwBench>,
+	node_extra_args: NodeExtraArgs,
+	wait_for_aura: bool,
 ) -> sc_service::error::Result<(TaskManager, Arc<ParachainClient<RuntimeApi>>)>
 where
 	RuntimeApi: ConstructRuntimeApi<Block, ParachainClient<RuntimeApi>> + Send + Sync + 'static,
@@ -609,18 +1003,66 @@ where
 		+ cumulus_primitives_core::CollectCollationInfo<Block>
 		+ sp_consensus_aura::AuraApi<Block, <<AuraId as AppCrypto>::Pair as Pair>::Public>
 		+ pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>
-		+ frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
+		+ frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>
+		+ parachains_common::runtime_api::XcmDeliveryFeeApi<Block>
+		+ parachains_common::runtime_api::XtokensFeeApi<Block>
+		+ parachains_common::runtime_api::XcmEventsApi<Block>
+		+ parachains_common::runtime_api::CurrencyRegistryApi<Block>
+		+ parachains_common::runtime_api::TransactDryRunApi<Block>
+		+ parachains_common::runtime_api::BarrierDryRunApi<Block>
+		+ parachains_common::runtime_api::XcmDryRunApi<Block>
+		+ parachains_common::runtime_api::TrappedAssetsApi<Block>
+		+ parachains_common::runtime_api::XcmpQueueApi<Block>
+		+ parachains_common::runtime_api::XcmLocationApi<Block>
+		+ parachains_common::runtime_api::AssetMetadataApi<Block>
+		+ parachains_common::runtime_api::IntegrityCheckApi<Block>
+		+ parachains_common::runtime_api::XcmWeightApi<Block>,
 	sc_client_api::StateBackendFor<ParachainBackend, Block>: sp_api::StateBackend<BlakeTwo256>,
 	<<AuraId as AppCrypto>::Pair as Pair>::Signature:
 		TryFrom<Vec<u8>> + std::hash::Hash + sp_runtime::traits::Member + Codec,
 {
+	node_extra_args
+		.proposal_slot
+		.validate()
+		.map_err(|e| format!("Invalid block proposal slot portions: {}", e))?;
+
+	let authoring_stats =
+		node_extra_args.authoring_stats_file.as_ref().map(|_| AuthoringStats::default());
+	let authoring_stats_export = authoring_stats
+		.clone()
+		.zip(node_extra_args.authoring_stats_file.clone())
+		.map(|(stats, path)| (stats, path, node_extra_args.authoring_stats_interval));
+
+	collator_peers::reserve(&mut parachain_config.network, &node_extra_args.collator_peers);
+	collator_allow_list::restrict(
+		&mut parachain_config.network,
+		&node_extra_args.allowed_collators,
+	);
+	let block_metadata_export = node_extra_args
+		.block_metadata_file
+		.clone()
+		.map(|path| (path, node_extra_args.block_metadata_max_bytes));
+	let relay_parent_age = node_extra_args.max_relay_parent_age.map(RelayParentAgeCheck::new);
+	let relay_parent_age_for_import = relay_parent_age.clone();
+
 	start_node_impl::<RuntimeApi, _, _, _>(
 		parachain_config,
 		polkadot_config,
 		collator_options,
 		para_id,
 		|_| Ok(RpcModule::new(())),
-		aura_build_import_queue::<_, AuraId>,
+		move |client, block_import, config, telemetry, task_manager| {
+			aura_build_import_queue_with_timestamp_grace::<_, AuraId>(
+				client,
+				block_import,
+				config,
+				telemetry,
+				task_manager,
+				node_extra_args.timestamp_grace_ms,
+				node_extra_args.verifier_cross_check,
+				relay_parent_age_for_import,
+			)
+		},
 		|client,
 		 block_import,
 		 prometheus_registry,
@@ -631,75 +1073,418 @@ where
 		 sync_oracle,
 		 keystore,
 		 force_authoring| {
-			let slot_duration = cumulus_client_consensus_aura::slot_duration(&*client).unwrap();
-
-			let proposer_factory = sc_basic_authorship::ProposerFactory::with_proof_recording(
-				task_manager.spawn_handle(),
-				client.clone(),
+			if let Some(max) = node_extra_args.max_extrinsics_per_block {
+				log::warn!("Authored blocks are limited to {} user extrinsics", max);
+			}
+			let transaction_pool = Arc::new(ThrottledTransactionPool::new(
 				transaction_pool,
-				prometheus_registry,
-				telemetry.clone(),
-			);
-
-			Ok(AuraConsensus::build::<<AuraId as AppCrypto>::Pair, _, _, _, _, _, _>(
-				BuildAuraConsensusParams {
-					proposer_factory,
-					create_inherent_data_providers: move |_, (relay_parent, validation_data)| {
-						let relay_chain_interface = relay_chain_interface.clone();
-						async move {
-							let parachain_inherent =
-								cumulus_primitives_parachain_inherent::ParachainInherentData::create_at(
-									relay_parent,
-									&relay_chain_interface,
-									&validation_data,
-									para_id,
-								).await;
-
-							let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
-
-							let slot =
+				node_extra_args.max_extrinsics_per_block,
+			));
+
+			let spawn_handle = task_manager.spawn_handle();
+			let aura_client = client.clone();
+			let aura_block_import = block_import.clone();
+			let aura_transaction_pool = transaction_pool.clone();
+			let aura_prometheus_registry = prometheus_registry.cloned();
+			let aura_telemetry = telemetry.clone();
+			let aura_relay_chain_interface = relay_chain_interface.clone();
+			let aura_sync_oracle = sync_oracle.clone();
+			let aura_keystore = keystore.clone();
+			let proposal_slot = node_extra_args.proposal_slot;
+			let candidate_retry = node_extra_args.candidate_retry;
+			let max_staleness = node_extra_args.max_validation_data_staleness;
+			let aura_consensus = move || {
+				let client = aura_client;
+				let relay_chain_interface = aura_relay_chain_interface;
+				let slot_duration = cumulus_client_consensus_aura::slot_duration(&*client).unwrap();
+
+				let proposer_factory = StatsProposerFactory::new(
+					sc_basic_authorship::ProposerFactory::with_proof_recording(
+						spawn_handle,
+						client.clone(),
+						aura_transaction_pool,
+						aura_prometheus_registry.as_ref(),
+						aura_telemetry.clone(),
+					),
+					authoring_stats,
+				);
+
+				AuraConsensus::build::<<AuraId as AppCrypto>::Pair, _, _, _, _, _, _>(
+					BuildAuraConsensusParams {
+						proposer_factory,
+						create_inherent_data_providers:
+							move |_, (relay_parent, validation_data)| {
+								let relay_chain_interface = relay_chain_interface.clone();
+								async move {
+									let parachain_inherent = create_parachain_inherent(
+										relay_parent,
+										&relay_chain_interface,
+										&validation_data,
+										para_id,
+										candidate_retry,
+										max_staleness,
+									)
+									.await?;
+
+									let timestamp =
+										sp_timestamp::InherentDataProvider::from_system_time();
+
+									let slot =
 								sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
 									*timestamp,
 									slot_duration,
 								);
 
-							let parachain_inherent = parachain_inherent.ok_or_else(|| {
-								Box::<dyn std::error::Error + Send + Sync>::from(
-									"Failed to create parachain inherent",
-								)
-							})?;
+									Ok((slot, timestamp, parachain_inherent))
+								}
+							},
+						block_import: aura_block_import,
+						para_client: client,
+						backoff_authoring_blocks: Option::<()>::None,
+						sync_oracle: aura_sync_oracle,
+						keystore: aura_keystore,
+						force_authoring,
+						slot_duration,
+						block_proposal_slot_portion: proposal_slot.block_proposal_slot_portion(),
+						max_block_proposal_slot_portion: proposal_slot
+							.max_block_proposal_slot_portion(),
+						telemetry: aura_telemetry,
+					},
+				)
+			};
+			if !wait_for_aura {
+				return Ok(aura_consensus())
+			}
 
-							Ok((slot, timestamp, parachain_inherent))
-						}
+			let relay_chain_consensus =
+				cumulus_client_consensus_relay_chain::build_relay_chain_consensus(
+					cumulus_client_consensus_relay_chain::BuildRelayChainConsensusParams {
+						para_id,
+						proposer_factory:
+							sc_basic_authorship::ProposerFactory::with_proof_recording(
+								task_manager.spawn_handle(),
+								client.clone(),
+								transaction_pool,
+								prometheus_registry,
+								telemetry.clone(),
+							),
+						block_import,
+						relay_chain_interface: relay_chain_interface.clone(),
+						create_inherent_data_providers:
+							move |_, (relay_parent, validation_data)| {
+								let relay_chain_interface = relay_chain_interface.clone();
+								async move {
+									create_parachain_inherent(
+										relay_parent,
+										&relay_chain_interface,
+										&validation_data,
+										para_id,
+										candidate_retry,
+										max_staleness,
+									)
+									.await
+								}
+							},
 					},
-					block_import,
-					para_client: client,
-					backoff_authoring_blocks: Option::<()>::None,
-					sync_oracle,
-					keystore,
-					force_authoring,
-					slot_duration,
-					// We got around 500ms for proposing
-					block_proposal_slot_portion: SlotProportion::new(1f32 / 24f32),
-					// And a maximum of 750ms if slots are skipped
-					max_block_proposal_slot_portion: Some(SlotProportion::new(1f32 / 16f32)),
-					telemetry,
-				},
-			))
+				);
+
+			Ok(Box::new(WaitForAuraConsensus::new(
+				ClientAuraApiVersion::<_, <<AuraId as AppCrypto>::Pair as Pair>::Public>::new(
+					client,
+				),
+				aura_consensus,
+				relay_chain_consensus,
+				aura_version::expected_aura_api_version::<
+					<<AuraId as AppCrypto>::Pair as Pair>::Public,
+				>(),
+				telemetry,
+			)))
 		},
 		hwbench,
+		node_extra_args.telemetry_buffer_size,
+		authoring_stats_export,
+		node_extra_args.announce_relay_parent_check,
+		block_metadata_export,
+		node_extra_args.metric_prefix,
+		node_extra_args.relay_chain_slot_duration,
+		relay_parent_age,
+		node_extra_args.check_integrity,
+		node_extra_args.max_concurrent_pov_recoveries,
 	)
 	.await
 }
 
-/// Checks that the hardware meets the requirements and print a warning otherwise.
-fn warn_if_slow_hardware(hwbench: &sc_sysinfo::HwBench) {
+/// Start a node for local development, which seals blocks on its own, without a relay chain.
+///
+/// Blocks are sealed and finalized every `block_time`, or whenever a transaction enters the pool
+/// if it is zero. The `engine_createBlock` and `engine_finalizeBlock` RPCs seal and finalize blocks
+/// on demand on top of that. The relay chain is mocked by the parachain inherent, and timestamps
+/// advance by at least a slot per block, so they run ahead of the clock when sealing quickly.
+pub async fn start_dev_node<RuntimeApi>(
+	config: Configuration,
+	para_id: ParaId,
+	block_time: Duration,
+	telemetry_buffer_size: usize,
+) -> sc_service::error::Result<(TaskManager, Arc<ParachainClient<RuntimeApi>>)>
+where
+	RuntimeApi: ConstructRuntimeApi<Block, ParachainClient<RuntimeApi>> + Send + Sync + 'static,
+	RuntimeApi::RuntimeApi: sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block>
+		+ sp_api::Metadata<Block>
+		+ sp_session::SessionKeys<Block>
+		+ sp_api::ApiExt<
+			Block,
+			StateBackend = sc_client_api::StateBackendFor<ParachainBackend, Block>,
+		> + sp_offchain::OffchainWorkerApi<Block>
+		+ sp_block_builder::BlockBuilder<Block>
+		+ sp_consensus_aura::AuraApi<Block, parachains_common::AuraId>
+		+ pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>
+		+ frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>
+		+ parachains_common::runtime_api::XcmDeliveryFeeApi<Block>
+		+ parachains_common::runtime_api::XtokensFeeApi<Block>
+		+ parachains_common::runtime_api::XcmEventsApi<Block>
+		+ parachains_common::runtime_api::CurrencyRegistryApi<Block>
+		+ parachains_common::runtime_api::TransactDryRunApi<Block>
+		+ parachains_common::runtime_api::BarrierDryRunApi<Block>
+		+ parachains_common::runtime_api::XcmDryRunApi<Block>
+		+ parachains_common::runtime_api::TrappedAssetsApi<Block>
+		+ parachains_common::runtime_api::XcmpQueueApi<Block>
+		+ parachains_common::runtime_api::XcmWeightApi<Block>
+		+ parachains_common::runtime_api::XcmLocationApi<Block>
+		+ parachains_common::runtime_api::AssetMetadataApi<Block>,
+	sc_client_api::StateBackendFor<ParachainBackend, Block>: sp_api::StateBackend<BlakeTwo256>,
+{
+	let params = new_partial::<RuntimeApi, _>(
+		&config,
+		|_, block_import, config, _, task_manager| {
+			Ok(sc_consensus_manual_seal::import_queue(
+				Box::new(block_import),
+				&task_manager.spawn_essential_handle(),
+				config.prometheus_registry(),
+			))
+		},
+		telemetry_buffer_size,
+	)?;
+	let (block_import, mut telemetry, _) = params.other;
+
+	let client = params.client.clone();
+	let backend = params.backend.clone();
+	let mut task_manager = params.task_manager;
+	let transaction_pool = params.transaction_pool.clone();
+	let prometheus_registry = config.prometheus_registry().cloned();
+
+	let (network, system_rpc_tx, tx_handler_controller, start_network, sync_service) =
+		sc_service::build_network(sc_service::BuildNetworkParams {
+			config: &config,
+			client: client.clone(),
+			transaction_pool: transaction_pool.clone(),
+			spawn_handle: task_manager.spawn_handle(),
+			import_queue: params.import_queue,
+			block_announce_validator_builder: None,
+			warp_sync_params: None,
+		})?;
+
+	// Commands of the `engine_*` RPCs, merged with the ones sealing blocks periodically.
+	let (manual_seal, rpc_commands) = futures::channel::mpsc::channel(1024);
+	let seal = move || EngineCommand::SealNewBlock {
+		create_empty: !block_time.is_zero(),
+		finalize: true,
+		parent_hash: None,
+		sender: None,
+	};
+	let seal_commands = if block_time.is_zero() {
+		transaction_pool.import_notification_stream().map(move |_| seal()).boxed()
+	} else {
+		futures::stream::unfold((), move |()| async move {
+			futures_timer::Delay::new(block_time).await;
+			Some((seal(), ()))
+		})
+		.boxed()
+	};
+
+	let rpc_builder = {
+		let client = client.clone();
+		let transaction_pool = transaction_pool.clone();
+
+		let backend_for_rpc = backend.clone();
+		Box::new(move |deny_unsafe, subscription_executor| {
+			let deps = rpc::FullDeps {
+				client: client.clone(),
+				pool: transaction_pool.clone(),
+				deny_unsafe,
+				subscription_executor,
+				relay_resync: None,
+				manual_seal: Some(manual_seal.clone()),
+			};
+
+			rpc::create_full(deps, backend_for_rpc.clone()).map_err(Into::into)
+		})
+	};
+
+	sc_service::spawn_tasks(sc_service::SpawnTasksParams {
+		rpc_builder,
+		client: client.clone(),
+		transaction_pool: transaction_pool.clone(),
+		task_manager: &mut task_manager,
+		config,
+		keystore: params.keystore_container.keystore(),
+		backend: backend.clone(),
+		network,
+		sync_service,
+		system_rpc_tx,
+		tx_handler_controller,
+		telemetry: telemetry.as_mut(),
+	})?;
+
+	let proposer_factory = sc_basic_authorship::ProposerFactory::new(
+		task_manager.spawn_handle(),
+		client.clone(),
+		transaction_pool.clone(),
+		prometheus_registry.as_ref(),
+		telemetry.as_ref().map(|telemetry| telemetry.handle()),
+	);
+
+	let slot_duration = cumulus_client_consensus_aura::slot_duration(&*client)?;
+	// The timestamp of the last sealed block, Aura requires every block to be in a later slot.
+	let last_timestamp = Arc::new(AtomicU64::new(0));
+	let create_inherent_data_providers = {
+		let client = client.clone();
+		move |parent: Hash, ()| {
+			let client = client.clone();
+			let last_timestamp = last_timestamp.clone();
+			async move {
+				let current_para_block = client.number(parent)?.unwrap_or_default();
+				let parachain_inherent = MockValidationDataInherentDataProvider {
+					current_para_block,
+					relay_offset: 1000,
+					relay_blocks_per_para_block: 2,
+					para_blocks_per_relay_epoch: 0,
+					relay_randomness_config: (),
+					xcm_config: MockXcmConfig::new(&*client, parent, para_id, Default::default()),
+					raw_downward_messages: Vec::new(),
+					raw_horizontal_messages: Vec::new(),
+				};
+
+				let next = last_timestamp.load(Ordering::Relaxed) + slot_duration.as_millis();
+				let now = sp_timestamp::Timestamp::current().as_millis().max(next);
+				last_timestamp.store(now, Ordering::Relaxed);
+				let timestamp = sp_timestamp::InherentDataProvider::new(now.into());
+
+				Ok::<_, Box<dyn std::error::Error + Send + Sync>>((timestamp, parachain_inherent))
+			}
+		}
+	};
+
+	task_manager.spawn_essential_handle().spawn_blocking(
+		"manual-seal",
+		None,
+		sc_consensus_manual_seal::run_manual_seal(ManualSealParams {
+			block_import,
+			env: proposer_factory,
+			client: client.clone(),
+			pool: transaction_pool,
+			commands_stream: futures::stream::select(rpc_commands, seal_commands),
+			select_chain: LongestChain::new(backend),
+			consensus_data_provider: Some(Box::new(AuraConsensusDataProvider::new(client.clone()))),
+			create_inherent_data_providers,
+		}),
+	);
+
+	if block_time.is_zero() {
+		log::info!("🛠️  Development node, sealing a block for every transaction");
+	} else {
+		log::info!("🛠️  Development node, sealing a block every {:?}", block_time);
+	}
+	start_network.start_network();
+
+	Ok((task_manager, client))
+}
+
+/// Checks that the hardware meets the requirements.
+fn meets_reference_hardware(hwbench: &sc_sysinfo::HwBench) -> bool {
 	// Polkadot para-chains should generally use these requirements to ensure that the relay-chain
 	// will not take longer than expected to import its blocks.
-	if !frame_benchmarking_cli::SUBSTRATE_REFERENCE_HARDWARE.check_hardware(hwbench) {
-		log::warn!(
-			"⚠️  The hardware does not meet the minimal requirements for role 'Authority' find out more at:\n\
-			https://wiki.polkadot.network/docs/maintain-guides-how-to-validate-polkadot#reference-hardware"
-		);
+	frame_benchmarking_cli::SUBSTRATE_REFERENCE_HARDWARE.check_hardware(hwbench)
+}
+
+/// Warn that the hardware does not meet the requirements for collators.
+fn warn_slow_hardware() {
+	log::warn!(
+		"⚠️  The hardware does not meet the minimal requirements for role 'Authority' find out more at:\n\
+		https://wiki.polkadot.network/docs/maintain-guides-how-to-validate-polkadot#reference-hardware"
+	);
+}
+
+/// The parachain role of the node as shown in the startup summary.
+fn node_role(role: &sc_service::Role) -> &'static str {
+	match role {
+		sc_service::Role::Authority => "collator",
+		sc_service::Role::Full => "full",
+		sc_service::Role::Light => "observer",
+	}
+}
+
+/// Log a single machine readable line summarizing the node's setup.
+///
+/// Must only contain public information, never log anything related to keys here.
+fn log_startup_summary(
+	para_id: ParaId,
+	role: &str,
+	relay_chain: &str,
+	relay_chain_slot_duration: Duration,
+	meets_reference_hardware: Option<bool>,
+) {
+	let hardware = match meets_reference_hardware {
+		Some(true) => "passed",
+		Some(false) => "failed",
+		None => "skipped",
+	};
+
+	log::info!(
+		"📋 Startup summary: para_id={} role={} relay_chain={} relay_slot_ms={} \
+		reference_hardware={} xcm_version={}",
+		u32::from(para_id),
+		role,
+		relay_chain,
+		relay_chain_slot_duration.as_millis(),
+		hardware,
+		xcm::latest::VERSION,
+	);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_timestamp::Timestamp;
+
+	/// How far `pallet_timestamp` lets the timestamp of a block be ahead of the one it is checked
+	/// against.
+	const MAX_TIMESTAMP_DRIFT_MS: u64 = 30 * 1000;
+
+	const NOW: u64 = 1_000_000;
+
+	/// Whether the runtime accepts a block with `timestamp` on import at [`NOW`], like the inherent
+	/// check of `pallet_timestamp`.
+	fn accepts(timestamp: u64, timestamp_grace_ms: u64) -> bool {
+		let checked_against = graced_timestamp(Timestamp::new(NOW), timestamp_grace_ms);
+		Timestamp::new(timestamp) <= checked_against + MAX_TIMESTAMP_DRIFT_MS
+	}
+
+	#[test]
+	fn blocks_inside_the_grace_window_are_accepted() {
+		assert!(accepts(NOW + MAX_TIMESTAMP_DRIFT_MS + 500, 1_000));
+		assert!(accepts(NOW + MAX_TIMESTAMP_DRIFT_MS + 1_000, 1_000));
+	}
+
+	#[test]
+	fn blocks_just_after_the_grace_window_are_rejected() {
+		assert!(!accepts(NOW + MAX_TIMESTAMP_DRIFT_MS + 1_001, 1_000));
+		assert!(!accepts(NOW + MAX_TIMESTAMP_DRIFT_MS + 1, 0));
+	}
+
+	#[test]
+	fn grace_window_is_capped() {
+		let edge = NOW + MAX_TIMESTAMP_DRIFT_MS + MAX_TIMESTAMP_GRACE_MS;
+		assert!(accepts(edge, u64::MAX));
+		assert!(!accepts(edge + 1, u64::MAX));
 	}
 }
@@ -0,0 +1,145 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Refuse to produce candidates on stale validation data.
+//!
+//! The validation data of a candidate is read at its relay parent. A collator whose relay chain
+//! node lags behind, e.g. because it is still importing, keeps building on relay parents the relay
+//! chain has long moved past. Such candidates keep the parachain live, but are built on outdated
+//! relay state and messages and are likely to miss their backing. Operators can bound how many
+//! relay blocks the relay parent may lag behind the relay chain's best block, see [`check`]. A
+//! tolerance of 0 only builds on the best block.
+//!
+//! Without a tolerance, candidates are built on any relay parent.
+
+use crate::relay_parent_age::{self, RelayBest};
+use cumulus_primitives_core::relay_chain::BlockNumber as RelayBlockNumber;
+use std::fmt;
+
+const LOG_TARGET: &str = "stale-validation-data";
+
+/// The validation data of a candidate is too old to build on.
+#[derive(Debug, PartialEq)]
+pub struct StaleValidationData {
+	pub relay_parent_number: RelayBlockNumber,
+	pub best_number: RelayBlockNumber,
+	pub tolerance: RelayBlockNumber,
+}
+
+impl fmt::Display for StaleValidationData {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"the validation data of relay parent #{} is {} relay blocks behind the relay chain's \
+			best block #{}, more than the tolerated {}",
+			self.relay_parent_number,
+			self.best_number - self.relay_parent_number,
+			self.best_number,
+			self.tolerance,
+		)
+	}
+}
+
+impl std::error::Error for StaleValidationData {}
+
+/// Check that the relay parent `relay_parent_number` lags at most `tolerance` relay blocks behind
+/// the relay chain's best block.
+///
+/// Passes if the relay chain's best block can't be looked up, building the parachain inherent is
+/// going to fail anyway in that case.
+pub async fn check<R: RelayBest>(
+	relay: &R,
+	relay_parent_number: RelayBlockNumber,
+	tolerance: RelayBlockNumber,
+) -> Result<(), StaleValidationData> {
+	let best_number = match relay.best_number().await {
+		Ok(Some(best_number)) => best_number,
+		Ok(None) => return Ok(()),
+		Err(e) => {
+			log::debug!(
+				target: LOG_TARGET,
+				"Could not look up the relay chain's best block: {}",
+				e
+			);
+			return Ok(())
+		},
+	};
+	if relay_parent_age::is_too_old(relay_parent_number, best_number, tolerance) {
+		let stale = StaleValidationData { relay_parent_number, best_number, tolerance };
+		log::warn!(target: LOG_TARGET, "Skipping block production, {}", stale);
+		return Err(stale)
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use cumulus_relay_chain_interface::{RelayChainError, RelayChainResult};
+	use futures::executor::block_on;
+
+	#[derive(Clone)]
+	struct MockRelay(Option<RelayBlockNumber>);
+
+	#[async_trait::async_trait]
+	impl RelayBest for MockRelay {
+		async fn best_number(&self) -> RelayChainResult<Option<RelayBlockNumber>> {
+			self.0
+				.ok_or_else(|| RelayChainError::GenericError("unreachable".into()))
+				.map(Some)
+		}
+	}
+
+	#[test]
+	fn validation_data_within_the_tolerance_is_used() {
+		let relay = MockRelay(Some(105));
+		assert_eq!(block_on(check(&relay, 105, 5)), Ok(()));
+		assert_eq!(block_on(check(&relay, 100, 5)), Ok(()));
+	}
+
+	#[test]
+	fn validation_data_beyond_the_tolerance_is_refused() {
+		let relay = MockRelay(Some(106));
+		assert_eq!(
+			block_on(check(&relay, 100, 5)),
+			Err(StaleValidationData { relay_parent_number: 100, best_number: 106, tolerance: 5 })
+		);
+	}
+
+	#[test]
+	fn zero_tolerance_requires_the_best_block() {
+		let relay = MockRelay(Some(100));
+		assert_eq!(block_on(check(&relay, 100, 0)), Ok(()));
+		assert!(block_on(check(&relay, 99, 0)).is_err());
+	}
+
+	#[test]
+	fn unknown_best_block_passes() {
+		assert_eq!(block_on(check(&MockRelay(None), 100, 0)), Ok(()));
+	}
+
+	#[test]
+	fn refusal_tells_how_stale_the_data_is() {
+		let stale =
+			StaleValidationData { relay_parent_number: 100, best_number: 108, tolerance: 5 };
+		assert_eq!(
+			stale.to_string(),
+			"the validation data of relay parent #100 is 8 relay blocks behind the relay chain's \
+			best block #108, more than the tolerated 5"
+		);
+	}
+}
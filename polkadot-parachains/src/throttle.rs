@@ -0,0 +1,198 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Limit the number of user extrinsics the block proposer includes per block.
+//!
+//! Only meant for load tests, where one wants to observe the chain at a specific throughput
+//! instead of having the proposer fill the blocks greedily.
+//!
+//! The proposer pulls user extrinsics from the transaction pool's ready queue, hence we wrap the
+//! pool and cut the ready queue after the configured number of transactions. Inherents don't come
+//! from the pool, so they are always included.
+
+use futures::{future::FutureExt, Future};
+use sc_transaction_pool_api::{
+	ImportNotificationStream, PoolFuture, PoolStatus, ReadyTransactions, TransactionFor,
+	TransactionPool, TransactionSource, TransactionStatusStreamFor, TxHash,
+};
+use sp_runtime::{generic::BlockId, traits::NumberFor};
+use std::{collections::HashMap, pin::Pin, sync::Arc};
+
+/// A transaction pool that hands out at most `max_extrinsics` ready transactions at once.
+///
+/// Everything apart from the ready queue is passed through to the inner pool. Without a limit
+/// the pool behaves exactly like the inner one.
+pub struct ThrottledTransactionPool<P> {
+	inner: Arc<P>,
+	max_extrinsics: Option<usize>,
+}
+
+impl<P> ThrottledTransactionPool<P> {
+	/// Wrap `inner`, limiting its ready queue to `max_extrinsics` transactions.
+	pub fn new(inner: Arc<P>, max_extrinsics: Option<usize>) -> Self {
+		Self { inner, max_extrinsics }
+	}
+}
+
+impl<P: TransactionPool> TransactionPool for ThrottledTransactionPool<P> {
+	type Block = P::Block;
+	type Hash = P::Hash;
+	type InPoolTransaction = P::InPoolTransaction;
+	type Error = P::Error;
+
+	fn submit_at(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xts: Vec<TransactionFor<Self>>,
+	) -> PoolFuture<Vec<Result<TxHash<Self>, Self::Error>>, Self::Error> {
+		self.inner.submit_at(at, source, xts)
+	}
+
+	fn submit_one(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<TxHash<Self>, Self::Error> {
+		self.inner.submit_one(at, source, xt)
+	}
+
+	fn submit_and_watch(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<Pin<Box<TransactionStatusStreamFor<Self>>>, Self::Error> {
+		self.inner.submit_and_watch(at, source, xt)
+	}
+
+	fn ready_at(
+		&self,
+		at: NumberFor<Self::Block>,
+	) -> Pin<
+		Box<
+			dyn Future<
+					Output = Box<dyn ReadyTransactions<Item = Arc<Self::InPoolTransaction>> + Send>,
+				> + Send,
+		>,
+	> {
+		let Some(max_extrinsics) = self.max_extrinsics else {
+			return self.inner.ready_at(at)
+		};
+		self.inner
+			.ready_at(at)
+			.map(move |ready| {
+				Box::new(LimitedReadyTransactions::new(ready, max_extrinsics))
+					as Box<dyn ReadyTransactions<Item = _> + Send>
+			})
+			.boxed()
+	}
+
+	fn ready(&self) -> Box<dyn ReadyTransactions<Item = Arc<Self::InPoolTransaction>> + Send> {
+		match self.max_extrinsics {
+			Some(max_extrinsics) =>
+				Box::new(LimitedReadyTransactions::new(self.inner.ready(), max_extrinsics)),
+			None => self.inner.ready(),
+		}
+	}
+
+	fn remove_invalid(&self, hashes: &[TxHash<Self>]) -> Vec<Arc<Self::InPoolTransaction>> {
+		self.inner.remove_invalid(hashes)
+	}
+
+	fn status(&self) -> PoolStatus {
+		self.inner.status()
+	}
+
+	fn import_notification_stream(&self) -> ImportNotificationStream<TxHash<Self>> {
+		self.inner.import_notification_stream()
+	}
+
+	fn on_broadcasted(&self, propagations: HashMap<TxHash<Self>, Vec<String>>) {
+		self.inner.on_broadcasted(propagations)
+	}
+
+	fn hash_of(&self, xt: &TransactionFor<Self>) -> TxHash<Self> {
+		self.inner.hash_of(xt)
+	}
+
+	fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>> {
+		self.inner.ready_transaction(hash)
+	}
+}
+
+/// Ready queue that ends after `remaining` transactions.
+struct LimitedReadyTransactions<T> {
+	inner: Box<dyn ReadyTransactions<Item = T> + Send>,
+	remaining: usize,
+}
+
+impl<T> LimitedReadyTransactions<T> {
+	fn new(inner: Box<dyn ReadyTransactions<Item = T> + Send>, limit: usize) -> Self {
+		Self { inner, remaining: limit }
+	}
+}
+
+impl<T> Iterator for LimitedReadyTransactions<T> {
+	type Item = T;
+
+	fn next(&mut self) -> Option<T> {
+		if self.remaining == 0 {
+			return None
+		}
+		self.remaining -= 1;
+		self.inner.next()
+	}
+}
+
+impl<T> ReadyTransactions for LimitedReadyTransactions<T> {
+	fn report_invalid(&mut self, tx: &T) {
+		self.inner.report_invalid(tx)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct Ready(std::ops::Range<u32>);
+
+	impl Iterator for Ready {
+		type Item = u32;
+
+		fn next(&mut self) -> Option<u32> {
+			self.0.next()
+		}
+	}
+
+	impl ReadyTransactions for Ready {
+		fn report_invalid(&mut self, _tx: &u32) {}
+	}
+
+	#[test]
+	fn ready_transactions_are_cut_at_the_limit() {
+		let ready = LimitedReadyTransactions::new(Box::new(Ready(0..10)), 3);
+		assert_eq!(ready.collect::<Vec<_>>(), vec![0, 1, 2]);
+	}
+
+	#[test]
+	fn ready_transactions_below_the_limit_are_all_returned() {
+		let ready = LimitedReadyTransactions::new(Box::new(Ready(0..2)), 3);
+		assert_eq!(ready.collect::<Vec<_>>(), vec![0, 1]);
+	}
+}
@@ -0,0 +1,220 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Diagnostic cross-check of the Aura and the relay chain verifier.
+//!
+//! The import queue verifies a block with Aura if the runtime of its parent has a suitable version
+//! of the Aura API (see [`crate::aura_version`]), and with the relay chain verifier otherwise. To
+//! catch bugs around the runtime upgrade switching from one to the other, blocks close to the
+//! upgrade can be verified by both of them. Only the verifier that would have been chosen anyway
+//! decides about the import; a disagreement of the other one is merely logged.
+
+use parachains_common::{Block, Hash};
+use sc_consensus::{import_queue::Verifier as VerifierT, BlockImportParams};
+use sp_runtime::traits::Header as HeaderT;
+
+const LOG_TARGET: &str = "verifier-cross-check";
+
+/// Number of blocks after the Aura upgrade which are verified by both verifiers.
+pub const CROSS_CHECK_WINDOW: u32 = 10;
+
+/// Whether the block built on `parent` is within [`CROSS_CHECK_WINDOW`] blocks of the Aura upgrade,
+/// i.e. whether Aura availability changed between `parent` and its ancestor that many blocks up.
+pub fn near_aura_upgrade(
+	parent: Hash,
+	has_aura: impl Fn(Hash) -> bool,
+	parent_of: impl Fn(Hash) -> Option<Hash>,
+) -> bool {
+	let mut ancestor = parent;
+	for _ in 0..CROSS_CHECK_WINDOW {
+		match parent_of(ancestor) {
+			Some(hash) => ancestor = hash,
+			None => break,
+		}
+	}
+	ancestor != parent && has_aura(parent) != has_aura(ancestor)
+}
+
+/// Outcome of a cross-check.
+#[derive(Debug, PartialEq, Eq)]
+pub enum CrossCheck {
+	/// Both verifiers came to the same decision.
+	Agreed,
+	/// The verifier deciding about the import accepted the block, the other one rejected it.
+	OnlyPrimaryAccepted(String),
+	/// The verifier deciding about the import rejected the block, the other one accepted it.
+	OnlyShadowAccepted(String),
+}
+
+/// A copy of the parts of `params` a verifier looks at.
+fn shadow_params(params: &BlockImportParams<Block, ()>) -> BlockImportParams<Block, ()> {
+	let mut shadow = BlockImportParams::new(params.origin, params.header.clone());
+	shadow.justifications = params.justifications.clone();
+	shadow.body = params.body.clone();
+	shadow.indexed_body = params.indexed_body.clone();
+	shadow.finalized = params.finalized;
+	shadow.fork_choice = params.fork_choice;
+	shadow.import_existing = params.import_existing;
+	shadow
+}
+
+/// Verify a block with both verifiers, logging any disagreement.
+///
+/// The result of `primary` is returned as is, whatever `shadow` thinks of the block.
+pub async fn verify_both(
+	(primary_name, primary): (&str, &mut dyn VerifierT<Block>),
+	(shadow_name, shadow): (&str, &mut dyn VerifierT<Block>),
+	block_import: BlockImportParams<Block, ()>,
+) -> (Result<BlockImportParams<Block, ()>, String>, CrossCheck) {
+	let number = *block_import.header.number();
+	let hash = block_import.header.hash();
+	let shadow_import = shadow_params(&block_import);
+
+	let result = primary.verify(block_import).await;
+	let shadow_result = shadow.verify(shadow_import).await;
+
+	let cross_check = match (&result, shadow_result) {
+		(Ok(_), Err(e)) => CrossCheck::OnlyPrimaryAccepted(e),
+		(Err(e), Ok(_)) => CrossCheck::OnlyShadowAccepted(e.clone()),
+		_ => CrossCheck::Agreed,
+	};
+	match &cross_check {
+		CrossCheck::Agreed => log::debug!(
+			target: LOG_TARGET,
+			"{} and {} verifier agree on block #{} ({})",
+			primary_name,
+			shadow_name,
+			number,
+			hash,
+		),
+		CrossCheck::OnlyPrimaryAccepted(e) => log::warn!(
+			target: LOG_TARGET,
+			"{} verifier accepted block #{} ({}), {} verifier rejected it: {}",
+			primary_name,
+			number,
+			hash,
+			shadow_name,
+			e,
+		),
+		CrossCheck::OnlyShadowAccepted(e) => log::warn!(
+			target: LOG_TARGET,
+			"{} verifier rejected block #{} ({}): {}, {} verifier accepted it",
+			primary_name,
+			number,
+			hash,
+			e,
+			shadow_name,
+		),
+	}
+
+	(result, cross_check)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::executor::block_on;
+	use parachains_common::Header;
+	use sp_consensus::BlockOrigin;
+	use std::collections::HashMap;
+
+	struct MockVerifier {
+		accept: bool,
+		verified: usize,
+	}
+
+	impl MockVerifier {
+		fn new(accept: bool) -> Self {
+			Self { accept, verified: 0 }
+		}
+	}
+
+	#[async_trait::async_trait]
+	impl VerifierT<Block> for MockVerifier {
+		async fn verify(
+			&mut self,
+			block_import: BlockImportParams<Block, ()>,
+		) -> Result<BlockImportParams<Block, ()>, String> {
+			self.verified += 1;
+			if self.accept {
+				Ok(block_import)
+			} else {
+				Err("bad seal".into())
+			}
+		}
+	}
+
+	fn block_import() -> BlockImportParams<Block, ()> {
+		let header = Header {
+			parent_hash: Default::default(),
+			number: 100,
+			state_root: Default::default(),
+			extrinsics_root: Default::default(),
+			digest: Default::default(),
+		};
+		BlockImportParams::new(BlockOrigin::NetworkBroadcast, header)
+	}
+
+	fn cross_check(primary_accepts: bool, shadow_accepts: bool) -> (bool, CrossCheck) {
+		let mut primary = MockVerifier::new(primary_accepts);
+		let mut shadow = MockVerifier::new(shadow_accepts);
+		let (result, cross_check) = block_on(verify_both(
+			("Aura", &mut primary),
+			("relay chain", &mut shadow),
+			block_import(),
+		));
+		assert_eq!((primary.verified, shadow.verified), (1, 1));
+		(result.is_ok(), cross_check)
+	}
+
+	#[test]
+	fn agreement_is_not_reported() {
+		assert_eq!(cross_check(true, true), (true, CrossCheck::Agreed));
+		assert_eq!(cross_check(false, false), (false, CrossCheck::Agreed));
+	}
+
+	#[test]
+	fn disagreement_does_not_change_the_import_decision() {
+		assert_eq!(
+			cross_check(true, false),
+			(true, CrossCheck::OnlyPrimaryAccepted("bad seal".into()))
+		);
+		assert_eq!(
+			cross_check(false, true),
+			(false, CrossCheck::OnlyShadowAccepted("bad seal".into()))
+		);
+	}
+
+	#[test]
+	fn only_blocks_near_the_upgrade_are_cross_checked() {
+		// A chain of 40 blocks whose runtime has Aura from block 20 on.
+		let hash = |n: u8| Hash::repeat_byte(n);
+		let parents: HashMap<Hash, Hash> = (1..40).map(|n| (hash(n), hash(n - 1))).collect();
+		let parent_of = |h: Hash| parents.get(&h).copied();
+		let has_aura = |h: Hash| (20..40).any(|n| hash(n) == h);
+
+		let near = |parent: u8| near_aura_upgrade(hash(parent), has_aura, parent_of);
+		assert!(!near(15));
+		assert!(!near(19));
+		assert!(near(20));
+		assert!(near(29));
+		assert!(!near(30));
+		// Close to genesis, the window is cut short.
+		assert!(!near(5));
+		assert!(!near(0));
+	}
+}
@@ -0,0 +1,279 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Author with the relay chain consensus until the runtime implements Aura.
+//!
+//! The collators of a parachain starting with a runtime without Aura, like the original shell
+//! runtime, author with the relay chain consensus. [`WaitForAuraConsensus`] looks up the `AuraApi`
+//! version of the parent block's runtime for every candidate, and switches to Aura once a runtime
+//! upgrade brings it, see [`crate::aura_version`]. The Aura consensus is only built then, as it needs the slot duration
+//! of the runtime. The switch is logged and sent to telemetry once.
+
+use crate::aura_version::{self, AuraVersionGuard};
+use codec::Codec;
+use cumulus_client_consensus_common::{ParachainCandidate, ParachainConsensus};
+use cumulus_primitives_core::relay_chain::{Hash as PHash, PersistedValidationData};
+use futures::lock::Mutex;
+use parachains_common::{Block, Hash, Header};
+use sc_telemetry::TelemetryHandle;
+use sp_api::ProvideRuntimeApi;
+use sp_consensus_aura::AuraApi;
+use sp_runtime::traits::Header as HeaderT;
+use std::{marker::PhantomData, sync::Arc};
+
+/// A value which is built when it is first accessed.
+pub enum BuildOnAccess<R> {
+	Uninitialized(Option<Box<dyn FnOnce() -> R + Send + Sync>>),
+	Initialized(R),
+}
+
+impl<R> BuildOnAccess<R> {
+	/// The value, building it if it hasn't been built yet.
+	pub fn get_mut(&mut self) -> &mut R {
+		loop {
+			match self {
+				Self::Uninitialized(f) => {
+					*self = Self::Initialized((f.take().unwrap())());
+				},
+				Self::Initialized(ref mut r) => return r,
+			}
+		}
+	}
+}
+
+/// Looks up the version of the `AuraApi` the runtime of a block implements.
+pub trait AuraApiVersion: Send + Sync + 'static {
+	/// The version of the `AuraApi` the runtime at `hash` implements, `None` if it doesn't.
+	fn aura_api_version(&self, hash: Hash) -> Option<u32>;
+}
+
+/// The [`AuraApiVersion`] of the runtimes of a client.
+pub struct ClientAuraApiVersion<Client, AuraId> {
+	client: Arc<Client>,
+	_phantom: PhantomData<AuraId>,
+}
+
+impl<Client, AuraId> ClientAuraApiVersion<Client, AuraId> {
+	pub fn new(client: Arc<Client>) -> Self {
+		Self { client, _phantom: PhantomData }
+	}
+}
+
+impl<Client, AuraId> AuraApiVersion for ClientAuraApiVersion<Client, AuraId>
+where
+	Client: ProvideRuntimeApi<Block> + Send + Sync + 'static,
+	Client::Api: AuraApi<Block, AuraId>,
+	AuraId: Codec + Send + Sync + 'static,
+{
+	fn aura_api_version(&self, hash: Hash) -> Option<u32> {
+		aura_version::aura_api_version::<_, AuraId>(&*self.client, hash)
+	}
+}
+
+/// Special [`ParachainConsensus`] implementation that waits for the upgrade from
+/// shell to a parachain runtime that implements Aura.
+pub struct WaitForAuraConsensus<V> {
+	versions: Arc<V>,
+	aura_consensus: Arc<Mutex<BuildOnAccess<Box<dyn ParachainConsensus<Block>>>>>,
+	relay_chain_consensus: Arc<Mutex<Box<dyn ParachainConsensus<Block>>>>,
+	aura_version: Arc<AuraVersionGuard>,
+}
+
+impl<V> Clone for WaitForAuraConsensus<V> {
+	fn clone(&self) -> Self {
+		Self {
+			versions: self.versions.clone(),
+			aura_consensus: self.aura_consensus.clone(),
+			relay_chain_consensus: self.relay_chain_consensus.clone(),
+			aura_version: self.aura_version.clone(),
+		}
+	}
+}
+
+impl<V: AuraApiVersion> WaitForAuraConsensus<V> {
+	/// Author with `relay_chain_consensus` until the runtime implements at least version
+	/// `expected_version` of the `AuraApi`, then with the Aura consensus `build_aura` builds. The
+	/// switch is reported to `telemetry`.
+	pub fn new(
+		versions: V,
+		build_aura: impl FnOnce() -> Box<dyn ParachainConsensus<Block>> + Send + Sync + 'static,
+		relay_chain_consensus: Box<dyn ParachainConsensus<Block>>,
+		expected_version: u32,
+		telemetry: Option<TelemetryHandle>,
+	) -> Self {
+		Self {
+			versions: Arc::new(versions),
+			aura_consensus: Arc::new(Mutex::new(BuildOnAccess::Uninitialized(Some(Box::new(
+				build_aura,
+			))))),
+			relay_chain_consensus: Arc::new(Mutex::new(relay_chain_consensus)),
+			aura_version: Arc::new(AuraVersionGuard::new(expected_version, "authoring", telemetry)),
+		}
+	}
+}
+
+#[async_trait::async_trait]
+impl<V: AuraApiVersion> ParachainConsensus<Block> for WaitForAuraConsensus<V> {
+	async fn produce_candidate(
+		&mut self,
+		parent: &Header,
+		relay_parent: PHash,
+		validation_data: &PersistedValidationData,
+	) -> Option<ParachainCandidate<Block>> {
+		let version = self.versions.aura_api_version(parent.hash());
+		if self.aura_version.use_aura_at(version, parent.number() + 1) {
+			self.aura_consensus
+				.lock()
+				.await
+				.get_mut()
+				.produce_candidate(parent, relay_parent, validation_data)
+				.await
+		} else {
+			self.relay_chain_consensus
+				.lock()
+				.await
+				.produce_candidate(parent, relay_parent, validation_data)
+				.await
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::executor::block_on;
+	use std::sync::{
+		atomic::{AtomicBool, AtomicU32, Ordering},
+		Mutex as StdMutex,
+	};
+
+	/// A runtime whose `AuraApi` version can be changed, as by a runtime upgrade.
+	#[derive(Clone, Default)]
+	struct MockVersions(Arc<StdMutex<Option<u32>>>);
+
+	impl MockVersions {
+		fn upgrade(&self, version: Option<u32>) {
+			*self.0.lock().unwrap() = version;
+		}
+	}
+
+	impl AuraApiVersion for MockVersions {
+		fn aura_api_version(&self, _hash: Hash) -> Option<u32> {
+			*self.0.lock().unwrap()
+		}
+	}
+
+	/// Counts the candidates it is asked to produce.
+	#[derive(Clone, Default)]
+	struct MockConsensus(Arc<AtomicU32>);
+
+	impl MockConsensus {
+		fn produced(&self) -> u32 {
+			self.0.load(Ordering::Relaxed)
+		}
+	}
+
+	#[async_trait::async_trait]
+	impl ParachainConsensus<Block> for MockConsensus {
+		async fn produce_candidate(
+			&mut self,
+			_parent: &Header,
+			_relay_parent: PHash,
+			_validation_data: &PersistedValidationData,
+		) -> Option<ParachainCandidate<Block>> {
+			self.0.fetch_add(1, Ordering::Relaxed);
+			None
+		}
+	}
+
+	struct Setup {
+		versions: MockVersions,
+		aura: MockConsensus,
+		aura_built: Arc<AtomicBool>,
+		relay_chain: MockConsensus,
+		consensus: WaitForAuraConsensus<MockVersions>,
+	}
+
+	fn setup() -> Setup {
+		let versions = MockVersions::default();
+		let aura = MockConsensus::default();
+		let aura_built = Arc::new(AtomicBool::new(false));
+		let relay_chain = MockConsensus::default();
+
+		let build_aura = {
+			let aura = aura.clone();
+			let aura_built = aura_built.clone();
+			move || -> Box<dyn ParachainConsensus<Block>> {
+				aura_built.store(true, Ordering::Relaxed);
+				Box::new(aura)
+			}
+		};
+		let consensus = WaitForAuraConsensus::new(
+			versions.clone(),
+			build_aura,
+			Box::new(relay_chain.clone()),
+			1,
+			None,
+		);
+		Setup { versions, aura, aura_built, relay_chain, consensus }
+	}
+
+	fn produce(consensus: &mut WaitForAuraConsensus<MockVersions>) {
+		let parent = Header::new(
+			1,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		);
+		block_on(consensus.produce_candidate(&parent, PHash::zero(), &Default::default()));
+	}
+
+	#[test]
+	fn shell_runtime_authors_with_the_relay_chain_consensus() {
+		let mut setup = setup();
+
+		produce(&mut setup.consensus);
+		assert_eq!(setup.relay_chain.produced(), 1);
+		assert_eq!(setup.aura.produced(), 0);
+		// Building Aura would fail on a runtime without it.
+		assert!(!setup.aura_built.load(Ordering::Relaxed));
+	}
+
+	#[test]
+	fn runtime_upgrade_switches_to_aura() {
+		let mut setup = setup();
+		produce(&mut setup.consensus);
+
+		setup.versions.upgrade(Some(1));
+		produce(&mut setup.consensus);
+		produce(&mut setup.consensus.clone());
+		assert!(setup.aura_built.load(Ordering::Relaxed));
+		assert_eq!(setup.aura.produced(), 2);
+		assert_eq!(setup.relay_chain.produced(), 1);
+	}
+
+	#[test]
+	fn outdated_aura_api_keeps_the_relay_chain_consensus() {
+		let mut setup = setup();
+		setup.versions.upgrade(Some(0));
+
+		produce(&mut setup.consensus);
+		assert_eq!(setup.relay_chain.produced(), 1);
+		assert!(!setup.aura_built.load(Ordering::Relaxed));
+	}
+}
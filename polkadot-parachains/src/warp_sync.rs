@@ -0,0 +1,261 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Warp sync a parachain node.
+//!
+//! A parachain has no finality proofs of its own to warp sync with. The target of a warp sync is
+//! the parachain head the relay chain has finalized, which [`wait_for_target`] fetches once the
+//! relay chain has synced. [`select`] decides how the node syncs: a warp sync falls back to a full
+//! sync if the database already has blocks, or if the relay chain interface can't provide the
+//! parachain head.
+
+use codec::Decode;
+use cumulus_primitives_core::ParaId;
+use cumulus_relay_chain_interface::{RelayChainInterface, RelayChainResult};
+use futures::channel::oneshot;
+use parachains_common::{BlockNumber, Header};
+use polkadot_primitives::OccupiedCoreAssumption;
+use sc_network::config::SyncMode;
+use std::{fmt, sync::Arc, time::Duration};
+
+const LOG_TARGET: &str = "warp-sync";
+
+/// How long to wait before asking the relay chain for the target again.
+const RETRY_INTERVAL: Duration = Duration::from_secs(6);
+
+/// What warp syncing needs to know about the relay chain.
+#[async_trait::async_trait]
+pub trait ParaHeads: Send + Sync + 'static {
+	/// The encoded head of `para_id` at the last finalized relay chain block, `None` if the
+	/// parachain isn't registered there.
+	async fn finalized_para_head(&self, para_id: ParaId) -> RelayChainResult<Option<Vec<u8>>>;
+
+	/// Whether the relay chain is still doing its initial sync.
+	async fn is_major_syncing(&self) -> RelayChainResult<bool>;
+}
+
+#[async_trait::async_trait]
+impl ParaHeads for Arc<dyn RelayChainInterface> {
+	async fn finalized_para_head(&self, para_id: ParaId) -> RelayChainResult<Option<Vec<u8>>> {
+		let finalized = self.finalized_block_hash().await?;
+		let validation_data = self
+			.persisted_validation_data(finalized, para_id, OccupiedCoreAssumption::TimedOut)
+			.await?;
+		Ok(validation_data.map(|data| data.parent_head.0))
+	}
+
+	async fn is_major_syncing(&self) -> RelayChainResult<bool> {
+		RelayChainInterface::is_major_syncing(&**self).await
+	}
+}
+
+/// Why a requested warp sync became a full sync.
+#[derive(Debug, PartialEq)]
+pub enum Fallback {
+	/// Warp sync needs an empty database.
+	DatabaseNotEmpty,
+	/// The relay chain interface failed to provide the parachain head.
+	RelayChainUnable(String),
+}
+
+/// The way the node syncs.
+#[derive(Debug, PartialEq)]
+pub enum SyncSelection {
+	/// Sync as configured, no warp sync was requested.
+	Configured(SyncMode),
+	/// Warp sync to the parachain head finalized by the relay chain.
+	Warp,
+	/// Full sync, although a warp sync was requested.
+	Full(Fallback),
+}
+
+impl SyncSelection {
+	/// The sync mode to configure the network with.
+	pub fn sync_mode(&self) -> SyncMode {
+		match self {
+			SyncSelection::Configured(mode) => *mode,
+			SyncSelection::Warp => SyncMode::Warp,
+			SyncSelection::Full(_) => SyncMode::Full,
+		}
+	}
+}
+
+impl fmt::Display for SyncSelection {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			SyncSelection::Configured(SyncMode::Full) => write!(f, "full sync"),
+			SyncSelection::Configured(mode) => write!(f, "{:?} sync", mode),
+			SyncSelection::Warp =>
+				write!(f, "warp sync to the parachain head finalized by the relay chain"),
+			SyncSelection::Full(Fallback::DatabaseNotEmpty) =>
+				write!(f, "full sync instead of warp sync, as the database is not empty"),
+			SyncSelection::Full(Fallback::RelayChainUnable(e)) => write!(
+				f,
+				"full sync instead of warp sync, as the relay chain can't provide the parachain \
+				head: {}",
+				e
+			),
+		}
+	}
+}
+
+/// Select how to sync, given the `requested` mode and the best block in the database.
+pub async fn select<R: ParaHeads>(
+	requested: SyncMode,
+	best_number: BlockNumber,
+	relay: &R,
+	para_id: ParaId,
+) -> SyncSelection {
+	if !matches!(requested, SyncMode::Warp) {
+		return SyncSelection::Configured(requested)
+	}
+	if best_number > 0 {
+		return SyncSelection::Full(Fallback::DatabaseNotEmpty)
+	}
+	// A missing head is fine, the relay chain may not have synced up to the registration yet.
+	match relay.finalized_para_head(para_id).await {
+		Ok(_) => SyncSelection::Warp,
+		Err(e) => SyncSelection::Full(Fallback::RelayChainUnable(e.to_string())),
+	}
+}
+
+/// The header to warp sync to, `None` while the relay chain can't tell yet.
+async fn target<R: ParaHeads>(relay: &R, para_id: ParaId) -> RelayChainResult<Option<Header>> {
+	if relay.is_major_syncing().await? {
+		return Ok(None)
+	}
+	let Some(head) = relay.finalized_para_head(para_id).await? else { return Ok(None) };
+	match Header::decode(&mut &head[..]) {
+		Ok(header) => Ok(Some(header)),
+		Err(e) => {
+			log::warn!(target: LOG_TARGET, "Unable to decode the parachain head: {}", e);
+			Ok(None)
+		},
+	}
+}
+
+/// Send the target of the warp sync to `sender`, once the relay chain has synced and finalized a
+/// head of `para_id`.
+pub async fn wait_for_target<R: ParaHeads>(
+	relay: R,
+	para_id: ParaId,
+	sender: oneshot::Sender<Header>,
+) {
+	loop {
+		match target(&relay, para_id).await {
+			Ok(Some(header)) => {
+				log::info!(target: LOG_TARGET, "Warp syncing to block #{}", header.number);
+				let _ = sender.send(header);
+				return
+			},
+			Ok(None) => {},
+			Err(e) =>
+				log::debug!(target: LOG_TARGET, "Unable to fetch the warp sync target: {}", e),
+		}
+		futures_timer::Delay::new(RETRY_INTERVAL).await;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use cumulus_relay_chain_interface::RelayChainError;
+	use futures::executor::block_on;
+	use sp_runtime::traits::Header as HeaderT;
+
+	struct MockRelay {
+		head: Option<Vec<u8>>,
+		major_syncing: bool,
+		unavailable: bool,
+	}
+
+	#[async_trait::async_trait]
+	impl ParaHeads for MockRelay {
+		async fn finalized_para_head(&self, _: ParaId) -> RelayChainResult<Option<Vec<u8>>> {
+			if self.unavailable {
+				return Err(RelayChainError::GenericError("unsupported".into()))
+			}
+			Ok(self.head.clone())
+		}
+
+		async fn is_major_syncing(&self) -> RelayChainResult<bool> {
+			Ok(self.major_syncing)
+		}
+	}
+
+	fn relay_with_head(number: BlockNumber) -> MockRelay {
+		let header = Header::new(
+			number,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		);
+		MockRelay { head: Some(header.encode()), major_syncing: false, unavailable: false }
+	}
+
+	fn select_warp(best_number: BlockNumber, relay: &MockRelay) -> SyncSelection {
+		block_on(select(SyncMode::Warp, best_number, relay, ParaId::from(2015)))
+	}
+
+	#[test]
+	fn warp_sync_is_selected_on_an_empty_database() {
+		assert_eq!(select_warp(0, &relay_with_head(10)), SyncSelection::Warp);
+	}
+
+	#[test]
+	fn full_sync_is_kept() {
+		let selection =
+			block_on(select(SyncMode::Full, 0, &relay_with_head(10), ParaId::from(2015)));
+		assert_eq!(selection, SyncSelection::Configured(SyncMode::Full));
+		assert_eq!(selection.to_string(), "full sync");
+	}
+
+	#[test]
+	fn warp_sync_falls_back_on_a_non_empty_database() {
+		let selection = select_warp(5, &relay_with_head(10));
+		assert_eq!(selection, SyncSelection::Full(Fallback::DatabaseNotEmpty));
+		assert!(matches!(selection.sync_mode(), SyncMode::Full));
+	}
+
+	#[test]
+	fn warp_sync_falls_back_if_the_relay_chain_is_unable() {
+		let relay = MockRelay { head: None, major_syncing: false, unavailable: true };
+		let selection = select_warp(0, &relay);
+		assert!(matches!(selection, SyncSelection::Full(Fallback::RelayChainUnable(_))));
+		assert!(selection.to_string().contains("unsupported"), "{}", selection);
+	}
+
+	#[test]
+	fn target_waits_for_the_relay_chain_to_sync() {
+		let mut relay = relay_with_head(10);
+		relay.major_syncing = true;
+		assert_eq!(block_on(target(&relay, ParaId::from(2015))).unwrap(), None);
+
+		relay.major_syncing = false;
+		let header = block_on(target(&relay, ParaId::from(2015))).unwrap().unwrap();
+		assert_eq!(header.number, 10);
+	}
+
+	#[test]
+	fn wait_for_target_sends_the_finalized_head() {
+		let (sender, receiver) = oneshot::channel();
+		block_on(wait_for_target(relay_with_head(7), ParaId::from(2015), sender));
+		assert_eq!(block_on(receiver).unwrap().number, 7);
+	}
+}
@@ -0,0 +1,160 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Prometheus metrics of the weight inbound XCM messages are charged and consume.
+//!
+//! The XCM executor runs in the runtime, which can't reach the node's Prometheus registry. The
+//! runtime meters the messages of its inbound queues instead (`pallet_xcm_admin::MeterInbound`),
+//! recording the weight each message has been charged by the `Weigher` and the weight it consumed
+//! in an event. The node reads them back from every finalized block through the `XcmWeightApi`,
+//! such that each message is observed once whatever the reorgs, and records their `ref_time` in
+//! histograms by queue (`dmp` or `xcmp`).
+
+use crate::metric_prefix::MetricPrefix;
+use futures::StreamExt;
+use parachains_common::{runtime_api::XcmWeightApi, xcm_config::InboundXcmWeight, Block, Hash};
+use sc_client_api::BlockchainEvents;
+use sp_api::ProvideRuntimeApi;
+use std::sync::Arc;
+use substrate_prometheus_endpoint::{
+	exponential_buckets, register, HistogramOpts, HistogramVec, PrometheusError, Registry,
+};
+
+const LOG_TARGET: &str = "xcm-weight-metrics";
+
+/// Prometheus metrics of the weight of inbound XCM messages.
+#[derive(Clone)]
+pub struct Metrics {
+	weight_charged: HistogramVec,
+	weight_consumed: HistogramVec,
+}
+
+impl Metrics {
+	/// Register the metrics at the given registry, their names prefixed with `prefix`.
+	pub fn register(registry: &Registry, prefix: &MetricPrefix) -> Result<Self, PrometheusError> {
+		// From 0.1 ms to about 0.2 s of `ref_time`, our executor charges 0.2 ms per instruction.
+		let buckets = exponential_buckets(100_000_000.0, 2.0, 12)?;
+		let histogram = |name: &str, help: &str| -> Result<HistogramVec, PrometheusError> {
+			register(
+				HistogramVec::new(
+					HistogramOpts::new(prefix.apply(name), help).buckets(buckets.clone()),
+					&["queue"],
+				)?,
+				registry,
+			)
+		};
+		Ok(Self {
+			weight_charged: histogram(
+				"integritee_parachain_xcm_weight_charged",
+				"The ref_time weight inbound XCM messages have been charged",
+			)?,
+			weight_consumed: histogram(
+				"integritee_parachain_xcm_weight_consumed",
+				"The ref_time weight the execution of inbound XCM messages consumed",
+			)?,
+		})
+	}
+
+	fn observe(&self, weights: &[InboundXcmWeight]) {
+		for weight in weights {
+			let queue = weight.queue.as_str();
+			self.weight_charged
+				.with_label_values(&[queue])
+				.observe(weight.charged.ref_time() as f64);
+			self.weight_consumed
+				.with_label_values(&[queue])
+				.observe(weight.consumed.ref_time() as f64);
+		}
+	}
+}
+
+/// Follow the finalized blocks and record the weight of the inbound XCM messages they executed.
+pub async fn run<C>(client: Arc<C>, metrics: Metrics)
+where
+	C: ProvideRuntimeApi<Block> + BlockchainEvents<Block>,
+	C::Api: XcmWeightApi<Block>,
+{
+	let mut finalized = client.finality_notification_stream();
+	while let Some(notification) = finalized.next().await {
+		// The tree route holds the blocks finalized along with the notified one.
+		let hashes: Vec<Hash> =
+			notification.tree_route.iter().copied().chain(Some(notification.hash)).collect();
+		for hash in hashes {
+			match client.runtime_api().inbound_xcm_weights(hash) {
+				Ok(weights) => metrics.observe(&weights),
+				Err(e) => log::debug!(
+					target: LOG_TARGET,
+					"Could not read the inbound XCM weights of {:?}: {:?}",
+					hash,
+					e
+				),
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use parachains_common::xcm_config::InboundQueue;
+	use xcm::latest::Weight;
+
+	fn weight(queue: InboundQueue, charged: u64, consumed: u64) -> InboundXcmWeight {
+		InboundXcmWeight {
+			queue,
+			charged: Weight::from_parts(charged, 0),
+			consumed: Weight::from_parts(consumed, 0),
+		}
+	}
+
+	#[test]
+	fn weights_are_observed_by_queue() {
+		let registry = Registry::new();
+		let metrics = Metrics::register(&registry, &MetricPrefix::default()).unwrap();
+
+		metrics.observe(&[
+			weight(InboundQueue::Dmp, 1_000, 600),
+			weight(InboundQueue::Xcmp, 2_000, 2_000),
+			weight(InboundQueue::Dmp, 3_000, 400),
+		]);
+
+		let dmp_charged = metrics.weight_charged.with_label_values(&["dmp"]);
+		assert_eq!(dmp_charged.get_sample_count(), 2);
+		assert_eq!(dmp_charged.get_sample_sum(), 4_000.0);
+		assert_eq!(metrics.weight_consumed.with_label_values(&["dmp"]).get_sample_sum(), 1_000.0);
+		let xcmp_consumed = metrics.weight_consumed.with_label_values(&["xcmp"]);
+		assert_eq!(xcmp_consumed.get_sample_count(), 1);
+		assert_eq!(xcmp_consumed.get_sample_sum(), 2_000.0);
+	}
+
+	#[test]
+	fn metric_names_are_prefixed() {
+		let registry = Registry::new();
+		let metrics = Metrics::register(&registry, &"collator".parse().unwrap()).unwrap();
+		metrics.observe(&[weight(InboundQueue::Xcmp, 1, 1)]);
+
+		let names: Vec<_> =
+			registry.gather().iter().map(|family| family.get_name().to_owned()).collect();
+		assert_eq!(
+			names,
+			[
+				"collator_integritee_parachain_xcm_weight_charged",
+				"collator_integritee_parachain_xcm_weight_consumed"
+			]
+		);
+	}
+}
@@ -0,0 +1,59 @@
+use assert_cmd::cargo::cargo_bin;
+use std::{
+	path::{Path, PathBuf},
+	process::{Command, Output},
+};
+use tempfile::tempdir;
+
+/// A chain spec of each runtime this binary can benchmark.
+static SPECS: [&'static str; 2] = ["shell-rococo-local-dev", "integritee-rococo-local-dev"];
+
+/// The `benchmark pallet` command runs against the runtime of the given spec.
+#[test]
+#[ignore]
+fn benchmark_pallet_works() {
+	for spec in SPECS {
+		let output = benchmark_pallet(spec);
+		assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+	}
+}
+
+/// The `benchmark pallet` command rejects a spec whose runtime it doesn't know.
+#[test]
+#[ignore]
+fn benchmark_pallet_fails_for_unknown_runtime() {
+	let tmp_dir = tempdir().expect("could not create a temp dir");
+	let spec = unknown_spec(tmp_dir.path());
+
+	let output = benchmark_pallet(spec.to_str().unwrap());
+	assert!(!output.status.success());
+	assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown runtime for chain spec"));
+}
+
+/// Invoke the `benchmark pallet` sub-command for `frame_system` on the given spec.
+fn benchmark_pallet(spec: &str) -> Output {
+	Command::new(cargo_bin("polkadot-parachain"))
+		.args(&["benchmark", "pallet", "--chain", spec])
+		.args(["--pallet", "frame_system", "--extrinsic", "remark"])
+		.args(["--steps", "2", "--repeat", "1"])
+		.output()
+		.unwrap()
+}
+
+/// Write an integritee spec renamed to something no runtime of ours matches.
+fn unknown_spec(base_path: &Path) -> PathBuf {
+	let output = Command::new(cargo_bin("polkadot-parachain"))
+		.args(&["build-spec", "--chain", "integritee-rococo-local-dev"])
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+
+	let spec = String::from_utf8(output.stdout).unwrap().replacen(
+		"\"name\": \"Integritee Network\"",
+		"\"name\": \"Unknown Network\"",
+		1,
+	);
+	let path = base_path.join("unknown.json");
+	std::fs::write(&path, spec).unwrap();
+	path
+}
@@ -0,0 +1,51 @@
+// Copyright 2020-2021 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use assert_cmd::cargo::cargo_bin;
+use std::{
+	convert::TryInto,
+	process::{Command, Stdio},
+	thread,
+	time::Duration,
+};
+
+#[test]
+#[cfg(unix)]
+fn dev_node_seals_blocks_without_relay_chain() {
+	use nix::{
+		sys::signal::{kill, Signal::SIGINT},
+		unistd::Pid,
+	};
+
+	let base_path = tempfile::tempdir().unwrap();
+
+	let mut cmd = Command::new(cargo_bin("polkadot-parachain"))
+		.args(&["--chain", "integritee-rococo-local-dev", "--dev-block-time", "1000", "-d"])
+		.arg(base_path.path())
+		.stderr(Stdio::piped())
+		.spawn()
+		.unwrap();
+
+	thread::sleep(Duration::from_secs(20));
+	assert!(cmd.try_wait().unwrap().is_none(), "the process should still be running");
+	kill(Pid::from_raw(cmd.id().try_into().unwrap()), SIGINT).unwrap();
+
+	let output = cmd.wait_with_output().unwrap();
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	assert!(stderr.contains("Development node, sealing a block every 1s"), "{}", stderr);
+	assert!(stderr.contains("Imported #3"), "{}", stderr);
+	assert!(!stderr.contains("[Relaychain]"), "{}", stderr);
+}
@@ -0,0 +1,67 @@
+// Copyright 2020-2021 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use assert_cmd::cargo::cargo_bin;
+use std::{
+	convert::TryInto,
+	process::{Command, Stdio},
+	thread,
+	time::Duration,
+};
+
+#[test]
+#[cfg(unix)]
+fn startup_summary_is_logged_once() {
+	use nix::{
+		sys::signal::{kill, Signal::SIGINT},
+		unistd::Pid,
+	};
+
+	fn startup_summaries(extra_args: &[&str]) -> Vec<String> {
+		let base_path = tempfile::tempdir().unwrap();
+
+		let mut cmd = Command::new(cargo_bin("polkadot-parachain"))
+			.args(extra_args)
+			.args(&["--chain", "integritee-rococo-local-dev", "-d"])
+			.arg(base_path.path())
+			.args(&["--", "--dev"])
+			.stderr(Stdio::piped())
+			.spawn()
+			.unwrap();
+
+		thread::sleep(Duration::from_secs(20));
+		assert!(cmd.try_wait().unwrap().is_none(), "the process should still be running");
+		kill(Pid::from_raw(cmd.id().try_into().unwrap()), SIGINT).unwrap();
+
+		let output = cmd.wait_with_output().unwrap();
+		String::from_utf8_lossy(&output.stderr)
+			.lines()
+			.filter(|line| line.contains("Startup summary:"))
+			.map(ToOwned::to_owned)
+			.collect()
+	}
+
+	let summaries = startup_summaries(&[]);
+	assert_eq!(summaries.len(), 1, "{:?}", summaries);
+	assert!(summaries[0].contains("[Parachain]"));
+	assert!(summaries[0].contains("para_id=2015 role=full"));
+	assert!(summaries[0].contains("relay_slot_ms=6000"));
+
+	let summaries = startup_summaries(&["--collator", "--relay-chain-slot-duration-ms", "2000"]);
+	assert_eq!(summaries.len(), 1, "{:?}", summaries);
+	assert!(summaries[0].contains("para_id=2015 role=collator"));
+	assert!(summaries[0].contains("relay_slot_ms=2000"));
+}
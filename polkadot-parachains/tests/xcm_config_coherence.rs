@@ -0,0 +1,135 @@
+// Copyright 2021 Integritee AG and Supercomputing Systems AG
+// This file is part of the "Integritee parachain" and is
+// based on Cumulus from Parity Technologies (UK) Ltd.
+
+// Integritee parachain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Cumulus is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Integritee parachain.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The XCM configurations of the integritee and the shell runtime are maintained separately, but
+//! must agree on what they have in common: the relay network, how the chain derives its own
+//! locations, and where the currencies are. Intentional differences must be allow-listed.
+
+use codec::Encode;
+use parachain_runtime::xcm_config as integritee;
+use parachains_common::xcm_config::CurrencyId;
+use shell_runtime::xcm_config as shell;
+use sp_runtime::traits::Convert;
+use xcm::latest::prelude::*;
+
+/// Invariants which intentionally differ between the runtimes, each with the reason.
+const ALLOWED_DIFFERENCES: &[(&str, &str)] = &[];
+
+/// A shared invariant, SCALE encoded as the integritee and the shell runtime have it.
+struct Invariant {
+	name: String,
+	integritee: Vec<u8>,
+	shell: Vec<u8>,
+}
+
+/// Evaluate `value` in the externalities of both runtimes, in which both use the same para id.
+macro_rules! invariant {
+	($name:expr, |$config:ident| $value:expr) => {
+		Invariant {
+			name: $name.into(),
+			integritee: sp_io::TestExternalities::default().execute_with(|| {
+				use integritee as $config;
+				$value.encode()
+			}),
+			shell: sp_io::TestExternalities::default().execute_with(|| {
+				use shell as $config;
+				$value.encode()
+			}),
+		}
+	};
+}
+
+fn location<C: Convert<CurrencyId, Option<MultiLocation>>>(
+	id: CurrencyId,
+) -> Option<MultiLocation> {
+	C::convert(id)
+}
+
+fn currency<C: Convert<MultiLocation, Option<CurrencyId>>>(
+	location: MultiLocation,
+) -> Option<CurrencyId> {
+	C::convert(location)
+}
+
+fn invariants() -> Vec<Invariant> {
+	let mut invariants = vec![
+		invariant!("RelayNetwork", |config| config::RelayNetwork::get()),
+		invariant!("RelayChainLocation", |config| config::RelayChainLocation::get()),
+		invariant!("UniversalLocation", |config| config::UniversalLocation::get()),
+		invariant!("SelfLocation", |config| config::SelfLocation::get()),
+		invariant!("SelfLocationAbsolute", |config| config::SelfLocationAbsolute::get()),
+		invariant!("SelfReserve", |config| config::SelfReserve::get()),
+		invariant!("UnitWeightCost", |config| config::UnitWeightCost::get()),
+		invariant!("BaseXcmWeight", |config| config::BaseXcmWeight::get()),
+		invariant!("MaxAssetsIntoHolding", |config| config::MaxAssetsIntoHolding::get()),
+		invariant!("currency of SelfReserve", |config| {
+			currency::<config::CurrencyIdConvert>(config::SelfReserve::get())
+		}),
+	];
+
+	for id in CurrencyId::all() {
+		invariants.push(invariant!(format!("location of {:?}", id), |config| {
+			location::<config::CurrencyIdConvert>(id)
+		}));
+		invariants.push(invariant!(format!("currency of the location of {:?}", id), |config| {
+			location::<config::CurrencyIdConvert>(id)
+				.and_then(currency::<config::CurrencyIdConvert>)
+		}));
+	}
+	invariants
+}
+
+/// The names of the `invariants` which differ between the runtimes without being in `allowed`.
+fn differences(invariants: &[Invariant], allowed: &[(&str, &str)]) -> Vec<String> {
+	invariants
+		.iter()
+		.filter(|invariant| invariant.integritee != invariant.shell)
+		.filter(|invariant| !allowed.iter().any(|(name, _)| *name == invariant.name))
+		.map(|invariant| invariant.name.clone())
+		.collect()
+}
+
+#[test]
+fn xcm_configs_agree() {
+	assert_eq!(differences(&invariants(), ALLOWED_DIFFERENCES), Vec::<String>::new());
+}
+
+#[test]
+fn allowed_differences_are_not_stale() {
+	let invariants = invariants();
+	for (name, reason) in ALLOWED_DIFFERENCES {
+		let invariant = invariants
+			.iter()
+			.find(|invariant| invariant.name == *name)
+			.unwrap_or_else(|| panic!("{} is no invariant anymore", name));
+		assert_ne!(invariant.integritee, invariant.shell, "{} doesn't differ ({})", name, reason);
+	}
+}
+
+#[test]
+fn diverging_relay_network_is_reported_unless_allowed() {
+	let diverging = [Invariant {
+		name: "RelayNetwork".into(),
+		integritee: NetworkId::Kusama.encode(),
+		shell: NetworkId::Polkadot.encode(),
+	}];
+	assert_eq!(differences(&diverging, &[]), vec!["RelayNetwork".to_string()]);
+	assert_eq!(
+		differences(&diverging, &[("RelayNetwork", "the shell runs on Polkadot")]),
+		Vec::<String>::new()
+	);
+}
@@ -3,6 +3,8 @@
 # Create `WeightInfo` implementations for all the pallets and store it in the weight module of the `integritee-runtime`.
 
 INTEGRITEE_RUNTIME_WEIGHT_DIR=polkadot-parachains/integritee-runtime/src/weights
+# The weights shared with the shell runtime.
+COMMON_WEIGHT_DIR=polkadot-parachains/common/src/weights
 COLLATOR=./target/release/integritee-collator
 
 mkdir -p $INTEGRITEE_RUNTIME_WEIGHT_DIR
@@ -19,6 +21,11 @@ while read -r line; do
   pallet="$(echo "$line" | cut -d' ' -f1)";
   echo benchmarking "$pallet"...
 
+  output=./$INTEGRITEE_RUNTIME_WEIGHT_DIR/"$pallet".rs
+  if [ "$pallet" = "pallet_xcm" ]; then
+    output=./$COMMON_WEIGHT_DIR/"$pallet".rs
+  fi
+
   $COLLATOR \
   benchmark pallet \
   --chain=integritee-rococo-local-dev \
@@ -29,6 +36,6 @@ while read -r line; do
   --execution=wasm \
   --wasm-execution=compiled \
   --heap-pages=4096 \
-  --output=./$INTEGRITEE_RUNTIME_WEIGHT_DIR/"$pallet".rs
+  --output="$output"
 done < "integritee_runtime_pallets"
 rm "integritee_runtime_pallets"